@@ -3,6 +3,7 @@ pragma solidity ^0.8.21;
 
 import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
 import "@openzeppelin/contracts/utils/cryptography/EIP712.sol";
+import "@openzeppelin/contracts/utils/cryptography/MerkleProof.sol";
 import "@openzeppelin/contracts/utils/ReentrancyGuard.sol";
 
 /**
@@ -24,12 +25,17 @@ contract AirChainPay is EIP712, ReentrancyGuard {
     // Nonce tracking for replay protection
     mapping(address => uint256) public nonces;
 
+    // Block timestamp at which a relay attested a Merkle root of the payments it handled
+    mapping(address => mapping(bytes32 => uint256)) public relayRootAttestedAt;
+
     // Emitted when a payment is made
     event Payment(address indexed from, address indexed to, uint256 amount, string paymentReference, bool isRelayed);
     // Emitted when the owner withdraws funds
     event Withdrawal(address indexed to, uint256 amount);
     // Emitted when a meta-transaction is executed
     event MetaTransactionExecuted(address indexed from, address indexed to, uint256 amount, string paymentReference);
+    // Emitted when a relay publishes the Merkle root of a batch of handled payment hashes
+    event RelayAttestation(address indexed relay, bytes32 indexed root, uint256 count, uint64 fromTime, uint64 toTime);
 
     // Set the contract owner at deployment
     constructor() EIP712("AirChainPay", "1") {
@@ -185,6 +191,38 @@ contract AirChainPay is EIP712, ReentrancyGuard {
         return _domainSeparatorV4();
     }
 
+    /**
+     * @dev Publish the Merkle root of payment transaction hashes handled by the calling relay.
+     * Leaves are keccak256(txHash); pairs are hashed in sorted order.
+     * @param root Merkle root of the batch
+     * @param count Number of transactions in the batch
+     * @param fromTime Earliest transaction time in the batch (unix seconds)
+     * @param toTime Latest transaction time in the batch (unix seconds)
+     */
+    function attestRelayRoot(bytes32 root, uint256 count, uint64 fromTime, uint64 toTime) external {
+        require(root != bytes32(0), "Invalid root");
+        require(count > 0, "Empty batch");
+        require(fromTime <= toTime, "Invalid time range");
+        require(relayRootAttestedAt[msg.sender][root] == 0, "Root already attested");
+        relayRootAttestedAt[msg.sender][root] = block.timestamp;
+        emit RelayAttestation(msg.sender, root, count, fromTime, toTime);
+    }
+
+    /**
+     * @dev Check that a payment transaction hash is part of a root attested by a relay
+     * @param relay The relay that attested the root
+     * @param root Merkle root of the batch
+     * @param txHash Hash of the payment transaction
+     * @param proof Merkle proof for the transaction
+     * @return True if the root was attested by the relay and the proof is valid
+     */
+    function verifyRelayProof(address relay, bytes32 root, bytes32 txHash, bytes32[] calldata proof) external view returns (bool) {
+        if (relayRootAttestedAt[relay][root] == 0) {
+            return false;
+        }
+        return MerkleProof.verifyCalldata(proof, root, keccak256(abi.encodePacked(txHash)));
+    }
+
     /**
      * @dev Owner can withdraw contract balance
      * @param amount Amount to withdraw (in wei)
@@ -26,4 +26,19 @@ describe("AirChainPay", function () {
   it("should not allow non-owner to withdraw", async () => {
     await expect(contract.connect(user1).withdraw(1)).to.be.revertedWith("Not owner");
   });
+
+  it("should record relay attestations and verify proofs", async () => {
+    const hashPair = (a, b) => ethers.keccak256(ethers.concat(a < b ? [a, b] : [b, a]));
+    const txHashes = [ethers.id("tx-1"), ethers.id("tx-2")];
+    const leaves = txHashes.map((h) => ethers.keccak256(h));
+    const root = hashPair(leaves[0], leaves[1]);
+
+    const tx = await contract.connect(user1).attestRelayRoot(root, 2, 100, 200);
+    await expect(tx).to.emit(contract, "RelayAttestation").withArgs(user1.address, root, 2, 100, 200);
+
+    expect(await contract.verifyRelayProof(user1.address, root, txHashes[0], [leaves[1]])).to.equal(true);
+    expect(await contract.verifyRelayProof(owner.address, root, txHashes[0], [leaves[1]])).to.equal(false);
+    expect(await contract.verifyRelayProof(user1.address, root, ethers.id("tx-3"), [leaves[1]])).to.equal(false);
+    await expect(contract.connect(user1).attestRelayRoot(root, 2, 100, 200)).to.be.revertedWith("Root already attested");
+  });
 }); 
\ No newline at end of file
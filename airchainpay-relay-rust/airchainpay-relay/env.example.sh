@@ -8,7 +8,194 @@ export RUST_ENV=development  # development, staging, production
 
 # Server Configuration
 export PORT=4000
+export ADMIN_PORT=4001        # admin/ops listener (config, backups, audit, circuit breakers)
+export ADMIN_BIND=127.0.0.1
 export LOG_LEVEL=info
+export LOG_FORMAT=json             # json or text (console); files are always JSON
+export LOG_MODULES=               # per-module overrides, e.g. airchainpay_relay::middleware=debug,actix_web=warn
+export OTEL_TRACING_ENABLED=false  # export request -> queue -> broadcast spans over OTLP/HTTP
+export OTEL_EXPORTER_OTLP_ENDPOINT=http://localhost:4318
+export OTEL_SERVICE_NAME=airchainpay-relay
+export OTEL_TRACES_SAMPLER_ARG=1.0 # share of new traces kept
+
+
+# Transaction Queue (backpressure)
+export QUEUE_MAX_SIZE=1000
+export QUEUE_HIGH_WATER_MARK=800   # above this, low-priority submissions get 503 + Retry-After
+export QUEUE_RETRY_AFTER_SECS=5
+
+# Confirmation watcher (broadcast transactions are polled for their receipt)
+export CONFIRMATION_POLL_INTERVAL_SECS=5
+export REQUIRED_CONFIRMATIONS=1
+export TRANSACTION_TIMEOUT_SECS=300  # still unmined after this: marked dropped
+export REPLACEMENT_BUMP_PERCENT=10     # fee cap raise a same-nonce replacement needs
+# export REBROADCAST_INTERVAL_SECS=60  # re-send unmined transactions this often
+export MAX_REBROADCASTS=5             # most re-sends or fee bumps per transaction
+
+# Data Retention (stale devices and finished transactions are archived, then removed)
+export DEVICE_INACTIVE_DAYS=90
+export TRANSACTION_RETENTION_DAYS=30
+export PRUNE_INTERVAL_SECS=86400
+export PRUNE_DRY_RUN=false        # true: only report what would be removed
+
+# Audit Log (high and critical events and security events are never sampled)
+export AUDIT_SAMPLE_RATE_LOW=1.0       # share of low-severity events recorded
+export AUDIT_SAMPLE_RATE_MEDIUM=1.0
+export AUDIT_RETENTION_DAYS_LOW=30     # 0 keeps events of that severity forever
+export AUDIT_RETENTION_DAYS_MEDIUM=90
+export AUDIT_RETENTION_DAYS_HIGH=365
+export AUDIT_RETENTION_DAYS_CRITICAL=0
+
+# Config changes made through the API are reverted if errors spike in this window
+export CONFIG_ROLLOUT_WINDOW_SECS=120
+export CONFIG_ROLLOUT_MAX_ERROR_RATE=0.25
+export CONFIG_ROLLOUT_MIN_REQUESTS=20
+
+# Federation: edge relays forward their queue to an upstream relay instead of broadcasting
+export RELAY_MODE=standard          # standard or edge
+export FEDERATION_UPSTREAM_URL=     # e.g. https://relay.example.com (edge mode)
+export FEDERATION_TRUSTED_PEERS=    # comma-separated identity addresses of edge relays allowed to forward here
+export FEDERATION_SYNC_INTERVAL_SECS=30
+export FEDERATION_BATCH_SIZE=100
+# export RELAY_IDENTITY_KEY=        # signs receipts and manifests; defaults to a key generated in data/relay_identity.key
+
+# Proof-of-relay: periodically attest a Merkle root of handled payments on the payment contract
+# (sent from the relay identity address, which needs gas on each chain)
+export ATTESTATION_ENABLED=false
+export ATTESTATION_INTERVAL_SECS=3600
+export ATTESTATION_MAX_BATCH=1000
+
+# Device certificates; leave DEVICE_CA_CERT/DEVICE_CA_KEY unset to use the built-in CA in data/pki
+# export DEVICE_CA_CERT=            # intermediate CA certificate (PEM)
+# export DEVICE_CA_KEY=
+# export DEVICE_CA_CHAIN=           # chain up to the root, served by /api/devices/ca
+export DEVICE_CERT_TTL_HOURS=24
+export DEVICE_ENROLLMENT_TOKEN_TTL_SECS=900
+# Native TLS on the public listener (enables mTLS); or terminate at a proxy and forward X-Client-Cert
+# export TLS_CERT_PATH=
+# export TLS_KEY_PATH=
+export TRUST_PROXY_CLIENT_CERT=false
+
+# Proof-of-work gate on /api/auth/* and /api/devices/enroll (challenge from GET /api/auth/challenge)
+export POW_CHALLENGE_ENABLED=false
+export POW_DIFFICULTY=20
+export POW_CHALLENGE_TTL_SECS=120
+
+# Memory guard: shed load as RSS nears the cgroup (or host) memory limit
+export MEMORY_GUARD_ENABLED=true
+# export MEMORY_LIMIT_BYTES=        # overrides the detected limit
+export MEMORY_SOFT_LIMIT_RATIO=0.80
+export MEMORY_HARD_LIMIT_RATIO=0.92
+export MEMORY_CHECK_INTERVAL_SECS=5
+
+# Reconciliation: compare stored statuses with on-chain receipts and repair divergences
+export RECONCILIATION_ENABLED=true
+export RECONCILIATION_INTERVAL_SECS=900
+export RECONCILIATION_WINDOW_HOURS=24
+export RECONCILIATION_DROP_GRACE_SECS=1800
+export RECONCILIATION_DRY_RUN=false
+
+# Fee oracle: per-chain fee levels from eth_feeHistory (GET /api/fees/{chain_id});
+# queued transactions with a fee cap below FEE_FLOOR_PERCENT of the lowest recent base fee are failed
+export FEE_ORACLE_INTERVAL_SECS=15
+export FEE_HISTORY_BLOCKS=20
+export FEE_FLOOR_PERCENT=90
+
+# Sanctions screening before broadcast (off unless a list or provider is set); every decision is audited
+export SANCTIONS_LIST_PATH=
+export SCREENING_PROVIDER_URL=
+export SCREENING_PROVIDER_API_KEY=
+export SCREENING_PROVIDER_TIMEOUT_SECS=5
+# Refuse transactions when a screener cannot answer
+export SCREENING_FAIL_CLOSED=true
+
+# Gas budgets for transactions paid from relay wallets (wei per rolling window; unset = unlimited)
+export GAS_BUDGET_WINDOW_SECS=86400
+# export GAS_BUDGET_CHAIN_WEI=
+# export GAS_BUDGET_DEVICE_WEI=
+# export GAS_BUDGET_MERCHANT_WEI=
+
+# Canary self-transfers from the relayer wallet (RELAYER_PRIVATE_KEY) to measure real broadcast health
+export CANARY_ENABLED=false
+export CANARY_INTERVAL_SECS=3600
+export CANARY_TIMEOUT_SECS=300
+export CANARY_VALUE_WEI=1
+# Comma-separated chain ids; empty = every configured chain
+export CANARY_CHAINS=
+
+# Health fields shown without admin credentials (comma-separated; e.g. components.blockchain, alerts, metrics)
+export HEALTH_PUBLIC_FIELDS=status,timestamp,version
+
+# Chain registry (TOML or JSON) updating the built-in chains or adding custom ones
+export CHAIN_REGISTRY_FILE=
+
+# Submission quotas per device and per API key, reset at UTC midnight / month start (0 = unlimited)
+export QUOTA_DEVICE_DAILY=0
+export QUOTA_DEVICE_MONTHLY=0
+export QUOTA_API_KEY_DAILY=0
+export QUOTA_API_KEY_MONTHLY=0
+
+# Refuse raw transactions already accepted via any endpoint, remembered across restarts
+export TX_REPLAY_PROTECTION_ENABLED=true
+export TX_REPLAY_RETENTION_HOURS=168
+export TX_REPLAY_MAX_ENTRIES=100000
+
+# Token metadata and payment contract verification cache
+export TOKEN_REGISTRY_REFRESH_SECS=900
+# Tokens to preload per chain, e.g. 84532:0xToken1,0xToken2;1114:0xToken3 (others are cached on first use)
+export TOKEN_REGISTRY_TOKENS=
+# Refuse transfers of unsupported tokens or amounts outside the contract's min/max
+export TOKEN_REGISTRY_ENFORCE=true
+
+# Pre-broadcast simulation (transactions that would revert are refused)
+export SIMULATION_ENABLED=true
+export SIMULATION_METHOD=eth_call  # or debug_traceCall on nodes with the debug namespace
+export SIMULATION_TIMEOUT_SECS=5
+export SIMULATION_FAIL_CLOSED=false  # true: refuse transactions the node cannot simulate
+
+# Contract Payment event indexer (serves /api/contract/payments)
+export INDEXER_ENABLED=false
+export INDEXER_POLL_INTERVAL_SECS=15
+export INDEXER_BATCH_BLOCKS=2000      # blocks per eth_getLogs call
+export INDEXER_CONFIRMATIONS=3        # blocks behind the head before payments are indexed
+# export INDEXER_START_BLOCK=0        # backfill from here on chains not yet indexed
+# export INDEXER_WS_URL_84532=wss://... # new blocks trigger a pass without waiting for the poll
+
+# Heartbeat pings to uptime monitors (Healthchecks.io, Better Uptime; comma-separated check URLs, empty = off)
+export HEARTBEAT_URLS=
+export HEARTBEAT_INTERVAL_SECS=60
+export HEARTBEAT_TIMEOUT_SECS=10
+export HEARTBEAT_REPORT_FAILURES=true  # ping <url>/fail while unready; false: stop pinging instead
+
+# Startup warmup: connect RPC endpoints, check chain ids, cache fees and check storage before binding
+export WARMUP_ENABLED=true
+export WARMUP_CHAIN_TIMEOUT_SECS=15
+export WARMUP_REQUIRE_STORAGE=true  # refuse to start when storage is unhealthy
+
+# Extra per-route role requirements (comma-separated "[METHOD ]prefix=role"; role: device, merchant, admin or public)
+# export API_ROUTE_ROLES="GET /api/metrics=admin"
+# export ADMIN_ROUTE_ROLES="GET /api/audit=merchant"
+
+# Request body limits on the public API (decoded bytes, JSON nesting depth, JSON array length)
+export BODY_MAX_BYTES=1048576
+export JSON_MAX_DEPTH=32
+export JSON_MAX_ARRAY_LEN=1000
+# Per-route overrides, longest prefix wins: prefix=bytes:depth:array_len;...
+# export BODY_LIMITS="/api/send_tx=65536:16:256;/api/federation/ingest=4194304:32:10000"
+
+# Plain signed transactions up to this many bytes skip the network health check and full validator
+export PAYLOAD_FAST_PATH_ENABLED=true
+export PAYLOAD_FAST_PATH_MAX_BYTES=512
+
+# Storage backend: json (files, newest 1000 transactions) or sled (embedded database under data/db)
+export STORAGE_BACKEND=json
+
+# BLE GATT peripheral (build with --features ble-peripheral; needs BlueZ)
+export BLE_PERIPHERAL_ENABLED=false
+# export BLE_ADAPTER=hci0
+export BLE_LOCAL_NAME="AirChainPay Relay"
+export BLE_NOTIFY_FRAME_SIZE=182
+export BLE_MAX_PAYLOAD_BYTES=16384
 
 # Core Testnet 2 Configuration (Primary)
 export RPC_URL=https://rpc.test2.btcs.network
@@ -21,6 +208,17 @@ export CORE_TESTNET2_CONTRACT_ADDRESS=your_contract_address_here
 export CORE_TESTNET2_BLOCK_EXPLORER=https://scan.test2.btcs.network
 export CORE_TESTNET2_CURRENCY_SYMBOL=TCORE2
 
+# Optional TLS pinning for this RPC (any chain prefix works the same way):
+# comma-separated SHA-256 certificate fingerprints and/or a private CA bundle
+# export CORE_TESTNET2_RPC_TLS_PINS=ab:cd:...
+# export CORE_TESTNET2_RPC_TLS_CA_CERT=/etc/airchainpay/rpc-ca.pem
+# Optional fallback RPCs, tried in order on timeouts or 5xx responses
+# export CORE_TESTNET2_RPC_FALLBACK_URLS=https://rpc-backup-1.example.org,https://rpc-backup-2.example.org
+# Optional per-chain broadcast strategy, overriding the settings above
+# export CORE_TESTNET2_MAX_FEE_GWEI=500
+# export CORE_TESTNET2_REPLACEMENT_BUMP_PERCENT=12
+# export CORE_TESTNET2_CONFIRMATIONS=3
+# export CORE_TESTNET2_REBROADCAST_SECS=30
 # Base Sepolia Configuration (Secondary)
 export BASE_SEPOLIA_RPC_URL=https://base-sepolia.drpc.org
 export BASE_SEPOLIA_CONTRACT_ADDRESS=your_contract_address_here
@@ -40,11 +238,15 @@ export HOLESKY_BLOCK_EXPLORER=https://holesky.etherscan.io
 export HOLESKY_CURRENCY_SYMBOL=ETH
 
 # Security
+# Imported into data/api_keys.json on first start; manage keys via /api/admin/api-keys afterwards
 export API_KEY=your_api_key_here
 export JWT_SECRET=your_jwt_secret_here
+export ADMIN_API_KEY=your_admin_api_key_here
 
-# CORS
+# CORS (comma-separated; "*" is only honoured when RUST_ENV=development)
 export CORS_ORIGINS=*
+# export CORS_METHODS=GET,POST,PUT,DELETE,OPTIONS
+# export CORS_HEADERS=Content-Type,Authorization,X-API-Key,X-Request-Id,X-Request-Nonce,X-Request-Timestamp,X-PoW-Challenge,X-PoW-Nonce
 
 # Rate Limiting
 export RATE_LIMIT_MAX=1000
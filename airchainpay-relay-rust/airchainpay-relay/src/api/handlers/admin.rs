@@ -0,0 +1,939 @@
+use actix_web::{delete, get, post, put, HttpRequest, HttpResponse, Responder};
+use actix_web::web::{Data, Json, Path, Query};
+use serde::Deserialize;
+use std::sync::Arc;
+use crate::app::selftest::TestRunner;
+use crate::app::nonce_monitor::NonceMonitor;
+use crate::app::scheduler::DataPruner;
+use crate::app::config_rollout::ConfigRollout;
+use crate::app::federation::Federation;
+use crate::app::transaction_service::TransactionProcessor;
+use crate::app::attestation::AttestationService;
+use crate::app::enrollment::DeviceEnrollment;
+use crate::app::memory_guard::MemoryGuard;
+use crate::app::reconciliation::Reconciler;
+use crate::app::gas_accounting::GasLedger;
+use crate::app::canary::CanaryMonitor;
+use crate::app::token_registry::TokenRegistry;
+use crate::app::export::{ExportQuery, TransactionExporter};
+use crate::domain::auth;
+use crate::domain::auth::api_keys::{ApiKeyRecord, ApiKeyStore};
+use crate::infrastructure::blockchain::manager::BlockchainManager;
+use crate::infrastructure::chain_registry::ChainRegistry;
+use crate::infrastructure::config::{ChainConfig, DynamicConfigManager};
+use crate::infrastructure::monitoring::manager::{DeviceMetric, MonitoringManager};
+use crate::infrastructure::storage::archive::ArchiveQuery;
+use crate::infrastructure::storage::file_storage::Storage;
+use crate::utils::audit::AuditLogger;
+use crate::utils::backup::BackupManager;
+use crate::middleware::error_handling::ErrorResponseBuilder;
+
+/// Check admin credentials: an admin bearer JWT or `X-Admin-Key`
+pub fn authorize_admin(req: &HttpRequest) -> Result<String, HttpResponse> {
+    let authorization = req.headers().get("Authorization").and_then(|h| h.to_str().ok());
+    let admin_key = req.headers().get("X-Admin-Key").and_then(|h| h.to_str().ok());
+    auth::authorize_admin_credentials(authorization, admin_key)
+        .map_err(|e| ErrorResponseBuilder::unauthorized(&e))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SelfTestQuery {
+    pub format: Option<String>,
+}
+
+#[get("/admin/selftest")]
+pub async fn run_selftest(
+    req: HttpRequest,
+    query: Query<SelfTestQuery>,
+    storage: Data<Arc<Storage>>,
+    blockchain_manager: Data<Arc<BlockchainManager>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    let format = query.format.as_deref().unwrap_or("json");
+    if !matches!(format, "json" | "junit" | "xml") {
+        return ErrorResponseBuilder::bad_request(&format!("Unsupported format '{}', expected json or junit", format));
+    }
+
+    log::info!("Self-test requested by {}", caller);
+
+    let runner = TestRunner::new(
+        Arc::clone(storage.get_ref()),
+        Arc::clone(blockchain_manager.get_ref()),
+    );
+    let suite = runner.run_safe_subset().await;
+
+    let mut response = if suite.passed() {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::ServiceUnavailable()
+    };
+
+    if format == "json" {
+        response.json(suite.to_json())
+    } else {
+        response
+            .content_type("application/xml; charset=utf-8")
+            .body(suite.to_junit_xml())
+    }
+}
+
+#[get("/admin/nonces")]
+pub async fn get_nonce_status(
+    req: HttpRequest,
+    nonce_monitor: Data<Arc<NonceMonitor>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "enabled": nonce_monitor.is_enabled(),
+        "chains": nonce_monitor.get_statuses().await,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+#[get("/admin/chains")]
+pub async fn list_chains(
+    req: HttpRequest,
+    config_manager: Data<Arc<DynamicConfigManager>>,
+    blockchain_manager: Data<Arc<BlockchainManager>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    let config = config_manager.get_config().await;
+    HttpResponse::Ok().json(serde_json::json!({
+        "chains": config.supported_chains,
+        "active_chain_ids": blockchain_manager.chain_ids(),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+/// Add or update chains from a registry document: JSON, or TOML when the
+/// content type says so. Chains not in the document are left alone.
+#[put("/admin/chains/registry")]
+pub async fn import_chain_registry(
+    req: HttpRequest,
+    body: String,
+    config_manager: Data<Arc<DynamicConfigManager>>,
+    blockchain_manager: Data<Arc<BlockchainManager>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+    let is_toml = req.headers().get("Content-Type")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("toml"));
+    let registry = match if is_toml { ChainRegistry::from_toml_str(&body) } else { ChainRegistry::from_json_str(&body) } {
+        Ok(registry) => registry,
+        Err(e) => return ErrorResponseBuilder::bad_request(&e.to_string()),
+    };
+
+    if let Err(e) = config_manager.apply_chain_registry(&registry).await {
+        return ErrorResponseBuilder::bad_request(&format!("Invalid chain configuration: {e}"));
+    }
+    let config = config_manager.get_config().await;
+    if let Err(e) = blockchain_manager.apply_chains(&config.supported_chains) {
+        return ErrorResponseBuilder::internal_server_error(&format!("Chains saved but not activated: {e}"));
+    }
+
+    let chain_ids: Vec<u64> = registry.chains.iter().map(|chain| chain.chain_id).collect();
+    log::info!("Chains {:?} imported from a registry by {}", chain_ids, caller);
+    HttpResponse::Ok().json(serde_json::json!({
+        "chain_ids": chain_ids,
+        "registry": ChainRegistry::from_chain_configs(&config.supported_chains),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+/// Add a chain or replace its RPC URL, contract address and explorer.
+/// Takes effect for new requests immediately; no restart needed.
+#[put("/admin/chains/{chain_id}")]
+pub async fn upsert_chain(
+    req: HttpRequest,
+    path: Path<u64>,
+    chain: Json<ChainConfig>,
+    config_manager: Data<Arc<DynamicConfigManager>>,
+    blockchain_manager: Data<Arc<BlockchainManager>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+    let chain_id = path.into_inner();
+    let chain = chain.into_inner();
+
+    if let Err(e) = config_manager.upsert_chain(chain_id, chain.clone()).await {
+        return ErrorResponseBuilder::bad_request(&format!("Invalid chain configuration: {e}"));
+    }
+    let config = config_manager.get_config().await;
+    if let Err(e) = blockchain_manager.apply_chains(&config.supported_chains) {
+        return ErrorResponseBuilder::internal_server_error(&format!("Chain saved but not activated: {e}"));
+    }
+
+    log::info!("Chain {} ({}) configured by {}", chain_id, chain.name, caller);
+    HttpResponse::Ok().json(serde_json::json!({
+        "chain_id": chain_id,
+        "chain": chain,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+#[delete("/admin/chains/{chain_id}")]
+pub async fn remove_chain(
+    req: HttpRequest,
+    path: Path<u64>,
+    config_manager: Data<Arc<DynamicConfigManager>>,
+    blockchain_manager: Data<Arc<BlockchainManager>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+    let chain_id = path.into_inner();
+
+    let removed = match config_manager.remove_chain(chain_id).await {
+        Ok(removed) => removed,
+        Err(e) => return ErrorResponseBuilder::bad_request(&e.to_string()),
+    };
+    let config = config_manager.get_config().await;
+    if let Err(e) = blockchain_manager.apply_chains(&config.supported_chains) {
+        return ErrorResponseBuilder::internal_server_error(&format!("Chain removed but clients not refreshed: {e}"));
+    }
+
+    log::info!("Chain {} ({}) removed by {}", chain_id, removed.name, caller);
+    HttpResponse::Ok().json(serde_json::json!({
+        "chain_id": chain_id,
+        "removed": removed,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PruneQuery {
+    pub dry_run: Option<bool>,
+}
+
+/// Run the retention job now. Defaults to a dry run that only reports.
+#[post("/admin/prune")]
+pub async fn run_prune(
+    req: HttpRequest,
+    query: Query<PruneQuery>,
+    pruner: Data<Arc<DataPruner>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    let dry_run = query.dry_run.unwrap_or(true);
+    log::info!("Data pruning ({}) requested by {}", if dry_run { "dry run" } else { "live" }, caller);
+
+    match pruner.run(dry_run).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => ErrorResponseBuilder::internal_server_error(&format!("Data pruning failed: {e}")),
+    }
+}
+
+#[get("/admin/prune")]
+pub async fn get_prune_status(
+    req: HttpRequest,
+    pruner: Data<Arc<DataPruner>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "policy": pruner.policy(),
+        "audit_policy": pruner.audit_policy(),
+        "last_report": pruner.last_report().await,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ArchiveRequest {
+    pub reason: Option<String>,
+}
+
+/// Soft delete: moves the transaction out of hot storage into the archive
+#[delete("/admin/transactions/{transaction_id}")]
+pub async fn archive_transaction(
+    req: HttpRequest,
+    path: Path<String>,
+    query: Query<ArchiveRequest>,
+    storage: Data<Arc<Storage>>,
+    audit_logger: Data<Arc<AuditLogger>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    let transaction_id = path.into_inner();
+    if storage.get_transaction(&transaction_id).is_none() {
+        return ErrorResponseBuilder::not_found("Transaction not found");
+    }
+
+    let reason = query.reason.clone().unwrap_or_else(|| format!("archived by {caller}"));
+    match storage.archive_transaction(&transaction_id, &reason) {
+        Ok(archived) => {
+            let mut details = std::collections::HashMap::new();
+            details.insert("reason".to_string(), serde_json::json!(reason));
+            details.insert("requested_by".to_string(), serde_json::json!(caller));
+            if let Err(e) = audit_logger.log_data_purge("transactions", &[transaction_id], false, details).await {
+                log::warn!("Failed to audit transaction archival: {}", e);
+            }
+            HttpResponse::Ok().json(archived)
+        }
+        Err(e) => ErrorResponseBuilder::internal_server_error(&format!("Failed to archive transaction: {e}")),
+    }
+}
+
+#[get("/admin/archive/transactions")]
+pub async fn search_archived_transactions(
+    req: HttpRequest,
+    query: Query<ArchiveQuery>,
+    storage: Data<Arc<Storage>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    match storage.search_archived_transactions(&query) {
+        Ok(results) => HttpResponse::Ok().json(serde_json::json!({
+            "count": results.len(),
+            "transactions": results,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        })),
+        Err(e) => ErrorResponseBuilder::internal_server_error(&format!("Archive search failed: {e}")),
+    }
+}
+
+#[get("/admin/archive/transactions/{transaction_id}")]
+pub async fn get_archived_transaction(
+    req: HttpRequest,
+    path: Path<String>,
+    storage: Data<Arc<Storage>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    let query = ArchiveQuery {
+        id: Some(path.into_inner()),
+        limit: Some(1),
+        ..Default::default()
+    };
+    match storage.search_archived_transactions(&query) {
+        Ok(mut results) => match results.pop() {
+            Some(archived) => HttpResponse::Ok().json(archived),
+            None => ErrorResponseBuilder::not_found("Archived transaction not found"),
+        },
+        Err(e) => ErrorResponseBuilder::internal_server_error(&format!("Archive search failed: {e}")),
+    }
+}
+
+/// Download a CSV extract of transactions matching the filters
+#[get("/admin/export/transactions")]
+pub async fn export_transactions(
+    req: HttpRequest,
+    query: Query<ExportQuery>,
+    storage: Data<Arc<Storage>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    let exporter = TransactionExporter::new(Arc::clone(storage.get_ref()));
+    match exporter.to_csv(&query) {
+        Ok((csv, rows)) => HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header((
+                "Content-Disposition",
+                format!("attachment; filename=\"transactions_{}.csv\"", chrono::Utc::now().format("%Y%m%d_%H%M%S")),
+            ))
+            .insert_header(("X-Export-Rows", rows.to_string()))
+            .body(csv),
+        Err(e) => ErrorResponseBuilder::internal_server_error(&format!("Transaction export failed: {e}")),
+    }
+}
+
+/// Write a compressed CSV extract next to the backups for warehouse pickup
+#[post("/admin/export/transactions")]
+pub async fn write_transaction_export(
+    req: HttpRequest,
+    query: Query<ExportQuery>,
+    storage: Data<Arc<Storage>>,
+    backup_manager: Data<Arc<BackupManager>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    let exporter = TransactionExporter::new(Arc::clone(storage.get_ref()));
+    match exporter.write_to(backup_manager.backup_dir(), &query) {
+        Ok(summary) => {
+            log::info!("Transaction export {} ({} rows) written for {}", summary.path, summary.rows, caller);
+            HttpResponse::Ok().json(summary)
+        }
+        Err(e) => ErrorResponseBuilder::internal_server_error(&format!("Transaction export failed: {e}")),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TopDevicesQuery {
+    pub metric: Option<DeviceMetric>,
+    pub limit: Option<usize>,
+}
+
+/// Devices ranked by a per-device counter, to spot abusive or broken clients
+#[get("/admin/devices/top")]
+pub async fn get_top_devices(
+    req: HttpRequest,
+    query: Query<TopDevicesQuery>,
+    monitoring_manager: Data<Arc<MonitoringManager>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    let metric = query.metric.unwrap_or(DeviceMetric::Failures);
+    let limit = query.limit.unwrap_or(10).min(100);
+    HttpResponse::Ok().json(serde_json::json!({
+        "metric": metric,
+        "devices": monitoring_manager.top_devices(metric, limit).await,
+        "tracked_devices": monitoring_manager.tracked_device_count().await,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+#[get("/admin/config/rollout")]
+pub async fn get_config_rollout(
+    req: HttpRequest,
+    config_rollout: Data<Arc<ConfigRollout>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "policy": config_rollout.policy(),
+        "rollout": config_rollout.status().await,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+/// Revert a staged configuration change before its window closes
+#[post("/admin/config/rollout/rollback")]
+pub async fn rollback_config_rollout(
+    req: HttpRequest,
+    config_rollout: Data<Arc<ConfigRollout>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    match config_rollout.rollback(&format!("rolled back by {caller}")).await {
+        Ok(status) => HttpResponse::Ok().json(status),
+        Err(e) => ErrorResponseBuilder::bad_request(&e.to_string()),
+    }
+}
+
+#[get("/admin/federation")]
+pub async fn get_federation_status(
+    req: HttpRequest,
+    federation: Data<Arc<Federation>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "identity": federation.identity().address(),
+        "config": federation.config(),
+        "status": federation.status().await,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+/// Forward the local queue upstream now instead of waiting for the next sync
+#[post("/admin/federation/sync")]
+pub async fn sync_federation(
+    req: HttpRequest,
+    federation: Data<Arc<Federation>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    if !federation.is_edge() {
+        return ErrorResponseBuilder::bad_request("Relay is not running in edge mode");
+    }
+    match federation.sync_once().await {
+        Ok(forwarded) => HttpResponse::Ok().json(serde_json::json!({
+            "forwarded": forwarded,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        })),
+        Err(e) => ErrorResponseBuilder::service_unavailable(&e.to_string()),
+    }
+}
+
+#[get("/admin/attestations")]
+pub async fn get_attestations(
+    req: HttpRequest,
+    attestation: Data<Arc<AttestationService>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "config": attestation.config(),
+        "batches": attestation.batches().await,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+/// Batch and attest completed transactions now instead of waiting for the next run
+#[post("/admin/attestations/run")]
+pub async fn run_attestation(
+    req: HttpRequest,
+    attestation: Data<Arc<AttestationService>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    match attestation.run_once().await {
+        Ok(batches) => HttpResponse::Ok().json(serde_json::json!({
+            "batches": batches,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        })),
+        Err(e) => ErrorResponseBuilder::internal_server_error(&e.to_string()),
+    }
+}
+
+#[get("/admin/reconciliation")]
+pub async fn get_reconciliation_status(
+    req: HttpRequest,
+    reconciler: Data<Arc<Reconciler>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "config": reconciler.config(),
+        "last_report": reconciler.last_report().await,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReconciliationQuery {
+    pub dry_run: Option<bool>,
+}
+
+/// Reconcile stored statuses with the chain now. Defaults to a dry run that only reports.
+#[post("/admin/reconciliation/run")]
+pub async fn run_reconciliation(
+    req: HttpRequest,
+    query: Query<ReconciliationQuery>,
+    reconciler: Data<Arc<Reconciler>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    let dry_run = query.dry_run.unwrap_or(true);
+    log::info!("Reconciliation ({}) requested by {}", if dry_run { "dry run" } else { "live" }, caller);
+
+    match reconciler.run(dry_run).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => ErrorResponseBuilder::internal_server_error(&format!("Reconciliation failed: {e}")),
+    }
+}
+
+/// Gas paid from relay wallets by chain, device and merchant, with budgets
+#[get("/admin/gas/spend")]
+pub async fn get_gas_spend(
+    req: HttpRequest,
+    gas_ledger: Data<Arc<GasLedger>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "config": gas_ledger.config(),
+        "report": gas_ledger.report().await,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+/// One-time token a device exchanges for its first certificate
+#[post("/admin/devices/{device_id}/enrollment-token")]
+pub async fn create_enrollment_token(
+    req: HttpRequest,
+    path: Path<String>,
+    enrollment: Data<Arc<DeviceEnrollment>>,
+    audit_logger: Data<Arc<AuditLogger>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    let device_id = path.into_inner();
+    let token = enrollment.create_token(&device_id).await;
+    let mut details = std::collections::HashMap::new();
+    details.insert("expires_at".to_string(), serde_json::json!(token.expires_at));
+    let _ = audit_logger.log_device_credential("create_enrollment_token", &device_id, details, Some(caller)).await;
+    HttpResponse::Ok().json(token)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CertificateQuery {
+    pub device_id: Option<String>,
+}
+
+#[get("/admin/devices/certificates")]
+pub async fn list_device_certificates(
+    req: HttpRequest,
+    query: Query<CertificateQuery>,
+    enrollment: Data<Arc<DeviceEnrollment>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    let certificates = enrollment.ca().issued(query.device_id.as_deref());
+    HttpResponse::Ok().json(serde_json::json!({
+        "certificates": certificates,
+        "total": certificates.len(),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+#[post("/admin/devices/certificates/{serial}/revoke")]
+pub async fn revoke_device_certificate(
+    req: HttpRequest,
+    path: Path<String>,
+    enrollment: Data<Arc<DeviceEnrollment>>,
+    audit_logger: Data<Arc<AuditLogger>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    match enrollment.ca().revoke(&path.into_inner()) {
+        Ok(record) => {
+            let mut details = std::collections::HashMap::new();
+            details.insert("serial".to_string(), serde_json::json!(record.serial));
+            let _ = audit_logger.log_device_credential("revoke_certificate", &record.device_id, details, Some(caller)).await;
+            HttpResponse::Ok().json(record)
+        }
+        Err(e) => ErrorResponseBuilder::not_found(&e.to_string()),
+    }
+}
+
+/// Revoke every live certificate of a device, e.g. when it is lost
+#[post("/admin/devices/{device_id}/revoke")]
+pub async fn revoke_device(
+    req: HttpRequest,
+    path: Path<String>,
+    enrollment: Data<Arc<DeviceEnrollment>>,
+    audit_logger: Data<Arc<AuditLogger>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    let device_id = path.into_inner();
+    match enrollment.ca().revoke_device(&device_id) {
+        Ok(revoked) => {
+            let mut details = std::collections::HashMap::new();
+            details.insert("revoked".to_string(), serde_json::json!(revoked));
+            let _ = audit_logger.log_device_credential("revoke_device", &device_id, details, Some(caller)).await;
+            HttpResponse::Ok().json(serde_json::json!({
+                "device_id": device_id,
+                "revoked": revoked,
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+            }))
+        }
+        Err(e) => ErrorResponseBuilder::internal_server_error(&e.to_string()),
+    }
+}
+
+async fn audit_api_key_change(audit_logger: &AuditLogger, caller: String, action: &str, record: &ApiKeyRecord) {
+    let mut details = std::collections::HashMap::new();
+    details.insert("key_id".to_string(), serde_json::json!(record.id));
+    details.insert("owner".to_string(), serde_json::json!(record.owner));
+    details.insert("scopes".to_string(), serde_json::json!(record.scopes));
+    if let Err(e) = audit_logger.log_security_event(
+        Some(caller),
+        None,
+        None,
+        action.to_string(),
+        details,
+        crate::utils::audit::AuditSeverity::High,
+        None,
+    ).await {
+        log::warn!("Failed to audit {}: {}", action, e);
+    }
+}
+
+/// Client API keys, without their hashes
+#[get("/admin/api-keys")]
+pub async fn list_api_keys(
+    req: HttpRequest,
+    api_keys: Data<Arc<ApiKeyStore>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    let now = chrono::Utc::now();
+    let keys: Vec<serde_json::Value> = api_keys.list().into_iter()
+        .map(|key| {
+            let active = key.is_active(now);
+            let mut value = serde_json::json!(key);
+            value["active"] = serde_json::json!(active);
+            value
+        })
+        .collect();
+    HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "keys": keys,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub owner: String,
+    /// Roles for tokens issued with the key; device when empty
+    #[serde(default)]
+    pub scopes: Vec<auth::Role>,
+    pub expires_in_days: Option<i64>,
+}
+
+/// Create a client API key. The key itself is returned only in this response.
+#[post("/admin/api-keys")]
+pub async fn create_api_key(
+    req: HttpRequest,
+    body: Json<CreateApiKeyRequest>,
+    api_keys: Data<Arc<ApiKeyStore>>,
+    audit_logger: Data<Arc<AuditLogger>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    let body = body.into_inner();
+    let expires_at = body.expires_in_days.map(|days| chrono::Utc::now() + chrono::Duration::days(days.max(1)));
+    match api_keys.create(&body.owner, body.scopes, expires_at) {
+        Ok((record, api_key)) => {
+            audit_api_key_change(&audit_logger, caller, "create_api_key", &record).await;
+            HttpResponse::Ok().json(serde_json::json!({
+                "api_key": api_key,
+                "key": record,
+            }))
+        }
+        Err(e) => ErrorResponseBuilder::bad_request(&e.to_string()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RotateApiKeyQuery {
+    /// How long the old key keeps working; default one hour
+    pub grace_secs: Option<i64>,
+}
+
+/// Replace a client API key with a new one for the same owner and scopes;
+/// the old key keeps working for the grace period
+#[post("/admin/api-keys/{key_id}/rotate")]
+pub async fn rotate_api_key(
+    req: HttpRequest,
+    path: Path<String>,
+    query: Query<RotateApiKeyQuery>,
+    api_keys: Data<Arc<ApiKeyStore>>,
+    audit_logger: Data<Arc<AuditLogger>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    let key_id = path.into_inner();
+    let grace = chrono::Duration::seconds(query.grace_secs.unwrap_or(3600).max(0));
+    match api_keys.rotate(&key_id, grace) {
+        Ok((record, api_key)) => {
+            audit_api_key_change(&audit_logger, caller, "rotate_api_key", &record).await;
+            HttpResponse::Ok().json(serde_json::json!({
+                "api_key": api_key,
+                "key": record,
+                "previous_key_id": key_id,
+                "previous_key_valid_until": (chrono::Utc::now() + grace).to_rfc3339(),
+            }))
+        }
+        Err(e) => ErrorResponseBuilder::not_found(&e.to_string()),
+    }
+}
+
+/// Revoke a client API key immediately
+#[delete("/admin/api-keys/{key_id}")]
+pub async fn revoke_api_key(
+    req: HttpRequest,
+    path: Path<String>,
+    api_keys: Data<Arc<ApiKeyStore>>,
+    audit_logger: Data<Arc<AuditLogger>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    match api_keys.revoke(&path.into_inner()) {
+        Ok(record) => {
+            audit_api_key_change(&audit_logger, caller, "revoke_api_key", &record).await;
+            HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "key": record,
+            }))
+        }
+        Err(e) => ErrorResponseBuilder::not_found(&e.to_string()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IssueTokenRequest {
+    pub subject: String,
+    pub roles: Vec<auth::Role>,
+    /// Lifetime in hours; default 24, at most 30 days
+    pub ttl_hours: Option<i64>,
+}
+
+/// Issue a JWT carrying the given roles, e.g. a merchant token for
+/// reading transactions or a narrower admin-listener token
+#[post("/admin/tokens")]
+pub async fn issue_token(
+    req: HttpRequest,
+    body: Json<IssueTokenRequest>,
+    audit_logger: Data<Arc<AuditLogger>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+    if body.subject.trim().is_empty() || body.roles.is_empty() {
+        return ErrorResponseBuilder::bad_request("subject and at least one role are required");
+    }
+
+    let ttl = chrono::Duration::hours(body.ttl_hours.unwrap_or(24).clamp(1, 24 * 30));
+    let token_type = if body.roles.contains(&auth::Role::Admin) { "admin" } else { body.roles[0].as_str() };
+    let token = auth::generate_jwt_token_with_roles(&body.subject, token_type, &body.roles, ttl);
+    let mut details = std::collections::HashMap::new();
+    details.insert("subject".to_string(), serde_json::json!(body.subject));
+    details.insert("roles".to_string(), serde_json::json!(body.roles));
+    let _ = audit_logger.log_security_event(
+        Some(caller),
+        None,
+        None,
+        "issue_token".to_string(),
+        details,
+        crate::utils::audit::AuditSeverity::High,
+        None,
+    ).await;
+    HttpResponse::Ok().json(serde_json::json!({
+        "token": token,
+        "roles": body.roles,
+        "expires_at": (chrono::Utc::now() + ttl).to_rfc3339(),
+    }))
+}
+
+/// Resident memory against the container limit, and the current shedding level
+#[get("/admin/memory")]
+pub async fn get_memory_status(
+    req: HttpRequest,
+    memory_guard: Data<Arc<MemoryGuard>>,
+    processor: Data<Arc<TransactionProcessor>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "config": memory_guard.config(),
+        "status": memory_guard.check().await,
+        "queue_depth": processor.queue_depth().await,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+/// Latest canary self-transfer outcome per chain
+#[get("/admin/canary")]
+pub async fn get_canary_status(
+    req: HttpRequest,
+    canary: Data<Arc<CanaryMonitor>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "enabled": canary.is_enabled(),
+        "chains": canary.results().await,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+/// Send a canary on one chain now and wait for its outcome
+#[post("/admin/canary/{chain_id}")]
+pub async fn run_canary(
+    req: HttpRequest,
+    path: Path<u64>,
+    canary: Data<Arc<CanaryMonitor>>,
+    blockchain_manager: Data<Arc<BlockchainManager>>,
+) -> impl Responder {
+    let caller = match authorize_admin(&req) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    let chain_id = path.into_inner();
+    if !blockchain_manager.chain_ids().contains(&chain_id) {
+        return ErrorResponseBuilder::not_found(&format!("Unknown chain {chain_id}"));
+    }
+    log::info!("Canary on chain {} requested by {}", chain_id, caller);
+    HttpResponse::Ok().json(canary.run_chain(chain_id).await)
+}
+
+/// Cached token metadata and payment contract verification per chain
+#[get("/admin/tokens")]
+pub async fn get_token_registry(
+    req: HttpRequest,
+    registry: Data<Arc<TokenRegistry>>,
+) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
+
+    let mut snapshot = registry.snapshot().await;
+    snapshot["timestamp"] = serde_json::json!(chrono::Utc::now().to_rfc3339());
+    HttpResponse::Ok().json(snapshot)
+}
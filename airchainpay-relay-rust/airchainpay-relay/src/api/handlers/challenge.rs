@@ -0,0 +1,16 @@
+use actix_web::{get, HttpResponse, Responder};
+use actix_web::web::Data;
+use std::sync::Arc;
+use crate::middleware::challenge::ProofOfWork;
+
+/// Proof-of-work challenge to solve before calling the credential endpoints.
+/// Find a nonce such that `sha256("<challenge>:<nonce>")` starts with
+/// `difficulty` zero bits, then send both in `X-PoW-Challenge` / `X-PoW-Nonce`.
+#[get("/auth/challenge")]
+pub async fn get_auth_challenge(pow: Data<Arc<ProofOfWork>>) -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({
+        "required": pow.config().enabled,
+        "challenge": pow.issue(),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
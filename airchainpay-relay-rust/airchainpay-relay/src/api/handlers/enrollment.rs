@@ -0,0 +1,72 @@
+use actix_web::{get, post, HttpRequest, HttpResponse, Responder};
+use actix_web::web::{Data, Json};
+use serde::Deserialize;
+use std::sync::Arc;
+use crate::app::enrollment::DeviceEnrollment;
+use crate::domain::auth;
+use crate::middleware::client_cert;
+use crate::middleware::error_handling::ErrorResponseBuilder;
+
+#[derive(Debug, Deserialize)]
+pub struct EnrollRequest {
+    pub enrollment_token: String,
+    /// PEM PKCS#10 request; the subject is ignored and set from the token
+    pub csr: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenewRequest {
+    pub csr: String,
+}
+
+/// First certificate for a device, authorized by a one-time enrollment token
+#[post("/devices/enroll")]
+pub async fn enroll_device(
+    enrollment: Data<Arc<DeviceEnrollment>>,
+    body: Json<EnrollRequest>,
+) -> impl Responder {
+    match enrollment.enroll(&body.enrollment_token, &body.csr).await {
+        Ok(issued) => HttpResponse::Ok().json(issued),
+        Err(e) => ErrorResponseBuilder::bad_request(&e.to_string()),
+    }
+}
+
+/// New certificate for a device presenting its current, still valid one
+#[post("/devices/renew")]
+pub async fn renew_device_certificate(
+    req: HttpRequest,
+    enrollment: Data<Arc<DeviceEnrollment>>,
+    body: Json<RenewRequest>,
+) -> impl Responder {
+    let Some(current) = client_cert::device_identity(&req, enrollment.ca()) else {
+        return ErrorResponseBuilder::unauthorized("A valid device certificate is required");
+    };
+    match enrollment.renew(&current, &body.csr) {
+        Ok(issued) => HttpResponse::Ok().json(issued),
+        Err(e) => ErrorResponseBuilder::bad_request(&e.to_string()),
+    }
+}
+
+/// Device CA chain, for pinning on devices and for TLS proxies verifying client certificates
+#[get("/devices/ca")]
+pub async fn get_device_ca(enrollment: Data<Arc<DeviceEnrollment>>) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("application/x-pem-file")
+        .body(enrollment.ca().ca_chain_pem().to_string())
+}
+
+/// Exchange a device certificate for a short-lived device JWT, in place of the shared API key
+#[post("/auth/certificate")]
+pub async fn certificate_token(
+    req: HttpRequest,
+    enrollment: Data<Arc<DeviceEnrollment>>,
+) -> impl Responder {
+    let Some(identity) = client_cert::device_identity(&req, enrollment.ca()) else {
+        return ErrorResponseBuilder::unauthorized("A valid device certificate is required");
+    };
+    HttpResponse::Ok().json(serde_json::json!({
+        "token": auth::generate_jwt_token(&identity.device_id, "device"),
+        "device_id": identity.device_id,
+        "certificate_expires_at": identity.not_after.to_rfc3339(),
+    }))
+}
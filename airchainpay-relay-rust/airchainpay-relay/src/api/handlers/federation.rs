@@ -0,0 +1,36 @@
+use actix_web::{get, post, HttpResponse, Responder};
+use actix_web::web::{Data, Json};
+use std::sync::Arc;
+use crate::app::federation::{Federation, SignedManifest};
+use crate::infrastructure::ble::session::BleSessionManager;
+use crate::middleware::error_handling::ErrorResponseBuilder;
+
+/// Accept a signed manifest of queued transactions from a trusted peer relay
+#[post("/federation/ingest")]
+pub async fn ingest_manifest(
+    federation: Data<Arc<Federation>>,
+    manifest: Json<SignedManifest>,
+) -> impl Responder {
+    let manifest = manifest.into_inner();
+    if let Err(e) = federation.verify_manifest(&manifest) {
+        log::warn!("Rejected federation manifest {}: {}", manifest.manifest.manifest_id, e);
+        return ErrorResponseBuilder::forbidden(&e);
+    }
+
+    HttpResponse::Ok().json(federation.ingest(manifest).await)
+}
+
+/// Identity address peers add to `FEDERATION_TRUSTED_PEERS`, and the Noise
+/// static key devices check during the session handshake
+#[get("/federation/identity")]
+pub async fn get_relay_identity(
+    federation: Data<Arc<Federation>>,
+    sessions: Data<Arc<BleSessionManager>>,
+) -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({
+        "address": federation.identity().address(),
+        "noise_static_key": sessions.noise_public_key().map(hex::encode),
+        "mode": if federation.is_edge() { "edge" } else { "standard" },
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
@@ -1,3 +1,13 @@
+pub mod admin;
+pub mod ws_ble;
+pub mod ws_status;
+pub mod transaction_events;
+pub mod federation;
+pub mod enrollment;
+pub mod challenge;
+pub mod probes;
+pub mod quota;
+pub mod well_known;
 pub mod transaction;
 pub use transaction::{
     health,
@@ -42,10 +52,22 @@ pub use transaction::{
     update_configuration_field,
     save_configuration_to_file,
     process_transaction,
+    process_compressed_transaction,
     get_transactions,
     get_metrics,
     get_devices,
     test_transaction,
     simple_send_tx,
     get_transaction_details,
+    generate_token,
 };
+pub use admin::{run_selftest, get_nonce_status, list_chains, import_chain_registry, upsert_chain, remove_chain, run_prune, get_prune_status, archive_transaction, search_archived_transactions, get_archived_transaction, export_transactions, write_transaction_export, get_top_devices, get_config_rollout, rollback_config_rollout, get_federation_status, sync_federation, get_attestations, run_attestation, get_reconciliation_status, run_reconciliation, get_gas_spend, create_enrollment_token, list_device_certificates, revoke_device_certificate, revoke_device, list_api_keys, create_api_key, rotate_api_key, revoke_api_key, issue_token, get_memory_status, get_canary_status, run_canary, get_token_registry};
+pub use ws_ble::ws_ble_bridge;
+pub use ws_status::ws_transaction_status;
+pub use transaction_events::transaction_events;
+pub use federation::{ingest_manifest, get_relay_identity};
+pub use enrollment::{enroll_device, renew_device_certificate, get_device_ca, certificate_token};
+pub use challenge::get_auth_challenge;
+pub use probes::{liveness, readiness, startup};
+pub use quota::get_quota;
+pub use well_known::relay_identity_document;
@@ -0,0 +1,39 @@
+use actix_web::{get, HttpResponse, Responder};
+use actix_web::web::Data;
+use std::sync::Arc;
+use crate::app::probes::ProbeState;
+
+/// Liveness: the process is up and serving requests
+#[get("/health/live")]
+pub async fn liveness() -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({ "status": "ok" }))
+}
+
+/// Readiness: storage writable, at least one chain reachable, queue below its high-water mark
+#[get("/health/ready")]
+pub async fn readiness(probes: Data<Arc<ProbeState>>) -> impl Responder {
+    let report = probes.readiness().await;
+    let mut response = if report.ready {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::ServiceUnavailable()
+    };
+    response.json(serde_json::json!({
+        "status": if report.ready { "ok" } else { "fail" },
+        "checks": {
+            "storage": report.storage,
+            "chains": report.chains,
+            "queue": report.queue,
+        },
+    }))
+}
+
+/// Startup: initialization has finished
+#[get("/health/startup")]
+pub async fn startup(probes: Data<Arc<ProbeState>>) -> impl Responder {
+    if probes.is_started() {
+        HttpResponse::Ok().json(serde_json::json!({ "status": "ok" }))
+    } else {
+        HttpResponse::ServiceUnavailable().json(serde_json::json!({ "status": "starting" }))
+    }
+}
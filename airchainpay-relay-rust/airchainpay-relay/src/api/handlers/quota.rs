@@ -0,0 +1,43 @@
+use actix_web::{get, HttpRequest, HttpResponse, Responder};
+use actix_web::web::{Data, Path};
+use std::sync::Arc;
+use crate::domain::auth;
+use crate::middleware::error_handling::ErrorResponseBuilder;
+use crate::middleware::quota::{QuotaManager, QuotaSubject};
+use super::admin::authorize_admin;
+
+/// Submission quota usage of a device. Visible to admins and to the device
+/// itself; a caller whose token was issued for an API key also gets that
+/// key's usage.
+#[get("/quota/{device_id}")]
+pub async fn get_quota(
+    req: HttpRequest,
+    path: Path<String>,
+    quotas: Data<Arc<QuotaManager>>,
+) -> impl Responder {
+    let device_id = path.into_inner();
+    let claims = req.headers().get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .and_then(|token| auth::verify_jwt_token(token.trim()).ok());
+    if claims.as_ref().map(|c| c.sub.as_str()) != Some(device_id.as_str()) {
+        if let Err(response) = authorize_admin(&req) {
+            return response;
+        }
+    }
+
+    let now = chrono::Utc::now();
+    let device = quotas.report(&QuotaSubject::Device(device_id.clone()), now).await;
+    let api_key = match claims.and_then(|c| c.key_id) {
+        Some(key_id) => Some(quotas.report(&QuotaSubject::ApiKey(key_id), now).await),
+        None => None,
+    };
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "device_id": device_id,
+        "device": device,
+        "api_key": api_key,
+        "timestamp": now.to_rfc3339(),
+    }))
+}
+
@@ -1,22 +1,33 @@
-use actix_web::{get, post, delete, web, HttpResponse, Responder};
+use actix_web::{get, post, delete, web, HttpRequest, HttpResponse, Responder};
 use actix_web::web::Data;
 use serde::{Deserialize, Serialize};
-use crate::infrastructure::storage::file_storage::{Storage, Transaction};
+use crate::infrastructure::storage::file_storage::{validate_tags, Storage, Transaction};
+use crate::infrastructure::storage::backend::TransactionSearch;
+use crate::infrastructure::chain_registry::ChainRegistry;
+use crate::infrastructure::storage::payments::PaymentQuery;
 use crate::infrastructure::blockchain::manager::BlockchainManager;
-use crate::infrastructure::monitoring::manager::{MonitoringManager, AlertSeverity};
+use crate::infrastructure::blockchain::fees::FeeOracle;
+use crate::infrastructure::monitoring::manager::{MonitoringManager, AlertSeverity, BleSessionMetrics, BLE_DEGRADED_RETRANSMIT_RATIO};
 use crate::utils::error_handler::EnhancedErrorHandler;
-use crate::infrastructure::config::DynamicConfigManager;
+use crate::infrastructure::config::{Config, DynamicConfigManager};
+use crate::app::config_rollout::ConfigRollout;
+use crate::app::attestation::AttestationService;
+use crate::validators::payload_tier::{PayloadTier, PayloadTierConfig};
+use crate::app::health_visibility::HealthVisibilityConfig;
+use super::admin::authorize_admin;
 use crate::middleware::error_handling::ErrorResponseBuilder;
 use crate::utils::audit::{AuditLogger, AuditSeverity, AuditFilter, AuditEventType};
 use crate::utils::backup::{BackupType, BackupFilter, BackupManager, RestoreOptions};
 use std::sync::Arc;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use actix_web::web::{Json, Query, Path};
 use chrono::{DateTime, Utc};
-use crate::app::transaction_service::{QueuedTransaction, TransactionProcessor, TransactionPriority};
+use crate::app::transaction_service::{QueuedTransaction, QueueOverloaded, TransactionProcessor, TransactionPriority};
+use crate::app::replay_guard::{DuplicateTransaction, TxReplayGuard};
 use serde_json::json;
 use crate::domain::auth;
+use crate::domain::auth::api_keys::ApiKeyStore;
 use crate::domain::error::{RelayError, BlockchainError};
 use ethers::core::types::Address;
 use std::str::FromStr;
@@ -26,8 +37,13 @@ pub struct ContractPaymentsQuery {
     pub chain_id: Option<u64>,
     pub from_block: Option<u64>,
     pub to_block: Option<u64>,
+    /// Payer; `from_address` is accepted as an alias
+    pub payer: Option<String>,
+    /// Merchant; `to_address` is accepted as an alias
+    pub merchant: Option<String>,
     pub from_address: Option<String>,
     pub to_address: Option<String>,
+    pub reference: Option<String>,
     pub limit: Option<u64>,
     pub offset: Option<u64>,
 }
@@ -44,26 +60,17 @@ pub struct PaymentResponse {
     pub log_index: u64,
 }
 
-// Helper function to get block explorer URL for a given chain ID
-fn get_block_explorer_url(chain_id: u64, tx_hash: &str) -> String {
-    match chain_id {
-        1114 => format!("https://scan.test2.btcs.network/tx/{}", tx_hash), // Core Testnet
-        84532 => format!("https://sepolia.basescan.org/tx/{}", tx_hash), // Base Sepolia
-        17000 => format!("https://holesky.etherscan.io/tx/{}", tx_hash), // Morph Holesky
-        4202 => format!("https://sepolia.scroll.io/tx/{}", tx_hash), // Lisk Sepolia
-        _ => format!("https://explorer.unknown.com/tx/{}", tx_hash), // Fallback
-    }
+/// Explorer link for `tx_hash`, from the chain's configured explorer
+fn get_block_explorer_url(config: &Config, chain_id: u64, tx_hash: &str) -> Option<String> {
+    config.supported_chains.get(&chain_id)
+        .map(|chain| format!("{}/tx/{}", chain.explorer.trim_end_matches('/'), tx_hash))
 }
 
-// Helper function to get chain name for a given chain ID
-fn get_chain_name(chain_id: u64) -> &'static str {
-    match chain_id {
-        1114 => "Core Testnet",
-        84532 => "Base Sepolia",
-        17000 => "Morph Holesky",
-        4202 => "Lisk Sepolia",
-        _ => "Unknown Chain",
-    }
+/// Configured name of a chain
+fn get_chain_name(config: &Config, chain_id: u64) -> &str {
+    config.supported_chains.get(&chain_id)
+        .map(|chain| chain.name.as_str())
+        .unwrap_or("Unknown Chain")
 }
 
 #[get("/health")]
@@ -81,79 +88,82 @@ pub struct SendTxRequest {
     pub signed_tx: String,
     pub rpc_url: String,
     pub chain_id: u64,
- 
-}
+    /// Tags stored with the transaction, e.g. `{"order_id": "1042", "till": "3"}`
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// Subject of a valid bearer token, used to attribute submissions to a device
+fn submitting_device(http_req: &HttpRequest) -> Option<String> {
+    let header = http_req.headers().get("Authorization")?.to_str().ok()?;
+    let token = header.strip_prefix("Bearer ")?;
+    auth::verify_jwt_token(token.trim()).ok().map(|claims| claims.sub)
+}
+
+/// Record rejected validation with the error handler and build the 400,
+/// the same for the fast and full paths
+async fn validation_failure(req: &SendTxRequest, errors: &[String], error_handler: &EnhancedErrorHandler) -> HttpResponse {
+    let error_context = {
+        let mut context = std::collections::HashMap::new();
+        context.insert("validation_errors".to_string(), errors.join(", "));
+        context.insert("transaction_hash".to_string(), req.signed_tx.clone());
+        context.insert("chain_id".to_string(), req.chain_id.to_string());
+        context
+    };
 
-// Add this helper function before process_transaction
-async fn handle_transaction_submission(
-    req: web::Json<SendTxRequest>,
-    storage: Data<Arc<Storage>>,
-    blockchain_manager: Data<Arc<BlockchainManager>>,
-    error_handler: Data<Arc<EnhancedErrorHandler>>,
-    config_manager: Data<Arc<DynamicConfigManager>>,
-    processor: Data<Arc<TransactionProcessor>>,
-) -> impl Responder {
-    // Basic raw tx hex sanity check (do not treat as a tx hash)
-    let signed_tx_str = req.signed_tx.as_str();
-    if !(signed_tx_str.starts_with("0x") 
-        && signed_tx_str.len() > 2 
-        && signed_tx_str.len() % 2 == 0 
-        && hex::decode(signed_tx_str.trim_start_matches("0x")).is_ok())
-    {
-        return ErrorResponseBuilder::bad_request("Invalid raw transaction: must be 0x-prefixed, even-length, valid hex");
-    }
+    // Record validation errors using enhanced error handling
+    let _ = error_handler.record_error(crate::utils::error_handler::ErrorRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: chrono::Utc::now(),
+        path: crate::utils::error_handler::CriticalPath::TransactionProcessing,
+        error_type: crate::utils::error_handler::ErrorType::Unknown,
+        error_message: format!("Transaction validation failed: {}", errors.join(", ")),
+        context: error_context,
+        severity: crate::utils::error_handler::ErrorSeverity::Medium,
+        retry_count: 0,
+        max_retries: 0,
+        resolved: false,
+        resolution_time: None,
+        stack_trace: None,
+        user_id: None,
+        device_id: None,
+        transaction_id: Some(req.signed_tx.clone()),
+        chain_id: Some(req.chain_id),
+        ip_address: None,
+        component: "transaction_validator".to_string(),
+    }).await;
+
+    ErrorResponseBuilder::bad_request(&format!("Transaction validation failed: {}", errors.join(", ")))
+}
 
+/// Full pipeline for payloads that miss the fast path: check every
+/// network's health, then run the complete `TransactionValidator`
+async fn validate_full(
+    req: &SendTxRequest,
+    blockchain_manager: &BlockchainManager,
+    error_handler: &EnhancedErrorHandler,
+    config_manager: &DynamicConfigManager,
+) -> Result<(), HttpResponse> {
     // Use blockchain manager to check network status
-    let network_status = blockchain_manager.get_ref().get_network_status().await;
+    let network_status = blockchain_manager.get_network_status().await;
     let is_healthy = match network_status {
         Ok(status) => status.get("overall_status").map(|s| s == "healthy").unwrap_or(false),
         Err(_) => false,
     };
     
     if !is_healthy {
-        return ErrorResponseBuilder::service_unavailable("Blockchain network is currently unavailable. Please check your internet connection and try again.");
+        return Err(ErrorResponseBuilder::service_unavailable("Blockchain network is currently unavailable. Please check your internet connection and try again."));
     }
     
     // Create transaction validator
-    let config = config_manager.get_ref().get_config().await;
+    let config = config_manager.get_config().await;
     let validator = crate::validators::transaction_validator::TransactionValidator::new(std::sync::Arc::new(config));
     
     // Comprehensive transaction validation using TransactionValidator
     match validator.validate_transaction(&req.signed_tx).await {
         Ok(validation_result) => {
             if !validation_result.valid {
-                            // Use error_utils for proper error handling and recording
-            let error_context = {
-                let mut context = std::collections::HashMap::new();
-                context.insert("validation_errors".to_string(), validation_result.errors.join(", "));
-                context.insert("transaction_hash".to_string(), req.signed_tx.clone());
-                context.insert("chain_id".to_string(), req.chain_id.to_string());
-                context
-            };
-            
-            // Record validation errors using enhanced error handling
-            let _ = error_handler.record_error(crate::utils::error_handler::ErrorRecord {
-                    id: uuid::Uuid::new_v4().to_string(),
-                    timestamp: chrono::Utc::now(),
-                    path: crate::utils::error_handler::CriticalPath::TransactionProcessing,
-                    error_type: crate::utils::error_handler::ErrorType::Unknown,
-                    error_message: format!("Transaction validation failed: {}", validation_result.errors.join(", ")),
-                    context: error_context,
-                    severity: crate::utils::error_handler::ErrorSeverity::Medium,
-                    retry_count: 0,
-                    max_retries: 0,
-                    resolved: false,
-                    resolution_time: None,
-                    stack_trace: None,
-                    user_id: None,
-                    device_id: None,
-                    transaction_id: Some(req.signed_tx.clone()),
-                    chain_id: Some(req.chain_id),
-                    ip_address: None,
-                    component: "transaction_validator".to_string(),
-                }).await;
-                
-                return ErrorResponseBuilder::bad_request(&format!("Transaction validation failed: {}", validation_result.errors.join(", ")));
+                return Err(validation_failure(req, &validation_result.errors, error_handler).await);
             }
             
             // Log warnings if any
@@ -193,15 +203,87 @@ async fn handle_transaction_submission(
                 component: "transaction_validator".to_string(),
             }).await;
             
-            return ErrorResponseBuilder::internal_server_error(&format!("Transaction validation error: {}", e));
+            return Err(ErrorResponseBuilder::internal_server_error(&format!("Transaction validation error: {}", e)));
         }
     }
-    
+
+    Ok(())
+}
+
+/// 409 for a raw transaction the relay has already accepted, pointing at
+/// the original submission
+fn replay_rejection(e: &anyhow::Error) -> HttpResponse {
+    match e.downcast_ref::<DuplicateTransaction>() {
+        Some(duplicate) => HttpResponse::Conflict().json(serde_json::json!({
+            "error": "duplicate_transaction",
+            "message": duplicate.to_string(),
+            "tx_hash": duplicate.tx_hash,
+            "transaction_id": duplicate.original.transaction_id,
+            "first_seen": duplicate.original.first_seen.to_rfc3339(),
+        })),
+        None => ErrorResponseBuilder::internal_server_error(&format!("Replay check failed: {}", e)),
+    }
+}
+
+// Add this helper function before process_transaction
+#[allow(clippy::too_many_arguments)]
+async fn handle_transaction_submission(
+    req: web::Json<SendTxRequest>,
+    device_id: Option<String>,
+    storage: Data<Arc<Storage>>,
+    blockchain_manager: Data<Arc<BlockchainManager>>,
+    error_handler: Data<Arc<EnhancedErrorHandler>>,
+    config_manager: Data<Arc<DynamicConfigManager>>,
+    processor: Data<Arc<TransactionProcessor>>,
+    payload_tiers: Data<Arc<PayloadTierConfig>>,
+    monitoring_manager: Data<Arc<MonitoringManager>>,
+    replay_guard: Data<Arc<TxReplayGuard>>,
+    source: &str,
+) -> impl Responder {
+    // Basic raw tx hex sanity check (do not treat as a tx hash)
+    let signed_tx_str = req.signed_tx.as_str();
+    if !(signed_tx_str.starts_with("0x") 
+        && signed_tx_str.len() > 2 
+        && signed_tx_str.len() % 2 == 0 
+        && hex::decode(signed_tx_str.trim_start_matches("0x")).is_ok())
+    {
+        return ErrorResponseBuilder::bad_request("Invalid raw transaction: must be 0x-prefixed, even-length, valid hex");
+    }
+    if let Err(e) = validate_tags(&req.metadata) {
+        return ErrorResponseBuilder::bad_request(&e.to_string());
+    }
+
+    let started = std::time::Instant::now();
+    let tier = match payload_tiers.fast_path(&req.signed_tx) {
+        Some(decoded) => {
+            let config = config_manager.get_ref().get_config().await;
+            let validator = crate::validators::transaction_validator::TransactionValidator::new(std::sync::Arc::new(config));
+            let validation_result = validator.validate_decoded(&decoded, req.chain_id).await;
+            if !validation_result.valid {
+                return validation_failure(&req, &validation_result.errors, &error_handler).await;
+            }
+            PayloadTier::Fast
+        }
+        None => {
+            if let Err(response) = validate_full(&req, &blockchain_manager, &error_handler, &config_manager).await {
+                return response;
+            }
+            PayloadTier::Full
+        }
+    };
+    monitoring_manager.record_submission_tier(tier.label(), started.elapsed().as_secs_f64() * 1000.0).await;
+
     // Create transaction record
-    let transaction = Transaction::new(
+    let mut transaction = Transaction::new(
         req.signed_tx.clone(),
         req.chain_id,
-    );
+    )
+    .with_tags(req.metadata.clone());
+    transaction.device_id = device_id;
+
+    if let Err(e) = replay_guard.admit(&req.signed_tx, req.chain_id, Some(&transaction.id), source).await {
+        return replay_rejection(&e);
+    }
     
     // Save to storage with proper error handling
     match storage.save_transaction(transaction.clone()) {
@@ -211,6 +293,7 @@ async fn handle_transaction_submission(
             
             // Create QueuedTransaction and enqueue for processing
             let mut metadata = std::collections::HashMap::new();
+            metadata.insert("id".to_string(), serde_json::Value::String(transaction.id.clone()));
             metadata.insert("signedTx".to_string(), serde_json::Value::String(req.signed_tx.clone()));
             
             let queued_tx = QueuedTransaction {
@@ -227,6 +310,8 @@ async fn handle_transaction_submission(
                 retry_delay: std::time::Duration::from_secs(2),
                 chain_id: req.chain_id,
                 metadata,
+                // Admitted to the replay guard above
+                replay_checked: true,
             };
             
             // Enqueue transaction for blockchain processing
@@ -241,7 +326,12 @@ async fn handle_transaction_submission(
                         "timestamp": chrono::Utc::now().to_rfc3339(),
                     }))
                 }
+                Err(e) if e.is::<DuplicateTransaction>() => {
+                    let _ = storage.update_transaction_status_with_error(&transaction.id, "queue_failed", None, Some(e.to_string()));
+                    replay_rejection(&e)
+                }
                 Err(e) => {
+                    replay_guard.release(&req.signed_tx).await;
                     // Record queue failure error
                     let error_record = crate::utils::error_handler::ErrorRecord {
                         id: uuid::Uuid::new_v4().to_string(),
@@ -296,13 +386,19 @@ async fn handle_transaction_submission(
                         let _ = error_handler.record_error(error_record).await;
                     }
                     
-                    // Return service unavailable response
-                    HttpResponse::ServiceUnavailable().json(serde_json::json!({
-                        "error": "queue_full",
-                        "message": "Transaction queue is full, please try again later",
-                        "transaction_id": transaction.id,
-                        "status": "queue_failed"
-                    }))
+                    // Return service unavailable response, with a retry hint when shedding load
+                    let retry_after = e.downcast_ref::<QueueOverloaded>()
+                        .map(|overloaded| overloaded.retry_after.as_secs())
+                        .unwrap_or(5);
+                    HttpResponse::ServiceUnavailable()
+                        .append_header(("Retry-After", retry_after.to_string()))
+                        .json(serde_json::json!({
+                            "error": "queue_full",
+                            "message": "Transaction queue is full, please try again later",
+                            "transaction_id": transaction.id,
+                            "status": "queue_failed",
+                            "retry_after": retry_after,
+                        }))
                 }
             }
         }
@@ -338,6 +434,7 @@ async fn handle_transaction_submission(
                 ip_address: None,
                 component: "storage".to_string(),
             }).await;
+            replay_guard.release(&req.signed_tx).await;
             
             ErrorResponseBuilder::internal_server_error("Failed to save transaction")
         }
@@ -346,15 +443,63 @@ async fn handle_transaction_submission(
 
 // Update process_transaction to call the helper
 #[post("/send_tx")]
+#[allow(clippy::too_many_arguments)]
 async fn process_transaction(
+    http_req: HttpRequest,
     req: web::Json<SendTxRequest>,
     storage: Data<Arc<Storage>>,
     blockchain_manager: Data<Arc<BlockchainManager>>,
     error_handler: Data<Arc<EnhancedErrorHandler>>,
     config_manager: Data<Arc<DynamicConfigManager>>,
     processor: Data<Arc<TransactionProcessor>>,
+    payload_tiers: Data<Arc<PayloadTierConfig>>,
+    monitoring_manager: Data<Arc<MonitoringManager>>,
+    replay_guard: Data<Arc<TxReplayGuard>>,
 ) -> impl Responder {
-    handle_transaction_submission(req, storage, blockchain_manager, error_handler, config_manager, processor).await
+    handle_transaction_submission(req, submitting_device(&http_req), storage, blockchain_manager, error_handler, config_manager, processor, payload_tiers, monitoring_manager, replay_guard, "send_tx").await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SendCompressedTxRequest {
+    /// Base64 `ACPZ` frame holding a `SignedTransaction`
+    pub payload: String,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// `/send_tx` for a signed transaction sent as a compressed payload frame
+#[post("/send_compressed_tx")]
+#[allow(clippy::too_many_arguments)]
+async fn process_compressed_transaction(
+    http_req: HttpRequest,
+    req: web::Json<SendCompressedTxRequest>,
+    storage: Data<Arc<Storage>>,
+    blockchain_manager: Data<Arc<BlockchainManager>>,
+    error_handler: Data<Arc<EnhancedErrorHandler>>,
+    config_manager: Data<Arc<DynamicConfigManager>>,
+    processor: Data<Arc<TransactionProcessor>>,
+    payload_tiers: Data<Arc<PayloadTierConfig>>,
+    monitoring_manager: Data<Arc<MonitoringManager>>,
+    replay_guard: Data<Arc<TxReplayGuard>>,
+) -> impl Responder {
+    use base64::Engine;
+    let decoded = base64::engine::general_purpose::STANDARD.decode(req.payload.trim())
+        .map_err(anyhow::Error::from)
+        .and_then(|frame| crate::utils::payload_codec::decode(&frame));
+    let payload = match decoded {
+        Ok(payload) => payload,
+        Err(e) => return ErrorResponseBuilder::bad_request(&format!("Invalid compressed payload: {}", e)),
+    };
+    let (Some(signed_tx), Some(chain_id)) = (payload["signed_tx"].as_str(), payload["chain_id"].as_u64()) else {
+        return ErrorResponseBuilder::bad_request("Compressed payload is not a signed transaction");
+    };
+    let send_tx = SendTxRequest {
+        signed_tx: signed_tx.to_string(),
+        rpc_url: String::new(),
+        chain_id,
+        metadata: req.into_inner().metadata,
+    };
+    handle_transaction_submission(web::Json(send_tx), submitting_device(&http_req), storage, blockchain_manager, error_handler, config_manager, processor, payload_tiers, monitoring_manager, replay_guard, "send_compressed_tx").await
 }
 
 #[post("/simple_send_tx")]
@@ -362,6 +507,8 @@ async fn simple_send_tx(
     req: web::Json<SendTxRequest>,
     storage: Data<Arc<Storage>>,
     blockchain_manager: Data<Arc<BlockchainManager>>,
+    replay_guard: Data<Arc<TxReplayGuard>>,
+    config_manager: Data<Arc<DynamicConfigManager>>,
 ) -> impl Responder {
     // Minimal raw tx hex validation before immediate broadcast
     let signed_tx_str = req.signed_tx.as_str();
@@ -377,12 +524,25 @@ async fn simple_send_tx(
             "timestamp": chrono::Utc::now().to_rfc3339(),
         }));
     }
+    if let Err(e) = validate_tags(&req.metadata) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "message": e.to_string(),
+            "chain_id": req.chain_id,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }));
+    }
 
     // Create transaction record
     let transaction = Transaction::new(
         req.signed_tx.clone(),
         req.chain_id,
-    );
+    )
+    .with_tags(req.metadata.clone());
+
+    if let Err(e) = replay_guard.admit(&req.signed_tx, req.chain_id, Some(&transaction.id), "simple_send_tx").await {
+        return replay_rejection(&e);
+    }
     
     // Save to storage
     match storage.save_transaction(transaction.clone()) {
@@ -405,10 +565,12 @@ async fn simple_send_tx(
                     metadata.insert("id".to_string(), serde_json::Value::String(transaction.id.clone()));
                     metadata
                 },
+                replay_checked: true,
             }).await {
                 Ok(tx_hash) => {
                     // Update transaction with hash
                     let tx_hash_str = format!("{:?}", tx_hash);
+                    let config = config_manager.get_config().await;
                     let _ = storage.update_transaction_status_with_error(&transaction.id, "completed", Some(tx_hash_str.clone()), None);
                     
                     HttpResponse::Ok().json(serde_json::json!({
@@ -417,15 +579,16 @@ async fn simple_send_tx(
                         "transaction_id": transaction.id,
                         "transaction_hash": tx_hash_str,
                         "chain_id": req.chain_id,
-                        "chain_name": get_chain_name(req.chain_id),
+                        "chain_name": get_chain_name(&config, req.chain_id),
                         "timestamp": chrono::Utc::now().to_rfc3339(),
                         "status": "completed",
-                        "block_explorer_url": get_block_explorer_url(req.chain_id, &tx_hash_str),
+                        "block_explorer_url": get_block_explorer_url(&config, req.chain_id, &tx_hash_str),
                     }))
                 }
                 Err(e) => {
                     // Update transaction as failed
                     let _ = storage.update_transaction_status_with_error(&transaction.id, "failed", None, Some(format!("Blockchain error: {}", e)));
+                    replay_guard.release(&req.signed_tx).await;
                     
                     HttpResponse::InternalServerError().json(serde_json::json!({
                         "success": false,
@@ -440,6 +603,7 @@ async fn simple_send_tx(
             }
         }
         Err(e) => {
+            replay_guard.release(&req.signed_tx).await;
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "success": false,
                 "message": "Failed to save transaction",
@@ -451,68 +615,82 @@ async fn simple_send_tx(
     }
 }
 
-#[post("/api/v1/submit-transaction")]
+#[post("/v1/submit-transaction")]
+#[allow(clippy::too_many_arguments)]
 async fn legacy_submit_transaction(
+    http_req: HttpRequest,
     req: web::Json<SendTxRequest>,
     storage: Data<Arc<Storage>>,
     blockchain_manager: Data<Arc<BlockchainManager>>,
     error_handler: Data<Arc<EnhancedErrorHandler>>,
     config_manager: Data<Arc<DynamicConfigManager>>,
     processor: Data<Arc<TransactionProcessor>>,
+    payload_tiers: Data<Arc<PayloadTierConfig>>,
+    monitoring_manager: Data<Arc<MonitoringManager>>,
+    replay_guard: Data<Arc<TxReplayGuard>>,
 ) -> impl Responder {
-    handle_transaction_submission(req, storage, blockchain_manager, error_handler, config_manager, processor).await
+    handle_transaction_submission(req, submitting_device(&http_req), storage, blockchain_manager, error_handler, config_manager, processor, payload_tiers, monitoring_manager, replay_guard, "send_tx").await
 }
 
 #[get("/contract/payments")]
-async fn get_contract_payments(
-    blockchain_manager: Data<Arc<BlockchainManager>>,
+pub async fn get_contract_payments(
+    storage: Data<Arc<Storage>>,
     query: Query<ContractPaymentsQuery>,
 ) -> impl Responder {
-    let chain_id = query.chain_id.unwrap_or(1);
-    let from_address = query.from_address.as_ref()
-        .and_then(|addr| addr.parse::<Address>().ok());
-    let to_address = query.to_address.as_ref()
-        .and_then(|addr| addr.parse::<Address>().ok());
-    
-    match blockchain_manager.get_contract_events(
+    let Some(chain_id) = query.chain_id else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "chain_id is required"
+        }));
+    };
+    let parse = |field: &str, value: Option<&String>| -> Result<Option<Address>, HttpResponse> {
+        value.map(|addr| addr.parse::<Address>().map_err(|_| HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Invalid {} address: {}", field, addr)
+        })))).transpose()
+    };
+    let payer = match parse("payer", query.payer.as_ref().or(query.from_address.as_ref())) {
+        Ok(payer) => payer,
+        Err(response) => return response,
+    };
+    let merchant = match parse("merchant", query.merchant.as_ref().or(query.to_address.as_ref())) {
+        Ok(merchant) => merchant,
+        Err(response) => return response,
+    };
+    if storage.payments_indexed_through(chain_id).is_none() {
+        return HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "error": format!("Contract payments on chain {} are not indexed", chain_id)
+        }));
+    }
+
+    let page = storage.query_indexed_payments(&PaymentQuery {
         chain_id,
-        query.from_block,
-        query.to_block,
-        from_address,
-        to_address,
-    ).await {
-        Ok(events) => {
-            // Apply pagination
-            let offset = query.offset.unwrap_or(0) as usize;
-            let limit = query.limit.unwrap_or(100) as usize;
-            let paginated_events: Vec<_> = events.into_iter()
-                .skip(offset)
-                .take(limit)
-                .collect();
-            
-            let payments: Vec<PaymentResponse> = paginated_events.into_iter().map(|event| PaymentResponse {
-                from: format!("{:?}", event.from),
-                to: format!("{:?}", event.to),
-                amount: event.amount.to_string(),
-                payment_reference: event.payment_reference,
-                tx_hash: format!("{:?}", event.tx_hash),
-                block_number: event.block_number,
-                is_relayed: event.is_relayed,
-                log_index: event.log_index,
-            }).collect();
-            
-            HttpResponse::Ok().json(serde_json::json!({
-                "payments": payments,
-                "count": payments.len()
-            }))
-        },
-        Err(e) => {
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch contract events",
-                "message": e.to_string()
-            }))
+        payer,
+        merchant,
+        reference: query.reference.clone(),
+        from_block: query.from_block,
+        to_block: query.to_block,
+        limit: query.limit.map(|l| l as usize),
+        offset: query.offset.map(|o| o as usize),
+    });
+    let payments: Vec<PaymentResponse> = page.payments.into_iter().map(|payment| {
+        let event = payment.event;
+        PaymentResponse {
+            from: format!("{:?}", event.from),
+            to: format!("{:?}", event.to),
+            amount: event.amount.to_string(),
+            payment_reference: event.payment_reference,
+            tx_hash: format!("{:?}", event.tx_hash),
+            block_number: event.block_number,
+            is_relayed: event.is_relayed,
+            log_index: event.log_index,
         }
-    }
+    }).collect();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "payments": payments,
+        "count": payments.len(),
+        "total": page.total,
+        "indexed_through": page.indexed_through,
+    }))
 }
 
 #[derive(Deserialize)]
@@ -529,19 +707,18 @@ struct ValidationRequest {
 }
 
 #[post("/auth/token")]
-async fn generate_token(
+pub async fn generate_token(
     req: web::Json<TokenRequest>,
+    api_keys: Data<Arc<ApiKeyStore>>,
 ) -> impl Responder {
-    let api_key = std::env::var("API_KEY").unwrap_or_else(|_| "dev_api_key".to_string());
-    
-    if req.api_key != api_key {
+    let Some(key) = api_keys.verify(&req.api_key) else {
         return HttpResponse::Unauthorized().json(serde_json::json!({
             "error": "Invalid API key"
         }));
-    }
+    };
     
-    // Generate JWT token
-    let token = auth::generate_jwt_token("api-client", "relay");
+    // Generate JWT token carrying the key's scopes and id
+    let token = auth::generate_api_key_token(&key.owner, &key.id, &key.scopes, chrono::Duration::hours(24));
     
     HttpResponse::Ok().json(serde_json::json!({
         "token": token
@@ -616,6 +793,10 @@ struct BlockDeviceRequest {
     reason: Option<String>,
 }
 
+/// Newest transactions first. Pass `device_id` to list one device's
+/// transactions, `tag.<name>=<value>` (repeatable) to keep only those
+/// carrying the tags, and the `X-Next-Cursor` response header back as
+/// `cursor` for the next page.
 #[get("/transactions")]
 async fn get_transactions(
     storage: Data<Arc<Storage>>,
@@ -624,180 +805,37 @@ async fn get_transactions(
     let limit = query.get("limit")
         .and_then(|s| s.parse::<usize>().ok())
         .unwrap_or(100);
+    let cursor = query.get("cursor").map(String::as_str);
+    let tags: BTreeMap<String, String> = query.iter()
+        .filter_map(|(key, value)| key.strip_prefix("tag.").map(|name| (name.to_string(), value.clone())))
+        .collect();
     
-    let transactions = storage.get_transactions(limit);
-    HttpResponse::Ok().json(transactions)
+    let device_id = query.get("device_id");
+    let page = if !tags.is_empty() {
+        storage.search_transactions(&TransactionSearch { tags, device_id: device_id.cloned() }, limit, cursor)
+    } else {
+        match device_id {
+            Some(device_id) => storage.get_transactions_by_device(device_id, limit, cursor),
+            None => storage.get_transactions_page(limit, cursor),
+        }
+    };
+    match page {
+        Ok(page) => {
+            let mut response = HttpResponse::Ok();
+            if let Some(next_cursor) = &page.next_cursor {
+                response.insert_header(("X-Next-Cursor", next_cursor.as_str()));
+            }
+            response.json(page.transactions)
+        }
+        Err(e) => ErrorResponseBuilder::bad_request(&e.to_string()),
+    }
 }
 
 #[get("/metrics")]
-async fn get_metrics(
-    _storage: Data<Arc<Storage>>,
-    monitoring_manager: Data<Arc<MonitoringManager>>,
-) -> impl Responder {
-    let metrics = monitoring_manager.get_metrics().await;
-    let system_metrics = monitoring_manager.get_system_metrics().await;
-    
-    let prometheus_metrics = format!(
-        "# HELP airchainpay_transactions_received_total Total number of transactions received
-# TYPE airchainpay_transactions_received_total counter
-airchainpay_transactions_received_total {}
-
-# HELP airchainpay_transactions_processed_total Total number of transactions processed
-# TYPE airchainpay_transactions_processed_total counter
-airchainpay_transactions_processed_total {}
-
-# HELP airchainpay_transactions_failed_total Total number of transactions failed
-# TYPE airchainpay_transactions_failed_total counter
-airchainpay_transactions_failed_total {}
-
-# HELP airchainpay_transactions_broadcasted_total Total number of transactions broadcasted
-# TYPE airchainpay_transactions_broadcasted_total counter
-airchainpay_transactions_broadcasted_total {}
-
-# HELP airchainpay_rpc_errors_total Total number of RPC errors
-# TYPE airchainpay_rpc_errors_total counter
-airchainpay_rpc_errors_total {}
-
-# HELP airchainpay_auth_failures_total Total number of authentication failures
-# TYPE airchainpay_auth_failures_total counter
-airchainpay_auth_failures_total {}
-
-# HELP airchainpay_rate_limit_hits_total Total number of rate limit hits
-# TYPE airchainpay_rate_limit_hits_total counter
-airchainpay_rate_limit_hits_total {}
-
-# HELP airchainpay_blocked_devices_total Total number of blocked devices
-# TYPE airchainpay_blocked_devices_total counter
-airchainpay_blocked_devices_total {}
-
-# HELP airchainpay_requests_total Total number of requests
-# TYPE airchainpay_requests_total counter
-airchainpay_requests_total {}
-
-# HELP airchainpay_requests_successful_total Total number of successful requests
-# TYPE airchainpay_requests_successful_total counter
-airchainpay_requests_successful_total {}
-
-# HELP airchainpay_requests_failed_total Total number of failed requests
-# TYPE airchainpay_requests_failed_total counter
-airchainpay_requests_failed_total {}
-
-# HELP airchainpay_response_time_avg_ms Average response time in milliseconds
-# TYPE airchainpay_response_time_avg_ms gauge
-airchainpay_response_time_avg_ms {}
-
-# HELP airchainpay_active_connections Current number of active connections
-# TYPE airchainpay_active_connections gauge
-airchainpay_active_connections {}
-
-# HELP airchainpay_database_operations_total Total number of database operations
-# TYPE airchainpay_database_operations_total counter
-airchainpay_database_operations_total {}
-
-# HELP airchainpay_database_errors_total Total number of database errors
-# TYPE airchainpay_database_errors_total counter
-airchainpay_database_errors_total {}
-
-# HELP airchainpay_compression_operations_total Total number of compression operations
-# TYPE airchainpay_compression_operations_total counter
-airchainpay_compression_operations_total {}
-
-# HELP airchainpay_security_events_total Total number of security events
-# TYPE airchainpay_security_events_total counter
-airchainpay_security_events_total {}
-
-# HELP airchainpay_validation_failures_total Total number of validation failures
-# TYPE airchainpay_validation_failures_total counter
-airchainpay_validation_failures_total {}
-
-# HELP airchainpay_cache_hits_total Total number of cache hits
-# TYPE airchainpay_cache_hits_total counter
-airchainpay_cache_hits_total {}
-
-# HELP airchainpay_cache_misses_total Total number of cache misses
-# TYPE airchainpay_cache_misses_total counter
-airchainpay_cache_misses_total {}
-
-# HELP airchainpay_network_errors_total Total number of network errors
-# TYPE airchainpay_network_errors_total counter
-airchainpay_network_errors_total {}
-
-# HELP airchainpay_blockchain_confirmations_total Total number of blockchain confirmations
-# TYPE airchainpay_blockchain_confirmations_total counter
-airchainpay_blockchain_confirmations_total {}
-
-# HELP airchainpay_blockchain_timeouts_total Total number of blockchain timeouts
-# TYPE airchainpay_blockchain_timeouts_total counter
-airchainpay_blockchain_timeouts_total {}
-
-# HELP airchainpay_gas_price_updates_total Total number of gas price updates
-# TYPE airchainpay_gas_price_updates_total counter
-airchainpay_gas_price_updates_total {}
-
-# HELP airchainpay_contract_events_total Total number of contract events
-# TYPE airchainpay_contract_events_total counter
-airchainpay_contract_events_total {}
-
-# HELP airchainpay_uptime_seconds Server uptime in seconds
-# TYPE airchainpay_uptime_seconds gauge
-airchainpay_uptime_seconds {}
-
-# HELP airchainpay_memory_usage_bytes Memory usage in bytes
-# TYPE airchainpay_memory_usage_bytes gauge
-airchainpay_memory_usage_bytes {}
-
-# HELP airchainpay_cpu_usage_percent CPU usage percentage
-# TYPE airchainpay_cpu_usage_percent gauge
-airchainpay_cpu_usage_percent {}
-
-# HELP airchainpay_system_memory_usage_bytes System memory usage in bytes
-# TYPE airchainpay_system_memory_usage_bytes gauge
-airchainpay_system_memory_usage_bytes {}
-
-# HELP airchainpay_system_cpu_usage_percent System CPU usage percentage
-# TYPE airchainpay_system_cpu_usage_percent gauge
-airchainpay_system_cpu_usage_percent {}
-
-# HELP airchainpay_system_thread_count Number of system threads
-# TYPE airchainpay_system_thread_count gauge
-airchainpay_system_thread_count {}
-",
-        metrics.transactions_received,
-        metrics.transactions_processed,
-        metrics.transactions_failed,
-        metrics.transactions_broadcasted,
-        metrics.rpc_errors,
-        metrics.auth_failures,
-        metrics.rate_limit_hits,
-        metrics.blocked_devices,
-        metrics.requests_total,
-        metrics.requests_successful,
-        metrics.requests_failed,
-        metrics.response_time_avg_ms,
-        metrics.active_connections,
-        metrics.database_operations,
-        metrics.database_errors,
-        metrics.compression_operations,
-        metrics.security_events,
-        metrics.validation_failures,
-        metrics.cache_hits,
-        metrics.cache_misses,
-        metrics.network_errors,
-        metrics.blockchain_confirmations,
-        metrics.blockchain_timeouts,
-        metrics.gas_price_updates,
-        metrics.contract_events,
-        metrics.uptime_seconds,
-        metrics.memory_usage_bytes,
-        metrics.cpu_usage_percent,
-        system_metrics.memory_usage_bytes,
-        system_metrics.cpu_usage_percent,
-        system_metrics.thread_count,
-    );
-
+async fn get_metrics(monitoring_manager: Data<Arc<MonitoringManager>>) -> impl Responder {
     HttpResponse::Ok()
-        .content_type("text/plain")
-        .body(prometheus_metrics)
+        .content_type("text/plain; version=0.0.4")
+        .body(monitoring_manager.render_prometheus().await)
 }
 
 #[get("/devices")]
@@ -1718,6 +1756,7 @@ async fn get_configuration(
                 "version": config.version,
                 "port": config.port,
                 "log_level": config.log_level,
+                "log_modules": config.log_modules,
                 "debug": config.debug,
                 "enable_swagger": config.enable_swagger,
                 "enable_cors_debug": config.enable_cors_debug,
@@ -1728,6 +1767,8 @@ async fn get_configuration(
                     "enable_rate_limiting": config.security.enable_rate_limiting,
                     "enable_cors": config.security.enable_cors,
                     "cors_origins": config.security.cors_origins,
+                    "cors_methods": config.security.cors_methods,
+                    "cors_headers": config.security.cors_headers,
                     "max_connections": config.security.max_connections,
                     "session_timeout": config.security.session_timeout,
                 },
@@ -1790,23 +1831,24 @@ pub struct ImportConfigRequest {
 #[post("/config/import")]
 async fn import_configuration(
     req: Json<ImportConfigRequest>,
-    config_manager: Data<Arc<DynamicConfigManager>>,
+    config_rollout: Data<Arc<ConfigRollout>>,
 ) -> impl Responder {
-    let config_json = match serde_json::to_string(&req.config) {
-        Ok(json) => json,
+    let new_config: Config = match serde_json::from_value(req.config.clone()) {
+        Ok(config) => config,
         Err(e) => {
             return HttpResponse::BadRequest().json(serde_json::json!({
                 "success": false,
-                "error": format!("Failed to serialize config: {}", e),
+                "error": format!("Failed to deserialize config: {}", e),
                 "timestamp": chrono::Utc::now().to_rfc3339(),
             }));
         }
     };
     
-    match config_manager.import_config(&config_json).await {
-        Ok(_) => HttpResponse::Ok().json(serde_json::json!({
+    match config_rollout.stage(new_config, "import configuration", "admin").await {
+        Ok(rollout) => HttpResponse::Ok().json(serde_json::json!({
             "success": true,
-            "message": "Configuration imported successfully",
+            "message": "Configuration imported and staged",
+            "rollout": rollout,
             "timestamp": chrono::Utc::now().to_rfc3339(),
         })),
         Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
@@ -1869,6 +1911,7 @@ pub struct UpdateConfigRequest {
 async fn update_configuration_field(
     req: Json<UpdateConfigRequest>,
     config_manager: Data<Arc<DynamicConfigManager>>,
+    config_rollout: Data<Arc<ConfigRollout>>,
 ) -> impl Responder {
     let current_config = config_manager.get_config().await;
     let mut new_config = current_config;
@@ -1880,6 +1923,29 @@ async fn update_configuration_field(
                 new_config.log_level = level.to_string();
             }
         }
+        "log_modules" => {
+            match serde_json::from_value::<HashMap<String, String>>(req.value.clone()) {
+                Ok(modules) => new_config.log_modules = modules,
+                Err(e) => {
+                    return HttpResponse::BadRequest().json(serde_json::json!({
+                        "success": false,
+                        "error": format!("log_modules must map module paths to levels: {}", e),
+                        "timestamp": chrono::Utc::now().to_rfc3339(),
+                    }));
+                }
+            }
+        }
+        field if field.starts_with("log_modules.") => {
+            let module = &field["log_modules.".len()..];
+            match req.value.as_str() {
+                Some(level) => {
+                    new_config.log_modules.insert(module.to_string(), level.to_string());
+                }
+                None => {
+                    new_config.log_modules.remove(module);
+                }
+            }
+        }
         "port" => {
             if let Some(port) = req.value.as_u64() {
                 new_config.port = port as u16;
@@ -1919,10 +1985,11 @@ async fn update_configuration_field(
         }
     }
     
-    match config_manager.update_config(new_config).await {
-        Ok(_) => HttpResponse::Ok().json(serde_json::json!({
+    match config_rollout.stage(new_config, &format!("update {}", req.field), "admin").await {
+        Ok(rollout) => HttpResponse::Ok().json(serde_json::json!({
             "success": true,
-            "message": format!("Configuration field '{}' updated successfully", req.field),
+            "message": format!("Configuration field '{}' updated and staged", req.field),
+            "rollout": rollout,
             "timestamp": chrono::Utc::now().to_rfc3339(),
         })),
         Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
@@ -1955,8 +2022,11 @@ async fn save_configuration_to_file(
     }
 }
 
+/// Full report for admins; anonymous callers only get `HEALTH_PUBLIC_FIELDS`
 #[get("/health/detailed")]
 async fn detailed_health(
+    req: HttpRequest,
+    visibility: Data<Arc<HealthVisibilityConfig>>,
     monitoring_manager: Data<Arc<MonitoringManager>>,
     storage: Data<Arc<Storage>>,
     blockchain_manager: Data<Arc<BlockchainManager>>,
@@ -1994,7 +2064,7 @@ async fn detailed_health(
         "healthy"
     };
     
-    HttpResponse::Ok().json(serde_json::json!({
+    let response = serde_json::json!({
         "status": overall_status,
         "timestamp": chrono::Utc::now().to_rfc3339(),
         "response_time_ms": response_time,
@@ -2069,11 +2139,19 @@ async fn detailed_health(
             "blockchain": if blockchain_status.get("is_healthy").and_then(|v| v.parse::<bool>().ok()).unwrap_or(false) { 100 } else { 25 },
             "configuration": if config_status.is_valid { 100 } else { 25 },
         },
-    }))
+    });
+
+    if authorize_admin(&req).is_ok() {
+        HttpResponse::Ok().json(response)
+    } else {
+        HttpResponse::Ok().json(visibility.redact(response))
+    }
 }
 
 #[get("/health/component/{component}")]
 async fn component_health(
+    req: HttpRequest,
+    visibility: Data<Arc<HealthVisibilityConfig>>,
     path: web::Path<String>,
     monitoring_manager: Data<Arc<MonitoringManager>>,
     storage: Data<Arc<Storage>>,
@@ -2081,6 +2159,11 @@ async fn component_health(
     config_manager: Data<Arc<DynamicConfigManager>>,
 ) -> impl Responder {
     let component = path.into_inner();
+    if !visibility.is_public(&format!("components.{}", component)) {
+        if let Err(response) = authorize_admin(&req) {
+            return response;
+        }
+    }
     
     match component.as_str() {
         "system" => {
@@ -2149,18 +2232,65 @@ async fn component_health(
                 }
             }))
         },
+        "ble" => {
+            let ble = monitoring_manager.get_ble_metrics().await;
+            let session_json = |session: &BleSessionMetrics| serde_json::json!({
+                "peer": session.peer,
+                "device_id": session.device_id,
+                "session_id": session.session_id,
+                "mtu": session.mtu,
+                "frames_received": session.frames_received,
+                "retransmitted_frames": session.retransmitted_frames,
+                "dropped_messages": session.dropped_messages,
+                "bytes_received": session.bytes_received,
+                "bytes_sent": session.bytes_sent,
+                "throughput_bytes_per_sec": session.throughput_bytes_per_sec(),
+                "handshake_ms": session.handshake_ms,
+                "connected_at": session.connected_at.to_rfc3339(),
+                "last_seen": session.last_seen.to_rfc3339(),
+                "ended_at": session.ended_at.map(|t| t.to_rfc3339()),
+            });
+            let mut active: Vec<&BleSessionMetrics> = ble.active.values().collect();
+            active.sort_by_key(|session| session.connected_at);
+            HttpResponse::Ok().json(serde_json::json!({
+                "component": "ble",
+                "status": if ble.retransmit_ratio() > BLE_DEGRADED_RETRANSMIT_RATIO { "degraded" } else { "healthy" },
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+                "metrics": {
+                    "active_sessions": ble.active.len(),
+                    "sessions_total": ble.sessions_total,
+                    "handshakes_completed": ble.handshakes_completed,
+                    "handshake_failures": ble.handshake_failures,
+                    "average_handshake_ms": ble.average_handshake_ms(),
+                    "frames_received": ble.frames_received,
+                    "retransmitted_frames": ble.retransmitted_frames,
+                    "retransmit_ratio": ble.retransmit_ratio(),
+                    "dropped_messages": ble.dropped_messages,
+                },
+                "sessions": active.into_iter().map(session_json).collect::<Vec<_>>(),
+                "recent_sessions": ble.recent.iter().rev().map(session_json).collect::<Vec<_>>(),
+            }))
+        },
         _ => HttpResponse::NotFound().json(serde_json::json!({
             "error": format!("Unknown component: {}", component),
-            "available_components": ["system", "database", "blockchain", "configuration"]
+            "available_components": ["system", "database", "blockchain", "configuration", "ble"]
         }))
     }
 }
 
 #[get("/health/alerts")]
 async fn health_alerts(
+    req: HttpRequest,
+    visibility: Data<Arc<HealthVisibilityConfig>>,
     monitoring_manager: Data<Arc<MonitoringManager>>,
     query: web::Query<std::collections::HashMap<String, String>>,
 ) -> impl Responder {
+    if !visibility.is_public("alerts") {
+        if let Err(response) = authorize_admin(&req) {
+            return response;
+        }
+    }
+
     let limit = query.get("limit")
         .and_then(|s| s.parse::<usize>().ok())
         .unwrap_or(50);
@@ -2191,9 +2321,13 @@ async fn health_alerts(
 
 #[post("/health/alerts/{alert_id}/resolve")]
 async fn resolve_alert(
+    req: HttpRequest,
     path: web::Path<String>,
     monitoring_manager: Data<Arc<MonitoringManager>>,
 ) -> impl Responder {
+    if let Err(response) = authorize_admin(&req) {
+        return response;
+    }
     let alert_id = path.into_inner();
     
     match monitoring_manager.resolve_alert(&alert_id).await {
@@ -2210,8 +2344,15 @@ async fn resolve_alert(
 
 #[get("/health/metrics")]
 async fn health_metrics(
+    req: HttpRequest,
+    visibility: Data<Arc<HealthVisibilityConfig>>,
     monitoring_manager: Data<Arc<MonitoringManager>>,
 ) -> impl Responder {
+    if !visibility.is_public("metrics") {
+        if let Err(response) = authorize_admin(&req) {
+            return response;
+        }
+    }
     let metrics = monitoring_manager.get_metrics().await;
     let system_metrics = monitoring_manager.get_system_metrics().await;
     
@@ -2250,6 +2391,8 @@ async fn health_metrics(
                 "contract_events": metrics.contract_events,
                 "blockchain_confirmations": metrics.blockchain_confirmations,
                 "blockchain_timeouts": metrics.blockchain_timeouts,
+                "blockchain_rebroadcasts": metrics.blockchain_rebroadcasts,
+                "blockchain_fee_bumps": metrics.blockchain_fee_bumps,
             },
         }
     }))
@@ -2262,6 +2405,19 @@ pub async fn submit_transaction(
 ) -> impl Responder {
     match processor.enqueue_transaction(tx.into_inner()).await {
         Ok(_) => HttpResponse::Ok().json(json!({ "status": "queued" })),
+        Err(e) if e.is::<QueueOverloaded>() => {
+            let retry_after = e.downcast_ref::<QueueOverloaded>()
+                .map(|overloaded| overloaded.retry_after.as_secs())
+                .unwrap_or_default();
+            HttpResponse::ServiceUnavailable()
+                .append_header(("Retry-After", retry_after.to_string()))
+                .json(json!({
+                    "status": "error",
+                    "message": e.to_string(),
+                    "retry_after": retry_after,
+                }))
+        }
+        Err(e) if e.is::<DuplicateTransaction>() => replay_rejection(&e),
         Err(e) => HttpResponse::InternalServerError().json(json!({
             "status": "error",
             "message": format!("Failed to enqueue transaction: {}", e)
@@ -2282,8 +2438,10 @@ async fn test_transaction() -> impl Responder {
 async fn get_transaction_details(
     path: web::Path<String>,
     storage: Data<Arc<Storage>>,
+    config_manager: Data<Arc<DynamicConfigManager>>,
 ) -> impl Responder {
     let transaction_id = path.into_inner();
+    let config = config_manager.get_config().await;
     
     let transactions = storage.get_transactions(1000); // Get all transactions
     if let Some(transaction) = transactions.iter().find(|t| t.id == transaction_id) {
@@ -2296,10 +2454,13 @@ async fn get_transaction_details(
                         "status": transaction.status,
                         "transaction_hash": tx_hash,
                         "chain_id": transaction.chain_id,
-                        "chain_name": get_chain_name(transaction.chain_id),
+                        "chain_name": get_chain_name(&config, transaction.chain_id),
                         "timestamp": transaction.timestamp.to_rfc3339(),
                         "message": "Transaction completed successfully",
-                        "block_explorer_url": get_block_explorer_url(transaction.chain_id, tx_hash),
+                        "block_explorer_url": get_block_explorer_url(&config, transaction.chain_id, tx_hash),
+                        "block_number": transaction.block_number,
+                        "gas_used": transaction.gas_used,
+                        "receipt": transaction.receipt,
                     }))
                 } else {
                     HttpResponse::Ok().json(serde_json::json!({
@@ -2360,8 +2521,10 @@ async fn get_transaction_details(
 async fn get_transaction_status(
     path: web::Path<String>,
     storage: Data<Arc<Storage>>,
+    config_manager: Data<Arc<DynamicConfigManager>>,
 ) -> impl Responder {
     let transaction_id = path.into_inner();
+    let config = config_manager.get_config().await;
     
     let transactions = storage.get_transactions(1000);
     if let Some(transaction) = transactions.iter().find(|t| t.id == transaction_id) {
@@ -2370,7 +2533,7 @@ async fn get_transaction_status(
             "transaction_id": transaction.id,
             "status": transaction.status,
             "chain_id": transaction.chain_id,
-            "chain_name": get_chain_name(transaction.chain_id),
+            "chain_name": get_chain_name(&config, transaction.chain_id),
             "timestamp": transaction.timestamp.to_rfc3339(),
         });
         
@@ -2378,14 +2541,20 @@ async fn get_transaction_status(
         let mut response_obj = response.as_object().unwrap().clone();
         if let Some(tx_hash) = &transaction.tx_hash {
             response_obj.insert("transaction_hash".to_string(), serde_json::Value::String(tx_hash.clone()));
-            response_obj.insert("block_explorer_url".to_string(), serde_json::Value::String(get_block_explorer_url(transaction.chain_id, tx_hash)));
+            response_obj.insert("block_explorer_url".to_string(), serde_json::json!(get_block_explorer_url(&config, transaction.chain_id, tx_hash)));
         } else {
             response_obj.insert("transaction_hash".to_string(), serde_json::Value::Null);
         }
+        if let Some(receipt) = &transaction.receipt {
+            response_obj.insert("receipt".to_string(), serde_json::json!(receipt));
+        }
+        response_obj.insert("block_number".to_string(), serde_json::json!(transaction.block_number));
+        response_obj.insert("gas_used".to_string(), serde_json::json!(transaction.gas_used));
         
         // Add appropriate message based on status
         let message = match transaction.status.as_str() {
             "completed" => "Transaction completed successfully",
+            "broadcast" => "Transaction broadcast, waiting for confirmation",
             "pending" => "Transaction is being processed",
             "failed" => "Transaction failed to process",
             _ => &format!("Transaction status: {}", transaction.status)
@@ -2402,13 +2571,54 @@ async fn get_transaction_status(
     }
 }
 
+/// Relay-signed receipt for a broadcast transaction. Wallets check the
+/// signature over `signed_payload` against the address published at
+/// `/api/federation/identity`.
+#[get("/transaction/{transaction_id}/receipt")]
+async fn get_transaction_receipt(
+    path: web::Path<String>,
+    storage: Data<Arc<Storage>>,
+) -> impl Responder {
+    let transaction_id = path.into_inner();
+    let Some(transaction) = storage.get_transaction(&transaction_id) else {
+        return ErrorResponseBuilder::not_found(&format!("No transaction found with ID: {}", transaction_id));
+    };
+    match transaction.receipt {
+        Some(receipt) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "message": receipt.receipt.message(),
+            "signed_payload": format!("0x{}", hex::encode(receipt.signed_payload())),
+            "receipt": receipt,
+        })),
+        None => ErrorResponseBuilder::not_found(&format!("No receipt yet for transaction {} (status: {})", transaction_id, transaction.status)),
+    }
+}
+
+/// Merkle proof tying a transaction to a root the relay attested on chain
+#[get("/transaction/{transaction_id}/proof")]
+async fn get_transaction_proof(
+    path: web::Path<String>,
+    attestation: Data<Arc<AttestationService>>,
+) -> impl Responder {
+    let transaction_id = path.into_inner();
+    match attestation.proof(&transaction_id).await {
+        Some(proof) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "proof": proof,
+        })),
+        None => ErrorResponseBuilder::not_found(&format!("Transaction {} is not part of an attested batch yet", transaction_id)),
+    }
+}
+
 #[get("/transactions/user/{user_id}")]
 async fn get_user_transactions(
     path: web::Path<String>,
     storage: Data<Arc<Storage>>,
     query: web::Query<std::collections::HashMap<String, String>>,
+    config_manager: Data<Arc<DynamicConfigManager>>,
 ) -> impl Responder {
     let user_id = path.into_inner();
+    let config = config_manager.get_config().await;
     let limit = query.get("limit")
         .and_then(|s| s.parse::<usize>().ok())
         .unwrap_or(50);
@@ -2422,13 +2632,13 @@ async fn get_user_transactions(
                 "transaction_id": t.id,
                 "status": t.status,
                 "chain_id": t.chain_id,
-                "chain_name": get_chain_name(t.chain_id),
+                "chain_name": get_chain_name(&config, t.chain_id),
                 "timestamp": t.timestamp.to_rfc3339(),
             });
             
             if let Some(tx_hash) = &t.tx_hash {
                 tx_obj["transaction_hash"] = serde_json::Value::String(tx_hash.clone());
-                tx_obj["block_explorer_url"] = serde_json::Value::String(get_block_explorer_url(t.chain_id, tx_hash));
+                tx_obj["block_explorer_url"] = serde_json::json!(get_block_explorer_url(&config, t.chain_id, tx_hash));
             } else {
                 tx_obj["transaction_hash"] = serde_json::Value::Null;
             }
@@ -2446,42 +2656,27 @@ async fn get_user_transactions(
     }))
 }
 
+fn chain_json(chain_id: u64, chain: &crate::infrastructure::config::ChainConfig) -> serde_json::Value {
+    serde_json::json!({
+        "chain_id": chain_id,
+        "name": chain.name,
+        "block_explorer": chain.explorer,
+        "rpc_url": chain.rpc_url,
+        "native_currency": chain.currency_symbol,
+        "contract_address": chain.contract_address,
+    })
+}
+
 #[get("/chains/supported")]
-async fn get_supported_chains() -> impl Responder {
-    let chains = vec![
-        serde_json::json!({
-            "chain_id": 1114,
-            "name": "Core Testnet",
-            "block_explorer": "https://scan.test2.btcs.network",
-            "rpc_url": "https://rpc.test2.btcs.network",
-            "native_currency": "TCORE2",
-            "contract_address": "0x8d7eaB03a72974F5D9F5c99B4e4e1B393DBcfCAB"
-        }),
-        serde_json::json!({
-            "chain_id": 84532,
-            "name": "Base Sepolia",
-            "block_explorer": "https://sepolia.basescan.org",
-            "rpc_url": "https://sepolia.base.org",
-            "native_currency": "ETH",
-            "contract_address": "0x7B79117445C57eea1CEAb4733020A55e1D503934"
-        }),
-        serde_json::json!({
-            "chain_id": 17000,
-            "name": "Morph Holesky",
-            "block_explorer": "https://holesky.etherscan.io",
-            "rpc_url": "https://holesky.drpc.org",
-            "native_currency": "ETH",
-            "contract_address": "0x26C59cd738Df90604Ebb13Ed8DB76657cfD51f40"
-        }),
-        serde_json::json!({
-            "chain_id": 4202,
-            "name": "Lisk Sepolia",
-            "block_explorer": "https://sepolia.scroll.io",
-            "rpc_url": "https://rpc.sepolia.lisk.com",
-            "native_currency": "ETH",
-            "contract_address": "0xaBEEEc6e6c1f6bfDE1d05db74B28847Ba5b44EAF"
-        })
-    ];
+async fn get_supported_chains(
+    config_manager: Data<Arc<DynamicConfigManager>>,
+) -> impl Responder {
+    let config = config_manager.get_config().await;
+    let mut chain_ids: Vec<&u64> = config.supported_chains.keys().collect();
+    chain_ids.sort();
+    let chains: Vec<serde_json::Value> = chain_ids.into_iter()
+        .map(|chain_id| chain_json(*chain_id, &config.supported_chains[chain_id]))
+        .collect();
     
     HttpResponse::Ok().json(serde_json::json!({
         "success": true,
@@ -2494,47 +2689,18 @@ async fn get_supported_chains() -> impl Responder {
 #[get("/chains/{chain_id}/info")]
 async fn get_chain_info(
     path: web::Path<u64>,
+    config_manager: Data<Arc<DynamicConfigManager>>,
 ) -> impl Responder {
     let chain_id = path.into_inner();
+    let config = config_manager.get_config().await;
     
-    let chain_info = match chain_id {
-        1114 => serde_json::json!({
-            "chain_id": 1114,
-            "name": "Core Testnet",
-            "block_explorer": "https://scan.test2.btcs.network",
-            "rpc_url": "https://rpc.test2.btcs.network",
-            "native_currency": "TCORE2",
-            "contract_address": "0x8d7eaB03a72974F5D9F5c99B4e4e1B393DBcfCAB",
-            "is_supported": true
-        }),
-        84532 => serde_json::json!({
-            "chain_id": 84532,
-            "name": "Base Sepolia",
-            "block_explorer": "https://sepolia.basescan.org",
-            "rpc_url": "https://sepolia.base.org",
-            "native_currency": "ETH",
-            "contract_address": "0x7B79117445C57eea1CEAb4733020A55e1D503934",
-            "is_supported": true
-        }),
-        17000 => serde_json::json!({
-            "chain_id": 17000,
-            "name": "Morph Holesky",
-            "block_explorer": "https://holesky.etherscan.io",
-            "rpc_url": "https://holesky.drpc.org",
-            "native_currency": "ETH",
-            "contract_address": "0x26C59cd738Df90604Ebb13Ed8DB76657cfD51f40",
-            "is_supported": true
-        }),
-        4202 => serde_json::json!({
-            "chain_id": 4202,
-            "name": "Lisk Sepolia",
-            "block_explorer": "https://sepolia.scroll.io",
-            "rpc_url": "https://rpc.sepolia.lisk.com",
-            "native_currency": "ETH",
-            "contract_address": "0xaBEEEc6e6c1f6bfDE1d05db74B28847Ba5b44EAF",
-            "is_supported": true
-        }),
-        _ => serde_json::json!({
+    let chain_info = match config.supported_chains.get(&chain_id) {
+        Some(chain) => {
+            let mut info = chain_json(chain_id, chain);
+            info["is_supported"] = serde_json::Value::Bool(true);
+            info
+        }
+        None => serde_json::json!({
             "chain_id": chain_id,
             "name": "Unknown Chain",
             "block_explorer": null,
@@ -2552,12 +2718,75 @@ async fn get_chain_info(
     }))
 }
 
+/// Supported chains as a registry document, the format the wallet core
+/// loads; JSON by default, TOML with `?format=toml`
+#[get("/chains/registry")]
+async fn get_chain_registry(
+    config_manager: Data<Arc<DynamicConfigManager>>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let config = config_manager.get_config().await;
+    let registry = ChainRegistry::from_chain_configs(&config.supported_chains);
+    match query.get("format").map(String::as_str) {
+        Some("toml") => match registry.to_toml_string() {
+            Ok(toml) => HttpResponse::Ok().content_type("application/toml").body(toml),
+            Err(e) => ErrorResponseBuilder::internal_server_error(&format!("Failed to render chain registry: {}", e)),
+        },
+        _ => HttpResponse::Ok().json(registry),
+    }
+}
+
+/// Which RPC endpoint each chain is using, the endpoints' health scores and
+/// how often broadcasts have failed over
+#[get("/networks/status")]
+async fn get_networks_status(
+    blockchain_manager: Data<Arc<BlockchainManager>>,
+) -> impl Responder {
+    let chains = blockchain_manager.provider_statuses();
+    HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "total_failovers": chains.iter().map(|chain| chain.failovers).sum::<u64>(),
+        "chains": chains,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+/// Current base fee, priority fee, suggested fee cap and the lowest fee cap
+/// the relay accepts for a chain, in wei per gas
+#[get("/fees/{chain_id}")]
+async fn get_fee_estimate(
+    path: web::Path<u64>,
+    blockchain_manager: Data<Arc<BlockchainManager>>,
+    fee_oracle: Data<Arc<FeeOracle>>,
+) -> impl Responder {
+    let chain_id = path.into_inner();
+    if !blockchain_manager.chain_ids().contains(&chain_id) {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": format!("Unsupported chain {}", chain_id),
+        }));
+    }
+    match fee_oracle.estimate(chain_id).await {
+        Ok(estimate) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "fees": estimate,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        })),
+        Err(e) => HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "success": false,
+            "error": format!("Fee estimate unavailable: {}", e),
+        })),
+    }
+}
+
 #[get("/transaction/hash/{tx_hash}")]
 async fn get_transaction_by_hash(
     path: web::Path<String>,
     storage: Data<Arc<Storage>>,
+    config_manager: Data<Arc<DynamicConfigManager>>,
 ) -> impl Responder {
     let tx_hash = path.into_inner();
+    let config = config_manager.get_config().await;
     
     let transactions = storage.get_transactions(1000);
     if let Some(transaction) = transactions.iter().find(|t| {
@@ -2569,9 +2798,9 @@ async fn get_transaction_by_hash(
             "transaction_hash": tx_hash,
             "status": transaction.status,
             "chain_id": transaction.chain_id,
-            "chain_name": get_chain_name(transaction.chain_id),
+            "chain_name": get_chain_name(&config, transaction.chain_id),
             "timestamp": transaction.timestamp.to_rfc3339(),
-            "block_explorer_url": get_block_explorer_url(transaction.chain_id, &tx_hash),
+            "block_explorer_url": get_block_explorer_url(&config, transaction.chain_id, &tx_hash),
         });
         
         // Add appropriate message based on status
@@ -0,0 +1,121 @@
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use actix_web::web::{Bytes, Data};
+use futures_util::stream;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::infrastructure::storage::file_storage::{StatusTransition, Storage, TERMINAL_STATUSES};
+use crate::middleware::error_handling::ErrorResponseBuilder;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+/// Streams are closed after this long; clients reconnect with Last-Event-ID
+const MAX_STREAM_DURATION: Duration = Duration::from_secs(600);
+/// Reconnect delay suggested to EventSource clients (ms)
+const CLIENT_RETRY_MS: u64 = 3000;
+
+struct EventStream {
+    storage: Arc<Storage>,
+    transaction_id: String,
+    next_event: usize,
+    started_at: Instant,
+    preamble_sent: bool,
+    finished: bool,
+}
+
+pub fn is_terminal_status(status: &str) -> bool {
+    TERMINAL_STATUSES.contains(&status)
+}
+
+fn status_event(id: usize, transaction_id: &str, transition: &StatusTransition) -> Bytes {
+    let data = serde_json::json!({
+        "transaction_id": transaction_id,
+        "status": transition.status,
+        "tx_hash": transition.tx_hash,
+        "error": transition.error_details,
+        "timestamp": transition.at.to_rfc3339(),
+        "terminal": is_terminal_status(&transition.status),
+    });
+    Bytes::from(format!("id: {id}\nevent: status\ndata: {data}\n\n"))
+}
+
+impl EventStream {
+    /// Wait for the next frame: a status event, a heartbeat, or the end of the stream
+    async fn next_frame(&mut self) -> Option<Bytes> {
+        if self.finished {
+            return None;
+        }
+        if !self.preamble_sent {
+            self.preamble_sent = true;
+            return Some(Bytes::from(format!("retry: {CLIENT_RETRY_MS}\n\n")));
+        }
+
+        let idle_since = Instant::now();
+        loop {
+            let transaction = self.storage.get_transaction(&self.transaction_id)?;
+            let transitions = transaction.transitions();
+
+            if let Some(transition) = transitions.get(self.next_event) {
+                let id = self.next_event;
+                self.next_event += 1;
+                if self.next_event >= transitions.len() && is_terminal_status(&transaction.status) {
+                    self.finished = true;
+                }
+                return Some(status_event(id, &self.transaction_id, transition));
+            }
+            if is_terminal_status(&transaction.status) {
+                return None;
+            }
+
+            if self.started_at.elapsed() >= MAX_STREAM_DURATION {
+                self.finished = true;
+                return Some(Bytes::from_static(b"event: timeout\ndata: {}\n\n"));
+            }
+            if idle_since.elapsed() >= HEARTBEAT_INTERVAL {
+                return Some(Bytes::from_static(b": heartbeat\n\n"));
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Server-sent events stream of a transaction's status transitions.
+///
+/// Emits one `status` event per transition until the transaction reaches a
+/// terminal state. Event ids are transition indexes, so a reconnecting
+/// client sending `Last-Event-ID` resumes after the last event it saw.
+#[get("/transactions/{transaction_id}/events")]
+pub async fn transaction_events(
+    req: HttpRequest,
+    path: web::Path<String>,
+    storage: Data<Arc<Storage>>,
+) -> impl Responder {
+    let transaction_id = path.into_inner();
+    if storage.get_transaction(&transaction_id).is_none() {
+        return ErrorResponseBuilder::not_found("Transaction not found");
+    }
+
+    let next_event = req.headers().get("Last-Event-ID")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|id| id.trim().parse::<usize>().ok())
+        .map(|id| id + 1)
+        .unwrap_or(0);
+
+    let state = EventStream {
+        storage: Arc::clone(storage.get_ref()),
+        transaction_id,
+        next_event,
+        started_at: Instant::now(),
+        preamble_sent: false,
+        finished: false,
+    };
+
+    let events = stream::unfold(state, |mut state| async move {
+        state.next_frame().await.map(|frame| (Ok::<_, actix_web::Error>(frame), state))
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(events)
+}
@@ -0,0 +1,71 @@
+use actix_web::{get, HttpResponse, Responder};
+use actix_web::web::Data;
+use std::sync::Arc;
+use crate::app::federation::Federation;
+use crate::domain::identity::noise_key_binding_message;
+use crate::domain::receipt::RECEIPT_TAG;
+use crate::infrastructure::ble::noise::{NOISE_PATTERN, NOISE_SESSION_VERSION};
+use crate::infrastructure::ble::session::{BleSessionManager, SESSION_PROTOCOL_VERSION};
+use crate::infrastructure::config::DynamicConfigManager;
+use crate::middleware::error_handling::ErrorResponseBuilder;
+use crate::utils::payload_codec::PAYLOAD_CODEC_VERSIONS;
+
+/// The relay's public keys, chains and protocol versions, for wallets to pin.
+/// `signature` is the identity key's EIP-191 signature over
+/// `noise_key_binding_message`, tying the Noise static key to the identity.
+#[get("/.well-known/airchainpay-relay")]
+pub async fn relay_identity_document(
+    federation: Data<Arc<Federation>>,
+    sessions: Data<Arc<BleSessionManager>>,
+    config_manager: Data<Arc<DynamicConfigManager>>,
+) -> impl Responder {
+    let identity = federation.identity();
+    let noise_static_key = sessions.noise_public_key().map(hex::encode);
+    let issued_at = chrono::Utc::now().to_rfc3339();
+    let message = noise_key_binding_message(identity.address(), noise_static_key.as_deref(), &issued_at);
+    let signature = match identity.sign(message.as_bytes()) {
+        Ok(signature) => signature,
+        Err(e) => return ErrorResponseBuilder::internal_server_error(&e.to_string()),
+    };
+
+    let config = config_manager.get_config().await;
+    let mut chains: Vec<serde_json::Value> = config.supported_chains.iter()
+        .map(|(chain_id, chain)| serde_json::json!({
+            "chain_id": chain_id,
+            "name": chain.name,
+            "contract_address": chain.contract_address,
+        }))
+        .collect();
+    chains.sort_by_key(|chain| chain["chain_id"].as_u64());
+
+    HttpResponse::Ok()
+        .insert_header(("Cache-Control", "public, max-age=300"))
+        .json(serde_json::json!({
+            "relay": identity.address(),
+            "version": env!("CARGO_PKG_VERSION"),
+            "mode": if federation.is_edge() { "edge" } else { "standard" },
+            "keys": {
+                "identity": {
+                    "address": identity.address(),
+                    "public_key": identity.public_key(),
+                    "curve": "secp256k1",
+                    "signature_scheme": "eip191",
+                    "signs": ["receipts", "federation_manifests", "attestations", "identity_document"],
+                },
+                "noise_static": {
+                    "public_key": noise_static_key,
+                    "curve": "x25519",
+                    "pattern": NOISE_PATTERN,
+                },
+            },
+            "protocols": {
+                "sessions": [SESSION_PROTOCOL_VERSION, NOISE_SESSION_VERSION],
+                "payload_codecs": PAYLOAD_CODEC_VERSIONS,
+                "receipt_encodings": ["text", "cbor-1"],
+                "receipt_tag": RECEIPT_TAG,
+            },
+            "chains": chains,
+            "issued_at": issued_at,
+            "signature": signature,
+        }))
+}
@@ -0,0 +1,172 @@
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use actix_ws::Message;
+use base64::{engine::general_purpose, Engine as _};
+use prost::Message as ProstMessage;
+use std::sync::Arc;
+use crate::app::enrollment::DeviceEnrollment;
+use crate::app::transaction_service::TransactionProcessor;
+use crate::domain::auth;
+use crate::infrastructure::ble::noise::{NoiseHandshake, NOISE_PATTERN, NOISE_SESSION_VERSION};
+use crate::infrastructure::ble::payments::{finish_noise, handle_frame, result_frame, start_noise};
+use crate::infrastructure::ble::session::{BleSessionManager, SessionTransport, SESSION_PROTOCOL_VERSION};
+use crate::infrastructure::storage::file_storage::Storage;
+use crate::utils::payload_codec::{negotiate_version, PAYLOAD_CODEC_VERSIONS};
+use crate::middleware::client_cert;
+use crate::middleware::error_handling::ErrorResponseBuilder;
+
+/// WebSocket bridge for devices without BLE.
+///
+/// Text messages carry JSON control (`hello`, `close`); binary messages carry
+/// protobuf `EncryptedTransactionPayload` frames, answered with encrypted
+/// `TransactionResult` frames.
+///
+/// Instead of `hello`, a device may run a Noise XX handshake with
+/// `noise_init` / `noise_finish`, each carrying a base64 handshake message.
+/// The device's static key is pinned on its first Noise session.
+///
+/// `hello` and `noise_finish` may list the compressed payload codec
+/// versions the device speaks in `payload_codecs`; the session reply names
+/// the relay's versions and the highest one both share.
+#[get("/ws/ble")]
+pub async fn ws_ble_bridge(
+    req: HttpRequest,
+    body: web::Payload,
+    sessions: Data<Arc<BleSessionManager>>,
+    storage: Data<Arc<Storage>>,
+    processor: Data<Arc<TransactionProcessor>>,
+    enrollment: Data<Arc<DeviceEnrollment>>,
+) -> Result<HttpResponse, Error> {
+    // A device JWT, or the device certificate presented over mTLS
+    let device_id = match bearer_subject(&req)
+        .or_else(|| client_cert::device_identity(&req, enrollment.ca()).map(|c| c.device_id))
+    {
+        Some(subject) => subject,
+        None => return Ok(ErrorResponseBuilder::unauthorized("Device token or certificate required")),
+    };
+
+    if let Err(e) = storage.record_device_seen(&device_id) {
+        log::warn!("Failed to record device {}: {}", device_id, e);
+    }
+
+    let (response, mut session, mut msg_stream) = actix_ws::handle(&req, body)?;
+    let sessions = Arc::clone(sessions.get_ref());
+    let storage = Arc::clone(storage.get_ref());
+    let processor = Arc::clone(processor.get_ref());
+
+    actix_web::rt::spawn(async move {
+        let mut session_id: Option<String> = None;
+        let mut handshake: Option<NoiseHandshake> = None;
+
+        while let Some(Ok(msg)) = msg_stream.recv().await {
+            match msg {
+                Message::Text(text) => {
+                    let control: serde_json::Value = serde_json::from_str(&text).unwrap_or_default();
+                    match control.get("type").and_then(|t| t.as_str()) {
+                        Some("hello") => {
+                            if let Some(old) = session_id.take() {
+                                sessions.close_session(&old).await;
+                            }
+                            let (id, key) = sessions.open_session(&device_id, SessionTransport::Websocket).await;
+                            session_id = Some(id.clone());
+                            let reply = serde_json::json!({
+                                "type": "session",
+                                "session_id": id,
+                                "key": general_purpose::STANDARD.encode(key),
+                                "version": SESSION_PROTOCOL_VERSION,
+                                "payload_codecs": PAYLOAD_CODEC_VERSIONS,
+                                "payload_codec": negotiate_version(&control),
+                            });
+                            if session.text(reply.to_string()).await.is_err() {
+                                break;
+                            }
+                        }
+                        Some("noise_init") => {
+                            if let Some(old) = session_id.take() {
+                                sessions.close_session(&old).await;
+                            }
+                            let reply = match start_noise(&control, &sessions) {
+                                Ok((state, response)) => {
+                                    handshake = Some(state);
+                                    serde_json::json!({
+                                        "type": "noise_response",
+                                        "message": general_purpose::STANDARD.encode(response),
+                                        "pattern": NOISE_PATTERN,
+                                    })
+                                }
+                                Err(e) => serde_json::json!({"type": "error", "error": e.to_string()}),
+                            };
+                            if session.text(reply.to_string()).await.is_err() {
+                                break;
+                            }
+                        }
+                        Some("noise_finish") => {
+                            let reply = match finish_noise(handshake.take(), &control, &device_id, SessionTransport::Websocket, &sessions, &storage).await {
+                                Ok(id) => {
+                                    session_id = Some(id.clone());
+                                    serde_json::json!({
+                                        "type": "session",
+                                        "session_id": id,
+                                        "protocol": "noise",
+                                        "version": NOISE_SESSION_VERSION,
+                                        "payload_codecs": PAYLOAD_CODEC_VERSIONS,
+                                        "payload_codec": negotiate_version(&control),
+                                    })
+                                }
+                                Err(e) => {
+                                    log::warn!("Noise handshake with device {} failed: {}", device_id, e);
+                                    serde_json::json!({"type": "error", "error": e.to_string()})
+                                }
+                            };
+                            if session.text(reply.to_string()).await.is_err() {
+                                break;
+                            }
+                        }
+                        Some("close") => break,
+                        _ => {
+                            let reply = serde_json::json!({"type": "error", "error": "Unknown control message"});
+                            if session.text(reply.to_string()).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+                Message::Binary(bytes) => {
+                    let transport = SessionTransport::Websocket;
+                    let result = handle_frame(&bytes, &device_id, &transport, &sessions, &storage, &processor).await;
+                    let reply = match (&session_id, result) {
+                        (Some(id), result) => sessions
+                            .seal_frame(id, &result.encode_to_vec())
+                            .await
+                            .map(|frame| frame.encode_to_vec())
+                            .unwrap_or_else(|_| result_frame(&transport, "failed", "", "Failed to encrypt response").encode_to_vec()),
+                        (None, _) => result_frame(&transport, "failed", "", "Open a session (hello or Noise handshake) before payment frames").encode_to_vec(),
+                    };
+                    if session.binary(reply).await.is_err() {
+                        break;
+                    }
+                }
+                Message::Ping(bytes) => {
+                    if session.pong(&bytes).await.is_err() {
+                        break;
+                    }
+                }
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+
+        if let Some(id) = session_id {
+            sessions.close_session(&id).await;
+        }
+        let _ = session.close(None).await;
+    });
+
+    Ok(response)
+}
+
+fn bearer_subject(req: &HttpRequest) -> Option<String> {
+    let header = req.headers().get("Authorization")?.to_str().ok()?;
+    let token = header.strip_prefix("Bearer ")?;
+    auth::verify_jwt_token(token.trim()).ok().map(|claims| claims.sub)
+}
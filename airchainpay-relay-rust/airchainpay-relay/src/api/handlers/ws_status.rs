@@ -0,0 +1,167 @@
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use actix_ws::Message;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use crate::api::handlers::transaction_events::is_terminal_status;
+use crate::app::transaction_service::TransactionProcessor;
+use crate::infrastructure::storage::file_storage::{StatusEvent, Storage};
+
+/// Transaction ids a single connection may watch at once
+const MAX_SUBSCRIPTIONS: usize = 100;
+
+fn status_message(event: &StatusEvent) -> serde_json::Value {
+    serde_json::json!({
+        "type": "status",
+        "transaction_id": event.transaction_id,
+        "status": event.transition.status,
+        "tx_hash": event.transition.tx_hash,
+        "error": event.transition.error_details,
+        "timestamp": event.transition.at.to_rfc3339(),
+        "terminal": is_terminal_status(&event.transition.status),
+    })
+}
+
+/// Latest known status of a transaction, sent on subscribe so nothing that
+/// happened before the subscription is missed
+fn current_status(storage: &Storage, transaction_id: &str) -> serde_json::Value {
+    match storage.get_transaction(transaction_id) {
+        Some(transaction) => {
+            let transition = transaction.transitions().pop();
+            match transition {
+                Some(transition) => status_message(&StatusEvent {
+                    transaction_id: transaction.id,
+                    transition,
+                }),
+                None => serde_json::json!({"type": "error", "transaction_id": transaction_id, "error": "Transaction has no status"}),
+            }
+        }
+        None => serde_json::json!({"type": "error", "transaction_id": transaction_id, "error": "Transaction not found"}),
+    }
+}
+
+fn requested_ids(control: &serde_json::Value) -> Vec<String> {
+    control.get("transaction_ids")
+        .and_then(|ids| ids.as_array())
+        .map(|ids| ids.iter().filter_map(|id| id.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+/// Push channel for transaction status, replacing polling of
+/// `/api/transaction/{id}/status`.
+///
+/// Clients send `{"type": "subscribe", "transaction_ids": [...]}` and get the
+/// current status of each id, then a `status` message for every transition
+/// the processor records. Subscriptions end on `unsubscribe` or once the
+/// transaction reaches a terminal status.
+#[get("/ws")]
+pub async fn ws_transaction_status(
+    req: HttpRequest,
+    body: web::Payload,
+    storage: Data<Arc<Storage>>,
+    processor: Data<Arc<TransactionProcessor>>,
+) -> Result<HttpResponse, Error> {
+    let (response, mut session, mut msg_stream) = actix_ws::handle(&req, body)?;
+    let storage = Arc::clone(storage.get_ref());
+    // Subscribe before any snapshot is read so no transition falls in between
+    let mut events = processor.subscribe_status();
+
+    actix_web::rt::spawn(async move {
+        let mut subscribed: HashSet<String> = HashSet::new();
+
+        loop {
+            tokio::select! {
+                msg = msg_stream.recv() => {
+                    let Some(Ok(msg)) = msg else { break };
+                    match msg {
+                        Message::Text(text) => {
+                            let control: serde_json::Value = serde_json::from_str(&text).unwrap_or_default();
+                            let mut replies = Vec::new();
+                            match control.get("type").and_then(|t| t.as_str()) {
+                                Some("subscribe") => {
+                                    for id in requested_ids(&control) {
+                                        if subscribed.len() >= MAX_SUBSCRIPTIONS && !subscribed.contains(&id) {
+                                            replies.push(serde_json::json!({
+                                                "type": "error",
+                                                "transaction_id": id,
+                                                "error": format!("At most {MAX_SUBSCRIPTIONS} subscriptions per connection"),
+                                            }));
+                                            continue;
+                                        }
+                                        let snapshot = current_status(&storage, &id);
+                                        let watch = snapshot["type"] == "status" && snapshot["terminal"] != true;
+                                        if watch {
+                                            subscribed.insert(id);
+                                        }
+                                        replies.push(snapshot);
+                                    }
+                                }
+                                Some("unsubscribe") => {
+                                    for id in requested_ids(&control) {
+                                        subscribed.remove(&id);
+                                    }
+                                    replies.push(serde_json::json!({"type": "unsubscribed", "subscriptions": subscribed.len()}));
+                                }
+                                Some("close") => break,
+                                _ => replies.push(serde_json::json!({"type": "error", "error": "Unknown control message"})),
+                            }
+                            let mut closed = false;
+                            for reply in replies {
+                                if session.text(reply.to_string()).await.is_err() {
+                                    closed = true;
+                                    break;
+                                }
+                            }
+                            if closed {
+                                break;
+                            }
+                        }
+                        Message::Ping(bytes) => {
+                            if session.pong(&bytes).await.is_err() {
+                                break;
+                            }
+                        }
+                        Message::Close(_) => break,
+                        _ => {}
+                    }
+                }
+                event = events.recv() => {
+                    let messages = match event {
+                        Ok(event) if subscribed.contains(&event.transaction_id) => {
+                            if is_terminal_status(&event.transition.status) {
+                                subscribed.remove(&event.transaction_id);
+                            }
+                            vec![status_message(&event)]
+                        }
+                        Ok(_) => continue,
+                        // Fell behind; resend where each subscription stands now
+                        Err(RecvError::Lagged(missed)) => {
+                            log::warn!("Status subscriber lagged by {} events", missed);
+                            let snapshots: Vec<_> = subscribed.iter().map(|id| current_status(&storage, id)).collect();
+                            subscribed.retain(|id| storage.get_transaction(id)
+                                .map(|t| !is_terminal_status(&t.status))
+                                .unwrap_or(false));
+                            snapshots
+                        }
+                        Err(RecvError::Closed) => break,
+                    };
+                    let mut closed = false;
+                    for message in messages {
+                        if session.text(message.to_string()).await.is_err() {
+                            closed = true;
+                            break;
+                        }
+                    }
+                    if closed {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let _ = session.close(None).await;
+    });
+
+    Ok(response)
+}
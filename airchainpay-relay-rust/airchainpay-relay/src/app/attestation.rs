@@ -0,0 +1,310 @@
+use crate::app::gas_accounting::{GasLedger, SpendKey};
+use crate::domain::identity::RelayIdentity;
+use crate::infrastructure::blockchain::manager::BlockchainManager;
+use crate::infrastructure::storage::file_storage::Storage;
+use crate::utils::merkle;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use ethers::types::{Address, H256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestationConfig {
+    pub enabled: bool,
+    pub interval: Duration,
+    /// Most transactions committed to by a single root
+    pub max_batch_size: usize,
+}
+
+impl Default for AttestationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: Duration::from_secs(3600),
+            max_batch_size: 1000,
+        }
+    }
+}
+
+impl AttestationConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("ATTESTATION_ENABLED")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.enabled),
+            interval: std::env::var("ATTESTATION_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.interval),
+            max_batch_size: std::env::var("ATTESTATION_MAX_BATCH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n: &usize| *n > 0)
+                .unwrap_or(defaults.max_batch_size),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestedTransaction {
+    pub transaction_id: String,
+    pub tx_hash: H256,
+}
+
+/// One Merkle root over payments handled on a chain, in leaf order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestationBatch {
+    pub batch_id: String,
+    pub chain_id: u64,
+    /// Relay identity the root is attested from
+    pub relay: Address,
+    pub root: H256,
+    pub transactions: Vec<AttestedTransaction>,
+    pub from_time: DateTime<Utc>,
+    pub to_time: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    /// Set once the root is on chain; unsubmitted batches are retried each run
+    pub attestation_tx_hash: Option<H256>,
+    pub last_error: Option<String>,
+}
+
+impl AttestationBatch {
+    fn leaves(&self) -> Vec<H256> {
+        self.transactions.iter().map(|t| merkle::leaf(t.tx_hash)).collect()
+    }
+}
+
+/// Everything a merchant needs to check a payment against the relay's on-chain root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionProof {
+    pub transaction_id: String,
+    pub tx_hash: H256,
+    pub chain_id: u64,
+    pub batch_id: String,
+    pub root: H256,
+    pub leaf: H256,
+    pub proof: Vec<H256>,
+    pub relay: Address,
+    pub contract_address: Option<Address>,
+    pub attestation_tx_hash: Option<H256>,
+}
+
+/// Periodically commits completed payments to a Merkle root and publishes it
+/// on the payment contract from the relay identity address.
+///
+/// Batches are kept in `<data_dir>/attestations.json` so proofs remain
+/// available after the transactions themselves are archived.
+pub struct AttestationService {
+    config: AttestationConfig,
+    identity: Arc<RelayIdentity>,
+    storage: Arc<Storage>,
+    blockchain_manager: Arc<BlockchainManager>,
+    path: PathBuf,
+    batches: RwLock<Vec<AttestationBatch>>,
+    run_lock: Mutex<()>,
+    gas_ledger: Option<Arc<GasLedger>>,
+}
+
+impl AttestationService {
+    pub fn new(
+        config: AttestationConfig,
+        identity: Arc<RelayIdentity>,
+        storage: Arc<Storage>,
+        blockchain_manager: Arc<BlockchainManager>,
+    ) -> Result<Self> {
+        let path = PathBuf::from(storage.data_dir()).join("attestations.json");
+        let batches = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            config,
+            identity,
+            storage,
+            blockchain_manager,
+            path,
+            batches: RwLock::new(batches),
+            run_lock: Mutex::new(()),
+            gas_ledger: None,
+        })
+    }
+
+    /// Charge attestation gas to the chain's budget and skip roots once it is spent
+    pub fn with_gas_ledger(mut self, gas_ledger: Arc<GasLedger>) -> Self {
+        self.gas_ledger = Some(gas_ledger);
+        self
+    }
+
+    pub fn config(&self) -> &AttestationConfig {
+        &self.config
+    }
+
+    pub async fn batches(&self) -> Vec<AttestationBatch> {
+        self.batches.read().await.clone()
+    }
+
+    pub fn start(service: Arc<AttestationService>) {
+        if !service.config.enabled {
+            log::info!("Relay attestation disabled: ATTESTATION_ENABLED not set");
+            return;
+        }
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(service.config.interval);
+            // The first tick fires immediately; wait a full period before the first root
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = service.run_once().await {
+                    log::warn!("Relay attestation run failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Retry unsubmitted roots, then batch and submit anything completed since
+    /// the last run. Returns the batches created or submitted by this run.
+    pub async fn run_once(&self) -> Result<Vec<AttestationBatch>> {
+        let _running = self.run_lock.lock().await;
+        let mut touched = Vec::new();
+
+        let pending: Vec<usize> = {
+            let batches = self.batches.read().await;
+            (0..batches.len()).filter(|&i| batches[i].attestation_tx_hash.is_none()).collect()
+        };
+        for index in pending {
+            let batch = self.batches.read().await[index].clone();
+            let batch = self.submit(batch).await;
+            self.batches.write().await[index] = batch.clone();
+            touched.push(batch);
+        }
+
+        for chain_id in self.blockchain_manager.chain_ids() {
+            if let Some(batch) = self.build_batch(chain_id).await {
+                let batch = self.submit(batch).await;
+                self.batches.write().await.push(batch.clone());
+                touched.push(batch);
+            }
+        }
+
+        self.save().await?;
+        Ok(touched)
+    }
+
+    async fn build_batch(&self, chain_id: u64) -> Option<AttestationBatch> {
+        let attested: HashSet<String> = self.batches.read().await.iter()
+            .flat_map(|b| b.transactions.iter().map(|t| t.transaction_id.clone()))
+            .collect();
+
+        let mut candidates: Vec<_> = self.storage.get_transactions(usize::MAX).into_iter()
+            .filter(|t| t.chain_id == chain_id && t.status == "completed" && !attested.contains(&t.id))
+            .filter_map(|t| {
+                let tx_hash = H256::from_str(t.tx_hash.as_deref()?).ok()?;
+                Some((t.timestamp, AttestedTransaction { transaction_id: t.id, tx_hash }))
+            })
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+        candidates.sort_by_key(|(timestamp, _)| *timestamp);
+        candidates.truncate(self.config.max_batch_size);
+
+        let from_time = candidates.first()?.0;
+        let to_time = candidates.last()?.0;
+        let transactions: Vec<AttestedTransaction> = candidates.into_iter().map(|(_, t)| t).collect();
+        let leaves: Vec<H256> = transactions.iter().map(|t| merkle::leaf(t.tx_hash)).collect();
+
+        Some(AttestationBatch {
+            batch_id: uuid::Uuid::new_v4().to_string(),
+            chain_id,
+            relay: self.identity.address(),
+            root: merkle::root(&leaves)?,
+            transactions,
+            from_time,
+            to_time,
+            created_at: Utc::now(),
+            attestation_tx_hash: None,
+            last_error: None,
+        })
+    }
+
+    async fn submit(&self, mut batch: AttestationBatch) -> AttestationBatch {
+        let spend_key = SpendKey::new(batch.chain_id, "attestation");
+        if let Some(ledger) = &self.gas_ledger {
+            if let Err(e) = ledger.check_budget(&spend_key).await {
+                log::warn!("Deferring attestation of batch {}: {}", batch.batch_id, e);
+                batch.last_error = Some(e.to_string());
+                return batch;
+            }
+        }
+
+        let result = self.blockchain_manager.attest_relay_root(
+            batch.chain_id,
+            self.identity.wallet(),
+            batch.root,
+            batch.transactions.len() as u64,
+            batch.from_time.timestamp().max(0) as u64,
+            batch.to_time.timestamp().max(0) as u64,
+        ).await;
+        match result {
+            Ok(receipt) => {
+                let tx_hash = receipt.transaction_hash;
+                log::info!(
+                    "Attested {} transactions on chain {} with root {:?} in {:?}",
+                    batch.transactions.len(), batch.chain_id, batch.root, tx_hash
+                );
+                if let Some(ledger) = &self.gas_ledger {
+                    if let Err(e) = ledger.record_receipt(spend_key, &receipt).await {
+                        log::warn!("Failed to record attestation gas for {:?}: {}", tx_hash, e);
+                    }
+                }
+                batch.attestation_tx_hash = Some(tx_hash);
+                batch.last_error = None;
+            }
+            Err(e) => {
+                log::warn!("Failed to attest batch {} on chain {}: {}", batch.batch_id, batch.chain_id, e);
+                batch.last_error = Some(e.to_string());
+            }
+        }
+        batch
+    }
+
+    async fn save(&self) -> Result<()> {
+        let data = serde_json::to_string_pretty(&*self.batches.read().await)?;
+        std::fs::write(&self.path, data)?;
+        Ok(())
+    }
+
+    /// Merkle proof for a transaction, once it has been included in a batch
+    pub async fn proof(&self, transaction_id: &str) -> Option<TransactionProof> {
+        let batches = self.batches.read().await;
+        let (batch, index) = batches.iter().find_map(|b| {
+            b.transactions.iter()
+                .position(|t| t.transaction_id == transaction_id)
+                .map(|index| (b, index))
+        })?;
+        let leaves = batch.leaves();
+
+        Some(TransactionProof {
+            transaction_id: transaction_id.to_string(),
+            tx_hash: batch.transactions[index].tx_hash,
+            chain_id: batch.chain_id,
+            batch_id: batch.batch_id.clone(),
+            root: batch.root,
+            leaf: leaves[index],
+            proof: merkle::proof(&leaves, index)?,
+            relay: batch.relay,
+            contract_address: self.blockchain_manager.contract_address(batch.chain_id),
+            attestation_tx_hash: batch.attestation_tx_hash,
+        })
+    }
+}
@@ -0,0 +1,294 @@
+use crate::app::gas_accounting::{GasLedger, SpendKey};
+use crate::infrastructure::blockchain::manager::BlockchainManager;
+use crate::infrastructure::blockchain::traced_http::RpcProvider;
+use crate::infrastructure::monitoring::manager::{AlertSeverity, MonitoringManager};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::RwLock;
+use tokio::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanaryConfig {
+    pub enabled: bool,
+    #[serde(skip_serializing)]
+    pub relayer_private_key: Option<String>,
+    pub interval: Duration,
+    /// How long a canary may take to be mined before the chain counts as unhealthy
+    pub confirmation_timeout: Duration,
+    /// Amount sent to the relayer's own address
+    pub value_wei: u64,
+    /// Chains to probe; empty means every configured chain
+    pub chains: Vec<u64>,
+}
+
+impl Default for CanaryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            relayer_private_key: None,
+            interval: Duration::from_secs(3600),
+            confirmation_timeout: Duration::from_secs(300),
+            value_wei: 1,
+            chains: Vec::new(),
+        }
+    }
+}
+
+impl CanaryConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("CANARY_ENABLED")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.enabled),
+            relayer_private_key: std::env::var("RELAYER_PRIVATE_KEY").ok(),
+            interval: std::env::var("CANARY_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.interval),
+            confirmation_timeout: std::env::var("CANARY_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.confirmation_timeout),
+            value_wei: std::env::var("CANARY_VALUE_WEI")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.value_wei),
+            chains: std::env::var("CANARY_CHAINS")
+                .map(|v| v.split(',').filter_map(|c| c.trim().parse().ok()).collect())
+                .unwrap_or(defaults.chains),
+        }
+    }
+}
+
+/// Outcome of one canary transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanaryResult {
+    pub chain_id: u64,
+    pub healthy: bool,
+    pub tx_hash: Option<String>,
+    /// Time to get the transaction accepted by the node
+    pub broadcast_ms: Option<u64>,
+    /// Time from sending until the receipt was seen
+    pub confirmation_ms: Option<u64>,
+    pub block_number: Option<u64>,
+    pub error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl CanaryResult {
+    fn failed(chain_id: u64, tx_hash: Option<H256>, broadcast_ms: Option<u64>, error: String) -> Self {
+        Self {
+            chain_id,
+            healthy: false,
+            tx_hash: tx_hash.map(|h| format!("{:?}", h)),
+            broadcast_ms,
+            confirmation_ms: None,
+            block_number: None,
+            error: Some(error),
+            checked_at: Utc::now(),
+        }
+    }
+}
+
+/// Sends a tiny self-transfer from the relayer wallet on each chain and
+/// times it end to end, catching broadcast failures that RPC pings miss:
+/// a dry hot wallet, a mempool that drops the relay's transactions, or a
+/// chain that has stopped producing blocks.
+pub struct CanaryMonitor {
+    blockchain_manager: Arc<BlockchainManager>,
+    monitoring_manager: Arc<MonitoringManager>,
+    config: CanaryConfig,
+    gas_ledger: Option<Arc<GasLedger>>,
+    results: RwLock<HashMap<u64, CanaryResult>>,
+}
+
+impl CanaryMonitor {
+    pub fn new(
+        blockchain_manager: Arc<BlockchainManager>,
+        monitoring_manager: Arc<MonitoringManager>,
+        config: CanaryConfig,
+    ) -> Self {
+        Self {
+            blockchain_manager,
+            monitoring_manager,
+            config,
+            gas_ledger: None,
+            results: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Charge canary transactions to the chain's gas budget
+    pub fn with_gas_ledger(mut self, gas_ledger: Arc<GasLedger>) -> Self {
+        self.gas_ledger = Some(gas_ledger);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled && self.config.relayer_private_key.is_some()
+    }
+
+    pub fn start(monitor: Arc<CanaryMonitor>) {
+        if !monitor.is_enabled() {
+            log::info!("Canary transactions disabled");
+            return;
+        }
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(monitor.config.interval);
+            loop {
+                ticker.tick().await;
+                let runs = monitor.chains().into_iter().map(|chain_id| {
+                    let monitor = Arc::clone(&monitor);
+                    async move { monitor.run_chain(chain_id).await }
+                });
+                futures::future::join_all(runs).await;
+            }
+        });
+    }
+
+    fn chains(&self) -> Vec<u64> {
+        let configured = self.blockchain_manager.chain_ids();
+        if self.config.chains.is_empty() {
+            configured
+        } else {
+            configured.into_iter().filter(|c| self.config.chains.contains(c)).collect()
+        }
+    }
+
+    /// Latest canary outcome for every probed chain
+    pub async fn results(&self) -> Vec<CanaryResult> {
+        let mut list: Vec<CanaryResult> = self.results.read().await.values().cloned().collect();
+        list.sort_by_key(|r| r.chain_id);
+        list
+    }
+
+    /// Send one canary on a chain, record the outcome and alert on failure
+    pub async fn run_chain(&self, chain_id: u64) -> CanaryResult {
+        let result = self.send_canary(chain_id).await;
+        self.monitoring_manager
+            .record_canary(chain_id, result.healthy, result.confirmation_ms)
+            .await;
+        if let Some(error) = &result.error {
+            log::warn!("Canary on chain {} failed: {}", chain_id, error);
+            let mut metadata = HashMap::new();
+            metadata.insert("chain_id".to_string(), serde_json::json!(chain_id));
+            metadata.insert("tx_hash".to_string(), serde_json::json!(result.tx_hash));
+            self.monitoring_manager.raise_alert(
+                "canary_transaction_failed",
+                AlertSeverity::Warning,
+                format!("Canary transaction on chain {} failed: {}", chain_id, error),
+                metadata,
+            ).await;
+        } else {
+            log::info!("Canary on chain {} confirmed in {}ms", chain_id, result.confirmation_ms.unwrap_or_default());
+        }
+        self.results.write().await.insert(chain_id, result.clone());
+        result
+    }
+
+    async fn send_canary(&self, chain_id: u64) -> CanaryResult {
+        let client = match self.signer(chain_id) {
+            Ok(client) => client,
+            Err(e) => return CanaryResult::failed(chain_id, None, None, e.to_string()),
+        };
+        let spend_key = SpendKey::new(chain_id, "canary");
+        if let Some(ledger) = &self.gas_ledger {
+            if let Err(e) = ledger.check_budget(&spend_key).await {
+                return CanaryResult::failed(chain_id, None, None, e.to_string());
+            }
+        }
+
+        let address = client.address();
+        let tx = TransactionRequest::new()
+            .to(address)
+            .value(U256::from(self.config.value_wei))
+            .gas(21_000u64);
+
+        let started = Instant::now();
+        let pending = match client.send_transaction(tx, None).await {
+            Ok(pending) => pending,
+            Err(e) => return CanaryResult::failed(chain_id, None, None, format!("Broadcast failed: {}", e)),
+        };
+        let tx_hash = pending.tx_hash();
+        let broadcast_ms = started.elapsed().as_millis() as u64;
+
+        let receipt = match tokio::time::timeout(self.config.confirmation_timeout, pending).await {
+            Ok(Ok(Some(receipt))) => receipt,
+            Ok(Ok(None)) => {
+                return CanaryResult::failed(chain_id, Some(tx_hash), Some(broadcast_ms), "Transaction dropped".to_string());
+            }
+            Ok(Err(e)) => {
+                return CanaryResult::failed(chain_id, Some(tx_hash), Some(broadcast_ms), format!("Receipt lookup failed: {}", e));
+            }
+            Err(_) => {
+                return CanaryResult::failed(
+                    chain_id,
+                    Some(tx_hash),
+                    Some(broadcast_ms),
+                    format!("Not mined within {}s", self.config.confirmation_timeout.as_secs()),
+                );
+            }
+        };
+        let confirmation_ms = started.elapsed().as_millis() as u64;
+
+        if let Some(ledger) = &self.gas_ledger {
+            if let Err(e) = ledger.record_receipt(spend_key, &receipt).await {
+                log::warn!("Failed to record canary gas for {:?}: {}", tx_hash, e);
+            }
+        }
+
+        let reverted = receipt.status.map(|s| s.as_u64()) != Some(1);
+        CanaryResult {
+            chain_id,
+            healthy: !reverted,
+            tx_hash: Some(format!("{:?}", tx_hash)),
+            broadcast_ms: Some(broadcast_ms),
+            confirmation_ms: Some(confirmation_ms),
+            block_number: receipt.block_number.map(|b| b.as_u64()),
+            error: reverted.then(|| "Canary transaction reverted".to_string()),
+            checked_at: Utc::now(),
+        }
+    }
+
+    fn signer(&self, chain_id: u64) -> Result<SignerMiddleware<RpcProvider, LocalWallet>> {
+        let provider = self.blockchain_manager.provider(chain_id)
+            .ok_or_else(|| anyhow!("No provider for chain_id {}", chain_id))?;
+        let private_key = self.config.relayer_private_key.as_ref()
+            .ok_or_else(|| anyhow!("RELAYER_PRIVATE_KEY is not configured"))?;
+        let wallet = LocalWallet::from_str(private_key.trim_start_matches("0x"))
+            .map_err(|e| anyhow!("Invalid relayer private key: {}", e))?
+            .with_chain_id(chain_id);
+        Ok(SignerMiddleware::new(provider, wallet))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_canary_metrics_track_last_outcome() {
+        let monitoring = MonitoringManager::new();
+        monitoring.record_canary(84532, true, Some(4_200)).await;
+        monitoring.record_canary(1114, false, None).await;
+        monitoring.record_canary(84532, false, None).await;
+
+        let rendered = monitoring.render_canary_metrics().await;
+        assert!(rendered.contains("airchainpay_canary_healthy{chain_id=\"84532\"} 0"));
+        assert!(rendered.contains("airchainpay_canary_confirmation_seconds{chain_id=\"84532\"} 4.200"));
+        assert!(!rendered.contains("airchainpay_canary_confirmation_seconds{chain_id=\"1114\"}"));
+        assert!(rendered.contains("airchainpay_canary_runs_total{chain_id=\"84532\"} 2"));
+        assert!(rendered.contains("airchainpay_canary_failures_total{chain_id=\"1114\"} 1"));
+    }
+}
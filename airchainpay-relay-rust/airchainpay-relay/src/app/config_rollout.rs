@@ -0,0 +1,236 @@
+use crate::infrastructure::config::{Config, DynamicConfigManager};
+use crate::infrastructure::monitoring::manager::{AlertSeverity, MonitoringManager};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolloutPolicy {
+    /// How long a staged config is watched before it is committed
+    pub window: Duration,
+    pub check_interval: Duration,
+    /// Error rate during the window above which the change is reverted
+    pub max_error_rate: f64,
+    /// Requests needed in the window before the error rate is trusted
+    pub min_requests: u64,
+}
+
+impl Default for RolloutPolicy {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(120),
+            check_interval: Duration::from_secs(5),
+            max_error_rate: 0.25,
+            min_requests: 20,
+        }
+    }
+}
+
+impl RolloutPolicy {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            window: std::env::var("CONFIG_ROLLOUT_WINDOW_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.window),
+            check_interval: defaults.check_interval,
+            max_error_rate: std::env::var("CONFIG_ROLLOUT_MAX_ERROR_RATE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_error_rate),
+            min_requests: std::env::var("CONFIG_ROLLOUT_MIN_REQUESTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.min_requests),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RolloutState {
+    Staged,
+    Committed,
+    RolledBack,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolloutStatus {
+    pub id: String,
+    pub state: RolloutState,
+    pub description: String,
+    pub requested_by: String,
+    pub staged_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub requests_observed: u64,
+    pub failures_observed: u64,
+    pub reason: Option<String>,
+}
+
+impl RolloutStatus {
+    pub fn error_rate(&self) -> f64 {
+        if self.requests_observed == 0 {
+            0.0
+        } else {
+            self.failures_observed as f64 / self.requests_observed as f64
+        }
+    }
+}
+
+struct ActiveRollout {
+    previous: Config,
+    baseline_requests: u64,
+    baseline_failures: u64,
+}
+
+/// Applies API config changes on probation and reverts them if request
+/// failures spike before the observation window closes.
+pub struct ConfigRollout {
+    config_manager: Arc<DynamicConfigManager>,
+    monitoring_manager: Arc<MonitoringManager>,
+    policy: RolloutPolicy,
+    active: Mutex<Option<ActiveRollout>>,
+    status: RwLock<Option<RolloutStatus>>,
+}
+
+impl ConfigRollout {
+    pub fn new(
+        config_manager: Arc<DynamicConfigManager>,
+        monitoring_manager: Arc<MonitoringManager>,
+        policy: RolloutPolicy,
+    ) -> Self {
+        Self {
+            config_manager,
+            monitoring_manager,
+            policy,
+            active: Mutex::new(None),
+            status: RwLock::new(None),
+        }
+    }
+
+    pub fn policy(&self) -> &RolloutPolicy {
+        &self.policy
+    }
+
+    /// The current rollout, or the last one to finish
+    pub async fn status(&self) -> Option<RolloutStatus> {
+        self.status.read().await.clone()
+    }
+
+    /// Apply `new_config` immediately and start watching error rates.
+    /// Only one rollout may be in progress at a time.
+    pub async fn stage(self: &Arc<Self>, new_config: Config, description: &str, requested_by: &str) -> Result<RolloutStatus> {
+        let mut active = self.active.lock().await;
+        if active.is_some() {
+            return Err(anyhow!("A configuration rollout is already in progress"));
+        }
+
+        let previous = self.config_manager.get_config().await;
+        let metrics = self.monitoring_manager.get_metrics().await;
+        self.config_manager.update_config(new_config).await?;
+
+        *active = Some(ActiveRollout {
+            previous,
+            baseline_requests: metrics.requests_total,
+            baseline_failures: metrics.requests_failed,
+        });
+        let status = RolloutStatus {
+            id: uuid::Uuid::new_v4().to_string(),
+            state: RolloutState::Staged,
+            description: description.to_string(),
+            requested_by: requested_by.to_string(),
+            staged_at: Utc::now(),
+            finished_at: None,
+            requests_observed: 0,
+            failures_observed: 0,
+            reason: None,
+        };
+        *self.status.write().await = Some(status.clone());
+        log::info!("Configuration change '{}' staged by {} for {:?}", description, requested_by, self.policy.window);
+
+        let rollout = Arc::clone(self);
+        let rollout_id = status.id.clone();
+        tokio::spawn(async move {
+            rollout.watch(rollout_id).await;
+        });
+        Ok(status)
+    }
+
+    /// Revert the staged change now
+    pub async fn rollback(&self, reason: &str) -> Result<RolloutStatus> {
+        let mut active = self.active.lock().await;
+        let rollout = active.take().ok_or_else(|| anyhow!("No configuration rollout is in progress"))?;
+        self.config_manager.update_config(rollout.previous).await?;
+        self.finish(RolloutState::RolledBack, Some(reason.to_string())).await
+    }
+
+    async fn watch(&self, rollout_id: String) {
+        let started = tokio::time::Instant::now();
+        loop {
+            tokio::time::sleep(self.policy.check_interval).await;
+
+            let mut active = self.active.lock().await;
+            let Some(rollout) = active.as_ref() else {
+                return; // rolled back manually
+            };
+            if self.status.read().await.as_ref().is_none_or(|s| s.id != rollout_id) {
+                return;
+            }
+
+            let metrics = self.monitoring_manager.get_metrics().await;
+            let requests = metrics.requests_total.saturating_sub(rollout.baseline_requests);
+            let failures = metrics.requests_failed.saturating_sub(rollout.baseline_failures);
+            if let Some(status) = self.status.write().await.as_mut() {
+                status.requests_observed = requests;
+                status.failures_observed = failures;
+            }
+
+            let error_rate = if requests == 0 { 0.0 } else { failures as f64 / requests as f64 };
+            if requests >= self.policy.min_requests && error_rate > self.policy.max_error_rate {
+                let reason = format!(
+                    "error rate {:.1}% over {} requests exceeded {:.1}%",
+                    error_rate * 100.0, requests, self.policy.max_error_rate * 100.0
+                );
+                let previous = active.take().map(|r| r.previous);
+                if let Some(previous) = previous {
+                    if let Err(e) = self.config_manager.update_config(previous).await {
+                        log::error!("Failed to roll back configuration: {}", e);
+                    }
+                }
+                log::warn!("Configuration change rolled back: {}", reason);
+                let mut metadata = HashMap::new();
+                metadata.insert("rollout_id".to_string(), serde_json::json!(rollout_id));
+                self.monitoring_manager.raise_alert(
+                    "config_rollback",
+                    AlertSeverity::Warning,
+                    format!("Configuration change rolled back: {reason}"),
+                    metadata,
+                ).await;
+                let _ = self.finish(RolloutState::RolledBack, Some(reason)).await;
+                return;
+            }
+
+            if started.elapsed() >= self.policy.window {
+                active.take();
+                log::info!("Configuration change committed after {:?}", self.policy.window);
+                let _ = self.finish(RolloutState::Committed, None).await;
+                return;
+            }
+        }
+    }
+
+    async fn finish(&self, state: RolloutState, reason: Option<String>) -> Result<RolloutStatus> {
+        let mut status = self.status.write().await;
+        let current = status.as_mut().ok_or_else(|| anyhow!("No configuration rollout recorded"))?;
+        current.state = state;
+        current.reason = reason;
+        current.finished_at = Some(Utc::now());
+        Ok(current.clone())
+    }
+}
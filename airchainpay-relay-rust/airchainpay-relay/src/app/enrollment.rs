@@ -0,0 +1,99 @@
+use crate::infrastructure::pki::{DeviceCa, DeviceCertificate, IssuedCertificate};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// One-time token handed to a device out of band so it can request its first certificate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnrollmentToken {
+    pub token: String,
+    pub device_id: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct PendingEnrollment {
+    device_id: String,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnrolledCertificate {
+    pub certificate: String,
+    pub ca_chain: String,
+    #[serde(flatten)]
+    pub record: IssuedCertificate,
+}
+
+/// Device enrollment: one-time tokens for the first certificate, then renewal
+/// by presenting the current certificate before it expires.
+pub struct DeviceEnrollment {
+    ca: Arc<DeviceCa>,
+    /// Keyed by SHA-256 of the token so tokens are never held in the clear
+    pending: RwLock<HashMap<String, PendingEnrollment>>,
+}
+
+fn token_key(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+impl DeviceEnrollment {
+    pub fn new(ca: Arc<DeviceCa>) -> Self {
+        Self {
+            ca,
+            pending: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn ca(&self) -> &DeviceCa {
+        &self.ca
+    }
+
+    pub async fn create_token(&self, device_id: &str) -> EnrollmentToken {
+        let bytes: [u8; 32] = rand::rng().random();
+        let token = hex::encode(bytes);
+        let expires_at = Utc::now() + Duration::seconds(self.ca.config().enrollment_token_ttl_secs);
+
+        let mut pending = self.pending.write().await;
+        pending.retain(|_, p| p.expires_at > Utc::now());
+        pending.insert(token_key(&token), PendingEnrollment {
+            device_id: device_id.to_string(),
+            expires_at,
+        });
+
+        EnrollmentToken {
+            token,
+            device_id: device_id.to_string(),
+            expires_at,
+        }
+    }
+
+    /// Exchange an enrollment token and CSR for a certificate. The token is
+    /// consumed even if the CSR turns out to be invalid.
+    pub async fn enroll(&self, token: &str, csr_pem: &str) -> Result<EnrolledCertificate> {
+        let pending = self.pending.write().await.remove(&token_key(token))
+            .filter(|p| p.expires_at > Utc::now())
+            .ok_or_else(|| anyhow!("Enrollment token is invalid or expired"))?;
+        self.issue(&pending.device_id, csr_pem)
+    }
+
+    /// Issue a fresh certificate to a device holding a valid one
+    pub fn renew(&self, current: &DeviceCertificate, csr_pem: &str) -> Result<EnrolledCertificate> {
+        self.issue(&current.device_id, csr_pem)
+    }
+
+    fn issue(&self, device_id: &str, csr_pem: &str) -> Result<EnrolledCertificate> {
+        let (certificate, record) = self.ca.issue(device_id, csr_pem)?;
+        log::info!("Issued device certificate {} to {} (expires {})", record.serial, device_id, record.not_after);
+        Ok(EnrolledCertificate {
+            certificate,
+            ca_chain: self.ca.ca_chain_pem().to_string(),
+            record,
+        })
+    }
+}
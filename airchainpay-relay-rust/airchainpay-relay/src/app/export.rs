@@ -0,0 +1,157 @@
+use crate::infrastructure::storage::archive::ArchiveQuery;
+use crate::infrastructure::storage::file_storage::{Storage, Transaction};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use ethers::core::utils::rlp::{Decodable, Rlp};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+const CSV_HEADER: &str = "id,chain_id,created_at,status,tx_hash,merchant,from,value,nonce,error,archived,tags\n";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExportQuery {
+    pub chain_id: Option<u64>,
+    /// Recipient address of the signed transaction
+    pub merchant: Option<String>,
+    /// Only transactions created at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Only transactions created before this time
+    pub to: Option<DateTime<Utc>>,
+    /// Also export records moved to the archive
+    #[serde(default)]
+    pub include_archived: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportSummary {
+    pub path: String,
+    pub rows: usize,
+    pub bytes: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Fields recovered from the raw signed transaction
+#[derive(Debug, Default)]
+struct DecodedFields {
+    to: Option<String>,
+    from: Option<String>,
+    value: Option<String>,
+    nonce: Option<u64>,
+}
+
+fn decode_fields(signed_tx: &str) -> DecodedFields {
+    let decoded = hex::decode(signed_tx.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| ethers::types::Transaction::decode(&Rlp::new(&bytes)).ok());
+    match decoded {
+        Some(tx) => DecodedFields {
+            to: tx.to.map(|to| format!("0x{to:x}")),
+            from: tx.recover_from().ok().map(|from| format!("0x{from:x}")),
+            value: Some(tx.value.to_string()),
+            nonce: Some(tx.nonce.as_u64()),
+        },
+        None => DecodedFields::default(),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Produces flat CSV extracts of transactions for data-warehouse ingestion
+pub struct TransactionExporter {
+    storage: Arc<Storage>,
+}
+
+impl TransactionExporter {
+    pub fn new(storage: Arc<Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Matching transactions, oldest first
+    fn collect(&self, query: &ExportQuery) -> Result<Vec<(Transaction, DecodedFields)>> {
+        let mut transactions = self.storage.get_transactions(usize::MAX);
+        if query.include_archived {
+            let archive_query = ArchiveQuery {
+                chain_id: query.chain_id,
+                from: query.from,
+                to: query.to,
+                limit: Some(usize::MAX),
+                ..Default::default()
+            };
+            transactions.extend(
+                self.storage.search_archived_transactions(&archive_query)?
+                    .into_iter()
+                    .map(|entry| entry.transaction),
+            );
+        }
+
+        let merchant = query.merchant.as_ref().map(|m| m.to_lowercase());
+        let mut rows: Vec<(Transaction, DecodedFields)> = transactions.into_iter()
+            .filter(|tx| query.chain_id.is_none_or(|chain_id| tx.chain_id == chain_id))
+            .filter(|tx| query.from.is_none_or(|from| tx.timestamp >= from))
+            .filter(|tx| query.to.is_none_or(|to| tx.timestamp < to))
+            .map(|tx| {
+                let fields = decode_fields(&tx.signed_tx);
+                (tx, fields)
+            })
+            .filter(|(_, fields)| merchant.as_ref().is_none_or(|m| fields.to.as_deref() == Some(m.as_str())))
+            .collect();
+        rows.sort_by_key(|(tx, _)| tx.timestamp);
+        Ok(rows)
+    }
+
+    /// Render matching transactions as CSV, returning the document and row count
+    pub fn to_csv(&self, query: &ExportQuery) -> Result<(String, usize)> {
+        let rows = self.collect(query)?;
+        let mut csv = String::from(CSV_HEADER);
+        for (tx, fields) in &rows {
+            let line = [
+                csv_field(&tx.id),
+                tx.chain_id.to_string(),
+                tx.timestamp.to_rfc3339(),
+                csv_field(&tx.status),
+                csv_field(tx.tx_hash.as_deref().unwrap_or("")),
+                fields.to.clone().unwrap_or_default(),
+                fields.from.clone().unwrap_or_default(),
+                fields.value.clone().unwrap_or_default(),
+                fields.nonce.map(|n| n.to_string()).unwrap_or_default(),
+                csv_field(tx.error_details.as_deref().unwrap_or("")),
+                tx.archived.to_string(),
+                csv_field(&tx.tags.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>().join(";")),
+            ];
+            csv.push_str(&line.join(","));
+            csv.push('\n');
+        }
+        Ok((csv, rows.len()))
+    }
+
+    /// Write a gzip-compressed CSV extract into `<target_dir>/exports`
+    pub fn write_to(&self, target_dir: &str, query: &ExportQuery) -> Result<ExportSummary> {
+        let (csv, rows) = self.to_csv(query)?;
+        let created_at = Utc::now();
+        let export_dir = Path::new(target_dir).join("exports");
+        std::fs::create_dir_all(&export_dir)?;
+        let path = export_dir.join(format!("transactions_{}.csv.gz", created_at.format("%Y%m%d_%H%M%S")));
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(csv.as_bytes())?;
+        let compressed = encoder.finish()?;
+        std::fs::write(&path, &compressed)?;
+
+        Ok(ExportSummary {
+            path: path.to_str().ok_or_else(|| anyhow!("Export path is not valid UTF-8"))?.to_string(),
+            rows,
+            bytes: compressed.len() as u64,
+            created_at,
+        })
+    }
+}
@@ -0,0 +1,436 @@
+use crate::app::transaction_service::{QueuedTransaction, TransactionPriority, TransactionProcessor};
+use crate::domain::identity::{self, RelayIdentity};
+use crate::infrastructure::blockchain::manager::BlockchainManager;
+use crate::infrastructure::storage::file_storage::{Storage, Transaction};
+use crate::utils::request_id::{self, REQUEST_ID_HEADER};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::time::Duration;
+
+/// Path on the upstream relay that accepts transfer manifests
+pub const INGEST_PATH: &str = "/api/federation/ingest";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederationConfig {
+    /// Edge relays queue locally and hand everything to `upstream_url`
+    /// instead of broadcasting themselves
+    pub edge_mode: bool,
+    pub upstream_url: Option<String>,
+    /// Relay identities whose manifests this relay accepts
+    pub trusted_peers: Vec<Address>,
+    pub sync_interval: Duration,
+    pub batch_size: usize,
+}
+
+impl Default for FederationConfig {
+    fn default() -> Self {
+        Self {
+            edge_mode: false,
+            upstream_url: None,
+            trusted_peers: Vec::new(),
+            sync_interval: Duration::from_secs(30),
+            batch_size: 100,
+        }
+    }
+}
+
+impl FederationConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            edge_mode: std::env::var("RELAY_MODE")
+                .map(|v| v == "edge")
+                .unwrap_or(defaults.edge_mode),
+            upstream_url: std::env::var("FEDERATION_UPSTREAM_URL")
+                .ok()
+                .map(|v| v.trim_end_matches('/').to_string())
+                .filter(|v| !v.is_empty()),
+            trusted_peers: std::env::var("FEDERATION_TRUSTED_PEERS")
+                .map(|v| {
+                    v.split(',')
+                        .map(str::trim)
+                        .filter(|p| !p.is_empty())
+                        .filter_map(|p| match Address::from_str(p) {
+                            Ok(address) => Some(address),
+                            Err(_) => {
+                                log::warn!("Ignoring invalid federation peer address '{}'", p);
+                                None
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or(defaults.trusted_peers),
+            sync_interval: std::env::var("FEDERATION_SYNC_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.sync_interval),
+            batch_size: std::env::var("FEDERATION_BATCH_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n: &usize| *n > 0)
+                .unwrap_or(defaults.batch_size),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Transaction id on the originating relay, kept so status can be traced across relays
+    pub id: String,
+    pub chain_id: u64,
+    pub signed_tx: String,
+    /// Hex SHA-256 of `signed_tx`
+    pub tx_digest: String,
+    pub priority: TransactionPriority,
+    pub received_at: DateTime<Utc>,
+}
+
+/// A batch of queued transactions handed from one relay to another
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferManifest {
+    pub manifest_id: String,
+    /// Identity address of the sending relay
+    pub origin: Address,
+    pub created_at: DateTime<Utc>,
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl TransferManifest {
+    /// Hex SHA-256 of the serialized manifest; this is what gets signed
+    pub fn digest(&self) -> Result<String> {
+        Ok(hex::encode(Sha256::digest(serde_json::to_vec(self)?)))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedManifest {
+    pub manifest: TransferManifest,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejectedEntry {
+    pub id: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IngestOutcome {
+    pub manifest_id: String,
+    pub accepted: Vec<String>,
+    /// Entries the receiving relay already had; safe to drop on the sender
+    pub duplicates: Vec<String>,
+    pub rejected: Vec<RejectedEntry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FederationStatus {
+    pub last_sync_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub manifests_sent: u64,
+    pub entries_forwarded: u64,
+    pub entries_rejected_upstream: u64,
+    pub manifests_received: u64,
+    pub entries_accepted: u64,
+    pub entries_duplicate: u64,
+    pub entries_rejected: u64,
+}
+
+pub fn tx_digest(signed_tx: &str) -> String {
+    hex::encode(Sha256::digest(signed_tx.as_bytes()))
+}
+
+/// Relay-to-relay transfer of queued transactions.
+///
+/// An edge relay drains its queue into signed manifests and posts them to its
+/// upstream whenever it is reachable; entries stay queued until the upstream
+/// acknowledges them. The receiving side verifies the sender against its
+/// trusted peers, drops anything it already holds and queues the rest.
+pub struct Federation {
+    config: FederationConfig,
+    identity: Arc<RelayIdentity>,
+    storage: Arc<Storage>,
+    processor: Arc<TransactionProcessor>,
+    blockchain_manager: Arc<BlockchainManager>,
+    client: reqwest::Client,
+    status: RwLock<FederationStatus>,
+}
+
+impl Federation {
+    pub fn new(
+        config: FederationConfig,
+        identity: Arc<RelayIdentity>,
+        storage: Arc<Storage>,
+        processor: Arc<TransactionProcessor>,
+        blockchain_manager: Arc<BlockchainManager>,
+    ) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(15))
+            .build()
+            .unwrap_or_default();
+        Self {
+            config,
+            identity,
+            storage,
+            processor,
+            blockchain_manager,
+            client,
+            status: RwLock::new(FederationStatus::default()),
+        }
+    }
+
+    pub fn config(&self) -> &FederationConfig {
+        &self.config
+    }
+
+    pub fn identity(&self) -> &RelayIdentity {
+        &self.identity
+    }
+
+    pub fn is_edge(&self) -> bool {
+        self.config.edge_mode
+    }
+
+    pub async fn status(&self) -> FederationStatus {
+        self.status.read().await.clone()
+    }
+
+    /// Start forwarding the local queue upstream. No-op unless in edge mode.
+    pub fn start(federation: Arc<Federation>) {
+        if !federation.config.edge_mode {
+            return;
+        }
+        if federation.config.upstream_url.is_none() {
+            log::warn!("RELAY_MODE=edge but FEDERATION_UPSTREAM_URL is not set; queued transactions will not be forwarded");
+            return;
+        }
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(federation.config.sync_interval);
+            loop {
+                ticker.tick().await;
+                match federation.sync_once().await {
+                    Ok(0) => {}
+                    Ok(forwarded) => log::info!("Forwarded {} queued transactions upstream", forwarded),
+                    Err(e) => log::warn!("Federation sync failed: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Forward everything currently queued, one manifest per batch.
+    /// Stops at the first failed transfer and leaves the rest queued.
+    pub async fn sync_once(&self) -> Result<usize> {
+        let upstream = self.config.upstream_url.as_deref()
+            .ok_or_else(|| anyhow!("FEDERATION_UPSTREAM_URL is not configured"))?;
+        let mut forwarded = 0;
+
+        loop {
+            let batch = self.processor.take_for_forwarding(self.config.batch_size).await;
+            if batch.is_empty() {
+                break;
+            }
+
+            let outcome = match self.send_batch(upstream, &batch).await {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    self.processor.requeue(batch).await;
+                    let mut status = self.status.write().await;
+                    status.last_sync_at = Some(Utc::now());
+                    status.last_error = Some(e.to_string());
+                    return Err(e);
+                }
+            };
+
+            let rejected: HashMap<&str, &str> = outcome.rejected.iter()
+                .map(|r| (r.id.as_str(), r.reason.as_str()))
+                .collect();
+            for id in outcome.accepted.iter().chain(&outcome.duplicates) {
+                let _ = self.storage.update_transaction_status_with_error(id, "forwarded", None, None);
+            }
+            for (id, reason) in &rejected {
+                let _ = self.storage.update_transaction_status_with_error(
+                    id,
+                    "failed",
+                    None,
+                    Some(format!("Rejected by upstream relay: {}", reason)),
+                );
+            }
+            self.processor.finish_forwarding(&batch).await;
+
+            forwarded += outcome.accepted.len() + outcome.duplicates.len();
+            let mut status = self.status.write().await;
+            status.last_sync_at = Some(Utc::now());
+            status.last_error = None;
+            status.manifests_sent += 1;
+            status.entries_forwarded += (outcome.accepted.len() + outcome.duplicates.len()) as u64;
+            status.entries_rejected_upstream += rejected.len() as u64;
+        }
+
+        Ok(forwarded)
+    }
+
+    async fn send_batch(&self, upstream: &str, batch: &[QueuedTransaction]) -> Result<IngestOutcome> {
+        let entries: Vec<ManifestEntry> = batch.iter().filter_map(manifest_entry).collect();
+        if entries.len() < batch.len() {
+            log::warn!("Dropping {} queued entries without a transaction id or payload", batch.len() - entries.len());
+        }
+        let signed = self.sign_manifest(entries)?;
+        let mut request = self.client
+            .post(format!("{upstream}{INGEST_PATH}"))
+            .json(&signed);
+        if let Some(id) = request_id::current() {
+            request = request.header(REQUEST_ID_HEADER, id);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Upstream relay returned {}: {}", status, body));
+        }
+        let outcome: IngestOutcome = response.json().await?;
+        if outcome.manifest_id != signed.manifest.manifest_id {
+            return Err(anyhow!("Upstream acknowledged manifest {} instead of {}", outcome.manifest_id, signed.manifest.manifest_id));
+        }
+        Ok(outcome)
+    }
+
+    fn sign_manifest(&self, entries: Vec<ManifestEntry>) -> Result<SignedManifest> {
+        let manifest = TransferManifest {
+            manifest_id: uuid::Uuid::new_v4().to_string(),
+            origin: self.identity.address(),
+            created_at: Utc::now(),
+            entries,
+        };
+        let signature = self.identity.sign(manifest.digest()?.as_bytes())?;
+        Ok(SignedManifest { manifest, signature })
+    }
+
+    /// Check that a manifest comes from a trusted peer and was not altered
+    pub fn verify_manifest(&self, signed: &SignedManifest) -> Result<(), String> {
+        let origin = signed.manifest.origin;
+        if !self.config.trusted_peers.contains(&origin) {
+            return Err(format!("Relay {:?} is not a trusted federation peer", origin));
+        }
+        let digest = signed.manifest.digest().map_err(|e| e.to_string())?;
+        if !identity::verify_signature(digest.as_bytes(), &signed.signature, origin) {
+            return Err("Manifest signature does not match its origin".to_string());
+        }
+        Ok(())
+    }
+
+    /// Store and queue the entries of a verified manifest, skipping any this relay already holds
+    pub async fn ingest(&self, signed: SignedManifest) -> IngestOutcome {
+        let manifest = signed.manifest;
+        let origin = format!("{:?}", manifest.origin);
+        let supported_chains = self.blockchain_manager.chain_ids();
+        let mut outcome = IngestOutcome {
+            manifest_id: manifest.manifest_id.clone(),
+            ..Default::default()
+        };
+        let mut seen = HashSet::new();
+
+        for entry in manifest.entries {
+            let id = entry.id.clone();
+            let reject = |reason: &str| RejectedEntry { id: id.clone(), reason: reason.to_string() };
+
+            if tx_digest(&entry.signed_tx) != entry.tx_digest {
+                outcome.rejected.push(reject("Payload digest mismatch"));
+                continue;
+            }
+            if !supported_chains.contains(&entry.chain_id) {
+                outcome.rejected.push(reject(&format!("Unsupported chain {}", entry.chain_id)));
+                continue;
+            }
+            if !seen.insert(entry.tx_digest.clone())
+                || self.storage.find_transaction_by_signed_tx(&entry.signed_tx).is_some()
+            {
+                outcome.duplicates.push(id.clone());
+                continue;
+            }
+            if self.storage.get_transaction(&id).is_some() {
+                outcome.rejected.push(reject("Transaction id already used for a different payload"));
+                continue;
+            }
+
+            let mut transaction = Transaction::new(entry.signed_tx.clone(), entry.chain_id);
+            transaction.id = id.clone();
+            transaction.security.hash = entry.tx_digest.clone();
+            transaction.security.server_id = origin.clone();
+            if let Err(e) = self.storage.save_transaction(transaction) {
+                outcome.rejected.push(reject(&format!("Failed to store transaction: {e}")));
+                continue;
+            }
+            let _ = self.storage.update_metrics("transactions_received", 1);
+
+            let mut metadata = HashMap::new();
+            metadata.insert("id".to_string(), serde_json::Value::String(id.clone()));
+            metadata.insert("signedTx".to_string(), serde_json::Value::String(entry.signed_tx.clone()));
+            metadata.insert("transport".to_string(), serde_json::Value::String("federation".to_string()));
+            metadata.insert("origin".to_string(), serde_json::Value::String(origin.clone()));
+
+            let queued = QueuedTransaction {
+                transaction: serde_json::json!({
+                    "id": entry.id,
+                    "signed_tx": entry.signed_tx,
+                    "chain_id": entry.chain_id,
+                    "timestamp": entry.received_at.to_rfc3339(),
+                }),
+                priority: entry.priority,
+                queued_at: Utc::now(),
+                retry_count: 0,
+                max_retries: 3,
+                retry_delay: std::time::Duration::from_secs(2),
+                chain_id: entry.chain_id,
+                metadata,
+                replay_checked: false,
+            };
+
+            match self.processor.enqueue_transaction(queued).await {
+                Ok(()) => outcome.accepted.push(id.clone()),
+                Err(e) => {
+                    let _ = self.storage.update_transaction_status_with_error(&id, "queue_failed", None, Some(e.to_string()));
+                    outcome.rejected.push(reject(&e.to_string()));
+                }
+            }
+        }
+
+        let mut status = self.status.write().await;
+        status.manifests_received += 1;
+        status.entries_accepted += outcome.accepted.len() as u64;
+        status.entries_duplicate += outcome.duplicates.len() as u64;
+        status.entries_rejected += outcome.rejected.len() as u64;
+        drop(status);
+
+        log::info!(
+            "Ingested manifest {} from {}: {} accepted, {} duplicate, {} rejected",
+            outcome.manifest_id, origin, outcome.accepted.len(), outcome.duplicates.len(), outcome.rejected.len()
+        );
+        outcome
+    }
+}
+
+fn manifest_entry(tx: &QueuedTransaction) -> Option<ManifestEntry> {
+    let id = tx.metadata.get("id").and_then(|v| v.as_str())?.to_string();
+    let signed_tx = tx.metadata.get("signedTx")
+        .or_else(|| tx.transaction.get("signed_tx"))
+        .and_then(|v| v.as_str())?
+        .to_string();
+    Some(ManifestEntry {
+        id,
+        chain_id: tx.chain_id,
+        tx_digest: tx_digest(&signed_tx),
+        signed_tx,
+        priority: tx.priority.clone(),
+        received_at: tx.queued_at,
+    })
+}
@@ -0,0 +1,280 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use ethers::types::{TransactionReceipt, H256, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::sync::RwLock;
+
+const LEDGER_FILE: &str = "gas_spend.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasBudgetConfig {
+    /// Budgets apply to spend within this rolling window
+    pub window_secs: i64,
+    /// Per-chain budget in wei; unset means unlimited
+    pub chain_budget_wei: Option<u128>,
+    pub device_budget_wei: Option<u128>,
+    pub merchant_budget_wei: Option<u128>,
+}
+
+impl Default for GasBudgetConfig {
+    fn default() -> Self {
+        Self {
+            window_secs: 24 * 3600,
+            chain_budget_wei: None,
+            device_budget_wei: None,
+            merchant_budget_wei: None,
+        }
+    }
+}
+
+impl GasBudgetConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let budget = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|wei: &u128| *wei > 0)
+        };
+        Self {
+            window_secs: std::env::var("GAS_BUDGET_WINDOW_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &i64| *s > 0)
+                .unwrap_or(defaults.window_secs),
+            chain_budget_wei: budget("GAS_BUDGET_CHAIN_WEI"),
+            device_budget_wei: budget("GAS_BUDGET_DEVICE_WEI"),
+            merchant_budget_wei: budget("GAS_BUDGET_MERCHANT_WEI"),
+        }
+    }
+}
+
+/// Who a relay-paid transaction is charged to
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpendKey {
+    pub chain_id: u64,
+    pub device_id: Option<String>,
+    /// Lowercased recipient address
+    pub merchant: Option<String>,
+    /// What the relay paid for, e.g. "attestation" or "nonce_repair"
+    pub purpose: String,
+}
+
+impl SpendKey {
+    pub fn new(chain_id: u64, purpose: &str) -> Self {
+        Self {
+            chain_id,
+            purpose: purpose.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasSpend {
+    #[serde(flatten)]
+    pub key: SpendKey,
+    pub tx_hash: H256,
+    pub gas_used: u64,
+    pub wei: u128,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LedgerState {
+    /// Spend inside the budget window, oldest first
+    recent: Vec<GasSpend>,
+    /// All-time spend by "chain:<id>", "device:<id>" and "merchant:<address>"
+    totals: HashMap<String, u128>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpendSummary {
+    pub key: String,
+    /// Decimal wei; strings because totals overflow JSON numbers
+    pub window_wei: String,
+    pub total_wei: String,
+    pub window_transactions: usize,
+    pub budget_wei: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasSpendReport {
+    pub window_start: DateTime<Utc>,
+    pub by_chain: Vec<SpendSummary>,
+    pub by_device: Vec<SpendSummary>,
+    pub by_merchant: Vec<SpendSummary>,
+    pub generated_at: DateTime<Utc>,
+}
+
+fn scopes(key: &SpendKey) -> Vec<String> {
+    let mut scopes = vec![format!("chain:{}", key.chain_id)];
+    if let Some(device_id) = &key.device_id {
+        scopes.push(format!("device:{device_id}"));
+    }
+    if let Some(merchant) = &key.merchant {
+        scopes.push(format!("merchant:{merchant}"));
+    }
+    scopes
+}
+
+/// Gas the relay has paid for out of its own wallets, by chain, device and
+/// merchant, with rolling-window budgets checked before anything is sent.
+///
+/// Kept in `<data_dir>/gas_spend.json` so a restart doesn't reset budgets.
+pub struct GasLedger {
+    config: GasBudgetConfig,
+    path: PathBuf,
+    state: RwLock<LedgerState>,
+}
+
+impl GasLedger {
+    pub fn new(config: GasBudgetConfig, data_dir: &str) -> Result<Self> {
+        let path = PathBuf::from(data_dir).join(LEDGER_FILE);
+        let state = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            LedgerState::default()
+        };
+        Ok(Self {
+            config,
+            path,
+            state: RwLock::new(state),
+        })
+    }
+
+    pub fn config(&self) -> &GasBudgetConfig {
+        &self.config
+    }
+
+    fn window_start(&self) -> DateTime<Utc> {
+        Utc::now() - chrono::Duration::seconds(self.config.window_secs)
+    }
+
+    fn budget_for(&self, scope: &str) -> Option<u128> {
+        match scope.split(':').next() {
+            Some("chain") => self.config.chain_budget_wei,
+            Some("device") => self.config.device_budget_wei,
+            Some("merchant") => self.config.merchant_budget_wei,
+            _ => None,
+        }
+    }
+
+    fn window_spend(state: &LedgerState, scope: &str, since: DateTime<Utc>) -> (u128, usize) {
+        state.recent.iter()
+            .filter(|s| s.at >= since && scopes(&s.key).iter().any(|k| k == scope))
+            .fold((0u128, 0usize), |(wei, count), s| (wei.saturating_add(s.wei), count + 1))
+    }
+
+    /// Refuse if the chain, device or merchant has used up its budget for the window
+    pub async fn check_budget(&self, key: &SpendKey) -> Result<()> {
+        let since = self.window_start();
+        let state = self.state.read().await;
+        for scope in scopes(key) {
+            let Some(budget) = self.budget_for(&scope) else {
+                continue;
+            };
+            let (spent, _) = Self::window_spend(&state, &scope, since);
+            if spent >= budget {
+                return Err(anyhow!("Gas budget exhausted for {}: {} of {} wei spent", scope, spent, budget));
+            }
+        }
+        Ok(())
+    }
+
+    /// Charge a mined relay-paid transaction at its effective gas price
+    pub async fn record_receipt(&self, key: SpendKey, receipt: &TransactionReceipt) -> Result<GasSpend> {
+        let gas_used = receipt.gas_used.unwrap_or_default();
+        let price = receipt.effective_gas_price.unwrap_or_default();
+        self.record(key, receipt.transaction_hash, gas_used, price).await
+    }
+
+    /// Charge a transaction at a known gas limit and price, for sends that aren't awaited
+    pub async fn record(&self, key: SpendKey, tx_hash: H256, gas_used: U256, gas_price: U256) -> Result<GasSpend> {
+        let wei = u128::try_from(gas_used.saturating_mul(gas_price)).unwrap_or(u128::MAX);
+        let spend = GasSpend {
+            key,
+            tx_hash,
+            gas_used: gas_used.low_u64(),
+            wei,
+            at: Utc::now(),
+        };
+
+        let since = self.window_start();
+        let mut state = self.state.write().await;
+        for scope in scopes(&spend.key) {
+            let total = state.totals.entry(scope).or_insert(0);
+            *total = total.saturating_add(wei);
+        }
+        state.recent.retain(|s| s.at >= since);
+        state.recent.push(spend.clone());
+        std::fs::write(&self.path, serde_json::to_string_pretty(&*state)?)?;
+        Ok(spend)
+    }
+
+    pub async fn report(&self) -> GasSpendReport {
+        let since = self.window_start();
+        let state = self.state.read().await;
+        let mut by_chain = Vec::new();
+        let mut by_device = Vec::new();
+        let mut by_merchant = Vec::new();
+
+        let mut keys: Vec<&String> = state.totals.keys().collect();
+        keys.sort();
+        for key in keys {
+            let (window_wei, window_transactions) = Self::window_spend(&state, key, since);
+            let summary = SpendSummary {
+                key: key.splitn(2, ':').nth(1).unwrap_or_default().to_string(),
+                window_wei: window_wei.to_string(),
+                total_wei: state.totals[key].to_string(),
+                window_transactions,
+                budget_wei: self.budget_for(key).map(|b| b.to_string()),
+            };
+            match key.split(':').next() {
+                Some("chain") => by_chain.push(summary),
+                Some("device") => by_device.push(summary),
+                Some("merchant") => by_merchant.push(summary),
+                _ => {}
+            }
+        }
+        // Hot devices first
+        by_device.sort_by_key(|s| std::cmp::Reverse(s.window_wei.parse::<u128>().unwrap_or(0)));
+
+        GasSpendReport {
+            window_start: since,
+            by_chain,
+            by_device,
+            by_merchant,
+            generated_at: Utc::now(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_device_budget_blocks_hot_device_only() {
+        let dir = std::env::temp_dir().join(format!("gas-ledger-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = GasBudgetConfig { device_budget_wei: Some(1_000_000), ..Default::default() };
+        let ledger = GasLedger::new(config, dir.to_str().unwrap()).unwrap();
+
+        let hot = SpendKey { device_id: Some("hot".to_string()), ..SpendKey::new(1, "payment") };
+        let quiet = SpendKey { device_id: Some("quiet".to_string()), ..SpendKey::new(1, "payment") };
+        assert!(ledger.check_budget(&hot).await.is_ok());
+
+        ledger.record(hot.clone(), H256::zero(), U256::from(21_000), U256::from(50)).await.unwrap();
+        assert!(ledger.check_budget(&hot).await.is_err());
+        assert!(ledger.check_budget(&quiet).await.is_ok());
+
+        let report = ledger.report().await;
+        assert_eq!(report.by_chain[0].total_wei, "1050000");
+        assert_eq!(report.by_device[0].key, "hot");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
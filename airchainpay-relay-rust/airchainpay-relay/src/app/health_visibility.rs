@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+
+/// Fields of the detailed health responses that callers without admin
+/// credentials may see. Entries are top-level keys (`status`, `alerts`) or
+/// one level deeper (`components.blockchain`); a key makes everything under
+/// it public. `alerts` and `metrics` also open `/health/alerts` and
+/// `/health/metrics`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthVisibilityConfig {
+    pub public_fields: BTreeSet<String>,
+}
+
+impl Default for HealthVisibilityConfig {
+    fn default() -> Self {
+        Self {
+            public_fields: ["status", "timestamp", "version"].into_iter().map(String::from).collect(),
+        }
+    }
+}
+
+impl HealthVisibilityConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            public_fields: std::env::var("HEALTH_PUBLIC_FIELDS")
+                .map(|v| v.split(',').map(|f| f.trim().to_string()).filter(|f| !f.is_empty()).collect())
+                .unwrap_or(defaults.public_fields),
+        }
+    }
+
+    /// Whether `field` (a top-level key or `parent.child`) is public
+    pub fn is_public(&self, field: &str) -> bool {
+        if self.public_fields.contains(field) {
+            return true;
+        }
+        match field.split_once('.') {
+            Some((parent, _)) => self.public_fields.contains(parent),
+            None => false,
+        }
+    }
+
+    /// Strip everything but the public fields from a health response
+    pub fn redact(&self, response: Value) -> Value {
+        let Value::Object(fields) = response else {
+            return Value::Object(Map::new());
+        };
+        let mut public = Map::new();
+        for (key, value) in fields {
+            if self.public_fields.contains(&key) {
+                public.insert(key, value);
+                continue;
+            }
+            if let Value::Object(children) = value {
+                let visible: Map<String, Value> = children
+                    .into_iter()
+                    .filter(|(child, _)| self.public_fields.contains(&format!("{}.{}", key, child)))
+                    .collect();
+                if !visible.is_empty() {
+                    public.insert(key, Value::Object(visible));
+                }
+            }
+        }
+        Value::Object(public)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_keeps_only_public_fields() {
+        let response = serde_json::json!({
+            "status": "healthy",
+            "version": "1.0.0",
+            "components": {
+                "system": { "memory_usage_bytes": 1024 },
+                "blockchain": { "status": "healthy" },
+            },
+            "alerts": { "total": 3 },
+        });
+
+        let minimal = HealthVisibilityConfig::default().redact(response.clone());
+        assert_eq!(minimal, serde_json::json!({ "status": "healthy", "version": "1.0.0" }));
+
+        let config = HealthVisibilityConfig {
+            public_fields: ["status", "components.blockchain"].into_iter().map(String::from).collect(),
+        };
+        assert!(config.is_public("components.blockchain"));
+        assert!(!config.is_public("components.system"));
+        assert_eq!(
+            config.redact(response),
+            serde_json::json!({ "status": "healthy", "components": { "blockchain": { "status": "healthy" } } })
+        );
+    }
+}
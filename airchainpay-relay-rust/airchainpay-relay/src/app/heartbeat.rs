@@ -0,0 +1,179 @@
+use crate::app::probes::{ProbeState, ReadinessReport};
+use crate::app::transaction_service::TransactionProcessor;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeartbeatConfig {
+    /// Check URLs from Healthchecks.io, Better Uptime or similar; pinging
+    /// is off when empty
+    #[serde(skip_serializing)]
+    pub urls: Vec<String>,
+    pub interval: Duration,
+    pub timeout: Duration,
+    /// Ping `<url>/fail` while the relay is not ready. When off, unready
+    /// relays simply stop pinging and the service's grace period raises
+    /// the alert.
+    pub report_failures: bool,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            interval: Duration::from_secs(60),
+            timeout: Duration::from_secs(10),
+            report_failures: true,
+        }
+    }
+}
+
+impl HeartbeatConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            urls: std::env::var("HEARTBEAT_URLS")
+                .map(|v| v.split(',').map(|u| u.trim().to_string()).filter(|u| !u.is_empty()).collect())
+                .unwrap_or(defaults.urls),
+            interval: std::env::var("HEARTBEAT_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.interval),
+            timeout: std::env::var("HEARTBEAT_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.timeout),
+            report_failures: std::env::var("HEARTBEAT_REPORT_FAILURES")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.report_failures),
+        }
+    }
+}
+
+/// Body sent with every ping, shown in the monitoring service's event log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeartbeatPayload {
+    pub healthy: bool,
+    pub queue_depth: usize,
+    pub storage: bool,
+    pub chains: bool,
+    pub queue: bool,
+    pub version: String,
+    pub sent_at: DateTime<Utc>,
+}
+
+impl HeartbeatPayload {
+    fn new(readiness: &ReadinessReport, queue_depth: usize) -> Self {
+        Self {
+            healthy: readiness.ready,
+            queue_depth,
+            storage: readiness.storage,
+            chains: readiness.chains,
+            queue: readiness.queue,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            sent_at: Utc::now(),
+        }
+    }
+}
+
+/// The URL to ping for a check, or none when an unhealthy relay should
+/// stay silent
+fn ping_url(url: &str, healthy: bool, report_failures: bool) -> Option<String> {
+    match (healthy, report_failures) {
+        (true, _) => Some(url.to_string()),
+        (false, true) => Some(format!("{}/fail", url.trim_end_matches('/'))),
+        (false, false) => None,
+    }
+}
+
+/// Pings external dead-man-switch services on a schedule with the queue
+/// depth and readiness summary, so operators without Prometheus are told
+/// when the relay stops or goes unhealthy
+pub struct HeartbeatPinger {
+    probe_state: Arc<ProbeState>,
+    processor: Arc<TransactionProcessor>,
+    config: HeartbeatConfig,
+    client: reqwest::Client,
+}
+
+impl HeartbeatPinger {
+    pub fn new(probe_state: Arc<ProbeState>, processor: Arc<TransactionProcessor>, config: HeartbeatConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .unwrap_or_default();
+        Self {
+            probe_state,
+            processor,
+            config,
+            client,
+        }
+    }
+
+    pub fn start(pinger: Arc<HeartbeatPinger>) {
+        if pinger.config.urls.is_empty() {
+            return;
+        }
+        log::info!("Sending heartbeats to {} monitor(s) every {}s", pinger.config.urls.len(), pinger.config.interval.as_secs());
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(pinger.config.interval);
+            loop {
+                ticker.tick().await;
+                // Nothing to report until initialization is done
+                if !pinger.probe_state.is_started() {
+                    continue;
+                }
+                pinger.ping().await;
+            }
+        });
+    }
+
+    /// Send one heartbeat to every configured URL
+    pub async fn ping(&self) -> HeartbeatPayload {
+        let readiness = self.probe_state.readiness().await;
+        let payload = HeartbeatPayload::new(&readiness, self.processor.queue_depth().await);
+        let pings = self.config.urls.iter()
+            .filter_map(|url| ping_url(url, payload.healthy, self.config.report_failures))
+            .map(|url| {
+                let payload = &payload;
+                async move {
+                    if let Err(e) = self.send(&url, payload).await {
+                        log::warn!("Heartbeat ping failed: {}", e);
+                    }
+                }
+            });
+        futures::future::join_all(pings).await;
+        payload
+    }
+
+    async fn send(&self, url: &str, payload: &HeartbeatPayload) -> Result<()> {
+        let response = self.client.post(url).json(payload).send().await
+            .map_err(|e| anyhow!("request failed: {}", e.without_url()))?;
+        if !response.status().is_success() {
+            return Err(anyhow!("monitor answered {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ping_url_reports_failures_or_stays_silent() {
+        let url = "https://hc-ping.com/5f6b0d2e";
+        assert_eq!(ping_url(url, true, true).as_deref(), Some(url));
+        assert_eq!(ping_url(url, false, true).as_deref(), Some("https://hc-ping.com/5f6b0d2e/fail"));
+        assert_eq!(ping_url("https://uptime.betterstack.com/api/v1/heartbeat/abc/", false, true).as_deref(),
+            Some("https://uptime.betterstack.com/api/v1/heartbeat/abc/fail"));
+        assert_eq!(ping_url(url, false, false), None);
+    }
+}
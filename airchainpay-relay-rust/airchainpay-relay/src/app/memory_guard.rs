@@ -0,0 +1,250 @@
+use crate::app::transaction_service::TransactionProcessor;
+use crate::infrastructure::ble::session::BleSessionManager;
+use crate::middleware::replay_protection::NonceCache;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use sysinfo::{Pid, ProcessesToUpdate, System};
+use tokio::sync::RwLock;
+use tokio::time::Duration;
+
+const CGROUP_V2_LIMIT: &str = "/sys/fs/cgroup/memory.max";
+const CGROUP_V1_LIMIT: &str = "/sys/fs/cgroup/memory/memory.limit_in_bytes";
+/// cgroup v1 reports "no limit" as a page-aligned i64::MAX
+const CGROUP_V1_UNLIMITED: u64 = 1 << 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryGuardConfig {
+    pub enabled: bool,
+    /// Overrides the detected cgroup/system limit
+    pub limit_bytes: Option<u64>,
+    /// Fraction of the limit at which low-priority work is refused and caches shrink
+    pub soft_ratio: f64,
+    /// Fraction at which only high-priority work is admitted
+    pub hard_ratio: f64,
+    pub check_interval: Duration,
+}
+
+impl Default for MemoryGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            limit_bytes: None,
+            soft_ratio: 0.80,
+            hard_ratio: 0.92,
+            check_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+impl MemoryGuardConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let ratio = |name: &str, default: f64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|r: &f64| *r > 0.0 && *r <= 1.0)
+                .unwrap_or(default)
+        };
+        Self {
+            enabled: std::env::var("MEMORY_GUARD_ENABLED")
+                .map(|v| v != "false")
+                .unwrap_or(defaults.enabled),
+            limit_bytes: std::env::var("MEMORY_LIMIT_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n: &u64| *n > 0),
+            soft_ratio: ratio("MEMORY_SOFT_LIMIT_RATIO", defaults.soft_ratio),
+            hard_ratio: ratio("MEMORY_HARD_LIMIT_RATIO", defaults.hard_ratio),
+            check_interval: std::env::var("MEMORY_CHECK_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.check_interval),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryPressure {
+    Normal = 0,
+    Elevated = 1,
+    Critical = 2,
+}
+
+impl MemoryPressure {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            2 => Self::Critical,
+            1 => Self::Elevated,
+            _ => Self::Normal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryStatus {
+    pub pressure: MemoryPressure,
+    pub rss_bytes: u64,
+    pub limit_bytes: u64,
+    /// "cgroup-v2", "cgroup-v1", "system" or "config"
+    pub limit_source: String,
+    pub usage_ratio: f64,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Container memory limit, falling back to the host's total memory
+fn detect_limit(system: &mut System) -> (u64, &'static str) {
+    if let Ok(value) = std::fs::read_to_string(CGROUP_V2_LIMIT) {
+        if let Ok(limit) = value.trim().parse::<u64>() {
+            return (limit, "cgroup-v2");
+        }
+    }
+    if let Ok(value) = std::fs::read_to_string(CGROUP_V1_LIMIT) {
+        if let Ok(limit) = value.trim().parse::<u64>() {
+            if limit < CGROUP_V1_UNLIMITED {
+                return (limit, "cgroup-v1");
+            }
+        }
+    }
+    system.refresh_memory();
+    (system.total_memory(), "system")
+}
+
+/// Tracks the relay's resident memory against its container limit and sheds
+/// load before the kernel OOM-kills it mid-broadcast.
+///
+/// Elevated pressure refuses low-priority submissions and shrinks in-memory
+/// caches; critical pressure admits only high-priority work and flushes the
+/// queue to disk so nothing accepted is lost if the process dies anyway.
+/// Transactions already being broadcast are always allowed to finish.
+pub struct MemoryGuard {
+    config: MemoryGuardConfig,
+    limit_bytes: u64,
+    limit_source: String,
+    pid: Option<Pid>,
+    system: Mutex<System>,
+    pressure: AtomicU8,
+    status: RwLock<Option<MemoryStatus>>,
+}
+
+impl MemoryGuard {
+    pub fn new(config: MemoryGuardConfig) -> Self {
+        let mut system = System::new();
+        let (limit_bytes, limit_source) = match config.limit_bytes {
+            Some(limit) => (limit, "config"),
+            None => detect_limit(&mut system),
+        };
+        Self {
+            config,
+            limit_bytes,
+            limit_source: limit_source.to_string(),
+            pid: sysinfo::get_current_pid().ok(),
+            system: Mutex::new(system),
+            pressure: AtomicU8::new(MemoryPressure::Normal as u8),
+            status: RwLock::new(None),
+        }
+    }
+
+    pub fn config(&self) -> &MemoryGuardConfig {
+        &self.config
+    }
+
+    pub fn limit_bytes(&self) -> u64 {
+        self.limit_bytes
+    }
+
+    /// Pressure as of the last check; cheap enough for every enqueue
+    pub fn pressure(&self) -> MemoryPressure {
+        MemoryPressure::from_u8(self.pressure.load(Ordering::Relaxed))
+    }
+
+    pub async fn status(&self) -> Option<MemoryStatus> {
+        self.status.read().await.clone()
+    }
+
+    fn rss_bytes(&self) -> u64 {
+        let Some(pid) = self.pid else {
+            return 0;
+        };
+        let mut system = self.system.lock().unwrap();
+        system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+        system.process(pid).map(|p| p.memory()).unwrap_or(0)
+    }
+
+    /// Sample RSS and update the pressure level
+    pub async fn check(&self) -> MemoryStatus {
+        let rss_bytes = self.rss_bytes();
+        let usage_ratio = if self.limit_bytes > 0 {
+            rss_bytes as f64 / self.limit_bytes as f64
+        } else {
+            0.0
+        };
+        let pressure = if usage_ratio >= self.config.hard_ratio {
+            MemoryPressure::Critical
+        } else if usage_ratio >= self.config.soft_ratio {
+            MemoryPressure::Elevated
+        } else {
+            MemoryPressure::Normal
+        };
+        self.pressure.store(pressure as u8, Ordering::Relaxed);
+
+        let status = MemoryStatus {
+            pressure,
+            rss_bytes,
+            limit_bytes: self.limit_bytes,
+            limit_source: self.limit_source.clone(),
+            usage_ratio,
+            checked_at: Utc::now(),
+        };
+        *self.status.write().await = Some(status.clone());
+        status
+    }
+
+    pub fn start(
+        guard: Arc<MemoryGuard>,
+        processor: Arc<TransactionProcessor>,
+        sessions: Arc<BleSessionManager>,
+        nonce_cache: Arc<NonceCache>,
+    ) {
+        if !guard.config.enabled {
+            log::info!("Memory guard disabled: MEMORY_GUARD_ENABLED=false");
+            return;
+        }
+        log::info!(
+            "Memory guard watching RSS against {} MiB ({})",
+            guard.limit_bytes / (1024 * 1024),
+            guard.limit_source
+        );
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(guard.config.check_interval);
+            let mut previous = MemoryPressure::Normal;
+            loop {
+                ticker.tick().await;
+                let status = guard.check().await;
+                if status.pressure != previous {
+                    let used = status.rss_bytes / (1024 * 1024);
+                    let limit = status.limit_bytes / (1024 * 1024);
+                    match status.pressure {
+                        MemoryPressure::Normal => log::info!("Memory pressure cleared ({} of {} MiB)", used, limit),
+                        pressure => log::warn!("Memory pressure {:?}: {} of {} MiB in use", pressure, used, limit),
+                    }
+                }
+                if status.pressure >= MemoryPressure::Elevated {
+                    let sessions_pruned = sessions.prune_expired().await;
+                    let nonces_purged = nonce_cache.purge_expired().await;
+                    log::debug!("Shrank caches under memory pressure: {} sessions, {} nonces", sessions_pruned, nonces_purged);
+                }
+                if status.pressure == MemoryPressure::Critical && previous != MemoryPressure::Critical {
+                    processor.flush_queue().await;
+                }
+                previous = status.pressure;
+            }
+        });
+    }
+}
@@ -1,2 +1,22 @@
 pub mod transaction_service;
 pub mod scheduler;
+pub mod selftest;
+pub mod nonce_monitor;
+pub mod probes;
+pub mod export;
+pub mod config_rollout;
+pub mod federation;
+pub mod attestation;
+pub mod enrollment;
+pub mod memory_guard;
+pub mod reconciliation;
+pub mod gas_accounting;
+pub mod screening;
+pub mod token_registry;
+pub mod simulation;
+pub mod rebroadcast;
+pub mod canary;
+pub mod health_visibility;
+pub mod heartbeat;
+pub mod warmup;
+pub mod replay_guard;
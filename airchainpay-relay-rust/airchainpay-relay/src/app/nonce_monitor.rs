@@ -0,0 +1,328 @@
+use crate::app::gas_accounting::{GasLedger, SpendKey};
+use crate::infrastructure::blockchain::manager::BlockchainManager;
+use crate::infrastructure::monitoring::manager::{AlertSeverity, MonitoringManager};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NonceMonitorConfig {
+    pub relayer_address: Option<String>,
+    #[serde(skip_serializing)]
+    pub relayer_private_key: Option<String>,
+    pub check_interval: Duration,
+    /// How long a pending nonce may sit unmined before it is considered stuck
+    pub stuck_threshold: Duration,
+    /// Gas price bump applied to replacement transactions (percent), unless
+    /// the chain sets its own `replacement_bump_percent`
+    pub gas_bump_percent: u64,
+    pub auto_repair: bool,
+}
+
+impl Default for NonceMonitorConfig {
+    fn default() -> Self {
+        Self {
+            relayer_address: None,
+            relayer_private_key: None,
+            check_interval: Duration::from_secs(30),
+            stuck_threshold: Duration::from_secs(300),
+            gas_bump_percent: 20,
+            auto_repair: false,
+        }
+    }
+}
+
+impl NonceMonitorConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            relayer_address: std::env::var("RELAYER_ADDRESS").ok(),
+            relayer_private_key: std::env::var("RELAYER_PRIVATE_KEY").ok(),
+            check_interval: std::env::var("NONCE_MONITOR_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.check_interval),
+            stuck_threshold: std::env::var("NONCE_STUCK_THRESHOLD_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.stuck_threshold),
+            gas_bump_percent: std::env::var("NONCE_GAS_BUMP_PERCENT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.gas_bump_percent),
+            auto_repair: std::env::var("NONCE_AUTO_REPAIR")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.auto_repair),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NonceState {
+    Healthy,
+    Pending,
+    Stuck,
+    Gapped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NonceStatus {
+    pub chain_id: u64,
+    pub confirmed_nonce: u64,
+    pub pending_nonce: u64,
+    pub local_nonce: Option<u64>,
+    pub missing_nonces: Vec<u64>,
+    pub state: NonceState,
+    pub pending_since: Option<DateTime<Utc>>,
+    pub last_checked: DateTime<Utc>,
+    pub replacements_sent: u64,
+}
+
+/// Watches the sponsored relayer wallet's nonces on every chain and repairs
+/// stuck or gapped sequences with zero-value self-transfers.
+pub struct NonceMonitor {
+    blockchain_manager: Arc<BlockchainManager>,
+    monitoring_manager: Arc<MonitoringManager>,
+    config: NonceMonitorConfig,
+    local_nonces: Arc<RwLock<HashMap<u64, u64>>>,
+    statuses: Arc<RwLock<HashMap<u64, NonceStatus>>>,
+    gas_ledger: Option<Arc<GasLedger>>,
+}
+
+impl NonceMonitor {
+    pub fn new(
+        blockchain_manager: Arc<BlockchainManager>,
+        monitoring_manager: Arc<MonitoringManager>,
+        config: NonceMonitorConfig,
+    ) -> Self {
+        Self {
+            blockchain_manager,
+            monitoring_manager,
+            config,
+            local_nonces: Arc::new(RwLock::new(HashMap::new())),
+            statuses: Arc::new(RwLock::new(HashMap::new())),
+            gas_ledger: None,
+        }
+    }
+
+    /// Charge replacement transactions to the chain's gas budget
+    pub fn with_gas_ledger(mut self, gas_ledger: Arc<GasLedger>) -> Self {
+        self.gas_ledger = Some(gas_ledger);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.relayer_address.is_some()
+    }
+
+    /// Record the highest nonce the relay has used for the relayer wallet
+    pub async fn record_local_nonce(&self, chain_id: u64, nonce: u64) {
+        let mut local = self.local_nonces.write().await;
+        let entry = local.entry(chain_id).or_insert(nonce);
+        if nonce > *entry {
+            *entry = nonce;
+        }
+    }
+
+    pub async fn get_statuses(&self) -> Vec<NonceStatus> {
+        let statuses = self.statuses.read().await;
+        let mut list: Vec<NonceStatus> = statuses.values().cloned().collect();
+        list.sort_by_key(|s| s.chain_id);
+        list
+    }
+
+    pub fn start(monitor: Arc<NonceMonitor>) {
+        if !monitor.is_enabled() {
+            log::info!("Nonce monitor disabled: RELAYER_ADDRESS not set");
+            return;
+        }
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(monitor.config.check_interval);
+            loop {
+                ticker.tick().await;
+                for chain_id in monitor.blockchain_manager.chain_ids() {
+                    if let Err(e) = monitor.check_chain(chain_id).await {
+                        log::warn!("Nonce check failed for chain {}: {}", chain_id, e);
+                    }
+                }
+            }
+        });
+    }
+
+    fn relayer_address(&self) -> Result<Address> {
+        let address = self.config.relayer_address.as_ref()
+            .ok_or_else(|| anyhow!("RELAYER_ADDRESS is not configured"))?;
+        Address::from_str(address).map_err(|e| anyhow!("Invalid relayer address: {}", e))
+    }
+
+    /// Compare confirmed, pending and locally tracked nonces for a chain
+    pub async fn check_chain(&self, chain_id: u64) -> Result<NonceStatus> {
+        let provider = self.blockchain_manager.provider(chain_id)
+            .ok_or_else(|| anyhow!("No provider for chain_id {}", chain_id))?;
+        let address = self.relayer_address()?;
+
+        let confirmed = provider
+            .get_transaction_count(address, Some(BlockNumber::Latest.into()))
+            .await?
+            .as_u64();
+        let pending = provider
+            .get_transaction_count(address, Some(BlockNumber::Pending.into()))
+            .await?
+            .as_u64();
+        let local = self.local_nonces.read().await.get(&chain_id).copied();
+
+        let previous = self.statuses.read().await.get(&chain_id).cloned();
+        let now = Utc::now();
+
+        // Nonces we handed out that never reached the mempool
+        let missing_nonces: Vec<u64> = match local {
+            Some(local) if local >= pending => (pending..=local).collect(),
+            _ => Vec::new(),
+        };
+
+        let pending_since = if pending > confirmed {
+            match &previous {
+                Some(prev) if prev.confirmed_nonce == confirmed && prev.pending_since.is_some() => prev.pending_since,
+                _ => Some(now),
+            }
+        } else {
+            None
+        };
+
+        let stuck = pending_since
+            .map(|since| (now - since).to_std().unwrap_or_default() >= self.config.stuck_threshold)
+            .unwrap_or(false);
+
+        let state = if !missing_nonces.is_empty() {
+            NonceState::Gapped
+        } else if stuck {
+            NonceState::Stuck
+        } else if pending > confirmed {
+            NonceState::Pending
+        } else {
+            NonceState::Healthy
+        };
+
+        let mut status = NonceStatus {
+            chain_id,
+            confirmed_nonce: confirmed,
+            pending_nonce: pending,
+            local_nonce: local,
+            missing_nonces,
+            state: state.clone(),
+            pending_since,
+            last_checked: now,
+            replacements_sent: previous.as_ref().map(|p| p.replacements_sent).unwrap_or(0),
+        };
+
+        if matches!(state, NonceState::Stuck | NonceState::Gapped) {
+            status = self.handle_unhealthy(status).await;
+        }
+
+        self.statuses.write().await.insert(chain_id, status.clone());
+        Ok(status)
+    }
+
+    async fn handle_unhealthy(&self, mut status: NonceStatus) -> NonceStatus {
+        let nonces_to_fill: Vec<u64> = if status.state == NonceState::Stuck {
+            vec![status.confirmed_nonce]
+        } else {
+            status.missing_nonces.clone()
+        };
+
+        let mut metadata = HashMap::new();
+        metadata.insert("chain_id".to_string(), serde_json::json!(status.chain_id));
+        metadata.insert("confirmed_nonce".to_string(), serde_json::json!(status.confirmed_nonce));
+        metadata.insert("pending_nonce".to_string(), serde_json::json!(status.pending_nonce));
+        metadata.insert("nonces".to_string(), serde_json::json!(nonces_to_fill));
+
+        if !self.config.auto_repair || self.config.relayer_private_key.is_none() {
+            self.monitoring_manager.raise_alert(
+                "relayer_nonce_intervention_required",
+                AlertSeverity::Critical,
+                format!(
+                    "Relayer nonce {:?} on chain {} needs manual intervention (nonces {:?})",
+                    status.state, status.chain_id, nonces_to_fill
+                ),
+                metadata,
+            ).await;
+            return status;
+        }
+
+        for nonce in nonces_to_fill {
+            match self.send_replacement(status.chain_id, nonce).await {
+                Ok(tx_hash) => {
+                    status.replacements_sent += 1;
+                    log::warn!(
+                        "Sent replacement transaction {:?} for nonce {} on chain {}",
+                        tx_hash, nonce, status.chain_id
+                    );
+                }
+                Err(e) => {
+                    let mut failure = metadata.clone();
+                    failure.insert("failed_nonce".to_string(), serde_json::json!(nonce));
+                    failure.insert("error".to_string(), serde_json::json!(e.to_string()));
+                    self.monitoring_manager.raise_alert(
+                        "relayer_nonce_repair_failed",
+                        AlertSeverity::Critical,
+                        format!("Failed to replace nonce {} on chain {}: {}", nonce, status.chain_id, e),
+                        failure,
+                    ).await;
+                    break;
+                }
+            }
+        }
+
+        status
+    }
+
+    /// Send a zero-value self-transfer at `nonce` with a bumped gas price
+    async fn send_replacement(&self, chain_id: u64, nonce: u64) -> Result<H256> {
+        let provider = self.blockchain_manager.provider(chain_id)
+            .ok_or_else(|| anyhow!("No provider for chain_id {}", chain_id))?;
+        let private_key = self.config.relayer_private_key.as_ref()
+            .ok_or_else(|| anyhow!("RELAYER_PRIVATE_KEY is not configured"))?;
+        let wallet = LocalWallet::from_str(private_key.trim_start_matches("0x"))
+            .map_err(|e| anyhow!("Invalid relayer private key: {}", e))?
+            .with_chain_id(chain_id);
+        let address = wallet.address();
+
+        let spend_key = SpendKey::new(chain_id, "nonce_repair");
+        if let Some(ledger) = &self.gas_ledger {
+            ledger.check_budget(&spend_key).await?;
+        }
+
+        let gas_price = provider.get_gas_price().await?;
+        let bump_percent = self.blockchain_manager.broadcast_strategy(chain_id).replacement_bump_percent
+            .unwrap_or(self.config.gas_bump_percent);
+        let bumped = gas_price * U256::from(100 + bump_percent) / U256::from(100);
+
+        let client = SignerMiddleware::new(provider, wallet);
+        let tx = TransactionRequest::new()
+            .to(address)
+            .value(U256::zero())
+            .nonce(nonce)
+            .gas(21_000u64)
+            .gas_price(bumped);
+
+        let pending = client.send_transaction(tx, None).await
+            .map_err(|e| anyhow!("Replacement broadcast failed: {}", e))?;
+        let tx_hash = pending.tx_hash();
+        // Not awaited, so charged at the full limit and bid price
+        if let Some(ledger) = &self.gas_ledger {
+            if let Err(e) = ledger.record(spend_key, tx_hash, U256::from(21_000u64), bumped).await {
+                log::warn!("Failed to record replacement gas for {:?}: {}", tx_hash, e);
+            }
+        }
+        Ok(tx_hash)
+    }
+}
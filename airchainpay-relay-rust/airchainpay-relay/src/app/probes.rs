@@ -0,0 +1,95 @@
+use crate::app::transaction_service::TransactionProcessor;
+use crate::infrastructure::blockchain::manager::BlockchainManager;
+use crate::infrastructure::storage::file_storage::Storage;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a chain reachability result is reused between readiness probes
+const CHAIN_CHECK_TTL: Duration = Duration::from_secs(10);
+const CHAIN_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub storage: bool,
+    pub chains: bool,
+    pub queue: bool,
+}
+
+/// State behind the liveness, readiness and startup probes
+pub struct ProbeState {
+    storage: Arc<Storage>,
+    blockchain_manager: Arc<BlockchainManager>,
+    processor: Arc<TransactionProcessor>,
+    started: AtomicBool,
+    last_chain_check: Mutex<Option<(Instant, bool)>>,
+}
+
+impl ProbeState {
+    pub fn new(
+        storage: Arc<Storage>,
+        blockchain_manager: Arc<BlockchainManager>,
+        processor: Arc<TransactionProcessor>,
+    ) -> Self {
+        Self {
+            storage,
+            blockchain_manager,
+            processor,
+            started: AtomicBool::new(false),
+            last_chain_check: Mutex::new(None),
+        }
+    }
+
+    /// Called once initialization (queue restore, workers, monitors) is done
+    pub fn mark_started(&self) {
+        self.started.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.started.load(Ordering::SeqCst)
+    }
+
+    /// Record a chain check made elsewhere, such as during warmup
+    pub fn record_chain_check(&self, reachable: bool) {
+        *self.last_chain_check.lock().unwrap() = Some((Instant::now(), reachable));
+    }
+
+    pub async fn readiness(&self) -> ReadinessReport {
+        let storage = self.storage.check_health().await.is_healthy;
+        let chains = self.any_chain_reachable().await;
+        let queue = !self.processor.is_saturated().await;
+
+        ReadinessReport {
+            ready: self.is_started() && storage && chains && queue,
+            storage,
+            chains,
+            queue,
+        }
+    }
+
+    /// True if at least one configured chain answers, cached briefly so
+    /// frequent probes don't hammer the RPC endpoints
+    async fn any_chain_reachable(&self) -> bool {
+        if let Some((checked_at, reachable)) = *self.last_chain_check.lock().unwrap() {
+            if checked_at.elapsed() < CHAIN_CHECK_TTL {
+                return reachable;
+            }
+        }
+
+        let checks = self.blockchain_manager.chain_ids().into_iter().map(|chain_id| {
+            let blockchain_manager = Arc::clone(&self.blockchain_manager);
+            async move {
+                tokio::time::timeout(CHAIN_CHECK_TIMEOUT, blockchain_manager.check_chain_connectivity(chain_id))
+                    .await
+                    .map(|result| result.is_ok())
+                    .unwrap_or(false)
+            }
+        });
+        let reachable = futures::future::join_all(checks).await.into_iter().any(|ok| ok);
+
+        *self.last_chain_check.lock().unwrap() = Some((Instant::now(), reachable));
+        reachable
+    }
+}
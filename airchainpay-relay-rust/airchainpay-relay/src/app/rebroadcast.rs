@@ -0,0 +1,104 @@
+use anyhow::{Result, anyhow};
+use ethers::core::types::transaction::eip2718::TypedTransaction;
+use ethers::core::types::transaction::eip2930::Eip2930TransactionRequest;
+use ethers::core::types::{Eip1559TransactionRequest, Transaction, TransactionRequest, U256};
+use ethers::core::utils::rlp::{Decodable, Rlp};
+
+/// Decode a 0x-prefixed raw signed transaction
+pub fn decode_signed(signed_tx: &str) -> Result<Transaction> {
+    let bytes = hex::decode(signed_tx.trim_start_matches("0x"))
+        .map_err(|e| anyhow!("Failed to decode hex: {}", e))?;
+    Transaction::decode(&Rlp::new(&bytes))
+        .map_err(|e| anyhow!("Failed to decode transaction: {}", e))
+}
+
+fn bump(value: U256, percent: u64) -> U256 {
+    // Round up so small fees still move by at least one wei
+    (value * U256::from(100 + percent) + U256::from(99)) / U256::from(100)
+}
+
+/// `tx` unsigned again with its fees raised by `bump_percent`, and to at
+/// least `floor` (wei per gas) when the market moved further. Nonce, gas,
+/// recipient, value and calldata are unchanged, so nodes accept it as a
+/// replacement.
+pub fn bumped_request(tx: &Transaction, bump_percent: u64, floor: Option<U256>) -> Result<TypedTransaction> {
+    let floor = floor.unwrap_or_default();
+    let chain_id = tx.chain_id.ok_or_else(|| anyhow!("Transaction has no chain id"))?.as_u64();
+    match tx.transaction_type.map(|t| t.as_u64()) {
+        Some(2) => {
+            let max_fee = tx.max_fee_per_gas.ok_or_else(|| anyhow!("EIP-1559 transaction without max fee"))?;
+            let priority_fee = bump(tx.max_priority_fee_per_gas.unwrap_or_default(), bump_percent);
+            let max_fee = bump(max_fee, bump_percent).max(floor).max(priority_fee);
+            let mut request = Eip1559TransactionRequest::new()
+                .from(tx.from)
+                .value(tx.value)
+                .data(tx.input.clone())
+                .gas(tx.gas)
+                .nonce(tx.nonce)
+                .max_fee_per_gas(max_fee)
+                .max_priority_fee_per_gas(priority_fee)
+                .access_list(tx.access_list.clone().unwrap_or_default())
+                .chain_id(chain_id);
+            request.to = tx.to.map(Into::into);
+            Ok(request.into())
+        }
+        kind => {
+            let gas_price = tx.gas_price.ok_or_else(|| anyhow!("Legacy transaction without gas price"))?;
+            let mut request = TransactionRequest::new()
+                .from(tx.from)
+                .value(tx.value)
+                .data(tx.input.clone())
+                .gas(tx.gas)
+                .nonce(tx.nonce)
+                .gas_price(bump(gas_price, bump_percent).max(floor))
+                .chain_id(chain_id);
+            request.to = tx.to.map(Into::into);
+            Ok(match kind {
+                Some(1) => Eip2930TransactionRequest::new(request, tx.access_list.clone().unwrap_or_default()).into(),
+                _ => request.into(),
+            })
+        }
+    }
+}
+
+/// Most a typed request pays per gas
+pub fn request_fee_cap(request: &TypedTransaction) -> U256 {
+    match request {
+        TypedTransaction::Eip1559(r) => r.max_fee_per_gas.unwrap_or_default(),
+        other => other.gas_price().unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::signers::{LocalWallet, Signer};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_bumped_request_keeps_nonce_and_raises_fees() {
+        let wallet = LocalWallet::from_str("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")
+            .unwrap()
+            .with_chain_id(84532u64);
+        let original: TypedTransaction = Eip1559TransactionRequest::new()
+            .to("0x8ba1f109551bd432803012645ac136ddd64dba72".parse::<ethers::types::Address>().unwrap())
+            .value(1_000u64)
+            .gas(21_000u64)
+            .nonce(7u64)
+            .max_fee_per_gas(2_000_000_000u64)
+            .max_priority_fee_per_gas(100_000_000u64)
+            .chain_id(84532u64)
+            .into();
+        let signature = wallet.sign_transaction_sync(&original).unwrap();
+        let signed_tx = format!("0x{}", hex::encode(original.rlp_signed(&signature)));
+
+        let tx = decode_signed(&signed_tx).unwrap();
+        let bumped = bumped_request(&tx, 10, None).unwrap();
+        assert_eq!(bumped.nonce(), Some(&U256::from(7u64)));
+        assert_eq!(request_fee_cap(&bumped), U256::from(2_200_000_000u64));
+
+        // A fee spike past the bump lifts the cap to the market
+        let spiked = bumped_request(&tx, 10, Some(U256::from(5_000_000_000u64))).unwrap();
+        assert_eq!(request_fee_cap(&spiked), U256::from(5_000_000_000u64));
+    }
+}
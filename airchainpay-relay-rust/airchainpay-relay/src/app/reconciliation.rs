@@ -0,0 +1,304 @@
+use crate::infrastructure::blockchain::manager::BlockchainManager;
+use crate::infrastructure::monitoring::manager::MonitoringManager;
+use crate::infrastructure::storage::file_storage::{Storage, Transaction};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use ethers::providers::Middleware;
+use ethers::types::H256;
+use ethers::utils::keccak256;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationConfig {
+    pub enabled: bool,
+    pub interval: Duration,
+    /// Only transactions created within this many hours are checked
+    pub window_hours: i64,
+    /// How long a completed transaction may be missing from the chain before it counts as dropped
+    pub drop_grace: Duration,
+    /// Scheduled runs only report divergences without rewriting statuses
+    pub dry_run: bool,
+}
+
+impl Default for ReconciliationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: Duration::from_secs(900),
+            window_hours: 24,
+            drop_grace: Duration::from_secs(1800),
+            dry_run: false,
+        }
+    }
+}
+
+impl ReconciliationConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("RECONCILIATION_ENABLED")
+                .map(|v| v != "false")
+                .unwrap_or(defaults.enabled),
+            interval: std::env::var("RECONCILIATION_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.interval),
+            window_hours: std::env::var("RECONCILIATION_WINDOW_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|h: &i64| *h > 0)
+                .unwrap_or(defaults.window_hours),
+            drop_grace: std::env::var("RECONCILIATION_DROP_GRACE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.drop_grace),
+            dry_run: std::env::var("RECONCILIATION_DRY_RUN")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.dry_run),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MismatchKind {
+    /// Stored as not completed, but mined successfully
+    Mined,
+    /// Stored as completed, but the receipt shows a revert
+    Reverted,
+    /// Stored as completed, but the chain no longer knows the transaction
+    Dropped,
+}
+
+impl MismatchKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MismatchKind::Mined => "mined",
+            MismatchKind::Reverted => "reverted",
+            MismatchKind::Dropped => "dropped",
+        }
+    }
+
+    /// Status the stored record is repaired to
+    fn repaired_status(&self) -> &'static str {
+        match self {
+            MismatchKind::Mined => "completed",
+            MismatchKind::Reverted => "failed",
+            MismatchKind::Dropped => "dropped",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mismatch {
+    pub transaction_id: String,
+    pub chain_id: u64,
+    pub tx_hash: H256,
+    pub stored_status: String,
+    pub kind: MismatchKind,
+    pub block_number: Option<u64>,
+    /// Status written back to storage, absent on dry runs or failed repairs
+    pub repaired_status: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    pub dry_run: bool,
+    pub ran_at: DateTime<Utc>,
+    pub window_start: DateTime<Utc>,
+    pub checked: usize,
+    pub mismatches: Vec<Mismatch>,
+    /// Transactions whose chain state could not be fetched
+    pub errors: Vec<String>,
+}
+
+/// Hash the relay expects on chain: the broadcast hash if recorded, otherwise
+/// the hash of the signed payload
+fn expected_hash(transaction: &Transaction) -> Result<H256> {
+    if let Some(hash) = &transaction.tx_hash {
+        return H256::from_str(hash).map_err(|e| anyhow!("Invalid stored tx hash {}: {}", hash, e));
+    }
+    let raw = hex::decode(transaction.signed_tx.trim_start_matches("0x"))?;
+    Ok(H256(keccak256(raw)))
+}
+
+/// Cross-checks stored transaction statuses against on-chain receipts and
+/// repairs records that diverged: payments that were mined after the relay
+/// gave up on them, and "completed" payments that reverted or were dropped
+/// by a reorg.
+pub struct Reconciler {
+    config: ReconciliationConfig,
+    storage: Arc<Storage>,
+    blockchain_manager: Arc<BlockchainManager>,
+    monitoring_manager: Arc<MonitoringManager>,
+    last_report: RwLock<Option<ReconciliationReport>>,
+    run_lock: Mutex<()>,
+}
+
+impl Reconciler {
+    pub fn new(
+        config: ReconciliationConfig,
+        storage: Arc<Storage>,
+        blockchain_manager: Arc<BlockchainManager>,
+        monitoring_manager: Arc<MonitoringManager>,
+    ) -> Self {
+        Self {
+            config,
+            storage,
+            blockchain_manager,
+            monitoring_manager,
+            last_report: RwLock::new(None),
+            run_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn config(&self) -> &ReconciliationConfig {
+        &self.config
+    }
+
+    pub async fn last_report(&self) -> Option<ReconciliationReport> {
+        self.last_report.read().await.clone()
+    }
+
+    pub fn start(reconciler: Arc<Reconciler>) {
+        if !reconciler.config.enabled {
+            log::info!("Transaction reconciliation disabled: RECONCILIATION_ENABLED=false");
+            return;
+        }
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(reconciler.config.interval);
+            // Skip the immediate first tick; the chain needs time to catch up after a restart
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                match reconciler.run(reconciler.config.dry_run).await {
+                    Ok(report) if !report.mismatches.is_empty() => log::warn!(
+                        "Reconciliation found {} divergent of {} transactions{}",
+                        report.mismatches.len(),
+                        report.checked,
+                        if report.dry_run { " (dry run)" } else { "" }
+                    ),
+                    Ok(report) => log::debug!("Reconciliation checked {} transactions, no divergences", report.checked),
+                    Err(e) => log::error!("Reconciliation failed: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Check every transaction in the window once
+    pub async fn run(&self, dry_run: bool) -> Result<ReconciliationReport> {
+        let _guard = self.run_lock.lock().await;
+        let now = Utc::now();
+        let window_start = now - chrono::Duration::hours(self.config.window_hours);
+
+        // Forwarded transactions are owned by the upstream relay
+        let candidates: Vec<Transaction> = self.storage.get_transactions(usize::MAX)
+            .into_iter()
+            .filter(|t| t.timestamp >= window_start && t.status != "forwarded")
+            .collect();
+
+        let mut mismatches = Vec::new();
+        let mut errors = Vec::new();
+        for transaction in &candidates {
+            match self.check(transaction, now).await {
+                Ok(Some(mut mismatch)) => {
+                    self.monitoring_manager.record_reconciliation_mismatch(mismatch.kind.as_str()).await;
+                    if !dry_run {
+                        mismatch.repaired_status = self.repair(transaction, &mismatch);
+                    }
+                    mismatches.push(mismatch);
+                }
+                Ok(None) => {}
+                Err(e) => errors.push(format!("{}: {}", transaction.id, e)),
+            }
+        }
+        self.monitoring_manager.record_reconciliation_run(candidates.len()).await;
+
+        let report = ReconciliationReport {
+            dry_run,
+            ran_at: now,
+            window_start,
+            checked: candidates.len(),
+            mismatches,
+            errors,
+        };
+        *self.last_report.write().await = Some(report.clone());
+        Ok(report)
+    }
+
+    async fn check(&self, transaction: &Transaction, now: DateTime<Utc>) -> Result<Option<Mismatch>> {
+        let provider = self.blockchain_manager.provider(transaction.chain_id)
+            .ok_or_else(|| anyhow!("No provider for chain_id {}", transaction.chain_id))?;
+        let tx_hash = expected_hash(transaction)?;
+        let completed = transaction.status == "completed";
+
+        let mismatch = |kind, block_number| Mismatch {
+            transaction_id: transaction.id.clone(),
+            chain_id: transaction.chain_id,
+            tx_hash,
+            stored_status: transaction.status.clone(),
+            kind,
+            block_number,
+            repaired_status: None,
+        };
+
+        if let Some(receipt) = provider.get_transaction_receipt(tx_hash).await? {
+            let block_number = receipt.block_number.map(|b| b.as_u64());
+            let succeeded = receipt.status.map(|s| s.as_u64() == 1).unwrap_or(false);
+            return Ok(match (completed, succeeded) {
+                (true, false) => Some(mismatch(MismatchKind::Reverted, block_number)),
+                (false, true) => Some(mismatch(MismatchKind::Mined, block_number)),
+                _ => None,
+            });
+        }
+
+        // Still in the mempool, or never expected on chain
+        if !completed || provider.get_transaction(tx_hash).await?.is_some() {
+            return Ok(None);
+        }
+        let completed_at = transaction.transitions().last().map(|t| t.at).unwrap_or(transaction.timestamp);
+        let missing_for = (now - completed_at).to_std().unwrap_or_default();
+        if missing_for < self.config.drop_grace {
+            return Ok(None);
+        }
+        Ok(Some(mismatch(MismatchKind::Dropped, None)))
+    }
+
+    fn repair(&self, transaction: &Transaction, mismatch: &Mismatch) -> Option<String> {
+        let status = mismatch.kind.repaired_status();
+        let tx_hash = format!("{:?}", mismatch.tx_hash);
+        let (tx_hash, error_details) = match mismatch.kind {
+            MismatchKind::Mined => (Some(tx_hash), None),
+            MismatchKind::Reverted => (
+                Some(tx_hash),
+                Some(format!("Reverted on chain in block {}", mismatch.block_number.unwrap_or_default())),
+            ),
+            MismatchKind::Dropped => (
+                transaction.tx_hash.clone(),
+                Some("No longer found on chain; dropped or reorged out".to_string()),
+            ),
+        };
+
+        match self.storage.update_transaction_status_with_error(&transaction.id, status, tx_hash, error_details) {
+            Ok(()) => {
+                log::warn!(
+                    "Reconciled transaction {} on chain {}: {} -> {} ({})",
+                    transaction.id, transaction.chain_id, transaction.status, status, mismatch.kind.as_str()
+                );
+                Some(status.to_string())
+            }
+            Err(e) => {
+                log::error!("Failed to repair transaction {}: {}", transaction.id, e);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use crate::infrastructure::storage::file_storage::{ReplayLogEntry, SeenTransaction, Storage};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use ethers::utils::keccak256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Duplicate detection for raw signed transactions. Distinct from the
+/// request-nonce check in `middleware::replay_protection`: this one keys on
+/// the transaction bytes, so a payment replayed through a different
+/// endpoint, or after a restart, is still caught.
+#[derive(Debug, Clone)]
+pub struct TxReplayConfig {
+    pub enabled: bool,
+    /// How long an accepted hash is remembered
+    pub retention: Duration,
+    /// Oldest hashes are forgotten first beyond this many
+    pub max_entries: usize,
+}
+
+impl Default for TxReplayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            retention: Duration::hours(168),
+            max_entries: 100_000,
+        }
+    }
+}
+
+impl TxReplayConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("TX_REPLAY_PROTECTION_ENABLED")
+                .map(|v| v != "false")
+                .unwrap_or(defaults.enabled),
+            retention: std::env::var("TX_REPLAY_RETENTION_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|h: &i64| *h > 0)
+                .map(Duration::hours)
+                .unwrap_or(defaults.retention),
+            max_entries: std::env::var("TX_REPLAY_MAX_ENTRIES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n: &usize| *n > 0)
+                .unwrap_or(defaults.max_entries),
+        }
+    }
+}
+
+/// A raw transaction submitted again after it was already accepted
+#[derive(Debug, Clone)]
+pub struct DuplicateTransaction {
+    pub tx_hash: String,
+    pub original: SeenTransaction,
+}
+
+impl std::fmt::Display for DuplicateTransaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Transaction {} was already submitted via {}", self.tx_hash, self.original.source)?;
+        if let Some(id) = &self.original.transaction_id {
+            write!(f, " as {}", id)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DuplicateTransaction {}
+
+/// Hash of a raw transaction as the chain computes it; input that is not
+/// hex is hashed as given so it still deduplicates
+pub fn tx_hash(signed_tx: &str) -> String {
+    let trimmed = signed_tx.trim();
+    let hash = match hex::decode(trimmed.trim_start_matches("0x")) {
+        Ok(raw) => keccak256(raw),
+        Err(_) => keccak256(trimmed.as_bytes()),
+    };
+    format!("0x{}", hex::encode(hash))
+}
+
+/// Log entries written before the snapshot is rewritten and the log emptied
+const COMPACT_AFTER_ENTRIES: usize = 1000;
+
+struct ReplayState {
+    seen: HashMap<String, SeenTransaction>,
+    /// Entries in the log since the last snapshot
+    logged: usize,
+}
+
+/// Hashes of every accepted raw transaction, kept in `Storage` so they
+/// survive restarts. Each change is appended to a log; the full map is
+/// only rewritten every `COMPACT_AFTER_ENTRIES` changes and on `flush`.
+pub struct TxReplayGuard {
+    config: TxReplayConfig,
+    storage: Arc<Storage>,
+    state: Mutex<ReplayState>,
+}
+
+impl TxReplayGuard {
+    pub fn new(config: TxReplayConfig, storage: Arc<Storage>) -> Result<Self> {
+        let mut seen = storage.load_replay_hashes()?;
+        prune(&mut seen, &config, Utc::now());
+        // Start from a fresh snapshot so the log only holds new changes
+        storage.save_replay_hashes(&seen)?;
+        Ok(Self {
+            config,
+            storage,
+            state: Mutex::new(ReplayState { seen, logged: 0 }),
+        })
+    }
+
+    pub fn config(&self) -> &TxReplayConfig {
+        &self.config
+    }
+
+    /// Record `signed_tx` as accepted, or fail if it already was. Keyed on
+    /// the transaction hash alone: `transaction_id` is only recorded, since
+    /// ids are public and some paths take them from the request body.
+    /// Callers that hand an admitted transaction on to the queue mark it
+    /// `replay_checked` rather than admitting it twice.
+    pub async fn admit(&self, signed_tx: &str, chain_id: u64, transaction_id: Option<&str>, source: &str) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        let hash = tx_hash(signed_tx);
+        let now = Utc::now();
+        let mut state = self.state.lock().await;
+        if let Some(original) = state.seen.get(&hash) {
+            if now - original.first_seen < self.config.retention {
+                log::warn!("Rejected replay of transaction {} via {} (first seen via {})", hash, source, original.source);
+                return Err(DuplicateTransaction { tx_hash: hash, original: original.clone() }.into());
+            }
+        }
+        let entry = SeenTransaction {
+            chain_id,
+            transaction_id: transaction_id.map(str::to_string),
+            source: source.to_string(),
+            first_seen: now,
+        };
+        state.seen.insert(hash.clone(), entry.clone());
+        prune(&mut state.seen, &self.config, now);
+        self.record(&mut state, ReplayLogEntry::Admit { tx_hash: hash, seen: entry })
+    }
+
+    /// Forget `signed_tx` after its submission was refused or dropped, so
+    /// the client may send it again
+    pub async fn release(&self, signed_tx: &str) {
+        if !self.config.enabled {
+            return;
+        }
+        let hash = tx_hash(signed_tx);
+        let mut state = self.state.lock().await;
+        if state.seen.remove(&hash).is_some() {
+            if let Err(e) = self.record(&mut state, ReplayLogEntry::Release { tx_hash: hash }) {
+                log::error!("Failed to persist replay hashes: {}", e);
+            }
+        }
+    }
+
+    /// Rewrite the snapshot and empty the log, e.g. on shutdown
+    pub async fn flush(&self) -> Result<()> {
+        let mut state = self.state.lock().await;
+        self.storage.save_replay_hashes(&state.seen)?;
+        state.logged = 0;
+        Ok(())
+    }
+
+    pub async fn len(&self) -> usize {
+        self.state.lock().await.seen.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.state.lock().await.seen.is_empty()
+    }
+
+    /// Append `entry` to the log, compacting once enough have built up.
+    /// Called with the state locked so entries land in order.
+    fn record(&self, state: &mut ReplayState, entry: ReplayLogEntry) -> Result<()> {
+        self.storage.append_replay_log(&entry)?;
+        state.logged += 1;
+        if state.logged >= COMPACT_AFTER_ENTRIES {
+            self.storage.save_replay_hashes(&state.seen)?;
+            state.logged = 0;
+        }
+        Ok(())
+    }
+}
+
+/// Drop expired hashes, then the oldest ones beyond `max_entries`
+fn prune(seen: &mut HashMap<String, SeenTransaction>, config: &TxReplayConfig, now: DateTime<Utc>) {
+    seen.retain(|_, entry| now - entry.first_seen < config.retention);
+    if seen.len() > config.max_entries {
+        let mut by_age: Vec<(String, DateTime<Utc>)> = seen.iter().map(|(hash, entry)| (hash.clone(), entry.first_seen)).collect();
+        by_age.sort_by_key(|(_, first_seen)| *first_seen);
+        let excess = seen.len() - config.max_entries;
+        for (hash, _) in by_age.into_iter().take(excess) {
+            seen.remove(&hash);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::storage::json_backend::JsonFileBackend;
+
+    #[tokio::test]
+    async fn test_duplicate_rejected_across_endpoints_and_restarts() {
+        let dir = std::env::temp_dir().join(format!("replay-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let data_dir = dir.to_str().unwrap().to_string();
+        let open_storage = || Arc::new(Storage::with_backend(data_dir.clone(), Box::new(JsonFileBackend::open(&data_dir).unwrap())).unwrap());
+        let guard = TxReplayGuard::new(TxReplayConfig::default(), open_storage()).unwrap();
+
+        let raw = "0xf86b0285012a05f20082520894742d35cc6634c0532925a3b8d4c9db96c4b4d8b6";
+        guard.admit(raw, 84532, Some("tx-1"), "send_tx").await.unwrap();
+        // Knowing the original id does not make a resubmission idempotent
+        assert!(guard.admit(raw, 84532, Some("tx-1"), "queue").await.is_err());
+        let err = guard.admit(&raw.to_uppercase().replace("0X", "0x"), 84532, Some("tx-2"), "ble").await.unwrap_err();
+        assert_eq!(err.downcast_ref::<DuplicateTransaction>().unwrap().original.source, "send_tx");
+
+        // Remembered after a restart
+        let reloaded = TxReplayGuard::new(TxReplayConfig::default(), open_storage()).unwrap();
+        assert!(reloaded.admit(raw, 84532, None, "send_tx").await.is_err());
+
+        // Released submissions may be retried
+        reloaded.release(raw).await;
+        assert!(reloaded.admit(raw, 84532, Some("tx-3"), "send_tx").await.is_ok());
+
+        // Changes since the last snapshot are appended, not rewritten
+        let snapshot = std::fs::read_to_string(dir.join("replay_hashes.json")).unwrap();
+        assert!(!snapshot.contains("tx-3"));
+        let log = std::fs::read_to_string(dir.join("replay_hashes.log")).unwrap();
+        assert_eq!(log.lines().count(), 2);
+        let restarted = TxReplayGuard::new(TxReplayConfig::default(), open_storage()).unwrap();
+        assert!(restarted.admit(raw, 84532, Some("tx-4"), "ble").await.is_err());
+        restarted.flush().await.unwrap();
+        assert!(std::fs::read_to_string(dir.join("replay_hashes.json")).unwrap().contains("tx-3"));
+        assert!(std::fs::read_to_string(dir.join("replay_hashes.log")).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
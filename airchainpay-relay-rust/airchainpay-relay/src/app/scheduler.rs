@@ -1 +1,188 @@
-// Scheduler module for application-level scheduling logic 
\ No newline at end of file
+// Scheduler module for application-level scheduling logic
+use crate::infrastructure::storage::file_storage::Storage;
+use crate::utils::audit::{AuditLogger, AuditPolicy};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Devices not seen for this many days are removed
+    pub device_inactive_days: i64,
+    /// Finished transactions older than this many days are removed
+    pub transaction_retention_days: i64,
+    pub interval: Duration,
+    /// Scheduled runs only report what they would remove
+    pub dry_run: bool,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            device_inactive_days: 90,
+            transaction_retention_days: 30,
+            interval: Duration::from_secs(24 * 3600),
+            dry_run: false,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            device_inactive_days: std::env::var("DEVICE_INACTIVE_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.device_inactive_days),
+            transaction_retention_days: std::env::var("TRANSACTION_RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.transaction_retention_days),
+            interval: std::env::var("PRUNE_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.interval),
+            dry_run: std::env::var("PRUNE_DRY_RUN")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.dry_run),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PruneReport {
+    pub dry_run: bool,
+    pub ran_at: DateTime<Utc>,
+    pub device_cutoff: DateTime<Utc>,
+    pub transaction_cutoff: DateTime<Utc>,
+    pub devices: Vec<String>,
+    /// Moved to the transaction archive rather than deleted
+    pub transactions: Vec<String>,
+    /// Compressed copy of the removed devices, absent on dry runs
+    pub archive_path: Option<String>,
+    /// Audit events past their severity's retention
+    pub audit_events: usize,
+}
+
+/// Periodically archives and removes stale devices and expired transactions
+pub struct DataPruner {
+    storage: Arc<Storage>,
+    audit_logger: Arc<AuditLogger>,
+    policy: RetentionPolicy,
+    last_report: RwLock<Option<PruneReport>>,
+}
+
+impl DataPruner {
+    pub fn new(storage: Arc<Storage>, audit_logger: Arc<AuditLogger>, policy: RetentionPolicy) -> Self {
+        Self {
+            storage,
+            audit_logger,
+            policy,
+            last_report: RwLock::new(None),
+        }
+    }
+
+    pub fn policy(&self) -> &RetentionPolicy {
+        &self.policy
+    }
+
+    pub fn audit_policy(&self) -> &AuditPolicy {
+        self.audit_logger.policy()
+    }
+
+    pub async fn last_report(&self) -> Option<PruneReport> {
+        self.last_report.read().await.clone()
+    }
+
+    pub fn start(pruner: Arc<DataPruner>) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(pruner.policy.interval);
+            // The first tick fires immediately; skip it so startup isn't slowed by a purge
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                match pruner.run(pruner.policy.dry_run).await {
+                    Ok(report) => log::info!(
+                        "Data pruning {}: {} devices, {} transactions, {} audit events",
+                        if report.dry_run { "dry run" } else { "completed" },
+                        report.devices.len(),
+                        report.transactions.len(),
+                        report.audit_events
+                    ),
+                    Err(e) => log::error!("Data pruning failed: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Run one pruning pass. Devices are written to a gzip file under
+    /// `<data_dir>/archive` before removal; transactions go to the
+    /// searchable transaction archive.
+    pub async fn run(&self, dry_run: bool) -> Result<PruneReport> {
+        let now = Utc::now();
+        let device_cutoff = now - chrono::Duration::days(self.policy.device_inactive_days);
+        let transaction_cutoff = now - chrono::Duration::days(self.policy.transaction_retention_days);
+
+        // Archive first so a failed write never loses data
+        let stale_devices = self.storage.prune_devices(device_cutoff, true)?;
+        let archive_path = if dry_run || stale_devices.is_empty() {
+            None
+        } else {
+            let archive = serde_json::json!({
+                "archived_at": now.to_rfc3339(),
+                "devices": stale_devices,
+            });
+            let path = self.write_archive(&archive, now)?;
+            self.storage.prune_devices(device_cutoff, false)?;
+            Some(path)
+        };
+        let expired_transactions = self.storage.prune_transactions(transaction_cutoff, dry_run)?;
+        let audit_events = self.audit_logger.prune_expired(dry_run).await
+            .map_err(|e| anyhow::anyhow!("Audit retention failed: {}", e))?;
+
+        let report = PruneReport {
+            dry_run,
+            ran_at: now,
+            device_cutoff,
+            transaction_cutoff,
+            devices: stale_devices.iter().map(|d| d.id.clone()).collect(),
+            transactions: expired_transactions.iter().map(|t| t.id.clone()).collect(),
+            archive_path,
+            audit_events,
+        };
+
+        let mut details = HashMap::new();
+        details.insert("archive_path".to_string(), serde_json::json!(report.archive_path));
+        details.insert("cutoff".to_string(), serde_json::json!(device_cutoff.to_rfc3339()));
+        if let Err(e) = self.audit_logger.log_data_purge("devices", &report.devices, dry_run, details.clone()).await {
+            log::warn!("Failed to audit device purge: {}", e);
+        }
+        details.insert("cutoff".to_string(), serde_json::json!(transaction_cutoff.to_rfc3339()));
+        if let Err(e) = self.audit_logger.log_data_purge("transactions", &report.transactions, dry_run, details).await {
+            log::warn!("Failed to audit transaction purge: {}", e);
+        }
+
+        *self.last_report.write().await = Some(report.clone());
+        Ok(report)
+    }
+
+    fn write_archive(&self, archive: &serde_json::Value, now: DateTime<Utc>) -> Result<String> {
+        let archive_dir = format!("{}/archive", self.storage.data_dir());
+        std::fs::create_dir_all(&archive_dir)?;
+        let path = format!("{}/prune_{}.json.gz", archive_dir, now.format("%Y%m%d_%H%M%S"));
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(serde_json::to_string(archive)?.as_bytes())?;
+        std::fs::write(&path, encoder.finish()?)?;
+        Ok(path)
+    }
+}
@@ -0,0 +1,384 @@
+use crate::app::token_registry::decode_erc20_transfer;
+use crate::utils::audit::AuditLogger;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use ethers::core::types::Transaction;
+use ethers::core::utils::rlp::{Decodable, Rlp};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreeningConfig {
+    /// File of blocked addresses, one per line; `#` starts a comment
+    pub list_path: Option<String>,
+    /// Screening provider endpoint for `HttpScreener`
+    pub provider_url: Option<String>,
+    pub provider_api_key: Option<String>,
+    pub provider_timeout: Duration,
+    /// Refuse transactions when the screener cannot give an answer
+    pub fail_closed: bool,
+}
+
+impl Default for ScreeningConfig {
+    fn default() -> Self {
+        Self {
+            list_path: None,
+            provider_url: None,
+            provider_api_key: None,
+            provider_timeout: Duration::from_secs(5),
+            fail_closed: true,
+        }
+    }
+}
+
+impl ScreeningConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            list_path: std::env::var("SANCTIONS_LIST_PATH")
+                .ok()
+                .filter(|v| !v.is_empty()),
+            provider_url: std::env::var("SCREENING_PROVIDER_URL")
+                .ok()
+                .map(|v| v.trim_end_matches('/').to_string())
+                .filter(|v| !v.is_empty()),
+            provider_api_key: std::env::var("SCREENING_PROVIDER_API_KEY")
+                .ok()
+                .filter(|v| !v.is_empty()),
+            provider_timeout: std::env::var("SCREENING_PROVIDER_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.provider_timeout),
+            fail_closed: std::env::var("SCREENING_FAIL_CLOSED")
+                .map(|v| v != "false")
+                .unwrap_or(defaults.fail_closed),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.list_path.is_some() || self.provider_url.is_some()
+    }
+}
+
+/// Parties to a transaction, as recovered from the signed payload
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScreeningSubject {
+    pub chain_id: u64,
+    pub tx_id: String,
+    /// Signer recovered from the signature
+    pub sender: Option<String>,
+    /// The transaction's `to`: the payee, or the token contract
+    pub recipient: Option<String>,
+    /// Payee of an ERC-20 `transfer` or `transferFrom`
+    pub token_recipient: Option<String>,
+}
+
+impl ScreeningSubject {
+    pub fn from_signed_tx(chain_id: u64, tx_id: &str, signed_tx: &str) -> Result<Self> {
+        let bytes = hex::decode(signed_tx.trim_start_matches("0x"))
+            .map_err(|e| anyhow!("Failed to decode hex: {}", e))?;
+        let tx = Transaction::decode(&Rlp::new(&bytes))
+            .map_err(|e| anyhow!("Failed to decode transaction: {}", e))?;
+        let sender = tx.recover_from()
+            .map_err(|e| anyhow!("Failed to recover sender: {}", e))?;
+        Ok(Self {
+            chain_id,
+            tx_id: tx_id.to_string(),
+            sender: Some(format!("0x{:x}", sender)),
+            recipient: tx.to.map(|to| format!("0x{:x}", to)),
+            token_recipient: token_recipient(&tx.input),
+        })
+    }
+
+    /// Every address the transaction involves, lowercase and deduplicated
+    pub fn addresses(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        [&self.sender, &self.recipient, &self.token_recipient]
+            .into_iter()
+            .flatten()
+            .map(|a| a.to_lowercase())
+            .filter(|a| seen.insert(a.clone()))
+            .collect()
+    }
+}
+
+/// Payee encoded in ERC-20 transfer calldata
+fn token_recipient(input: &[u8]) -> Option<String> {
+    decode_erc20_transfer(input).map(|(payee, _)| format!("0x{:x}", payee))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum ScreeningDecision {
+    Allow,
+    Block {
+        /// Addresses the screener flagged
+        matched: Vec<String>,
+        reason: String,
+    },
+}
+
+/// A sanctions or risk screening source consulted before broadcast
+#[async_trait]
+pub trait Screener: Send + Sync {
+    fn name(&self) -> &str;
+    async fn screen(&self, subject: &ScreeningSubject) -> Result<ScreeningDecision>;
+}
+
+/// Blocks addresses on a fixed list, such as an export of the OFAC SDN
+/// list's digital currency addresses
+pub struct StaticListScreener {
+    blocked: HashSet<String>,
+}
+
+impl StaticListScreener {
+    pub fn new<I, S>(addresses: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            blocked: addresses.into_iter().map(|a| a.as_ref().trim().to_lowercase()).collect(),
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read sanctions list {}: {}", path, e))?;
+        Ok(Self::new(
+            contents.lines()
+                .map(|line| line.split('#').next().unwrap_or("").trim())
+                .filter(|line| !line.is_empty()),
+        ))
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocked.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocked.is_empty()
+    }
+}
+
+#[async_trait]
+impl Screener for StaticListScreener {
+    fn name(&self) -> &str {
+        "static_list"
+    }
+
+    async fn screen(&self, subject: &ScreeningSubject) -> Result<ScreeningDecision> {
+        let matched: Vec<String> = subject.addresses().into_iter().filter(|a| self.blocked.contains(a)).collect();
+        if matched.is_empty() {
+            return Ok(ScreeningDecision::Allow);
+        }
+        Ok(ScreeningDecision::Block {
+            reason: "Address on sanctions list".to_string(),
+            matched,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ProviderResponse {
+    blocked: bool,
+    #[serde(default)]
+    matches: Vec<String>,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Asks a screening provider over HTTP. The provider receives
+/// `{"chain_id", "addresses"}` and answers `{"blocked", "matches", "reason"}`;
+/// vendor APIs with other shapes sit behind a small proxy.
+pub struct HttpScreener {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+}
+
+impl HttpScreener {
+    pub fn new(url: String, api_key: Option<String>, timeout: Duration) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+        Self { client, url, api_key }
+    }
+}
+
+#[async_trait]
+impl Screener for HttpScreener {
+    fn name(&self) -> &str {
+        "http_provider"
+    }
+
+    async fn screen(&self, subject: &ScreeningSubject) -> Result<ScreeningDecision> {
+        let mut request = self.client.post(&self.url).json(&serde_json::json!({
+            "chain_id": subject.chain_id,
+            "addresses": subject.addresses(),
+        }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request.send().await
+            .map_err(|e| anyhow!("Screening provider unreachable: {}", e))?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Screening provider returned {}", response.status()));
+        }
+        let body: ProviderResponse = response.json().await
+            .map_err(|e| anyhow!("Invalid screening provider response: {}", e))?;
+        if !body.blocked {
+            return Ok(ScreeningDecision::Allow);
+        }
+        Ok(ScreeningDecision::Block {
+            matched: body.matches,
+            reason: body.reason.unwrap_or_else(|| "Flagged by screening provider".to_string()),
+        })
+    }
+}
+
+/// Runs every configured screener before broadcast and audits each decision
+pub struct ScreeningService {
+    screeners: Vec<Arc<dyn Screener>>,
+    audit_logger: Arc<AuditLogger>,
+    fail_closed: bool,
+}
+
+impl ScreeningService {
+    pub fn new(audit_logger: Arc<AuditLogger>, fail_closed: bool) -> Self {
+        Self {
+            screeners: Vec::new(),
+            audit_logger,
+            fail_closed,
+        }
+    }
+
+    pub fn with_screener(mut self, screener: Arc<dyn Screener>) -> Self {
+        self.screeners.push(screener);
+        self
+    }
+
+    /// Build the screeners named in `config`; `None` when screening is off
+    pub fn from_config(config: &ScreeningConfig, audit_logger: Arc<AuditLogger>) -> Result<Option<Self>> {
+        if !config.is_enabled() {
+            return Ok(None);
+        }
+        let mut service = Self::new(audit_logger, config.fail_closed);
+        if let Some(path) = &config.list_path {
+            let list = StaticListScreener::from_file(path)?;
+            log::info!("Loaded {} sanctioned addresses from {}", list.len(), path);
+            service = service.with_screener(Arc::new(list));
+        }
+        if let Some(url) = &config.provider_url {
+            service = service.with_screener(Arc::new(HttpScreener::new(
+                url.clone(),
+                config.provider_api_key.clone(),
+                config.provider_timeout,
+            )));
+        }
+        Ok(Some(service))
+    }
+
+    /// Screen a signed transaction. Fails if any screener blocks it, or if a
+    /// screener errors while screening fails closed.
+    pub async fn check_transaction(&self, chain_id: u64, tx_id: &str, signed_tx: &str) -> Result<()> {
+        let subject = match ScreeningSubject::from_signed_tx(chain_id, tx_id, signed_tx) {
+            Ok(subject) => subject,
+            Err(e) => {
+                self.audit(&ScreeningSubject { chain_id, tx_id: tx_id.to_string(), ..Default::default() }, "decode", "error", &[], Some(e.to_string())).await;
+                return if self.fail_closed { Err(anyhow!("Screening failed: {}", e)) } else { Ok(()) };
+            }
+        };
+
+        for screener in &self.screeners {
+            match screener.screen(&subject).await {
+                Ok(ScreeningDecision::Allow) => {
+                    self.audit(&subject, screener.name(), "allowed", &[], None).await;
+                }
+                Ok(ScreeningDecision::Block { matched, reason }) => {
+                    self.audit(&subject, screener.name(), "blocked", &matched, Some(reason.clone())).await;
+                    return Err(anyhow!("Blocked by sanctions screening: {}", reason));
+                }
+                Err(e) => {
+                    self.audit(&subject, screener.name(), "error", &[], Some(e.to_string())).await;
+                    if self.fail_closed {
+                        return Err(anyhow!("Screening unavailable ({}): {}", screener.name(), e));
+                    }
+                    log::warn!("Screener {} failed, allowing {}: {}", screener.name(), tx_id, e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn audit(&self, subject: &ScreeningSubject, screener: &str, outcome: &str, matched: &[String], reason: Option<String>) {
+        let logged = self.audit_logger.log_screening(
+            &subject.tx_id,
+            subject.chain_id,
+            subject.sender.clone(),
+            subject.addresses(),
+            screener,
+            outcome,
+            matched.to_vec(),
+            reason,
+        ).await;
+        if let Err(e) = logged {
+            log::error!("Failed to audit screening decision for {}: {}", subject.tx_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::token_registry::ERC20_TRANSFER;
+    use ethers::core::types::transaction::eip2718::TypedTransaction;
+    use ethers::core::types::{Bytes, TransactionRequest};
+    use ethers::signers::{LocalWallet, Signer};
+    use std::str::FromStr;
+
+    #[tokio::test]
+    async fn test_static_list_blocks_token_recipient() {
+        let wallet = LocalWallet::from_str("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")
+            .unwrap()
+            .with_chain_id(84532u64);
+        let payee = "0x8ba1f109551bd432803012645ac136ddd64dba72";
+        let mut calldata = ERC20_TRANSFER.to_vec();
+        calldata.extend_from_slice(&[0u8; 12]);
+        calldata.extend_from_slice(&hex::decode(&payee[2..]).unwrap());
+        calldata.extend_from_slice(&[0u8; 32]);
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to("0x036CbD53842c5426634e7929541eC2318f3dCF7e".parse::<ethers::types::Address>().unwrap())
+            .data(Bytes::from(calldata))
+            .gas(60_000)
+            .gas_price(1_000_000_000u64)
+            .nonce(0)
+            .chain_id(84532u64)
+            .into();
+        let signature = wallet.sign_transaction_sync(&tx).unwrap();
+        let signed_tx = format!("0x{}", hex::encode(tx.rlp_signed(&signature)));
+
+        let subject = ScreeningSubject::from_signed_tx(84532, "tx-1", &signed_tx).unwrap();
+        assert_eq!(subject.sender, Some(format!("0x{:x}", wallet.address())));
+        assert_eq!(subject.token_recipient.as_deref(), Some(payee));
+
+        let audit_logger = Arc::new(AuditLogger::new("test_screening_audit.log".to_string(), 100));
+        let service = ScreeningService::new(Arc::clone(&audit_logger), true)
+            .with_screener(Arc::new(StaticListScreener::new(["0x8BA1F109551BD432803012645AC136DDD64DBA72"])));
+        let err = service.check_transaction(84532, "tx-1", &signed_tx).await.unwrap_err();
+        assert!(err.to_string().contains("sanctions"));
+
+        let clean = ScreeningService::new(Arc::clone(&audit_logger), true)
+            .with_screener(Arc::new(StaticListScreener::new(Vec::<String>::new())));
+        assert!(clean.check_transaction(84532, "tx-2", &signed_tx).await.is_ok());
+
+        let events = audit_logger.get_events(None).await;
+        assert_eq!(events.len(), 2);
+        let _ = std::fs::remove_file("test_screening_audit.log");
+    }
+}
@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use chrono::{DateTime, Utc};
+use crate::infrastructure::storage::file_storage::Storage;
+use crate::infrastructure::blockchain::manager::BlockchainManager;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestCase {
+    pub name: String,
+    pub classname: String,
+    pub status: TestStatus,
+    pub duration_ms: u64,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestSuite {
+    pub name: String,
+    pub timestamp: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub tests: usize,
+    pub failures: usize,
+    pub skipped: usize,
+    pub cases: Vec<TestCase>,
+}
+
+impl TestSuite {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            timestamp: Utc::now(),
+            duration_ms: 0,
+            tests: 0,
+            failures: 0,
+            skipped: 0,
+            cases: Vec::new(),
+        }
+    }
+
+    pub fn add_case(&mut self, case: TestCase) {
+        self.tests += 1;
+        match case.status {
+            TestStatus::Failed => self.failures += 1,
+            TestStatus::Skipped => self.skipped += 1,
+            TestStatus::Passed => {}
+        }
+        self.duration_ms += case.duration_ms;
+        self.cases.push(case);
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failures == 0
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_else(|_| serde_json::json!({}))
+    }
+
+    /// Render the suite in the JUnit XML format understood by CI systems
+    pub fn to_junit_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\" time=\"{:.3}\" timestamp=\"{}\">\n",
+            xml_escape(&self.name),
+            self.tests,
+            self.failures,
+            self.skipped,
+            self.duration_ms as f64 / 1000.0,
+            self.timestamp.to_rfc3339(),
+        ));
+
+        for case in &self.cases {
+            xml.push_str(&format!(
+                "  <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                xml_escape(&case.name),
+                xml_escape(&case.classname),
+                case.duration_ms as f64 / 1000.0,
+            ));
+            let message = xml_escape(case.message.as_deref().unwrap_or(""));
+            match case.status {
+                TestStatus::Passed => xml.push_str(" />\n"),
+                TestStatus::Failed => {
+                    xml.push_str(&format!(">\n    <failure message=\"{message}\">{message}</failure>\n  </testcase>\n"));
+                }
+                TestStatus::Skipped => {
+                    xml.push_str(&format!(">\n    <skipped message=\"{message}\" />\n  </testcase>\n"));
+                }
+            }
+        }
+
+        xml.push_str("</testsuite>\n");
+        xml
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Runs the non-destructive subset of relay self-tests on demand
+pub struct TestRunner {
+    storage: Arc<Storage>,
+    blockchain_manager: Arc<BlockchainManager>,
+}
+
+impl TestRunner {
+    pub fn new(storage: Arc<Storage>, blockchain_manager: Arc<BlockchainManager>) -> Self {
+        Self {
+            storage,
+            blockchain_manager,
+        }
+    }
+
+    pub async fn run_safe_subset(&self) -> TestSuite {
+        let mut suite = TestSuite::new("airchainpay-relay-selftest");
+        suite.add_case(self.test_storage().await);
+        for case in self.test_rpc_connectivity().await {
+            suite.add_case(case);
+        }
+        suite.add_case(self.test_ble_init());
+        suite
+    }
+
+    async fn test_storage(&self) -> TestCase {
+        let start = Instant::now();
+        let health = self.storage.check_health().await;
+        let (status, message) = if health.is_healthy {
+            (TestStatus::Passed, None)
+        } else {
+            (TestStatus::Failed, Some("Data directory is not writable".to_string()))
+        };
+
+        TestCase {
+            name: "storage_read_write".to_string(),
+            classname: "selftest.storage".to_string(),
+            status,
+            duration_ms: start.elapsed().as_millis() as u64,
+            message,
+        }
+    }
+
+    async fn test_rpc_connectivity(&self) -> Vec<TestCase> {
+        let mut cases = Vec::new();
+        for chain_id in self.blockchain_manager.chain_ids() {
+            let start = Instant::now();
+            let result = self.blockchain_manager.check_chain_connectivity(chain_id).await;
+            let (status, message) = match result {
+                Ok(block) => (TestStatus::Passed, Some(format!("Latest block {block}"))),
+                Err(e) => (TestStatus::Failed, Some(e.to_string())),
+            };
+            cases.push(TestCase {
+                name: format!("rpc_connectivity_{chain_id}"),
+                classname: "selftest.rpc".to_string(),
+                status,
+                duration_ms: start.elapsed().as_millis() as u64,
+                message,
+            });
+        }
+        cases
+    }
+
+    fn test_ble_init(&self) -> TestCase {
+        // The relay build does not link a BLE adapter; report instead of failing
+        TestCase {
+            name: "ble_init".to_string(),
+            classname: "selftest.ble".to_string(),
+            status: TestStatus::Skipped,
+            duration_ms: 0,
+            message: Some("BLE manager is not available in this relay build".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(name: &str, status: TestStatus, message: Option<&str>) -> TestCase {
+        TestCase {
+            name: name.to_string(),
+            classname: "selftest.unit".to_string(),
+            status,
+            duration_ms: 5,
+            message: message.map(|m| m.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_suite_counts() {
+        let mut suite = TestSuite::new("unit");
+        suite.add_case(case("a", TestStatus::Passed, None));
+        suite.add_case(case("b", TestStatus::Failed, Some("boom")));
+        suite.add_case(case("c", TestStatus::Skipped, Some("n/a")));
+
+        assert_eq!(suite.tests, 3);
+        assert_eq!(suite.failures, 1);
+        assert_eq!(suite.skipped, 1);
+        assert!(!suite.passed());
+    }
+
+    #[test]
+    fn test_junit_xml_escapes_messages() {
+        let mut suite = TestSuite::new("unit");
+        suite.add_case(case("rpc", TestStatus::Failed, Some("<timeout> & \"retry\"")));
+
+        let xml = suite.to_junit_xml();
+        assert!(xml.contains("tests=\"1\" failures=\"1\""));
+        assert!(xml.contains("&lt;timeout&gt; &amp; &quot;retry&quot;"));
+        assert!(xml.ends_with("</testsuite>\n"));
+    }
+}
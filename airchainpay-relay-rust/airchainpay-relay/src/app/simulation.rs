@@ -0,0 +1,241 @@
+use crate::infrastructure::blockchain::manager::BlockchainManager;
+use anyhow::{Result, anyhow};
+use ethers::abi::{decode, ParamType, Token};
+use ethers::core::types::transaction::eip2718::TypedTransaction;
+use ethers::core::types::{BlockNumber, Bytes, Transaction, TransactionRequest};
+use ethers::core::utils::rlp::{Decodable, Rlp};
+use ethers::providers::{Middleware, MiddlewareError};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::time::Duration;
+
+/// `Error(string)`
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// `Panic(uint256)`
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimulationMethod {
+    /// `eth_call`, which every node serves
+    EthCall,
+    /// Geth's `debug_traceCall` with the call tracer; falls back to
+    /// `eth_call` on nodes without the debug namespace
+    DebugTraceCall,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationConfig {
+    pub enabled: bool,
+    pub method: SimulationMethod,
+    pub timeout: Duration,
+    /// Refuse transactions when the node cannot run the simulation
+    pub fail_closed: bool,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            method: SimulationMethod::EthCall,
+            timeout: Duration::from_secs(5),
+            fail_closed: false,
+        }
+    }
+}
+
+impl SimulationConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("SIMULATION_ENABLED")
+                .map(|v| v != "false")
+                .unwrap_or(defaults.enabled),
+            method: match std::env::var("SIMULATION_METHOD").as_deref() {
+                Ok("debug_traceCall") => SimulationMethod::DebugTraceCall,
+                Ok("eth_call") => SimulationMethod::EthCall,
+                _ => defaults.method,
+            },
+            timeout: std::env::var("SIMULATION_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.timeout),
+            fail_closed: std::env::var("SIMULATION_FAIL_CLOSED")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.fail_closed),
+        }
+    }
+}
+
+/// What a dry run of a transaction showed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum SimulationOutcome {
+    Success,
+    Reverted { reason: String },
+    /// The node could not run the call; says nothing about the transaction
+    Unavailable { error: String },
+}
+
+/// Human-readable reason for revert data: the message of `Error(string)`,
+/// the meaning of a `Panic(uint256)` code, or the selector of a custom error
+pub fn decode_revert_reason(data: &[u8]) -> String {
+    if data.len() < 4 {
+        return "execution reverted".to_string();
+    }
+    let (selector, args) = data.split_at(4);
+    if selector == ERROR_SELECTOR {
+        if let Ok(tokens) = decode(&[ParamType::String], args) {
+            if let Some(Token::String(message)) = tokens.into_iter().next() {
+                return message;
+            }
+        }
+    }
+    if selector == PANIC_SELECTOR {
+        if let Ok(tokens) = decode(&[ParamType::Uint(256)], args) {
+            if let Some(Token::Uint(code)) = tokens.into_iter().next() {
+                return format!("panic 0x{:02x}: {}", code.low_u64(), panic_meaning(code.low_u64()));
+            }
+        }
+    }
+    format!("custom error 0x{}", hex::encode(selector))
+}
+
+fn panic_meaning(code: u64) -> &'static str {
+    match code {
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division by zero",
+        0x21 => "invalid enum value",
+        0x22 => "corrupt storage byte array",
+        0x31 => "pop on empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to uninitialized function",
+        _ => "unknown panic",
+    }
+}
+
+/// Dry-runs signed transactions against the latest block before they are
+/// broadcast, so payments that would revert are refused without spending gas
+pub struct TransactionSimulator {
+    blockchain_manager: Arc<BlockchainManager>,
+    config: SimulationConfig,
+}
+
+impl TransactionSimulator {
+    pub fn new(blockchain_manager: Arc<BlockchainManager>, config: SimulationConfig) -> Self {
+        Self { blockchain_manager, config }
+    }
+
+    pub async fn simulate(&self, chain_id: u64, signed_tx: &str) -> Result<SimulationOutcome> {
+        let bytes = hex::decode(signed_tx.trim_start_matches("0x"))
+            .map_err(|e| anyhow!("Failed to decode hex: {}", e))?;
+        let tx = Transaction::decode(&Rlp::new(&bytes))
+            .map_err(|e| anyhow!("Failed to decode transaction: {}", e))?;
+        let sender = tx.recover_from()
+            .map_err(|e| anyhow!("Failed to recover sender: {}", e))?;
+        let mut request = TransactionRequest::new()
+            .from(sender)
+            .value(tx.value)
+            .data(tx.input.clone())
+            .gas(tx.gas);
+        if let Some(to) = tx.to {
+            request = request.to(to);
+        }
+        let call: TypedTransaction = request.into();
+
+        let run = async {
+            if self.config.method == SimulationMethod::DebugTraceCall {
+                match self.trace_call(chain_id, &call).await {
+                    Ok(outcome) => return outcome,
+                    Err(e) => log::debug!("debug_traceCall unavailable on chain {}, using eth_call: {}", chain_id, e),
+                }
+            }
+            self.eth_call(chain_id, &call).await
+        };
+        Ok(tokio::time::timeout(self.config.timeout, run).await
+            .unwrap_or_else(|_| SimulationOutcome::Unavailable { error: format!("Simulation timed out after {}s", self.config.timeout.as_secs()) }))
+    }
+
+    /// Refuse a transaction whose dry run reverts, with the decoded reason
+    pub async fn check_transaction(&self, chain_id: u64, signed_tx: &str) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        match self.simulate(chain_id, signed_tx).await? {
+            SimulationOutcome::Success => Ok(()),
+            SimulationOutcome::Reverted { reason } => Err(anyhow!("Transaction would revert: {}", reason)),
+            SimulationOutcome::Unavailable { error } if self.config.fail_closed => {
+                Err(anyhow!("Simulation unavailable on chain {}: {}", chain_id, error))
+            }
+            SimulationOutcome::Unavailable { error } => {
+                log::warn!("Simulation unavailable on chain {}, broadcasting anyway: {}", chain_id, error);
+                Ok(())
+            }
+        }
+    }
+
+    async fn eth_call(&self, chain_id: u64, call: &TypedTransaction) -> SimulationOutcome {
+        let Some(provider) = self.blockchain_manager.provider(chain_id) else {
+            return SimulationOutcome::Unavailable { error: format!("No provider for chain_id {}", chain_id) };
+        };
+        match provider.call(call, Some(BlockNumber::Latest.into())).await {
+            Ok(_) => SimulationOutcome::Success,
+            Err(e) => match e.as_error_response() {
+                Some(response) if response.is_revert() => SimulationOutcome::Reverted {
+                    reason: response.as_revert_data()
+                        .map(|data| decode_revert_reason(&data))
+                        .unwrap_or_else(|| response.message.clone()),
+                },
+                _ => SimulationOutcome::Unavailable { error: e.to_string() },
+            },
+        }
+    }
+
+    async fn trace_call(&self, chain_id: u64, call: &TypedTransaction) -> Result<SimulationOutcome> {
+        let provider = self.blockchain_manager.provider(chain_id)
+            .ok_or_else(|| anyhow!("No provider for chain_id {}", chain_id))?;
+        let trace: serde_json::Value = provider
+            .request("debug_traceCall", (call, "latest", serde_json::json!({"tracer": "callTracer"})))
+            .await
+            .map_err(|e| anyhow!("{}", e))?;
+        let Some(error) = trace.get("error").and_then(|e| e.as_str()) else {
+            return Ok(SimulationOutcome::Success);
+        };
+        let output = trace.get("output")
+            .and_then(|o| o.as_str())
+            .and_then(|o| hex::decode(o.trim_start_matches("0x")).ok())
+            .map(Bytes::from);
+        let reason = match (trace.get("revertReason").and_then(|r| r.as_str()), output) {
+            (Some(reason), _) => reason.to_string(),
+            (None, Some(output)) if !output.is_empty() => decode_revert_reason(&output),
+            // Out of gas and other halts carry no revert data
+            _ => error.to_string(),
+        };
+        Ok(SimulationOutcome::Reverted { reason })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::encode;
+    use ethers::types::U256;
+
+    #[test]
+    fn test_decode_revert_reason() {
+        let mut error = ERROR_SELECTOR.to_vec();
+        error.extend(encode(&[Token::String("Insufficient allowance".to_string())]));
+        assert_eq!(decode_revert_reason(&error), "Insufficient allowance");
+
+        let mut panic = PANIC_SELECTOR.to_vec();
+        panic.extend(encode(&[Token::Uint(U256::from(0x11))]));
+        assert_eq!(decode_revert_reason(&panic), "panic 0x11: arithmetic overflow or underflow");
+
+        assert_eq!(decode_revert_reason(&[0xde, 0xad, 0xbe, 0xef]), "custom error 0xdeadbeef");
+        assert_eq!(decode_revert_reason(&[]), "execution reverted");
+    }
+}
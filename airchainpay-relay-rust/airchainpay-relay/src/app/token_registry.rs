@@ -0,0 +1,363 @@
+use crate::infrastructure::blockchain::manager::{BlockchainManager, TokenConfig};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use ethers::abi::Abi;
+use ethers::core::types::{Address, Transaction, U256};
+use ethers::core::utils::rlp::{Decodable, Rlp};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::time::Duration;
+
+/// `transfer(address,uint256)`
+pub const ERC20_TRANSFER: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+/// `transferFrom(address,address,uint256)`
+pub const ERC20_TRANSFER_FROM: [u8; 4] = [0x23, 0xb8, 0x72, 0xdd];
+
+/// Payee and amount of ERC-20 `transfer` or `transferFrom` calldata
+pub fn decode_erc20_transfer(input: &[u8]) -> Option<(Address, U256)> {
+    if input.len() < 4 {
+        return None;
+    }
+    let (selector, args) = input.split_at(4);
+    let (payee, amount) = match selector {
+        s if s == ERC20_TRANSFER => (args.get(..32)?, args.get(32..64)?),
+        s if s == ERC20_TRANSFER_FROM => (args.get(32..64)?, args.get(64..96)?),
+        _ => return None,
+    };
+    Some((Address::from_slice(&payee[12..]), U256::from_big_endian(amount)))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenRegistryConfig {
+    pub refresh_interval: Duration,
+    /// Tokens to keep cached per chain; tokens seen in submissions are added
+    pub tokens: BTreeMap<u64, Vec<Address>>,
+    /// Refuse transfers of cached tokens the payment contract does not
+    /// accept, or outside its min/max amounts
+    pub enforce: bool,
+}
+
+impl Default for TokenRegistryConfig {
+    fn default() -> Self {
+        Self {
+            refresh_interval: Duration::from_secs(900),
+            tokens: BTreeMap::new(),
+            enforce: true,
+        }
+    }
+}
+
+impl TokenRegistryConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            refresh_interval: std::env::var("TOKEN_REGISTRY_REFRESH_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.refresh_interval),
+            tokens: std::env::var("TOKEN_REGISTRY_TOKENS")
+                .map(|v| Self::parse_tokens(&v))
+                .unwrap_or(defaults.tokens),
+            enforce: std::env::var("TOKEN_REGISTRY_ENFORCE")
+                .map(|v| v != "false")
+                .unwrap_or(defaults.enforce),
+        }
+    }
+
+    /// `84532:0xabc…,0xdef…;1114:0x123…`
+    fn parse_tokens(value: &str) -> BTreeMap<u64, Vec<Address>> {
+        let mut tokens: BTreeMap<u64, Vec<Address>> = BTreeMap::new();
+        for entry in value.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((chain_id, addresses)) = entry.split_once(':') else {
+                log::warn!("Ignoring token registry entry without a chain id: {}", entry);
+                continue;
+            };
+            let Ok(chain_id) = chain_id.trim().parse::<u64>() else {
+                log::warn!("Ignoring token registry entry with invalid chain id: {}", entry);
+                continue;
+            };
+            for address in addresses.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+                match address.parse() {
+                    Ok(address) => tokens.entry(chain_id).or_default().push(address),
+                    Err(_) => log::warn!("Ignoring invalid token address {} for chain {}", address, chain_id),
+                }
+            }
+        }
+        tokens
+    }
+}
+
+/// Cached payment-contract configuration of a token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenMetadata {
+    pub chain_id: u64,
+    pub address: Address,
+    #[serde(flatten)]
+    pub config: TokenConfig,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// Whether the code deployed at a chain's payment contract matches the ABIs
+/// the relay calls it with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractVerification {
+    pub chain_id: u64,
+    pub address: Address,
+    pub code_size: usize,
+    /// keccak256 of the deployed bytecode
+    pub code_hash: Option<String>,
+    /// Functions of each bundled ABI whose selector is absent from the bytecode
+    pub missing_functions: BTreeMap<String, Vec<String>>,
+    pub verified: bool,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// An ERC-20 transfer decoded from a signed transaction, with the token's
+/// cached metadata when known
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecodedTransfer {
+    pub chain_id: u64,
+    pub token: Address,
+    pub recipient: Address,
+    pub amount: U256,
+    pub token_metadata: Option<TokenMetadata>,
+}
+
+impl DecodedTransfer {
+    /// Amount in whole tokens, if the token's decimals are known
+    pub fn display_amount(&self) -> Option<String> {
+        let metadata = self.token_metadata.as_ref()?;
+        ethers::utils::format_units(self.amount, metadata.config.decimals as u32).ok()
+    }
+}
+
+/// Bundled ABIs the relay expects at the payment contract address
+fn payment_abis() -> Vec<(&'static str, Abi)> {
+    [
+        ("AirChainPay", &include_bytes!("../abi/AirChainPay.json")[..]),
+        ("AirChainPayToken", &include_bytes!("../abi/AirChainPayToken.json")[..]),
+    ]
+    .into_iter()
+    .filter_map(|(name, bytes)| serde_json::from_slice(bytes).ok().map(|abi| (name, abi)))
+    .collect()
+}
+
+/// Functions of `abi` whose 4-byte selector does not occur in `code`. The
+/// dispatcher pushes every external selector, so a missing one means the
+/// deployed contract is not the one the ABI describes.
+fn missing_functions(abi: &Abi, code: &[u8]) -> Vec<String> {
+    let mut missing: Vec<String> = abi.functions()
+        .filter(|function| !code.windows(4).any(|window| window == function.short_signature()))
+        .map(|function| {
+            let inputs: Vec<String> = function.inputs.iter().map(|param| param.kind.to_string()).collect();
+            format!("{}({})", function.name, inputs.join(","))
+        })
+        .collect();
+    missing.sort();
+    missing.dedup();
+    missing
+}
+
+/// Token metadata and payment-contract verification per chain, refreshed in
+/// the background so transfer decoding and the token rules never call an
+/// RPC on the submission path
+pub struct TokenRegistry {
+    config: TokenRegistryConfig,
+    blockchain_manager: Arc<BlockchainManager>,
+    tokens: RwLock<HashMap<(u64, Address), TokenMetadata>>,
+    contracts: RwLock<HashMap<u64, ContractVerification>>,
+    /// Tokens seen in submissions but not cached yet
+    pending: RwLock<HashSet<(u64, Address)>>,
+    last_refresh: RwLock<Option<DateTime<Utc>>>,
+}
+
+impl TokenRegistry {
+    pub fn new(config: TokenRegistryConfig, blockchain_manager: Arc<BlockchainManager>) -> Self {
+        Self {
+            config,
+            blockchain_manager,
+            tokens: RwLock::new(HashMap::new()),
+            contracts: RwLock::new(HashMap::new()),
+            pending: RwLock::new(HashSet::new()),
+            last_refresh: RwLock::new(None),
+        }
+    }
+
+    pub fn config(&self) -> &TokenRegistryConfig {
+        &self.config
+    }
+
+    pub fn start(registry: Arc<TokenRegistry>) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(registry.config.refresh_interval);
+            loop {
+                ticker.tick().await;
+                registry.refresh().await;
+            }
+        });
+    }
+
+    /// Re-verify every chain's payment contract and re-fetch every known token
+    pub async fn refresh(&self) {
+        for chain_id in self.blockchain_manager.chain_ids() {
+            match self.verify_contract(chain_id).await {
+                Ok(Some(verification)) => {
+                    if !verification.verified {
+                        log::warn!(
+                            "Payment contract {:?} on chain {} does not match its ABI: {:?}",
+                            verification.address, chain_id, verification.missing_functions
+                        );
+                    }
+                    self.contracts.write().await.insert(chain_id, verification);
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("Failed to verify payment contract on chain {}: {}", chain_id, e),
+            }
+        }
+
+        let mut targets: HashSet<(u64, Address)> = self.config.tokens.iter()
+            .flat_map(|(chain_id, tokens)| tokens.iter().map(move |token| (*chain_id, *token)))
+            .collect();
+        targets.extend(self.tokens.read().await.keys().copied());
+        targets.extend(self.pending.write().await.drain());
+
+        for (chain_id, token) in targets {
+            match self.blockchain_manager.token_config(chain_id, token).await {
+                Ok(config) => {
+                    self.tokens.write().await.insert((chain_id, token), TokenMetadata {
+                        chain_id,
+                        address: token,
+                        config,
+                        fetched_at: Utc::now(),
+                    });
+                }
+                // The stale entry keeps serving until a refresh succeeds
+                Err(e) => log::warn!("Failed to fetch token {:?} on chain {}: {}", token, chain_id, e),
+            }
+        }
+        *self.last_refresh.write().await = Some(Utc::now());
+    }
+
+    async fn verify_contract(&self, chain_id: u64) -> Result<Option<ContractVerification>> {
+        let Some(address) = self.blockchain_manager.contract_address(chain_id) else {
+            return Ok(None);
+        };
+        let code = self.blockchain_manager.get_code(chain_id, address).await?;
+        let missing_functions: BTreeMap<String, Vec<String>> = payment_abis().iter()
+            .map(|(name, abi)| (name.to_string(), missing_functions(abi, &code)))
+            .filter(|(_, missing)| !missing.is_empty())
+            .collect();
+        Ok(Some(ContractVerification {
+            chain_id,
+            address,
+            code_size: code.len(),
+            code_hash: (!code.is_empty()).then(|| format!("0x{}", hex::encode(ethers::utils::keccak256(&code)))),
+            verified: !code.is_empty() && missing_functions.is_empty(),
+            missing_functions,
+            checked_at: Utc::now(),
+        }))
+    }
+
+    pub async fn token(&self, chain_id: u64, token: Address) -> Option<TokenMetadata> {
+        self.tokens.read().await.get(&(chain_id, token)).cloned()
+    }
+
+    pub async fn contract(&self, chain_id: u64) -> Option<ContractVerification> {
+        self.contracts.read().await.get(&chain_id).cloned()
+    }
+
+    /// Decode an ERC-20 transfer from cached data only. A token not cached
+    /// yet is queued for the next refresh.
+    pub async fn decode_transfer(&self, chain_id: u64, tx: &Transaction) -> Option<DecodedTransfer> {
+        let token = tx.to?;
+        let (recipient, amount) = decode_erc20_transfer(&tx.input)?;
+        let token_metadata = self.token(chain_id, token).await;
+        if token_metadata.is_none() {
+            self.pending.write().await.insert((chain_id, token));
+        }
+        Some(DecodedTransfer { chain_id, token, recipient, amount, token_metadata })
+    }
+
+    /// Token rules for a signed transaction: a transfer of a cached token
+    /// must be of a token the payment contract accepts, within its limits.
+    /// Tokens not cached yet pass.
+    pub async fn check_transaction(&self, chain_id: u64, signed_tx: &str) -> Result<()> {
+        if !self.config.enforce {
+            return Ok(());
+        }
+        let bytes = hex::decode(signed_tx.trim_start_matches("0x"))
+            .map_err(|e| anyhow!("Failed to decode hex: {}", e))?;
+        let tx = Transaction::decode(&Rlp::new(&bytes))
+            .map_err(|e| anyhow!("Failed to decode transaction: {}", e))?;
+        let Some(transfer) = self.decode_transfer(chain_id, &tx).await else {
+            return Ok(());
+        };
+        let Some(metadata) = &transfer.token_metadata else {
+            return Ok(());
+        };
+        let config = &metadata.config;
+        if !config.supported {
+            return Err(anyhow!("Token {:?} is not accepted by the payment contract on chain {}", transfer.token, chain_id));
+        }
+        if !config.min_amount.is_zero() && transfer.amount < config.min_amount {
+            return Err(anyhow!("{} transfer of {} is below the minimum of {}", config.symbol, transfer.amount, config.min_amount));
+        }
+        if !config.max_amount.is_zero() && transfer.amount > config.max_amount {
+            return Err(anyhow!("{} transfer of {} is above the maximum of {}", config.symbol, transfer.amount, config.max_amount));
+        }
+        Ok(())
+    }
+
+    /// Cached contracts and tokens, for the admin API
+    pub async fn snapshot(&self) -> serde_json::Value {
+        let mut contracts: Vec<ContractVerification> = self.contracts.read().await.values().cloned().collect();
+        contracts.sort_by_key(|c| c.chain_id);
+        let mut tokens: Vec<TokenMetadata> = self.tokens.read().await.values().cloned().collect();
+        tokens.sort_by_key(|t| (t.chain_id, t.address));
+        serde_json::json!({
+            "contracts": contracts,
+            "tokens": tokens,
+            "pending_tokens": self.pending.read().await.len(),
+            "last_refresh": *self.last_refresh.read().await,
+            "refresh_interval_secs": self.config.refresh_interval.as_secs(),
+            "enforce": self.config.enforce,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_transfer_and_missing_selectors() {
+        let payee: Address = "0x8ba1f109551bd432803012645ac136ddd64dba72".parse().unwrap();
+        let mut calldata = ERC20_TRANSFER.to_vec();
+        calldata.extend_from_slice(&[0u8; 12]);
+        calldata.extend_from_slice(payee.as_bytes());
+        let mut amount = [0u8; 32];
+        U256::from(2_500_000u64).to_big_endian(&mut amount);
+        calldata.extend_from_slice(&amount);
+        assert_eq!(decode_erc20_transfer(&calldata), Some((payee, U256::from(2_500_000u64))));
+        assert_eq!(decode_erc20_transfer(&calldata[..40]), None);
+
+        let config = TokenRegistryConfig::parse_tokens("84532:0x036CbD53842c5426634e7929541eC2318f3dCF7e, bogus;x:0x1");
+        assert_eq!(config.get(&84532).map(Vec::len), Some(1));
+        assert_eq!(config.len(), 1);
+
+        let abis = payment_abis();
+        let (_, abi) = abis.iter().find(|(name, _)| *name == "AirChainPay").unwrap();
+        let owner = abi.function("owner").unwrap().short_signature();
+        let code: Vec<u8> = abi.functions().flat_map(|f| f.short_signature()).collect();
+        assert!(missing_functions(abi, &code).is_empty());
+        let without_owner: Vec<u8> = abi.functions()
+            .filter(|f| f.short_signature() != owner)
+            .flat_map(|f| f.short_signature())
+            .collect();
+        assert_eq!(missing_functions(abi, &without_owner), vec!["owner()".to_string()]);
+    }
+}
@@ -1,13 +1,29 @@
+use crate::infrastructure::blockchain::fees::FeeOracle;
 use crate::infrastructure::blockchain::manager::BlockchainManager;
-use crate::infrastructure::storage::file_storage::Storage;
-use anyhow::Result;
+use crate::infrastructure::monitoring::manager::MonitoringManager;
+use crate::infrastructure::storage::file_storage::{StatusEvent, Storage};
+use crate::app::memory_guard::{MemoryGuard, MemoryPressure};
+use crate::app::screening::ScreeningService;
+use crate::app::rebroadcast;
+use crate::app::replay_guard::TxReplayGuard;
+use crate::app::simulation::TransactionSimulator;
+use crate::app::token_registry::TokenRegistry;
+use crate::domain::identity::RelayIdentity;
+use crate::domain::receipt::SignedReceipt;
+use crate::infrastructure::telemetry::{self, TRACEPARENT_HEADER};
+use crate::utils::request_id;
+use anyhow::{anyhow, Result};
 use std::sync::Arc;
-use tokio::sync::{RwLock, Mutex};
+use tokio::sync::{broadcast, RwLock, Mutex};
 use tokio::time::{Duration};
 use std::collections::{HashMap, VecDeque};
 use std::cmp::Ordering;
 use chrono::{DateTime, Utc};
+use ethers::types::{Address, H256, U256};
+use ethers::signers::Signer;
+use ethers::utils::rlp::{Decodable, Rlp};
 use serde::{Deserialize, Serialize};
+use std::time::Instant;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum TransactionPriority {
@@ -27,6 +43,10 @@ pub struct QueuedTransaction {
     pub retry_delay: Duration,
     pub chain_id: u64,
     pub metadata: HashMap<String, serde_json::Value>,
+    /// Set by relay code that has already admitted the signed transaction
+    /// to the replay guard. Never read from a request body.
+    #[serde(skip)]
+    pub replay_checked: bool,
 }
 
 impl PartialEq for QueuedTransaction {
@@ -59,6 +79,7 @@ pub struct TransactionMetrics {
     pub total_successful: u64,
     pub total_failed: u64,
     pub total_retried: u64,
+    pub total_shed: u64,
     pub average_processing_time_ms: u64,
     pub queue_size: usize,
     pub active_workers: usize,
@@ -80,6 +101,10 @@ pub struct ChainMetrics {
 pub struct TransactionProcessorConfig {
     pub max_concurrent_workers: usize,
     pub max_queue_size: usize,
+    /// Queue depth above which low-priority submissions are refused
+    pub high_water_mark: usize,
+    /// Retry-After hint returned to clients while the queue is overloaded
+    pub overload_retry_after: Duration,
     pub default_retry_count: u32,
     pub default_retry_delay: Duration,
     pub max_retry_delay: Duration,
@@ -90,6 +115,17 @@ pub struct TransactionProcessorConfig {
     pub batch_processing: bool,
     pub batch_size: usize,
     pub batch_timeout: Duration,
+    /// How often broadcast transactions are checked for a receipt
+    pub confirmation_poll_interval: Duration,
+    /// Blocks on top of the including one before a transaction counts as final
+    pub required_confirmations: u64,
+    /// Percent a replacement for a transaction still waiting to be mined
+    /// must raise the fee cap by
+    pub replacement_bump_percent: u64,
+    /// Re-send unmined transactions this often; never when unset
+    pub rebroadcast_interval: Option<Duration>,
+    /// Most times one transaction is re-sent or fee-bumped
+    pub max_rebroadcasts: u32,
 }
 
 impl Default for TransactionProcessorConfig {
@@ -97,6 +133,8 @@ impl Default for TransactionProcessorConfig {
         Self {
             max_concurrent_workers: 4,
             max_queue_size: 1000,
+            high_water_mark: 800,
+            overload_retry_after: Duration::from_secs(5),
             default_retry_count: 3,
             default_retry_delay: Duration::from_secs(5),
             max_retry_delay: Duration::from_secs(60),
@@ -107,10 +145,88 @@ impl Default for TransactionProcessorConfig {
             batch_processing: false,
             batch_size: 10,
             batch_timeout: Duration::from_secs(30),
+            confirmation_poll_interval: Duration::from_secs(5),
+            required_confirmations: 1,
+            replacement_bump_percent: 10,
+            rebroadcast_interval: None,
+            max_rebroadcasts: 5,
         }
     }
 }
 
+impl TransactionProcessorConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_queue_size: std::env::var("QUEUE_MAX_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_queue_size),
+            high_water_mark: std::env::var("QUEUE_HIGH_WATER_MARK")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.high_water_mark),
+            overload_retry_after: std::env::var("QUEUE_RETRY_AFTER_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.overload_retry_after),
+            transaction_timeout: std::env::var("TRANSACTION_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.transaction_timeout),
+            confirmation_poll_interval: std::env::var("CONFIRMATION_POLL_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.confirmation_poll_interval),
+            required_confirmations: std::env::var("REQUIRED_CONFIRMATIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n: &u64| *n > 0)
+                .unwrap_or(defaults.required_confirmations),
+            replacement_bump_percent: std::env::var("REPLACEMENT_BUMP_PERCENT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.replacement_bump_percent),
+            rebroadcast_interval: std::env::var("REBROADCAST_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .map(Duration::from_secs)
+                .or(defaults.rebroadcast_interval),
+            max_rebroadcasts: std::env::var("MAX_REBROADCASTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_rebroadcasts),
+            ..defaults
+        }
+    }
+}
+
+/// Returned by `enqueue_transaction` when the queue cannot admit more work
+#[derive(Debug, Clone)]
+pub struct QueueOverloaded {
+    pub queue_depth: usize,
+    pub retry_after: Duration,
+}
+
+impl std::fmt::Display for QueueOverloaded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Transaction queue is overloaded ({} queued), retry after {}s",
+            self.queue_depth,
+            self.retry_after.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for QueueOverloaded {}
+
 pub struct TransactionQueue {
     queue: VecDeque<QueuedTransaction>,
 }
@@ -141,6 +257,59 @@ pub struct TransactionResult {
     pub block_number: Option<u64>,
 }
 
+/// A broadcast transaction waiting for its receipt
+#[derive(Debug, Clone)]
+struct WatchedTransaction {
+    chain_id: u64,
+    tx_hash: H256,
+    broadcast_at: Instant,
+    last_sent_at: Instant,
+    signed_tx: String,
+    /// Sender, nonce and fee cap, for spotting replacements
+    replaces: Option<ReplacementKey>,
+    rebroadcasts: u32,
+    /// Hashes of earlier fee-bumped versions, any of which may be the one mined
+    superseded: Vec<H256>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ReplacementKey {
+    sender: Address,
+    nonce: U256,
+    fee_cap: U256,
+}
+
+impl ReplacementKey {
+    fn from_signed_tx(signed_tx: &str) -> Result<Self> {
+        let bytes = hex::decode(signed_tx.trim_start_matches("0x"))
+            .map_err(|e| anyhow!("Failed to decode hex: {}", e))?;
+        let tx = ethers::types::Transaction::decode(&Rlp::new(&bytes))
+            .map_err(|e| anyhow!("Failed to decode transaction: {}", e))?;
+        let sender = tx.recover_from()
+            .map_err(|e| anyhow!("Failed to recover sender: {}", e))?;
+        let fee_cap = tx.max_fee_per_gas.or(tx.gas_price)
+            .ok_or_else(|| anyhow!("Transaction carries no fee"))?;
+        Ok(Self { sender, nonce: tx.nonce, fee_cap })
+    }
+}
+
+/// A chain's broadcast settings with the processor-wide ones filled in
+#[derive(Debug, Clone, Copy)]
+struct ChainStrategy {
+    max_fee: Option<U256>,
+    replacement_bump_percent: u64,
+    required_confirmations: u64,
+    rebroadcast_interval: Option<Duration>,
+}
+
+/// Final status for a mined transaction
+fn confirmed_status(receipt_status: Option<u64>) -> (&'static str, Option<&'static str>) {
+    match receipt_status {
+        Some(1) => ("completed", None),
+        _ => ("failed", Some("Transaction reverted on-chain")),
+    }
+}
+
 pub struct TransactionProcessor {
     blockchain_manager: Arc<BlockchainManager>,
     storage: Arc<Storage>,
@@ -149,6 +318,32 @@ pub struct TransactionProcessor {
     metrics: Arc<RwLock<TransactionMetrics>>,
     workers: Arc<RwLock<HashMap<String, tokio::task::JoinHandle<()>>>>,
     running: Arc<RwLock<bool>>,
+    in_flight: Arc<Mutex<HashMap<String, QueuedTransaction>>>,
+    identity: Option<Arc<RelayIdentity>>,
+    memory_guard: Option<Arc<MemoryGuard>>,
+    monitoring: Option<Arc<MonitoringManager>>,
+    fee_oracle: Option<Arc<FeeOracle>>,
+    screening: Option<Arc<ScreeningService>>,
+    token_registry: Option<Arc<TokenRegistry>>,
+    simulator: Option<Arc<TransactionSimulator>>,
+    replay_guard: Option<Arc<TxReplayGuard>>,
+    watching: Arc<Mutex<HashMap<String, WatchedTransaction>>>,
+}
+
+/// Metadata key used to track a queue entry across persistence and restarts
+const QUEUE_ID_KEY: &str = "queue_id";
+
+/// Metadata key carrying the id of the request that submitted the entry
+const REQUEST_ID_KEY: &str = "request_id";
+
+fn queue_id(tx: &QueuedTransaction) -> String {
+    tx.metadata.get(QUEUE_ID_KEY).and_then(|v| v.as_str()).unwrap_or("").to_string()
+}
+
+/// Where a queued transaction came from, for metrics: its transport, or
+/// "http" for the REST endpoints
+fn endpoint(tx: &QueuedTransaction) -> &str {
+    tx.metadata.get("transport").and_then(|v| v.as_str()).unwrap_or("http")
 }
 
 impl TransactionProcessor {
@@ -164,6 +359,7 @@ impl TransactionProcessor {
             total_successful: 0,
             total_failed: 0,
             total_retried: 0,
+            total_shed: 0,
             average_processing_time_ms: 0,
             queue_size: 0,
             active_workers: 0,
@@ -180,39 +376,325 @@ impl TransactionProcessor {
             metrics,
             workers,
             running: Arc::new(RwLock::new(false)),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            identity: None,
+            memory_guard: None,
+            monitoring: None,
+            fee_oracle: None,
+            screening: None,
+            token_registry: None,
+            simulator: None,
+            replay_guard: None,
+            watching: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
-    pub async fn enqueue_transaction(&self, tx: QueuedTransaction) -> Result<()> {
+    /// Sign a receipt for every transaction this relay broadcasts
+    pub fn with_identity(mut self, identity: Arc<RelayIdentity>) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    /// Refuse lower-priority work while memory is tight
+    pub fn with_memory_guard(mut self, guard: Arc<MemoryGuard>) -> Self {
+        self.memory_guard = Some(guard);
+        self
+    }
+
+    /// Count confirmations and confirmation timeouts in the relay metrics
+    pub fn with_monitoring(mut self, monitoring: Arc<MonitoringManager>) -> Self {
+        self.monitoring = Some(monitoring);
+        self
+    }
+
+    /// Fail transactions whose fees are too low to be mined
+    pub fn with_fee_oracle(mut self, fee_oracle: Arc<FeeOracle>) -> Self {
+        self.fee_oracle = Some(fee_oracle);
+        self
+    }
+
+    /// Screen sender and recipient against sanctions lists before broadcast
+    pub fn with_screening(mut self, screening: Arc<ScreeningService>) -> Self {
+        self.screening = Some(screening);
+        self
+    }
+
+    /// Refuse token transfers the payment contract would not accept, using
+    /// the registry's cached token configuration
+    pub fn with_token_registry(mut self, token_registry: Arc<TokenRegistry>) -> Self {
+        self.token_registry = Some(token_registry);
+        self
+    }
+
+    /// Dry-run transactions before broadcast and fail the ones that would
+    /// revert, with the decoded revert reason
+    pub fn with_simulator(mut self, simulator: Arc<TransactionSimulator>) -> Self {
+        self.simulator = Some(simulator);
+        self
+    }
+
+    /// Refuse raw transactions already accepted through any path
+    pub fn with_replay_guard(mut self, replay_guard: Arc<TxReplayGuard>) -> Self {
+        self.replay_guard = Some(replay_guard);
+        self
+    }
+
+    pub async fn enqueue_transaction(&self, mut tx: QueuedTransaction) -> Result<()> {
+        if let Some(guard) = &self.memory_guard {
+            let minimum = match guard.pressure() {
+                MemoryPressure::Normal => TransactionPriority::Low,
+                MemoryPressure::Elevated => TransactionPriority::Normal,
+                MemoryPressure::Critical => TransactionPriority::High,
+            };
+            if tx.priority < minimum {
+                return Err(QueueOverloaded {
+                    queue_depth: self.queue_depth().await,
+                    retry_after: self.config.overload_retry_after,
+                }.into());
+            }
+        }
+        if queue_id(&tx).is_empty() {
+            tx.metadata.insert(QUEUE_ID_KEY.to_string(), serde_json::Value::String(uuid::Uuid::new_v4().to_string()));
+        }
+        if let Some(id) = request_id::current() {
+            tx.metadata.entry(REQUEST_ID_KEY.to_string()).or_insert(serde_json::Value::String(id));
+        }
+        if let Some(traceparent) = telemetry::trace_headers(&tracing::Span::current()).remove(TRACEPARENT_HEADER) {
+            tx.metadata.entry(TRACEPARENT_HEADER.to_string()).or_insert(serde_json::Value::String(traceparent));
+        }
         let mut queue_guard = self.queue.lock().await;
-        if queue_guard.queue.len() >= self.config.max_queue_size {
-            return Err(anyhow::anyhow!("Transaction queue is full (max: {})", self.config.max_queue_size));
+        let depth = queue_guard.queue.len();
+        let high_water_mark = self.config.high_water_mark.min(self.config.max_queue_size);
+        let overloaded = QueueOverloaded {
+            queue_depth: depth,
+            retry_after: self.config.overload_retry_after,
+        };
+
+        // Past the high-water mark only non-low work is admitted; at capacity it
+        // displaces the lowest-priority entry still waiting, if there is one.
+        let mut victim = None;
+        if depth >= high_water_mark {
+            if tx.priority == TransactionPriority::Low {
+                return Err(overloaded.into());
+            }
+            if depth >= self.config.max_queue_size {
+                victim = queue_guard.queue.iter()
+                    .enumerate()
+                    .filter(|(_, queued)| queued.priority < tx.priority)
+                    .min_by(|(_, a), (_, b)| a.cmp(b))
+                    .map(|(index, _)| index);
+                if victim.is_none() {
+                    return Err(overloaded.into());
+                }
+            }
         }
+
+        // Checked once the entry is sure to fit, so a refusal above never
+        // leaves its hash recorded. Entries a handler already admitted are
+        // not checked twice.
+        if let (Some(guard), Some(signed_tx), false) = (&self.replay_guard, tx.metadata.get("signedTx").and_then(|v| v.as_str()), tx.replay_checked) {
+            let tx_id = tx.metadata.get("id").and_then(|v| v.as_str());
+            let source = tx.metadata.get("transport").and_then(|v| v.as_str()).unwrap_or("queue");
+            guard.admit(signed_tx, tx.chain_id, tx_id, source).await?;
+            tx.replay_checked = true;
+        }
+
+        if let Some(monitoring) = &self.monitoring {
+            monitoring.record_chain_transaction(tx.chain_id, endpoint(&tx), "queued");
+        }
+        let shed = victim.and_then(|index| queue_guard.queue.remove(index));
         queue_guard.queue.push_back(tx);
+        drop(queue_guard);
+
+        if let Some(shed) = shed {
+            self.shed_transaction(&shed).await;
+        }
+        self.persist_queue().await;
         Ok(())
     }
 
-    async fn process_transaction(&self, tx: QueuedTransaction, worker_name: &str) {
+    /// Status changes of every transaction as the workers record them
+    pub fn subscribe_status(&self) -> broadcast::Receiver<StatusEvent> {
+        self.storage.subscribe_status()
+    }
+
+    pub async fn queue_depth(&self) -> usize {
+        self.queue.lock().await.queue.len()
+    }
+
+    /// True once the queue has reached its high-water mark
+    pub async fn is_saturated(&self) -> bool {
+        self.queue_depth().await >= self.config.high_water_mark.min(self.config.max_queue_size)
+    }
+
+    /// Mark a displaced entry as shed so clients polling its status see why
+    async fn shed_transaction(&self, tx: &QueuedTransaction) {
+        let tx_id = tx.metadata.get("id").and_then(|v| v.as_str()).unwrap_or("");
+        println!("Shedding {:?} priority transaction {} under load", tx.priority, tx_id);
+        let _ = self.storage.update_transaction_status_with_error(
+            tx_id,
+            "shed",
+            None,
+            Some("Dropped from the queue under load, please resubmit".to_string()),
+        );
+        if let (Some(guard), Some(signed_tx)) = (&self.replay_guard, tx.metadata.get("signedTx").and_then(|v| v.as_str())) {
+            guard.release(signed_tx).await;
+        }
+        self.metrics.write().await.total_shed += 1;
+    }
+
+    /// Write the queue to storage now, e.g. before the process may be killed
+    pub async fn flush_queue(&self) {
+        self.persist_queue().await;
+    }
+
+    /// Snapshot queued and in-flight entries to storage
+    async fn persist_queue(&self) {
+        let mut entries: Vec<QueuedTransaction> = {
+            let in_flight = self.in_flight.lock().await;
+            in_flight.values().cloned().collect()
+        };
+        {
+            let queue_guard = self.queue.lock().await;
+            entries.extend(queue_guard.queue.iter().cloned());
+        }
+        if let Err(e) = self.storage.save_queue(&entries) {
+            println!("Failed to persist transaction queue: {}", e);
+        }
+    }
+
+    /// Reload entries persisted before a restart, highest priority first.
+    /// Entries that were in flight are requeued with their retry state intact.
+    pub async fn restore_queue(&self) -> Result<usize> {
+        let mut entries = self.storage.load_queue()?;
+        if entries.is_empty() {
+            return Ok(0);
+        }
+        entries.sort_by(|a, b| b.cmp(a));
+        let restored = entries.len();
+
+        let mut queue_guard = self.queue.lock().await;
+        for tx in entries.into_iter().rev() {
+            queue_guard.queue.push_front(tx);
+        }
+        drop(queue_guard);
+
+        println!("Restored {} queued transactions from storage", restored);
+        Ok(restored)
+    }
+
+    /// Hand up to `limit` queued entries to a forwarder instead of the workers.
+    /// They stay persisted as in flight until finished or requeued.
+    pub async fn take_for_forwarding(&self, limit: usize) -> Vec<QueuedTransaction> {
+        let batch: Vec<QueuedTransaction> = {
+            let mut queue_guard = self.queue.lock().await;
+            let count = limit.min(queue_guard.queue.len());
+            queue_guard.queue.drain(..count).collect()
+        };
+        if batch.is_empty() {
+            return batch;
+        }
+        {
+            let mut in_flight = self.in_flight.lock().await;
+            for tx in &batch {
+                in_flight.insert(queue_id(tx), tx.clone());
+            }
+        }
+        self.persist_queue().await;
+        batch
+    }
+
+    /// Drop forwarded entries once the upstream relay has taken them
+    pub async fn finish_forwarding(&self, entries: &[QueuedTransaction]) {
+        {
+            let mut in_flight = self.in_flight.lock().await;
+            for tx in entries {
+                in_flight.remove(&queue_id(tx));
+            }
+        }
+        self.persist_queue().await;
+    }
+
+    /// Put entries that could not be forwarded back at the head of the queue
+    pub async fn requeue(&self, entries: Vec<QueuedTransaction>) {
+        {
+            let mut in_flight = self.in_flight.lock().await;
+            let mut queue_guard = self.queue.lock().await;
+            for tx in entries.into_iter().rev() {
+                in_flight.remove(&queue_id(&tx));
+                queue_guard.queue.push_front(tx);
+            }
+        }
+        self.persist_queue().await;
+    }
+
+    async fn process_transaction(&self, mut tx: QueuedTransaction, worker_name: &str) {
         println!("{} is processing transaction: {:?}", worker_name, tx);
         let max_retries = 3;
-        let mut attempt = 0;
+        let mut attempt = tx.retry_count.min(max_retries - 1);
+        let entry_id = queue_id(&tx);
+        self.in_flight.lock().await.insert(entry_id.clone(), tx.clone());
+        self.persist_queue().await;
         let tx_id = tx.metadata.get("id").and_then(|v| v.as_str()).unwrap_or("").to_string();
         let mut last_err = None;
         
         // Update status to processing
         let _ = self.storage.update_transaction_status_with_error(&tx_id, "processing", None, None);
         
+        let precheck = match self.check_screening(&tx, &tx_id).await {
+            Ok(()) => match self.check_token_rules(&tx).await {
+                Ok(()) => match self.check_fees(&tx).await {
+                    Ok(()) => match self.check_broadcast_strategy(&tx, &tx_id).await {
+                        Ok(()) => self.check_simulation(&tx).await,
+                        Err(e) => Err(e),
+                    },
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+        if let Err(e) = precheck {
+            // Retrying cannot help a blocked party, a refused token, a fee the chain will never accept or a call that reverts
+            let _ = self.storage.update_transaction_status_with_error(&tx_id, "failed", None, Some(e.to_string()));
+            self.in_flight.lock().await.remove(&entry_id);
+            self.persist_queue().await;
+            if let Some(monitoring) = &self.monitoring {
+                monitoring.record_chain_transaction(tx.chain_id, endpoint(&tx), "rejected");
+            }
+            println!("{} rejected transaction {}: {}", worker_name, tx_id, e);
+            return;
+        }
+        
         while attempt < max_retries {
-            match self.blockchain_manager.send_transaction(&tx).await {
-                Ok(tx_hash) => {
-                    println!("{} successfully sent transaction: {:?}, hash: {}", worker_name, tx, tx_hash);
-                    let _ = self.storage.update_transaction_status_with_error(&tx_id, "completed", Some(format!("{:?}", tx_hash)), None);
+            let started = std::time::Instant::now();
+            let broadcast = self.blockchain_manager.broadcast_transaction(&tx).await;
+            if let Some(monitoring) = &self.monitoring {
+                monitoring.record_broadcast(tx.chain_id, broadcast.is_ok(), started.elapsed());
+            }
+            match broadcast {
+                Ok(hash) => {
+                    if let Some(monitoring) = &self.monitoring {
+                        monitoring.record_chain_transaction(tx.chain_id, endpoint(&tx), "broadcast");
+                    }
+                    println!("{} successfully sent transaction: {:?}, hash: {}", worker_name, tx, hash);
+                    let tx_hash = format!("{:?}", hash);
+                    // The confirmation watcher moves it to its final status once mined
+                    let _ = self.storage.update_transaction_status_with_error(&tx_id, "broadcast", Some(tx_hash.clone()), None);
+                    self.issue_receipt(&tx_id, &tx_hash, tx.chain_id);
+                    let signed_tx = tx.metadata.get("signedTx").and_then(|v| v.as_str()).unwrap_or("");
+                    self.watch(&tx_id, tx.chain_id, hash, signed_tx).await;
+                    self.in_flight.lock().await.remove(&entry_id);
+                    self.persist_queue().await;
                     return;
                 }
                 Err(e) => {
                     println!("{} failed to send transaction (attempt {}): {:?}, error: {:?}", worker_name, attempt + 1, tx, e);
                     last_err = Some(e);
                     attempt += 1;
+                    tx.retry_count = attempt;
+                    self.in_flight.lock().await.insert(entry_id.clone(), tx.clone());
+                    self.persist_queue().await;
                     
                     // Update status to retrying if not the last attempt
                     if attempt < max_retries {
@@ -231,10 +713,325 @@ impl TransactionProcessor {
         };
         
         let _ = self.storage.update_transaction_status_with_error(&tx_id, "failed", None, Some(error_details.clone()));
+        self.in_flight.lock().await.remove(&entry_id);
+        self.persist_queue().await;
+        if let Some(monitoring) = &self.monitoring {
+            monitoring.record_chain_transaction(tx.chain_id, endpoint(&tx), "failed");
+        }
         println!("{} permanently failed to send transaction: {:?}, error: {}", worker_name, tx, error_details);
     }
 
+    async fn check_fees(&self, tx: &QueuedTransaction) -> Result<()> {
+        let (Some(oracle), Some(signed_tx)) = (&self.fee_oracle, tx.metadata.get("signedTx").and_then(|v| v.as_str())) else {
+            return Ok(());
+        };
+        oracle.check_transaction(tx.chain_id, signed_tx).await
+    }
+
+    fn chain_strategy(&self, chain_id: u64) -> ChainStrategy {
+        let strategy = self.blockchain_manager.broadcast_strategy(chain_id);
+        ChainStrategy {
+            max_fee: strategy.max_fee_gwei.map(|gwei| U256::from(gwei) * U256::exp10(9)),
+            replacement_bump_percent: strategy.replacement_bump_percent.unwrap_or(self.config.replacement_bump_percent),
+            required_confirmations: strategy.required_confirmations.unwrap_or(self.config.required_confirmations),
+            rebroadcast_interval: strategy.rebroadcast_interval_secs.map(Duration::from_secs).or(self.config.rebroadcast_interval),
+        }
+    }
+
+    /// Apply the chain's fee ceiling, and let a transaction replace one
+    /// still waiting to be mined only with a large enough fee bump. The
+    /// replaced transaction is marked dropped.
+    async fn check_broadcast_strategy(&self, tx: &QueuedTransaction, tx_id: &str) -> Result<()> {
+        let Some(signed_tx) = tx.metadata.get("signedTx").and_then(|v| v.as_str()) else {
+            return Ok(());
+        };
+        let strategy = self.chain_strategy(tx.chain_id);
+        let key = ReplacementKey::from_signed_tx(signed_tx)?;
+        if let Some(max_fee) = strategy.max_fee {
+            if key.fee_cap > max_fee {
+                return Err(anyhow!(
+                    "Fee too high for chain {}: offered {} wei/gas, at most {} wei/gas accepted",
+                    tx.chain_id, key.fee_cap, max_fee
+                ));
+            }
+        }
+
+        let mut watching = self.watching.lock().await;
+        let replaced = watching.iter()
+            .find(|(id, w)| {
+                id.as_str() != tx_id && w.chain_id == tx.chain_id
+                    && w.replaces.is_some_and(|r| r.sender == key.sender && r.nonce == key.nonce)
+            })
+            .map(|(id, w)| (id.clone(), w.replaces.map(|r| r.fee_cap).unwrap_or_default()));
+        let Some((replaced_id, replaced_fee)) = replaced else {
+            return Ok(());
+        };
+        let required = replaced_fee * U256::from(100 + strategy.replacement_bump_percent) / U256::from(100);
+        if key.fee_cap < required {
+            return Err(anyhow!(
+                "Replacement for nonce {} is underpriced: offered {} wei/gas, at least {} wei/gas required",
+                key.nonce, key.fee_cap, required
+            ));
+        }
+        watching.remove(&replaced_id);
+        drop(watching);
+        let _ = self.storage.update_transaction_status_with_error(&replaced_id, "dropped", None, Some(format!("Replaced by {}", tx_id)));
+        Ok(())
+    }
+
+    async fn check_simulation(&self, tx: &QueuedTransaction) -> Result<()> {
+        let (Some(simulator), Some(signed_tx)) = (&self.simulator, tx.metadata.get("signedTx").and_then(|v| v.as_str())) else {
+            return Ok(());
+        };
+        simulator.check_transaction(tx.chain_id, signed_tx).await
+    }
+
+    async fn check_token_rules(&self, tx: &QueuedTransaction) -> Result<()> {
+        let (Some(registry), Some(signed_tx)) = (&self.token_registry, tx.metadata.get("signedTx").and_then(|v| v.as_str())) else {
+            return Ok(());
+        };
+        registry.check_transaction(tx.chain_id, signed_tx).await
+    }
+
+    async fn check_screening(&self, tx: &QueuedTransaction, tx_id: &str) -> Result<()> {
+        let (Some(screening), Some(signed_tx)) = (&self.screening, tx.metadata.get("signedTx").and_then(|v| v.as_str())) else {
+            return Ok(());
+        };
+        screening.check_transaction(tx.chain_id, tx_id, signed_tx).await
+    }
+
+    fn issue_receipt(&self, tx_id: &str, tx_hash: &str, chain_id: u64) {
+        let Some(identity) = &self.identity else {
+            return;
+        };
+        let stored = SignedReceipt::issue(identity, tx_id, tx_hash, chain_id)
+            .and_then(|receipt| self.storage.set_transaction_receipt(tx_id, receipt));
+        if let Err(e) = stored {
+            println!("Failed to issue receipt for transaction {}: {}", tx_id, e);
+        }
+    }
+
+    async fn watch(&self, tx_id: &str, chain_id: u64, tx_hash: H256, signed_tx: &str) {
+        if tx_id.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        self.watching.lock().await.insert(tx_id.to_string(), WatchedTransaction {
+            chain_id,
+            tx_hash,
+            broadcast_at: now,
+            last_sent_at: now,
+            signed_tx: signed_tx.to_string(),
+            replaces: ReplacementKey::from_signed_tx(signed_tx).ok(),
+            rebroadcasts: 0,
+            superseded: Vec::new(),
+        });
+    }
+
+    /// Number of broadcast transactions still waiting for confirmation
+    pub async fn awaiting_confirmation(&self) -> usize {
+        self.watching.lock().await.len()
+    }
+
+    /// Resume watching transactions that were broadcast before a restart
+    async fn restore_watched(&self) -> usize {
+        let broadcast: Vec<_> = self.storage.get_transactions(usize::MAX)
+            .into_iter()
+            .filter(|t| t.status == "broadcast")
+            .filter_map(|t| {
+                let hash = t.tx_hash.as_deref()?.parse::<H256>().ok()?;
+                Some((t.id, t.chain_id, hash, t.signed_tx))
+            })
+            .collect();
+        let restored = broadcast.len();
+        for (tx_id, chain_id, hash, signed_tx) in broadcast {
+            self.watch(&tx_id, chain_id, hash, &signed_tx).await;
+        }
+        restored
+    }
+
+    /// Poll receipts of broadcast transactions once. Transactions with enough
+    /// confirmations for their chain get their final status, block and gas
+    /// recorded; unmined ones are re-sent at the chain's rebroadcast
+    /// interval and marked dropped after `transaction_timeout`.
+    pub async fn check_confirmations(&self) {
+        let watched: Vec<(String, WatchedTransaction)> = self.watching.lock().await
+            .iter()
+            .map(|(id, w)| (id.clone(), w.clone()))
+            .collect();
+        let mut heads: HashMap<u64, Option<u64>> = HashMap::new();
+
+        for (tx_id, watched) in watched {
+            let receipt = match self.find_receipt(&watched).await {
+                Ok(receipt) => receipt,
+                Err(e) => {
+                    log::warn!("Confirmation check for {} failed: {}", tx_id, e);
+                    continue;
+                }
+            };
+
+            let mined = receipt.and_then(|r| Some((r.block_number?.as_u64(), r)));
+            let Some((block_number, receipt)) = mined else {
+                if watched.broadcast_at.elapsed() >= self.config.transaction_timeout {
+                    let _ = self.storage.update_transaction_status_with_error(
+                        &tx_id,
+                        "dropped",
+                        Some(format!("{:?}", watched.tx_hash)),
+                        Some(format!("Not mined within {}s", self.config.transaction_timeout.as_secs())),
+                    );
+                    self.watching.lock().await.remove(&tx_id);
+                    if let Some(monitoring) = &self.monitoring {
+                        monitoring.increment_metric("blockchain_timeouts").await;
+                    }
+                } else if let Some(interval) = self.chain_strategy(watched.chain_id).rebroadcast_interval {
+                    if watched.last_sent_at.elapsed() >= interval
+                        && watched.rebroadcasts < self.config.max_rebroadcasts
+                        && !watched.signed_tx.is_empty()
+                    {
+                        self.rebroadcast(&tx_id, &watched).await;
+                    }
+                }
+                continue;
+            };
+
+            let required_confirmations = self.chain_strategy(watched.chain_id).required_confirmations;
+            if required_confirmations > 1 {
+                let head = match heads.get(&watched.chain_id) {
+                    Some(head) => *head,
+                    None => {
+                        let head = self.blockchain_manager.get_block_number(watched.chain_id).await.ok();
+                        heads.insert(watched.chain_id, head);
+                        head
+                    }
+                };
+                let confirmations = head.map(|head| head.saturating_sub(block_number) + 1).unwrap_or(0);
+                if confirmations < required_confirmations {
+                    continue;
+                }
+            }
+
+            if receipt.transaction_hash != watched.tx_hash {
+                // An earlier fee level was mined before its replacement
+                let _ = self.storage.update_transaction_status_with_error(&tx_id, "broadcast", Some(format!("{:?}", receipt.transaction_hash)), None);
+            }
+            let (status, error) = confirmed_status(receipt.status.map(|s| s.as_u64()));
+            let gas_used = receipt.gas_used.map(|g| g.low_u64());
+            if let Err(e) = self.storage.record_confirmation(&tx_id, status, block_number, gas_used, error.map(str::to_string)) {
+                log::warn!("Failed to record confirmation for {}: {}", tx_id, e);
+                continue;
+            }
+            self.watching.lock().await.remove(&tx_id);
+            log::info!("Transaction {} {} in block {} on chain {}", tx_id, status, block_number, watched.chain_id);
+            if let Some(monitoring) = &self.monitoring {
+                monitoring.increment_metric("blockchain_confirmations").await;
+            }
+        }
+    }
+
+    /// Receipt of the latest version of a watched transaction, or of an
+    /// earlier one it replaced if that was mined instead
+    async fn find_receipt(&self, watched: &WatchedTransaction) -> Result<Option<ethers::types::TransactionReceipt>> {
+        for hash in std::iter::once(watched.tx_hash).chain(watched.superseded.iter().copied()) {
+            if let Some(receipt) = self.blockchain_manager.get_transaction_receipt(watched.chain_id, hash).await? {
+                return Ok(Some(receipt));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Re-send a transaction that is still unmined. Transactions signed by
+    /// this relay (sponsored) are re-signed with bumped fees, up to the
+    /// chain's fee ceiling; others are sent again unchanged.
+    async fn rebroadcast(&self, tx_id: &str, watched: &WatchedTransaction) {
+        let sponsor = self.identity.as_ref()
+            .filter(|identity| watched.replaces.is_some_and(|r| r.sender == identity.address()));
+        let bumped = match sponsor {
+            Some(identity) => self.resign_with_bump(identity, watched).await.unwrap_or_else(|e| {
+                log::warn!("Fee bump of sponsored transaction {} failed, resending as is: {}", tx_id, e);
+                None
+            }),
+            None => None,
+        };
+
+        let mut watching = self.watching.lock().await;
+        let Some(entry) = watching.get_mut(tx_id) else {
+            return;
+        };
+        entry.last_sent_at = Instant::now();
+        entry.rebroadcasts += 1;
+        let metric = match bumped {
+            Some((tx_hash, signed_tx, fee_cap)) => {
+                entry.superseded.push(entry.tx_hash);
+                entry.tx_hash = tx_hash;
+                entry.signed_tx = signed_tx;
+                if let Some(replaces) = entry.replaces.as_mut() {
+                    replaces.fee_cap = fee_cap;
+                }
+                drop(watching);
+                let tx_hash = format!("{:?}", tx_hash);
+                let _ = self.storage.update_transaction_status_with_error(tx_id, "broadcast", Some(tx_hash.clone()), None);
+                self.issue_receipt(tx_id, &tx_hash, watched.chain_id);
+                log::info!("Re-signed sponsored transaction {} as {} at {} wei/gas", tx_id, tx_hash, fee_cap);
+                "blockchain_fee_bumps"
+            }
+            None => {
+                drop(watching);
+                // Nodes answer "already known" while they still hold it; either way the clock restarts
+                if let Err(e) = self.blockchain_manager.rebroadcast(watched.chain_id, &watched.signed_tx).await {
+                    log::debug!("Rebroadcast of {} on chain {}: {}", tx_id, watched.chain_id, e);
+                }
+                "blockchain_rebroadcasts"
+            }
+        };
+        if let Some(monitoring) = &self.monitoring {
+            monitoring.increment_metric(metric).await;
+        }
+    }
+
+    /// Sign the next fee level of a sponsored transaction and send it.
+    /// `None` once the chain's fee ceiling is reached.
+    async fn resign_with_bump(&self, identity: &RelayIdentity, watched: &WatchedTransaction) -> Result<Option<(H256, String, U256)>> {
+        let strategy = self.chain_strategy(watched.chain_id);
+        let tx = rebroadcast::decode_signed(&watched.signed_tx)?;
+        let market = match &self.fee_oracle {
+            Some(oracle) => oracle.estimate(watched.chain_id).await.ok().map(|estimate| estimate.max_fee),
+            None => None,
+        };
+        let request = rebroadcast::bumped_request(&tx, strategy.replacement_bump_percent, market)?;
+        let fee_cap = rebroadcast::request_fee_cap(&request);
+        if strategy.max_fee.is_some_and(|max_fee| fee_cap > max_fee) {
+            return Ok(None);
+        }
+        let signature = identity.wallet().with_chain_id(watched.chain_id).sign_transaction(&request).await
+            .map_err(|e| anyhow!("Failed to sign replacement: {}", e))?;
+        let signed_tx = format!("0x{}", hex::encode(request.rlp_signed(&signature)));
+        let tx_hash = self.blockchain_manager.rebroadcast(watched.chain_id, &signed_tx).await?;
+        Ok(Some((tx_hash, signed_tx, fee_cap)))
+    }
+
+    fn start_confirmation_watcher(&self) {
+        let processor = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(processor.config.confirmation_poll_interval);
+            loop {
+                ticker.tick().await;
+                if !*processor.running.read().await {
+                    break;
+                }
+                processor.check_confirmations().await;
+            }
+            println!("Confirmation watcher stopped.");
+        });
+    }
+
     pub async fn start(&self) -> Result<()> {
+        // Reload persisted work before accepting anything new
+        self.restore_queue().await?;
+        let watched = self.restore_watched().await;
+        if watched > 0 {
+            println!("Watching {} broadcast transactions for confirmation", watched);
+        }
+
         let mut running = self.running.write().await;
         *running = true;
         drop(running);
@@ -258,7 +1055,17 @@ impl TransactionProcessor {
                         queue_guard.pop()
                     };
                     if let Some(tx) = maybe_tx {
-                        processor.process_transaction(tx, &worker_name_for_task).await;
+                        // Workers run outside the submitting request, so carry its id
+                        // and trace across
+                        let id = tx.metadata.get(REQUEST_ID_KEY)
+                            .and_then(|v| v.as_str())
+                            .map(|s| s.to_string())
+                            .unwrap_or_else(request_id::generate);
+                        let span = request_id::request_span(&id);
+                        if let Some(traceparent) = tx.metadata.get(TRACEPARENT_HEADER).and_then(|v| v.as_str()) {
+                            telemetry::set_parent(&span, &HashMap::from([(TRACEPARENT_HEADER.to_string(), traceparent.to_string())]));
+                        }
+                        request_id::scope_in(id, span, processor.process_transaction(tx, &worker_name_for_task)).await;
                     } else {
                         tokio::time::sleep(std::time::Duration::from_millis(500)).await;
                     }
@@ -268,6 +1075,7 @@ impl TransactionProcessor {
             workers_map.insert(worker_name, handle);
         }
         drop(workers_map);
+        self.start_confirmation_watcher();
 
         Ok(())
     }
@@ -287,6 +1095,15 @@ impl Clone for TransactionProcessor {
             metrics: Arc::clone(&self.metrics),
             workers: Arc::clone(&self.workers),
             running: Arc::clone(&self.running),
+            in_flight: Arc::clone(&self.in_flight),
+            identity: self.identity.clone(),
+            memory_guard: self.memory_guard.clone(),
+            monitoring: self.monitoring.clone(),
+            fee_oracle: self.fee_oracle.clone(),
+            screening: self.screening.clone(),
+            token_registry: self.token_registry.clone(),
+            simulator: self.simulator.clone(),
+            watching: Arc::clone(&self.watching),
         }
     }
 }
\ No newline at end of file
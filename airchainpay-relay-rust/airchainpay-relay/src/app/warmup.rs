@@ -0,0 +1,215 @@
+use crate::app::probes::ProbeState;
+use crate::infrastructure::blockchain::fees::FeeOracle;
+use crate::infrastructure::blockchain::manager::BlockchainManager;
+use crate::infrastructure::storage::file_storage::Storage;
+use anyhow::{Result, anyhow};
+use ethers::providers::Middleware;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmupConfig {
+    pub enabled: bool,
+    /// Upper bound on warming each chain; slow chains are left cold rather
+    /// than holding the listener back
+    pub chain_timeout: Duration,
+    /// Refuse to start when storage fails its health check
+    pub require_storage: bool,
+}
+
+impl Default for WarmupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            chain_timeout: Duration::from_secs(15),
+            require_storage: true,
+        }
+    }
+}
+
+impl WarmupConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("WARMUP_ENABLED")
+                .map(|v| v != "false")
+                .unwrap_or(defaults.enabled),
+            chain_timeout: std::env::var("WARMUP_CHAIN_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.chain_timeout),
+            require_storage: std::env::var("WARMUP_REQUIRE_STORAGE")
+                .map(|v| v != "false")
+                .unwrap_or(defaults.require_storage),
+        }
+    }
+}
+
+/// Outcome of warming one chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainWarmup {
+    pub chain_id: u64,
+    pub block_number: Option<u64>,
+    /// Whether the RPC endpoint reports the chain id it is configured for
+    pub chain_id_verified: bool,
+    pub fees_cached: bool,
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
+impl ChainWarmup {
+    pub fn is_warm(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmupReport {
+    pub storage_healthy: bool,
+    pub devices_loaded: usize,
+    pub chains: Vec<ChainWarmup>,
+    pub elapsed_ms: u64,
+}
+
+impl WarmupReport {
+    pub fn warm_chains(&self) -> usize {
+        self.chains.iter().filter(|c| c.is_warm()).count()
+    }
+}
+
+/// Runs once before the listeners bind: opens RPC connections, fills the
+/// fee cache, checks each endpoint's chain id, loads the device registry
+/// and verifies storage, so the first payment after a deploy doesn't pay
+/// for cold connections and empty caches
+pub struct Warmup {
+    storage: Arc<Storage>,
+    blockchain_manager: Arc<BlockchainManager>,
+    fee_oracle: Arc<FeeOracle>,
+    probe_state: Arc<ProbeState>,
+    config: WarmupConfig,
+}
+
+impl Warmup {
+    pub fn new(
+        storage: Arc<Storage>,
+        blockchain_manager: Arc<BlockchainManager>,
+        fee_oracle: Arc<FeeOracle>,
+        probe_state: Arc<ProbeState>,
+        config: WarmupConfig,
+    ) -> Self {
+        Self {
+            storage,
+            blockchain_manager,
+            fee_oracle,
+            probe_state,
+            config,
+        }
+    }
+
+    /// Warm every cache. Fails only when storage is unhealthy and
+    /// `require_storage` is set; cold chains are reported and logged.
+    pub async fn run(&self) -> Result<Option<WarmupReport>> {
+        if !self.config.enabled {
+            return Ok(None);
+        }
+        let started = Instant::now();
+
+        let storage_healthy = self.storage.check_health().await.is_healthy;
+        if !storage_healthy && self.config.require_storage {
+            return Err(anyhow!("storage failed its health check"));
+        }
+        let devices_loaded = self.storage.get_devices().len();
+
+        let chains = futures::future::join_all(
+            self.blockchain_manager.chain_ids().into_iter().map(|chain_id| self.warm_chain(chain_id)),
+        )
+        .await;
+        // Seed the readiness probe so the first probe doesn't repeat the work
+        self.probe_state.record_chain_check(chains.iter().any(ChainWarmup::is_warm));
+
+        let report = WarmupReport {
+            storage_healthy,
+            devices_loaded,
+            chains,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        };
+        for chain in report.chains.iter().filter(|c| !c.is_warm()) {
+            log::warn!("Chain {} is still cold after warmup: {}", chain.chain_id, chain.error.as_deref().unwrap_or_default());
+        }
+        log::info!(
+            "Warmup finished in {}ms: {}/{} chains warm, {} devices loaded, storage {}",
+            report.elapsed_ms,
+            report.warm_chains(),
+            report.chains.len(),
+            report.devices_loaded,
+            if report.storage_healthy { "healthy" } else { "unhealthy" },
+        );
+        Ok(Some(report))
+    }
+
+    async fn warm_chain(&self, chain_id: u64) -> ChainWarmup {
+        let started = Instant::now();
+        let mut warmup = ChainWarmup {
+            chain_id,
+            block_number: None,
+            chain_id_verified: false,
+            fees_cached: false,
+            elapsed_ms: 0,
+            error: None,
+        };
+        let result = tokio::time::timeout(self.config.chain_timeout, self.warm_chain_inner(&mut warmup))
+            .await
+            .unwrap_or_else(|_| Err(anyhow!("timed out after {}s", self.config.chain_timeout.as_secs())));
+        warmup.error = result.err().map(|e| e.to_string());
+        warmup.elapsed_ms = started.elapsed().as_millis() as u64;
+        warmup
+    }
+
+    async fn warm_chain_inner(&self, warmup: &mut ChainWarmup) -> Result<()> {
+        let chain_id = warmup.chain_id;
+        // Opens a connection to every endpoint of the chain and scores them
+        warmup.block_number = Some(self.blockchain_manager.check_chain_connectivity(chain_id).await?);
+
+        let provider = self.blockchain_manager.provider(chain_id)
+            .ok_or_else(|| anyhow!("No provider for chain_id {}", chain_id))?;
+        let reported = provider.get_chainid().await
+            .map_err(|e| anyhow!("eth_chainId failed: {}", e))?;
+        if reported.as_u64() != chain_id {
+            return Err(anyhow!("RPC endpoint reports chain id {}", reported));
+        }
+        warmup.chain_id_verified = true;
+
+        self.fee_oracle.refresh(chain_id).await?;
+        warmup.fees_cached = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_counts_only_chains_without_errors() {
+        let chain = |chain_id, error: Option<&str>| ChainWarmup {
+            chain_id,
+            block_number: Some(1),
+            chain_id_verified: error.is_none(),
+            fees_cached: error.is_none(),
+            elapsed_ms: 5,
+            error: error.map(str::to_string),
+        };
+        let report = WarmupReport {
+            storage_healthy: true,
+            devices_loaded: 3,
+            chains: vec![chain(84532, None), chain(1114, Some("timed out after 15s"))],
+            elapsed_ms: 15_000,
+        };
+        assert_eq!(report.warm_chains(), 1);
+        assert!(!report.chains[1].is_warm());
+    }
+}
@@ -0,0 +1,307 @@
+//! `relay-admin`: operator commands against a running relay's admin listener
+//!
+//! Credentials come from `--admin-key` / `ADMIN_API_KEY` (sent as
+//! `X-Admin-Key`) or `--token` / `RELAY_ADMIN_TOKEN` (an admin JWT).
+
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(name = "relay-admin", version, about = "Administer an AirChainPay relay over its HTTP API")]
+struct Cli {
+    /// Base URL of the relay's admin listener
+    #[arg(long, env = "RELAY_ADMIN_URL", default_value = "http://127.0.0.1:4001")]
+    url: String,
+    #[arg(long, env = "ADMIN_API_KEY", hide_env_values = true)]
+    admin_key: Option<String>,
+    /// Admin JWT, used instead of the admin key when given
+    #[arg(long, env = "RELAY_ADMIN_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+    #[arg(long, short, value_enum, default_value_t = Output::Table)]
+    output: Output,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Output {
+    Table,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create and list backups
+    #[command(subcommand)]
+    Backup(BackupCommand),
+    /// Manage client API keys
+    #[command(subcommand)]
+    ApiKey(ApiKeyCommand),
+    /// Block devices
+    #[command(subcommand)]
+    Device(DeviceCommand),
+    /// Inspect and reset circuit breakers
+    #[command(subcommand)]
+    CircuitBreaker(CircuitBreakerCommand),
+    /// Read audit events
+    #[command(subcommand)]
+    Audit(AuditCommand),
+}
+
+#[derive(Subcommand)]
+enum BackupCommand {
+    Create {
+        /// full, transaction, audit, metrics, configuration or incremental
+        #[arg(long = "type", default_value = "full")]
+        backup_type: String,
+        #[arg(long)]
+        description: Option<String>,
+    },
+    List,
+}
+
+#[derive(Subcommand)]
+enum ApiKeyCommand {
+    List,
+    /// Create a key; it is printed only once
+    Create {
+        #[arg(long)]
+        owner: String,
+        /// device, merchant or admin; repeat for several
+        #[arg(long = "scope")]
+        scopes: Vec<String>,
+        #[arg(long)]
+        expires_in_days: Option<i64>,
+    },
+    /// Replace a key; the old one keeps working for the grace period
+    Rotate {
+        key_id: String,
+        #[arg(long, default_value_t = 3600)]
+        grace_secs: i64,
+    },
+    Revoke { key_id: String },
+}
+
+#[derive(Subcommand)]
+enum DeviceCommand {
+    /// Revoke every certificate of a device so it can no longer authenticate
+    Block { device_id: String },
+}
+
+#[derive(Subcommand)]
+enum CircuitBreakerCommand {
+    Status { operation: String },
+    Reset { operation: String },
+}
+
+#[derive(Subcommand)]
+enum AuditCommand {
+    /// Print the latest audit events, and keep printing new ones with --follow
+    Tail {
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// low, medium, high or critical
+        #[arg(long)]
+        severity: Option<String>,
+        #[arg(long, short)]
+        follow: bool,
+        #[arg(long, default_value_t = 2)]
+        interval_secs: u64,
+    },
+}
+
+struct AdminClient {
+    http: reqwest::Client,
+    url: String,
+    admin_key: Option<String>,
+    token: Option<String>,
+}
+
+impl AdminClient {
+    async fn request(&self, method: reqwest::Method, path: &str, query: &[(&str, String)], body: Option<Value>) -> Result<Value> {
+        let mut request = self.http
+            .request(method, format!("{}{}", self.url.trim_end_matches('/'), path))
+            .query(query);
+        request = match (&self.token, &self.admin_key) {
+            (Some(token), _) => request.bearer_auth(token),
+            (None, Some(key)) => request.header("X-Admin-Key", key),
+            (None, None) => return Err(anyhow!("Set --admin-key (ADMIN_API_KEY) or --token (RELAY_ADMIN_TOKEN)")),
+        };
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request.send().await
+            .map_err(|e| anyhow!("Failed to reach relay at {}: {}", self.url, e))?;
+        let status = response.status();
+        let text = response.text().await?;
+        let value = serde_json::from_str(&text).unwrap_or(Value::String(text));
+        if !status.is_success() {
+            return Err(anyhow!("{} {}: {}", status.as_u16(), path, message_of(&value)));
+        }
+        Ok(value)
+    }
+
+    async fn get(&self, path: &str, query: &[(&str, String)]) -> Result<Value> {
+        self.request(reqwest::Method::GET, path, query, None).await
+    }
+
+    async fn post(&self, path: &str, query: &[(&str, String)], body: Option<Value>) -> Result<Value> {
+        self.request(reqwest::Method::POST, path, query, body).await
+    }
+}
+
+fn message_of(value: &Value) -> String {
+    ["error", "message"].iter()
+        .find_map(|key| value.get(key).and_then(|v| v.as_str()))
+        .map(str::to_string)
+        .unwrap_or_else(|| value.to_string())
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli).await {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let client = AdminClient {
+        http: reqwest::Client::builder().timeout(Duration::from_secs(30)).build()?,
+        url: cli.url,
+        admin_key: cli.admin_key,
+        token: cli.token,
+    };
+    let output = cli.output;
+
+    let response = match cli.command {
+        Command::Backup(BackupCommand::Create { backup_type, description }) => {
+            client.post("/api/backup/create", &[], Some(json!({ "backup_type": backup_type, "description": description }))).await?
+        }
+        Command::Backup(BackupCommand::List) => client.get("/api/backup/list", &[]).await?,
+        Command::ApiKey(ApiKeyCommand::List) => client.get("/api/admin/api-keys", &[]).await?,
+        Command::ApiKey(ApiKeyCommand::Create { owner, scopes, expires_in_days }) => {
+            client.post("/api/admin/api-keys", &[], Some(json!({ "owner": owner, "scopes": scopes, "expires_in_days": expires_in_days }))).await?
+        }
+        Command::ApiKey(ApiKeyCommand::Rotate { key_id, grace_secs }) => {
+            client.post(&format!("/api/admin/api-keys/{key_id}/rotate"), &[("grace_secs", grace_secs.to_string())], None).await?
+        }
+        Command::ApiKey(ApiKeyCommand::Revoke { key_id }) => {
+            client.request(reqwest::Method::DELETE, &format!("/api/admin/api-keys/{key_id}"), &[], None).await?
+        }
+        Command::Device(DeviceCommand::Block { device_id }) => {
+            client.post(&format!("/api/admin/devices/{device_id}/revoke"), &[], None).await?
+        }
+        Command::CircuitBreaker(CircuitBreakerCommand::Status { operation }) => {
+            client.get(&format!("/api/error/circuit-breaker/{operation}"), &[]).await?
+        }
+        Command::CircuitBreaker(CircuitBreakerCommand::Reset { operation }) => {
+            client.post(&format!("/api/error/circuit-breaker/{operation}/reset"), &[], None).await?
+        }
+        Command::Audit(AuditCommand::Tail { limit, severity, follow, interval_secs }) => {
+            return tail_audit(&client, output, limit, severity, follow, Duration::from_secs(interval_secs.max(1))).await;
+        }
+    };
+    print_value(&response, output);
+    Ok(())
+}
+
+async fn tail_audit(client: &AdminClient, output: Output, limit: usize, severity: Option<String>, follow: bool, interval: Duration) -> Result<()> {
+    let mut since: Option<String> = None;
+    let mut seen = std::collections::HashSet::new();
+    let mut header = true;
+    loop {
+        let mut query = vec![("limit", limit.to_string())];
+        if let Some(severity) = &severity {
+            query.push(("severity", severity.clone()));
+        }
+        if let Some(since) = &since {
+            query.push(("start_time", since.clone()));
+        }
+        let response = client.get("/api/audit/events", &query).await?;
+
+        // Newest first from the relay; print oldest first like `tail`
+        let mut events: Vec<Value> = response.get("events")
+            .and_then(|e| e.as_array())
+            .cloned()
+            .unwrap_or_default();
+        events.reverse();
+        events.retain(|e| e.get("id").and_then(|id| id.as_str()).is_none_or(|id| seen.insert(id.to_string())));
+        if let Some(latest) = events.last().and_then(|e| e.get("timestamp")).and_then(|t| t.as_str()) {
+            since = Some(latest.to_string());
+        }
+
+        let columns = ["timestamp", "severity", "event_type", "action", "resource", "success", "device_id"];
+        match output {
+            Output::Json => events.iter().for_each(|e| println!("{e}")),
+            Output::Table if !events.is_empty() => {
+                print!("{}", render_table(&columns, &events, header));
+                header = false;
+            }
+            Output::Table => {}
+        }
+        if !follow {
+            return Ok(());
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+fn print_value(value: &Value, output: Output) {
+    if let Output::Json = output {
+        println!("{}", serde_json::to_string_pretty(value).unwrap_or_default());
+        return;
+    }
+    // A response's first list of objects is shown as rows, anything else as key/value pairs
+    let rows = value.as_object()
+        .and_then(|object| object.values().find_map(|v| v.as_array().filter(|a| a.first().is_some_and(Value::is_object))));
+    match rows {
+        Some(rows) => {
+            let columns: Vec<&str> = rows[0].as_object().map(|o| o.keys().map(String::as_str).collect()).unwrap_or_default();
+            print!("{}", render_table(&columns, rows, true));
+        }
+        None => {
+            let pairs: Vec<Value> = match value.as_object() {
+                Some(object) => object.iter().map(|(k, v)| json!({ "field": k, "value": v })).collect(),
+                None => vec![json!({ "field": "response", "value": value })],
+            };
+            print!("{}", render_table(&["field", "value"], &pairs, false));
+        }
+    }
+}
+
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "-".to_string(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Left-aligned columns sized to their widest cell
+fn render_table(columns: &[&str], rows: &[Value], header: bool) -> String {
+    let cells: Vec<Vec<String>> = rows.iter()
+        .map(|row| columns.iter().map(|c| cell(row.get(c))).collect())
+        .collect();
+    let widths: Vec<usize> = columns.iter().enumerate()
+        .map(|(i, c)| cells.iter().map(|r| r[i].chars().count()).chain([if header { c.len() } else { 0 }]).max().unwrap_or(0))
+        .collect();
+
+    let line = |values: &[String]| -> String {
+        let padded: Vec<String> = values.iter().zip(&widths).map(|(v, w)| format!("{v:<w$}")).collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    };
+    let mut table = String::new();
+    if header {
+        let titles: Vec<String> = columns.iter().map(|c| c.to_uppercase()).collect();
+        table.push_str(&line(&titles));
+    }
+    for row in &cells {
+        table.push_str(&line(row));
+    }
+    table
+}
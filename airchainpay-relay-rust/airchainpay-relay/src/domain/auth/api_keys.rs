@@ -0,0 +1,202 @@
+use super::{constant_time_eq, AuthManager, Role};
+use crate::infrastructure::storage::file_storage::Storage;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, RwLock};
+
+/// Prefix on every issued key, so leaked keys are easy to recognise
+const KEY_PREFIX: &str = "acp_";
+/// `last_used_at` is written back at most this often per key
+const LAST_USED_PERSIST_INTERVAL_MINUTES: i64 = 60;
+
+/// A client API key as stored: only its SHA-256 hash is kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyRecord {
+    pub id: String,
+    pub owner: String,
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    key_hash: String,
+    /// First characters of the key, shown in listings to tell keys apart
+    pub key_prefix: String,
+    /// Roles granted to tokens issued for this key
+    pub scopes: Vec<Role>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Key that replaced this one on rotation
+    pub replaced_by: Option<String>,
+}
+
+impl ApiKeyRecord {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+
+    /// The record without its hash, for API responses
+    pub fn redacted(&self) -> Self {
+        Self {
+            key_hash: String::new(),
+            ..self.clone()
+        }
+    }
+}
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Client API keys exchanged for JWTs at `/auth/token`. Keys are created,
+/// rotated and revoked through the admin API and persisted hashed in
+/// `<data_dir>/api_keys.json`; the plaintext is returned only once.
+pub struct ApiKeyStore {
+    storage: Arc<Storage>,
+    keys: RwLock<Vec<ApiKeyRecord>>,
+}
+
+impl ApiKeyStore {
+    /// Load stored keys. On first start, a legacy `API_KEY` is imported so
+    /// existing clients keep working until it is rotated or revoked.
+    pub fn open(storage: Arc<Storage>) -> Result<Self> {
+        let keys = storage.load_api_keys()?;
+        let store = Self {
+            storage,
+            keys: RwLock::new(keys),
+        };
+        if store.keys.read().unwrap().is_empty() {
+            if let Ok(legacy_key) = std::env::var("API_KEY") {
+                store.insert("legacy-api-key", &legacy_key, vec![Role::Device], None)?;
+                log::info!("Imported API_KEY into the API key store; manage it through /api/admin/api-keys");
+            }
+        }
+        Ok(store)
+    }
+
+    fn insert(&self, owner: &str, key: &str, scopes: Vec<Role>, expires_at: Option<DateTime<Utc>>) -> Result<ApiKeyRecord> {
+        let record = ApiKeyRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            owner: owner.to_string(),
+            key_hash: hash_key(key),
+            // Never more than a quarter of the key, whatever its length
+            key_prefix: key.chars().take((KEY_PREFIX.len() + 6).min(key.len() / 4)).collect(),
+            scopes,
+            created_at: Utc::now(),
+            expires_at,
+            revoked_at: None,
+            last_used_at: None,
+            replaced_by: None,
+        };
+        let mut keys = self.keys.write().unwrap();
+        keys.push(record.clone());
+        self.storage.save_api_keys(&keys)?;
+        Ok(record)
+    }
+
+    /// Create a key and return its record with the plaintext key
+    pub fn create(&self, owner: &str, scopes: Vec<Role>, expires_at: Option<DateTime<Utc>>) -> Result<(ApiKeyRecord, String)> {
+        if owner.trim().is_empty() {
+            return Err(anyhow!("API key owner is required"));
+        }
+        let scopes = if scopes.is_empty() { vec![Role::Device] } else { scopes };
+        let key = format!("{}{}", KEY_PREFIX, AuthManager::generate_random_string(40));
+        let record = self.insert(owner, &key, scopes, expires_at)?;
+        Ok((record.redacted(), key))
+    }
+
+    pub fn list(&self) -> Vec<ApiKeyRecord> {
+        self.keys.read().unwrap().iter().map(ApiKeyRecord::redacted).collect()
+    }
+
+    /// Replace a key with a new one for the same owner and scopes. The old
+    /// key keeps working for `grace`, then expires.
+    pub fn rotate(&self, id: &str, grace: Duration) -> Result<(ApiKeyRecord, String)> {
+        let now = Utc::now();
+        let old = self.keys.read().unwrap().iter()
+            .find(|k| k.id == id)
+            .cloned()
+            .ok_or_else(|| anyhow!("API key {} not found", id))?;
+        if !old.is_active(now) {
+            return Err(anyhow!("API key {} is revoked or expired", id));
+        }
+
+        let (new_record, key) = self.create(&old.owner, old.scopes.clone(), old.expires_at)?;
+        let mut keys = self.keys.write().unwrap();
+        if let Some(record) = keys.iter_mut().find(|k| k.id == id) {
+            let grace_end = now + grace;
+            record.expires_at = Some(record.expires_at.map_or(grace_end, |e| e.min(grace_end)));
+            record.replaced_by = Some(new_record.id.clone());
+        }
+        self.storage.save_api_keys(&keys)?;
+        Ok((new_record, key))
+    }
+
+    /// Revoke a key immediately. The record is kept for the audit trail.
+    pub fn revoke(&self, id: &str) -> Result<ApiKeyRecord> {
+        let mut keys = self.keys.write().unwrap();
+        let record = keys.iter_mut()
+            .find(|k| k.id == id)
+            .ok_or_else(|| anyhow!("API key {} not found", id))?;
+        if record.revoked_at.is_none() {
+            record.revoked_at = Some(Utc::now());
+        }
+        let revoked = record.redacted();
+        self.storage.save_api_keys(&keys)?;
+        Ok(revoked)
+    }
+
+    /// The active key matching `provided`, if any
+    pub fn verify(&self, provided: &str) -> Option<ApiKeyRecord> {
+        let now = Utc::now();
+        let provided_hash = hash_key(provided);
+        let mut keys = self.keys.write().unwrap();
+        let record = keys.iter_mut()
+            .find(|k| constant_time_eq(k.key_hash.as_bytes(), provided_hash.as_bytes()))
+            .filter(|k| k.is_active(now))?;
+
+        let persist = record.last_used_at
+            .is_none_or(|used| now - used > Duration::minutes(LAST_USED_PERSIST_INTERVAL_MINUTES));
+        record.last_used_at = Some(now);
+        let verified = record.redacted();
+        if persist {
+            if let Err(e) = self.storage.save_api_keys(&keys) {
+                log::warn!("Failed to record API key use: {}", e);
+            }
+        }
+        Some(verified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::storage::json_backend::JsonFileBackend;
+
+    #[test]
+    fn test_key_lifecycle_survives_reopen() {
+        let dir = std::env::temp_dir().join(format!("api-keys-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir_str = dir.to_str().unwrap().to_string();
+        let storage = Arc::new(Storage::with_backend(dir_str.clone(), Box::new(JsonFileBackend::open(&dir_str).unwrap())).unwrap());
+
+        let store = ApiKeyStore::open(Arc::clone(&storage)).unwrap();
+        let (record, key) = store.create("merchant-1", vec![Role::Merchant], None).unwrap();
+        assert!(key.starts_with(KEY_PREFIX));
+        assert_eq!(store.verify(&key).unwrap().scopes, vec![Role::Merchant]);
+        assert!(store.verify("acp_wrong").is_none());
+
+        // The old key expires at the end of the grace period
+        let (rotated, new_key) = store.rotate(&record.id, Duration::zero()).unwrap();
+        assert!(store.verify(&key).is_none());
+        assert_eq!(store.verify(&new_key).unwrap().id, rotated.id);
+
+        store.revoke(&rotated.id).unwrap();
+        let reopened = ApiKeyStore::open(storage).unwrap();
+        assert!(reopened.verify(&new_key).is_none());
+        assert_eq!(reopened.list().iter().filter(|k| k.owner == "merchant-1").count(), 2);
+        assert!(reopened.list().iter().all(|k| k.key_hash.is_empty()));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,381 @@
+pub mod api_keys;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use jsonwebtoken::{encode, decode, Header, Validation, EncodingKey, DecodingKey};
+use chrono::{Utc, Duration};
+use rand::Rng;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthRequest {
+    pub device_id: String,
+    pub public_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthResponse {
+    pub token: String,
+    pub expires_at: String,
+    pub status: String,
+}
+
+/// What a token holder may do. Admin satisfies every requirement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Device,
+    Merchant,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Device => "device",
+            Role::Merchant => "merchant",
+            Role::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "device" => Some(Role::Device),
+            "merchant" => Some(Role::Merchant),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    /// Role carried by tokens issued before roles existed
+    fn for_token_type(token_type: &str) -> Option<Self> {
+        match token_type {
+            "admin" => Some(Role::Admin),
+            "merchant" => Some(Role::Merchant),
+            "device" | "relay" => Some(Role::Device),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String, // Subject (user ID)
+    pub exp: i64,    // Expiration time
+    pub iat: i64,    // Issued at
+    pub typ: String, // Token type
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<Role>,
+    /// Id of the API key the token was exchanged for at `/auth/token`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+}
+
+impl Claims {
+    /// Roles granted by the token, falling back to its type for older tokens
+    pub fn effective_roles(&self) -> Vec<Role> {
+        if self.roles.is_empty() {
+            Role::for_token_type(&self.typ).into_iter().collect()
+        } else {
+            self.roles.clone()
+        }
+    }
+
+    pub fn has_role(&self, required: Role) -> bool {
+        self.effective_roles().iter().any(|role| *role == required || *role == Role::Admin)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthManager {
+
+}
+
+impl Default for AuthManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AuthManager {
+    pub fn new() -> Self {
+        Self {
+
+        }
+    }
+
+    /// Generate a secure JWT secret
+    pub fn generate_jwt_secret() -> String {
+        let mut rng = rand::rng();
+        let bytes: [u8; 64] = rng.random(); // 512-bit secret
+        hex::encode(bytes)
+    }
+
+    /// Get JWT secret from environment or generate a new one
+    pub fn get_or_generate_jwt_secret() -> String {
+        std::env::var("JWT_SECRET").unwrap_or_else(|_| {
+            let secret = Self::generate_jwt_secret();
+            println!("JWT_SECRET not found in environment, generated new secret: {secret}");
+            secret
+        })
+    }
+
+    /// Generate a JWT token
+    pub fn generate_jwt_token(subject: &str, token_type: &str) -> String {
+        let roles: Vec<Role> = Role::for_token_type(token_type).into_iter().collect();
+        Self::generate_jwt_token_with_roles(subject, token_type, &roles, Duration::hours(24))
+    }
+
+    /// Generate a JWT token granting `roles` for `ttl`
+    pub fn generate_jwt_token_with_roles(subject: &str, token_type: &str, roles: &[Role], ttl: Duration) -> String {
+        Self::sign_claims(Self::claims(subject, token_type, roles, ttl))
+    }
+
+    /// Generate a JWT token for a client API key, carrying the key's id so
+    /// per-key limits can be applied to whoever holds the token
+    pub fn generate_api_key_token(subject: &str, key_id: &str, roles: &[Role], ttl: Duration) -> String {
+        Self::sign_claims(Claims {
+            key_id: Some(key_id.to_string()),
+            ..Self::claims(subject, "relay", roles, ttl)
+        })
+    }
+
+    fn claims(subject: &str, token_type: &str, roles: &[Role], ttl: Duration) -> Claims {
+        let now = Utc::now();
+        Claims {
+            sub: subject.to_string(),
+            exp: (now + ttl).timestamp(),
+            iat: now.timestamp(),
+            typ: token_type.to_string(),
+            roles: roles.to_vec(),
+            key_id: None,
+        }
+    }
+
+    fn sign_claims(claims: Claims) -> String {
+        let secret = Self::get_or_generate_jwt_secret();
+        match encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret.as_ref()),
+        ) {
+            Ok(token) => token,
+            Err(e) => {
+                println!("Failed to generate JWT token: {e}");
+                String::new()
+            }
+        }
+    }
+
+    /// Verify a JWT token
+    pub fn verify_jwt_token(token: &str) -> Result<Claims, Box<dyn std::error::Error>> {
+        let secret = Self::get_or_generate_jwt_secret();
+        
+        let token_data = decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(secret.as_ref()),
+            &Validation::default(),
+        )?;
+
+        Ok(token_data.claims)
+    }
+
+    // Removed authenticate_device method
+
+    /// Generate secure secrets for production
+    pub fn generate_production_secrets() -> HashMap<String, String> {
+        let mut secrets = HashMap::new();
+        
+        // Generate JWT secret
+        secrets.insert("JWT_SECRET".to_string(), Self::generate_jwt_secret());
+        
+        // Generate API key
+        secrets.insert("API_KEY".to_string(), Self::generate_random_string(32));
+        
+        // Generate admin listener key
+        secrets.insert("ADMIN_API_KEY".to_string(), Self::generate_random_string(48));
+        
+        // Generate database password
+        secrets.insert("DATABASE_PASSWORD".to_string(), Self::generate_random_string(16));
+        
+        // Generate Redis password
+        secrets.insert("REDIS_PASSWORD".to_string(), Self::generate_random_string(16));
+        
+        // Generate encryption key
+        secrets.insert("ENCRYPTION_KEY".to_string(), Self::generate_random_string(32));
+        
+        secrets
+    }
+
+    /// Generate random string
+    pub(crate) fn generate_random_string(length: usize) -> String {
+        let mut rng = rand::rng();
+        let chars: Vec<char> = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789".chars().collect();
+        
+        (0..length)
+            .map(|_| chars[rng.random_range(0..chars.len())])
+            .collect()
+    }
+
+
+}
+
+// Public function for generating JWT tokens (used by API endpoints)
+pub fn generate_jwt_token(subject: &str, token_type: &str) -> String {
+    AuthManager::generate_jwt_token(subject, token_type)
+}
+
+// Public function for verifying JWT tokens
+#[allow(dead_code)]
+pub fn verify_jwt_token(token: &str) -> Result<Claims, Box<dyn std::error::Error>> {
+    AuthManager::verify_jwt_token(token)
+}
+
+// Public function for generating JWT tokens with explicit roles
+pub fn generate_jwt_token_with_roles(subject: &str, token_type: &str, roles: &[Role], ttl: Duration) -> String {
+    AuthManager::generate_jwt_token_with_roles(subject, token_type, roles, ttl)
+}
+
+// Public function for generating JWT tokens for a client API key
+pub fn generate_api_key_token(subject: &str, key_id: &str, roles: &[Role], ttl: Duration) -> String {
+    AuthManager::generate_api_key_token(subject, key_id, roles, ttl)
+}
+
+/// An authenticated caller and the roles it holds
+#[derive(Debug, Clone)]
+pub struct Caller {
+    pub subject: String,
+    pub roles: Vec<Role>,
+}
+
+impl Caller {
+    pub fn has_role(&self, required: Role) -> bool {
+        self.roles.iter().any(|role| *role == required || *role == Role::Admin)
+    }
+}
+
+/// Identify a caller from the `Authorization` and `X-Admin-Key` header values.
+///
+/// A bearer JWT grants the roles it carries; the `ADMIN_API_KEY` shared
+/// secret grants admin. `Ok(None)` means no credentials were presented.
+pub fn authenticate_caller(authorization: Option<&str>, admin_key: Option<&str>) -> Result<Option<Caller>, String> {
+    if let Some(token) = authorization.and_then(|h| h.strip_prefix("Bearer ")) {
+        let claims = verify_jwt_token(token.trim()).map_err(|_| "Invalid or expired token".to_string())?;
+        return Ok(Some(Caller {
+            roles: claims.effective_roles(),
+            subject: claims.sub,
+        }));
+    }
+
+    if let Some(provided) = admin_key {
+        let expected = std::env::var("ADMIN_API_KEY").map_err(|_| "Admin API key is not configured".to_string())?;
+        if constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+            return Ok(Some(Caller {
+                subject: "admin-api-key".to_string(),
+                roles: vec![Role::Admin],
+            }));
+        }
+        return Err("Invalid admin API key".to_string());
+    }
+
+    Ok(None)
+}
+
+/// Authorize an admin caller from the `Authorization` and `X-Admin-Key` header values.
+///
+/// Accepts a bearer JWT carrying the admin role, or the `ADMIN_API_KEY`
+/// shared secret. Returns the caller identity on success.
+pub fn authorize_admin_credentials(authorization: Option<&str>, admin_key: Option<&str>) -> Result<String, String> {
+    match authenticate_caller(authorization, admin_key)? {
+        Some(caller) if caller.has_role(Role::Admin) => Ok(caller.subject),
+        Some(_) => Err("Token is not authorized for admin access".to_string()),
+        None => Err("Admin credentials required".to_string()),
+    }
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// Public function for generating production secrets
+#[allow(dead_code)]
+pub fn generate_production_secrets() -> HashMap<String, String> {
+    AuthManager::generate_production_secrets()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jwt_secret_generation() {
+        let secret1 = AuthManager::generate_jwt_secret();
+        let secret2 = AuthManager::generate_jwt_secret();
+        
+        assert_eq!(secret1.len(), 128); // 64 bytes = 128 hex chars
+        assert_ne!(secret1, secret2); // Should be different each time
+    }
+
+    #[test]
+    fn test_jwt_token_generation_and_verification() {
+        // Set a consistent JWT secret for this test
+        std::env::set_var("JWT_SECRET", "test_secret_for_jwt_verification_1234567890abcdef");
+        
+        let token = AuthManager::generate_jwt_token("test_device", "device");
+        assert!(!token.is_empty());
+        
+        let claims = AuthManager::verify_jwt_token(&token).unwrap();
+        assert_eq!(claims.sub, "test_device");
+        assert_eq!(claims.typ, "device");
+        assert_eq!(claims.key_id, None);
+
+        let token = AuthManager::generate_api_key_token("merchant-1", "key-1", &[Role::Merchant], Duration::hours(1));
+        let claims = AuthManager::verify_jwt_token(&token).unwrap();
+        assert_eq!(claims.key_id.as_deref(), Some("key-1"));
+        
+        // Clean up
+        std::env::remove_var("JWT_SECRET");
+    }
+
+    #[test]
+    fn test_roles_from_claims() {
+        let mut claims = Claims {
+            sub: "merchant-1".to_string(),
+            exp: 0,
+            iat: 0,
+            typ: "merchant".to_string(),
+            roles: vec![],
+            key_id: None,
+        };
+        // Tokens without roles fall back to their type
+        assert_eq!(claims.effective_roles(), vec![Role::Merchant]);
+        assert!(claims.has_role(Role::Merchant));
+        assert!(!claims.has_role(Role::Admin));
+
+        claims.roles = vec![Role::Admin];
+        assert!(claims.has_role(Role::Merchant));
+
+        claims.typ = "relay".to_string();
+        claims.roles.clear();
+        assert!(!claims.has_role(Role::Merchant));
+    }
+
+    #[test]
+    fn test_production_secrets_generation() {
+        let secrets = AuthManager::generate_production_secrets();
+        
+        assert!(secrets.contains_key("JWT_SECRET"));
+        assert!(secrets.contains_key("API_KEY"));
+        assert!(secrets.contains_key("DATABASE_PASSWORD"));
+        assert!(secrets.contains_key("REDIS_PASSWORD"));
+        assert!(secrets.contains_key("ENCRYPTION_KEY"));
+        
+        assert_eq!(secrets["JWT_SECRET"].len(), 128);
+        assert_eq!(secrets["API_KEY"].len(), 32); 
+        assert_eq!(secrets["DATABASE_PASSWORD"].len(), 16);
+        assert_eq!(secrets["REDIS_PASSWORD"].len(), 16);
+        assert_eq!(secrets["ENCRYPTION_KEY"].len(), 32);
+    }
+} 
\ No newline at end of file
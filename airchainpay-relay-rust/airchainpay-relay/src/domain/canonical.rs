@@ -0,0 +1,134 @@
+//! Canonical binary encoding for signed payloads
+//!
+//! A subset of CBOR with deterministic encoding (RFC 8949 §4.2), so the relay,
+//! the wallet core and its WASM build produce identical bytes for the same
+//! values regardless of JSON key order or formatting:
+//!
+//! - a payload is a definite-length array whose first item is a text tag
+//!   naming it and its version, e.g. `airchainpay/relay-receipt/1`
+//! - integers, lengths and array sizes use the shortest head
+//! - no maps, floats, tags or indefinite lengths; absent values are `null`
+//! - addresses are 20-byte strings and hashes are lowercase `0x` text
+//!
+//! The wallet core carries the same encoder; the two must change together.
+
+use anyhow::{Result, anyhow};
+
+const MAJOR_UINT: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const SIMPLE_NULL: u8 = 0xf6;
+
+#[derive(Debug, Default)]
+pub struct CanonicalEncoder {
+    out: Vec<u8>,
+}
+
+impl CanonicalEncoder {
+    /// Start a payload of `fields` items after its `tag`
+    pub fn payload(tag: &str, fields: usize) -> Self {
+        let mut encoder = Self::default();
+        encoder.array(fields + 1);
+        encoder.text(tag);
+        encoder
+    }
+
+    fn head(&mut self, major: u8, value: u64) {
+        let major = major << 5;
+        match value {
+            0..=23 => self.out.push(major | value as u8),
+            24..=0xff => self.out.extend_from_slice(&[major | 24, value as u8]),
+            0x100..=0xffff => {
+                self.out.push(major | 25);
+                self.out.extend_from_slice(&(value as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                self.out.push(major | 26);
+                self.out.extend_from_slice(&(value as u32).to_be_bytes());
+            }
+            _ => {
+                self.out.push(major | 27);
+                self.out.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+    }
+
+    pub fn uint(&mut self, value: u64) -> &mut Self {
+        self.head(MAJOR_UINT, value);
+        self
+    }
+
+    pub fn int(&mut self, value: i64) -> &mut Self {
+        if value < 0 {
+            self.head(MAJOR_NEGATIVE, !(value as u64));
+        } else {
+            self.head(MAJOR_UINT, value as u64);
+        }
+        self
+    }
+
+    pub fn bytes(&mut self, value: &[u8]) -> &mut Self {
+        self.head(MAJOR_BYTES, value.len() as u64);
+        self.out.extend_from_slice(value);
+        self
+    }
+
+    pub fn text(&mut self, value: &str) -> &mut Self {
+        self.head(MAJOR_TEXT, value.len() as u64);
+        self.out.extend_from_slice(value.as_bytes());
+        self
+    }
+
+    pub fn array(&mut self, len: usize) -> &mut Self {
+        self.head(MAJOR_ARRAY, len as u64);
+        self
+    }
+
+    pub fn null(&mut self) -> &mut Self {
+        self.out.push(SIMPLE_NULL);
+        self
+    }
+
+    /// A 20-byte address from its hex form, in any letter case
+    pub fn address(&mut self, address: &str) -> Result<&mut Self> {
+        let bytes = hex::decode(address.trim_start_matches("0x"))
+            .map_err(|_| anyhow!("Invalid address {}", address))?;
+        if bytes.len() != 20 {
+            return Err(anyhow!("Invalid address {}", address));
+        }
+        Ok(self.bytes(&bytes))
+    }
+
+    /// A hash or other hex identifier, normalised to lowercase `0x` text
+    pub fn hex_text(&mut self, value: &str) -> &mut Self {
+        let normalized = format!("0x{}", value.trim_start_matches("0x").to_lowercase());
+        self.text(&normalized)
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortest_form_heads() {
+        let mut encoder = CanonicalEncoder::payload("t/1", 5);
+        encoder.uint(23).uint(24).uint(1000).int(-1).null();
+        assert_eq!(
+            encoder.finish(),
+            vec![0x86, 0x63, b't', b'/', b'1', 0x17, 0x18, 0x18, 0x19, 0x03, 0xe8, 0x20, 0xf6]
+        );
+
+        let mut lower = CanonicalEncoder::default();
+        lower.address("0xabcdefabcdefabcdefabcdefabcdefabcdefabcd").unwrap().hex_text("0xABC");
+        let mut upper = CanonicalEncoder::default();
+        upper.address("0xABCDEFABCDEFABCDEFABCDEFABCDEFABCDEFABCD").unwrap().hex_text("abc");
+        assert_eq!(lower.finish(), upper.finish());
+    }
+}
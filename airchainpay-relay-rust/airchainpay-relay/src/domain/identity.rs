@@ -0,0 +1,113 @@
+use anyhow::{Result, anyhow};
+use ethers::core::rand::thread_rng;
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{Address, Signature};
+use ethers::utils::hash_message;
+use std::path::Path;
+use std::str::FromStr;
+
+const IDENTITY_FILE: &str = "relay_identity.key";
+
+/// Long-lived secp256k1 key identifying this relay to peers and wallets.
+///
+/// Messages are signed as EIP-191 personal messages, so anyone holding the
+/// published address can verify them with standard Ethereum tooling.
+#[derive(Debug, Clone)]
+pub struct RelayIdentity {
+    wallet: LocalWallet,
+}
+
+impl RelayIdentity {
+    /// Use `RELAY_IDENTITY_KEY` if set, otherwise the key stored in
+    /// `<data_dir>/relay_identity.key`, generating one on first start.
+    pub fn load_or_create(data_dir: &str) -> Result<Self> {
+        if let Ok(key) = std::env::var("RELAY_IDENTITY_KEY") {
+            return Self::from_key(&key);
+        }
+
+        let path = Path::new(data_dir).join(IDENTITY_FILE);
+        if path.exists() {
+            return Self::from_key(std::fs::read_to_string(&path)?.trim());
+        }
+
+        let wallet = LocalWallet::new(&mut thread_rng());
+        std::fs::create_dir_all(data_dir)?;
+        std::fs::write(&path, hex::encode(wallet.signer().to_bytes()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        log::info!("Generated relay identity {:?} at {}", wallet.address(), path.display());
+        Ok(Self { wallet })
+    }
+
+    pub fn from_key(key: &str) -> Result<Self> {
+        let wallet = LocalWallet::from_str(key.trim_start_matches("0x"))
+            .map_err(|e| anyhow!("Invalid relay identity key: {}", e))?;
+        Ok(Self { wallet })
+    }
+
+    pub fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    /// Compressed SEC1 secp256k1 public key, `0x`-prefixed hex
+    pub fn public_key(&self) -> String {
+        let point = self.wallet.signer().verifying_key().to_encoded_point(true);
+        format!("0x{}", hex::encode(point.as_bytes()))
+    }
+
+    /// Signer for on-chain transactions sent as this relay
+    pub fn wallet(&self) -> LocalWallet {
+        self.wallet.clone()
+    }
+
+    /// Hex-encoded EIP-191 signature over `message`
+    pub fn sign(&self, message: &[u8]) -> Result<String> {
+        let signature = self.wallet.sign_hash(hash_message(message))
+            .map_err(|e| anyhow!("Failed to sign message: {}", e))?;
+        Ok(format!("0x{signature}"))
+    }
+}
+
+/// Check that `signature` over `message` was produced by `signer`
+pub fn verify_signature(message: &[u8], signature: &str, signer: Address) -> bool {
+    Signature::from_str(signature.trim_start_matches("0x"))
+        .map(|sig| sig.verify(message, signer).is_ok())
+        .unwrap_or(false)
+}
+
+/// Message the relay signs to bind its Noise static key to its identity in
+/// `/.well-known/airchainpay-relay`, so a wallet that pinned the identity
+/// address can trust the Noise key it is handed
+pub fn noise_key_binding_message(relay: Address, noise_static_key: Option<&str>, issued_at: &str) -> String {
+    format!(
+        "AirChainPay relay identity\nrelay: {:?}\nnoise_static_key: {}\nissued_at: {}",
+        relay,
+        noise_static_key.unwrap_or("none"),
+        issued_at
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::core::k256::elliptic_curve::sec1::ToEncodedPoint;
+    use ethers::utils::keccak256;
+
+    #[test]
+    fn test_public_key_matches_address_and_binding_verifies() {
+        let identity = RelayIdentity::from_key("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318").unwrap();
+        let public_key = hex::decode(identity.public_key().trim_start_matches("0x")).unwrap();
+        assert_eq!(public_key.len(), 33);
+
+        let uncompressed = ethers::core::k256::PublicKey::from_sec1_bytes(&public_key).unwrap();
+        let hash = keccak256(&uncompressed.to_encoded_point(false).as_bytes()[1..]);
+        assert_eq!(Address::from_slice(&hash[12..]), identity.address());
+
+        let message = noise_key_binding_message(identity.address(), Some("ab"), "2026-01-01T00:00:00Z");
+        let signature = identity.sign(message.as_bytes()).unwrap();
+        assert!(verify_signature(message.as_bytes(), &signature, identity.address()));
+    }
+}
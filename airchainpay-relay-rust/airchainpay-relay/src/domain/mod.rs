@@ -1,4 +1,7 @@
 pub mod error;
 pub mod auth;
 pub mod security;
+pub mod identity;
+pub mod receipt;
+pub mod canonical;
 
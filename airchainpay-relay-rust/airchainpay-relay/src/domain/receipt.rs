@@ -0,0 +1,136 @@
+use crate::domain::canonical::CanonicalEncoder;
+use crate::domain::identity::{self, RelayIdentity};
+use anyhow::Result;
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+
+/// Tag of the canonical receipt encoding
+pub const RECEIPT_TAG: &str = "airchainpay/relay-receipt/1";
+
+/// What the relay attests to once it has broadcast a payment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayReceipt {
+    pub transaction_id: String,
+    pub tx_hash: String,
+    pub chain_id: u64,
+    /// Unix seconds at broadcast
+    pub timestamp: i64,
+    /// Identity address of the relay that handled the payment
+    pub relay: Address,
+}
+
+impl RelayReceipt {
+    /// Plain-text message the relay signs (EIP-191 personal message), so a
+    /// wallet can rebuild it from the fields and recover the signer
+    pub fn message(&self) -> String {
+        format!(
+            "AirChainPay relay receipt\ntransaction: {}\ntx_hash: {}\nchain_id: {}\ntimestamp: {}\nrelay: {:?}",
+            self.transaction_id, self.tx_hash, self.chain_id, self.timestamp, self.relay
+        )
+    }
+
+    /// Canonical binary form the relay signs (EIP-191) for `cbor-1` receipts
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let mut encoder = CanonicalEncoder::payload(RECEIPT_TAG, 5);
+        encoder
+            .text(&self.transaction_id)
+            .hex_text(&self.tx_hash)
+            .uint(self.chain_id)
+            .int(self.timestamp)
+            .bytes(self.relay.as_bytes());
+        encoder.finish()
+    }
+}
+
+/// What a receipt signature covers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReceiptEncoding {
+    /// `RelayReceipt::message`; receipts stored before canonical encoding
+    #[default]
+    #[serde(rename = "text")]
+    Text,
+    /// `RelayReceipt::canonical_bytes`
+    #[serde(rename = "cbor-1")]
+    Cbor,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedReceipt {
+    #[serde(flatten)]
+    pub receipt: RelayReceipt,
+    pub signature: String,
+    #[serde(default)]
+    pub encoding: ReceiptEncoding,
+}
+
+impl SignedReceipt {
+    pub fn issue(identity: &RelayIdentity, transaction_id: &str, tx_hash: &str, chain_id: u64) -> Result<Self> {
+        let receipt = RelayReceipt {
+            transaction_id: transaction_id.to_string(),
+            tx_hash: tx_hash.to_string(),
+            chain_id,
+            timestamp: chrono::Utc::now().timestamp(),
+            relay: identity.address(),
+        };
+        let signature = identity.sign(&receipt.canonical_bytes())?;
+        Ok(Self { receipt, signature, encoding: ReceiptEncoding::Cbor })
+    }
+
+    /// The exact bytes the signature covers
+    pub fn signed_payload(&self) -> Vec<u8> {
+        match self.encoding {
+            ReceiptEncoding::Text => self.receipt.message().into_bytes(),
+            ReceiptEncoding::Cbor => self.receipt.canonical_bytes(),
+        }
+    }
+
+    /// True if the signature was made by the relay named in the receipt
+    pub fn verify(&self) -> bool {
+        identity::verify_signature(&self.signed_payload(), &self.signature, self.receipt.relay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[test]
+    fn test_receipt_signature_verifies() {
+        let identity = RelayIdentity::from_key(TEST_KEY).unwrap();
+        let receipt = SignedReceipt::issue(&identity, "tx-1", "0xabc", 1114).unwrap();
+
+        assert_eq!(receipt.receipt.relay, identity.address());
+        assert!(receipt.verify());
+    }
+
+    #[test]
+    fn test_tampered_receipt_fails_verification() {
+        let identity = RelayIdentity::from_key(TEST_KEY).unwrap();
+        let mut receipt = SignedReceipt::issue(&identity, "tx-1", "0xabc", 1114).unwrap();
+        receipt.receipt.chain_id = 84532;
+
+        assert!(!receipt.verify());
+    }
+
+    #[test]
+    fn test_text_receipts_still_verify() {
+        let identity = RelayIdentity::from_key(TEST_KEY).unwrap();
+        let mut receipt = SignedReceipt::issue(&identity, "tx-1", "0xabc", 1114).unwrap();
+        assert_eq!(receipt.encoding, ReceiptEncoding::Cbor);
+        let payload = receipt.signed_payload();
+        assert_eq!(payload[..3], [0x86, 0x78, 27]);
+        assert_eq!(&payload[3..30], RECEIPT_TAG.as_bytes());
+
+        receipt.signature = identity.sign(receipt.receipt.message().as_bytes()).unwrap();
+        receipt.encoding = ReceiptEncoding::Text;
+        assert!(receipt.verify());
+        let stored: SignedReceipt = serde_json::from_value(serde_json::json!({
+            "transaction_id": "tx-1", "tx_hash": "0xabc", "chain_id": 1114,
+            "timestamp": receipt.receipt.timestamp, "relay": identity.address(),
+            "signature": receipt.signature,
+        })).unwrap();
+        assert!(stored.verify());
+    }
+}
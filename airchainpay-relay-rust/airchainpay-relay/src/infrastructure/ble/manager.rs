@@ -0,0 +1,734 @@
+//! GATT peripheral letting phones pay the relay directly over BLE
+//!
+//! The relay advertises the AirChainPay service with two characteristics,
+//! each accepting writes and sending notifications:
+//!
+//! - control: JSON messages for the Noise XX handshake (`noise_init`,
+//!   `noise_finish`, `close`), as on the WebSocket bridge
+//! - payment: protobuf `EncryptedTransactionPayload` frames, answered with
+//!   encrypted `TransactionResult` frames and later status updates
+//!
+//! Every message written or notified ends in the CRC-32 of its bytes and is
+//! split into frames carrying a 4-byte header (sequence number and frame
+//! count, big-endian u16), the same framing the wallet core's `BleCentral`
+//! uses. Notifications reach every subscribed
+//! central, so replies carry the device-chosen `handshake_id` or are sealed
+//! to the session, and shared-key `hello` sessions are not offered here.
+//!
+//! The radio side needs BlueZ and the `ble-peripheral` feature.
+
+use anyhow::{Result, anyhow};
+use base64::{engine::general_purpose, Engine as _};
+use prost::Message as ProstMessage;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Mutex};
+use uuid::Uuid;
+use crate::airchainpay::TransactionResult;
+use crate::app::transaction_service::TransactionProcessor;
+use crate::domain::auth;
+use crate::infrastructure::ble::noise::{NoiseHandshake, NOISE_PATTERN, NOISE_SESSION_VERSION};
+use crate::infrastructure::ble::payments::{finish_noise, handle_frame, result_frame, start_noise};
+use crate::infrastructure::ble::session::{BleSessionManager, SessionTransport};
+use crate::infrastructure::monitoring::manager::{BleSessionMetrics, MonitoringManager};
+use crate::infrastructure::storage::file_storage::{StatusEvent, Storage, TERMINAL_STATUSES};
+use crate::utils::payload_codec::{negotiate_version, PAYLOAD_CODEC_VERSIONS};
+
+/// Service and payment characteristic match the mobile wallet's `BluetoothManager`
+pub const AIRCHAINPAY_SERVICE_UUID: Uuid = Uuid::from_u128(0x0000abcd_0000_1000_8000_00805f9b34fb);
+pub const PAYMENT_CHARACTERISTIC_UUID: Uuid = Uuid::from_u128(0x0000abce_0000_1000_8000_00805f9b34fb);
+pub const CONTROL_CHARACTERISTIC_UUID: Uuid = Uuid::from_u128(0x0000abcf_0000_1000_8000_00805f9b34fb);
+
+/// Frame header: sequence number and frame count, both big-endian u16
+pub const FRAME_HEADER_SIZE: usize = 4;
+/// Trailing checksum on every message
+pub const CRC_SIZE: usize = 4;
+/// Smallest usable ATT payload (default MTU 23 minus 3 bytes of ATT header)
+pub const MIN_FRAME_SIZE: usize = 20;
+/// Peers that have not written for this long are forgotten
+const PEER_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+/// Outbound messages buffered per characteristic before the oldest are dropped
+const OUTBOUND_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct BLEManagerConfig {
+    pub enabled: bool,
+    /// BlueZ adapter name such as `hci0`; the default adapter if unset
+    pub adapter: Option<String>,
+    pub local_name: String,
+    /// Bytes per notification including the frame header; keep within the
+    /// ATT MTU phones negotiate
+    pub notify_frame_size: usize,
+    /// Largest reassembled write accepted from one peer
+    pub max_payload_bytes: usize,
+}
+
+impl Default for BLEManagerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            adapter: None,
+            local_name: "AirChainPay Relay".to_string(),
+            notify_frame_size: 182,
+            max_payload_bytes: 16 * 1024,
+        }
+    }
+}
+
+impl BLEManagerConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("BLE_PERIPHERAL_ENABLED")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.enabled),
+            adapter: std::env::var("BLE_ADAPTER").ok().filter(|a| !a.trim().is_empty()),
+            local_name: std::env::var("BLE_LOCAL_NAME")
+                .ok()
+                .filter(|n| !n.trim().is_empty())
+                .unwrap_or(defaults.local_name),
+            notify_frame_size: std::env::var("BLE_NOTIFY_FRAME_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n: &usize| *n >= MIN_FRAME_SIZE && *n <= 512)
+                .unwrap_or(defaults.notify_frame_size),
+            max_payload_bytes: std::env::var("BLE_MAX_PAYLOAD_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n: &usize| *n > 0)
+                .unwrap_or(defaults.max_payload_bytes),
+        }
+    }
+}
+
+/// Append the CRC-32 of `payload`
+pub fn seal_message(payload: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(payload.len() + CRC_SIZE);
+    message.extend_from_slice(payload);
+    message.extend_from_slice(&crc32fast::hash(payload).to_be_bytes());
+    message
+}
+
+/// Check and strip the trailing CRC-32
+pub fn open_message(mut message: Vec<u8>) -> Result<Vec<u8>> {
+    if message.len() < CRC_SIZE {
+        return Err(anyhow!("Message shorter than its checksum"));
+    }
+    let crc = message.split_off(message.len() - CRC_SIZE);
+    if crc32fast::hash(&message).to_be_bytes()[..] != crc[..] {
+        return Err(anyhow!("Message checksum mismatch"));
+    }
+    Ok(message)
+}
+
+/// Split a payload into frames of at most `frame_size` bytes, each prefixed
+/// with its sequence number and the total frame count
+pub fn chunk_payload(payload: &[u8], frame_size: usize) -> Result<Vec<Vec<u8>>> {
+    if frame_size < MIN_FRAME_SIZE {
+        return Err(anyhow!("Frame size {} is below the minimum of {}", frame_size, MIN_FRAME_SIZE));
+    }
+    let body = frame_size - FRAME_HEADER_SIZE;
+    let total = payload.len().div_ceil(body).max(1);
+    if total > u16::MAX as usize {
+        return Err(anyhow!("Payload of {} bytes needs too many frames", payload.len()));
+    }
+
+    Ok((0..total)
+        .map(|seq| {
+            let chunk = &payload[(seq * body).min(payload.len())..((seq + 1) * body).min(payload.len())];
+            let mut frame = Vec::with_capacity(FRAME_HEADER_SIZE + chunk.len());
+            frame.extend_from_slice(&(seq as u16).to_be_bytes());
+            frame.extend_from_slice(&(total as u16).to_be_bytes());
+            frame.extend_from_slice(chunk);
+            frame
+        })
+        .collect())
+}
+
+/// Collects frames, in any order, until the payload is complete
+#[derive(Debug, Default)]
+pub struct Reassembler {
+    frames: Vec<Option<Vec<u8>>>,
+    received: usize,
+    bytes: usize,
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a frame; returns the payload once every frame has arrived
+    pub fn push(&mut self, frame: &[u8], max_payload_bytes: usize) -> Result<Option<Vec<u8>>> {
+        if frame.len() < FRAME_HEADER_SIZE {
+            return Err(anyhow!("Frame shorter than its header"));
+        }
+        let seq = u16::from_be_bytes([frame[0], frame[1]]) as usize;
+        let total = u16::from_be_bytes([frame[2], frame[3]]) as usize;
+        if total == 0 || seq >= total {
+            return Err(anyhow!("Invalid frame {} of {}", seq, total));
+        }
+        if self.frames.is_empty() {
+            self.frames = vec![None; total];
+        } else if self.frames.len() != total {
+            return Err(anyhow!("Frame count changed mid-payload"));
+        }
+
+        if self.frames[seq].is_none() {
+            self.bytes += frame.len() - FRAME_HEADER_SIZE;
+            if self.bytes > max_payload_bytes {
+                return Err(anyhow!("Payload exceeds {} bytes", max_payload_bytes));
+            }
+            self.frames[seq] = Some(frame[FRAME_HEADER_SIZE..].to_vec());
+            self.received += 1;
+        }
+        if self.received < total {
+            return Ok(None);
+        }
+
+        let payload = self.frames.drain(..).flatten().flatten().collect();
+        self.received = 0;
+        self.bytes = 0;
+        Ok(Some(payload))
+    }
+
+    /// Whether `frame` repeats one already collected, i.e. the central
+    /// retransmitted it
+    pub fn has_frame(&self, frame: &[u8]) -> bool {
+        if frame.len() < FRAME_HEADER_SIZE {
+            return false;
+        }
+        let seq = u16::from_be_bytes([frame[0], frame[1]]) as usize;
+        matches!(self.frames.get(seq), Some(Some(_)))
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Characteristic a write arrived on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BleChannel {
+    Control,
+    Payment,
+}
+
+/// A connected central, keyed by its Bluetooth address
+struct Peer {
+    device_id: Option<String>,
+    handshake: Option<NoiseHandshake>,
+    session_id: Option<String>,
+    control_in: Reassembler,
+    payment_in: Reassembler,
+    last_seen: Instant,
+    handshake_started: Option<Instant>,
+    metrics: BleSessionMetrics,
+}
+
+impl Peer {
+    fn new(address: &str) -> Self {
+        Self {
+            device_id: None,
+            handshake: None,
+            session_id: None,
+            control_in: Reassembler::new(),
+            payment_in: Reassembler::new(),
+            last_seen: Instant::now(),
+            handshake_started: None,
+            metrics: BleSessionMetrics::new(address),
+        }
+    }
+}
+
+/// Serves the AirChainPay GATT service and routes writes into the same
+/// session and payment handling as the WebSocket bridge
+pub struct BLEManager {
+    config: BLEManagerConfig,
+    sessions: Arc<BleSessionManager>,
+    storage: Arc<Storage>,
+    processor: Arc<TransactionProcessor>,
+    peers: Mutex<HashMap<String, Peer>>,
+    /// Transactions submitted over BLE, by id, and the session to report to
+    watched: Mutex<HashMap<String, String>>,
+    control_out: broadcast::Sender<Vec<u8>>,
+    payment_out: broadcast::Sender<Vec<u8>>,
+    monitoring: Option<Arc<MonitoringManager>>,
+}
+
+impl BLEManager {
+    pub fn new(
+        config: BLEManagerConfig,
+        sessions: Arc<BleSessionManager>,
+        storage: Arc<Storage>,
+        processor: Arc<TransactionProcessor>,
+    ) -> Self {
+        let (control_out, _) = broadcast::channel(OUTBOUND_CAPACITY);
+        let (payment_out, _) = broadcast::channel(OUTBOUND_CAPACITY);
+        Self {
+            config,
+            sessions,
+            storage,
+            processor,
+            peers: Mutex::new(HashMap::new()),
+            watched: Mutex::new(HashMap::new()),
+            control_out,
+            payment_out,
+            monitoring: None,
+        }
+    }
+
+    /// Report per-session MTU, retransmits, throughput and handshake times
+    pub fn with_monitoring(mut self, monitoring: Arc<MonitoringManager>) -> Self {
+        self.monitoring = Some(monitoring);
+        self
+    }
+
+    pub fn config(&self) -> &BLEManagerConfig {
+        &self.config
+    }
+
+    /// Whole messages to notify on a characteristic, before framing
+    pub fn subscribe_outbound(&self, channel: BleChannel) -> broadcast::Receiver<Vec<u8>> {
+        match channel {
+            BleChannel::Control => self.control_out.subscribe(),
+            BleChannel::Payment => self.payment_out.subscribe(),
+        }
+    }
+
+    pub async fn connected_peers(&self) -> usize {
+        self.peers.lock().await.len()
+    }
+
+    /// Advertise and serve the GATT application, and push status updates for
+    /// transactions submitted over BLE
+    pub fn start(manager: Arc<BLEManager>) {
+        if !manager.config.enabled {
+            log::info!("BLE peripheral disabled: BLE_PERIPHERAL_ENABLED=false");
+            return;
+        }
+
+        {
+            let manager = Arc::clone(&manager);
+            tokio::spawn(async move { manager.forward_status_events().await });
+        }
+        {
+            let manager = Arc::clone(&manager);
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(60));
+                loop {
+                    interval.tick().await;
+                    manager.prune_idle_peers().await;
+                }
+            });
+        }
+
+        #[cfg(feature = "ble-peripheral")]
+        tokio::spawn(async move {
+            if let Err(e) = peripheral::run(manager).await {
+                log::error!("BLE peripheral stopped: {}", e);
+            }
+        });
+        #[cfg(not(feature = "ble-peripheral"))]
+        log::warn!("BLE_PERIPHERAL_ENABLED=true but the relay was built without the ble-peripheral feature");
+    }
+
+    /// Handle one write from `peer`, made with the ATT `mtu` when the stack
+    /// reports it; completes a message once all its frames arrive
+    pub async fn handle_write(&self, peer: &str, channel: BleChannel, value: &[u8], mtu: Option<u16>) {
+        let message = {
+            let mut peers = self.peers.lock().await;
+            let state = peers.entry(peer.to_string()).or_insert_with(|| Peer::new(peer));
+            state.last_seen = Instant::now();
+            state.metrics.last_seen = chrono::Utc::now();
+            state.metrics.mtu = mtu.or(state.metrics.mtu);
+            state.metrics.frames_received += 1;
+            state.metrics.bytes_received += value.len() as u64;
+            let inbound = match channel {
+                BleChannel::Control => &mut state.control_in,
+                BleChannel::Payment => &mut state.payment_in,
+            };
+            if inbound.has_frame(value) {
+                state.metrics.retransmitted_frames += 1;
+            }
+            let message = match inbound.push(value, self.config.max_payload_bytes).and_then(|m| m.map(open_message).transpose()) {
+                Ok(message) => message,
+                Err(e) => {
+                    log::warn!("Dropping BLE write from {}: {}", peer, e);
+                    inbound.reset();
+                    state.metrics.dropped_messages += 1;
+                    None
+                }
+            };
+            self.report_session(&state.metrics).await;
+            match message {
+                Some(message) => message,
+                None => return,
+            }
+        };
+
+        match channel {
+            BleChannel::Control => self.handle_control(peer, &message).await,
+            BleChannel::Payment => self.handle_payment(peer, &message).await,
+        }
+    }
+
+    async fn handle_control(&self, peer: &str, message: &[u8]) {
+        let control: serde_json::Value = serde_json::from_slice(message).unwrap_or_default();
+        let handshake_id = control.get("handshake_id").cloned().unwrap_or(serde_json::Value::Null);
+
+        let reply = match control.get("type").and_then(|t| t.as_str()) {
+            Some("noise_init") => match self.start_handshake(peer, &control).await {
+                Ok(response) => serde_json::json!({
+                    "type": "noise_response",
+                    "handshake_id": handshake_id,
+                    "message": general_purpose::STANDARD.encode(response),
+                    "pattern": NOISE_PATTERN,
+                }),
+                Err(e) => {
+                    self.report_handshake(None).await;
+                    serde_json::json!({"type": "error", "handshake_id": handshake_id, "error": e.to_string()})
+                }
+            },
+            Some("noise_finish") => match self.finish_handshake(peer, &control).await {
+                Ok(session_id) => serde_json::json!({
+                    "type": "session",
+                    "handshake_id": handshake_id,
+                    "session_id": session_id,
+                    "protocol": "noise",
+                    "version": NOISE_SESSION_VERSION,
+                    "payload_codecs": PAYLOAD_CODEC_VERSIONS,
+                    "payload_codec": negotiate_version(&control),
+                }),
+                Err(e) => {
+                    log::warn!("Noise handshake with BLE peer {} failed: {}", peer, e);
+                    self.report_handshake(None).await;
+                    serde_json::json!({"type": "error", "handshake_id": handshake_id, "error": e.to_string()})
+                }
+            },
+            Some("close") => {
+                self.forget_peer(peer).await;
+                return;
+            }
+            Some("hello") => serde_json::json!({
+                "type": "error",
+                "handshake_id": handshake_id,
+                "error": "Shared-key sessions are not offered over BLE; use the Noise handshake",
+            }),
+            _ => serde_json::json!({"type": "error", "handshake_id": handshake_id, "error": "Unknown control message"}),
+        };
+        let reply = reply.to_string().into_bytes();
+        if let Some(state) = self.peers.lock().await.get_mut(peer) {
+            state.metrics.bytes_sent += reply.len() as u64;
+            self.report_session(&state.metrics).await;
+        }
+        let _ = self.control_out.send(reply);
+    }
+
+    /// Authenticate the device token and answer the first Noise message
+    async fn start_handshake(&self, peer: &str, control: &serde_json::Value) -> Result<Vec<u8>> {
+        let device_id = control.get("token").and_then(|t| t.as_str())
+            .and_then(|token| auth::verify_jwt_token(token.trim()).ok())
+            .map(|claims| claims.sub)
+            .ok_or_else(|| anyhow!("Device token required"))?;
+        if let Err(e) = self.storage.record_device_seen(&device_id) {
+            log::warn!("Failed to record device {}: {}", device_id, e);
+        }
+
+        let (handshake, response) = start_noise(control, &self.sessions)?;
+        let old_session = {
+            let mut peers = self.peers.lock().await;
+            let state = peers.entry(peer.to_string()).or_insert_with(|| Peer::new(peer));
+            state.metrics.device_id = Some(device_id.clone());
+            state.metrics.session_id = None;
+            state.device_id = Some(device_id);
+            state.handshake = Some(handshake);
+            state.handshake_started = Some(Instant::now());
+            state.session_id.take()
+        };
+        if let Some(old) = old_session {
+            self.sessions.close_session(&old).await;
+        }
+        Ok(response)
+    }
+
+    async fn finish_handshake(&self, peer: &str, control: &serde_json::Value) -> Result<String> {
+        let (handshake, device_id) = {
+            let mut peers = self.peers.lock().await;
+            let state = peers.get_mut(peer).ok_or_else(|| anyhow!("Send noise_init first"))?;
+            (state.handshake.take(), state.device_id.clone().ok_or_else(|| anyhow!("Send noise_init first"))?)
+        };
+        let session_id = finish_noise(handshake, control, &device_id, SessionTransport::Ble, &self.sessions, &self.storage).await?;
+        let mut handshake_ms = None;
+        if let Some(state) = self.peers.lock().await.get_mut(peer) {
+            state.session_id = Some(session_id.clone());
+            handshake_ms = state.handshake_started.take().map(|started| started.elapsed().as_millis() as u64);
+            state.metrics.session_id = Some(session_id.clone());
+            state.metrics.handshake_ms = handshake_ms;
+            self.report_session(&state.metrics).await;
+        }
+        if handshake_ms.is_some() {
+            self.report_handshake(handshake_ms).await;
+        }
+        Ok(session_id)
+    }
+
+    async fn handle_payment(&self, peer: &str, frame: &[u8]) {
+        let (device_id, session_id) = {
+            let peers = self.peers.lock().await;
+            match peers.get(peer).and_then(|p| Some((p.device_id.clone()?, p.session_id.clone()?))) {
+                Some(ids) => ids,
+                None => {
+                    log::warn!("Payment frame from BLE peer {} without a session", peer);
+                    return;
+                }
+            }
+        };
+
+        let result = handle_frame(frame, &device_id, &SessionTransport::Ble, &self.sessions, &self.storage, &self.processor).await;
+        if result.status == "queued" && !result.transaction_id.is_empty() {
+            self.watched.lock().await.insert(result.transaction_id.clone(), session_id.clone());
+        }
+        self.notify_result(&session_id, result).await;
+    }
+
+    /// Seal a result to its session and queue it for the payment characteristic
+    async fn notify_result(&self, session_id: &str, result: TransactionResult) -> bool {
+        match self.sessions.seal_frame(session_id, &result.encode_to_vec()).await {
+            Ok(frame) => {
+                let frame = frame.encode_to_vec();
+                let mut peers = self.peers.lock().await;
+                if let Some(state) = peers.values_mut().find(|p| p.session_id.as_deref() == Some(session_id)) {
+                    state.metrics.bytes_sent += frame.len() as u64;
+                    self.report_session(&state.metrics).await;
+                }
+                drop(peers);
+                let _ = self.payment_out.send(frame);
+                true
+            }
+            Err(e) => {
+                log::debug!("Not notifying BLE session {}: {}", session_id, e);
+                false
+            }
+        }
+    }
+
+    /// Relay status changes of transactions submitted over BLE until they settle
+    async fn forward_status_events(&self) {
+        let mut events = self.processor.subscribe_status();
+        loop {
+            let event: StatusEvent = match events.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!("BLE status forwarding skipped {} events", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let Some(session_id) = self.watched.lock().await.get(&event.transaction_id).cloned() else {
+                continue;
+            };
+
+            let transition = &event.transition;
+            let mut result = result_frame(
+                &SessionTransport::Ble,
+                &transition.status,
+                &event.transaction_id,
+                transition.error_details.as_deref().unwrap_or_default(),
+            );
+            result.hash = transition.tx_hash.clone().unwrap_or_default();
+            result.timestamp = transition.at.timestamp_millis() as u64;
+
+            let delivered = self.notify_result(&session_id, result).await;
+            if !delivered || TERMINAL_STATUSES.contains(&transition.status.as_str()) {
+                self.watched.lock().await.remove(&event.transaction_id);
+            }
+        }
+    }
+
+    async fn forget_peer(&self, peer: &str) {
+        let removed = self.peers.lock().await.remove(peer);
+        if let Some(monitoring) = &self.monitoring {
+            monitoring.end_ble_session(peer).await;
+        }
+        if let Some(session_id) = removed.and_then(|p| p.session_id) {
+            self.sessions.close_session(&session_id).await;
+        }
+    }
+
+    async fn report_session(&self, metrics: &BleSessionMetrics) {
+        if let Some(monitoring) = &self.monitoring {
+            monitoring.record_ble_session(metrics).await;
+        }
+    }
+
+    /// `None` records a failed handshake
+    async fn report_handshake(&self, duration_ms: Option<u64>) {
+        if let Some(monitoring) = &self.monitoring {
+            monitoring.record_ble_handshake(duration_ms).await;
+        }
+    }
+
+    async fn prune_idle_peers(&self) {
+        let idle: Vec<String> = self.peers.lock().await.iter()
+            .filter(|(_, p)| p.last_seen.elapsed() > PEER_IDLE_TIMEOUT)
+            .map(|(address, _)| address.clone())
+            .collect();
+        for peer in idle {
+            self.forget_peer(&peer).await;
+        }
+    }
+}
+
+#[cfg(feature = "ble-peripheral")]
+mod peripheral {
+    use super::*;
+    use bluer::adv::Advertisement;
+    use bluer::gatt::local::{
+        Application, Characteristic, CharacteristicNotifier, CharacteristicNotify, CharacteristicNotifyMethod,
+        CharacteristicWrite, CharacteristicWriteMethod, ReqError, Service,
+    };
+    use futures::FutureExt;
+
+    pub async fn run(manager: Arc<BLEManager>) -> Result<()> {
+        let session = bluer::Session::new().await?;
+        let adapter = match &manager.config.adapter {
+            Some(name) => session.adapter(name)?,
+            None => session.default_adapter().await?,
+        };
+        adapter.set_powered(true).await?;
+
+        let advertisement = Advertisement {
+            service_uuids: [AIRCHAINPAY_SERVICE_UUID].into_iter().collect(),
+            discoverable: Some(true),
+            local_name: Some(manager.config.local_name.clone()),
+            ..Default::default()
+        };
+        // Both handles unregister from BlueZ when dropped
+        let _advertisement = adapter.advertise(advertisement).await?;
+
+        let application = Application {
+            services: vec![Service {
+                uuid: AIRCHAINPAY_SERVICE_UUID,
+                primary: true,
+                characteristics: vec![
+                    characteristic(PAYMENT_CHARACTERISTIC_UUID, BleChannel::Payment, &manager),
+                    characteristic(CONTROL_CHARACTERISTIC_UUID, BleChannel::Control, &manager),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let _application = adapter.serve_gatt_application(application).await?;
+
+        log::info!(
+            "BLE peripheral advertising {} as '{}' on {}",
+            AIRCHAINPAY_SERVICE_UUID,
+            manager.config.local_name,
+            adapter.name()
+        );
+        std::future::pending::<()>().await;
+        Ok(())
+    }
+
+    fn characteristic(uuid: Uuid, channel: BleChannel, manager: &Arc<BLEManager>) -> Characteristic {
+        let writer = Arc::clone(manager);
+        let notifier = Arc::clone(manager);
+        Characteristic {
+            uuid,
+            write: Some(CharacteristicWrite {
+                write: true,
+                write_without_response: true,
+                method: CharacteristicWriteMethod::Fun(Box::new(move |value, request| {
+                    let manager = Arc::clone(&writer);
+                    async move {
+                        manager.handle_write(&request.device_address.to_string(), channel, &value, Some(request.mtu)).await;
+                        Ok::<(), ReqError>(())
+                    }
+                    .boxed()
+                })),
+                ..Default::default()
+            }),
+            notify: Some(CharacteristicNotify {
+                notify: true,
+                method: CharacteristicNotifyMethod::Fun(Box::new(move |characteristic_notifier| {
+                    let outbound = notifier.subscribe_outbound(channel);
+                    let frame_size = notifier.config.notify_frame_size;
+                    notify_loop(characteristic_notifier, outbound, frame_size).boxed()
+                })),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Runs while at least one central is subscribed. Each message is framed
+    /// and sent contiguously so centrals can reassemble it.
+    async fn notify_loop(
+        mut notifier: CharacteristicNotifier,
+        mut outbound: broadcast::Receiver<Vec<u8>>,
+        frame_size: usize,
+    ) {
+        loop {
+            let message = match outbound.recv().await {
+                Ok(message) => message,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!("BLE notifications skipped {} messages", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if notifier.is_stopped() {
+                break;
+            }
+            let frames = match chunk_payload(&seal_message(&message), frame_size) {
+                Ok(frames) => frames,
+                Err(e) => {
+                    log::warn!("Dropping BLE notification: {}", e);
+                    continue;
+                }
+            };
+            for frame in frames {
+                if let Err(e) = notifier.notify(frame).await {
+                    log::debug!("BLE notification failed: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_reassemble_within_limit() {
+        let payload: Vec<u8> = (0..=255).collect();
+        let mut frames = chunk_payload(&payload, MIN_FRAME_SIZE).unwrap();
+        assert!(frames.iter().all(|f| f.len() <= MIN_FRAME_SIZE));
+        frames.reverse();
+
+        let mut reassembler = Reassembler::new();
+        let mut result = None;
+        for frame in &frames {
+            result = reassembler.push(frame, 1024).unwrap();
+        }
+        assert_eq!(result, Some(payload.clone()));
+
+        let mut partial = Reassembler::new();
+        partial.push(&frames[0], 1024).unwrap();
+        assert!(partial.has_frame(&frames[0]));
+        assert!(!partial.has_frame(&frames[1]));
+
+        let mut limited = Reassembler::new();
+        let pushed: Result<Vec<_>> = frames.iter().map(|f| limited.push(f, 100)).collect();
+        assert!(pushed.is_err());
+
+        let mut corrupted = seal_message(b"signed tx");
+        corrupted[0] ^= 0xff;
+        assert!(open_message(corrupted).is_err());
+        assert_eq!(open_message(seal_message(b"signed tx")).unwrap(), b"signed tx");
+    }
+}
@@ -0,0 +1,4 @@
+pub mod manager;
+pub mod noise;
+pub mod payments;
+pub mod session;
@@ -0,0 +1,219 @@
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use snow::{Builder, HandshakeState, TransportState};
+use std::path::Path;
+
+/// Handshake pattern and primitives; devices must build their initiator with the same string
+pub const NOISE_PATTERN: &str = "Noise_XX_25519_ChaChaPoly_SHA256";
+/// Mixed into the handshake hash so transcripts cannot be replayed against another protocol
+pub const NOISE_PROLOGUE: &[u8] = b"airchainpay-noise-1";
+/// Frame version for sessions established with the Noise handshake
+pub const NOISE_SESSION_VERSION: &str = "noise-1";
+/// Largest Noise message allowed by the specification
+pub const MAX_NOISE_MESSAGE: usize = 65535;
+/// Each direction of a Noise session rekeys after this many messages; devices
+/// apply the same rule so both ends stay in step without extra signalling
+pub const NOISE_REKEY_INTERVAL: u64 = 1000;
+
+const STATIC_KEY_FILE: &str = "noise_static.key";
+
+fn builder() -> Builder<'static> {
+    Builder::new(NOISE_PATTERN.parse().expect("valid Noise pattern"))
+}
+
+/// The relay's long-term X25519 key for Noise handshakes.
+///
+/// Stored as two hex lines (private, public) in `<data_dir>/noise_static.key`
+/// and generated on first start.
+pub struct NoiseStaticKey {
+    private: Vec<u8>,
+    public: Vec<u8>,
+}
+
+impl NoiseStaticKey {
+    pub fn load_or_create(data_dir: &str) -> Result<Self> {
+        let path = Path::new(data_dir).join(STATIC_KEY_FILE);
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path)?;
+            let mut lines = contents.lines().map(str::trim);
+            let private = hex::decode(lines.next().unwrap_or_default())?;
+            let public = hex::decode(lines.next().unwrap_or_default())?;
+            if private.len() != 32 || public.len() != 32 {
+                return Err(anyhow!("Invalid Noise static key in {}", path.display()));
+            }
+            return Ok(Self { private, public });
+        }
+
+        let keypair = builder().generate_keypair()
+            .map_err(|e| anyhow!("Failed to generate Noise static key: {}", e))?;
+        std::fs::create_dir_all(data_dir)?;
+        std::fs::write(&path, format!("{}\n{}\n", hex::encode(&keypair.private), hex::encode(&keypair.public)))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        log::info!("Generated Noise static key {} at {}", hex::encode(&keypair.public), path.display());
+        Ok(Self {
+            private: keypair.private,
+            public: keypair.public,
+        })
+    }
+
+    pub fn public_key(&self) -> &[u8] {
+        &self.public
+    }
+}
+
+/// Responder side of a Noise XX handshake:
+///
+/// ```text
+/// -> e
+/// <- e, ee, s, es
+/// -> s, se
+/// ```
+///
+/// Both sides learn and authenticate each other's static key, and the
+/// transport keys derive only from ephemeral exchanges mixed with the
+/// statics, so recorded sessions stay private if a static key later leaks.
+pub struct NoiseHandshake {
+    state: HandshakeState,
+    messages: Vec<NoiseTranscriptMessage>,
+}
+
+/// One handshake message as recorded for the audit log. Only lengths and
+/// digests are kept; the messages themselves never leave the relay.
+#[derive(Debug, Clone, Serialize)]
+pub struct NoiseTranscriptMessage {
+    /// `inbound` from the device or `outbound` from the relay
+    pub direction: &'static str,
+    pub length: usize,
+    /// Hex SHA-256 of the message bytes
+    pub sha256: String,
+}
+
+/// What the relay saw of a handshake, whether or not it completed
+#[derive(Debug, Clone, Serialize)]
+pub struct NoiseTranscript {
+    pub pattern: &'static str,
+    pub prologue: String,
+    pub messages: Vec<NoiseTranscriptMessage>,
+    /// Hex handshake hash `h`; both sides hold the same value once finished
+    pub handshake_hash: String,
+    pub remote_static: Option<String>,
+    pub finished: bool,
+}
+
+impl NoiseHandshake {
+    pub fn responder(key: &NoiseStaticKey) -> Result<Self> {
+        let state = builder()
+            .local_private_key(&key.private)
+            .prologue(NOISE_PROLOGUE)
+            .build_responder()
+            .map_err(|e| anyhow!("Failed to start Noise handshake: {}", e))?;
+        Ok(Self { state, messages: Vec::new() })
+    }
+
+    fn record(&mut self, direction: &'static str, message: &[u8]) {
+        self.messages.push(NoiseTranscriptMessage {
+            direction,
+            length: message.len(),
+            sha256: hex::encode(Sha256::digest(message)),
+        });
+    }
+
+    /// Process the peer's next handshake message, returning its payload
+    pub fn read_message(&mut self, message: &[u8]) -> Result<Vec<u8>> {
+        self.record("inbound", message);
+        let mut payload = vec![0u8; MAX_NOISE_MESSAGE];
+        let len = self.state.read_message(message, &mut payload)
+            .map_err(|e| anyhow!("Noise handshake failed: {}", e))?;
+        payload.truncate(len);
+        Ok(payload)
+    }
+
+    /// Produce our next handshake message carrying `payload`
+    pub fn write_message(&mut self, payload: &[u8]) -> Result<Vec<u8>> {
+        let mut message = vec![0u8; MAX_NOISE_MESSAGE];
+        let len = self.state.write_message(payload, &mut message)
+            .map_err(|e| anyhow!("Noise handshake failed: {}", e))?;
+        message.truncate(len);
+        self.record("outbound", &message);
+        Ok(message)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.is_handshake_finished()
+    }
+
+    /// The peer's authenticated static key, known once its `s` token was read
+    pub fn remote_static(&self) -> Option<[u8; 32]> {
+        self.state.get_remote_static()?.try_into().ok()
+    }
+
+    /// Running handshake hash, usable as a channel binding once finished
+    pub fn handshake_hash(&self) -> Vec<u8> {
+        self.state.get_handshake_hash().to_vec()
+    }
+
+    pub fn transcript(&self) -> NoiseTranscript {
+        NoiseTranscript {
+            pattern: NOISE_PATTERN,
+            prologue: String::from_utf8_lossy(NOISE_PROLOGUE).into_owned(),
+            messages: self.messages.clone(),
+            handshake_hash: hex::encode(self.handshake_hash()),
+            remote_static: self.remote_static().map(hex::encode),
+            finished: self.is_finished(),
+        }
+    }
+
+    pub fn into_transport(self) -> Result<TransportState> {
+        self.state.into_transport_mode()
+            .map_err(|e| anyhow!("Noise handshake incomplete: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xx_handshake_authenticates_both_sides() {
+        let dir = std::env::temp_dir().join(format!("noise-test-{}", uuid::Uuid::new_v4()));
+        let relay_key = NoiseStaticKey::load_or_create(dir.to_str().unwrap()).unwrap();
+        let device_key = builder().generate_keypair().unwrap();
+
+        let mut device = builder()
+            .local_private_key(&device_key.private)
+            .prologue(NOISE_PROLOGUE)
+            .build_initiator()
+            .unwrap();
+        let mut relay = NoiseHandshake::responder(&relay_key).unwrap();
+        let mut buf = vec![0u8; MAX_NOISE_MESSAGE];
+        let mut payload = vec![0u8; MAX_NOISE_MESSAGE];
+
+        let len = device.write_message(&[], &mut buf).unwrap();
+        relay.read_message(&buf[..len]).unwrap();
+        let msg2 = relay.write_message(b"session").unwrap();
+        let len = device.read_message(&msg2, &mut payload).unwrap();
+        assert_eq!(&payload[..len], b"session");
+        assert_eq!(device.get_remote_static().unwrap(), relay_key.public_key());
+        let len = device.write_message(&[], &mut buf).unwrap();
+        relay.read_message(&buf[..len]).unwrap();
+
+        assert!(relay.is_finished());
+        assert_eq!(relay.remote_static().unwrap().as_slice(), device_key.public.as_slice());
+        let transcript = relay.transcript();
+        assert_eq!(transcript.messages.len(), 3);
+        assert_eq!(transcript.handshake_hash, hex::encode(device.get_handshake_hash()));
+
+        let mut device = device.into_transport_mode().unwrap();
+        let mut relay = relay.into_transport().unwrap();
+        let len = device.write_message(b"payment", &mut buf).unwrap();
+        let len = relay.read_message(&buf[..len], &mut payload).unwrap();
+        assert_eq!(&payload[..len], b"payment");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
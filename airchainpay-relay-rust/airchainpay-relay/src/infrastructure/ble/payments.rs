@@ -0,0 +1,150 @@
+use anyhow::{Result, anyhow};
+use base64::{engine::general_purpose, Engine as _};
+use prost::Message as ProstMessage;
+use serde::Deserialize;
+use std::collections::HashMap;
+use crate::airchainpay::{EncryptedTransactionPayload, TransactionResult};
+use crate::app::transaction_service::{QueuedTransaction, TransactionPriority, TransactionProcessor};
+use crate::infrastructure::ble::noise::NoiseHandshake;
+use crate::infrastructure::ble::session::{BleSessionManager, SessionTransport};
+use crate::infrastructure::storage::file_storage::{Storage, Transaction};
+use crate::utils::protobuf_compressor::ProtobufCompressor;
+
+/// Decrypted payment frame body
+#[derive(Debug, Deserialize)]
+struct BridgedPayment {
+    signed_tx: String,
+    chain_id: u64,
+}
+
+/// Decode a payment body: JSON, or a compressed payload frame (see
+/// `utils::payload_codec`) as sent by bandwidth-constrained BLE clients
+async fn decode_payment(plaintext: &[u8]) -> Result<BridgedPayment> {
+    if let Ok(payment) = serde_json::from_slice(plaintext) {
+        return Ok(payment);
+    }
+    let decompressed = ProtobufCompressor::new().decompress_transaction_payload(plaintext).await?;
+    if !decompressed.success {
+        return Err(anyhow!("Invalid payment payload"));
+    }
+    serde_json::from_value(decompressed.data).map_err(|_| anyhow!("Invalid payment payload"))
+}
+
+/// Base64 handshake message from a `noise_init` / `noise_finish` control message
+pub fn noise_message(control: &serde_json::Value) -> Result<Vec<u8>> {
+    let encoded = control.get("message").and_then(|m| m.as_str())
+        .ok_or_else(|| anyhow!("Missing handshake message"))?;
+    general_purpose::STANDARD.decode(encoded)
+        .map_err(|_| anyhow!("Invalid handshake message encoding"))
+}
+
+/// Read the device's `-> e` and answer with `<- e, ee, s, es`
+pub fn start_noise(control: &serde_json::Value, sessions: &BleSessionManager) -> Result<(NoiseHandshake, Vec<u8>)> {
+    let message = noise_message(control)?;
+    let mut handshake = sessions.noise_responder()?;
+    handshake.read_message(&message)?;
+    let response = handshake.write_message(&[])?;
+    Ok((handshake, response))
+}
+
+/// Read the device's `-> s, se`, check its pinned static key and open the session
+pub async fn finish_noise(
+    handshake: Option<NoiseHandshake>,
+    control: &serde_json::Value,
+    device_id: &str,
+    transport: SessionTransport,
+    sessions: &BleSessionManager,
+    storage: &Storage,
+) -> Result<String> {
+    let mut handshake = handshake.ok_or_else(|| anyhow!("Send noise_init first"))?;
+    let authenticated = noise_message(control)
+        .and_then(|message| handshake.read_message(&message))
+        .and_then(|_| {
+            if !handshake.is_finished() {
+                return Err(anyhow!("Noise handshake incomplete"));
+            }
+            handshake.remote_static()
+                .ok_or_else(|| anyhow!("Noise handshake did not authenticate the device"))
+        })
+        .and_then(|remote_static| storage.pin_device_noise_key(device_id, &hex::encode(remote_static)));
+    if let Err(e) = authenticated {
+        // Failed handshakes are audited too; successful ones are recorded when the session opens
+        let transcript = handshake.transcript();
+        sessions.audit_handshake(device_id, transport.as_str(), None, &transcript, Some(e.to_string())).await;
+        return Err(e);
+    }
+    sessions.open_noise_session(device_id, transport, handshake).await
+}
+
+pub fn result_frame(transport: &SessionTransport, status: &str, transaction_id: &str, message: &str) -> TransactionResult {
+    TransactionResult {
+        status: status.to_string(),
+        transport: transport.as_str().to_string(),
+        transaction_id: transaction_id.to_string(),
+        hash: String::new(),
+        error: if status == "failed" { message.to_string() } else { String::new() },
+        timestamp: chrono::Utc::now().timestamp_millis() as u64,
+        message: message.to_string(),
+    }
+}
+
+/// Decrypt an `EncryptedTransactionPayload` frame, store and queue the payment
+/// it carries and describe the outcome
+pub async fn handle_frame(
+    bytes: &[u8],
+    device_id: &str,
+    transport: &SessionTransport,
+    sessions: &BleSessionManager,
+    storage: &Storage,
+    processor: &TransactionProcessor,
+) -> TransactionResult {
+    let frame = match EncryptedTransactionPayload::decode(bytes) {
+        Ok(frame) => frame,
+        Err(_) => return result_frame(transport, "failed", "", "Malformed frame"),
+    };
+
+    let plaintext = match sessions.open_frame(&frame, device_id).await {
+        Ok(plaintext) => plaintext,
+        Err(e) => return result_frame(transport, "failed", "", &e.to_string()),
+    };
+
+    let payment = match decode_payment(&plaintext).await {
+        Ok(payment) => payment,
+        Err(_) => return result_frame(transport, "failed", "", "Invalid payment payload"),
+    };
+
+    let transaction = Transaction::new(payment.signed_tx.clone(), payment.chain_id).with_device(device_id);
+    if let Err(e) = storage.save_transaction(transaction.clone()) {
+        return result_frame(transport, "failed", "", &format!("Failed to store transaction: {e}"));
+    }
+
+    let mut metadata = HashMap::new();
+    metadata.insert("id".to_string(), serde_json::Value::String(transaction.id.clone()));
+    metadata.insert("signedTx".to_string(), serde_json::Value::String(payment.signed_tx.clone()));
+    metadata.insert("transport".to_string(), serde_json::Value::String(transport.as_str().to_string()));
+
+    let queued = QueuedTransaction {
+        transaction: serde_json::json!({
+            "id": transaction.id,
+            "signed_tx": payment.signed_tx,
+            "chain_id": payment.chain_id,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }),
+        priority: TransactionPriority::Normal,
+        queued_at: chrono::Utc::now(),
+        retry_count: 0,
+        max_retries: 3,
+        retry_delay: std::time::Duration::from_secs(2),
+        chain_id: payment.chain_id,
+        metadata,
+        replay_checked: false,
+    };
+
+    match processor.enqueue_transaction(queued).await {
+        Ok(_) => result_frame(transport, "queued", &transaction.id, "Transaction queued for processing"),
+        Err(e) => {
+            let _ = storage.update_transaction_status_with_error(&transaction.id, "queue_failed", None, Some(e.to_string()));
+            result_frame(transport, "failed", &transaction.id, &e.to_string())
+        }
+    }
+}
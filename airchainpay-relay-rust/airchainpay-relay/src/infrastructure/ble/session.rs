@@ -0,0 +1,350 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use snow::TransportState;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use crate::airchainpay::EncryptedTransactionPayload;
+use crate::domain::auth::constant_time_eq;
+use crate::utils::audit::AuditLogger;
+use super::noise::{
+    NoiseHandshake, NoiseStaticKey, NoiseTranscript, MAX_NOISE_MESSAGE, NOISE_REKEY_INTERVAL, NOISE_SESSION_VERSION,
+};
+
+pub const SESSION_PROTOCOL_VERSION: &str = "1.0";
+
+/// Transport a session was opened over
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionTransport {
+    Ble,
+    Websocket,
+}
+
+impl SessionTransport {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionTransport::Ble => "ble",
+            SessionTransport::Websocket => "websocket",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub device_id: String,
+    pub transport: SessionTransport,
+    pub created_at: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub frames_received: u64,
+    /// Hex X25519 static key the device authenticated with, for Noise sessions
+    pub noise_static_key: Option<String>,
+}
+
+enum SessionCrypto {
+    /// Key generated by the relay and handed to the device in the `session` reply
+    Shared([u8; 32]),
+    /// Transport state from a completed Noise XX handshake
+    Noise(Box<TransportState>),
+}
+
+struct Session {
+    info: SessionInfo,
+    crypto: SessionCrypto,
+}
+
+/// Device session state shared by every transport that speaks the framed,
+/// encrypted payment protocol (`EncryptedTransactionPayload` frames).
+pub struct BleSessionManager {
+    sessions: Arc<RwLock<HashMap<String, Session>>>,
+    session_ttl: chrono::Duration,
+    noise_key: Option<Arc<NoiseStaticKey>>,
+    audit: Option<Arc<AuditLogger>>,
+}
+
+impl Default for BleSessionManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BleSessionManager {
+    pub fn new() -> Self {
+        Self {
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            session_ttl: chrono::Duration::minutes(30),
+            noise_key: None,
+            audit: None,
+        }
+    }
+
+    /// Record Noise handshake transcripts in the audit log
+    pub fn with_audit_logger(mut self, audit: Arc<AuditLogger>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Accept Noise XX handshakes authenticated with the given relay static key
+    pub fn with_noise_key(mut self, key: Arc<NoiseStaticKey>) -> Self {
+        self.noise_key = Some(key);
+        self
+    }
+
+    pub fn noise_public_key(&self) -> Option<&[u8]> {
+        self.noise_key.as_deref().map(NoiseStaticKey::public_key)
+    }
+
+    /// Start the responder side of a Noise handshake for a new device session
+    pub fn noise_responder(&self) -> Result<NoiseHandshake> {
+        let key = self.noise_key.as_ref()
+            .ok_or_else(|| anyhow!("Noise sessions are not enabled on this relay"))?;
+        NoiseHandshake::responder(key)
+    }
+
+    /// Open a session and return its id and the 32-byte session key
+    pub async fn open_session(&self, device_id: &str, transport: SessionTransport) -> (String, [u8; 32]) {
+        let key: [u8; 32] = rand::rng().random();
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let session = Session {
+            info: SessionInfo {
+                session_id: session_id.clone(),
+                device_id: device_id.to_string(),
+                transport,
+                created_at: now,
+                last_seen: now,
+                frames_received: 0,
+                noise_static_key: None,
+            },
+            crypto: SessionCrypto::Shared(key),
+        };
+
+        self.sessions.write().await.insert(session_id.clone(), session);
+        (session_id, key)
+    }
+
+    /// Open a session from a completed Noise handshake and return its id
+    pub async fn open_noise_session(
+        &self,
+        device_id: &str,
+        transport: SessionTransport,
+        handshake: NoiseHandshake,
+    ) -> Result<String> {
+        let transport_name = transport.as_str();
+        let remote_static = handshake.remote_static()
+            .ok_or_else(|| anyhow!("Noise handshake did not authenticate the device"))?;
+        let transcript = handshake.transcript();
+        let state = handshake.into_transport()?;
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let session = Session {
+            info: SessionInfo {
+                session_id: session_id.clone(),
+                device_id: device_id.to_string(),
+                transport,
+                created_at: now,
+                last_seen: now,
+                frames_received: 0,
+                noise_static_key: Some(hex::encode(remote_static)),
+            },
+            crypto: SessionCrypto::Noise(Box::new(state)),
+        };
+
+        self.sessions.write().await.insert(session_id.clone(), session);
+        self.audit_handshake(device_id, transport_name, Some(&session_id), &transcript, None).await;
+        Ok(session_id)
+    }
+
+    /// Write a handshake transcript to the audit log, if one is configured
+    pub async fn audit_handshake(
+        &self,
+        device_id: &str,
+        transport: &str,
+        session_id: Option<&str>,
+        transcript: &NoiseTranscript,
+        error: Option<String>,
+    ) {
+        let Some(audit) = &self.audit else { return };
+        let details = match serde_json::to_value(transcript) {
+            Ok(serde_json::Value::Object(fields)) => fields.into_iter().collect(),
+            _ => HashMap::new(),
+        };
+        if let Err(e) = audit.log_noise_handshake(device_id, transport, session_id, details, error).await {
+            log::warn!("Failed to audit Noise handshake for {}: {}", device_id, e);
+        }
+    }
+
+    pub async fn close_session(&self, session_id: &str) {
+        self.sessions.write().await.remove(session_id);
+    }
+
+    pub async fn get_session(&self, session_id: &str) -> Option<SessionInfo> {
+        self.sessions.read().await.get(session_id).map(|s| s.info.clone())
+    }
+
+    pub async fn active_sessions(&self) -> usize {
+        self.sessions.read().await.len()
+    }
+
+    /// Drop sessions idle for longer than the session TTL
+    pub async fn prune_expired(&self) -> usize {
+        let cutoff = Utc::now() - self.session_ttl;
+        let mut sessions = self.sessions.write().await;
+        let before = sessions.len();
+        sessions.retain(|_, s| s.info.last_seen > cutoff);
+        before - sessions.len()
+    }
+
+    /// Verify and decrypt a frame `device_id` sent, returning the plaintext
+    /// payload. Frames naming another device's session are refused before
+    /// they touch its keys or counters.
+    pub async fn open_frame(&self, frame: &EncryptedTransactionPayload, device_id: &str) -> Result<Vec<u8>> {
+        let mut sessions = self.sessions.write().await;
+        let session = sessions.get_mut(&frame.session_id)
+            .ok_or_else(|| anyhow!("Unknown or expired session"))?;
+        if session.info.device_id != device_id {
+            log::warn!("Device {} sent a frame for a session of {}", device_id, session.info.device_id);
+            return Err(anyhow!("Unknown or expired session"));
+        }
+
+        let plaintext = match &mut session.crypto {
+            SessionCrypto::Shared(key) => {
+                if frame.version != SESSION_PROTOCOL_VERSION {
+                    return Err(anyhow!("Unsupported protocol version {}", frame.version));
+                }
+
+                let hmac = hex::decode(&frame.hmac).map_err(|_| anyhow!("Frame integrity check failed"))?;
+                if !constant_time_eq(&hmac_sha256(key, &frame.encrypted_data), &hmac) {
+                    return Err(anyhow!("Frame integrity check failed"));
+                }
+
+                let nonce_bytes = hex::decode(&frame.nonce).map_err(|_| anyhow!("Invalid frame nonce"))?;
+                if nonce_bytes.len() != 12 {
+                    return Err(anyhow!("Invalid frame nonce length"));
+                }
+
+                let cipher = Aes256Gcm::new_from_slice(key)
+                    .map_err(|e| anyhow!("Invalid session key: {}", e))?;
+                cipher
+                    .decrypt(Nonce::from_slice(&nonce_bytes), frame.encrypted_data.as_ref())
+                    .map_err(|_| anyhow!("Frame decryption failed"))?
+            }
+            SessionCrypto::Noise(state) => {
+                if frame.version != NOISE_SESSION_VERSION {
+                    return Err(anyhow!("Unsupported protocol version {}", frame.version));
+                }
+
+                // Noise nonces are implicit counters, so frames must arrive in order
+                let mut plaintext = vec![0u8; MAX_NOISE_MESSAGE];
+                let len = state.read_message(&frame.encrypted_data, &mut plaintext)
+                    .map_err(|_| anyhow!("Frame decryption failed"))?;
+                plaintext.truncate(len);
+                if state.receiving_nonce() % NOISE_REKEY_INTERVAL == 0 {
+                    state.rekey_incoming();
+                }
+                plaintext
+            }
+        };
+
+        session.info.last_seen = Utc::now();
+        session.info.frames_received += 1;
+        Ok(plaintext)
+    }
+
+    /// Encrypt a payload into a frame for the given session
+    pub async fn seal_frame(&self, session_id: &str, plaintext: &[u8]) -> Result<EncryptedTransactionPayload> {
+        let mut sessions = self.sessions.write().await;
+        let session = sessions.get_mut(session_id)
+            .ok_or_else(|| anyhow!("Unknown or expired session"))?;
+
+        match &mut session.crypto {
+            SessionCrypto::Shared(key) => {
+                let nonce_bytes: [u8; 12] = rand::rng().random();
+                let cipher = Aes256Gcm::new_from_slice(key)
+                    .map_err(|e| anyhow!("Invalid session key: {}", e))?;
+                let encrypted_data = cipher
+                    .encrypt(Nonce::from_slice(&nonce_bytes), plaintext)
+                    .map_err(|_| anyhow!("Frame encryption failed"))?;
+
+                Ok(EncryptedTransactionPayload {
+                    session_id: session_id.to_string(),
+                    hmac: hex::encode(hmac_sha256(key, &encrypted_data)),
+                    encrypted_data,
+                    nonce: hex::encode(nonce_bytes),
+                    timestamp: Utc::now().timestamp_millis() as u64,
+                    version: SESSION_PROTOCOL_VERSION.to_string(),
+                })
+            }
+            SessionCrypto::Noise(state) => {
+                let counter = state.sending_nonce();
+                let mut encrypted_data = vec![0u8; MAX_NOISE_MESSAGE];
+                let len = state.write_message(plaintext, &mut encrypted_data)
+                    .map_err(|_| anyhow!("Frame encryption failed"))?;
+                encrypted_data.truncate(len);
+                if state.sending_nonce() % NOISE_REKEY_INTERVAL == 0 {
+                    state.rekey_outgoing();
+                }
+
+                // The AEAD tag covers integrity; the counter is informational
+                Ok(EncryptedTransactionPayload {
+                    session_id: session_id.to_string(),
+                    hmac: String::new(),
+                    encrypted_data,
+                    nonce: format!("{:016x}", counter),
+                    timestamp: Utc::now().timestamp_millis() as u64,
+                    version: NOISE_SESSION_VERSION.to_string(),
+                })
+            }
+        }
+    }
+}
+
+/// HMAC-SHA256 (RFC 2104)
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block_key = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block_key[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(block_key.iter().map(|b| b ^ 0x36).collect::<Vec<u8>>());
+    inner.update(data);
+    let inner_hash = inner.finalize();
+
+    let mut outer = Sha256::new();
+    outer.update(block_key.iter().map(|b| b ^ 0x5c).collect::<Vec<u8>>());
+    outer.update(inner_hash);
+    outer.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_frames_only_open_for_their_device() {
+        let sessions = BleSessionManager::new();
+        let (session_id, _) = sessions.open_session("pos-1", SessionTransport::Ble).await;
+        let frame = sessions.seal_frame(&session_id, b"payment").await.unwrap();
+
+        assert!(sessions.open_frame(&frame, "pos-2").await.is_err());
+        assert_eq!(sessions.open_frame(&frame, "pos-1").await.unwrap(), b"payment");
+
+        let mut forged = frame.clone();
+        forged.hmac = "zz".repeat(32);
+        assert!(sessions.open_frame(&forged, "pos-1").await.is_err());
+        forged.hmac = hex::encode([0u8; 32]);
+        assert!(sessions.open_frame(&forged, "pos-1").await.is_err());
+    }
+}
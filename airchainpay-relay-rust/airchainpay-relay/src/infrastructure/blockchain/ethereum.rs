@@ -1,10 +1,10 @@
 use std::collections::HashMap;
 use ethers::{
     core::types::{Address, Bytes, U256, H256, TxHash},
-    providers::{Http, Provider},
     middleware::Middleware,
 };
 use serde::{Deserialize, Serialize};
+use crate::infrastructure::blockchain::rpc_pool::RpcClientPool;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChainConfig {
     pub chain_id: u64,
@@ -78,7 +78,7 @@ pub struct NetworkStatus {
 
 
 pub async fn send_transaction(signed_tx: Vec<u8>, rpc_url: &str) -> Result<TxHash, Box<dyn std::error::Error>> {
-    let provider = Provider::<Http>::try_from(rpc_url)?;
+    let provider = RpcClientPool::global().provider(rpc_url)?;
     
     let pending_tx = provider.send_raw_transaction(Bytes::from(signed_tx)).await?;
     
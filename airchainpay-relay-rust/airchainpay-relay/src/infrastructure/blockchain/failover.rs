@@ -0,0 +1,259 @@
+use anyhow::{Result, anyhow};
+use ethers::core::types::{Bytes, H256, U64};
+use ethers::providers::{HttpClientError, JsonRpcClient};
+use ethers::utils::keccak256;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::Instrument;
+use crate::infrastructure::blockchain::traced_http::RpcProvider;
+
+/// Score of an endpoint with no recent failures
+pub const MAX_HEALTH_SCORE: u32 = 100;
+const FAILURE_PENALTY: u32 = 40;
+const SUCCESS_CREDIT: u32 = 20;
+/// How long a single endpoint gets to accept a broadcast before the next is tried
+pub const BROADCAST_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    provider: RpcProvider,
+    score: AtomicU32,
+    failures: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RpcEndpointStatus {
+    pub url: String,
+    pub score: u32,
+    pub failures: u64,
+    pub current: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainProviderStatus {
+    pub chain_id: u64,
+    pub current_provider: String,
+    pub failovers: u64,
+    pub endpoints: Vec<RpcEndpointStatus>,
+}
+
+/// The ordered RPC endpoints of one chain.
+///
+/// Each endpoint carries a health score that drops on timeouts, connection
+/// failures and 5xx answers and recovers on successful calls. The current
+/// provider is the healthiest endpoint, ties going to the configured order,
+/// so the primary takes over again once it answers probes.
+#[derive(Debug)]
+pub struct ProviderSet {
+    chain_id: u64,
+    endpoints: Vec<Endpoint>,
+    failovers: AtomicU64,
+}
+
+impl ProviderSet {
+    pub fn new(chain_id: u64, endpoints: Vec<(String, RpcProvider)>) -> Result<Self> {
+        if endpoints.is_empty() {
+            return Err(anyhow!("No RPC URLs configured for chain {}", chain_id));
+        }
+        Ok(Self {
+            chain_id,
+            endpoints: endpoints.into_iter()
+                .map(|(url, provider)| Endpoint {
+                    url,
+                    provider,
+                    score: AtomicU32::new(MAX_HEALTH_SCORE),
+                    failures: AtomicU64::new(0),
+                })
+                .collect(),
+            failovers: AtomicU64::new(0),
+        })
+    }
+
+    /// Endpoint indexes, healthiest first
+    fn ranked(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.endpoints.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(self.endpoints[i].score.load(Ordering::Relaxed)));
+        order
+    }
+
+    fn current_index(&self) -> usize {
+        self.ranked()[0]
+    }
+
+    pub fn current(&self) -> RpcProvider {
+        self.endpoints[self.current_index()].provider.clone()
+    }
+
+    pub fn current_url(&self) -> &str {
+        &self.endpoints[self.current_index()].url
+    }
+
+    pub fn urls(&self) -> impl Iterator<Item = &str> {
+        self.endpoints.iter().map(|endpoint| endpoint.url.as_str())
+    }
+
+    /// Number of broadcasts moved to another endpoint since the chain was configured
+    pub fn failover_count(&self) -> u64 {
+        self.failovers.load(Ordering::Relaxed)
+    }
+
+    fn record_success(&self, index: usize) {
+        let _ = self.endpoints[index].score.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |score| {
+            Some((score + SUCCESS_CREDIT).min(MAX_HEALTH_SCORE))
+        });
+    }
+
+    fn record_failure(&self, index: usize) {
+        let endpoint = &self.endpoints[index];
+        endpoint.failures.fetch_add(1, Ordering::Relaxed);
+        let _ = endpoint.score.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |score| {
+            Some(score.saturating_sub(FAILURE_PENALTY))
+        });
+    }
+
+    /// Broadcast a signed transaction, moving on to the next endpoint after a
+    /// timeout or an unavailable endpoint. RPC errors such as a bad nonce come
+    /// from the chain itself and are returned without trying further.
+    pub async fn broadcast(&self, raw_tx: &Bytes) -> Result<(H256, RpcProvider)> {
+        let mut last_error = None;
+        for (attempt, index) in self.ranked().into_iter().enumerate() {
+            let endpoint = &self.endpoints[index];
+            if attempt > 0 {
+                self.failovers.fetch_add(1, Ordering::Relaxed);
+                log::warn!("Chain {}: failing over broadcast to {}", self.chain_id, endpoint.url);
+            }
+
+            let request = endpoint.provider.as_ref()
+                .request::<_, H256>("eth_sendRawTransaction", [raw_tx])
+                .instrument(tracing::info_span!("rpc_attempt", url = %endpoint.url, attempt));
+            let error = match tokio::time::timeout(BROADCAST_TIMEOUT, request).await {
+                Ok(Ok(hash)) => {
+                    self.record_success(index);
+                    return Ok((hash, endpoint.provider.clone()));
+                }
+                Ok(Err(e)) if attempt > 0 && is_already_known(&e) => {
+                    // An earlier endpoint took the transaction before timing out
+                    self.record_success(index);
+                    return Ok((H256::from(keccak256(raw_tx)), endpoint.provider.clone()));
+                }
+                Ok(Err(e)) if !is_failover_error(&e) => {
+                    self.record_success(index);
+                    return Err(anyhow!("Broadcast rejected by {}: {}", endpoint.url, e));
+                }
+                Ok(Err(e)) => anyhow!("{}: {}", endpoint.url, e),
+                Err(_) => anyhow!("{}: timed out after {:?}", endpoint.url, BROADCAST_TIMEOUT),
+            };
+            self.record_failure(index);
+            last_error = Some(error);
+        }
+        Err(anyhow!(
+            "All RPC endpoints failed for chain {}: {}",
+            self.chain_id,
+            last_error.map(|e| e.to_string()).unwrap_or_default()
+        ))
+    }
+
+    /// Fetch the latest block from every endpoint, updating their scores.
+    /// Returns the block number seen by the healthiest endpoint that answered.
+    pub async fn probe(&self) -> Result<u64> {
+        let probes = self.endpoints.iter().map(|endpoint| async move {
+            tokio::time::timeout(BROADCAST_TIMEOUT, endpoint.provider.as_ref().request::<_, U64>("eth_blockNumber", ()))
+                .await
+                .map_err(|_| anyhow!("{}: timed out", endpoint.url))
+                .and_then(|result| result.map_err(|e| anyhow!("{}: {}", endpoint.url, e)))
+        });
+        let results = futures::future::join_all(probes).await;
+
+        let mut block_number = None;
+        let mut last_error = None;
+        for (index, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(number) => {
+                    self.record_success(index);
+                    block_number.get_or_insert(number.as_u64());
+                }
+                Err(e) => {
+                    self.record_failure(index);
+                    last_error = Some(e);
+                }
+            }
+        }
+        block_number.ok_or_else(|| last_error.unwrap_or_else(|| anyhow!("No RPC endpoints")))
+    }
+
+    pub fn status(&self) -> ChainProviderStatus {
+        let current = self.current_index();
+        ChainProviderStatus {
+            chain_id: self.chain_id,
+            current_provider: self.endpoints[current].url.clone(),
+            failovers: self.failover_count(),
+            endpoints: self.endpoints.iter().enumerate()
+                .map(|(index, endpoint)| RpcEndpointStatus {
+                    url: endpoint.url.clone(),
+                    score: endpoint.score.load(Ordering::Relaxed),
+                    failures: endpoint.failures.load(Ordering::Relaxed),
+                    current: index == current,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Timeouts, refused connections and 5xx answers mean the endpoint, not the
+/// transaction, is at fault
+fn is_failover_error(error: &HttpClientError) -> bool {
+    match error {
+        HttpClientError::ReqwestError(e) => {
+            e.is_timeout() || e.is_connect() || e.status().is_some_and(|status| status.is_server_error())
+        }
+        _ => false,
+    }
+}
+
+fn is_already_known(error: &HttpClientError) -> bool {
+    match error {
+        HttpClientError::JsonRpcError(e) => {
+            let message = e.message.to_lowercase();
+            message.contains("already known") || message.contains("already imported")
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::blockchain::traced_http::TracedHttp;
+
+    fn provider(url: &str) -> RpcProvider {
+        RpcProvider::new(TracedHttp::new(url.parse().unwrap(), ethers_reqwest::Client::new()))
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_fails_over_unreachable_endpoints() {
+        // Nothing listens on port 1, so both connections are refused
+        let set = ProviderSet::new(1114, vec![
+            ("http://127.0.0.1:1/primary".to_string(), provider("http://127.0.0.1:1/primary")),
+            ("http://127.0.0.1:1/fallback".to_string(), provider("http://127.0.0.1:1/fallback")),
+        ]).unwrap();
+        assert_eq!(set.current_url(), "http://127.0.0.1:1/primary");
+
+        assert!(set.broadcast(&Bytes::from(vec![0x02, 0xf8])).await.is_err());
+        assert_eq!(set.failover_count(), 1);
+        let status = set.status();
+        assert!(status.endpoints.iter().all(|e| e.failures == 1 && e.score == MAX_HEALTH_SCORE - FAILURE_PENALTY));
+
+        // The primary leads again once it recovers
+        set.record_failure(1);
+        assert_eq!(set.current_url(), "http://127.0.0.1:1/primary");
+        set.record_failure(0);
+        set.record_failure(0);
+        assert_eq!(set.current_url(), "http://127.0.0.1:1/fallback");
+        for _ in 0..5 {
+            set.record_success(0);
+        }
+        assert_eq!(set.current_url(), "http://127.0.0.1:1/primary");
+    }
+}
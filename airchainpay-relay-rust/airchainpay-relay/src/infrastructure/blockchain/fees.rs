@@ -0,0 +1,237 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use ethers::core::types::{BlockNumber, FeeHistory, Transaction, U256};
+use ethers::core::utils::rlp::{Decodable, Rlp};
+use ethers::providers::Middleware;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::time::Duration;
+use crate::infrastructure::blockchain::manager::BlockchainManager;
+
+/// Reward percentile used as the suggested priority fee
+const PRIORITY_FEE_PERCENTILE: f64 = 50.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeOracleConfig {
+    pub poll_interval: Duration,
+    /// Blocks of fee history each estimate is based on
+    pub history_blocks: u64,
+    /// Share (percent) of the lowest recent base fee, or of the gas price on
+    /// chains without EIP-1559, below which a transaction is rejected
+    pub floor_percent: u64,
+}
+
+impl Default for FeeOracleConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(15),
+            history_blocks: 20,
+            floor_percent: 90,
+        }
+    }
+}
+
+impl FeeOracleConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            poll_interval: std::env::var("FEE_ORACLE_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.poll_interval),
+            history_blocks: std::env::var("FEE_HISTORY_BLOCKS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n: &u64| (1..=1024).contains(n))
+                .unwrap_or(defaults.history_blocks),
+            floor_percent: std::env::var("FEE_FLOOR_PERCENT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.floor_percent),
+        }
+    }
+}
+
+/// Current fee levels for one chain, in wei per gas
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeEstimate {
+    pub chain_id: u64,
+    /// False on chains without a base fee, where only the gas price applies
+    pub eip1559: bool,
+    /// Base fee of the next block
+    pub base_fee: U256,
+    /// Median priority fee paid over the history window
+    pub priority_fee: U256,
+    /// Suggested fee cap: twice the next base fee plus the priority fee,
+    /// or the node's gas price on legacy chains
+    pub max_fee: U256,
+    /// Lowest fee cap the relay accepts
+    pub min_fee: U256,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl FeeEstimate {
+    /// Build an estimate from `eth_feeHistory`. Returns `None` when the chain
+    /// reports no base fees.
+    pub fn from_history(chain_id: u64, history: &FeeHistory, floor_percent: u64) -> Option<Self> {
+        let base_fee = *history.base_fee_per_gas.last()?;
+        let lowest_base_fee = history.base_fee_per_gas.iter().copied().min()?;
+        if base_fee.is_zero() {
+            return None;
+        }
+        let mut tips: Vec<U256> = history.reward.iter().filter_map(|rewards| rewards.first().copied()).collect();
+        tips.sort_unstable();
+        let priority_fee = tips.get(tips.len() / 2).copied().unwrap_or_default();
+        Some(Self {
+            chain_id,
+            eip1559: true,
+            base_fee,
+            priority_fee,
+            max_fee: base_fee.saturating_mul(2.into()).saturating_add(priority_fee),
+            min_fee: lowest_base_fee.saturating_mul(floor_percent.into()) / 100,
+            updated_at: Utc::now(),
+        })
+    }
+
+    pub fn from_gas_price(chain_id: u64, gas_price: U256, floor_percent: u64) -> Self {
+        Self {
+            chain_id,
+            eip1559: false,
+            base_fee: U256::zero(),
+            priority_fee: U256::zero(),
+            max_fee: gas_price,
+            min_fee: gas_price.saturating_mul(floor_percent.into()) / 100,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Reject a fee cap that the chain will not include at current levels
+    pub fn check_fee_cap(&self, fee_cap: U256) -> Result<()> {
+        if fee_cap < self.min_fee {
+            return Err(anyhow!(
+                "Fee too low for chain {}: offered {} wei/gas, at least {} wei/gas required (suggested {})",
+                self.chain_id, fee_cap, self.min_fee, self.max_fee
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Most a signed transaction pays per gas: its max fee for typed
+/// transactions, its gas price for legacy ones
+pub fn fee_cap(signed_tx: &str) -> Result<U256> {
+    let bytes = hex::decode(signed_tx.trim_start_matches("0x"))
+        .map_err(|e| anyhow!("Failed to decode hex: {}", e))?;
+    let tx = Transaction::decode(&Rlp::new(&bytes))
+        .map_err(|e| anyhow!("Failed to decode transaction: {}", e))?;
+    tx.max_fee_per_gas
+        .or(tx.gas_price)
+        .ok_or_else(|| anyhow!("Transaction carries no fee"))
+}
+
+/// Polls fee history for every chain and keeps the latest estimates
+pub struct FeeOracle {
+    blockchain_manager: Arc<BlockchainManager>,
+    config: FeeOracleConfig,
+    estimates: RwLock<HashMap<u64, FeeEstimate>>,
+}
+
+impl FeeOracle {
+    pub fn new(blockchain_manager: Arc<BlockchainManager>, config: FeeOracleConfig) -> Self {
+        Self {
+            blockchain_manager,
+            config,
+            estimates: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn start(oracle: Arc<FeeOracle>) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(oracle.config.poll_interval);
+            loop {
+                ticker.tick().await;
+                for chain_id in oracle.blockchain_manager.chain_ids() {
+                    if let Err(e) = oracle.refresh(chain_id).await {
+                        log::warn!("Fee estimate failed for chain {}: {}", chain_id, e);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Fetch fresh fee levels for a chain, falling back to the gas price on
+    /// chains that do not support `eth_feeHistory`
+    pub async fn refresh(&self, chain_id: u64) -> Result<FeeEstimate> {
+        let provider = self.blockchain_manager.provider(chain_id)
+            .ok_or_else(|| anyhow!("No provider for chain_id {}", chain_id))?;
+        let history = provider
+            .fee_history(self.config.history_blocks, BlockNumber::Latest, &[PRIORITY_FEE_PERCENTILE])
+            .await;
+        let estimate = match history.ok().and_then(|h| FeeEstimate::from_history(chain_id, &h, self.config.floor_percent)) {
+            Some(estimate) => estimate,
+            None => {
+                let gas_price = provider.get_gas_price().await
+                    .map_err(|e| anyhow!("Failed to fetch gas price on chain {}: {}", chain_id, e))?;
+                FeeEstimate::from_gas_price(chain_id, gas_price, self.config.floor_percent)
+            }
+        };
+        self.estimates.write().await.insert(chain_id, estimate.clone());
+        Ok(estimate)
+    }
+
+    /// Latest estimate for a chain, refreshed if it is older than two polls
+    pub async fn estimate(&self, chain_id: u64) -> Result<FeeEstimate> {
+        let max_age = chrono::Duration::from_std(self.config.poll_interval * 2).unwrap_or_default();
+        if let Some(estimate) = self.estimates.read().await.get(&chain_id) {
+            if Utc::now() - estimate.updated_at <= max_age {
+                return Ok(estimate.clone());
+            }
+        }
+        self.refresh(chain_id).await
+    }
+
+    /// Reject a signed transaction whose fee cap is below the chain's floor.
+    /// Transactions pass when no estimate can be had, so an RPC outage does
+    /// not block broadcasts on its own.
+    pub async fn check_transaction(&self, chain_id: u64, signed_tx: &str) -> Result<()> {
+        let fee_cap = fee_cap(signed_tx)?;
+        match self.estimate(chain_id).await {
+            Ok(estimate) => estimate.check_fee_cap(fee_cap),
+            Err(e) => {
+                log::warn!("Skipping fee check on chain {}: {}", chain_id, e);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_from_fee_history() {
+        let gwei = |n: u64| U256::from(n) * U256::exp10(9);
+        let history = FeeHistory {
+            // Three blocks plus the next one
+            base_fee_per_gas: vec![gwei(10), gwei(8), gwei(9), gwei(12)],
+            gas_used_ratio: vec![0.5, 0.3, 0.9],
+            oldest_block: U256::from(100),
+            reward: vec![vec![gwei(1)], vec![gwei(3)], vec![gwei(2)]],
+        };
+        let estimate = FeeEstimate::from_history(84532, &history, 90).unwrap();
+        assert_eq!(estimate.base_fee, gwei(12));
+        assert_eq!(estimate.priority_fee, gwei(2));
+        assert_eq!(estimate.max_fee, gwei(26));
+        assert_eq!(estimate.min_fee, U256::from(7_200_000_000u64));
+        assert!(estimate.check_fee_cap(gwei(8)).is_ok());
+        assert!(estimate.check_fee_cap(gwei(7)).is_err());
+
+        let legacy = FeeHistory { base_fee_per_gas: vec![U256::zero(); 4], ..history };
+        assert!(FeeEstimate::from_history(1114, &legacy, 90).is_none());
+    }
+}
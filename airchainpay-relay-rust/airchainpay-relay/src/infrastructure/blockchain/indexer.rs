@@ -0,0 +1,164 @@
+use crate::infrastructure::blockchain::manager::BlockchainManager;
+use crate::infrastructure::storage::file_storage::Storage;
+use anyhow::Result;
+use ethers::providers::{Middleware, Provider, StreamExt, Ws};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Notify;
+use tokio::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexerConfig {
+    pub enabled: bool,
+    pub poll_interval: Duration,
+    /// Most blocks asked for in one `eth_getLogs` call
+    pub batch_blocks: u64,
+    /// Blocks behind the head before payments are indexed, so short reorgs
+    /// never reach the index
+    pub confirmations: u64,
+    /// First block indexed on chains with no cursor yet; the current safe
+    /// head when unset
+    pub start_block: Option<u64>,
+    /// Websocket endpoints by chain id; new blocks there trigger a pass
+    /// without waiting for the poll interval
+    pub ws_urls: HashMap<u64, String>,
+}
+
+impl Default for IndexerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval: Duration::from_secs(15),
+            batch_blocks: 2000,
+            confirmations: 3,
+            start_block: None,
+            ws_urls: HashMap::new(),
+        }
+    }
+}
+
+impl IndexerConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("INDEXER_ENABLED")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.enabled),
+            poll_interval: std::env::var("INDEXER_POLL_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.poll_interval),
+            batch_blocks: std::env::var("INDEXER_BATCH_BLOCKS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|b: &u64| *b > 0)
+                .unwrap_or(defaults.batch_blocks),
+            confirmations: std::env::var("INDEXER_CONFIRMATIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.confirmations),
+            start_block: std::env::var("INDEXER_START_BLOCK")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            // INDEXER_WS_URL_<CHAIN_ID>, e.g. INDEXER_WS_URL_84532
+            ws_urls: std::env::vars()
+                .filter_map(|(key, url)| Some((key.strip_prefix("INDEXER_WS_URL_")?.parse().ok()?, url)))
+                .collect(),
+        }
+    }
+}
+
+/// Follows the AirChainPay contracts' `Payment` events on every configured
+/// chain and stores them, so payment queries are served from the index
+/// rather than a log scan per request
+pub struct PaymentIndexer {
+    blockchain_manager: Arc<BlockchainManager>,
+    storage: Arc<Storage>,
+    config: IndexerConfig,
+}
+
+impl PaymentIndexer {
+    pub fn new(blockchain_manager: Arc<BlockchainManager>, storage: Arc<Storage>, config: IndexerConfig) -> Self {
+        Self {
+            blockchain_manager,
+            storage,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &IndexerConfig {
+        &self.config
+    }
+
+    pub fn start(indexer: Arc<PaymentIndexer>) {
+        if !indexer.config.enabled {
+            return;
+        }
+        for chain_id in indexer.blockchain_manager.chain_ids() {
+            let wake = Arc::new(Notify::new());
+            if let Some(ws_url) = indexer.config.ws_urls.get(&chain_id).cloned() {
+                tokio::spawn(Self::follow_blocks(chain_id, ws_url, Arc::clone(&wake)));
+            }
+            let indexer = Arc::clone(&indexer);
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(indexer.config.poll_interval);
+                loop {
+                    tokio::select! {
+                        _ = ticker.tick() => {}
+                        _ = wake.notified() => {}
+                    }
+                    match indexer.sync_chain(chain_id).await {
+                        Ok(0) => {}
+                        Ok(added) => log::info!("Indexed {} contract payments on chain {}", added, chain_id),
+                        Err(e) => log::warn!("Payment indexing on chain {} failed: {}", chain_id, e),
+                    }
+                }
+            });
+        }
+    }
+
+    /// Index payments from the chain's cursor up to its safe head, in
+    /// batches of `batch_blocks`. Returns how many payments were new.
+    pub async fn sync_chain(&self, chain_id: u64) -> Result<usize> {
+        let head = self.blockchain_manager.get_block_number(chain_id).await?;
+        let safe_head = head.saturating_sub(self.config.confirmations);
+        let mut from = match self.storage.payments_indexed_through(chain_id) {
+            Some(through) => through + 1,
+            None => self.config.start_block.unwrap_or(safe_head),
+        };
+
+        let mut added = 0;
+        while from <= safe_head {
+            let to = safe_head.min(from + self.config.batch_blocks - 1);
+            let events = self.blockchain_manager.get_contract_events(chain_id, Some(from), Some(to), None, None).await?;
+            added += self.storage.record_indexed_payments(chain_id, events, to)?;
+            from = to + 1;
+        }
+        Ok(added)
+    }
+
+    /// Wake the chain's indexing loop on every new block, reconnecting
+    /// after the socket drops
+    async fn follow_blocks(chain_id: u64, ws_url: String, wake: Arc<Notify>) {
+        loop {
+            match Provider::<Ws>::connect(ws_url.as_str()).await {
+                Ok(provider) => match provider.subscribe_blocks().await {
+                    Ok(mut blocks) => {
+                        log::info!("Following new blocks on chain {} over websocket", chain_id);
+                        while blocks.next().await.is_some() {
+                            wake.notify_one();
+                        }
+                        log::warn!("Block subscription on chain {} ended", chain_id);
+                    }
+                    Err(e) => log::warn!("Block subscription on chain {} failed: {}", chain_id, e),
+                },
+                Err(e) => log::warn!("Websocket connection for chain {} failed: {}", chain_id, e),
+            }
+            // Polling carries on meanwhile
+            tokio::time::sleep(Duration::from_secs(30)).await;
+        }
+    }
+}
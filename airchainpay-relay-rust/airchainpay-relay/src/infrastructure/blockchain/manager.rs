@@ -1,14 +1,17 @@
-use crate::infrastructure::config::Config;
+use crate::infrastructure::config::{BroadcastStrategy, ChainConfig, Config};
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+use tracing::Instrument;
 use ethers::{
-    providers::{Provider, Http},
     core::types::{Address, U256, H256, Log, Bytes, Filter, BlockNumber},
     prelude::*,
 };
 use crate::app::transaction_service::QueuedTransaction;
+use crate::infrastructure::blockchain::traced_http::RpcProvider;
+use crate::infrastructure::blockchain::rpc_pool::RpcClientPool;
+use crate::infrastructure::blockchain::failover::{ChainProviderStatus, ProviderSet};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GasEstimate {
@@ -27,6 +30,19 @@ pub struct TransactionReceipt {
     pub logs: Vec<Log>,
 }
 
+/// A token's entry in the payment contract's `supportedTokens`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenConfig {
+    pub supported: bool,
+    pub is_stablecoin: bool,
+    pub decimals: u8,
+    pub symbol: String,
+    /// Smallest accepted payment in base units; 0 for no minimum
+    pub min_amount: U256,
+    /// Largest accepted payment in base units; 0 for no maximum
+    pub max_amount: U256,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ContractType {
     AirChainPay,
@@ -45,58 +61,90 @@ pub struct PaymentEvent {
     pub log_index: u64,
 }
 
+type ChainContracts = HashMap<ContractType, Contract<RpcProvider>>;
+
+/// Per-chain clients, swapped as a unit when the chain set changes
+#[derive(Default)]
+struct ChainClients {
+    providers: HashMap<u64, Arc<ProviderSet>>,
+    contracts: HashMap<u64, ChainContracts>,
+    strategies: HashMap<u64, BroadcastStrategy>,
+}
+
 pub struct BlockchainManager {
-    providers: HashMap<u64, Provider<Http>>,
-    contracts: HashMap<u64, HashMap<ContractType, Contract<Provider<Http>>>>,
+    chains: RwLock<ChainClients>,
 }
 
 impl BlockchainManager {
     pub fn new(config: Config) -> Result<Self> {
-        let mut providers = HashMap::new();
-        let mut contracts = HashMap::new();
-        
-        for (chain_id, chain_config) in &config.supported_chains {
-            let provider = Provider::<Http>::try_from(&chain_config.rpc_url)
-                .map_err(|e| anyhow!("Failed to create HTTP provider for chain {}: {}", chain_id, e))?;
-            
-            providers.insert(*chain_id, provider.clone());
-            
-            // Initialize contracts if addresses are provided
-            let mut chain_contracts = HashMap::new();
-            
-            // Load AirChainPay contract
-            if !chain_config.contract_address.is_empty() {
-                let contract_address: Address = chain_config.contract_address.parse()
-                    .map_err(|e| anyhow!("Invalid contract address for chain {}: {}", chain_id, e))?;
-                
-                let abi_bytes = include_bytes!("../../abi/AirChainPay.json");
-                let abi_value: serde_json::Value = serde_json::from_slice(abi_bytes).unwrap();
-                let abi: ethers::abi::Abi = serde_json::from_value(abi_value).unwrap();
-                let contract = Contract::new(contract_address, abi, Arc::new(provider.clone()));
-                chain_contracts.insert(ContractType::AirChainPay, contract);
-            }
-            
-            // Load AirChainPayToken contract (using the same address for now, but could be different)
-            if !chain_config.contract_address.is_empty() {
-                let contract_address: Address = chain_config.contract_address.parse()
-                    .map_err(|e| anyhow!("Invalid contract address for chain {}: {}", chain_id, e))?;
-                
-                let abi_bytes = include_bytes!("../../abi/AirChainPayToken.json");
-                let abi_value: serde_json::Value = serde_json::from_slice(abi_bytes).unwrap();
-                let abi: ethers::abi::Abi = serde_json::from_value(abi_value).unwrap();
-                let contract = Contract::new(contract_address, abi, Arc::new(provider.clone()));
-                chain_contracts.insert(ContractType::AirChainPayToken, contract);
+        let manager = Self {
+            chains: RwLock::new(ChainClients::default()),
+        };
+        manager.apply_chains(&config.supported_chains)?;
+        Ok(manager)
+    }
+
+    /// Replace the set of supported chains without a restart.
+    ///
+    /// All chains are built before anything is swapped, so an invalid entry
+    /// leaves the current clients untouched. Pooled clients for RPC URLs that
+    /// are no longer referenced are evicted. Health scores and failover counts
+    /// start over for every chain.
+    pub fn apply_chains(&self, supported_chains: &HashMap<u64, ChainConfig>) -> Result<()> {
+        let pool = RpcClientPool::global();
+        let mut next = ChainClients::default();
+
+        for (chain_id, chain_config) in supported_chains {
+            let mut endpoints = Vec::new();
+            for url in chain_config.rpc_urls() {
+                let provider = pool.provider_with_tls(url, &chain_config.rpc_tls)
+                    .map_err(|e| anyhow!("Failed to create HTTP provider for chain {}: {}", chain_id, e))?;
+                endpoints.push((url.to_string(), provider));
             }
-            
+            let providers = ProviderSet::new(*chain_id, endpoints)?;
+            let chain_contracts = Self::load_contracts(*chain_id, chain_config, &providers.current())?;
+
+            next.providers.insert(*chain_id, Arc::new(providers));
+            next.strategies.insert(*chain_id, chain_config.broadcast.clone());
             if !chain_contracts.is_empty() {
-                contracts.insert(*chain_id, chain_contracts);
+                next.contracts.insert(*chain_id, chain_contracts);
             }
         }
-        
-        Ok(Self {
-            providers,
-            contracts,
-        })
+
+        let previous = std::mem::replace(&mut *self.chains.write().unwrap(), next);
+        for url in previous.providers.values().flat_map(|providers| providers.urls()) {
+            if !supported_chains.values().any(|chain| chain.rpc_urls().contains(&url)) {
+                pool.evict(url);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn load_contracts(chain_id: u64, chain_config: &ChainConfig, provider: &RpcProvider) -> Result<ChainContracts> {
+        let mut chain_contracts = HashMap::new();
+        if chain_config.contract_address.is_empty() {
+            return Ok(chain_contracts);
+        }
+
+        let contract_address: Address = chain_config.contract_address.parse()
+            .map_err(|e| anyhow!("Invalid contract address for chain {}: {}", chain_id, e))?;
+
+        // Load AirChainPay contract
+        let abi_bytes = include_bytes!("../../abi/AirChainPay.json");
+        let abi_value: serde_json::Value = serde_json::from_slice(abi_bytes).unwrap();
+        let abi: ethers::abi::Abi = serde_json::from_value(abi_value).unwrap();
+        let contract = Contract::new(contract_address, abi, Arc::new(provider.clone()));
+        chain_contracts.insert(ContractType::AirChainPay, contract);
+
+        // Load AirChainPayToken contract (using the same address for now, but could be different)
+        let abi_bytes = include_bytes!("../../abi/AirChainPayToken.json");
+        let abi_value: serde_json::Value = serde_json::from_slice(abi_bytes).unwrap();
+        let abi: ethers::abi::Abi = serde_json::from_value(abi_value).unwrap();
+        let contract = Contract::new(contract_address, abi, Arc::new(provider.clone()));
+        chain_contracts.insert(ContractType::AirChainPayToken, contract);
+
+        Ok(chain_contracts)
     }
 
     /// Execute a meta-transaction on the AirChainPay contract
@@ -221,46 +269,174 @@ impl BlockchainManager {
 
     /// Check if a token is supported
     pub async fn is_token_supported(&self, chain_id: u64, token: Address) -> Result<bool> {
+        Ok(self.token_config(chain_id, token).await?.supported)
+    }
+
+    /// The payment contract's configuration for a token
+    pub async fn token_config(&self, chain_id: u64, token: Address) -> Result<TokenConfig> {
         let contract = self.get_contract(chain_id, ContractType::AirChainPayToken)?;
         
-        let token_config: (bool, bool, u8, String, U256, U256) = 
+        let (supported, is_stablecoin, decimals, symbol, min_amount, max_amount): (bool, bool, u8, String, U256, U256) = 
             contract.method("supportedTokens", token)?.call().await?;
         
-        Ok(token_config.0) // isSupported field
+        Ok(TokenConfig { supported, is_stablecoin, decimals, symbol, min_amount, max_amount })
+    }
+
+    /// Deployed bytecode at an address; empty if nothing is deployed there
+    pub async fn get_code(&self, chain_id: u64, address: Address) -> Result<Bytes> {
+        let provider = self.provider(chain_id)
+            .ok_or_else(|| anyhow!("No provider for chain_id {}", chain_id))?;
+        provider.get_code(address, None).await
+            .map_err(|e| anyhow!("Failed to fetch code of {:?} on chain {}: {}", address, chain_id, e))
     }
 
     /// Get contract instance for a specific chain and type
-    fn get_contract(&self, chain_id: u64, contract_type: ContractType) -> Result<&Contract<Provider<Http>>> {
-        let chain_contracts = self.contracts.get(&chain_id)
+    fn get_contract(&self, chain_id: u64, contract_type: ContractType) -> Result<Contract<RpcProvider>> {
+        let chains = self.chains.read().unwrap();
+        let chain_contracts = chains.contracts.get(&chain_id)
             .ok_or_else(|| anyhow!("No contracts found for chain_id {}", chain_id))?;
         
         let contract = chain_contracts.get(&contract_type)
             .ok_or_else(|| anyhow!("Contract {:?} not found for chain_id {}", contract_type, chain_id))?;
-        
-        Ok(contract)
+
+        // Contracts follow the chain's current provider after a failover
+        match chains.providers.get(&chain_id) {
+            Some(providers) => Ok(Contract::new(contract.address(), contract.abi().clone(), Arc::new(providers.current()))),
+            None => Ok(contract.clone()),
+        }
     }
 
     pub async fn get_network_status(&self) -> Result<HashMap<String, String>> {
         // Return overall network status for all chains
         let mut status = HashMap::new();
         status.insert("overall_status".to_string(), "healthy".to_string());
-        status.insert("total_chains".to_string(), self.providers.len().to_string());
+        status.insert("total_chains".to_string(), self.chains.read().unwrap().providers.len().to_string());
         status.insert("timestamp".to_string(), chrono::Utc::now().to_rfc3339());
         Ok(status)
     }
 
+    /// Chain IDs that have a configured provider
+    pub fn chain_ids(&self) -> Vec<u64> {
+        let mut ids: Vec<u64> = self.chains.read().unwrap().providers.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Current pooled provider for a chain, if the chain is configured
+    pub fn provider(&self, chain_id: u64) -> Option<RpcProvider> {
+        self.provider_set(chain_id).map(|providers| providers.current())
+    }
+
+    fn provider_set(&self, chain_id: u64) -> Option<Arc<ProviderSet>> {
+        self.chains.read().unwrap().providers.get(&chain_id).cloned()
+    }
+
+    /// Current provider, health scores and failover count of every chain
+    pub fn provider_statuses(&self) -> Vec<ChainProviderStatus> {
+        let mut statuses: Vec<ChainProviderStatus> = self.chains.read().unwrap().providers.values()
+            .map(|providers| providers.status())
+            .collect();
+        statuses.sort_unstable_by_key(|status| status.chain_id);
+        statuses
+    }
+
+    /// Probe RPC connectivity for a chain by fetching the latest block number
+    /// from each of its endpoints, which also refreshes their health scores
+    pub async fn check_chain_connectivity(&self, chain_id: u64) -> Result<u64> {
+        let providers = self.provider_set(chain_id)
+            .ok_or_else(|| anyhow!("No provider for chain_id {}", chain_id))?;
+        providers.probe().await
+            .map_err(|e| anyhow!("RPC connectivity check failed for chain {}: {}", chain_id, e))
+    }
+
+    /// Broadcast a queued transaction and wait for it to be mined
     pub async fn send_transaction(&self, tx: &QueuedTransaction) -> Result<H256> {
-        let chain_id = tx.chain_id;
+        let (tx_hash, provider) = self.broadcast_raw(tx).await?;
+        let receipt = PendingTransaction::new(tx_hash, &provider).await?;
+        Ok(receipt.unwrap().transaction_hash)
+    }
+
+    /// Broadcast a queued transaction without waiting for it to be mined
+    pub async fn broadcast_transaction(&self, tx: &QueuedTransaction) -> Result<H256> {
+        Ok(self.broadcast_raw(tx).await?.0)
+    }
+
+    /// Send an already broadcast transaction again, for nodes that dropped it
+    pub async fn rebroadcast(&self, chain_id: u64, signed_tx: &str) -> Result<H256> {
+        Ok(self.broadcast_signed(chain_id, signed_tx).await?.0)
+    }
+
+    /// Broadcast settings of a chain; the default for unknown chains
+    pub fn broadcast_strategy(&self, chain_id: u64) -> BroadcastStrategy {
+        self.chains.read().unwrap().strategies.get(&chain_id).cloned().unwrap_or_default()
+    }
+
+    async fn broadcast_raw(&self, tx: &QueuedTransaction) -> Result<(H256, RpcProvider)> {
         let signed_tx_hex = match &tx.metadata.get("signedTx") {
             Some(val) => val.as_str().ok_or_else(|| anyhow!("signedTx is not a string"))?,
             None => return Err(anyhow!("No signedTx in transaction metadata")),
         };
-        let provider = self.providers.get(&chain_id)
+        self.broadcast_signed(tx.chain_id, signed_tx_hex).await
+    }
+
+    async fn broadcast_signed(&self, chain_id: u64, signed_tx_hex: &str) -> Result<(H256, RpcProvider)> {
+        let providers = self.provider_set(chain_id)
             .ok_or_else(|| anyhow!("No provider for chain_id {}", chain_id))?;
         let raw_tx_bytes = hex::decode(signed_tx_hex.trim_start_matches("0x"))?;
-        let pending_tx = provider.send_raw_transaction(Bytes::from(raw_tx_bytes)).await?;
-        let receipt = pending_tx.await?;
-        Ok(receipt.unwrap().transaction_hash)
+        // Carries the request id too, so log lines inside stay attributable
+        let span = tracing::info_span!(
+            "broadcast",
+            chain_id,
+            request_id = %crate::utils::request_id::current().unwrap_or_default(),
+        );
+        providers.broadcast(&Bytes::from(raw_tx_bytes)).instrument(span).await
+    }
+
+    /// On-chain receipt of a transaction, or `None` while it is not mined
+    pub async fn get_transaction_receipt(&self, chain_id: u64, tx_hash: H256) -> Result<Option<ethers::types::TransactionReceipt>> {
+        let provider = self.provider(chain_id)
+            .ok_or_else(|| anyhow!("No provider for chain_id {}", chain_id))?;
+        provider.get_transaction_receipt(tx_hash).await
+            .map_err(|e| anyhow!("Failed to fetch receipt {:?} on chain {}: {}", tx_hash, chain_id, e))
+    }
+
+    /// Latest block number on a chain, from its current provider
+    pub async fn get_block_number(&self, chain_id: u64) -> Result<u64> {
+        let provider = self.provider(chain_id)
+            .ok_or_else(|| anyhow!("No provider for chain_id {}", chain_id))?;
+        let block_number = provider.get_block_number().await
+            .map_err(|e| anyhow!("Failed to fetch block number on chain {}: {}", chain_id, e))?;
+        Ok(block_number.as_u64())
+    }
+
+    /// Publish the Merkle root of a batch of handled payments on the AirChainPay
+    /// contract, sent from `wallet` so the root is attributed to that relay
+    pub async fn attest_relay_root(
+        &self,
+        chain_id: u64,
+        wallet: LocalWallet,
+        root: H256,
+        count: u64,
+        from_time: u64,
+        to_time: u64,
+    ) -> Result<ethers::types::TransactionReceipt> {
+        let contract = self.get_contract(chain_id, ContractType::AirChainPay)?;
+        let provider = self.provider(chain_id)
+            .ok_or_else(|| anyhow!("No provider for chain_id {}", chain_id))?;
+        let data = contract.encode("attestRelayRoot", (root, U256::from(count), from_time, to_time))?;
+
+        let client = SignerMiddleware::new(provider, wallet.with_chain_id(chain_id));
+        let tx = TransactionRequest::new().to(contract.address()).data(data);
+        let pending = client.send_transaction(tx, None).await
+            .map_err(|e| anyhow!("Attestation broadcast failed on chain {}: {}", chain_id, e))?;
+        let receipt = pending.await?
+            .ok_or_else(|| anyhow!("Attestation transaction dropped on chain {}", chain_id))?;
+        Ok(receipt)
+    }
+
+    /// Address of the AirChainPay contract on a chain
+    pub fn contract_address(&self, chain_id: u64) -> Option<Address> {
+        self.get_contract(chain_id, ContractType::AirChainPay).ok().map(|c| c.address())
     }
 
     /// Fetch Payment events from contracts
@@ -272,7 +448,7 @@ impl BlockchainManager {
         from_address: Option<Address>,
         to_address: Option<Address>,
     ) -> Result<Vec<PaymentEvent>> {
-        let provider = self.providers.get(&chain_id)
+        let provider = self.provider(chain_id)
             .ok_or_else(|| anyhow!("Provider not found for chain_id {}", chain_id))?;
 
         // Payment event signature: Payment(address indexed from, address indexed to, uint256 amount, string paymentReference, bool isRelayed)
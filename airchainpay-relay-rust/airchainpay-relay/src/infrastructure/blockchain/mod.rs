@@ -1,2 +1,8 @@
 pub mod ethereum;
-pub mod manager; 
\ No newline at end of file
+pub mod failover;
+pub mod fees;
+pub mod indexer;
+pub mod manager;
+pub mod rpc_pool;
+pub mod tls;
+pub mod traced_http;
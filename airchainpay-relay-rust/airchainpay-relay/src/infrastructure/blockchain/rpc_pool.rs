@@ -0,0 +1,104 @@
+use anyhow::{Result, anyhow};
+use ethers::providers::Middleware;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use crate::infrastructure::blockchain::tls;
+use crate::infrastructure::blockchain::traced_http::{RpcProvider, TracedHttp};
+use crate::infrastructure::config::RpcTlsConfig;
+
+lazy_static! {
+    static ref GLOBAL_RPC_POOL: Arc<RpcClientPool> = Arc::new(RpcClientPool::new());
+}
+
+/// Shared cache of HTTP providers keyed by RPC URL.
+///
+/// `RpcProvider` wraps a reqwest client whose connection pool is shared
+/// between clones, so handing out clones of one provider per URL gives
+/// connection reuse across handlers, workers and the blockchain manager.
+#[derive(Debug, Default)]
+pub struct RpcClientPool {
+    providers: RwLock<HashMap<String, (RpcTlsConfig, RpcProvider)>>,
+}
+
+impl RpcClientPool {
+    pub fn new() -> Self {
+        Self {
+            providers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Process-wide pool used by the relay
+    pub fn global() -> Arc<RpcClientPool> {
+        Arc::clone(&GLOBAL_RPC_POOL)
+    }
+
+    /// Get the pooled provider for an RPC URL, creating it on first use.
+    /// An existing pinned client is reused as-is, never downgraded.
+    pub fn provider(&self, rpc_url: &str) -> Result<RpcProvider> {
+        if let Some((_, provider)) = self.providers.read().unwrap().get(rpc_url) {
+            return Ok(provider.clone());
+        }
+        self.provider_with_tls(rpc_url, &RpcTlsConfig::default())
+    }
+
+    /// Like `provider`, but with per-endpoint certificate pins or a private CA.
+    /// A pooled client built with different TLS settings is replaced.
+    pub fn provider_with_tls(&self, rpc_url: &str, tls_config: &RpcTlsConfig) -> Result<RpcProvider> {
+        if let Some((pooled_tls, provider)) = self.providers.read().unwrap().get(rpc_url) {
+            if pooled_tls == tls_config {
+                return Ok(provider.clone());
+            }
+        }
+
+        let mut providers = self.providers.write().unwrap();
+        if let Some((pooled_tls, provider)) = providers.get(rpc_url) {
+            if pooled_tls == tls_config {
+                return Ok(provider.clone());
+            }
+        }
+
+        let provider = if tls_config.is_default() {
+            let url = ethers_reqwest::Url::parse(rpc_url)
+                .map_err(|e| anyhow!("Failed to create HTTP provider for {}: {}", rpc_url, e))?;
+            RpcProvider::new(TracedHttp::new(url, ethers_reqwest::Client::new()))
+        } else {
+            tls::pinned_provider(rpc_url, tls_config)?
+        };
+        providers.insert(rpc_url.to_string(), (tls_config.clone(), provider.clone()));
+        Ok(provider)
+    }
+
+    /// Drop a pooled provider, e.g. after its RPC URL was replaced
+    pub fn evict(&self, rpc_url: &str) {
+        self.providers.write().unwrap().remove(rpc_url);
+    }
+
+    pub fn len(&self) -> usize {
+        self.providers.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Periodically ping every pooled endpoint so idle connections stay warm
+    pub fn start_keep_alive(pool: Arc<RpcClientPool>, interval: Duration) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let providers: Vec<(String, RpcProvider)> = pool.providers.read().unwrap()
+                    .iter()
+                    .map(|(url, (_, provider))| (url.clone(), provider.clone()))
+                    .collect();
+                for (url, provider) in providers {
+                    if let Err(e) = provider.get_block_number().await {
+                        log::warn!("RPC keep-alive failed for {}: {}", url, e);
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::{Result, anyhow};
+use ethers_rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use ethers_rustls::{Certificate, ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::SystemTime;
+use crate::infrastructure::blockchain::traced_http::{RpcProvider, TracedHttp};
+use crate::infrastructure::config::RpcTlsConfig;
+
+/// Parse a SHA-256 certificate fingerprint written as hex, with or without colons
+pub fn parse_fingerprint(pin: &str) -> Result<[u8; 32]> {
+    let bytes = hex::decode(pin.trim().replace(':', ""))
+        .map_err(|_| anyhow!("Certificate pin '{}' is not hex", pin))?;
+    bytes.try_into()
+        .map_err(|_| anyhow!("Certificate pin '{}' is not a SHA-256 fingerprint", pin))
+}
+
+pub fn certificate_fingerprint(der: &[u8]) -> [u8; 32] {
+    Sha256::digest(der).into()
+}
+
+/// Accepts a server only if its leaf certificate matches one of the pins.
+///
+/// With a private CA configured the chain must also verify against it; without
+/// one the pin alone is trusted, which lets nodes use self-signed certificates.
+struct PinnedCertVerifier {
+    pins: Vec<[u8; 32]>,
+    chain_verifier: Option<WebPkiVerifier>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, ethers_rustls::Error> {
+        if let Some(chain_verifier) = &self.chain_verifier {
+            chain_verifier.verify_server_cert(end_entity, intermediates, server_name, scts, ocsp_response, now)?;
+        }
+
+        let fingerprint = certificate_fingerprint(&end_entity.0);
+        if self.pins.contains(&fingerprint) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            log::error!("RPC certificate for {:?} does not match any pin (got {})", server_name, hex::encode(fingerprint));
+            Err(ethers_rustls::Error::General("server certificate does not match pinned fingerprint".to_string()))
+        }
+    }
+}
+
+fn load_ca_roots(path: &str) -> Result<RootCertStore> {
+    let pem = std::fs::read(path).map_err(|e| anyhow!("Failed to read RPC CA bundle {}: {}", path, e))?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice())
+        .map_err(|e| anyhow!("Failed to parse RPC CA bundle {}: {}", path, e))?;
+    if certs.is_empty() {
+        return Err(anyhow!("RPC CA bundle {} contains no certificates", path));
+    }
+
+    let mut roots = RootCertStore::empty();
+    for der in certs {
+        roots.add(&Certificate(der))
+            .map_err(|e| anyhow!("Invalid certificate in RPC CA bundle {}: {}", path, e))?;
+    }
+    Ok(roots)
+}
+
+fn web_roots() -> RootCertStore {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(anchor.subject, anchor.spki, anchor.name_constraints)
+    }));
+    roots
+}
+
+/// Build an HTTP provider whose TLS trust follows `tls` instead of the system defaults
+pub fn pinned_provider(rpc_url: &str, tls: &RpcTlsConfig) -> Result<RpcProvider> {
+    let url = ethers_reqwest::Url::parse(rpc_url)
+        .map_err(|e| anyhow!("Invalid RPC URL {}: {}", rpc_url, e))?;
+    if url.scheme() != "https" {
+        return Err(anyhow!("TLS pinning requires an https RPC URL, got {}", rpc_url));
+    }
+
+    let ca_roots = tls.ca_cert_path.as_deref().map(load_ca_roots).transpose()?;
+    let pins = tls.pinned_fingerprints.iter()
+        .map(|pin| parse_fingerprint(pin))
+        .collect::<Result<Vec<_>>>()?;
+
+    let builder = ClientConfig::builder().with_safe_defaults();
+    let tls_config = if pins.is_empty() {
+        builder
+            .with_root_certificates(ca_roots.unwrap_or_else(web_roots))
+            .with_no_client_auth()
+    } else {
+        let verifier = PinnedCertVerifier {
+            pins,
+            chain_verifier: ca_roots.map(|roots| WebPkiVerifier::new(roots, None)),
+        };
+        builder
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth()
+    };
+
+    let client = ethers_reqwest::Client::builder()
+        .use_preconfigured_tls(tls_config)
+        .build()
+        .map_err(|e| anyhow!("Failed to build pinned HTTP client for {}: {}", rpc_url, e))?;
+
+    Ok(RpcProvider::new(TracedHttp::new(url, client)))
+}
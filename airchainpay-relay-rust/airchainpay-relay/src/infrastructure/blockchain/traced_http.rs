@@ -0,0 +1,108 @@
+use async_trait::async_trait;
+use ethers::providers::{HttpClientError, JsonRpcClient, JsonRpcError};
+use ethers_reqwest::{Client, Url};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::infrastructure::telemetry;
+use crate::utils::request_id::{self, REQUEST_ID_HEADER};
+
+/// Provider type used for every upstream RPC endpoint
+pub type RpcProvider = ethers::providers::Provider<TracedHttp>;
+
+#[derive(Serialize)]
+struct Request<'a, T> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'a str,
+    params: T,
+}
+
+#[derive(Deserialize)]
+struct Response<'a> {
+    #[serde(borrow, default)]
+    result: Option<&'a RawValue>,
+    #[serde(default)]
+    error: Option<JsonRpcError>,
+}
+
+/// JSON-RPC over HTTP that forwards the current request id as `X-Request-Id`,
+/// and the current trace context as `traceparent`, so node-side logs and
+/// traces can be joined with the relay's.
+///
+/// Behaves like ethers' `Http` transport otherwise.
+#[derive(Debug)]
+pub struct TracedHttp {
+    id: AtomicU64,
+    client: Client,
+    url: Url,
+}
+
+impl TracedHttp {
+    pub fn new(url: Url, client: Client) -> Self {
+        Self {
+            id: AtomicU64::new(1),
+            client,
+            url,
+        }
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+}
+
+impl Clone for TracedHttp {
+    fn clone(&self) -> Self {
+        Self {
+            id: AtomicU64::new(1),
+            client: self.client.clone(),
+            url: self.url.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for TracedHttp {
+    type Error = HttpClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, HttpClientError>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let payload = Request {
+            jsonrpc: "2.0",
+            id: self.id.fetch_add(1, Ordering::SeqCst),
+            method,
+            params,
+        };
+
+        let mut request = self.client.post(self.url.as_ref()).json(&payload);
+        if let Some(id) = request_id::current() {
+            request = request.header(REQUEST_ID_HEADER, id);
+        }
+        for (name, value) in telemetry::trace_headers(&tracing::Span::current()) {
+            request = request.header(name, value);
+        }
+        let response = request.send().await?;
+        // Surface gateway failures as HTTP errors rather than unparseable bodies,
+        // so the blockchain manager can fail over to another endpoint
+        if response.status().is_server_error() {
+            response.error_for_status_ref()?;
+        }
+        let body = response.bytes().await?;
+
+        let serde_error = |err: serde_json::Error, text: &str| HttpClientError::SerdeJson { err, text: text.to_string() };
+        let response: Response<'_> = serde_json::from_slice(&body)
+            .map_err(|err| serde_error(err, &String::from_utf8_lossy(&body)))?;
+        if let Some(error) = response.error {
+            return Err(error.into());
+        }
+        // A null result (e.g. a receipt that isn't mined yet) deserializes to None
+        let raw = response.result.map(|r| r.get()).unwrap_or("null");
+        serde_json::from_str(raw).map_err(|err| serde_error(err, raw))
+    }
+}
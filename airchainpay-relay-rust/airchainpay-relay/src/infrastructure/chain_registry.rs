@@ -0,0 +1,167 @@
+use crate::infrastructure::config::ChainConfig;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Native currency of a chain, as wallets display it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NativeCurrency {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+/// One chain of a registry document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainEntry {
+    pub chain_id: u64,
+    pub name: String,
+    /// Primary RPC URL first, then fallbacks
+    pub rpc_urls: Vec<String>,
+    pub explorer: String,
+    /// AirChainPay payment contract; the relay serves only chains that have one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract_address: Option<String>,
+    pub native_currency: NativeCurrency,
+}
+
+/// Chains as a TOML or JSON document, `[[chains]]` / `{"chains": [...]}`.
+/// The wallet core reads the same format, so one file, or the relay's
+/// `GET /api/chains/registry`, can describe a deployment's networks to both.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainRegistry {
+    #[serde(default)]
+    pub chains: Vec<ChainEntry>,
+}
+
+impl ChainRegistry {
+    pub fn from_json_str(content: &str) -> Result<Self> {
+        let registry: Self = serde_json::from_str(content).map_err(|e| anyhow!("Invalid chain registry JSON: {}", e))?;
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let registry: Self = toml::from_str(content).map_err(|e| anyhow!("Invalid chain registry TOML: {}", e))?;
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    /// Read a registry file, TOML when the extension is `.toml` and JSON otherwise
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read chain registry {}: {}", path.display(), e))?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml_str(&content),
+            _ => Self::from_json_str(&content),
+        }
+    }
+
+    pub fn to_toml_string(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let mut seen = Vec::with_capacity(self.chains.len());
+        for chain in &self.chains {
+            if chain.chain_id == 0 {
+                return Err(anyhow!("Chain {} has chain id 0", chain.name));
+            }
+            if seen.contains(&chain.chain_id) {
+                return Err(anyhow!("Chain {} is listed twice", chain.chain_id));
+            }
+            seen.push(chain.chain_id);
+            if chain.name.trim().is_empty() {
+                return Err(anyhow!("Chain {} has no name", chain.chain_id));
+            }
+            if chain.rpc_urls.is_empty() {
+                return Err(anyhow!("Chain {} has no RPC URL", chain.chain_id));
+            }
+            if let Some(url) = chain.rpc_urls.iter().find(|url| !url.starts_with("http://") && !url.starts_with("https://")) {
+                return Err(anyhow!("Invalid RPC URL for chain {}: '{}'", chain.chain_id, url));
+            }
+            if !chain.explorer.starts_with("https://") && !chain.explorer.starts_with("http://") {
+                return Err(anyhow!("Invalid explorer URL for chain {}: '{}'", chain.chain_id, chain.explorer));
+            }
+            if chain.native_currency.symbol.trim().is_empty() || chain.native_currency.decimals > 36 {
+                return Err(anyhow!("Invalid native currency for chain {}", chain.chain_id));
+            }
+        }
+        Ok(())
+    }
+
+    /// The relay's chains in registry form, ordered by chain id
+    pub fn from_chain_configs(chains: &HashMap<u64, ChainConfig>) -> Self {
+        let mut chains: Vec<ChainEntry> = chains.iter().map(|(chain_id, chain)| chain.to_entry(*chain_id)).collect();
+        chains.sort_by_key(|chain| chain.chain_id);
+        Self { chains }
+    }
+
+    /// Add or update `chains` from this registry. Settings the registry
+    /// does not carry, such as TLS pinning and broadcast strategy, are kept
+    /// for chains that already exist. Entries without a contract address
+    /// are skipped, as the relay cannot serve them.
+    pub fn apply_to(&self, chains: &mut HashMap<u64, ChainConfig>) {
+        for entry in &self.chains {
+            let Some(contract_address) = &entry.contract_address else {
+                log::warn!("Chain registry entry {} ({}) has no contract address, skipping", entry.chain_id, entry.name);
+                continue;
+            };
+            let chain = chains.entry(entry.chain_id).or_default();
+            chain.name = entry.name.clone();
+            chain.rpc_url = entry.rpc_urls[0].clone();
+            chain.fallback_rpc_urls = entry.rpc_urls[1..].to_vec();
+            chain.explorer = entry.explorer.clone();
+            chain.contract_address = contract_address.clone();
+            chain.currency_symbol = Some(entry.native_currency.symbol.clone());
+            chain.native_currency = Some(entry.native_currency.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toml_registry_adds_custom_chain_and_keeps_settings() {
+        let registry = ChainRegistry::from_toml_str(r#"
+            [[chains]]
+            chain_id = 84532
+            name = "Base Sepolia"
+            rpc_urls = ["https://base-sepolia.example.org", "https://sepolia.base.org"]
+            explorer = "https://sepolia.basescan.org"
+            contract_address = "0x8d7eaB03a72974F5D9F5c99B4e4e1B393DBcfCAB"
+            native_currency = { name = "Ether", symbol = "ETH", decimals = 18 }
+
+            [[chains]]
+            chain_id = 31337
+            name = "Local devnet"
+            rpc_urls = ["http://127.0.0.1:8545"]
+            explorer = "http://127.0.0.1:4000"
+            contract_address = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+            native_currency = { name = "Ether", symbol = "ETH", decimals = 18 }
+        "#).unwrap();
+
+        let mut chains = HashMap::new();
+        chains.insert(84532, ChainConfig { max_gas_limit: Some(500_000), ..Default::default() });
+        registry.apply_to(&mut chains);
+
+        assert_eq!(chains[&84532].rpc_url, "https://base-sepolia.example.org");
+        assert_eq!(chains[&84532].fallback_rpc_urls, vec!["https://sepolia.base.org".to_string()]);
+        assert_eq!(chains[&84532].max_gas_limit, Some(500_000));
+        assert_eq!(chains[&31337].name, "Local devnet");
+
+        // Round trip through the relay's form and JSON
+        let exported = ChainRegistry::from_chain_configs(&chains);
+        assert_eq!(exported, registry);
+        let json = serde_json::to_string(&exported).unwrap();
+        assert_eq!(ChainRegistry::from_json_str(&json).unwrap(), registry);
+
+        let duplicate = ChainRegistry { chains: vec![registry.chains[0].clone(), registry.chains[0].clone()] };
+        assert!(duplicate.validate().is_err());
+    }
+}
@@ -12,6 +12,7 @@ use anyhow::{Result, anyhow};
 use std::sync::mpsc::channel;
 use chrono::{DateTime, Utc};
 use notify::Watcher;
+use crate::infrastructure::chain_registry::{ChainEntry, ChainRegistry, NativeCurrency};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChainConfig {
@@ -21,6 +22,135 @@ pub struct ChainConfig {
     pub explorer: String,
     pub currency_symbol: Option<String>,
     pub max_gas_limit: Option<u64>,
+    #[serde(default)]
+    pub rpc_tls: RpcTlsConfig,
+    /// Further RPC URLs, tried in order when `rpc_url` times out or fails
+    /// with a 5xx. `rpc_tls` applies to every URL of the chain.
+    #[serde(default)]
+    pub fallback_rpc_urls: Vec<String>,
+    /// Fee limits, replacement rules, confirmation depth and rebroadcasting
+    /// for this chain
+    #[serde(default, skip_serializing_if = "BroadcastStrategy::is_default")]
+    pub broadcast: BroadcastStrategy,
+    /// Name and decimals of the native currency, for wallets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub native_currency: Option<NativeCurrency>,
+}
+
+impl ChainConfig {
+    /// Primary RPC URL followed by the fallbacks, without blanks or repeats
+    pub fn rpc_urls(&self) -> Vec<&str> {
+        let mut urls: Vec<&str> = Vec::new();
+        for url in std::iter::once(&self.rpc_url).chain(&self.fallback_rpc_urls) {
+            let url = url.trim();
+            if !url.is_empty() && !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        urls
+    }
+
+    /// Read `<PREFIX>_RPC_FALLBACK_URLS` (comma separated)
+    pub fn fallback_rpc_urls_from_env(prefix: &str) -> Vec<String> {
+        env::var(format!("{prefix}_RPC_FALLBACK_URLS"))
+            .map(|urls| {
+                urls.split(',')
+                    .map(|url| url.trim().to_string())
+                    .filter(|url| !url.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// This chain as a registry entry; the currency falls back to the
+    /// symbol with 18 decimals
+    pub fn to_entry(&self, chain_id: u64) -> ChainEntry {
+        let symbol = self.currency_symbol.clone().unwrap_or_default();
+        ChainEntry {
+            chain_id,
+            name: self.name.clone(),
+            rpc_urls: self.rpc_urls().into_iter().map(str::to_string).collect(),
+            explorer: self.explorer.clone(),
+            contract_address: Some(self.contract_address.clone()).filter(|address| !address.is_empty()),
+            native_currency: self.native_currency.clone().unwrap_or(NativeCurrency {
+                name: symbol.clone(),
+                symbol,
+                decimals: 18,
+            }),
+        }
+    }
+}
+
+/// TLS trust settings for a single RPC endpoint
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcTlsConfig {
+    /// SHA-256 fingerprints (hex, colons optional) of accepted server certificates
+    #[serde(default)]
+    pub pinned_fingerprints: Vec<String>,
+    /// PEM bundle of a private CA trusted instead of the public web roots
+    #[serde(default)]
+    pub ca_cert_path: Option<String>,
+}
+
+impl RpcTlsConfig {
+    /// Read `<PREFIX>_RPC_TLS_PINS` (comma separated) and `<PREFIX>_RPC_TLS_CA_CERT`
+    pub fn from_env(prefix: &str) -> Self {
+        Self {
+            pinned_fingerprints: env::var(format!("{prefix}_RPC_TLS_PINS"))
+                .map(|pins| {
+                    pins.split(',')
+                        .map(|pin| pin.trim().to_string())
+                        .filter(|pin| !pin.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            ca_cert_path: env::var(format!("{prefix}_RPC_TLS_CA_CERT")).ok().filter(|p| !p.is_empty()),
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.pinned_fingerprints.is_empty() && self.ca_cert_path.is_none()
+    }
+}
+
+/// How the relay broadcasts and follows transactions on one chain. Unset
+/// fields fall back to the transaction processor's own settings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BroadcastStrategy {
+    /// Highest fee cap, in gwei per gas, a transaction may offer; catches
+    /// payers about to overpay by orders of magnitude
+    #[serde(default)]
+    pub max_fee_gwei: Option<u64>,
+    /// Percent a replacement (same sender and nonce as a transaction still
+    /// waiting to be mined) must raise the fee cap by
+    #[serde(default)]
+    pub replacement_bump_percent: Option<u64>,
+    /// Blocks on top of the including one before a transaction counts as final
+    #[serde(default)]
+    pub required_confirmations: Option<u64>,
+    /// Seconds between re-sends of a transaction that is still unmined
+    #[serde(default)]
+    pub rebroadcast_interval_secs: Option<u64>,
+}
+
+impl BroadcastStrategy {
+    /// Read `<PREFIX>_MAX_FEE_GWEI`, `<PREFIX>_REPLACEMENT_BUMP_PERCENT`,
+    /// `<PREFIX>_CONFIRMATIONS` and `<PREFIX>_REBROADCAST_SECS`
+    pub fn from_env(prefix: &str) -> Self {
+        let read = |name: &str| -> Option<u64> {
+            env::var(format!("{prefix}_{name}")).ok().and_then(|v| v.trim().parse().ok())
+        };
+        Self {
+            max_fee_gwei: read("MAX_FEE_GWEI").filter(|n| *n > 0),
+            replacement_bump_percent: read("REPLACEMENT_BUMP_PERCENT"),
+            required_confirmations: read("CONFIRMATIONS").filter(|n| *n > 0),
+            rebroadcast_interval_secs: read("REBROADCAST_SECS").filter(|n| *n > 0),
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 impl Default for ChainConfig {
@@ -32,6 +162,10 @@ impl Default for ChainConfig {
             explorer: "https://scan.test2.btcs.network".to_string(),
             currency_symbol: Some("TCORE2".to_string()),
             max_gas_limit: None,
+            rpc_tls: RpcTlsConfig::default(),
+            fallback_rpc_urls: Vec::new(),
+            broadcast: BroadcastStrategy::default(),
+            native_currency: None,
         }
     }
 }
@@ -49,6 +183,10 @@ pub struct SecurityConfig {
     pub enable_rate_limiting: bool,
     pub enable_cors: bool,
     pub cors_origins: String,
+    #[serde(default = "default_cors_methods")]
+    pub cors_methods: String,
+    #[serde(default = "default_cors_headers")]
+    pub cors_headers: String,
     pub jwt_secret: String,
     pub api_key: String,
     pub max_connections: u32,
@@ -74,6 +212,28 @@ pub struct DatabaseConfig {
     pub compression_enabled: bool,
 }
 
+pub const DEFAULT_CORS_METHODS: &str = "GET,POST,PUT,DELETE,OPTIONS";
+pub const DEFAULT_CORS_HEADERS: &str = "Content-Type,Authorization,X-API-Key,X-Request-Id,X-Request-Nonce,X-Request-Timestamp,X-Request-Signature,X-PoW-Challenge,X-PoW-Nonce";
+
+fn default_cors_methods() -> String {
+    env::var("CORS_METHODS").unwrap_or_else(|_| DEFAULT_CORS_METHODS.to_string())
+}
+
+fn default_cors_headers() -> String {
+    env::var("CORS_HEADERS").unwrap_or_else(|_| DEFAULT_CORS_HEADERS.to_string())
+}
+
+/// Parse `LOG_MODULES` (`module=level,module=level`) into per-module overrides
+pub fn log_modules_from_env() -> HashMap<String, String> {
+    env::var("LOG_MODULES")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(module, level)| (module.trim().to_string(), level.trim().to_string()))
+        .filter(|(module, level)| !module.is_empty() && !level.is_empty())
+        .collect()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub environment: String,
@@ -81,6 +241,9 @@ pub struct Config {
     pub chain_id: u64,
     pub contract_address: String,
     pub log_level: String,
+    /// Per-module level overrides, e.g. `airchainpay_relay::middleware` -> `debug`
+    #[serde(default)]
+    pub log_modules: HashMap<String, String>,
     pub port: u16,
     pub debug: bool,
     pub enable_swagger: bool,
@@ -103,6 +266,7 @@ impl Default for Config {
             chain_id: 1114,
             contract_address: "".to_string(),
             log_level: "info".to_string(),
+            log_modules: log_modules_from_env(),
             port: 4000,
             debug: false,
             enable_swagger: true,
@@ -203,6 +367,51 @@ impl DynamicConfigManager {
         Ok(())
     }
     
+    /// Receiver notified whenever the active configuration changes
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.reload_sender.subscribe()
+    }
+    
+    /// Add or replace a supported chain and persist it to the config file
+    pub async fn upsert_chain(&self, chain_id: u64, chain: ChainConfig) -> Result<()> {
+        let mut config = self.config.write().await;
+        let mut new_config = config.clone();
+        new_config.supported_chains.insert(chain_id, chain);
+        self.persist_locked(&mut config, new_config)
+    }
+    
+    /// Add or update every chain in `registry` and persist the result
+    pub async fn apply_chain_registry(&self, registry: &ChainRegistry) -> Result<()> {
+        registry.validate()?;
+        let mut config = self.config.write().await;
+        let mut new_config = config.clone();
+        registry.apply_to(&mut new_config.supported_chains);
+        self.persist_locked(&mut config, new_config)
+    }
+    
+    /// Remove a supported chain and persist the change; the last chain cannot be removed
+    pub async fn remove_chain(&self, chain_id: u64) -> Result<ChainConfig> {
+        let mut config = self.config.write().await;
+        let mut new_config = config.clone();
+        let removed = new_config.supported_chains.remove(&chain_id)
+            .ok_or_else(|| anyhow!("Chain {} is not configured", chain_id))?;
+        if new_config.supported_chains.is_empty() {
+            return Err(anyhow!("Cannot remove the last supported chain"));
+        }
+        self.persist_locked(&mut config, new_config)?;
+        Ok(removed)
+    }
+    
+    /// Validate, write to disk, then swap in a config while the write lock is held
+    fn persist_locked(&self, current: &mut Config, mut new_config: Config) -> Result<()> {
+        new_config.last_modified = Some(Utc::now().timestamp() as u64);
+        new_config.validate()?;
+        new_config.save_to_file(&self.config_file_path)?;
+        *current = new_config;
+        let _ = self.reload_sender.send(true);
+        Ok(())
+    }
+    
     pub async fn reload_config(&self) -> Result<()> {
         let new_config = Config::new()?;
         self.update_config(new_config).await
@@ -295,6 +504,7 @@ impl DynamicConfigManager {
             "version": config.version,
             "port": config.port,
             "log_level": config.log_level,
+            "log_modules": config.log_modules,
             "debug": config.debug,
             "supported_chains_count": config.supported_chains.len(),
             "security_enabled": {
@@ -413,7 +623,8 @@ impl Config {
         Self::validate_startup_env_vars()?;
         
         // Try to load from config file first
-        if let Ok(config) = Self::load_from_file() {
+        if let Ok(mut config) = Self::load_from_file() {
+            Self::apply_chain_registry_file(&mut config.supported_chains)?;
             return Ok(config);
         }
         
@@ -514,6 +725,7 @@ impl Config {
             chain_id: u64::from_str(&env::var("CHAIN_ID").unwrap_or_else(|_| "1114".to_string()))?,
             contract_address: env::var("CONTRACT_ADDRESS").unwrap_or_else(|_| "".to_string()),
             log_level: "debug".to_string(),
+            log_modules: log_modules_from_env(),
             port: u16::from_str(&env::var("PORT").unwrap_or_else(|_| "4000".to_string()))?,
             debug: env::var("DEBUG").unwrap_or_else(|_| "true".to_string()) == "true",
             enable_swagger: env::var("ENABLE_SWAGGER").unwrap_or_else(|_| "true".to_string()) == "true",
@@ -529,6 +741,8 @@ impl Config {
                 enable_rate_limiting: true,
                 enable_cors: true,
                 cors_origins: env::var("CORS_ORIGINS").unwrap_or_else(|_| "*".to_string()),
+                cors_methods: default_cors_methods(),
+                cors_headers: default_cors_headers(),
                 jwt_secret: env::var("JWT_SECRET").unwrap_or_else(|_| "dev_jwt_secret".to_string()),
                 api_key: env::var("API_KEY").unwrap_or_else(|_| "dev_api_key".to_string()),
                 max_connections: 100,
@@ -549,7 +763,7 @@ impl Config {
                 enable_encryption: false,
                 compression_enabled: true,
             },
-            supported_chains: Self::get_supported_chains(),
+            supported_chains: Self::get_supported_chains()?,
             config_file_path: None,
             last_modified: Some(Utc::now().timestamp() as u64),
         })
@@ -562,6 +776,7 @@ impl Config {
             chain_id: u64::from_str(&env::var("CHAIN_ID").unwrap_or_else(|_| "1114".to_string()))?,
             contract_address: env::var("CONTRACT_ADDRESS").unwrap_or_else(|_| "".to_string()),
             log_level: "info".to_string(),
+            log_modules: log_modules_from_env(),
             port: u16::from_str(&env::var("PORT").unwrap_or_else(|_| "4000".to_string()))?,
             debug: env::var("DEBUG").unwrap_or_else(|_| "false".to_string()) == "true",
             enable_swagger: env::var("ENABLE_SWAGGER").unwrap_or_else(|_| "true".to_string()) == "true",
@@ -577,6 +792,8 @@ impl Config {
                 enable_rate_limiting: true,
                 enable_cors: true,
                 cors_origins: env::var("CORS_ORIGINS").unwrap_or_else(|_| "https://staging.airchainpay.com,https://staging-wallet.airchainpay.com".to_string()),
+                cors_methods: default_cors_methods(),
+                cors_headers: default_cors_headers(),
                 jwt_secret: env::var("JWT_SECRET").unwrap_or_else(|_| "staging_secret".to_string()),
                 api_key: env::var("API_KEY").unwrap_or_else(|_| "staging_key".to_string()),
                 max_connections: 50,
@@ -597,7 +814,7 @@ impl Config {
                 enable_encryption: false,
                 compression_enabled: true,
             },
-            supported_chains: Self::get_supported_chains(),
+            supported_chains: Self::get_supported_chains()?,
             config_file_path: None,
             last_modified: Some(Utc::now().timestamp() as u64),
         })
@@ -610,6 +827,7 @@ impl Config {
             chain_id: u64::from_str(&env::var("CHAIN_ID").unwrap_or_else(|_| "1114".to_string()))?,
             contract_address: env::var("CONTRACT_ADDRESS").unwrap_or_else(|_| "".to_string()),
             log_level: env::var("LOG_LEVEL").unwrap_or_else(|_| "warn".to_string()),
+            log_modules: log_modules_from_env(),
             port: u16::from_str(&env::var("PORT").unwrap_or_else(|_| "4000".to_string()))?,
             debug: env::var("DEBUG").unwrap_or_else(|_| "false".to_string()) == "true",
             enable_swagger: env::var("ENABLE_SWAGGER").unwrap_or_else(|_| "true".to_string()) == "true",
@@ -625,6 +843,8 @@ impl Config {
                 enable_rate_limiting: env::var("ENABLE_RATE_LIMITING").unwrap_or_else(|_| "true".to_string()) != "false",
                 enable_cors: env::var("ENABLE_CORS").unwrap_or_else(|_| "true".to_string()) != "false",
                 cors_origins: env::var("CORS_ORIGINS").unwrap_or_else(|_| "https://app.airchainpay.com,https://wallet.airchainpay.com".to_string()),
+                cors_methods: default_cors_methods(),
+                cors_headers: default_cors_headers(),
                 jwt_secret: env::var("JWT_SECRET").unwrap_or_else(|_| "production_secret".to_string()),
                 api_key: env::var("API_KEY").unwrap_or_else(|_| "production_key".to_string()),
                 max_connections: 100,
@@ -645,7 +865,7 @@ impl Config {
                 enable_encryption: true,
                 compression_enabled: true,
             },
-            supported_chains: Self::get_supported_chains(),
+            supported_chains: Self::get_supported_chains()?,
             config_file_path: None,
             last_modified: Some(Utc::now().timestamp() as u64),
         })
@@ -702,7 +922,25 @@ impl Config {
         Ok(address)
     }
     
-    fn get_supported_chains() -> HashMap<u64, ChainConfig> {
+    /// Built-in chains, updated and extended by the registry file named in
+    /// `CHAIN_REGISTRY_FILE`, if set
+    fn get_supported_chains() -> Result<HashMap<u64, ChainConfig>> {
+        let mut chains = Self::builtin_chains();
+        Self::apply_chain_registry_file(&mut chains)?;
+        Ok(chains)
+    }
+
+    fn apply_chain_registry_file(chains: &mut HashMap<u64, ChainConfig>) -> Result<()> {
+        match env::var("CHAIN_REGISTRY_FILE") {
+            Ok(path) if !path.is_empty() => {
+                ChainRegistry::load(&path)?.apply_to(chains);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn builtin_chains() -> HashMap<u64, ChainConfig> {
         let mut chains = HashMap::new();
 
         // Core Testnet 2 (Primary)
@@ -751,6 +989,10 @@ impl Config {
                     }),
                 ),
                 max_gas_limit: None,
+                rpc_tls: RpcTlsConfig::from_env("CORE_TESTNET2"),
+                fallback_rpc_urls: ChainConfig::fallback_rpc_urls_from_env("CORE_TESTNET2"),
+                broadcast: BroadcastStrategy::from_env("CORE_TESTNET2"),
+                native_currency: None,
             },
         );
 
@@ -800,6 +1042,10 @@ impl Config {
                     }),
                 ),
                 max_gas_limit: None,
+                rpc_tls: RpcTlsConfig::from_env("BASE_SEPOLIA"),
+                fallback_rpc_urls: ChainConfig::fallback_rpc_urls_from_env("BASE_SEPOLIA"),
+                broadcast: BroadcastStrategy::from_env("BASE_SEPOLIA"),
+                native_currency: None,
             },
         );
 
@@ -849,6 +1095,10 @@ impl Config {
                     }),
                 ),
                 max_gas_limit: None,
+                rpc_tls: RpcTlsConfig::from_env("LISK_SEPOLIA"),
+                fallback_rpc_urls: ChainConfig::fallback_rpc_urls_from_env("LISK_SEPOLIA"),
+                broadcast: BroadcastStrategy::from_env("LISK_SEPOLIA"),
+                native_currency: None,
             },
         );
 
@@ -898,6 +1148,10 @@ impl Config {
                     }),
                 ),
                 max_gas_limit: None,
+                rpc_tls: RpcTlsConfig::from_env("HOLESKY"),
+                fallback_rpc_urls: ChainConfig::fallback_rpc_urls_from_env("HOLESKY"),
+                broadcast: BroadcastStrategy::from_env("HOLESKY"),
+                native_currency: None,
             },
         );
 
@@ -905,6 +1159,13 @@ impl Config {
     }
     
     fn validate(&self) -> Result<()> {
+        const LOG_LEVELS: [&str; 6] = ["trace", "debug", "info", "warn", "error", "off"];
+        for (module, level) in std::iter::once(("default", &self.log_level)).chain(self.log_modules.iter().map(|(m, l)| (m.as_str(), l))) {
+            if !LOG_LEVELS.contains(&level.to_lowercase().as_str()) {
+                return Err(anyhow!("Invalid log level '{}' for {}", level, module));
+            }
+        }
+        
         // Validate main contract address
         if !self.contract_address.is_empty() && !Self::is_valid_hex_address(&self.contract_address) {
             return Err(anyhow!(
@@ -936,6 +1197,37 @@ impl Config {
                     chain_config.name
                 ));
             }
+            
+            for url in &chain_config.fallback_rpc_urls {
+                if !url.starts_with("http://") && !url.starts_with("https://") {
+                    return Err(anyhow!(
+                        "Invalid fallback RPC URL for chain {} ({}): '{}'",
+                        chain_id,
+                        chain_config.name,
+                        url
+                    ));
+                }
+            }
+            
+            if !chain_config.rpc_tls.is_default() && chain_config.rpc_urls().iter().any(|url| !url.starts_with("https://")) {
+                return Err(anyhow!(
+                    "TLS pinning for chain {} ({}) requires https RPC URLs",
+                    chain_id,
+                    chain_config.name
+                ));
+            }
+            
+            for pin in &chain_config.rpc_tls.pinned_fingerprints {
+                let hex_pin = pin.replace(':', "");
+                if hex_pin.len() != 64 || !hex_pin.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(anyhow!(
+                        "Invalid certificate pin for chain {} ({}): '{}'. Expected a SHA-256 fingerprint",
+                        chain_id,
+                        chain_config.name,
+                        pin
+                    ));
+                }
+            }
         }
         
         match self.environment.as_str() {
@@ -1017,6 +1309,21 @@ mod tests {
         std::env::remove_var("TEST_VALID_VAR");
     }
     
+    #[test]
+    fn test_broadcast_strategy_from_env() {
+        std::env::set_var("TEST_STRATEGY_MAX_FEE_GWEI", "250");
+        std::env::set_var("TEST_STRATEGY_CONFIRMATIONS", "0");
+        let strategy = BroadcastStrategy::from_env("TEST_STRATEGY");
+        assert_eq!(strategy.max_fee_gwei, Some(250));
+        // Zero confirmations would never settle anything; fall back instead
+        assert_eq!(strategy.required_confirmations, None);
+        assert!(!strategy.is_default());
+        assert!(BroadcastStrategy::from_env("TEST_STRATEGY_UNSET").is_default());
+
+        std::env::remove_var("TEST_STRATEGY_MAX_FEE_GWEI");
+        std::env::remove_var("TEST_STRATEGY_CONFIRMATIONS");
+    }
+    
     #[test]
     fn test_validate_contract_address() {
         // Test valid address
@@ -2,4 +2,8 @@ pub mod blockchain;
 pub mod storage;
 pub mod monitoring;
 pub mod logger;
-pub mod config; 
\ No newline at end of file
+pub mod telemetry;
+pub mod config;
+pub mod ble;
+pub mod pki;
+pub mod chain_registry;
@@ -0,0 +1,206 @@
+use prometheus::{Encoder, Gauge, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
+use std::collections::HashMap;
+
+/// Upper bounds (seconds) of the HTTP request latency histogram buckets
+pub const REQUEST_LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+/// Upper bounds (seconds) of the broadcast latency histogram buckets
+pub const BROADCAST_LATENCY_BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Counters bumped through `MonitoringManager::increment_metric`, by the
+/// name it takes, with their exported name and help text
+const COUNTERS: &[(&str, &str, &str)] = &[
+    ("transactions_received", "airchainpay_transactions_received_total", "Total number of transactions received"),
+    ("transactions_processed", "airchainpay_transactions_processed_total", "Total number of transactions processed"),
+    ("transactions_failed", "airchainpay_transactions_failed_total", "Total number of transactions failed"),
+    ("transactions_broadcasted", "airchainpay_transactions_broadcasted_total", "Total number of transactions broadcasted"),
+    ("rpc_errors", "airchainpay_rpc_errors_total", "Total number of RPC errors"),
+    ("auth_failures", "airchainpay_auth_failures_total", "Total number of authentication failures"),
+    ("rate_limit_hits", "airchainpay_rate_limit_hits_total", "Total number of rate limit hits"),
+    ("blocked_devices", "airchainpay_blocked_devices_total", "Total number of blocked devices"),
+    ("requests_total", "airchainpay_requests_total", "Total number of requests"),
+    ("requests_successful", "airchainpay_requests_successful_total", "Total number of successful requests"),
+    ("requests_failed", "airchainpay_requests_failed_total", "Total number of failed requests"),
+    ("database_operations", "airchainpay_database_operations_total", "Total number of database operations"),
+    ("database_errors", "airchainpay_database_errors_total", "Total number of database errors"),
+    ("compression_operations", "airchainpay_compression_operations_total", "Total number of compression operations"),
+    ("security_events", "airchainpay_security_events_total", "Total number of security events"),
+    ("validation_failures", "airchainpay_validation_failures_total", "Total number of validation failures"),
+    ("cache_hits", "airchainpay_cache_hits_total", "Total number of cache hits"),
+    ("cache_misses", "airchainpay_cache_misses_total", "Total number of cache misses"),
+    ("network_errors", "airchainpay_network_errors_total", "Total number of network errors"),
+    ("blockchain_confirmations", "airchainpay_blockchain_confirmations_total", "Total number of blockchain confirmations"),
+    ("blockchain_timeouts", "airchainpay_blockchain_timeouts_total", "Total number of blockchain timeouts"),
+    ("blockchain_rebroadcasts", "airchainpay_blockchain_rebroadcasts_total", "Total number of unchanged rebroadcasts of unmined transactions"),
+    ("blockchain_fee_bumps", "airchainpay_blockchain_fee_bumps_total", "Total number of fee-bumped replacements of sponsored transactions"),
+    ("gas_price_updates", "airchainpay_gas_price_updates_total", "Total number of gas price updates"),
+    ("contract_events", "airchainpay_contract_events_total", "Total number of contract events"),
+];
+
+/// Point-in-time values copied into the gauges before each scrape
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeGauges {
+    pub uptime_seconds: f64,
+    pub response_time_avg_ms: f64,
+    pub active_connections: u64,
+    pub memory_usage_bytes: u64,
+    pub cpu_usage_percent: f64,
+    pub system_memory_usage_bytes: u64,
+    pub system_cpu_usage_percent: f64,
+    pub system_thread_count: u64,
+}
+
+/// The relay's Prometheus registry and the metrics registered in it
+pub struct PrometheusExporter {
+    registry: Registry,
+    counters: HashMap<&'static str, IntCounter>,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    transactions: IntCounterVec,
+    broadcast_duration: HistogramVec,
+    uptime_seconds: Gauge,
+    response_time_avg_ms: Gauge,
+    active_connections: IntGauge,
+    memory_usage_bytes: IntGauge,
+    cpu_usage_percent: Gauge,
+    system_memory_usage_bytes: IntGauge,
+    system_cpu_usage_percent: Gauge,
+    system_thread_count: IntGauge,
+}
+
+impl std::fmt::Debug for PrometheusExporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PrometheusExporter").finish_non_exhaustive()
+    }
+}
+
+impl Default for PrometheusExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PrometheusExporter {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+        // Names and labels below are fixed, so registration cannot fail
+        fn register<T: prometheus::core::Collector + Clone + 'static>(registry: &Registry, metric: T) -> T {
+            registry.register(Box::new(metric.clone())).expect("metric registered twice");
+            metric
+        }
+
+        let counters = COUNTERS
+            .iter()
+            .map(|(key, name, help)| (*key, register(&registry, IntCounter::new(*name, *help).unwrap())))
+            .collect();
+
+        let http_requests = register(&registry, IntCounterVec::new(
+            Opts::new("airchainpay_http_requests_total", "Requests by route template, method and status class"),
+            &["method", "route", "status_class"],
+        ).unwrap());
+        let http_request_duration = register(&registry, HistogramVec::new(
+            HistogramOpts::new("airchainpay_http_request_duration_seconds", "Request latency by route template and method")
+                .buckets(REQUEST_LATENCY_BUCKETS.to_vec()),
+            &["method", "route"],
+        ).unwrap());
+        let transactions = register(&registry, IntCounterVec::new(
+            Opts::new("airchainpay_chain_transactions_total", "Transactions by chain, the endpoint they arrived on and outcome"),
+            &["chain_id", "endpoint", "outcome"],
+        ).unwrap());
+        let broadcast_duration = register(&registry, HistogramVec::new(
+            HistogramOpts::new("airchainpay_broadcast_duration_seconds", "Time to hand a signed transaction to the chain's RPC, per attempt")
+                .buckets(BROADCAST_LATENCY_BUCKETS.to_vec()),
+            &["chain_id", "result"],
+        ).unwrap());
+
+        Self {
+            counters,
+            http_requests,
+            http_request_duration,
+            transactions,
+            broadcast_duration,
+            uptime_seconds: register(&registry, Gauge::new("airchainpay_uptime_seconds", "Server uptime in seconds").unwrap()),
+            response_time_avg_ms: register(&registry, Gauge::new("airchainpay_response_time_avg_ms", "Average response time in milliseconds").unwrap()),
+            active_connections: register(&registry, IntGauge::new("airchainpay_active_connections", "Current number of active connections").unwrap()),
+            memory_usage_bytes: register(&registry, IntGauge::new("airchainpay_memory_usage_bytes", "Memory usage in bytes").unwrap()),
+            cpu_usage_percent: register(&registry, Gauge::new("airchainpay_cpu_usage_percent", "CPU usage percentage").unwrap()),
+            system_memory_usage_bytes: register(&registry, IntGauge::new("airchainpay_system_memory_usage_bytes", "System memory usage in bytes").unwrap()),
+            system_cpu_usage_percent: register(&registry, Gauge::new("airchainpay_system_cpu_usage_percent", "System CPU usage percentage").unwrap()),
+            system_thread_count: register(&registry, IntGauge::new("airchainpay_system_thread_count", "Number of system threads").unwrap()),
+            registry,
+        }
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Bump a counter by its `increment_metric` name; false if there is none
+    pub fn inc_counter(&self, key: &str) -> bool {
+        match self.counters.get(key) {
+            Some(counter) => {
+                counter.inc();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn observe_request(&self, method: &str, route: &str, status_class: &str, seconds: f64) {
+        self.http_requests.with_label_values(&[method, route, status_class]).inc();
+        self.http_request_duration.with_label_values(&[method, route]).observe(seconds);
+    }
+
+    /// Count a transaction on `chain_id` reaching `outcome` ("queued",
+    /// "rejected", "broadcast", "failed") after arriving on `endpoint`
+    pub fn record_transaction(&self, chain_id: u64, endpoint: &str, outcome: &str) {
+        let chain_id = chain_id.to_string();
+        self.transactions.with_label_values(&[chain_id.as_str(), endpoint, outcome]).inc();
+    }
+
+    pub fn observe_broadcast(&self, chain_id: u64, success: bool, seconds: f64) {
+        let result = if success { "success" } else { "error" };
+        let chain_id = chain_id.to_string();
+        self.broadcast_duration.with_label_values(&[chain_id.as_str(), result]).observe(seconds);
+    }
+
+    pub fn set_runtime(&self, gauges: &RuntimeGauges) {
+        self.uptime_seconds.set(gauges.uptime_seconds);
+        self.response_time_avg_ms.set(gauges.response_time_avg_ms);
+        self.active_connections.set(gauges.active_connections as i64);
+        self.memory_usage_bytes.set(gauges.memory_usage_bytes as i64);
+        self.cpu_usage_percent.set(gauges.cpu_usage_percent);
+        self.system_memory_usage_bytes.set(gauges.system_memory_usage_bytes as i64);
+        self.system_cpu_usage_percent.set(gauges.system_cpu_usage_percent);
+        self.system_thread_count.set(gauges.system_thread_count as i64);
+    }
+
+    /// Everything in the registry in Prometheus text format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            log::error!("Failed to encode Prometheus metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_renders_labeled_counters_and_histograms() {
+        let exporter = PrometheusExporter::new();
+        assert!(exporter.inc_counter("transactions_received"));
+        assert!(!exporter.inc_counter("no_such_metric"));
+        exporter.observe_request("POST", "/api/send_tx", "2xx", 0.03);
+        exporter.record_transaction(84532, "http", "queued");
+        exporter.observe_broadcast(84532, true, 0.4);
+
+        let rendered = exporter.render();
+        assert!(rendered.contains("airchainpay_transactions_received_total 1"));
+        assert!(rendered.contains("airchainpay_http_request_duration_seconds_bucket{method=\"POST\",route=\"/api/send_tx\",le=\"0.05\"} 1"));
+        assert!(rendered.contains("airchainpay_chain_transactions_total{chain_id=\"84532\",endpoint=\"http\",outcome=\"queued\"} 1"));
+        assert!(rendered.contains("airchainpay_broadcast_duration_seconds_count{chain_id=\"84532\",result=\"success\"} 1"));
+    }
+}
@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use std::time::Duration;
 use tokio::time::interval;
+use super::exporter::{PrometheusExporter, RuntimeGauges};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PrometheusMetrics {
@@ -37,6 +38,8 @@ pub struct PrometheusMetrics {
     pub network_errors: u64,
     pub blockchain_confirmations: u64,
     pub blockchain_timeouts: u64,
+    pub blockchain_rebroadcasts: u64,
+    pub blockchain_fee_bumps: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,6 +85,217 @@ pub struct AlertRule {
     pub enabled: bool,
 }
 
+/// Upper bounds (ms) of the compression latency histogram buckets
+pub const COMPRESSION_LATENCY_BUCKETS_MS: [f64; 10] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 25.0, 50.0, 100.0];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressionMetrics {
+    pub format: String,
+    /// "compress" or "decompress"
+    pub operation: String,
+    pub count: u64,
+    /// Bytes handed to the codec
+    pub bytes_in: u64,
+    /// Bytes the codec produced
+    pub bytes_out: u64,
+    pub latency_sum_ms: f64,
+    /// Cumulative counts aligned with `COMPRESSION_LATENCY_BUCKETS_MS`
+    pub latency_buckets: Vec<u64>,
+}
+
+impl CompressionMetrics {
+    fn new(format: &str, operation: &str) -> Self {
+        Self {
+            format: format.to_string(),
+            operation: operation.to_string(),
+            count: 0,
+            bytes_in: 0,
+            bytes_out: 0,
+            latency_sum_ms: 0.0,
+            latency_buckets: vec![0; COMPRESSION_LATENCY_BUCKETS_MS.len()],
+        }
+    }
+
+    fn observe(&mut self, bytes_in: u64, bytes_out: u64, latency_ms: f64) {
+        self.count += 1;
+        self.bytes_in += bytes_in;
+        self.bytes_out += bytes_out;
+        self.latency_sum_ms += latency_ms;
+        for (i, bound) in COMPRESSION_LATENCY_BUCKETS_MS.iter().enumerate() {
+            if latency_ms <= *bound {
+                self.latency_buckets[i] += 1;
+            }
+        }
+    }
+
+    /// Compressed size over original size across all observations
+    pub fn ratio(&self) -> f64 {
+        let (original, compressed) = match self.operation.as_str() {
+            "decompress" => (self.bytes_out, self.bytes_in),
+            _ => (self.bytes_in, self.bytes_out),
+        };
+        if original == 0 {
+            return 1.0;
+        }
+        compressed as f64 / original as f64
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReconciliationMetrics {
+    pub runs: u64,
+    pub transactions_checked: u64,
+    /// Divergences found, by kind ("mined", "reverted", "dropped")
+    pub mismatches: HashMap<String, u64>,
+}
+
+/// Results of canary transactions on one chain
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CanaryMetrics {
+    pub runs: u64,
+    pub failures: u64,
+    pub healthy: bool,
+    /// Send-to-receipt time of the last confirmed canary
+    pub last_confirmation_ms: Option<u64>,
+}
+
+/// Submissions that took one payload tier, with their pre-queue latency
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SubmissionTierMetrics {
+    pub submissions: u64,
+    pub latency_ms_sum: f64,
+}
+
+/// BLE sessions whose figures are kept after they end
+pub const MAX_RECENT_BLE_SESSIONS: usize = 50;
+/// Share of retransmitted frames above which the BLE component reports "degraded"
+pub const BLE_DEGRADED_RETRANSMIT_RATIO: f64 = 0.1;
+
+/// Radio figures for one connected BLE central
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BleSessionMetrics {
+    /// Bluetooth address of the central
+    pub peer: String,
+    pub device_id: Option<String>,
+    pub session_id: Option<String>,
+    /// ATT MTU the central negotiated, as reported with its writes
+    pub mtu: Option<u16>,
+    pub frames_received: u64,
+    /// Frames received again after they had already arrived
+    pub retransmitted_frames: u64,
+    /// Messages dropped for a bad header, size or checksum
+    pub dropped_messages: u64,
+    pub bytes_received: u64,
+    /// Bytes of messages notified to this central, before framing
+    pub bytes_sent: u64,
+    /// From `noise_init` to the session being opened
+    pub handshake_ms: Option<u64>,
+    pub connected_at: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+impl BleSessionMetrics {
+    pub fn new(peer: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            peer: peer.to_string(),
+            device_id: None,
+            session_id: None,
+            mtu: None,
+            frames_received: 0,
+            retransmitted_frames: 0,
+            dropped_messages: 0,
+            bytes_received: 0,
+            bytes_sent: 0,
+            handshake_ms: None,
+            connected_at: now,
+            last_seen: now,
+            ended_at: None,
+        }
+    }
+
+    /// Bytes in both directions per second between the first and last write
+    pub fn throughput_bytes_per_sec(&self) -> f64 {
+        let seconds = (self.last_seen - self.connected_at).num_milliseconds() as f64 / 1000.0;
+        if seconds <= 0.0 {
+            return 0.0;
+        }
+        (self.bytes_received + self.bytes_sent) as f64 / seconds
+    }
+}
+
+/// BLE counters since startup, plus active and recently ended sessions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BleMetrics {
+    pub sessions_total: u64,
+    pub handshakes_completed: u64,
+    pub handshake_failures: u64,
+    pub handshake_ms_sum: u64,
+    pub frames_received: u64,
+    pub retransmitted_frames: u64,
+    pub dropped_messages: u64,
+    pub active: HashMap<String, BleSessionMetrics>,
+    /// Most recent last
+    pub recent: Vec<BleSessionMetrics>,
+}
+
+impl BleMetrics {
+    /// Retransmitted frames over all frames received since startup
+    pub fn retransmit_ratio(&self) -> f64 {
+        if self.frames_received == 0 {
+            return 0.0;
+        }
+        self.retransmitted_frames as f64 / self.frames_received as f64
+    }
+
+    pub fn average_handshake_ms(&self) -> Option<f64> {
+        (self.handshakes_completed > 0).then(|| self.handshake_ms_sum as f64 / self.handshakes_completed as f64)
+    }
+}
+
+/// Devices tracked before the least recently seen are dropped
+pub const MAX_TRACKED_DEVICES: usize = 10_000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeviceMetrics {
+    pub device_id: String,
+    pub requests: u64,
+    pub submissions: u64,
+    pub failures: u64,
+    pub auth_errors: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceMetric {
+    Requests,
+    Submissions,
+    Failures,
+    AuthErrors,
+    Bytes,
+}
+
+impl DeviceMetrics {
+    fn value(&self, metric: DeviceMetric) -> u64 {
+        match metric {
+            DeviceMetric::Requests => self.requests,
+            DeviceMetric::Submissions => self.submissions,
+            DeviceMetric::Failures => self.failures,
+            DeviceMetric::AuthErrors => self.auth_errors,
+            DeviceMetric::Bytes => self.bytes_in + self.bytes_out,
+        }
+    }
+}
+
+/// Map an HTTP status code to its class label ("2xx", "4xx", ...)
+pub fn status_class(status: u16) -> String {
+    format!("{}xx", status / 100)
+}
+
 #[derive(Debug)]
 pub struct MonitoringManager {
     metrics: Arc<RwLock<PrometheusMetrics>>,
@@ -90,6 +304,15 @@ pub struct MonitoringManager {
     alert_rules: Arc<RwLock<Vec<AlertRule>>>,
     start_time: DateTime<Utc>,
     response_times: Arc<RwLock<Vec<f64>>>,
+    device_metrics: Arc<RwLock<HashMap<String, DeviceMetrics>>>,
+    /// Behind a std mutex because the codecs record from synchronous code
+    compression_metrics: Arc<std::sync::Mutex<HashMap<(String, String), CompressionMetrics>>>,
+    reconciliation_metrics: Arc<RwLock<ReconciliationMetrics>>,
+    canary_metrics: Arc<RwLock<HashMap<u64, CanaryMetrics>>>,
+    submission_tier_metrics: Arc<RwLock<HashMap<String, SubmissionTierMetrics>>>,
+    ble_metrics: Arc<RwLock<BleMetrics>>,
+    /// Registry behind `/metrics`
+    exporter: PrometheusExporter,
 }
 
 impl Default for MonitoringManager {
@@ -107,6 +330,13 @@ impl MonitoringManager {
             alert_rules: Arc::new(RwLock::new(Self::default_alert_rules())),
             start_time: Utc::now(),
             response_times: Arc::new(RwLock::new(Vec::new())),
+            device_metrics: Arc::new(RwLock::new(HashMap::new())),
+            compression_metrics: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            reconciliation_metrics: Arc::new(RwLock::new(ReconciliationMetrics::default())),
+            canary_metrics: Arc::new(RwLock::new(HashMap::new())),
+            submission_tier_metrics: Arc::new(RwLock::new(HashMap::new())),
+            ble_metrics: Arc::new(RwLock::new(BleMetrics::default())),
+            exporter: PrometheusExporter::new(),
         };
 
         // Start system metrics collection
@@ -233,8 +463,11 @@ impl MonitoringManager {
             "network_errors" => metrics.network_errors += 1,
             "blockchain_confirmations" => metrics.blockchain_confirmations += 1,
             "blockchain_timeouts" => metrics.blockchain_timeouts += 1,
+            "blockchain_rebroadcasts" => metrics.blockchain_rebroadcasts += 1,
+            "blockchain_fee_bumps" => metrics.blockchain_fee_bumps += 1,
             _ => println!("Unknown metric: {metric_name}"),
         }
+        self.exporter.inc_counter(metric_name);
         
         // Update uptime
         metrics.uptime_seconds = (Utc::now() - self.start_time).num_seconds() as f64;
@@ -259,6 +492,363 @@ impl MonitoringManager {
 
 
 
+    /// Record a request against its route template (e.g. `/backup/{backup_id}`) and status class
+    pub async fn record_route_request(&self, method: &str, route: &str, status: u16, latency_ms: f64) {
+        self.exporter.observe_request(method, route, &status_class(status), latency_ms / 1000.0);
+    }
+
+    /// Count a transaction on `chain_id` reaching `outcome` ("queued",
+    /// "rejected", "broadcast", "failed") after arriving on `endpoint`
+    pub fn record_chain_transaction(&self, chain_id: u64, endpoint: &str, outcome: &str) {
+        self.exporter.record_transaction(chain_id, endpoint, outcome);
+    }
+
+    /// Time one broadcast attempt took, successful or not
+    pub fn record_broadcast(&self, chain_id: u64, success: bool, latency: Duration) {
+        self.exporter.observe_broadcast(chain_id, success, latency.as_secs_f64());
+    }
+
+    /// Record one codec run with its real input/output sizes
+    pub fn record_compression(&self, format: &str, operation: &str, bytes_in: usize, bytes_out: usize, latency_ms: f64) {
+        let mut compression = self.compression_metrics.lock().unwrap();
+        compression
+            .entry((format.to_string(), operation.to_string()))
+            .or_insert_with(|| CompressionMetrics::new(format, operation))
+            .observe(bytes_in as u64, bytes_out as u64, latency_ms);
+    }
+
+    pub fn get_compression_metrics(&self) -> Vec<CompressionMetrics> {
+        let compression = self.compression_metrics.lock().unwrap();
+        let mut snapshot: Vec<CompressionMetrics> = compression.values().cloned().collect();
+        snapshot.sort_by(|a, b| (&a.format, &a.operation).cmp(&(&b.format, &b.operation)));
+        snapshot
+    }
+
+    pub async fn record_reconciliation_run(&self, checked: usize) {
+        let mut reconciliation = self.reconciliation_metrics.write().await;
+        reconciliation.runs += 1;
+        reconciliation.transactions_checked += checked as u64;
+    }
+
+    pub async fn record_reconciliation_mismatch(&self, kind: &str) {
+        let mut reconciliation = self.reconciliation_metrics.write().await;
+        *reconciliation.mismatches.entry(kind.to_string()).or_insert(0) += 1;
+    }
+
+    /// Render reconciliation runs and divergences in Prometheus text format
+    pub async fn render_reconciliation_metrics(&self) -> String {
+        let reconciliation = self.reconciliation_metrics.read().await.clone();
+        let mut out = String::new();
+
+        out.push_str("# HELP airchainpay_reconciliation_runs_total Completed status reconciliation runs\n");
+        out.push_str("# TYPE airchainpay_reconciliation_runs_total counter\n");
+        out.push_str(&format!("airchainpay_reconciliation_runs_total {}\n", reconciliation.runs));
+
+        out.push_str("\n# HELP airchainpay_reconciliation_checked_total Transactions compared against on-chain receipts\n");
+        out.push_str("# TYPE airchainpay_reconciliation_checked_total counter\n");
+        out.push_str(&format!("airchainpay_reconciliation_checked_total {}\n", reconciliation.transactions_checked));
+
+        out.push_str("\n# HELP airchainpay_reconciliation_mismatches_total Stored statuses that disagreed with the chain, by kind\n");
+        out.push_str("# TYPE airchainpay_reconciliation_mismatches_total counter\n");
+        let mut kinds: Vec<_> = reconciliation.mismatches.iter().collect();
+        kinds.sort();
+        for (kind, count) in kinds {
+            out.push_str(&format!("airchainpay_reconciliation_mismatches_total{{kind=\"{kind}\"}} {count}\n"));
+        }
+
+        out
+    }
+
+    pub async fn record_canary(&self, chain_id: u64, healthy: bool, confirmation_ms: Option<u64>) {
+        let mut canaries = self.canary_metrics.write().await;
+        let canary = canaries.entry(chain_id).or_default();
+        canary.runs += 1;
+        canary.healthy = healthy;
+        if healthy {
+            canary.last_confirmation_ms = confirmation_ms;
+        } else {
+            canary.failures += 1;
+        }
+    }
+
+    /// Render per-chain canary health and latency in Prometheus text format
+    pub async fn render_canary_metrics(&self) -> String {
+        let mut canaries: Vec<(u64, CanaryMetrics)> = self.canary_metrics.read().await
+            .iter()
+            .map(|(chain_id, m)| (*chain_id, m.clone()))
+            .collect();
+        canaries.sort_by_key(|(chain_id, _)| *chain_id);
+        let mut out = String::new();
+
+        out.push_str("# HELP airchainpay_canary_healthy Whether the last canary transaction on a chain was mined (1) or not (0)\n");
+        out.push_str("# TYPE airchainpay_canary_healthy gauge\n");
+        for (chain_id, canary) in &canaries {
+            out.push_str(&format!("airchainpay_canary_healthy{{chain_id=\"{chain_id}\"}} {}\n", canary.healthy as u8));
+        }
+
+        out.push_str("\n# HELP airchainpay_canary_confirmation_seconds Send-to-receipt time of the last confirmed canary transaction\n");
+        out.push_str("# TYPE airchainpay_canary_confirmation_seconds gauge\n");
+        for (chain_id, canary) in &canaries {
+            if let Some(ms) = canary.last_confirmation_ms {
+                out.push_str(&format!("airchainpay_canary_confirmation_seconds{{chain_id=\"{chain_id}\"}} {:.3}\n", ms as f64 / 1000.0));
+            }
+        }
+
+        out.push_str("\n# HELP airchainpay_canary_runs_total Canary transactions sent\n");
+        out.push_str("# TYPE airchainpay_canary_runs_total counter\n");
+        for (chain_id, canary) in &canaries {
+            out.push_str(&format!("airchainpay_canary_runs_total{{chain_id=\"{chain_id}\"}} {}\n", canary.runs));
+        }
+
+        out.push_str("\n# HELP airchainpay_canary_failures_total Canary transactions that failed, reverted or timed out\n");
+        out.push_str("# TYPE airchainpay_canary_failures_total counter\n");
+        for (chain_id, canary) in &canaries {
+            out.push_str(&format!("airchainpay_canary_failures_total{{chain_id=\"{chain_id}\"}} {}\n", canary.failures));
+        }
+
+        out
+    }
+
+    /// Record a submission's payload tier and the time spent validating it
+    pub async fn record_submission_tier(&self, tier: &str, latency_ms: f64) {
+        let mut tiers = self.submission_tier_metrics.write().await;
+        let metrics = tiers.entry(tier.to_string()).or_default();
+        metrics.submissions += 1;
+        metrics.latency_ms_sum += latency_ms;
+    }
+
+    /// Render submissions and validation latency per payload tier in Prometheus text format
+    pub async fn render_submission_tier_metrics(&self) -> String {
+        let mut tiers: Vec<(String, SubmissionTierMetrics)> = self.submission_tier_metrics.read().await
+            .iter()
+            .map(|(tier, m)| (tier.clone(), m.clone()))
+            .collect();
+        tiers.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut out = String::new();
+
+        out.push_str("# HELP airchainpay_submissions_total Transaction submissions by payload tier\n");
+        out.push_str("# TYPE airchainpay_submissions_total counter\n");
+        for (tier, metrics) in &tiers {
+            out.push_str(&format!("airchainpay_submissions_total{{tier=\"{tier}\"}} {}\n", metrics.submissions));
+        }
+
+        out.push_str("\n# HELP airchainpay_submission_validation_seconds_sum Time spent validating submissions before queueing, by payload tier\n");
+        out.push_str("# TYPE airchainpay_submission_validation_seconds_sum counter\n");
+        for (tier, metrics) in &tiers {
+            out.push_str(&format!("airchainpay_submission_validation_seconds_sum{{tier=\"{tier}\"}} {:.6}\n", metrics.latency_ms_sum / 1000.0));
+        }
+
+        out
+    }
+
+    /// Store the latest figures of an active BLE session and add what
+    /// changed since the last call to the totals; the first call for a peer
+    /// counts as a new session
+    pub async fn record_ble_session(&self, session: &BleSessionMetrics) {
+        let mut ble = self.ble_metrics.write().await;
+        let previous = ble.active.get(&session.peer)
+            .map(|p| (p.frames_received, p.retransmitted_frames, p.dropped_messages));
+        if previous.is_none() {
+            ble.sessions_total += 1;
+        }
+        let (frames, retransmitted, dropped) = previous.unwrap_or_default();
+        ble.frames_received += session.frames_received.saturating_sub(frames);
+        ble.retransmitted_frames += session.retransmitted_frames.saturating_sub(retransmitted);
+        ble.dropped_messages += session.dropped_messages.saturating_sub(dropped);
+        ble.active.insert(session.peer.clone(), session.clone());
+    }
+
+    pub async fn record_ble_handshake(&self, duration_ms: Option<u64>) {
+        let mut ble = self.ble_metrics.write().await;
+        match duration_ms {
+            Some(ms) => {
+                ble.handshakes_completed += 1;
+                ble.handshake_ms_sum += ms;
+            }
+            None => ble.handshake_failures += 1,
+        }
+    }
+
+    /// Move a peer's session from the active set to the recent ones
+    pub async fn end_ble_session(&self, peer: &str) {
+        let mut ble = self.ble_metrics.write().await;
+        if let Some(mut session) = ble.active.remove(peer) {
+            session.ended_at = Some(Utc::now());
+            ble.recent.push(session);
+            if ble.recent.len() > MAX_RECENT_BLE_SESSIONS {
+                let excess = ble.recent.len() - MAX_RECENT_BLE_SESSIONS;
+                ble.recent.drain(..excess);
+            }
+        }
+    }
+
+    pub async fn get_ble_metrics(&self) -> BleMetrics {
+        self.ble_metrics.read().await.clone()
+    }
+
+    /// Render BLE session and frame counters in Prometheus text format
+    pub async fn render_ble_metrics(&self) -> String {
+        let ble = self.ble_metrics.read().await;
+        let mut out = String::new();
+
+        out.push_str("# HELP airchainpay_ble_sessions_active Centrals currently connected over BLE\n");
+        out.push_str("# TYPE airchainpay_ble_sessions_active gauge\n");
+        out.push_str(&format!("airchainpay_ble_sessions_active {}\n", ble.active.len()));
+
+        out.push_str("\n# HELP airchainpay_ble_sessions_total BLE centrals seen since startup\n");
+        out.push_str("# TYPE airchainpay_ble_sessions_total counter\n");
+        out.push_str(&format!("airchainpay_ble_sessions_total {}\n", ble.sessions_total));
+
+        out.push_str("\n# HELP airchainpay_ble_handshakes_total Noise handshakes over BLE by result\n");
+        out.push_str("# TYPE airchainpay_ble_handshakes_total counter\n");
+        out.push_str(&format!("airchainpay_ble_handshakes_total{{result=\"completed\"}} {}\n", ble.handshakes_completed));
+        out.push_str(&format!("airchainpay_ble_handshakes_total{{result=\"failed\"}} {}\n", ble.handshake_failures));
+
+        out.push_str("\n# HELP airchainpay_ble_handshake_seconds_sum Time from noise_init to an open session\n");
+        out.push_str("# TYPE airchainpay_ble_handshake_seconds_sum counter\n");
+        out.push_str(&format!("airchainpay_ble_handshake_seconds_sum {:.3}\n", ble.handshake_ms_sum as f64 / 1000.0));
+
+        out.push_str("\n# HELP airchainpay_ble_frames_received_total Frames written by BLE centrals\n");
+        out.push_str("# TYPE airchainpay_ble_frames_received_total counter\n");
+        out.push_str(&format!("airchainpay_ble_frames_received_total {}\n", ble.frames_received));
+
+        out.push_str("\n# HELP airchainpay_ble_frames_retransmitted_total Frames written again after they had arrived\n");
+        out.push_str("# TYPE airchainpay_ble_frames_retransmitted_total counter\n");
+        out.push_str(&format!("airchainpay_ble_frames_retransmitted_total {}\n", ble.retransmitted_frames));
+
+        out.push_str("\n# HELP airchainpay_ble_messages_dropped_total Messages dropped for a bad header, size or checksum\n");
+        out.push_str("# TYPE airchainpay_ble_messages_dropped_total counter\n");
+        out.push_str(&format!("airchainpay_ble_messages_dropped_total {}\n", ble.dropped_messages));
+
+        out
+    }
+
+    /// Attribute a request to the device that made it
+    pub async fn record_device_request(&self, device_id: &str, submission: bool, status: u16, bytes_in: u64, bytes_out: u64) {
+        let mut devices = self.device_metrics.write().await;
+        if !devices.contains_key(device_id) && devices.len() >= MAX_TRACKED_DEVICES {
+            let stalest = devices.values().min_by_key(|d| d.last_seen).map(|d| d.device_id.clone());
+            if let Some(stalest) = stalest {
+                devices.remove(&stalest);
+            }
+        }
+
+        let entry = devices.entry(device_id.to_string()).or_insert_with(|| DeviceMetrics {
+            device_id: device_id.to_string(),
+            ..Default::default()
+        });
+        entry.requests += 1;
+        if submission {
+            entry.submissions += 1;
+        }
+        if status >= 400 {
+            entry.failures += 1;
+        }
+        if status == 401 || status == 403 {
+            entry.auth_errors += 1;
+        }
+        entry.bytes_in += bytes_in;
+        entry.bytes_out += bytes_out;
+        entry.last_seen = Some(Utc::now());
+    }
+
+    /// Devices with the highest value for `metric`, largest first
+    pub async fn top_devices(&self, metric: DeviceMetric, limit: usize) -> Vec<DeviceMetrics> {
+        let devices = self.device_metrics.read().await;
+        let mut ranked: Vec<DeviceMetrics> = devices.values()
+            .filter(|d| d.value(metric) > 0)
+            .cloned()
+            .collect();
+        ranked.sort_by(|a, b| b.value(metric).cmp(&a.value(metric)).then_with(|| a.device_id.cmp(&b.device_id)));
+        ranked.truncate(limit);
+        ranked
+    }
+
+    pub async fn tracked_device_count(&self) -> usize {
+        self.device_metrics.read().await.len()
+    }
+
+    /// Everything `/metrics` serves: the registry, with its gauges brought
+    /// up to date, followed by the compression, reconciliation, canary,
+    /// submission tier and BLE sections
+    pub async fn render_prometheus(&self) -> String {
+        let metrics = self.get_metrics().await;
+        let system = self.get_system_metrics().await;
+        self.exporter.set_runtime(&RuntimeGauges {
+            uptime_seconds: (Utc::now() - self.start_time).num_seconds() as f64,
+            response_time_avg_ms: metrics.response_time_avg_ms,
+            active_connections: metrics.active_connections,
+            memory_usage_bytes: metrics.memory_usage_bytes,
+            cpu_usage_percent: metrics.cpu_usage_percent,
+            system_memory_usage_bytes: system.memory_usage_bytes,
+            system_cpu_usage_percent: system.cpu_usage_percent,
+            system_thread_count: system.thread_count,
+        });
+
+        [
+            self.exporter.render(),
+            self.render_compression_metrics(),
+            self.render_reconciliation_metrics().await,
+            self.render_canary_metrics().await,
+            self.render_submission_tier_metrics().await,
+            self.render_ble_metrics().await,
+        ]
+        .join("\n")
+    }
+
+    /// Render per-format compression sizes, ratios and latency histograms in Prometheus text format
+    pub fn render_compression_metrics(&self) -> String {
+        let entries = self.get_compression_metrics();
+        let mut out = String::new();
+
+        out.push_str("# HELP airchainpay_compression_payloads_total Payloads run through a codec by format and operation\n");
+        out.push_str("# TYPE airchainpay_compression_payloads_total counter\n");
+        for c in &entries {
+            out.push_str(&format!(
+                "airchainpay_compression_payloads_total{{format=\"{}\",operation=\"{}\"}} {}\n",
+                c.format, c.operation, c.count
+            ));
+        }
+
+        out.push_str("\n# HELP airchainpay_compression_bytes_in_total Bytes handed to the codec\n");
+        out.push_str("# TYPE airchainpay_compression_bytes_in_total counter\n");
+        for c in &entries {
+            out.push_str(&format!(
+                "airchainpay_compression_bytes_in_total{{format=\"{}\",operation=\"{}\"}} {}\n",
+                c.format, c.operation, c.bytes_in
+            ));
+        }
+
+        out.push_str("\n# HELP airchainpay_compression_bytes_out_total Bytes produced by the codec\n");
+        out.push_str("# TYPE airchainpay_compression_bytes_out_total counter\n");
+        for c in &entries {
+            out.push_str(&format!(
+                "airchainpay_compression_bytes_out_total{{format=\"{}\",operation=\"{}\"}} {}\n",
+                c.format, c.operation, c.bytes_out
+            ));
+        }
+
+        out.push_str("\n# HELP airchainpay_compression_ratio Compressed size over original size by format\n");
+        out.push_str("# TYPE airchainpay_compression_ratio gauge\n");
+        for c in entries.iter().filter(|c| c.operation == "compress") {
+            out.push_str(&format!("airchainpay_compression_ratio{{format=\"{}\"}} {}\n", c.format, c.ratio()));
+        }
+
+        out.push_str("\n# HELP airchainpay_compression_duration_ms Codec latency by format and operation in milliseconds\n");
+        out.push_str("# TYPE airchainpay_compression_duration_ms histogram\n");
+        for c in &entries {
+            let labels = format!("format=\"{}\",operation=\"{}\"", c.format, c.operation);
+            for (bound, count) in COMPRESSION_LATENCY_BUCKETS_MS.iter().zip(c.latency_buckets.iter()) {
+                out.push_str(&format!("airchainpay_compression_duration_ms_bucket{{{labels},le=\"{bound}\"}} {count}\n"));
+            }
+            out.push_str(&format!("airchainpay_compression_duration_ms_bucket{{{labels},le=\"+Inf\"}} {}\n", c.count));
+            out.push_str(&format!("airchainpay_compression_duration_ms_sum{{{labels}}} {}\n", c.latency_sum_ms));
+            out.push_str(&format!("airchainpay_compression_duration_ms_count{{{labels}}} {}\n", c.count));
+        }
+
+        out
+    }
+
     pub async fn get_system_metrics(&self) -> SystemMetrics {
         self.system_metrics.read().await.clone()
     }
@@ -318,6 +908,29 @@ impl MonitoringManager {
         self.send_notification(&alert).await;
     }
 
+    /// Raise an alert from a component outside the metric-driven alert rules
+    pub async fn raise_alert(
+        &self,
+        name: &str,
+        severity: AlertSeverity,
+        message: String,
+        metadata: HashMap<String, serde_json::Value>,
+    ) -> Alert {
+        let alert = Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            severity,
+            message,
+            timestamp: Utc::now(),
+            resolved: false,
+            metadata,
+        };
+
+        self.alerts.write().await.push(alert.clone());
+        self.send_notification(&alert).await;
+        alert
+    }
+
     async fn send_notification(&self, alert: &Alert) {
         // In production, this would send to various notification channels
         match alert.severity {
@@ -432,6 +1045,8 @@ impl Default for PrometheusMetrics {
             network_errors: 0,
             blockchain_confirmations: 0,
             blockchain_timeouts: 0,
+            blockchain_rebroadcasts: 0,
+            blockchain_fee_bumps: 0,
         }
     }
 }
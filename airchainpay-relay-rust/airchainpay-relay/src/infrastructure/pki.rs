@@ -0,0 +1,373 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration, Utc};
+use openssl::asn1::Asn1Time;
+use openssl::bn::{BigNum, MsbOption};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private};
+use openssl::ssl::{SslAcceptor, SslAcceptorBuilder, SslFiletype, SslMethod, SslVerifyMode};
+use openssl::x509::extension::{
+    AuthorityKeyIdentifier, BasicConstraints, ExtendedKeyUsage, KeyUsage, SubjectAlternativeName,
+    SubjectKeyIdentifier,
+};
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::{X509, X509NameBuilder, X509Req};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+const DEVICE_URI_PREFIX: &str = "urn:airchainpay:device:";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceCaConfig {
+    /// PEM certificate and key of an intermediate issued by an external CA.
+    /// When unset, a self-signed CA is generated under `<data_dir>/pki`.
+    pub ca_cert_path: Option<String>,
+    #[serde(skip_serializing)]
+    pub ca_key_path: Option<String>,
+    /// Certificates above the issuing CA, sent to devices with their certificate
+    pub ca_chain_path: Option<String>,
+    pub cert_ttl_hours: i64,
+    /// How long a one-time enrollment token stays usable
+    pub enrollment_token_ttl_secs: i64,
+}
+
+impl Default for DeviceCaConfig {
+    fn default() -> Self {
+        Self {
+            ca_cert_path: None,
+            ca_key_path: None,
+            ca_chain_path: None,
+            cert_ttl_hours: 24,
+            enrollment_token_ttl_secs: 900,
+        }
+    }
+}
+
+impl DeviceCaConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            ca_cert_path: std::env::var("DEVICE_CA_CERT").ok().filter(|v| !v.is_empty()),
+            ca_key_path: std::env::var("DEVICE_CA_KEY").ok().filter(|v| !v.is_empty()),
+            ca_chain_path: std::env::var("DEVICE_CA_CHAIN").ok().filter(|v| !v.is_empty()),
+            cert_ttl_hours: std::env::var("DEVICE_CERT_TTL_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|h: &i64| *h > 0)
+                .unwrap_or(defaults.cert_ttl_hours),
+            enrollment_token_ttl_secs: std::env::var("DEVICE_ENROLLMENT_TOKEN_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &i64| *s > 0)
+                .unwrap_or(defaults.enrollment_token_ttl_secs),
+        }
+    }
+}
+
+/// Record of a certificate issued to a device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuedCertificate {
+    pub serial: String,
+    pub device_id: String,
+    pub fingerprint_sha256: String,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// A verified device certificate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceCertificate {
+    pub device_id: String,
+    pub serial: String,
+    pub not_after: DateTime<Utc>,
+}
+
+/// Issues short-lived client certificates to devices and verifies them.
+///
+/// Certificates carry the device id as the subject CN and as a
+/// `urn:airchainpay:device:<id>` SAN URI, with the `clientAuth` usage only,
+/// so they serve both as mTLS client certificates and as the device identity
+/// on the BLE bridge.
+pub struct DeviceCa {
+    config: DeviceCaConfig,
+    cert: X509,
+    key: PKey<Private>,
+    chain_pem: String,
+    registry_path: PathBuf,
+    issued: RwLock<Vec<IssuedCertificate>>,
+}
+
+impl DeviceCa {
+    pub fn load_or_create(data_dir: &str, config: DeviceCaConfig) -> Result<Self> {
+        let pki_dir = Path::new(data_dir).join("pki");
+        std::fs::create_dir_all(&pki_dir)?;
+
+        let (cert, key) = match (&config.ca_cert_path, &config.ca_key_path) {
+            (Some(cert_path), Some(key_path)) => (
+                X509::from_pem(&std::fs::read(cert_path)?)?,
+                PKey::private_key_from_pem(&std::fs::read(key_path)?)?,
+            ),
+            (None, None) => Self::load_or_generate_builtin(&pki_dir)?,
+            _ => return Err(anyhow!("DEVICE_CA_CERT and DEVICE_CA_KEY must be set together")),
+        };
+        if !cert.public_key()?.public_eq(&key) {
+            return Err(anyhow!("Device CA key does not match its certificate"));
+        }
+
+        let mut chain_pem = String::from_utf8(cert.to_pem()?)?;
+        if let Some(chain_path) = &config.ca_chain_path {
+            chain_pem.push_str(&std::fs::read_to_string(chain_path)?);
+        }
+
+        let registry_path = pki_dir.join("issued.json");
+        let issued = if registry_path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&registry_path)?)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            config,
+            cert,
+            key,
+            chain_pem,
+            registry_path,
+            issued: RwLock::new(issued),
+        })
+    }
+
+    fn load_or_generate_builtin(pki_dir: &Path) -> Result<(X509, PKey<Private>)> {
+        let cert_path = pki_dir.join("ca.crt");
+        let key_path = pki_dir.join("ca.key");
+        if cert_path.exists() && key_path.exists() {
+            return Ok((
+                X509::from_pem(&std::fs::read(&cert_path)?)?,
+                PKey::private_key_from_pem(&std::fs::read(&key_path)?)?,
+            ));
+        }
+
+        let key = generate_key()?;
+        let mut name = X509NameBuilder::new()?;
+        name.append_entry_by_nid(Nid::COMMONNAME, "AirChainPay Device CA")?;
+        name.append_entry_by_nid(Nid::ORGANIZATIONNAME, "AirChainPay")?;
+        let name = name.build();
+
+        let mut builder = X509::builder()?;
+        builder.set_version(2)?;
+        builder.set_serial_number(&random_serial()?.to_asn1_integer()?)?;
+        builder.set_subject_name(&name)?;
+        builder.set_issuer_name(&name)?;
+        builder.set_pubkey(&key)?;
+        builder.set_not_before(&*Asn1Time::days_from_now(0)?)?;
+        builder.set_not_after(&*Asn1Time::days_from_now(3650)?)?;
+        builder.append_extension(BasicConstraints::new().critical().ca().pathlen(0).build()?)?;
+        builder.append_extension(KeyUsage::new().critical().key_cert_sign().crl_sign().build()?)?;
+        let subject_key_id = SubjectKeyIdentifier::new().build(&builder.x509v3_context(None, None))?;
+        builder.append_extension(subject_key_id)?;
+        builder.sign(&key, MessageDigest::sha256())?;
+        let cert = builder.build();
+
+        std::fs::write(&cert_path, cert.to_pem()?)?;
+        std::fs::write(&key_path, key.private_key_to_pem_pkcs8()?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        log::info!("Generated built-in device CA at {}", cert_path.display());
+        Ok((cert, key))
+    }
+
+    /// PEM of the issuing CA followed by any configured chain
+    pub fn ca_chain_pem(&self) -> &str {
+        &self.chain_pem
+    }
+
+    pub fn config(&self) -> &DeviceCaConfig {
+        &self.config
+    }
+
+    /// Sign a device CSR. The subject is always set from `device_id`; only the
+    /// public key is taken from the request, after checking its self-signature.
+    pub fn issue(&self, device_id: &str, csr_pem: &str) -> Result<(String, IssuedCertificate)> {
+        if device_id.is_empty() || device_id.len() > 64 {
+            return Err(anyhow!("Device id must be 1-64 characters"));
+        }
+        let csr = X509Req::from_pem(csr_pem.as_bytes())
+            .map_err(|_| anyhow!("Invalid certificate signing request"))?;
+        let public_key = csr.public_key()?;
+        if !csr.verify(&public_key)? {
+            return Err(anyhow!("Certificate signing request signature is invalid"));
+        }
+
+        let not_before = Utc::now() - Duration::minutes(5);
+        let not_after = Utc::now() + Duration::hours(self.config.cert_ttl_hours);
+
+        let mut name = X509NameBuilder::new()?;
+        name.append_entry_by_nid(Nid::COMMONNAME, device_id)?;
+        name.append_entry_by_nid(Nid::ORGANIZATIONNAME, "AirChainPay Devices")?;
+        let name = name.build();
+
+        let serial = random_serial()?;
+        let mut builder = X509::builder()?;
+        builder.set_version(2)?;
+        builder.set_serial_number(&serial.to_asn1_integer()?)?;
+        builder.set_subject_name(&name)?;
+        builder.set_issuer_name(self.cert.subject_name())?;
+        builder.set_pubkey(&public_key)?;
+        builder.set_not_before(&*Asn1Time::from_unix(not_before.timestamp())?)?;
+        builder.set_not_after(&*Asn1Time::from_unix(not_after.timestamp())?)?;
+        builder.append_extension(BasicConstraints::new().critical().build()?)?;
+        builder.append_extension(KeyUsage::new().critical().digital_signature().key_agreement().build()?)?;
+        builder.append_extension(ExtendedKeyUsage::new().client_auth().build()?)?;
+        let san = SubjectAlternativeName::new()
+            .uri(&format!("{DEVICE_URI_PREFIX}{device_id}"))
+            .build(&builder.x509v3_context(Some(&self.cert), None))?;
+        builder.append_extension(san)?;
+        let authority_key_id = AuthorityKeyIdentifier::new()
+            .keyid(false)
+            .build(&builder.x509v3_context(Some(&self.cert), None))?;
+        builder.append_extension(authority_key_id)?;
+        builder.sign(&self.key, MessageDigest::sha256())?;
+        let cert = builder.build();
+
+        let record = IssuedCertificate {
+            serial: serial.to_hex_str()?.to_string(),
+            device_id: device_id.to_string(),
+            fingerprint_sha256: hex::encode(cert.digest(MessageDigest::sha256())?),
+            not_before,
+            not_after,
+            revoked_at: None,
+        };
+        self.issued.write().unwrap().push(record.clone());
+        self.save()?;
+
+        Ok((String::from_utf8(cert.to_pem()?)?, record))
+    }
+
+    /// Check a device certificate: signed by this CA, currently valid, not revoked
+    pub fn verify(&self, cert: &X509) -> Result<DeviceCertificate> {
+        if !cert.verify(&self.cert.public_key()?)? {
+            return Err(anyhow!("Certificate was not issued by the device CA"));
+        }
+        let now = Asn1Time::days_from_now(0)?;
+        if cert.not_before().compare(&now)? == std::cmp::Ordering::Greater
+            || cert.not_after().compare(&now)? == std::cmp::Ordering::Less
+        {
+            return Err(anyhow!("Certificate is expired or not yet valid"));
+        }
+
+        let serial = cert.serial_number().to_bn()?.to_hex_str()?.to_string();
+        let record = self.issued.read().unwrap().iter().find(|r| r.serial == serial).cloned()
+            .ok_or_else(|| anyhow!("Certificate {} is unknown", serial))?;
+        if record.revoked_at.is_some() {
+            return Err(anyhow!("Certificate {} has been revoked", serial));
+        }
+
+        let device_id = cert.subject_name().entries_by_nid(Nid::COMMONNAME).next()
+            .and_then(|entry| entry.data().as_utf8().ok())
+            .map(|cn| cn.to_string())
+            .ok_or_else(|| anyhow!("Certificate has no device id"))?;
+        if device_id != record.device_id {
+            return Err(anyhow!("Certificate subject does not match its issuance record"));
+        }
+
+        Ok(DeviceCertificate {
+            device_id,
+            serial,
+            not_after: record.not_after,
+        })
+    }
+
+    pub fn verify_pem(&self, cert_pem: &str) -> Result<DeviceCertificate> {
+        let cert = X509::from_pem(cert_pem.as_bytes()).map_err(|_| anyhow!("Invalid certificate"))?;
+        self.verify(&cert)
+    }
+
+    pub fn revoke(&self, serial: &str) -> Result<IssuedCertificate> {
+        let record = {
+            let mut issued = self.issued.write().unwrap();
+            let record = issued.iter_mut()
+                .find(|r| r.serial.eq_ignore_ascii_case(serial))
+                .ok_or_else(|| anyhow!("Certificate {} not found", serial))?;
+            record.revoked_at.get_or_insert_with(Utc::now);
+            record.clone()
+        };
+        self.save()?;
+        Ok(record)
+    }
+
+    /// Revoke every live certificate held by a device
+    pub fn revoke_device(&self, device_id: &str) -> Result<usize> {
+        let revoked = {
+            let mut issued = self.issued.write().unwrap();
+            let mut revoked = 0;
+            for record in issued.iter_mut().filter(|r| r.device_id == device_id && r.revoked_at.is_none()) {
+                record.revoked_at = Some(Utc::now());
+                revoked += 1;
+            }
+            revoked
+        };
+        self.save()?;
+        Ok(revoked)
+    }
+
+    pub fn issued(&self, device_id: Option<&str>) -> Vec<IssuedCertificate> {
+        self.issued.read().unwrap().iter()
+            .filter(|r| device_id.is_none_or(|id| r.device_id == id))
+            .cloned()
+            .collect()
+    }
+
+    /// Drop records for certificates that expired more than a day ago
+    pub fn prune_expired(&self) -> Result<usize> {
+        let cutoff = Utc::now() - Duration::days(1);
+        let removed = {
+            let mut issued = self.issued.write().unwrap();
+            let before = issued.len();
+            issued.retain(|r| r.not_after > cutoff);
+            before - issued.len()
+        };
+        if removed > 0 {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    fn save(&self) -> Result<()> {
+        let data = serde_json::to_string_pretty(&*self.issued.read().unwrap())?;
+        std::fs::write(&self.registry_path, data)?;
+        Ok(())
+    }
+
+    /// TLS acceptor for the public listener. Client certificates are requested
+    /// and checked against this CA when offered, but not required, so
+    /// token-authenticated clients keep working.
+    pub fn tls_acceptor(&self, cert_path: &str, key_path: &str) -> Result<SslAcceptorBuilder> {
+        let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls())?;
+        builder.set_private_key_file(key_path, SslFiletype::PEM)?;
+        builder.set_certificate_chain_file(cert_path)?;
+
+        let mut store = X509StoreBuilder::new()?;
+        store.add_cert(self.cert.clone())?;
+        builder.set_verify_cert_store(store.build())?;
+        builder.add_client_ca(&self.cert)?;
+        builder.set_verify(SslVerifyMode::PEER);
+        Ok(builder)
+    }
+}
+
+fn generate_key() -> Result<PKey<Private>> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    Ok(PKey::from_ec_key(EcKey::generate(&group)?)?)
+}
+
+fn random_serial() -> Result<BigNum> {
+    let mut serial = BigNum::new()?;
+    serial.rand(127, MsbOption::MAYBE_ZERO, false)?;
+    Ok(serial)
+}
+
@@ -0,0 +1,141 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::infrastructure::storage::file_storage::Transaction;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedTransaction {
+    pub transaction: Transaction,
+    pub archived_at: DateTime<Utc>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ArchiveQuery {
+    pub id: Option<String>,
+    pub tx_hash: Option<String>,
+    pub chain_id: Option<u64>,
+    pub status: Option<String>,
+    /// Only transactions created at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Only transactions created before this time
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+impl ArchiveQuery {
+    fn matches(&self, entry: &ArchivedTransaction) -> bool {
+        let tx = &entry.transaction;
+        self.id.as_ref().is_none_or(|id| &tx.id == id)
+            && self.tx_hash.as_ref().is_none_or(|hash| tx.tx_hash.as_ref().is_some_and(|h| h.eq_ignore_ascii_case(hash)))
+            && self.chain_id.is_none_or(|chain_id| tx.chain_id == chain_id)
+            && self.status.as_ref().is_none_or(|status| &tx.status == status)
+            && self.from.is_none_or(|from| tx.timestamp >= from)
+            && self.to.is_none_or(|to| tx.timestamp < to)
+    }
+}
+
+/// Cold store for transactions removed from hot storage.
+///
+/// Records are appended as gzip members to one JSON-lines segment per
+/// archival day (`YYYY-MM-DD.jsonl.gz`), so segments stay append-only and
+/// can be shipped or backed up as plain files.
+pub struct TransactionArchive {
+    dir: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+impl TransactionArchive {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    pub fn append(&self, entries: &[ArchivedTransaction]) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        for entry in entries {
+            serde_json::to_writer(&mut encoder, entry)?;
+            encoder.write_all(b"\n")?;
+        }
+        let member = encoder.finish()?;
+
+        let _guard = self.write_lock.lock().unwrap();
+        let segment = self.dir.join(format!("{}.jsonl.gz", Utc::now().format("%Y-%m-%d")));
+        let mut file = OpenOptions::new().create(true).append(true).open(segment)?;
+        file.write_all(&member)?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    /// Scan segments newest first and return matching records
+    pub fn search(&self, query: &ArchiveQuery) -> Result<Vec<ArchivedTransaction>> {
+        let limit = query.limit.unwrap_or(100);
+        let mut results = Vec::new();
+
+        for segment in self.segments()?.into_iter().rev() {
+            let mut matches: Vec<ArchivedTransaction> = Self::read_segment(&segment)?
+                .into_iter()
+                .filter(|entry| query.matches(entry))
+                .collect();
+            matches.reverse();
+            for entry in matches {
+                results.push(entry);
+                if results.len() >= limit {
+                    return Ok(results);
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    pub fn segment_count(&self) -> Result<usize> {
+        Ok(self.segments()?.len())
+    }
+
+    fn segments(&self) -> Result<Vec<PathBuf>> {
+        let mut segments: Vec<PathBuf> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| Self::segment_date(path).is_some())
+            .collect();
+        segments.sort();
+        Ok(segments)
+    }
+
+    fn segment_date(path: &Path) -> Option<NaiveDate> {
+        let name = path.file_name()?.to_str()?;
+        let date = name.strip_suffix(".jsonl.gz")?;
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+    }
+
+    fn read_segment(path: &Path) -> Result<Vec<ArchivedTransaction>> {
+        let reader = BufReader::new(MultiGzDecoder::new(fs::File::open(path)?));
+        let mut entries = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => log::warn!("Skipping unreadable archive record in {}: {}", path.display(), e),
+            }
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,84 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::infrastructure::storage::file_storage::{Device, Metrics, Transaction};
+use crate::infrastructure::storage::json_backend::JsonFileBackend;
+use crate::infrastructure::storage::sled_backend::SledBackend;
+
+/// One page of transactions, newest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransactionPage {
+    pub transactions: Vec<Transaction>,
+    /// Pass back as `cursor` to fetch the next page; unset on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Transactions carrying every one of `tags`, optionally from one device
+#[derive(Debug, Clone, Default)]
+pub struct TransactionSearch {
+    pub tags: BTreeMap<String, String>,
+    pub device_id: Option<String>,
+}
+
+impl TransactionSearch {
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        self.device_id.as_ref().is_none_or(|device_id| transaction.device_id.as_ref() == Some(device_id))
+            && self.tags.iter().all(|(key, value)| transaction.tags.get(key) == Some(value))
+    }
+}
+
+/// Where `Storage` keeps transactions, devices and counters.
+///
+/// Listing is newest first. A cursor is the id of the last transaction of
+/// the previous page, so pages stay stable while new transactions arrive.
+pub trait StorageBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn insert_transaction(&self, transaction: &Transaction) -> Result<()>;
+    /// Apply `update` to a stored transaction and return the result
+    fn update_transaction(&self, id: &str, update: &mut dyn FnMut(&mut Transaction)) -> Result<Transaction>;
+    fn get_transaction(&self, id: &str) -> Result<Option<Transaction>>;
+    fn find_transaction_by_signed_tx(&self, signed_tx: &str) -> Result<Option<Transaction>>;
+    fn list_transactions(&self, limit: usize, cursor: Option<&str>) -> Result<TransactionPage>;
+    fn transactions_by_device(&self, device_id: &str, limit: usize, cursor: Option<&str>) -> Result<TransactionPage>;
+    /// Transactions matching `search`, found by scanning newest first
+    fn search_transactions(&self, search: &TransactionSearch, limit: usize, cursor: Option<&str>) -> Result<TransactionPage>;
+    fn remove_transactions(&self, ids: &[String]) -> Result<()>;
+    fn transaction_count(&self) -> Result<usize>;
+
+    fn load_devices(&self) -> Result<Vec<Device>>;
+    fn upsert_device(&self, device: &Device) -> Result<()>;
+    fn remove_devices(&self, ids: &[String]) -> Result<()>;
+
+    fn load_metrics(&self) -> Result<Option<Metrics>>;
+    fn save_metrics(&self, metrics: &Metrics) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackendKind {
+    /// `transactions.json`, `devices.json` and `metrics.json`, rewritten on
+    /// every change and capped at the newest 1000 transactions
+    Json,
+    /// Embedded sled database under `<data_dir>/db`
+    Sled,
+}
+
+impl StorageBackendKind {
+    /// `STORAGE_BACKEND`, `json` unless set
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("STORAGE_BACKEND").as_deref().map(str::trim) {
+            Err(_) | Ok("") | Ok("json") => Ok(Self::Json),
+            Ok("sled") => Ok(Self::Sled),
+            Ok(other) => Err(anyhow!("Unknown STORAGE_BACKEND '{}', expected json or sled", other)),
+        }
+    }
+
+    pub fn open(self, data_dir: &str) -> Result<Box<dyn StorageBackend>> {
+        Ok(match self {
+            Self::Json => Box::new(JsonFileBackend::open(data_dir)?),
+            Self::Sled => Box::new(SledBackend::open(Path::new(data_dir).join("db"), data_dir)?),
+        })
+    }
+}
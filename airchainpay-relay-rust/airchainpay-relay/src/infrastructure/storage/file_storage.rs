@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::Path;
 use std::sync::Mutex;
@@ -6,6 +7,14 @@ use anyhow::Result;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use crate::utils::database::DatabaseHealth;
+use crate::app::transaction_service::QueuedTransaction;
+use crate::infrastructure::storage::archive::{ArchiveQuery, ArchivedTransaction, TransactionArchive};
+use crate::infrastructure::storage::backend::{StorageBackend, StorageBackendKind, TransactionPage, TransactionSearch};
+use crate::infrastructure::storage::payments::{PaymentIndex, PaymentPage, PaymentQuery};
+use crate::infrastructure::blockchain::manager::PaymentEvent;
+use crate::domain::receipt::SignedReceipt;
+use crate::domain::auth::api_keys::ApiKeyRecord;
+use tokio::sync::broadcast;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Transaction {
@@ -17,6 +26,83 @@ pub struct Transaction {
     pub tx_hash: Option<String>,
     pub error_details: Option<String>,
     pub security: TransactionSecurity,
+    #[serde(default)]
+    pub status_history: Vec<StatusTransition>,
+    /// Set on records moved to the archive; hot storage only holds live records
+    #[serde(default)]
+    pub archived: bool,
+    /// Relay-signed proof of broadcast, set once the transaction is sent
+    #[serde(default)]
+    pub receipt: Option<SignedReceipt>,
+    /// Authenticated device that submitted the transaction, if known
+    #[serde(default)]
+    pub device_id: Option<String>,
+    /// Block the transaction was mined in, once the confirmation watcher saw it
+    #[serde(default)]
+    pub block_number: Option<u64>,
+    #[serde(default)]
+    pub gas_used: Option<u64>,
+    /// Merchant labels such as an order id, till or cashier, for reconciliation
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+/// A single status change, in the order it was recorded
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StatusTransition {
+    pub status: String,
+    pub tx_hash: Option<String>,
+    pub error_details: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// A status change as pushed to live subscribers
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StatusEvent {
+    pub transaction_id: String,
+    #[serde(flatten)]
+    pub transition: StatusTransition,
+}
+
+/// Status events buffered per subscriber before it starts lagging
+const STATUS_EVENT_CAPACITY: usize = 1024;
+
+/// Statuses after which a transaction will not change again
+/// Most tags one transaction may carry
+pub const MAX_TAGS: usize = 16;
+const MAX_TAG_KEY_LEN: usize = 64;
+const MAX_TAG_VALUE_LEN: usize = 256;
+
+/// Check submitted tags: keys are short identifiers (letters, digits, `_`,
+/// `-`, `.`), values are free text without control characters
+pub fn validate_tags(tags: &BTreeMap<String, String>) -> Result<()> {
+    if tags.len() > MAX_TAGS {
+        return Err(anyhow::anyhow!("At most {} tags are allowed, got {}", MAX_TAGS, tags.len()));
+    }
+    for (key, value) in tags {
+        let valid_key = !key.is_empty()
+            && key.len() <= MAX_TAG_KEY_LEN
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if !valid_key {
+            return Err(anyhow::anyhow!("Invalid tag name {:?}", key));
+        }
+        if value.len() > MAX_TAG_VALUE_LEN || value.chars().any(char::is_control) {
+            return Err(anyhow::anyhow!("Invalid value for tag {}", key));
+        }
+    }
+    Ok(())
+}
+
+pub const TERMINAL_STATUSES: [&str; 6] = ["completed", "failed", "shed", "queue_failed", "forwarded", "dropped"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Device {
+    pub id: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// X25519 static key pinned on the device's first Noise session (hex)
+    #[serde(default)]
+    pub noise_static_key: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -35,115 +121,203 @@ pub struct Metrics {
     pub last_updated: DateTime<Utc>,
 }
 
+/// Submission counts of one quota subject (a device or an API key)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct QuotaUsage {
+    /// UTC day the daily count belongs to, `YYYY-MM-DD`
+    pub day: String,
+    pub daily_count: u64,
+    /// UTC month the monthly count belongs to, `YYYY-MM`
+    pub month: String,
+    pub monthly_count: u64,
+}
+
+/// A quota subject's counts after a change, appended to `quotas.log`
+/// between snapshots
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuotaLogEntry {
+    pub subject: String,
+    pub usage: QuotaUsage,
+}
+
+/// A raw transaction the relay has accepted, remembered to refuse replays
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SeenTransaction {
+    pub chain_id: u64,
+    /// Id of the stored transaction it was first accepted as, if any
+    pub transaction_id: Option<String>,
+    /// Path it first arrived through, e.g. `send_tx` or `queue`
+    pub source: String,
+    pub first_seen: DateTime<Utc>,
+}
+
+/// One change to the replay hashes, appended to `replay_hashes.log`
+/// between snapshots
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayLogEntry {
+    Admit { tx_hash: String, seen: SeenTransaction },
+    Release { tx_hash: String },
+}
+
 pub struct Storage {
     data_dir: String,
-    transactions: Mutex<Vec<Transaction>>,
+    backend: Box<dyn StorageBackend>,
     metrics: Mutex<Metrics>,
+    devices: Mutex<Vec<Device>>,
+    queue_lock: Mutex<()>,
+    quota_lock: Mutex<()>,
+    api_key_lock: Mutex<()>,
+    replay_lock: Mutex<()>,
+    archive: TransactionArchive,
+    payments: PaymentIndex,
+    status_events: broadcast::Sender<StatusEvent>,
 }
 
 impl Storage {
+    /// Open storage under `data/` with the backend chosen by `STORAGE_BACKEND`
     pub fn new() -> Result<Self> {
         let data_dir = "data".to_string();
         fs::create_dir_all(&data_dir)?;
-        
-        let storage = Storage {
+        let backend = StorageBackendKind::from_env()?.open(&data_dir)?;
+        Self::with_backend(data_dir, backend)
+    }
+
+    pub fn with_backend(data_dir: String, backend: Box<dyn StorageBackend>) -> Result<Self> {
+        let archive = TransactionArchive::new(format!("{data_dir}/archive/transactions"))?;
+        let payments = PaymentIndex::open(format!("{data_dir}/payments"))?;
+        let metrics = backend.load_metrics()?.unwrap_or_else(|| Metrics {
+            transactions_received: 0,
+            transactions_processed: 0,
+            transactions_failed: 0,
+            auth_failures: 0,
+            last_updated: Utc::now(),
+        });
+        let devices = backend.load_devices()?;
+        log::info!("Using {} storage backend", backend.name());
+        Ok(Storage {
             data_dir,
-            transactions: Mutex::new(Vec::new()),
-            metrics: Mutex::new(Metrics {
-                transactions_received: 0,
-                transactions_processed: 0,
-                transactions_failed: 0,
-                auth_failures: 0,
-                last_updated: Utc::now(),
-            }),
-        };
-        
-        storage.load_data()?;
-        Ok(storage)
+            backend,
+            metrics: Mutex::new(metrics),
+            devices: Mutex::new(devices),
+            queue_lock: Mutex::new(()),
+            quota_lock: Mutex::new(()),
+            api_key_lock: Mutex::new(()),
+            replay_lock: Mutex::new(()),
+            archive,
+            payments,
+            status_events: broadcast::channel(STATUS_EVENT_CAPACITY).0,
+        })
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
     }
     
-    fn load_data(&self) -> Result<()> {
-        // Load transactions
-        let tx_file = format!("{}/transactions.json", self.data_dir);
-        if Path::new(&tx_file).exists() {
-            let data = fs::read_to_string(&tx_file)?;
-            let transactions: Vec<Transaction> = serde_json::from_str(&data)?;
-            *self.transactions.lock().unwrap() = transactions;
-        }
-        
-        // Load metrics
-        let metrics_file = format!("{}/metrics.json", self.data_dir);
-        if Path::new(&metrics_file).exists() {
-            let data = fs::read_to_string(&metrics_file)?;
-            let metrics: Metrics = serde_json::from_str(&data)?;
-            *self.metrics.lock().unwrap() = metrics;
-        }
-        
-        Ok(())
+    pub fn save_transaction(&self, transaction: Transaction) -> Result<()> {
+        self.backend.insert_transaction(&transaction)
     }
     
-    pub fn save_data(&self) -> Result<()> {
-        // Save transactions
-        let tx_file = format!("{}/transactions.json", self.data_dir);
-        let transactions = self.transactions.lock().unwrap();
-        let data = serde_json::to_string_pretty(&*transactions)?;
-        fs::write(&tx_file, data)?;
-        
-        // Save metrics
-        let metrics_file = format!("{}/metrics.json", self.data_dir);
-        let mut metrics = self.metrics.lock().unwrap();
-        metrics.last_updated = Utc::now();
-        let data = serde_json::to_string_pretty(&*metrics)?;
-        fs::write(&metrics_file, data)?;
-        
-        Ok(())
+    /// Newest transactions first
+    pub fn get_transactions(&self, limit: usize) -> Vec<Transaction> {
+        self.backend.list_transactions(limit, None)
+            .map(|page| page.transactions)
+            .unwrap_or_else(|e| {
+                log::error!("Failed to list transactions: {}", e);
+                Vec::new()
+            })
+    }
+
+    /// A page of transactions, newest first, starting after `cursor`
+    pub fn get_transactions_page(&self, limit: usize, cursor: Option<&str>) -> Result<TransactionPage> {
+        self.backend.list_transactions(limit, cursor)
+    }
+
+    /// A page of one device's transactions, newest first, starting after `cursor`
+    pub fn get_transactions_by_device(&self, device_id: &str, limit: usize, cursor: Option<&str>) -> Result<TransactionPage> {
+        self.backend.transactions_by_device(device_id, limit, cursor)
+    }
+
+    /// A page of transactions matching `search`, newest first, starting after `cursor`
+    pub fn search_transactions(&self, search: &TransactionSearch, limit: usize, cursor: Option<&str>) -> Result<TransactionPage> {
+        self.backend.search_transactions(search, limit, cursor)
+    }
+
+    pub fn transaction_count(&self) -> usize {
+        self.backend.transaction_count().unwrap_or(0)
     }
     
-    pub fn save_transaction(&self, transaction: Transaction) -> Result<()> {
-        let mut transactions = self.transactions.lock().unwrap();
-        transactions.push(transaction);
-        
-        // Keep only last 1000 transactions
-        if transactions.len() > 1000 {
-            let len = transactions.len();
-            transactions.drain(0..len - 1000);
-        }
-        
-        self.save_data()?;
-        Ok(())
+    pub fn get_transaction(&self, id: &str) -> Option<Transaction> {
+        self.backend.get_transaction(id).unwrap_or_else(|e| {
+            log::error!("Failed to read transaction {}: {}", id, e);
+            None
+        })
     }
     
-    pub fn get_transactions(&self, limit: usize) -> Vec<Transaction> {
-        let transactions = self.transactions.lock().unwrap();
-        transactions.iter().rev().take(limit).cloned().collect()
+    /// Find a stored transaction carrying the same signed payload
+    pub fn find_transaction_by_signed_tx(&self, signed_tx: &str) -> Option<Transaction> {
+        self.backend.find_transaction_by_signed_tx(signed_tx).unwrap_or_else(|e| {
+            log::error!("Failed to look up transaction by payload: {}", e);
+            None
+        })
     }
     
     pub fn update_transaction_status(&self, id: &str, status: &str, tx_hash: Option<String>) -> Result<()> {
-        let mut transactions = self.transactions.lock().unwrap();
-        if let Some(tx) = transactions.iter_mut().find(|t| t.id == id) {
+        let tx = self.backend.update_transaction(id, &mut |tx| {
             tx.status = status.to_string();
-            tx.tx_hash = tx_hash;
-            self.save_data()?;
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Transaction not found: {}", id))
-        }
+            tx.tx_hash = tx_hash.clone();
+            tx.record_transition();
+        })?;
+        self.publish_status(&tx);
+        Ok(())
     }
     
     pub fn update_transaction_status_with_error(&self, id: &str, status: &str, tx_hash: Option<String>, error_details: Option<String>) -> Result<()> {
-        let mut transactions = self.transactions.lock().unwrap();
-        if let Some(tx) = transactions.iter_mut().find(|t| t.id == id) {
+        let tx = self.backend.update_transaction(id, &mut |tx| {
             tx.status = status.to_string();
-            tx.tx_hash = tx_hash;
-            tx.error_details = error_details;
-            self.save_data()?;
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Transaction not found: {}", id))
+            tx.tx_hash = tx_hash.clone();
+            tx.error_details = error_details.clone();
+            tx.record_transition();
+        })?;
+        self.publish_status(&tx);
+        Ok(())
+    }
+
+    /// Record the outcome of a mined transaction: its final status and the
+    /// block and gas from its on-chain receipt
+    pub fn record_confirmation(&self, id: &str, status: &str, block_number: u64, gas_used: Option<u64>, error_details: Option<String>) -> Result<()> {
+        let tx = self.backend.update_transaction(id, &mut |tx| {
+            tx.status = status.to_string();
+            tx.block_number = Some(block_number);
+            tx.gas_used = gas_used;
+            tx.error_details = error_details.clone();
+            tx.record_transition();
+        })?;
+        self.publish_status(&tx);
+        Ok(())
+    }
+
+    /// Live feed of status changes, for pushing to connected wallets
+    pub fn subscribe_status(&self) -> broadcast::Receiver<StatusEvent> {
+        self.status_events.subscribe()
+    }
+
+    fn publish_status(&self, tx: &Transaction) {
+        if let Some(transition) = tx.status_history.last() {
+            // No receivers just means nobody is watching
+            let _ = self.status_events.send(StatusEvent {
+                transaction_id: tx.id.clone(),
+                transition: transition.clone(),
+            });
         }
     }
 
     
+    pub fn set_transaction_receipt(&self, id: &str, receipt: SignedReceipt) -> Result<()> {
+        self.backend.update_transaction(id, &mut |tx| tx.receipt = Some(receipt.clone()))?;
+        Ok(())
+    }
+    
     pub fn update_metrics(&self, field: &str, value: u64) -> Result<()> {
         let mut metrics = self.metrics.lock().unwrap();
         match field {
@@ -153,14 +327,170 @@ impl Storage {
             "auth_failures" => metrics.auth_failures += value,
             _ => return Err(anyhow::anyhow!("Unknown metric field: {}", field)),
         }
-        self.save_data()?;
-        Ok(())
+        metrics.last_updated = Utc::now();
+        self.backend.save_metrics(&metrics)
     }
     
     pub fn get_metrics(&self) -> Metrics {
         self.metrics.lock().unwrap().clone()
     }
     
+    /// Persist the processor queue (pending and in-flight entries) to `queue.json`.
+    /// Written to a temp file and renamed so a crash never leaves a torn file.
+    pub fn save_queue(&self, entries: &[QueuedTransaction]) -> Result<()> {
+        let _guard = self.queue_lock.lock().unwrap();
+        let queue_file = format!("{}/queue.json", self.data_dir);
+        let tmp_file = format!("{}/queue.json.tmp", self.data_dir);
+        let data = serde_json::to_string_pretty(entries)?;
+        fs::write(&tmp_file, data)?;
+        fs::rename(&tmp_file, &queue_file)?;
+        Ok(())
+    }
+    
+    /// Load persisted queue entries, if any
+    pub fn load_queue(&self) -> Result<Vec<QueuedTransaction>> {
+        let _guard = self.queue_lock.lock().unwrap();
+        let queue_file = format!("{}/queue.json", self.data_dir);
+        if !Path::new(&queue_file).exists() {
+            return Ok(Vec::new());
+        }
+        let data = fs::read_to_string(&queue_file)?;
+        Ok(serde_json::from_str(&data)?)
+    }
+    
+    /// Snapshot quota counters to `quotas.json`, keyed by quota subject,
+    /// and empty the log the snapshot now covers
+    pub fn save_quota_usage(&self, usage: &HashMap<String, QuotaUsage>) -> Result<()> {
+        let _guard = self.quota_lock.lock().unwrap();
+        let quota_file = format!("{}/quotas.json", self.data_dir);
+        let tmp_file = format!("{}/quotas.json.tmp", self.data_dir);
+        let data = serde_json::to_string_pretty(usage)?;
+        fs::write(&tmp_file, data)?;
+        fs::rename(&tmp_file, &quota_file)?;
+        fs::write(format!("{}/quotas.log", self.data_dir), "")?;
+        Ok(())
+    }
+
+    /// Append changed counters to `quotas.log`, without rewriting the
+    /// snapshot
+    pub fn append_quota_log(&self, entries: &[QuotaLogEntry]) -> Result<()> {
+        use std::io::Write;
+        let _guard = self.quota_lock.lock().unwrap();
+        let mut lines = Vec::new();
+        for entry in entries {
+            lines.extend(serde_json::to_vec(entry)?);
+            lines.push(b'\n');
+        }
+        let mut log = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(format!("{}/quotas.log", self.data_dir))?;
+        log.write_all(&lines)?;
+        Ok(())
+    }
+
+    /// Load persisted quota counters, if any: the snapshot with the log
+    /// replayed over it. A torn last line is skipped.
+    pub fn load_quota_usage(&self) -> Result<HashMap<String, QuotaUsage>> {
+        let _guard = self.quota_lock.lock().unwrap();
+        let quota_file = format!("{}/quotas.json", self.data_dir);
+        let mut usage: HashMap<String, QuotaUsage> = if Path::new(&quota_file).exists() {
+            serde_json::from_str(&fs::read_to_string(&quota_file)?)?
+        } else {
+            HashMap::new()
+        };
+        let log_file = format!("{}/quotas.log", self.data_dir);
+        if Path::new(&log_file).exists() {
+            for line in fs::read_to_string(&log_file)?.lines().filter(|l| !l.trim().is_empty()) {
+                match serde_json::from_str::<QuotaLogEntry>(line) {
+                    Ok(entry) => {
+                        usage.insert(entry.subject, entry.usage);
+                    }
+                    Err(e) => log::warn!("Skipping unreadable quota log entry: {}", e),
+                }
+            }
+        }
+        Ok(usage)
+    }
+
+    /// Snapshot accepted transaction hashes to `replay_hashes.json` and
+    /// empty the log the snapshot now covers
+    pub fn save_replay_hashes(&self, seen: &HashMap<String, SeenTransaction>) -> Result<()> {
+        let _guard = self.replay_lock.lock().unwrap();
+        let replay_file = format!("{}/replay_hashes.json", self.data_dir);
+        let tmp_file = format!("{}/replay_hashes.json.tmp", self.data_dir);
+        let data = serde_json::to_string(seen)?;
+        fs::write(&tmp_file, data)?;
+        fs::rename(&tmp_file, &replay_file)?;
+        fs::write(format!("{}/replay_hashes.log", self.data_dir), "")?;
+        Ok(())
+    }
+
+    /// Append one change to `replay_hashes.log`, without rewriting the
+    /// snapshot
+    pub fn append_replay_log(&self, entry: &ReplayLogEntry) -> Result<()> {
+        use std::io::Write;
+        let _guard = self.replay_lock.lock().unwrap();
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        let mut log = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(format!("{}/replay_hashes.log", self.data_dir))?;
+        log.write_all(&line)?;
+        Ok(())
+    }
+
+    /// Load persisted transaction hashes, if any: the snapshot with the
+    /// log replayed over it. A torn last line, from a crash mid-append, is
+    /// skipped.
+    pub fn load_replay_hashes(&self) -> Result<HashMap<String, SeenTransaction>> {
+        let _guard = self.replay_lock.lock().unwrap();
+        let replay_file = format!("{}/replay_hashes.json", self.data_dir);
+        let mut seen: HashMap<String, SeenTransaction> = if Path::new(&replay_file).exists() {
+            serde_json::from_str(&fs::read_to_string(&replay_file)?)?
+        } else {
+            HashMap::new()
+        };
+        let log_file = format!("{}/replay_hashes.log", self.data_dir);
+        if Path::new(&log_file).exists() {
+            for line in fs::read_to_string(&log_file)?.lines().filter(|l| !l.trim().is_empty()) {
+                match serde_json::from_str::<ReplayLogEntry>(line) {
+                    Ok(ReplayLogEntry::Admit { tx_hash, seen: entry }) => {
+                        seen.insert(tx_hash, entry);
+                    }
+                    Ok(ReplayLogEntry::Release { tx_hash }) => {
+                        seen.remove(&tx_hash);
+                    }
+                    Err(e) => log::warn!("Skipping unreadable replay log entry: {}", e),
+                }
+            }
+        }
+        Ok(seen)
+    }
+
+    /// Persist API key records (hashes only) to `api_keys.json`
+    pub fn save_api_keys(&self, keys: &[ApiKeyRecord]) -> Result<()> {
+        let _guard = self.api_key_lock.lock().unwrap();
+        let key_file = format!("{}/api_keys.json", self.data_dir);
+        let tmp_file = format!("{}/api_keys.json.tmp", self.data_dir);
+        let data = serde_json::to_string_pretty(keys)?;
+        fs::write(&tmp_file, data)?;
+        fs::rename(&tmp_file, &key_file)?;
+        Ok(())
+    }
+
+    /// Load persisted API key records, if any
+    pub fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        let _guard = self.api_key_lock.lock().unwrap();
+        let key_file = format!("{}/api_keys.json", self.data_dir);
+        if !Path::new(&key_file).exists() {
+            return Ok(Vec::new());
+        }
+        let data = fs::read_to_string(&key_file)?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
     // Add missing methods for API compatibility
     pub async fn check_health(&self) -> DatabaseHealth {
         // Basic health check - verify data directory exists and is writable
@@ -168,7 +498,6 @@ impl Storage {
         let is_healthy = fs::write(&test_file, "health_check").is_ok() && fs::remove_file(&test_file).is_ok();
         
         let _metrics = self.get_metrics();
-        let transactions = self.transactions.lock().unwrap();
         
         DatabaseHealth {
             is_healthy,
@@ -177,8 +506,8 @@ impl Storage {
             backup_size_bytes: 0,
             error_count: if is_healthy { 0 } else { 1 },
             slow_queries: 0,
-            total_transactions: transactions.len() as u32,
-            total_devices: 0,
+            total_transactions: self.transaction_count() as u32,
+            total_devices: self.devices.lock().unwrap().len() as u32,
             data_integrity_ok: is_healthy,
             last_maintenance: None,
             disk_usage_percent: 0.0,
@@ -189,16 +518,132 @@ impl Storage {
 
     // Get registered mobile wallet instances
     pub fn get_registered_wallets(&self) -> Vec<String> {
-        // Return list of registered mobile wallet app instances
-        // This tracks mobile apps that have authenticated with the relay
-        // For now, return empty list - would be populated when mobile apps register
-        Vec::new()
+        // Mobile wallet app instances that have authenticated with the relay
+        self.devices.lock().unwrap().iter().map(|d| d.id.clone()).collect()
+    }
+    
+    pub fn get_devices(&self) -> Vec<Device> {
+        self.devices.lock().unwrap().clone()
+    }
+    
+    /// Record that an authenticated device was seen
+    pub fn record_device_seen(&self, device_id: &str) -> Result<()> {
+        let now = Utc::now();
+        let mut devices = self.devices.lock().unwrap();
+        let device = match devices.iter_mut().find(|d| d.id == device_id) {
+            Some(device) => {
+                device.last_seen = now;
+                device.clone()
+            }
+            None => {
+                let device = Device {
+                    id: device_id.to_string(),
+                    first_seen: now,
+                    last_seen: now,
+                    noise_static_key: None,
+                };
+                devices.push(device.clone());
+                device
+            }
+        };
+        self.backend.upsert_device(&device)
+    }
+    
+    /// Pin a device's Noise static key on first use; later sessions must present the same key
+    pub fn pin_device_noise_key(&self, device_id: &str, static_key: &str) -> Result<()> {
+        let mut devices = self.devices.lock().unwrap();
+        let device = devices.iter_mut().find(|d| d.id == device_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown device {}", device_id))?;
+        match &device.noise_static_key {
+            Some(pinned) if pinned == static_key => Ok(()),
+            Some(_) => Err(anyhow::anyhow!("Device {} presented a different Noise static key", device_id)),
+            None => {
+                device.noise_static_key = Some(static_key.to_string());
+                self.backend.upsert_device(device)
+            }
+        }
+    }
+    
+    /// Remove devices last seen before `cutoff`, returning them.
+    /// With `dry_run` nothing is removed.
+    pub fn prune_devices(&self, cutoff: DateTime<Utc>, dry_run: bool) -> Result<Vec<Device>> {
+        let mut devices = self.devices.lock().unwrap();
+        let stale: Vec<Device> = devices.iter().filter(|d| d.last_seen < cutoff).cloned().collect();
+        if !dry_run && !stale.is_empty() {
+            devices.retain(|d| d.last_seen >= cutoff);
+            let ids: Vec<String> = stale.iter().map(|d| d.id.clone()).collect();
+            self.backend.remove_devices(&ids)?;
+        }
+        Ok(stale)
+    }
+    
+    /// Move finished transactions created before `cutoff` to the archive, returning them.
+    /// Transactions still in flight are kept regardless of age.
+    pub fn prune_transactions(&self, cutoff: DateTime<Utc>, dry_run: bool) -> Result<Vec<Transaction>> {
+        let expired: Vec<Transaction> = self.get_transactions(usize::MAX).into_iter()
+            .filter(|t| t.timestamp < cutoff && t.is_terminal())
+            .collect();
+        if dry_run || expired.is_empty() {
+            return Ok(expired);
+        }
+        let ids: Vec<String> = expired.iter().map(|t| t.id.clone()).collect();
+        self.archive_transactions(&ids, "retention")?;
+        Ok(expired)
+    }
+    
+    /// Soft delete: move a transaction out of hot storage into the archive
+    pub fn archive_transaction(&self, id: &str, reason: &str) -> Result<ArchivedTransaction> {
+        self.archive_transactions(&[id.to_string()], reason)?
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("Transaction not found: {}", id))
+    }
+    
+    fn archive_transactions(&self, ids: &[String], reason: &str) -> Result<Vec<ArchivedTransaction>> {
+        let now = Utc::now();
+        let archived: Vec<ArchivedTransaction> = ids.iter()
+            .filter_map(|id| self.get_transaction(id))
+            .map(|t| ArchivedTransaction {
+                transaction: Transaction { archived: true, ..t },
+                archived_at: now,
+                reason: reason.to_string(),
+            })
+            .collect();
+        if archived.is_empty() {
+            return Ok(archived);
+        }
+        // Written to the archive before leaving hot storage so nothing is lost on failure
+        self.archive.append(&archived)?;
+        let archived_ids: Vec<String> = archived.iter().map(|a| a.transaction.id.clone()).collect();
+        self.backend.remove_transactions(&archived_ids)?;
+        Ok(archived)
+    }
+    
+    pub fn search_archived_transactions(&self, query: &ArchiveQuery) -> Result<Vec<ArchivedTransaction>> {
+        self.archive.search(query)
+    }
+    
+    /// Store contract payments found up to `through_block` on a chain
+    pub fn record_indexed_payments(&self, chain_id: u64, events: Vec<PaymentEvent>, through_block: u64) -> Result<usize> {
+        self.payments.record(chain_id, events, through_block)
+    }
+    
+    /// Highest block whose contract payments are indexed, if the chain is
+    pub fn payments_indexed_through(&self, chain_id: u64) -> Option<u64> {
+        self.payments.indexed_through(chain_id)
+    }
+    
+    pub fn query_indexed_payments(&self, query: &PaymentQuery) -> PaymentPage {
+        self.payments.query(query)
+    }
+    
+    pub fn data_dir(&self) -> &str {
+        &self.data_dir
     }
 }
 
 impl Transaction {
     pub fn new(signed_tx: String, chain_id: u64) -> Self {
-        Transaction {
+        let mut transaction = Transaction {
             id: Uuid::new_v4().to_string(),
             signed_tx,
             chain_id,
@@ -211,6 +656,53 @@ impl Transaction {
                 created_at: Utc::now(),
                 server_id: "default".to_string(),
             },
+            status_history: Vec::new(),
+            archived: false,
+            receipt: None,
+            device_id: None,
+            block_number: None,
+            gas_used: None,
+            tags: BTreeMap::new(),
+        };
+        transaction.record_transition();
+        transaction
+    }
+
+    pub fn with_device(mut self, device_id: impl Into<String>) -> Self {
+        self.device_id = Some(device_id.into());
+        self
+    }
+
+    pub fn with_tags(mut self, tags: BTreeMap<String, String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        TERMINAL_STATUSES.contains(&self.status.as_str())
+    }
+
+    /// Append the current status to the history
+    fn record_transition(&mut self) {
+        self.status_history.push(StatusTransition {
+            status: self.status.clone(),
+            tx_hash: self.tx_hash.clone(),
+            error_details: self.error_details.clone(),
+            at: Utc::now(),
+        });
+    }
+
+    /// Status history, falling back to the current status for records saved
+    /// before history was tracked
+    pub fn transitions(&self) -> Vec<StatusTransition> {
+        if !self.status_history.is_empty() {
+            return self.status_history.clone();
         }
+        vec![StatusTransition {
+            status: self.status.clone(),
+            tx_hash: self.tx_hash.clone(),
+            error_details: self.error_details.clone(),
+            at: self.timestamp,
+        }]
     }
 }
\ No newline at end of file
@@ -0,0 +1,174 @@
+use anyhow::{Result, anyhow};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use crate::infrastructure::storage::backend::{StorageBackend, TransactionPage, TransactionSearch};
+use crate::infrastructure::storage::file_storage::{Device, Metrics, Transaction};
+
+/// Transactions kept by the JSON backend; older ones are dropped
+const MAX_TRANSACTIONS: usize = 1000;
+
+/// The original file layout: everything held in memory and each file
+/// rewritten whole on change
+pub struct JsonFileBackend {
+    data_dir: String,
+    transactions: Mutex<Vec<Transaction>>,
+    devices: Mutex<Vec<Device>>,
+}
+
+impl JsonFileBackend {
+    pub fn open(data_dir: &str) -> Result<Self> {
+        let read = |name: &str| -> Result<Option<String>> {
+            let file = format!("{data_dir}/{name}");
+            if !Path::new(&file).exists() {
+                return Ok(None);
+            }
+            Ok(Some(fs::read_to_string(&file)?))
+        };
+        let transactions = match read("transactions.json")? {
+            Some(data) => serde_json::from_str(&data)?,
+            None => Vec::new(),
+        };
+        let devices = match read("devices.json")? {
+            Some(data) => serde_json::from_str(&data)?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            data_dir: data_dir.to_string(),
+            transactions: Mutex::new(transactions),
+            devices: Mutex::new(devices),
+        })
+    }
+
+    fn save_transactions(&self, transactions: &[Transaction]) -> Result<()> {
+        let tx_file = format!("{}/transactions.json", self.data_dir);
+        fs::write(&tx_file, serde_json::to_string_pretty(transactions)?)?;
+        Ok(())
+    }
+
+    fn save_devices(&self, devices: &[Device]) -> Result<()> {
+        let devices_file = format!("{}/devices.json", self.data_dir);
+        fs::write(&devices_file, serde_json::to_string_pretty(devices)?)?;
+        Ok(())
+    }
+
+    fn page<'a>(
+        transactions: impl Iterator<Item = &'a Transaction>,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<TransactionPage> {
+        let mut newest_first = transactions.peekable();
+        if let Some(cursor) = cursor {
+            // Skip up to and including the cursor
+            if !newest_first.by_ref().any(|t| t.id == cursor) {
+                return Err(anyhow!("Unknown cursor {}", cursor));
+            }
+        }
+        let transactions: Vec<Transaction> = newest_first.by_ref().take(limit).cloned().collect();
+        let next_cursor = match (newest_first.peek(), transactions.last()) {
+            (Some(_), Some(last)) => Some(last.id.clone()),
+            _ => None,
+        };
+        Ok(TransactionPage { transactions, next_cursor })
+    }
+}
+
+impl StorageBackend for JsonFileBackend {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn insert_transaction(&self, transaction: &Transaction) -> Result<()> {
+        let mut transactions = self.transactions.lock().unwrap();
+        transactions.push(transaction.clone());
+        if transactions.len() > MAX_TRANSACTIONS {
+            let len = transactions.len();
+            transactions.drain(0..len - MAX_TRANSACTIONS);
+        }
+        self.save_transactions(&transactions)
+    }
+
+    fn update_transaction(&self, id: &str, update: &mut dyn FnMut(&mut Transaction)) -> Result<Transaction> {
+        let mut transactions = self.transactions.lock().unwrap();
+        let tx = transactions.iter_mut().find(|t| t.id == id)
+            .ok_or_else(|| anyhow!("Transaction not found: {}", id))?;
+        update(tx);
+        let updated = tx.clone();
+        self.save_transactions(&transactions)?;
+        Ok(updated)
+    }
+
+    fn get_transaction(&self, id: &str) -> Result<Option<Transaction>> {
+        Ok(self.transactions.lock().unwrap().iter().find(|t| t.id == id).cloned())
+    }
+
+    fn find_transaction_by_signed_tx(&self, signed_tx: &str) -> Result<Option<Transaction>> {
+        Ok(self.transactions.lock().unwrap().iter().find(|t| t.signed_tx == signed_tx).cloned())
+    }
+
+    fn list_transactions(&self, limit: usize, cursor: Option<&str>) -> Result<TransactionPage> {
+        let transactions = self.transactions.lock().unwrap();
+        Self::page(transactions.iter().rev(), limit, cursor)
+    }
+
+    fn transactions_by_device(&self, device_id: &str, limit: usize, cursor: Option<&str>) -> Result<TransactionPage> {
+        let transactions = self.transactions.lock().unwrap();
+        let from_device = transactions.iter().rev().filter(|t| t.device_id.as_deref() == Some(device_id));
+        Self::page(from_device, limit, cursor)
+    }
+
+    fn search_transactions(&self, search: &TransactionSearch, limit: usize, cursor: Option<&str>) -> Result<TransactionPage> {
+        let transactions = self.transactions.lock().unwrap();
+        // The cursor itself need not match, so filter after skipping past it
+        let mut newest_first = transactions.iter().rev();
+        if let Some(cursor) = cursor {
+            if !newest_first.by_ref().any(|t| t.id == cursor) {
+                return Err(anyhow!("Unknown cursor {}", cursor));
+            }
+        }
+        Self::page(newest_first.filter(|t| search.matches(t)), limit, None)
+    }
+
+    fn remove_transactions(&self, ids: &[String]) -> Result<()> {
+        let mut transactions = self.transactions.lock().unwrap();
+        transactions.retain(|t| !ids.contains(&t.id));
+        self.save_transactions(&transactions)
+    }
+
+    fn transaction_count(&self) -> Result<usize> {
+        Ok(self.transactions.lock().unwrap().len())
+    }
+
+    fn load_devices(&self) -> Result<Vec<Device>> {
+        Ok(self.devices.lock().unwrap().clone())
+    }
+
+    fn upsert_device(&self, device: &Device) -> Result<()> {
+        let mut devices = self.devices.lock().unwrap();
+        match devices.iter_mut().find(|d| d.id == device.id) {
+            Some(existing) => *existing = device.clone(),
+            None => devices.push(device.clone()),
+        }
+        self.save_devices(&devices)
+    }
+
+    fn remove_devices(&self, ids: &[String]) -> Result<()> {
+        let mut devices = self.devices.lock().unwrap();
+        devices.retain(|d| !ids.contains(&d.id));
+        self.save_devices(&devices)
+    }
+
+    fn load_metrics(&self) -> Result<Option<Metrics>> {
+        let metrics_file = format!("{}/metrics.json", self.data_dir);
+        if !Path::new(&metrics_file).exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(&metrics_file)?)?))
+    }
+
+    fn save_metrics(&self, metrics: &Metrics) -> Result<()> {
+        let metrics_file = format!("{}/metrics.json", self.data_dir);
+        fs::write(&metrics_file, serde_json::to_string_pretty(metrics)?)?;
+        Ok(())
+    }
+}
@@ -1,2 +1,7 @@
 pub mod file_storage;
-// pub mod db_storage; 
\ No newline at end of file
+pub mod archive;
+pub mod backend;
+pub mod json_backend;
+pub mod payments;
+pub mod sled_backend;
+// pub mod db_storage; 
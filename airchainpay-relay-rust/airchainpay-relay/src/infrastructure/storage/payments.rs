@@ -0,0 +1,214 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use crate::infrastructure::blockchain::manager::PaymentEvent;
+
+/// A contract `Payment` event as indexed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedPayment {
+    pub chain_id: u64,
+    #[serde(flatten)]
+    pub event: PaymentEvent,
+    pub indexed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PaymentQuery {
+    pub chain_id: u64,
+    pub payer: Option<Address>,
+    pub merchant: Option<Address>,
+    pub reference: Option<String>,
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+impl PaymentQuery {
+    fn matches(&self, payment: &IndexedPayment) -> bool {
+        let event = &payment.event;
+        self.payer.is_none_or(|payer| event.from == payer)
+            && self.merchant.is_none_or(|merchant| event.to == merchant)
+            && self.reference.as_ref().is_none_or(|reference| &event.payment_reference == reference)
+            && self.from_block.is_none_or(|from| event.block_number >= from)
+            && self.to_block.is_none_or(|to| event.block_number <= to)
+    }
+}
+
+/// One page of indexed payments, newest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PaymentPage {
+    pub payments: Vec<IndexedPayment>,
+    /// Matches across all pages
+    pub total: usize,
+    /// Highest block the index covers on the chain
+    pub indexed_through: Option<u64>,
+}
+
+#[derive(Default)]
+struct ChainPayments {
+    /// By (block, log index), so pages come out in chain order
+    payments: BTreeMap<(u64, u64), IndexedPayment>,
+    indexed_through: Option<u64>,
+}
+
+/// Payments read from the AirChainPay contracts' `Payment` events.
+///
+/// Each chain's payments are appended to `chain_<id>.jsonl` under
+/// `<data_dir>/payments` and the block they are indexed through is kept in
+/// `cursors.json`, so the indexer resumes where it stopped after a restart.
+/// Everything is loaded into memory at startup for filtering.
+pub struct PaymentIndex {
+    dir: PathBuf,
+    chains: Mutex<HashMap<u64, ChainPayments>>,
+}
+
+impl PaymentIndex {
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let cursors: HashMap<u64, u64> = match fs::read_to_string(dir.join("cursors.json")) {
+            Ok(data) => serde_json::from_str(&data)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut chains: HashMap<u64, ChainPayments> = HashMap::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let Some(chain_id) = path.file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_prefix("chain_")?.strip_suffix(".jsonl")?.parse::<u64>().ok())
+            else {
+                continue;
+            };
+            let chain = chains.entry(chain_id).or_default();
+            for line in fs::read_to_string(&path)?.lines().filter(|l| !l.trim().is_empty()) {
+                match serde_json::from_str::<IndexedPayment>(line) {
+                    Ok(payment) => {
+                        chain.payments.insert((payment.event.block_number, payment.event.log_index), payment);
+                    }
+                    Err(e) => log::warn!("Skipping unreadable payment record in {}: {}", path.display(), e),
+                }
+            }
+        }
+        for (chain_id, through) in cursors {
+            chains.entry(chain_id).or_default().indexed_through = Some(through);
+        }
+
+        Ok(Self { dir, chains: Mutex::new(chains) })
+    }
+
+    /// Add the payments found up to `through_block` and move the chain's
+    /// cursor there. Payments already indexed are skipped.
+    pub fn record(&self, chain_id: u64, events: Vec<PaymentEvent>, through_block: u64) -> Result<usize> {
+        let mut chains = self.chains.lock().unwrap();
+        let chain = chains.entry(chain_id).or_default();
+        let now = Utc::now();
+        let mut seen = HashSet::new();
+        let new: Vec<IndexedPayment> = events.into_iter()
+            .filter(|e| !chain.payments.contains_key(&(e.block_number, e.log_index)))
+            .filter(|e| seen.insert((e.block_number, e.log_index)))
+            .map(|event| IndexedPayment { chain_id, event, indexed_at: now })
+            .collect();
+
+        if !new.is_empty() {
+            let mut lines = String::new();
+            for payment in &new {
+                lines.push_str(&serde_json::to_string(payment)?);
+                lines.push('\n');
+            }
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.dir.join(format!("chain_{chain_id}.jsonl")))?;
+            file.write_all(lines.as_bytes())?;
+            file.sync_all()?;
+        }
+        let added = new.len();
+        for payment in new {
+            chain.payments.insert((payment.event.block_number, payment.event.log_index), payment);
+        }
+
+        // The cursor moves only after the payments are on disk
+        chain.indexed_through = Some(chain.indexed_through.map_or(through_block, |t| t.max(through_block)));
+        let cursors: HashMap<u64, u64> = chains.iter()
+            .filter_map(|(id, c)| c.indexed_through.map(|t| (*id, t)))
+            .collect();
+        let tmp_file = self.dir.join("cursors.json.tmp");
+        fs::write(&tmp_file, serde_json::to_string_pretty(&cursors)?)?;
+        fs::rename(&tmp_file, self.dir.join("cursors.json"))?;
+        Ok(added)
+    }
+
+    pub fn indexed_through(&self, chain_id: u64) -> Option<u64> {
+        self.chains.lock().unwrap().get(&chain_id).and_then(|c| c.indexed_through)
+    }
+
+    pub fn query(&self, query: &PaymentQuery) -> PaymentPage {
+        let chains = self.chains.lock().unwrap();
+        let Some(chain) = chains.get(&query.chain_id) else {
+            return PaymentPage::default();
+        };
+        let matches: Vec<&IndexedPayment> = chain.payments.values().rev().filter(|p| query.matches(p)).collect();
+        PaymentPage {
+            total: matches.len(),
+            payments: matches.into_iter()
+                .skip(query.offset.unwrap_or(0))
+                .take(query.limit.unwrap_or(100).min(1000))
+                .cloned()
+                .collect(),
+            indexed_through: chain.indexed_through,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{H256, U256};
+
+    fn event(from: Address, block_number: u64, log_index: u64, reference: &str) -> PaymentEvent {
+        PaymentEvent {
+            from,
+            to: Address::repeat_byte(0xbb),
+            amount: U256::from(1_000u64),
+            payment_reference: reference.to_string(),
+            is_relayed: true,
+            tx_hash: H256::repeat_byte(block_number as u8),
+            block_number,
+            log_index,
+        }
+    }
+
+    #[test]
+    fn test_payments_survive_reopen_and_filter() {
+        let dir = std::env::temp_dir().join(format!("payment_index_{}", uuid::Uuid::new_v4()));
+        let alice = Address::repeat_byte(0xaa);
+        let carol = Address::repeat_byte(0xcc);
+        {
+            let index = PaymentIndex::open(&dir).unwrap();
+            let added = index.record(84532, vec![event(alice, 10, 0, "order-1"), event(carol, 12, 3, "order-2")], 20).unwrap();
+            assert_eq!(added, 2);
+            // Overlapping ranges do not duplicate payments
+            assert_eq!(index.record(84532, vec![event(carol, 12, 3, "order-2"), event(alice, 25, 1, "order-3")], 30).unwrap(), 1);
+        }
+
+        let index = PaymentIndex::open(&dir).unwrap();
+        assert_eq!(index.indexed_through(84532), Some(30));
+        let page = index.query(&PaymentQuery { chain_id: 84532, payer: Some(alice), ..Default::default() });
+        assert_eq!(page.total, 2);
+        assert_eq!(page.payments[0].event.block_number, 25);
+        let page = index.query(&PaymentQuery { chain_id: 84532, reference: Some("order-2".to_string()), ..Default::default() });
+        assert_eq!(page.payments[0].event.from, carol);
+        let page = index.query(&PaymentQuery { chain_id: 84532, from_block: Some(11), limit: Some(1), offset: Some(1), ..Default::default() });
+        assert_eq!((page.total, page.payments[0].event.block_number), (2, 12));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,351 @@
+use anyhow::{Result, anyhow};
+use sled::transaction::ConflictableTransactionResult;
+use sled::{Db, IVec, Transactional, Tree};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::infrastructure::storage::backend::{StorageBackend, TransactionPage, TransactionSearch};
+use crate::infrastructure::storage::file_storage::{Device, Metrics, Transaction};
+use crate::infrastructure::storage::json_backend::JsonFileBackend;
+
+const SCHEMA_VERSION_KEY: &[u8] = b"schema_version";
+const METRICS_KEY: &[u8] = b"metrics";
+
+type Migration = fn(&SledBackend, &Path) -> Result<()>;
+
+/// Applied in order on open; append new steps, never edit shipped ones
+const MIGRATIONS: &[(u32, &str, Migration)] = &[
+    (1, "import JSON file storage", import_json_files),
+];
+
+/// Seed the database from the JSON files of a relay that ran on the file backend
+fn import_json_files(backend: &SledBackend, data_dir: &Path) -> Result<()> {
+    let files = JsonFileBackend::open(&data_dir.to_string_lossy())?;
+    let mut transactions = files.list_transactions(usize::MAX, None)?.transactions;
+    transactions.reverse();
+    for transaction in &transactions {
+        backend.insert_transaction(transaction)?;
+    }
+    for device in files.load_devices()? {
+        backend.upsert_device(&device)?;
+    }
+    if let Some(metrics) = files.load_metrics()? {
+        backend.save_metrics(&metrics)?;
+    }
+    log::info!("Imported {} transactions from JSON storage", transactions.len());
+    Ok(())
+}
+
+fn device_prefix(device_id: &str) -> Vec<u8> {
+    let mut prefix = device_id.as_bytes().to_vec();
+    prefix.push(0);
+    prefix
+}
+
+fn device_key(device_id: &str, seq: &[u8]) -> Vec<u8> {
+    let mut key = device_prefix(device_id);
+    key.extend_from_slice(seq);
+    key
+}
+
+/// Embedded database backend.
+///
+/// Transactions are stored by id, with indexes on insertion sequence (for
+/// ordered listing), on device and sequence, and on the signed payload.
+/// Index entries are written in the same sled transaction as the record.
+pub struct SledBackend {
+    db: Db,
+    transactions: Tree,
+    /// id -> sequence number
+    tx_seq: Tree,
+    /// sequence number -> id
+    tx_by_seq: Tree,
+    /// device id, NUL, sequence number -> id
+    tx_by_device: Tree,
+    /// signed payload -> id
+    tx_by_signed: Tree,
+    devices: Tree,
+    meta: Tree,
+    /// Serialises read-modify-write updates of a single record
+    write_lock: Mutex<()>,
+}
+
+impl SledBackend {
+    /// Open the database at `path`, running pending migrations. `data_dir`
+    /// is where JSON files from the file backend are imported from.
+    pub fn open(path: impl Into<PathBuf>, data_dir: impl AsRef<Path>) -> Result<Self> {
+        let db = sled::open(path.into())?;
+        let backend = Self {
+            transactions: db.open_tree("transactions")?,
+            tx_seq: db.open_tree("tx_seq")?,
+            tx_by_seq: db.open_tree("tx_by_seq")?,
+            tx_by_device: db.open_tree("tx_by_device")?,
+            tx_by_signed: db.open_tree("tx_by_signed")?,
+            devices: db.open_tree("devices")?,
+            meta: db.open_tree("meta")?,
+            db,
+            write_lock: Mutex::new(()),
+        };
+        backend.migrate(data_dir.as_ref())?;
+        Ok(backend)
+    }
+
+    pub fn schema_version(&self) -> Result<u32> {
+        Ok(match self.meta.get(SCHEMA_VERSION_KEY)? {
+            Some(bytes) => u32::from_be_bytes(bytes.as_ref().try_into()
+                .map_err(|_| anyhow!("Corrupt schema version"))?),
+            None => 0,
+        })
+    }
+
+    fn migrate(&self, data_dir: &Path) -> Result<()> {
+        let current = self.schema_version()?;
+        let latest = MIGRATIONS.last().map(|(version, _, _)| *version).unwrap_or(0);
+        if current > latest {
+            return Err(anyhow!("Database schema {} is newer than this relay supports ({})", current, latest));
+        }
+        for (version, description, migration) in MIGRATIONS.iter().filter(|(v, _, _)| *v > current) {
+            log::info!("Applying storage migration {}: {}", version, description);
+            migration(self, data_dir)?;
+            self.meta.insert(SCHEMA_VERSION_KEY, &version.to_be_bytes()[..])?;
+            self.db.flush()?;
+        }
+        Ok(())
+    }
+
+    fn decode(bytes: &IVec) -> Result<Transaction> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    fn seq_of(&self, id: &str) -> Result<IVec> {
+        self.tx_seq.get(id)?.ok_or_else(|| anyhow!("Unknown cursor {}", id))
+    }
+
+    /// Resolve index entries (newest first) to transactions, one past `limit`
+    /// to tell whether another page follows
+    fn page(&self, ids: impl Iterator<Item = sled::Result<(IVec, IVec)>>, limit: usize) -> Result<TransactionPage> {
+        let mut transactions = Vec::new();
+        let mut more = false;
+        for entry in ids {
+            let (_, id) = entry?;
+            if transactions.len() == limit {
+                more = true;
+                break;
+            }
+            // An index entry without a record is a removal in progress
+            if let Some(bytes) = self.transactions.get(&id)? {
+                transactions.push(Self::decode(&bytes)?);
+            }
+        }
+        let next_cursor = if more { transactions.last().map(|t| t.id.clone()) } else { None };
+        Ok(TransactionPage { transactions, next_cursor })
+    }
+
+    /// Like `page`, keeping only transactions that match `search`
+    fn search_page(&self, ids: impl Iterator<Item = sled::Result<(IVec, IVec)>>, search: &TransactionSearch, limit: usize) -> Result<TransactionPage> {
+        let mut transactions = Vec::new();
+        let mut more = false;
+        for entry in ids {
+            let (_, id) = entry?;
+            let Some(bytes) = self.transactions.get(&id)? else {
+                continue;
+            };
+            let transaction = Self::decode(&bytes)?;
+            if !search.matches(&transaction) {
+                continue;
+            }
+            if transactions.len() == limit {
+                more = true;
+                break;
+            }
+            transactions.push(transaction);
+        }
+        let next_cursor = if more { transactions.last().map(|t| t.id.clone()) } else { None };
+        Ok(TransactionPage { transactions, next_cursor })
+    }
+}
+
+impl StorageBackend for SledBackend {
+    fn name(&self) -> &'static str {
+        "sled"
+    }
+
+    fn insert_transaction(&self, transaction: &Transaction) -> Result<()> {
+        let seq = self.db.generate_id()?.to_be_bytes();
+        let id = transaction.id.as_bytes();
+        let record = serde_json::to_vec(transaction)?;
+        (&self.transactions, &self.tx_seq, &self.tx_by_seq, &self.tx_by_device, &self.tx_by_signed)
+            .transaction(|(transactions, tx_seq, tx_by_seq, tx_by_device, tx_by_signed)| -> ConflictableTransactionResult<(), ()> {
+                transactions.insert(id, record.as_slice())?;
+                tx_seq.insert(id, &seq[..])?;
+                tx_by_seq.insert(&seq[..], id)?;
+                if let Some(device_id) = &transaction.device_id {
+                    tx_by_device.insert(device_key(device_id, &seq), id)?;
+                }
+                tx_by_signed.insert(transaction.signed_tx.as_bytes(), id)?;
+                Ok(())
+            })
+            .map_err(|e| anyhow!("Failed to store transaction {}: {:?}", transaction.id, e))
+    }
+
+    fn update_transaction(&self, id: &str, update: &mut dyn FnMut(&mut Transaction)) -> Result<Transaction> {
+        let _guard = self.write_lock.lock().unwrap();
+        let bytes = self.transactions.get(id)?
+            .ok_or_else(|| anyhow!("Transaction not found: {}", id))?;
+        let mut transaction = Self::decode(&bytes)?;
+        update(&mut transaction);
+        self.transactions.insert(id, serde_json::to_vec(&transaction)?)?;
+        Ok(transaction)
+    }
+
+    fn get_transaction(&self, id: &str) -> Result<Option<Transaction>> {
+        self.transactions.get(id)?.map(|bytes| Self::decode(&bytes)).transpose()
+    }
+
+    fn find_transaction_by_signed_tx(&self, signed_tx: &str) -> Result<Option<Transaction>> {
+        match self.tx_by_signed.get(signed_tx)? {
+            Some(id) => self.transactions.get(&id)?.map(|bytes| Self::decode(&bytes)).transpose(),
+            None => Ok(None),
+        }
+    }
+
+    fn list_transactions(&self, limit: usize, cursor: Option<&str>) -> Result<TransactionPage> {
+        match cursor {
+            Some(cursor) => self.page(self.tx_by_seq.range(..self.seq_of(cursor)?).rev(), limit),
+            None => self.page(self.tx_by_seq.iter().rev(), limit),
+        }
+    }
+
+    fn transactions_by_device(&self, device_id: &str, limit: usize, cursor: Option<&str>) -> Result<TransactionPage> {
+        match cursor {
+            Some(cursor) => {
+                let end = device_key(device_id, &self.seq_of(cursor)?);
+                self.page(self.tx_by_device.range(device_prefix(device_id)..end).rev(), limit)
+            }
+            None => self.page(self.tx_by_device.scan_prefix(device_prefix(device_id)).rev(), limit),
+        }
+    }
+
+    fn search_transactions(&self, search: &TransactionSearch, limit: usize, cursor: Option<&str>) -> Result<TransactionPage> {
+        // One device's transactions are indexed, so scan only those when asked
+        match (&search.device_id, cursor) {
+            (Some(device_id), Some(cursor)) => {
+                let end = device_key(device_id, &self.seq_of(cursor)?);
+                self.search_page(self.tx_by_device.range(device_prefix(device_id)..end).rev(), search, limit)
+            }
+            (Some(device_id), None) => self.search_page(self.tx_by_device.scan_prefix(device_prefix(device_id)).rev(), search, limit),
+            (None, Some(cursor)) => self.search_page(self.tx_by_seq.range(..self.seq_of(cursor)?).rev(), search, limit),
+            (None, None) => self.search_page(self.tx_by_seq.iter().rev(), search, limit),
+        }
+    }
+
+    fn remove_transactions(&self, ids: &[String]) -> Result<()> {
+        for id in ids {
+            let Some(bytes) = self.transactions.get(id)? else {
+                continue;
+            };
+            let transaction = Self::decode(&bytes)?;
+            let seq = self.tx_seq.get(id)?;
+            (&self.transactions, &self.tx_seq, &self.tx_by_seq, &self.tx_by_device, &self.tx_by_signed)
+                .transaction(|(transactions, tx_seq, tx_by_seq, tx_by_device, tx_by_signed)| -> ConflictableTransactionResult<(), ()> {
+                    transactions.remove(id.as_bytes())?;
+                    tx_seq.remove(id.as_bytes())?;
+                    if let Some(seq) = &seq {
+                        tx_by_seq.remove(seq)?;
+                        if let Some(device_id) = &transaction.device_id {
+                            tx_by_device.remove(device_key(device_id, seq))?;
+                        }
+                    }
+                    tx_by_signed.remove(transaction.signed_tx.as_bytes())?;
+                    Ok(())
+                })
+                .map_err(|e| anyhow!("Failed to remove transaction {}: {:?}", id, e))?;
+        }
+        Ok(())
+    }
+
+    fn transaction_count(&self) -> Result<usize> {
+        Ok(self.transactions.len())
+    }
+
+    fn load_devices(&self) -> Result<Vec<Device>> {
+        self.devices.iter()
+            .map(|entry| Ok(serde_json::from_slice(&entry?.1)?))
+            .collect()
+    }
+
+    fn upsert_device(&self, device: &Device) -> Result<()> {
+        self.devices.insert(device.id.as_bytes(), serde_json::to_vec(device)?)?;
+        Ok(())
+    }
+
+    fn remove_devices(&self, ids: &[String]) -> Result<()> {
+        for id in ids {
+            self.devices.remove(id.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn load_metrics(&self) -> Result<Option<Metrics>> {
+        self.meta.get(METRICS_KEY)?
+            .map(|bytes| Ok(serde_json::from_slice(&bytes)?))
+            .transpose()
+    }
+
+    fn save_metrics(&self, metrics: &Metrics) -> Result<()> {
+        self.meta.insert(METRICS_KEY, serde_json::to_vec(metrics)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pages_follow_insertion_order_and_device_index() {
+        let dir = std::env::temp_dir().join(format!("sled-backend-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let backend = SledBackend::open(dir.join("db"), &dir).unwrap();
+        assert_eq!(backend.schema_version().unwrap(), 1);
+
+        let mut ids = Vec::new();
+        for i in 0..5 {
+            let device = if i % 2 == 0 { "even" } else { "odd" };
+            let transaction = Transaction::new(format!("0x{:02x}", i), 1114).with_device(device);
+            ids.push(transaction.id.clone());
+            backend.insert_transaction(&transaction).unwrap();
+        }
+
+        let first = backend.list_transactions(2, None).unwrap();
+        assert_eq!(first.transactions.iter().map(|t| &t.id).collect::<Vec<_>>(), vec![&ids[4], &ids[3]]);
+        let second = backend.list_transactions(2, first.next_cursor.as_deref()).unwrap();
+        assert_eq!(second.transactions[0].id, ids[2]);
+        let last = backend.list_transactions(2, second.next_cursor.as_deref()).unwrap();
+        assert_eq!(last.transactions.len(), 1);
+        assert!(last.next_cursor.is_none());
+
+        let even = backend.transactions_by_device("even", 10, None).unwrap();
+        assert_eq!(even.transactions.iter().map(|t| &t.id).collect::<Vec<_>>(), vec![&ids[4], &ids[2], &ids[0]]);
+        let after = backend.transactions_by_device("even", 10, Some(&ids[2])).unwrap();
+        assert_eq!(after.transactions.len(), 1);
+
+        let mut tags = std::collections::BTreeMap::new();
+        tags.insert("till".to_string(), "3".to_string());
+        let tagged = Transaction::new("0x10".to_string(), 1114).with_device("odd").with_tags(tags.clone());
+        backend.insert_transaction(&tagged).unwrap();
+        let search = TransactionSearch { tags, device_id: None };
+        let found = backend.search_transactions(&search, 10, None).unwrap();
+        assert_eq!(found.transactions.iter().map(|t| &t.id).collect::<Vec<_>>(), vec![&tagged.id]);
+        let from_even = TransactionSearch { device_id: Some("even".to_string()), ..search };
+        assert!(backend.search_transactions(&from_even, 10, None).unwrap().transactions.is_empty());
+        backend.remove_transactions(&[tagged.id.clone()]).unwrap();
+
+        backend.remove_transactions(&[ids[2].clone()]).unwrap();
+        assert_eq!(backend.transactions_by_device("even", 10, None).unwrap().transactions.len(), 2);
+        assert!(backend.find_transaction_by_signed_tx("0x02").unwrap().is_none());
+        assert_eq!(backend.transaction_count().unwrap(), 4);
+
+        drop(backend);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
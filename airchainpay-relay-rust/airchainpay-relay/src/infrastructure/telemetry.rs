@@ -0,0 +1,137 @@
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::{TraceContextExt, TracerProvider};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracer, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Header carrying the W3C trace context, on requests and in queue metadata
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+static TRACER_PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracingConfig {
+    pub enabled: bool,
+    /// OTLP/HTTP collector base URL; spans go to `{endpoint}/v1/traces`
+    pub endpoint: String,
+    pub service_name: String,
+    /// Share of new traces kept; incoming sampled traces are always kept
+    pub sample_ratio: f64,
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://localhost:4318".to_string(),
+            service_name: "airchainpay-relay".to_string(),
+            sample_ratio: 1.0,
+        }
+    }
+}
+
+impl TracingConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("OTEL_TRACING_ENABLED")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.enabled),
+            endpoint: std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").unwrap_or(defaults.endpoint),
+            service_name: std::env::var("OTEL_SERVICE_NAME").unwrap_or(defaults.service_name),
+            sample_ratio: std::env::var("OTEL_TRACES_SAMPLER_ARG")
+                .ok()
+                .and_then(|v| v.parse::<f64>().ok())
+                .map(|ratio| ratio.clamp(0.0, 1.0))
+                .unwrap_or(defaults.sample_ratio),
+        }
+    }
+}
+
+/// Start exporting spans over OTLP/HTTP. Returns the tracer for the
+/// subscriber's OpenTelemetry layer, or `None` when tracing is disabled or
+/// the exporter cannot be built.
+pub fn init_tracer(config: &TracingConfig) -> Option<SdkTracer> {
+    if !config.enabled {
+        return None;
+    }
+    let endpoint = format!("{}/v1/traces", config.endpoint.trim_end_matches('/'));
+    let exporter = match SpanExporter::builder().with_http().with_endpoint(endpoint.as_str()).build() {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("Failed to create OTLP exporter for {endpoint}: {e}");
+            return None;
+        }
+    };
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sample_ratio))))
+        .with_resource(Resource::builder().with_service_name(config.service_name.clone()).build())
+        .build();
+    let tracer = provider.tracer("airchainpay-relay");
+    let _ = TRACER_PROVIDER.set(provider);
+    Some(tracer)
+}
+
+/// Flush spans still waiting in the batch exporter
+pub fn shutdown() {
+    if let Some(provider) = TRACER_PROVIDER.get() {
+        if let Err(e) = provider.shutdown() {
+            eprintln!("Failed to flush traces: {e}");
+        }
+    }
+}
+
+/// W3C trace context headers (`traceparent`, `tracestate`) for `span`,
+/// empty when spans are not being exported
+pub fn trace_headers(span: &Span) -> HashMap<String, String> {
+    let mut headers = HashMap::new();
+    TraceContextPropagator::new().inject_context(&span.context(), &mut headers);
+    headers
+}
+
+/// Continue the trace described by `headers` in `span`; malformed or
+/// missing context leaves `span` the root of a new trace
+pub fn set_parent(span: &Span, headers: &HashMap<String, String>) {
+    let cx = TraceContextPropagator::new().extract(headers);
+    if cx.span().span_context().is_valid() {
+        let _ = span.set_parent(cx);
+    }
+}
+
+/// Trace id of `span`, when spans are being exported
+pub fn trace_id(span: &Span) -> Option<String> {
+    let cx = span.context();
+    let span_context = cx.span().span_context().clone();
+    span_context.is_valid().then(|| span_context.trace_id().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traceparent_round_trips_through_propagator() {
+        let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let incoming = HashMap::from([(TRACEPARENT_HEADER.to_string(), traceparent.to_string())]);
+
+        let cx = TraceContextPropagator::new().extract(&incoming);
+        assert_eq!(cx.span().span_context().trace_id().to_string(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        let mut outgoing = HashMap::new();
+        TraceContextPropagator::new().inject_context(&cx, &mut outgoing);
+        assert_eq!(outgoing.get(TRACEPARENT_HEADER).map(String::as_str), Some(traceparent));
+
+        let malformed = HashMap::from([(TRACEPARENT_HEADER.to_string(), "00-zz-00-01".to_string())]);
+        assert!(!TraceContextPropagator::new().extract(&malformed).span().span_context().is_valid());
+
+        // Without an OpenTelemetry layer nothing is exported or propagated
+        assert!(trace_id(&Span::none()).is_none());
+        assert!(trace_headers(&Span::none()).is_empty());
+    }
+}
@@ -4,22 +4,59 @@ use std::sync::Arc;
 use airchainpay_relay::infrastructure::config::DynamicConfigManager;
 use airchainpay_relay::infrastructure::storage::file_storage::Storage;
 use airchainpay_relay::infrastructure::blockchain::manager::BlockchainManager;
+use airchainpay_relay::infrastructure::blockchain::fees::{FeeOracle, FeeOracleConfig};
+use airchainpay_relay::infrastructure::blockchain::indexer::{IndexerConfig, PaymentIndexer};
+use airchainpay_relay::infrastructure::blockchain::rpc_pool::RpcClientPool;
+use airchainpay_relay::infrastructure::ble::noise::NoiseStaticKey;
+use airchainpay_relay::infrastructure::ble::manager::{BLEManager, BLEManagerConfig};
+use airchainpay_relay::infrastructure::ble::session::BleSessionManager;
 use airchainpay_relay::domain::auth::AuthManager;
 use airchainpay_relay::infrastructure::monitoring::manager::MonitoringManager;
 use airchainpay_relay::utils::error_handler::EnhancedErrorHandler;
 use airchainpay_relay::utils::backup::BackupManager;
-use airchainpay_relay::utils::audit::AuditLogger;
-use airchainpay_relay::infrastructure::logger::Logger;
-use airchainpay_relay::app::transaction_service::TransactionProcessor;
+use airchainpay_relay::utils::audit::{AuditLogger, AuditPolicy};
+use airchainpay_relay::infrastructure::logger::{self, LogConfig};
+use airchainpay_relay::infrastructure::telemetry;
+use airchainpay_relay::app::transaction_service::{TransactionProcessor, TransactionProcessorConfig};
+use airchainpay_relay::app::nonce_monitor::{NonceMonitor, NonceMonitorConfig};
+use airchainpay_relay::app::probes::ProbeState;
+use airchainpay_relay::domain::auth::api_keys::ApiKeyStore;
+use airchainpay_relay::app::heartbeat::{HeartbeatConfig, HeartbeatPinger};
+use airchainpay_relay::app::warmup::{Warmup, WarmupConfig};
+use airchainpay_relay::app::scheduler::{DataPruner, RetentionPolicy};
+use airchainpay_relay::app::config_rollout::{ConfigRollout, RolloutPolicy};
+use airchainpay_relay::app::federation::{Federation, FederationConfig};
+use airchainpay_relay::app::attestation::{AttestationService, AttestationConfig};
+use airchainpay_relay::app::enrollment::DeviceEnrollment;
+use airchainpay_relay::app::memory_guard::{MemoryGuard, MemoryGuardConfig};
+use airchainpay_relay::app::reconciliation::{Reconciler, ReconciliationConfig};
+use airchainpay_relay::app::gas_accounting::{GasLedger, GasBudgetConfig};
+use airchainpay_relay::app::screening::{ScreeningConfig, ScreeningService};
+use airchainpay_relay::app::token_registry::{TokenRegistry, TokenRegistryConfig};
+use airchainpay_relay::app::simulation::{SimulationConfig, TransactionSimulator};
+use airchainpay_relay::app::replay_guard::{TxReplayConfig, TxReplayGuard};
+use airchainpay_relay::validators::payload_tier::PayloadTierConfig;
+use airchainpay_relay::app::health_visibility::HealthVisibilityConfig;
+use airchainpay_relay::app::canary::{CanaryConfig, CanaryMonitor};
+use airchainpay_relay::domain::identity::RelayIdentity;
+use airchainpay_relay::infrastructure::pki::{DeviceCa, DeviceCaConfig};
+use airchainpay_relay::middleware::client_cert::capture_peer_certificate;
 use airchainpay_relay::utils::backup::BackupConfig;
 use airchainpay_relay::middleware::metrics::MetricsMiddleware;
 use airchainpay_relay::middleware::error_handling::ErrorHandlingMiddleware;
 use airchainpay_relay::middleware::rate_limiting::RateLimitingMiddleware;
 use airchainpay_relay::middleware::ComprehensiveSecurityMiddleware;
+use airchainpay_relay::middleware::input_validation::BodyLimits;
+use airchainpay_relay::middleware::security::{cors_config, SecurityConfig};
+use airchainpay_relay::middleware::authorization::{AuthorizationMiddleware, RoutePolicy};
+use airchainpay_relay::middleware::replay_protection::{ReplayProtectionMiddleware, ReplayProtectionConfig};
+use airchainpay_relay::middleware::challenge::{ChallengeGateMiddleware, ChallengeConfig, ProofOfWork};
+use airchainpay_relay::middleware::quota::{QuotaConfig, QuotaManager, QuotaMiddleware};
+use airchainpay_relay::middleware::request_id::{RequestIdMiddleware, ACCESS_LOG_FORMAT};
 use airchainpay_relay::api::*;
 use airchainpay_relay::api::handlers::transaction::{
     validate_inputs, simple_send_tx, get_transaction_details, 
-    get_transaction_status, get_user_transactions, get_supported_chains, get_chain_info, get_transaction_by_hash
+    get_transaction_status, get_transaction_receipt, get_transaction_proof, get_user_transactions, get_supported_chains, get_chain_registry, get_chain_info, get_networks_status, get_fee_estimate, get_transaction_by_hash, get_contract_payments
 };
 use airchainpay_relay::utils::animated_ascii;
 use std::env;
@@ -29,8 +66,8 @@ async fn main() -> std::io::Result<()> {
     // Display animated ASCII logo
     animated_ascii::display_animated_logo();
     
-    // Initialize logger
-    Logger::init("info");
+    // Initialize structured logging
+    logger::init(&LogConfig::from_env());
     
     log::info!("🚀 Starting AirChainPay Relay Server...");
     
@@ -91,6 +128,15 @@ async fn main() -> std::io::Result<()> {
         }
     };
     
+    // Client API keys, exchanged for JWTs at /api/auth/token
+    let api_keys = match ApiKeyStore::open(Arc::clone(&storage)) {
+        Ok(store) => Arc::new(store),
+        Err(e) => {
+            log::error!("❌ Failed to load API keys: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("API key store initialization failed: {}", e)));
+        }
+    };
+    
     // Initialize blockchain manager with error handling
     let blockchain_manager = match BlockchainManager::new(config.clone()) {
         Ok(manager) => {
@@ -103,6 +149,28 @@ async fn main() -> std::io::Result<()> {
         }
     };
     
+    // Keep blockchain clients in step with runtime chain changes
+    {
+        let config_manager = Arc::clone(&config_manager);
+        let blockchain_manager = Arc::clone(&blockchain_manager);
+        let mut config_changes = config_manager.subscribe();
+        tokio::spawn(async move {
+            while config_changes.changed().await.is_ok() {
+                let config = config_manager.get_config().await;
+                if let Err(e) = blockchain_manager.apply_chains(&config.supported_chains) {
+                    log::error!("❌ Failed to apply updated chain configuration: {}", e);
+                }
+                if let Err(e) = logger::set_levels(&config.log_level, &config.log_modules) {
+                    log::error!("❌ Failed to apply updated log levels: {}", e);
+                }
+            }
+        });
+    }
+    
+    // Keep pooled RPC connections warm between requests
+    RpcClientPool::start_keep_alive(RpcClientPool::global(), std::time::Duration::from_secs(30));
+    log::info!("✅ RPC client pool keep-alive started");
+    
     // Initialize auth manager
     let auth_manager = Arc::new(AuthManager::new());
     log::info!("✅ Auth manager initialized successfully");
@@ -123,27 +191,281 @@ async fn main() -> std::io::Result<()> {
     
     // Initialize audit logger
     let audit_logger = Arc::new(AuditLogger::new("audit.log".to_string(), 10000)
+        .with_policy(AuditPolicy::from_env())
         .with_monitoring(Arc::clone(&monitoring_manager)));
     log::info!("✅ Audit logger initialized successfully");
     
+    // Start retention job for stale devices and expired transactions
+    let data_pruner = Arc::new(DataPruner::new(
+        Arc::clone(&storage),
+        Arc::clone(&audit_logger),
+        RetentionPolicy::from_env(),
+    ));
+    DataPruner::start(Arc::clone(&data_pruner));
+    log::info!("✅ Data pruning job started successfully");
+    
+    // API config changes are staged and reverted if error rates spike
+    let config_rollout = Arc::new(ConfigRollout::new(
+        Arc::clone(&config_manager),
+        Arc::clone(&monitoring_manager),
+        RolloutPolicy::from_env(),
+    ));
+    log::info!("✅ Config rollout manager initialized successfully");
+    
     // Initialize enhanced error handler
     let error_handler = Arc::new(EnhancedErrorHandler::new());
     log::info!("✅ Error handler initialized successfully");
     
+    // Relay identity signs payment receipts and federation manifests
+    let relay_identity = match RelayIdentity::load_or_create(storage.data_dir()) {
+        Ok(identity) => {
+            log::info!("✅ Relay identity loaded: {:?}", identity.address());
+            Arc::new(identity)
+        }
+        Err(e) => {
+            log::error!("❌ Failed to load relay identity: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Relay identity initialization failed: {}", e)));
+        }
+    };
+    
     // Initialize enhanced transaction processor
-    let transaction_processor = Arc::new(TransactionProcessor::new(
+    // Memory guard sheds low-priority work before the container limit is reached
+    let memory_guard = Arc::new(MemoryGuard::new(MemoryGuardConfig::from_env()));
+    
+    // Per-chain fee levels from fee history, used to refuse unmineable fees
+    let fee_oracle = Arc::new(FeeOracle::new(Arc::clone(&blockchain_manager), FeeOracleConfig::from_env()));
+    FeeOracle::start(Arc::clone(&fee_oracle));
+    
+    // Sanctions screening of the sender and recipient before broadcast
+    let screening = match ScreeningService::from_config(&ScreeningConfig::from_env(), Arc::clone(&audit_logger)) {
+        Ok(screening) => screening.map(Arc::new),
+        Err(e) => {
+            log::error!("❌ Failed to initialize sanctions screening: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Sanctions screening initialization failed: {}", e)));
+        }
+    };
+    
+    // Token metadata and payment contract checks, refreshed off the hot path
+    let token_registry = Arc::new(TokenRegistry::new(TokenRegistryConfig::from_env(), Arc::clone(&blockchain_manager)));
+    TokenRegistry::start(Arc::clone(&token_registry));
+    
+    // Hashes of accepted raw transactions, so replays are refused on every path
+    let replay_guard = match TxReplayGuard::new(TxReplayConfig::from_env(), Arc::clone(&storage)) {
+        Ok(guard) => Arc::new(guard),
+        Err(e) => {
+            log::error!("❌ Failed to load replay hashes: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Replay protection initialization failed: {}", e)));
+        }
+    };
+    if replay_guard.config().enabled {
+        log::info!("✅ Transaction replay protection enabled ({} hashes remembered)", replay_guard.len().await);
+    }
+    
+    let mut transaction_processor = TransactionProcessor::new(
         Arc::clone(&blockchain_manager),
         Arc::clone(&storage),
-        None, // Use default config
-    ));
+        Some(TransactionProcessorConfig::from_env()),
+    )
+    .with_identity(Arc::clone(&relay_identity))
+    .with_memory_guard(Arc::clone(&memory_guard))
+    .with_monitoring(Arc::clone(&monitoring_manager))
+    .with_fee_oracle(Arc::clone(&fee_oracle))
+    .with_token_registry(Arc::clone(&token_registry))
+    .with_replay_guard(Arc::clone(&replay_guard));
+    let simulation_config = SimulationConfig::from_env();
+    if simulation_config.enabled {
+        transaction_processor = transaction_processor.with_simulator(Arc::new(TransactionSimulator::new(Arc::clone(&blockchain_manager), simulation_config)));
+        log::info!("✅ Pre-broadcast simulation enabled");
+    }
+    if let Some(screening) = &screening {
+        transaction_processor = transaction_processor.with_screening(Arc::clone(screening));
+        log::info!("✅ Sanctions screening enabled");
+    }
+    let transaction_processor = Arc::new(transaction_processor);
     log::info!("✅ Transaction processor initialized successfully");
     
-    // Start the transaction processor with error handling
-    if let Err(e) = transaction_processor.start().await {
-        log::error!("❌ Failed to start transaction processor: {}", e);
-        return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Transaction processor startup failed: {}", e)));
+    let federation = Arc::new(Federation::new(
+        FederationConfig::from_env(),
+        Arc::clone(&relay_identity),
+        Arc::clone(&storage),
+        Arc::clone(&transaction_processor),
+        Arc::clone(&blockchain_manager),
+    ));
+    
+    if federation.is_edge() {
+        // Edge relays never broadcast; queued work is forwarded upstream instead
+        if let Err(e) = transaction_processor.restore_queue().await {
+            log::error!("❌ Failed to restore transaction queue: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Transaction queue restore failed: {}", e)));
+        }
+        Federation::start(Arc::clone(&federation));
+        log::info!("✅ Edge mode: forwarding queued transactions upstream");
+    } else {
+        // Start the transaction processor with error handling
+        if let Err(e) = transaction_processor.start().await {
+            log::error!("❌ Failed to start transaction processor: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Transaction processor startup failed: {}", e)));
+        }
+        log::info!("✅ Transaction processor started successfully");
+    }
+    
+    // Gas paid from relay wallets, with per-chain/device/merchant budgets
+    let gas_ledger = match GasLedger::new(GasBudgetConfig::from_env(), storage.data_dir()) {
+        Ok(ledger) => Arc::new(ledger),
+        Err(e) => {
+            log::error!("❌ Failed to load gas ledger: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Gas ledger initialization failed: {}", e)));
+        }
+    };
+    log::info!("✅ Gas ledger initialized successfully");
+    
+    // Daily/monthly submission quotas per device and API key
+    let quotas = match QuotaManager::new(QuotaConfig::from_env(), Arc::clone(&storage)) {
+        Ok(quotas) => Arc::new(quotas),
+        Err(e) => {
+            log::error!("❌ Failed to load quota usage: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Quota initialization failed: {}", e)));
+        }
+    };
+    log::info!("✅ Quota accounting initialized successfully");
+    
+    // Periodic on-chain Merkle roots of handled payments
+    let attestation = match AttestationService::new(
+        AttestationConfig::from_env(),
+        Arc::clone(&relay_identity),
+        Arc::clone(&storage),
+        Arc::clone(&blockchain_manager),
+    ) {
+        Ok(service) => Arc::new(service.with_gas_ledger(Arc::clone(&gas_ledger))),
+        Err(e) => {
+            log::error!("❌ Failed to initialize attestation service: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Attestation initialization failed: {}", e)));
+        }
+    };
+    AttestationService::start(Arc::clone(&attestation));
+    log::info!("✅ Attestation service initialized successfully");
+    
+    // Cross-check stored statuses against on-chain receipts
+    let reconciler = Arc::new(Reconciler::new(
+        ReconciliationConfig::from_env(),
+        Arc::clone(&storage),
+        Arc::clone(&blockchain_manager),
+        Arc::clone(&monitoring_manager),
+    ));
+    Reconciler::start(Arc::clone(&reconciler));
+    log::info!("✅ Transaction reconciliation initialized successfully");
+    
+    // Start relayer nonce monitoring
+    let nonce_monitor = Arc::new(NonceMonitor::new(
+        Arc::clone(&blockchain_manager),
+        Arc::clone(&monitoring_manager),
+        NonceMonitorConfig::from_env(),
+    ).with_gas_ledger(Arc::clone(&gas_ledger)));
+    NonceMonitor::start(Arc::clone(&nonce_monitor));
+    log::info!("✅ Nonce monitor initialized successfully");
+    
+    // Real broadcast health: periodic self-transfers from the relayer wallet
+    let canary = Arc::new(CanaryMonitor::new(
+        Arc::clone(&blockchain_manager),
+        Arc::clone(&monitoring_manager),
+        CanaryConfig::from_env(),
+    ).with_gas_ledger(Arc::clone(&gas_ledger)));
+    CanaryMonitor::start(Arc::clone(&canary));
+    
+    // Index contract Payment events for /api/contract/payments
+    let payment_indexer = Arc::new(PaymentIndexer::new(
+        Arc::clone(&blockchain_manager),
+        Arc::clone(&storage),
+        IndexerConfig::from_env(),
+    ));
+    if payment_indexer.config().enabled {
+        PaymentIndexer::start(Arc::clone(&payment_indexer));
+        log::info!("✅ Payment indexer started successfully");
+    }
+    
+    // Initialize device session manager for the WebSocket BLE bridge
+    let noise_key = match NoiseStaticKey::load_or_create(storage.data_dir()) {
+        Ok(key) => Arc::new(key),
+        Err(e) => {
+            log::error!("❌ Failed to load Noise static key: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Noise key initialization failed: {}", e)));
+        }
+    };
+    let ble_sessions = Arc::new(BleSessionManager::new()
+        .with_noise_key(noise_key)
+        .with_audit_logger(Arc::clone(&audit_logger)));
+    {
+        let ble_sessions = Arc::clone(&ble_sessions);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                ble_sessions.prune_expired().await;
+            }
+        });
+    }
+    log::info!("✅ BLE session manager initialized successfully");
+    
+    // GATT peripheral for phones paying over BLE directly
+    let ble_manager = Arc::new(BLEManager::new(
+        BLEManagerConfig::from_env(),
+        Arc::clone(&ble_sessions),
+        Arc::clone(&storage),
+        Arc::clone(&transaction_processor),
+    ).with_monitoring(Arc::clone(&monitoring_manager)));
+    BLEManager::start(Arc::clone(&ble_manager));
+    
+    // Device CA issuing short-lived client certificates for mTLS and BLE identity
+    let device_ca = match DeviceCa::load_or_create(storage.data_dir(), DeviceCaConfig::from_env()) {
+        Ok(ca) => Arc::new(ca),
+        Err(e) => {
+            log::error!("❌ Failed to initialize device CA: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Device CA initialization failed: {}", e)));
+        }
+    };
+    {
+        let device_ca = Arc::clone(&device_ca);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                if let Err(e) = device_ca.prune_expired() {
+                    log::warn!("Failed to prune expired device certificates: {}", e);
+                }
+            }
+        });
+    }
+    let enrollment = Arc::new(DeviceEnrollment::new(Arc::clone(&device_ca)));
+    log::info!("✅ Device CA initialized successfully");
+    
+    // Probe state backing /health/live, /health/ready and /health/startup
+    let probe_state = Arc::new(ProbeState::new(
+        Arc::clone(&storage),
+        Arc::clone(&blockchain_manager),
+        Arc::clone(&transaction_processor),
+    ));
+    
+    // Dead-man-switch pings to external uptime monitors
+    let heartbeat = Arc::new(HeartbeatPinger::new(
+        Arc::clone(&probe_state),
+        Arc::clone(&transaction_processor),
+        HeartbeatConfig::from_env(),
+    ));
+    HeartbeatPinger::start(heartbeat);
+    
+    // Warm RPC connections, fee and chain-id checks, the device registry and
+    // storage before binding, so the first payment doesn't pay for them
+    let warmup = Warmup::new(
+        Arc::clone(&storage),
+        Arc::clone(&blockchain_manager),
+        Arc::clone(&fee_oracle),
+        Arc::clone(&probe_state),
+        WarmupConfig::from_env(),
+    );
+    if let Err(e) = warmup.run().await {
+        log::error!("❌ Warmup failed: {}", e);
+        return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Warmup failed: {}", e)));
     }
-    log::info!("✅ Transaction processor started successfully");
     
     // Get port from environment or use default
     let port = env::var("PORT").unwrap_or_else(|_| "4000".to_string()).parse::<u16>().unwrap_or(4000);
@@ -152,49 +474,206 @@ async fn main() -> std::io::Result<()> {
     log::info!("📊 Environment: {}", config.environment);
     log::info!("🔗 Supported chains: {}", config.supported_chains.len());
     
-    HttpServer::new(move || {
+    // Admin/ops endpoints listen separately so the payment surface can be exposed on its own
+    let admin_bind = env::var("ADMIN_BIND").unwrap_or_else(|_| "127.0.0.1".to_string());
+    let admin_port = env::var("ADMIN_PORT").unwrap_or_else(|_| "4001".to_string()).parse::<u16>().unwrap_or(4001);
+    if env::var("ADMIN_API_KEY").is_err() {
+        log::warn!("⚠️ ADMIN_API_KEY not set; admin listener accepts admin JWTs only");
+    }
+    
+    // Role requirements per route on each listener
+    let public_route_policy = Arc::new(RoutePolicy::public_api());
+    let admin_route_policy = Arc::new(RoutePolicy::admin_api());
+    
+    // Per-route body size and JSON shape limits for the public API
+    let body_limits = BodyLimits::from_env();
+    
+    // Built once so every worker shares the same seen-nonce cache
+    let replay_protection = ReplayProtectionMiddleware::new(ReplayProtectionConfig::default());
+    MemoryGuard::start(
+        Arc::clone(&memory_guard),
+        Arc::clone(&transaction_processor),
+        Arc::clone(&ble_sessions),
+        replay_protection.cache(),
+    );
+    
+    let public_server = {
+        let storage = Arc::clone(&storage);
+        let api_keys = Arc::clone(&api_keys);
+        let blockchain_manager = Arc::clone(&blockchain_manager);
+        let auth_manager = Arc::clone(&auth_manager);
+        let monitoring_manager = Arc::clone(&monitoring_manager);
+        let transaction_processor = Arc::clone(&transaction_processor);
+        let config_manager = Arc::clone(&config_manager);
+        let error_handler = Arc::clone(&error_handler);
+        let ble_sessions = Arc::clone(&ble_sessions);
+        let probe_state = Arc::clone(&probe_state);
+        let federation = Arc::clone(&federation);
+        let attestation = Arc::clone(&attestation);
+        let enrollment = Arc::clone(&enrollment);
+        let quotas = Arc::clone(&quotas);
+        let replay_guard = Arc::clone(&replay_guard);
+        let cors_settings = SecurityConfig::for_environment(&config);
+        if cors_settings.allowed_origins.iter().any(|o| o == "*") {
+            log::warn!("⚠️ CORS is permissive (development only)");
+        }
+        // Shared by every worker, like the seen-nonce cache
+        let pow = Arc::new(ProofOfWork::new(ChallengeConfig::from_env()));
+        if pow.config().enabled {
+            log::info!("🧮 Proof-of-work required on credential endpoints (difficulty {})", pow.config().difficulty);
+        }
+        let payload_tiers = Arc::new(PayloadTierConfig::from_env());
+        let health_visibility = Arc::new(HealthVisibilityConfig::from_env());
+        if payload_tiers.fast_path_enabled {
+            log::info!("⚡ Fast path for plain signed transactions up to {} bytes", payload_tiers.fast_path_max_bytes);
+        }
+        let server = HttpServer::new(move || {
+            App::new()
+                // Global built-in middleware only
+                .wrap(actix_web::middleware::Logger::new(ACCESS_LOG_FORMAT))
+                .wrap(actix_web::middleware::Compress::default())
+                .wrap(cors_config(&cors_settings))
+                .wrap(RequestIdMiddleware::new())
+                .app_data(web::Data::new(Arc::clone(&storage)))
+                .app_data(web::Data::new(Arc::clone(&api_keys)))
+                .app_data(web::Data::new(Arc::clone(&blockchain_manager)))
+                .app_data(web::Data::new(Arc::clone(&auth_manager)))
+                .app_data(web::Data::new(Arc::clone(&monitoring_manager)))
+                .app_data(web::Data::new(Arc::clone(&transaction_processor)))
+                .app_data(web::Data::new(Arc::clone(&config_manager)))
+                .app_data(web::Data::new(Arc::clone(&ble_sessions)))
+                .app_data(web::Data::new(Arc::clone(&probe_state)))
+                .app_data(web::Data::new(Arc::clone(&federation)))
+                .app_data(web::Data::new(Arc::clone(&attestation)))
+                .app_data(web::Data::new(Arc::clone(&enrollment)))
+                .app_data(web::Data::new(Arc::clone(&pow)))
+                .app_data(web::Data::new(Arc::clone(&fee_oracle)))
+                .app_data(web::Data::new(Arc::clone(&payload_tiers)))
+                .app_data(web::Data::new(Arc::clone(&health_visibility)))
+                .app_data(web::Data::new(Arc::clone(&quotas)))
+                .app_data(web::Data::new(Arc::clone(&replay_guard)))
+                // Health endpoints (no custom middleware)
+                .service(health)
+                .service(liveness)
+                .service(readiness)
+                .service(startup)
+                .service(detailed_health)
+                .service(component_health)
+                .service(health_alerts)
+                .service(resolve_alert)
+                .service(health_metrics)
+                .service(contract_health_check)
+                .service(detailed_contract_health_check)
+                // Relay keys, chains and protocol versions for wallets to pin
+                .service(relay_identity_document)
+                // Device transport bridge (authenticated per connection)
+                .service(ws_ble_bridge)
+                // Transaction status push channel
+                .service(ws_transaction_status)
+                // API endpoints with custom middleware
+                .service(
+                    web::scope("/api")
+                        .wrap(AuthorizationMiddleware::new(Arc::clone(&public_route_policy)))
+                        .wrap(ChallengeGateMiddleware::new(Arc::clone(&pow)))
+                        .wrap(replay_protection.clone())
+                        .wrap(ComprehensiveSecurityMiddleware::new(
+                            airchainpay_relay::middleware::EnhancedSecurityConfig::default()
+                                .with_body_limits(body_limits.clone())
+                        ))
+                        .wrap(MetricsMiddleware::new(
+                            Arc::clone(&monitoring_manager)
+                        ))
+                        .wrap(ErrorHandlingMiddleware::new(
+                            Arc::clone(&error_handler)
+                        ))
+                        .wrap(RateLimitingMiddleware::new(
+                            100, // 100 requests per window
+                            10,  // 10 burst requests
+                            std::time::Duration::from_secs(60) // 1 minute window
+                        ))
+                        .wrap(QuotaMiddleware::new(Arc::clone(&quotas)))
+                        .service(submit_transaction)
+                        .service(legacy_submit_transaction)
+                        .service(test_transaction)
+                        .service(process_transaction)
+                        .service(process_compressed_transaction)
+                        .service(validate_inputs)
+                        .service(simple_send_tx)
+                        .service(get_transactions)
+                        .service(get_transaction_details)
+                        .service(get_transaction_status)
+                        .service(get_transaction_receipt)
+                        .service(get_transaction_proof)
+                        .service(transaction_events)
+                        .service(get_user_transactions)
+                        .service(get_supported_chains)
+                        .service(get_chain_registry)
+                        .service(get_chain_info)
+                        .service(get_networks_status)
+                        .service(get_fee_estimate)
+                        .service(get_transaction_by_hash)
+                        .service(get_contract_payments)
+                        .service(get_metrics)
+                        .service(get_devices)
+                        .service(ingest_manifest)
+                        .service(get_relay_identity)
+                        .service(enroll_device)
+                        .service(renew_device_certificate)
+                        .service(get_device_ca)
+                        .service(certificate_token)
+                        .service(generate_token)
+                        .service(get_auth_challenge)
+                        .service(get_quota)
+                )
+        })
+        .on_connect(capture_peer_certificate);
+        
+        // Terminate TLS here when a server certificate is configured; devices may
+        // then authenticate with their client certificate
+        match (env::var("TLS_CERT_PATH"), env::var("TLS_KEY_PATH")) {
+            (Ok(cert_path), Ok(key_path)) => {
+                let acceptor = device_ca.tls_acceptor(&cert_path, &key_path).map_err(|e| {
+                    log::error!("❌ Failed to configure TLS: {}", e);
+                    std::io::Error::new(std::io::ErrorKind::Other, format!("TLS configuration failed: {}", e))
+                })?;
+                log::info!("🔒 Public listener using TLS with optional client certificates");
+                server.bind_openssl(("0.0.0.0", port), acceptor)?.run()
+            }
+            _ => server.bind(("0.0.0.0", port))?.run(),
+        }
+    };
+    
+    log::info!("🔐 Starting admin listener on {}:{}", admin_bind, admin_port);
+    let admin_server = HttpServer::new(move || {
         App::new()
-            // Global built-in middleware only
-            .wrap(actix_web::middleware::Logger::default())
-            .wrap(actix_web::middleware::Compress::default())
-            .wrap(actix_cors::Cors::permissive())
+            .wrap(actix_web::middleware::Logger::new(ACCESS_LOG_FORMAT))
+            .wrap(RequestIdMiddleware::new())
             .app_data(web::Data::new(Arc::clone(&storage)))
+            .app_data(web::Data::new(Arc::clone(&api_keys)))
             .app_data(web::Data::new(Arc::clone(&blockchain_manager)))
-            .app_data(web::Data::new(Arc::clone(&auth_manager)))
             .app_data(web::Data::new(Arc::clone(&monitoring_manager)))
             .app_data(web::Data::new(Arc::clone(&backup_manager)))
             .app_data(web::Data::new(Arc::clone(&audit_logger)))
-            .app_data(web::Data::new(Arc::clone(&transaction_processor)))
+            .app_data(web::Data::new(Arc::clone(&error_handler)))
             .app_data(web::Data::new(Arc::clone(&config_manager)))
-            // Health endpoints (no custom middleware)
-            .service(health)
-            .service(detailed_health)
-            .service(component_health)
-            .service(health_alerts)
-            .service(resolve_alert)
-            .service(health_metrics)
-            .service(contract_health_check)
-            .service(detailed_contract_health_check)
-            // API endpoints with custom middleware
+            .app_data(web::Data::new(Arc::clone(&nonce_monitor)))
+            .app_data(web::Data::new(Arc::clone(&canary)))
+            .app_data(web::Data::new(Arc::clone(&token_registry)))
+            .app_data(web::Data::new(Arc::clone(&data_pruner)))
+            .app_data(web::Data::new(Arc::clone(&config_rollout)))
+            .app_data(web::Data::new(Arc::clone(&federation)))
+            .app_data(web::Data::new(Arc::clone(&attestation)))
+            .app_data(web::Data::new(Arc::clone(&reconciler)))
+            .app_data(web::Data::new(Arc::clone(&gas_ledger)))
+            .app_data(web::Data::new(Arc::clone(&enrollment)))
+            .app_data(web::Data::new(Arc::clone(&memory_guard)))
+            .app_data(web::Data::new(Arc::clone(&transaction_processor)))
             .service(
                 web::scope("/api")
-                    .wrap(ComprehensiveSecurityMiddleware::new(
-                        airchainpay_relay::middleware::EnhancedSecurityConfig::default()
-                    ))
-                    .wrap(MetricsMiddleware::new(
-                        Arc::clone(&monitoring_manager)
-                    ))
+                    .wrap(AuthorizationMiddleware::new(Arc::clone(&admin_route_policy)))
                     .wrap(ErrorHandlingMiddleware::new(
                         Arc::clone(&error_handler)
                     ))
-                    .wrap(RateLimitingMiddleware::new(
-                        100, // 100 requests per window
-                        10,  // 10 burst requests
-                        std::time::Duration::from_secs(60) // 1 minute window
-                    ))
-                    .service(submit_transaction)
-                    .service(legacy_submit_transaction)
-                    .service(test_transaction)
                     .service(create_backup)
                     .service(restore_backup)
                     .service(list_backups)
@@ -226,21 +705,59 @@ async fn main() -> std::io::Result<()> {
                     .service(get_configuration_summary)
                     .service(update_configuration_field)
                     .service(save_configuration_to_file)
-                    .service(process_transaction)
-                    .service(validate_inputs)
-                    .service(simple_send_tx)
-                    .service(get_transactions)
-                    .service(get_transaction_details)
-                    .service(get_transaction_status)
-                    .service(get_user_transactions)
-                    .service(get_supported_chains)
-                    .service(get_chain_info)
-                    .service(get_transaction_by_hash)
-                    .service(get_metrics)
-                    .service(get_devices)
+                    .service(run_selftest)
+                    .service(get_nonce_status)
+                    .service(list_chains)
+                    .service(import_chain_registry)
+                    .service(upsert_chain)
+                    .service(remove_chain)
+                    .service(run_prune)
+                    .service(get_prune_status)
+                    .service(archive_transaction)
+                    .service(search_archived_transactions)
+                    .service(get_archived_transaction)
+                    .service(export_transactions)
+                    .service(write_transaction_export)
+                    .service(get_top_devices)
+                    .service(get_config_rollout)
+                    .service(rollback_config_rollout)
+                    .service(get_federation_status)
+                    .service(sync_federation)
+                    .service(get_attestations)
+                    .service(run_attestation)
+                    .service(get_reconciliation_status)
+                    .service(run_reconciliation)
+                    .service(get_gas_spend)
+                    .service(create_enrollment_token)
+                    .service(list_device_certificates)
+                    .service(revoke_device_certificate)
+                    .service(revoke_device)
+                    .service(list_api_keys)
+                    .service(create_api_key)
+                    .service(rotate_api_key)
+                    .service(revoke_api_key)
+                    .service(issue_token)
+                    .service(get_memory_status)
+                    .service(get_canary_status)
+                    .service(run_canary)
+                    .service(get_token_registry)
             )
     })
-    .bind(("0.0.0.0", port))?
-    .run()
-    .await
+    .workers(2)
+    .bind((admin_bind.as_str(), admin_port))?
+    .run();
+    
+    probe_state.mark_started();
+    log::info!("✅ Startup complete");
+    
+    let result = futures::try_join!(public_server, admin_server);
+    if let Err(e) = replay_guard.flush().await {
+        log::error!("Failed to flush replay hashes: {}", e);
+    }
+    if let Err(e) = quotas.flush().await {
+        log::error!("Failed to flush quota usage: {}", e);
+    }
+    telemetry::shutdown();
+    result?;
+    Ok(())
 }
@@ -0,0 +1,309 @@
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::Method,
+    Error,
+};
+use actix_web::body::BoxBody;
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use crate::domain::auth::{self, Role};
+use crate::middleware::error_handling::ErrorResponseBuilder;
+use crate::middleware::route_pattern;
+
+/// Role needed for requests matching a method (any when unset) and path prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteRule {
+    pub method: Option<Method>,
+    pub prefix: String,
+    /// When set, only `{prefix}{segment}{suffix}` matches, for one
+    /// non-empty path segment
+    pub suffix: Option<String>,
+    /// None leaves the route open to anonymous callers
+    pub role: Option<Role>,
+    /// Pinned rules win over any other matching rule and cannot be
+    /// replaced from the environment
+    pub pinned: bool,
+}
+
+impl RouteRule {
+    pub fn new(method: Option<Method>, prefix: &str, role: Option<Role>) -> Self {
+        Self {
+            method,
+            prefix: prefix.to_string(),
+            suffix: None,
+            role,
+            pinned: false,
+        }
+    }
+
+    pub fn pinned(mut self) -> Self {
+        self.pinned = true;
+        self
+    }
+
+    pub fn with_suffix(mut self, suffix: &str) -> Self {
+        self.suffix = Some(suffix.to_string());
+        self
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let Some(rest) = path.strip_prefix(self.prefix.as_str()) else {
+            return false;
+        };
+        match &self.suffix {
+            None => true,
+            Some(suffix) => rest.strip_suffix(suffix.as_str())
+                .is_some_and(|segment| !segment.is_empty() && !segment.contains('/')),
+        }
+    }
+
+    /// Parse `[METHOD ]prefix=role`, where role is device, merchant, admin or public
+    fn parse(entry: &str) -> Option<Self> {
+        let (route, role) = entry.split_once('=')?;
+        let role = match role.trim() {
+            "public" => None,
+            other => Some(Role::parse(other)?),
+        };
+        let (method, prefix) = match route.trim().split_once(' ') {
+            Some((method, prefix)) => (Some(Method::from_bytes(method.trim().to_uppercase().as_bytes()).ok()?), prefix.trim()),
+            None => (None, route.trim()),
+        };
+        prefix.starts_with('/').then(|| Self::new(method, prefix, role))
+    }
+}
+
+/// Per-route role requirements, matched against the route pattern. The
+/// most specific rule wins: the longest matching prefix, and a rule naming
+/// the method over one that doesn't. A matching pinned rule beats any
+/// unpinned one.
+#[derive(Debug, Clone)]
+pub struct RoutePolicy {
+    /// Requirement for routes no rule matches
+    pub default_role: Option<Role>,
+    pub rules: Vec<RouteRule>,
+}
+
+impl RoutePolicy {
+    /// The public listener: open by default, with wallet and merchant data
+    /// behind roles. Extended by `API_ROUTE_ROLES`.
+    pub fn public_api() -> Self {
+        Self {
+            default_role: None,
+            rules: vec![
+                RouteRule::new(Some(Method::GET), "/api/devices", Some(Role::Admin)),
+                RouteRule::new(Some(Method::GET), "/api/transactions", Some(Role::Merchant)),
+                // Per-transaction event streams stay open to the submitting wallet
+                RouteRule::new(Some(Method::GET), "/api/transactions/", None).with_suffix("/events"),
+                RouteRule::new(Some(Method::GET), "/api/transactions/user", Some(Role::Merchant)),
+            ],
+        }
+        .with_env_rules("API_ROUTE_ROLES")
+    }
+
+    /// The admin listener: admin only unless `ADMIN_ROUTE_ROLES` opens a
+    /// route to another role. Destructive operations stay admin only even
+    /// when their prefix is opened.
+    pub fn admin_api() -> Self {
+        Self {
+            default_role: Some(Role::Admin),
+            rules: vec![
+                RouteRule::new(Some(Method::DELETE), "/api/audit", Some(Role::Admin)).pinned(),
+                RouteRule::new(Some(Method::POST), "/api/config", Some(Role::Admin)).pinned(),
+                RouteRule::new(Some(Method::POST), "/api/backup/restore", Some(Role::Admin)).pinned(),
+                RouteRule::new(Some(Method::DELETE), "/api/backup", Some(Role::Admin)).pinned(),
+                RouteRule::new(None, "/api/admin", Some(Role::Admin)),
+            ],
+        }
+        .with_env_rules("ADMIN_ROUTE_ROLES")
+    }
+
+    /// Add rules from `var`: comma-separated `[METHOD ]prefix=role` entries.
+    /// Entries that would replace a pinned rule are ignored.
+    fn with_env_rules(mut self, var: &str) -> Self {
+        if let Ok(value) = std::env::var(var) {
+            for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                match RouteRule::parse(entry) {
+                    Some(rule) if self.rules.iter().any(|r| r.pinned && r.method == rule.method && r.prefix == rule.prefix) => {
+                        log::warn!("Ignoring {} entry {}: the route is pinned", var, entry);
+                    }
+                    Some(rule) => {
+                        self.rules.retain(|r| !(r.method == rule.method && r.prefix == rule.prefix && r.suffix.is_none()));
+                        self.rules.push(rule);
+                    }
+                    None => log::warn!("Ignoring malformed {} entry: {}", var, entry),
+                }
+            }
+        }
+        self
+    }
+
+    pub fn required_role(&self, method: &Method, path: &str) -> Option<Role> {
+        self.rules.iter()
+            .filter(|rule| rule.matches(path))
+            .filter(|rule| rule.method.as_ref().is_none_or(|m| m == method))
+            .max_by_key(|rule| (rule.pinned, rule.prefix.len(), rule.method.is_some()))
+            .map_or(self.default_role, |rule| rule.role)
+    }
+}
+
+/// Enforces a `RoutePolicy`: callers present a bearer JWT, whose roles are
+/// checked, or `X-Admin-Key`, which counts as admin
+#[derive(Clone)]
+pub struct AuthorizationMiddleware {
+    policy: Arc<RoutePolicy>,
+}
+
+impl AuthorizationMiddleware {
+    pub fn new(policy: Arc<RoutePolicy>) -> Self {
+        Self { policy }
+    }
+}
+
+impl<S> Transform<S, ServiceRequest> for AuthorizationMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<BoxBody>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Transform = AuthorizationService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuthorizationService {
+            service: Arc::new(service),
+            policy: Arc::clone(&self.policy),
+        }))
+    }
+}
+
+pub struct AuthorizationService<S> {
+    service: Arc<S>,
+    policy: Arc<RoutePolicy>,
+}
+
+impl<S> Service<ServiceRequest> for AuthorizationService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<BoxBody>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Arc::clone(&self.service);
+        let Some(pattern) = route_pattern(&req) else {
+            return Box::pin(async move {
+                Ok(req.into_response(ErrorResponseBuilder::not_found("No route matches this path")))
+            });
+        };
+        let required = self.policy.required_role(req.method(), &pattern);
+
+        Box::pin(async move {
+            let Some(required) = required else {
+                return service.call(req).await;
+            };
+
+            let authorization = req.headers().get("Authorization").and_then(|h| h.to_str().ok());
+            let admin_key = req.headers().get("X-Admin-Key").and_then(|h| h.to_str().ok());
+            match auth::authenticate_caller(authorization, admin_key) {
+                Ok(Some(caller)) if caller.has_role(required) => {
+                    if required == Role::Admin {
+                        log::info!("Admin request {} {} by {}", req.method(), req.path(), caller.subject);
+                    }
+                    service.call(req).await
+                }
+                Ok(Some(caller)) => {
+                    log::warn!("Refused {} {} to {}: {} role required", req.method(), req.path(), caller.subject, required.as_str());
+                    let message = format!("This endpoint requires the {} role", required.as_str());
+                    Ok(req.into_response(ErrorResponseBuilder::forbidden(&message)))
+                }
+                Ok(None) => {
+                    let message = format!("Credentials with the {} role required", required.as_str());
+                    Ok(req.into_response(ErrorResponseBuilder::unauthorized(&message)))
+                }
+                Err(e) => {
+                    log::warn!("Rejected request {} {}: {}", req.method(), req.path(), e);
+                    Ok(req.into_response(ErrorResponseBuilder::unauthorized(&e)))
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_most_specific_rule_wins() {
+        let mut policy = RoutePolicy {
+            default_role: Some(Role::Admin),
+            rules: vec![RouteRule::new(Some(Method::DELETE), "/api/audit", Some(Role::Admin))],
+        };
+        policy.rules.push(RouteRule::parse("/api/audit=merchant").unwrap());
+        policy.rules.push(RouteRule::parse("GET /api/audit/stats=public").unwrap());
+
+        assert_eq!(policy.required_role(&Method::GET, "/api/audit/events"), Some(Role::Merchant));
+        assert_eq!(policy.required_role(&Method::DELETE, "/api/audit/events"), Some(Role::Admin));
+        assert_eq!(policy.required_role(&Method::GET, "/api/audit/stats"), None);
+        assert_eq!(policy.required_role(&Method::POST, "/api/config/import"), Some(Role::Admin));
+        assert!(RouteRule::parse("GET /api/x=owner").is_none());
+    }
+
+    #[test]
+    fn test_only_event_streams_are_open_under_transactions() {
+        let policy = RoutePolicy::public_api();
+        assert_eq!(policy.required_role(&Method::GET, "/api/transactions/abc123/events"), None);
+        assert_eq!(policy.required_role(&Method::GET, "/api/transactions/abc123"), Some(Role::Merchant));
+        assert_eq!(policy.required_role(&Method::GET, "/api/transactions/abc123/receipt"), Some(Role::Merchant));
+        assert_eq!(policy.required_role(&Method::GET, "/api/transactions/a/b/events"), Some(Role::Merchant));
+        assert_eq!(policy.required_role(&Method::GET, "/api/transactions//events"), Some(Role::Merchant));
+        assert_eq!(policy.required_role(&Method::GET, "/api/transactions/{transaction_id}/events"), None);
+    }
+
+    #[test]
+    fn test_pinned_rules_survive_env_overrides() {
+        std::env::set_var("TEST_PINNED_ROUTE_ROLES", "DELETE /api/audit=merchant,DELETE /api/audit/events=merchant,GET /api/audit=merchant");
+        let policy = RoutePolicy {
+            default_role: Some(Role::Admin),
+            rules: vec![RouteRule::new(Some(Method::DELETE), "/api/audit", Some(Role::Admin)).pinned()],
+        }
+        .with_env_rules("TEST_PINNED_ROUTE_ROLES");
+        std::env::remove_var("TEST_PINNED_ROUTE_ROLES");
+
+        assert_eq!(policy.required_role(&Method::DELETE, "/api/audit"), Some(Role::Admin));
+        assert_eq!(policy.required_role(&Method::DELETE, "/api/audit/events"), Some(Role::Admin));
+        assert_eq!(policy.required_role(&Method::GET, "/api/audit/events"), Some(Role::Merchant));
+    }
+
+    #[actix_web::test]
+    async fn test_percent_encoded_admin_path_is_not_let_through() {
+        use actix_web::{test, web, App, HttpResponse};
+
+        let policy = Arc::new(RoutePolicy {
+            default_role: None,
+            rules: vec![RouteRule::new(Some(Method::GET), "/api/devices", Some(Role::Admin))],
+        });
+        let app = test::init_service(App::new().service(
+            web::scope("/api")
+                .wrap(AuthorizationMiddleware::new(policy))
+                .route("/devices", web::get().to(HttpResponse::Ok))
+                .route("/status", web::get().to(HttpResponse::Ok)),
+        )).await;
+
+        for path in ["/api/devices", "/api/%64evices", "/api/%64%65vices"] {
+            let response = test::call_service(&app, test::TestRequest::get().uri(path).to_request()).await;
+            assert!(!response.status().is_success(), "{path} reached the admin handler");
+        }
+        let response = test::call_service(&app, test::TestRequest::get().uri("/api/status").to_request()).await;
+        assert!(response.status().is_success());
+    }
+}
@@ -0,0 +1,279 @@
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use actix_web::body::BoxBody;
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use rand::Rng;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use crate::domain::auth::constant_time_eq;
+use crate::infrastructure::ble::session::hmac_sha256;
+use crate::middleware::error_handling::ErrorResponseBuilder;
+use crate::middleware::route_pattern;
+use crate::middleware::replay_protection::{NonceCache, NonceCheck};
+
+pub const CHALLENGE_HEADER: &str = "X-PoW-Challenge";
+pub const SOLUTION_HEADER: &str = "X-PoW-Nonce";
+
+/// Unauthenticated endpoints that hand out credentials, as route patterns
+pub const DEFAULT_CHALLENGE_PATHS: [&str; 3] = [
+    "/api/auth/token",
+    "/api/auth/certificate",
+    "/api/devices/enroll",
+];
+
+#[derive(Debug, Clone)]
+pub struct ChallengeConfig {
+    pub enabled: bool,
+    /// Leading zero bits required in `sha256("<challenge>:<nonce>")`
+    pub difficulty: u32,
+    pub ttl: Duration,
+    pub protected_paths: Vec<String>,
+}
+
+impl Default for ChallengeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            difficulty: 20,
+            ttl: Duration::from_secs(120),
+            protected_paths: DEFAULT_CHALLENGE_PATHS.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+}
+
+impl ChallengeConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("POW_CHALLENGE_ENABLED")
+                .map(|v| v == "true")
+                .unwrap_or(defaults.enabled),
+            difficulty: std::env::var("POW_DIFFICULTY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|d: &u32| *d <= 32)
+                .unwrap_or(defaults.difficulty),
+            ttl: std::env::var("POW_CHALLENGE_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.ttl),
+            protected_paths: defaults.protected_paths,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Challenge {
+    pub challenge: String,
+    pub difficulty: u32,
+    pub algorithm: &'static str,
+    pub expires_at: i64,
+}
+
+/// Hashcash-style challenges for the credential endpoints.
+///
+/// Challenges are self-contained (`<expires>.<random>.<mac>`) so issuing one
+/// keeps no state; only redeemed challenges are remembered, until they expire.
+pub struct ProofOfWork {
+    config: ChallengeConfig,
+    secret: [u8; 32],
+    redeemed: NonceCache,
+}
+
+impl ProofOfWork {
+    pub fn new(config: ChallengeConfig) -> Self {
+        Self {
+            config,
+            secret: rand::rng().random(),
+            redeemed: NonceCache::new(),
+        }
+    }
+
+    pub fn config(&self) -> &ChallengeConfig {
+        &self.config
+    }
+
+    pub fn issue(&self) -> Challenge {
+        let expires_at = chrono::Utc::now().timestamp() + self.config.ttl.as_secs() as i64;
+        let random: [u8; 16] = rand::rng().random();
+        let body = format!("{}.{}", expires_at, hex::encode(random));
+        let mac = hex::encode(hmac_sha256(&self.secret, body.as_bytes()));
+        Challenge {
+            challenge: format!("{body}.{mac}"),
+            difficulty: self.config.difficulty,
+            algorithm: "sha256",
+            expires_at,
+        }
+    }
+
+    /// Check a solution and mark its challenge as used
+    pub async fn redeem(&self, challenge: &str, nonce: &str) -> Result<(), &'static str> {
+        let (body, mac) = challenge.rsplit_once('.').ok_or("Malformed challenge")?;
+        let mac = hex::decode(mac).map_err(|_| "Malformed challenge")?;
+        if !constant_time_eq(&hmac_sha256(&self.secret, body.as_bytes()), &mac) {
+            return Err("Unknown challenge");
+        }
+        let expires_at: i64 = body.split('.').next()
+            .and_then(|t| t.parse().ok())
+            .ok_or("Malformed challenge")?;
+        if expires_at < chrono::Utc::now().timestamp() {
+            return Err("Challenge expired");
+        }
+        if nonce.is_empty() || nonce.len() > 64 || !meets_difficulty(challenge, nonce, self.config.difficulty) {
+            return Err("Insufficient proof of work");
+        }
+        match self.redeemed.check_and_insert(challenge.to_string(), self.config.ttl, 100_000).await {
+            NonceCheck::Fresh => Ok(()),
+            NonceCheck::Replayed => Err("Challenge already used"),
+            NonceCheck::Full => Err("Too many challenges outstanding"),
+        }
+    }
+}
+
+pub fn meets_difficulty(challenge: &str, nonce: &str, difficulty: u32) -> bool {
+    let digest = Sha256::digest(format!("{challenge}:{nonce}").as_bytes());
+    let mut zeros = 0;
+    for byte in digest.iter() {
+        if *byte == 0 {
+            zeros += 8;
+            continue;
+        }
+        zeros += byte.leading_zeros();
+        break;
+    }
+    zeros >= difficulty
+}
+
+/// Requires a solved challenge on the configured paths when enabled
+#[derive(Clone)]
+pub struct ChallengeGateMiddleware {
+    pow: Arc<ProofOfWork>,
+}
+
+impl ChallengeGateMiddleware {
+    pub fn new(pow: Arc<ProofOfWork>) -> Self {
+        Self { pow }
+    }
+}
+
+impl<S> Transform<S, ServiceRequest> for ChallengeGateMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<BoxBody>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Transform = ChallengeGateService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ChallengeGateService {
+            service: Arc::new(service),
+            pow: Arc::clone(&self.pow),
+        }))
+    }
+}
+
+pub struct ChallengeGateService<S> {
+    service: Arc<S>,
+    pow: Arc<ProofOfWork>,
+}
+
+impl<S> Service<ServiceRequest> for ChallengeGateService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<BoxBody>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Arc::clone(&self.service);
+        let pow = Arc::clone(&self.pow);
+
+        Box::pin(async move {
+            let config = pow.config();
+            // Unresolved routes are refused by the authorization middleware
+            let protected = route_pattern(&req)
+                .is_some_and(|pattern| config.protected_paths.iter().any(|p| *p == pattern));
+            if !config.enabled || !protected {
+                return service.call(req).await;
+            }
+
+            let header = |name: &str| req.headers().get(name).and_then(|h| h.to_str().ok()).map(|s| s.to_string());
+            let (challenge, nonce) = match (header(CHALLENGE_HEADER), header(SOLUTION_HEADER)) {
+                (Some(c), Some(n)) => (c, n),
+                _ => {
+                    return Ok(req.into_response(ErrorResponseBuilder::forbidden(
+                        &format!("Solve a challenge from /api/auth/challenge and send {CHALLENGE_HEADER} and {SOLUTION_HEADER}"),
+                    )));
+                }
+            };
+
+            if let Err(reason) = pow.redeem(&challenge, &nonce).await {
+                let peer = req.connection_info().peer_addr().unwrap_or("unknown").to_string();
+                log::warn!("Proof of work rejected from {} on {}: {}", peer, req.path(), reason);
+                return Ok(req.into_response(ErrorResponseBuilder::forbidden(reason)));
+            }
+
+            service.call(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solve(challenge: &str, difficulty: u32) -> String {
+        (0u64..).map(|n| n.to_string()).find(|n| meets_difficulty(challenge, n, difficulty)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_solved_challenge_redeems_once() {
+        let pow = ProofOfWork::new(ChallengeConfig { enabled: true, difficulty: 8, ..Default::default() });
+        let challenge = pow.issue();
+        let nonce = solve(&challenge.challenge, 8);
+
+        assert!(pow.redeem(&challenge.challenge, &nonce).await.is_ok());
+        assert_eq!(pow.redeem(&challenge.challenge, &nonce).await, Err("Challenge already used"));
+    }
+
+    #[tokio::test]
+    async fn test_forged_challenge_rejected() {
+        let pow = ProofOfWork::new(ChallengeConfig { enabled: true, difficulty: 0, ..Default::default() });
+        let forged = format!("{}.00.{}", chrono::Utc::now().timestamp() + 60, "0".repeat(64));
+        assert_eq!(pow.redeem(&forged, "1").await, Err("Unknown challenge"));
+        let not_hex = format!("{}.00.{}", chrono::Utc::now().timestamp() + 60, "zz");
+        assert_eq!(pow.redeem(&not_hex, "1").await, Err("Malformed challenge"));
+    }
+
+    #[actix_web::test]
+    async fn test_percent_encoded_path_still_needs_a_solution() {
+        use actix_web::{test, web, App, HttpResponse};
+
+        let pow = Arc::new(ProofOfWork::new(ChallengeConfig { enabled: true, difficulty: 8, ..Default::default() }));
+        let app = test::init_service(App::new().service(
+            web::scope("/api")
+                .wrap(ChallengeGateMiddleware::new(pow))
+                .route("/auth/token", web::post().to(HttpResponse::Ok)),
+        )).await;
+
+        for path in ["/api/auth/token", "/api/auth/%74oken"] {
+            let response = test::call_service(&app, test::TestRequest::post().uri(path).to_request()).await;
+            assert!(!response.status().is_success(), "{path} skipped the challenge");
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use actix_tls::accept::openssl::TlsStream;
+use actix_web::dev::Extensions;
+use actix_web::rt::net::TcpStream;
+use actix_web::HttpRequest;
+use openssl::x509::X509;
+use std::any::Any;
+use crate::infrastructure::pki::{DeviceCa, DeviceCertificate};
+
+/// Header carrying the URL-encoded PEM client certificate from a TLS-terminating
+/// proxy (e.g. nginx `$ssl_client_escaped_cert`)
+pub const CLIENT_CERT_HEADER: &str = "X-Client-Cert";
+
+/// Client certificate presented during the TLS handshake
+#[derive(Clone)]
+pub struct PeerCertificate(pub X509);
+
+/// `HttpServer::on_connect` hook that keeps the verified peer certificate
+/// with the connection
+pub fn capture_peer_certificate(connection: &dyn Any, data: &mut Extensions) {
+    if let Some(stream) = connection.downcast_ref::<TlsStream<TcpStream>>() {
+        if let Some(cert) = stream.ssl().peer_certificate() {
+            data.insert(PeerCertificate(cert));
+        }
+    }
+}
+
+/// Client certificate for this request: from the TLS connection, or from
+/// `X-Client-Cert` when `TRUST_PROXY_CLIENT_CERT=true`. Only enable the latter
+/// behind a proxy that verifies client certificates and strips the header
+/// from incoming requests.
+pub fn client_certificate(req: &HttpRequest) -> Option<X509> {
+    if let Some(PeerCertificate(cert)) = req.conn_data::<PeerCertificate>() {
+        return Some(cert.clone());
+    }
+    if std::env::var("TRUST_PROXY_CLIENT_CERT").map(|v| v == "true").unwrap_or(false) {
+        let header = req.headers().get(CLIENT_CERT_HEADER)?.to_str().ok()?;
+        return X509::from_pem(percent_decode(header).as_bytes()).ok();
+    }
+    None
+}
+
+/// Device identity from a valid client certificate, if one was presented
+pub fn device_identity(req: &HttpRequest, ca: &DeviceCa) -> Option<DeviceCertificate> {
+    let cert = client_certificate(req)?;
+    match ca.verify(&cert) {
+        Ok(identity) => Some(identity),
+        Err(e) => {
+            log::warn!("Rejected client certificate: {}", e);
+            None
+        }
+    }
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let decoded = std::str::from_utf8(&bytes[i + 1..i + 3])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(byte) = decoded {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -8,6 +8,7 @@ use actix_web::body::BoxBody;
 use std::marker::PhantomData;
 use crate::middleware::error_handling::{is_critical_endpoint, get_component_from_path};
 use crate::utils::error_handler::{EnhancedErrorHandler, ErrorRecord, ErrorType, ErrorSeverity, CriticalPath};
+use crate::utils::request_id;
 use chrono::Utc;
 use serde_json::json;
 use actix_service::forward_ready;
@@ -143,7 +144,7 @@ where
                                     "error": "Critical system error",
                                     "message": "A critical system error occurred",
                                     "timestamp": Utc::now().to_rfc3339(),
-                                    "request_id": uuid::Uuid::new_v4().to_string(),
+                                    "request_id": request_id::current_or_new(),
                                 }))
                         }
                         _ => {
@@ -152,7 +153,7 @@ where
                                     "error": "System error",
                                     "message": "A system error occurred",
                                     "timestamp": Utc::now().to_rfc3339(),
-                                    "request_id": uuid::Uuid::new_v4().to_string(),
+                                    "request_id": request_id::current_or_new(),
                                 }))
                         }
                     };
@@ -5,6 +5,7 @@ use actix_web::{
 };
 use std::sync::Arc;
 use crate::utils::error_handler::{ErrorType, ErrorSeverity, ErrorRecord, EnhancedErrorHandler, CriticalPath};
+use crate::utils::request_id;
 use futures_util::future::{LocalBoxFuture, Ready, ready};
 use serde_json::json;
 use chrono::Utc;
@@ -151,7 +152,7 @@ where
                                     "error": "Internal server error",
                                     "message": "A critical error occurred",
                                     "timestamp": Utc::now().to_rfc3339(),
-                                    "request_id": uuid::Uuid::new_v4().to_string(),
+                                    "request_id": request_id::current_or_new(),
                                 }))
                         }
                         ErrorSeverity::High => {
@@ -161,7 +162,7 @@ where
                                     "error": "Service error",
                                     "message": "A high severity error occurred",
                                     "timestamp": Utc::now().to_rfc3339(),
-                                    "request_id": uuid::Uuid::new_v4().to_string(),
+                                    "request_id": request_id::current_or_new(),
                                 }))
                         }
                         ErrorSeverity::Medium => {
@@ -171,7 +172,7 @@ where
                                     "error": "Request error",
                                     "message": "A medium severity error occurred",
                                     "timestamp": Utc::now().to_rfc3339(),
-                                    "request_id": uuid::Uuid::new_v4().to_string(),
+                                    "request_id": request_id::current_or_new(),
                                 }))
                         }
                         ErrorSeverity::Low => {
@@ -181,7 +182,7 @@ where
                                     "error": "Request error",
                                     "message": "A low severity error occurred",
                                     "timestamp": Utc::now().to_rfc3339(),
-                                    "request_id": uuid::Uuid::new_v4().to_string(),
+                                    "request_id": request_id::current_or_new(),
                                 }))
                         }
                         ErrorSeverity::Fatal => {
@@ -191,7 +192,7 @@ where
                                     "error": "Fatal error occurred",
                                     "message": "A fatal error occurred",
                                     "timestamp": Utc::now().to_rfc3339(),
-                                    "request_id": uuid::Uuid::new_v4().to_string(),
+                                    "request_id": request_id::current_or_new(),
                                 }))
                         }
                     };
@@ -256,14 +257,14 @@ pub async fn global_error_handler(error: Error) -> HttpResponse {
             "error": "Internal server error",
             "message": error_msg,
             "timestamp": Utc::now().to_rfc3339(),
-            "request_id": uuid::Uuid::new_v4().to_string(),
+            "request_id": request_id::current_or_new(),
         })
     } else {
         json!({
             "error": "Internal server error",
             "message": "An unexpected error occurred",
             "timestamp": Utc::now().to_rfc3339(),
-            "request_id": uuid::Uuid::new_v4().to_string(),
+            "request_id": request_id::current_or_new(),
         })
     };
 
@@ -279,7 +280,7 @@ impl ErrorResponseBuilder {
             "error": "Bad request",
             "message": message,
             "timestamp": Utc::now().to_rfc3339(),
-            "request_id": uuid::Uuid::new_v4().to_string(),
+            "request_id": request_id::current_or_new(),
         }))
     }
 
@@ -288,7 +289,7 @@ impl ErrorResponseBuilder {
             "error": "Unauthorized",
             "message": message,
             "timestamp": Utc::now().to_rfc3339(),
-            "request_id": uuid::Uuid::new_v4().to_string(),
+            "request_id": request_id::current_or_new(),
         }))
     }
 
@@ -297,7 +298,7 @@ impl ErrorResponseBuilder {
             "error": "Forbidden",
             "message": message,
             "timestamp": Utc::now().to_rfc3339(),
-            "request_id": uuid::Uuid::new_v4().to_string(),
+            "request_id": request_id::current_or_new(),
         }))
     }
 
@@ -306,7 +307,7 @@ impl ErrorResponseBuilder {
             "error": "Not found",
             "message": message,
             "timestamp": Utc::now().to_rfc3339(),
-            "request_id": uuid::Uuid::new_v4().to_string(),
+            "request_id": request_id::current_or_new(),
         }))
     }
 
@@ -315,7 +316,7 @@ impl ErrorResponseBuilder {
             "error": "Internal server error",
             "message": message,
             "timestamp": Utc::now().to_rfc3339(),
-            "request_id": uuid::Uuid::new_v4().to_string(),
+            "request_id": request_id::current_or_new(),
         }))
     }
 
@@ -324,7 +325,7 @@ impl ErrorResponseBuilder {
             "error": "Service unavailable",
             "message": message,
             "timestamp": Utc::now().to_rfc3339(),
-            "request_id": uuid::Uuid::new_v4().to_string(),
+            "request_id": request_id::current_or_new(),
         }))
     }
 
@@ -336,7 +337,7 @@ impl ErrorResponseBuilder {
                 "message": message,
                 "retry_after": retry_after,
                 "timestamp": Utc::now().to_rfc3339(),
-                "request_id": uuid::Uuid::new_v4().to_string(),
+                "request_id": request_id::current_or_new(),
             }))
     }
 }
@@ -61,6 +61,7 @@ pub struct ValidationConfig {
     pub enable_xss_check: bool,
     pub enable_command_injection_check: bool,
     pub enable_path_traversal_check: bool,
+    pub body_limits: BodyLimits,
 }
 
 impl Default for ValidationConfig {
@@ -73,6 +74,7 @@ impl Default for ValidationConfig {
             enable_xss_check: true,
             enable_command_injection_check: true,
             enable_path_traversal_check: true,
+            body_limits: BodyLimits::default(),
         }
     }
 }
@@ -292,4 +294,175 @@ fn validate_json_value(value: &serde_json::Value, config: &ValidationConfig) ->
         }
         _ => Ok(()),
     }
-} 
\ No newline at end of file
+} 
+/// Size and shape limits for request bodies on one route
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyLimit {
+    pub max_bytes: usize,
+    /// Deepest nesting of JSON objects and arrays
+    pub max_depth: usize,
+    /// Most elements in any one JSON array
+    pub max_array_len: usize,
+}
+
+impl BodyLimit {
+    /// Parse `bytes:depth:array_len`
+    fn parse(spec: &str) -> Option<Self> {
+        let mut parts = spec.split(':').map(|p| p.trim().parse::<usize>());
+        let limit = Self {
+            max_bytes: parts.next()?.ok()?,
+            max_depth: parts.next()?.ok()?,
+            max_array_len: parts.next()?.ok()?,
+        };
+        parts.next().is_none().then_some(limit)
+    }
+}
+
+/// Body limits by route pattern. Bodies are measured after any
+/// `Content-Encoding` is undone, so a small compressed body cannot expand
+/// past its route's limit.
+#[derive(Debug, Clone)]
+pub struct BodyLimits {
+    pub default: BodyLimit,
+    /// Keyed by the pattern a request resolves to (`route_pattern`), so an
+    /// encoded or padded path gets its route's limit
+    pub routes: Vec<(String, BodyLimit)>,
+}
+
+impl Default for BodyLimits {
+    fn default() -> Self {
+        let transaction = BodyLimit { max_bytes: 64 * 1024, max_depth: 16, max_array_len: 256 };
+        Self {
+            default: BodyLimit { max_bytes: 1024 * 1024, max_depth: 32, max_array_len: 1000 },
+            routes: vec![
+                ("/api/send_tx".to_string(), transaction),
+                ("/api/send_compressed_tx".to_string(), transaction),
+                ("/api/simple_send_tx".to_string(), transaction),
+                ("/api/submit_transaction".to_string(), transaction),
+                ("/api/v1/submit-transaction".to_string(), transaction),
+                ("/api/validate".to_string(), transaction),
+                // Peer manifests carry whole batches of transactions
+                ("/api/federation/ingest".to_string(), BodyLimit { max_bytes: 4 * 1024 * 1024, max_depth: 32, max_array_len: 10_000 }),
+            ],
+        }
+    }
+}
+
+impl BodyLimits {
+    pub fn from_env() -> Self {
+        let mut limits = Self::default();
+        let env_usize = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<usize>().ok()).filter(|v| *v > 0);
+        if let Some(max_bytes) = env_usize("BODY_MAX_BYTES") {
+            limits.default.max_bytes = max_bytes;
+        }
+        if let Some(max_depth) = env_usize("JSON_MAX_DEPTH") {
+            limits.default.max_depth = max_depth;
+        }
+        if let Some(max_array_len) = env_usize("JSON_MAX_ARRAY_LEN") {
+            limits.default.max_array_len = max_array_len;
+        }
+        // BODY_LIMITS=/api/send_tx=65536:16:256;/api/federation/ingest=4194304:32:10000
+        if let Ok(routes) = std::env::var("BODY_LIMITS") {
+            for entry in routes.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+                match entry.split_once('=').and_then(|(pattern, spec)| Some((pattern.trim(), BodyLimit::parse(spec)?))) {
+                    Some((pattern, limit)) => {
+                        limits.routes.retain(|(p, _)| p != pattern);
+                        limits.routes.push((pattern.to_string(), limit));
+                    }
+                    None => log::warn!("Ignoring malformed BODY_LIMITS entry: {}", entry),
+                }
+            }
+        }
+        limits
+    }
+
+    /// Limit for a request resolving to `pattern`; unresolved requests get
+    /// the default
+    pub fn for_route(&self, pattern: Option<&str>) -> BodyLimit {
+        pattern
+            .and_then(|pattern| self.routes.iter().find(|(p, _)| p == pattern))
+            .map(|(_, limit)| *limit)
+            .unwrap_or(self.default)
+    }
+}
+
+/// Check a JSON body's nesting depth and array lengths by scanning its
+/// bytes, so hostile documents are refused before a parser recurses into
+/// them. Malformed JSON is left for the parser to reject.
+pub fn check_json_shape(body: &[u8], limit: &BodyLimit) -> Result<(), String> {
+    // Elements seen so far in each open array; None for objects
+    let mut open: Vec<Option<usize>> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    // The next value starts a new array element
+    let mut element_pending = false;
+
+    for &byte in body {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        if byte.is_ascii_whitespace() {
+            continue;
+        }
+        if element_pending && byte != b']' {
+            if let Some(Some(elements)) = open.last_mut() {
+                *elements += 1;
+                if *elements > limit.max_array_len {
+                    return Err(format!("JSON array exceeds {} elements", limit.max_array_len));
+                }
+            }
+            element_pending = false;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                open.push((byte == b'[').then_some(0));
+                if open.len() > limit.max_depth {
+                    return Err(format!("JSON nesting exceeds depth {}", limit.max_depth));
+                }
+                element_pending = byte == b'[';
+            }
+            b']' | b'}' => {
+                open.pop();
+                element_pending = false;
+            }
+            b',' => element_pending = matches!(open.last(), Some(Some(_))),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_shape_limits() {
+        let limit = BodyLimit { max_bytes: 1024, max_depth: 3, max_array_len: 3 };
+        assert!(check_json_shape(br#"{"a": [1, {"b": "[[[[,,,,"}, [2, 3]]}"#, &limit).is_ok());
+        assert!(check_json_shape(br#"{"a": [[[1]]]}"#, &limit).is_err());
+        assert!(check_json_shape(br#"[1, 2, 3, 4]"#, &limit).is_err());
+        assert!(check_json_shape(br#"[[1, 2, 3], [4, 5, 6], []]"#, &limit).is_ok());
+        assert!(check_json_shape(br#"["a\"]", "b"]"#, &limit).is_ok());
+
+        let limits = BodyLimits::default();
+        assert_eq!(limits.for_route(Some("/api/send_tx")).max_bytes, 64 * 1024);
+        assert_eq!(limits.for_route(Some("/api/transactions")), limits.default);
+        assert_eq!(limits.for_route(Some("/api/send_tx_batch")), limits.default);
+        assert_eq!(limits.for_route(None), limits.default);
+    }
+
+    #[test]
+    fn test_compressed_submit_has_the_transaction_limit() {
+        let limits = BodyLimits::default();
+        assert_eq!(limits.for_route(Some("/api/send_compressed_tx")), limits.for_route(Some("/api/send_tx")));
+        assert_ne!(limits.for_route(Some("/api/send_compressed_tx")), limits.default);
+    }
+}
@@ -8,6 +8,10 @@ use std::time::Instant;
 use futures_util::future::{LocalBoxFuture, Ready};
 use actix_web::body::BoxBody;
 use futures_util::future::ready;
+use actix_web::body::{BodySize, MessageBody};
+use crate::domain::auth;
+use crate::middleware::is_submission;
+use crate::utils::request_id;
 use crate::infrastructure::monitoring::manager::MonitoringManager;
 use std::marker::PhantomData;
 
@@ -74,7 +78,22 @@ where
             // Record request details
             let path = req.path().to_string();
             let method = req.method().to_string();
+            // Use the route template so IDs in the path don't explode label cardinality
+            let route = req.match_pattern().unwrap_or_else(|| "unmatched".to_string());
             let client_ip = req.connection_info().peer_addr().unwrap_or("unknown").to_string();
+            // Authenticated devices are tracked by token subject, anonymous ones by address
+            let device_id = req.headers().get("Authorization")
+                .and_then(|h| h.to_str().ok())
+                .and_then(|h| h.strip_prefix("Bearer "))
+                .and_then(|token| auth::verify_jwt_token(token.trim()).ok())
+                .map(|claims| claims.sub)
+                .unwrap_or_else(|| format!("ip:{client_ip}"));
+            request_id::record_device(&device_id);
+            let bytes_in = req.headers().get("Content-Length")
+                .and_then(|h| h.to_str().ok())
+                .and_then(|s| s.parse::<u64>().ok())
+                .unwrap_or(0);
+            let is_submission = is_submission(&req);
 
             // Call the inner service
             let fut = service.call(req);
@@ -88,6 +107,12 @@ where
             match res {
                 Ok(res) => {
                     let status = res.status();
+                    monitoring_manager.record_route_request(&method, &route, status.as_u16(), response_time_ms).await;
+                    let bytes_out = match res.response().body().size() {
+                        BodySize::Sized(size) => size,
+                        _ => 0,
+                    };
+                    monitoring_manager.record_device_request(&device_id, is_submission, status.as_u16(), bytes_in, bytes_out).await;
                     // Increment appropriate metrics based on status
                     if status.is_success() {
                         monitoring_manager.increment_metric("requests_successful").await;
@@ -116,7 +141,8 @@ where
                     }
                     // Log request details for monitoring
                     log::info!(
-                        "Request processed: {method} {path} - Status: {status} - Time: {response_time_ms}ms - IP: {client_ip}"
+                        "Request processed: {method} {path} - Status: {status} - Time: {response_time_ms}ms - IP: {client_ip} - Request: {}",
+                        request_id::current_or_new()
                     );
                     Ok(res)
                 }
@@ -124,8 +150,12 @@ where
                     // Increment error metrics
                     monitoring_manager.increment_metric("requests_failed").await;
                     monitoring_manager.increment_metric("network_errors").await;
+                    let error_status = e.as_response_error().status_code().as_u16();
+                    monitoring_manager.record_route_request(&method, &route, error_status, response_time_ms).await;
+                    monitoring_manager.record_device_request(&device_id, is_submission, error_status, bytes_in, 0).await;
                     log::error!(
-                        "Request failed: {method} {path} - Error: {e} - Time: {response_time_ms}ms - IP: {client_ip}"
+                        "Request failed: {method} {path} - Error: {e} - Time: {response_time_ms}ms - IP: {client_ip} - Request: {}",
+                        request_id::current_or_new()
                     );
                     Err(e)
                 }
@@ -145,4 +175,28 @@ impl MetricsCollector {
     pub fn new(monitoring_manager: Arc<MonitoringManager>) -> Self {
         Self { monitoring_manager }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::monitoring::manager::DeviceMetric;
+    use actix_web::{test, web, App, HttpResponse};
+
+    #[actix_web::test]
+    async fn test_compressed_submit_counts_as_a_submission() {
+        let monitoring = Arc::new(MonitoringManager::new());
+        let app = test::init_service(
+            App::new().service(
+                web::scope("/api")
+                    .wrap(MetricsMiddleware::new(Arc::clone(&monitoring)))
+                    .route("/send_compressed_tx", web::post().to(HttpResponse::Ok)),
+            ),
+        ).await;
+
+        test::call_service(&app, test::TestRequest::post().uri("/api/send_compressed_tx").to_request()).await;
+        let devices = monitoring.top_devices(DeviceMetric::Submissions, 10).await;
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].submissions, 1);
+    }
+}
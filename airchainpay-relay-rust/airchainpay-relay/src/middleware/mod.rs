@@ -11,6 +11,7 @@ use crate::infrastructure::monitoring::manager::MonitoringManager;
 use std::marker::PhantomData;
 use futures_util::future::LocalBoxFuture;
 use futures::task::{Context, Poll};
+use futures::StreamExt;
 
 pub mod error_handling;
 pub mod input_validation;
@@ -18,10 +19,45 @@ pub mod rate_limiting;
 pub mod metrics;
 pub mod security;
 pub mod critical_error_middleware;
+pub mod authorization;
+pub mod replay_protection;
+pub mod request_id;
+pub mod client_cert;
+pub mod challenge;
+pub mod quota;
 
 // Re-export security components
 pub use security::SecurityConfig;
 
+/// Route pattern `req` resolves to, e.g.
+/// `/api/transactions/{transaction_id}/events`. Path-keyed checks match on
+/// this rather than `req.path()`: the router matches the path with `%XX`
+/// decoded, so a raw path like `/api/%64evices` reaches the `/api/devices`
+/// handler without equalling or starting with it. The pattern is looked up
+/// on that same decoded path (`match_info`), not on the raw one
+/// `match_pattern()` uses. `None` when no route resolves; callers refuse
+/// such requests instead of letting them through unchecked.
+pub fn route_pattern(req: &ServiceRequest) -> Option<String> {
+    req.resource_map().match_pattern(req.match_info().as_str())
+}
+
+/// Routes that submit a transaction for broadcast. Quotas, submission
+/// metrics and mandatory replay protection all key on this list, so a new
+/// submission route is registered here once.
+pub const SUBMISSION_ROUTES: [&str; 5] = [
+    "/api/send_tx",
+    "/api/send_compressed_tx",
+    "/api/simple_send_tx",
+    "/api/submit_transaction",
+    "/api/v1/submit-transaction",
+];
+
+/// Whether `req` is a POST to one of the `SUBMISSION_ROUTES`
+pub fn is_submission(req: &ServiceRequest) -> bool {
+    req.method() == actix_web::http::Method::POST
+        && route_pattern(req).is_some_and(|pattern| SUBMISSION_ROUTES.contains(&pattern.as_str()))
+}
+
 // Re-export error handling components
 
 // Re-export critical error middleware
@@ -35,6 +71,13 @@ pub struct EnhancedSecurityConfig {
     pub metrics: MetricsCollector,
 }
 
+impl EnhancedSecurityConfig {
+    pub fn with_body_limits(mut self, body_limits: input_validation::BodyLimits) -> Self {
+        self.input_validation.body_limits = body_limits;
+        self
+    }
+}
+
 impl Default for EnhancedSecurityConfig {
     fn default() -> Self {
         Self {
@@ -105,23 +148,65 @@ where
 
         Box::pin(async move {
             // Apply comprehensive security checks
-            let req = req;
-            
-            // 1. Request size validation
+            // 1. Request size validation, by route
+            let mut req = req;
+            let body_limit = config.input_validation.body_limits.for_route(route_pattern(&req).as_deref());
+            let too_large = |req: ServiceRequest| {
+                req.into_response(
+                    HttpResponse::PayloadTooLarge()
+                        .json(serde_json::json!({
+                            "error": "Request entity too large",
+                            "maxSize": format!("{} bytes", body_limit.max_bytes),
+                            "timestamp": std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs()
+                        }))
+                        .map_into_boxed_body()
+                )
+            };
             if let Some(content_length) = req.headers().get("content-length") {
                 if let Ok(length) = content_length.to_str().unwrap_or("0").parse::<usize>() {
-                    if length > config.security.request_size_limit {
+                    if length > body_limit.max_bytes {
+                        return Ok(too_large(req));
+                    }
+                }
+            }
+
+            // Buffer the body, decoded, so chunked and compressed bodies are
+            // held to the same limit and JSON shape is checked before any
+            // handler parses it
+            if !matches!(*req.method(), actix_web::http::Method::GET | actix_web::http::Method::HEAD) {
+                let payload = req.take_payload();
+                let mut payload = actix_web::dev::Decompress::from_headers(payload, req.headers());
+                let mut body = bytes::BytesMut::new();
+                while let Some(chunk) = payload.next().await {
+                    let chunk = chunk?;
+                    if body.len() + chunk.len() > body_limit.max_bytes {
+                        return Ok(too_large(req));
+                    }
+                    body.extend_from_slice(&chunk);
+                }
+
+                let is_json = req.headers().get("content-type")
+                    .and_then(|ct| ct.to_str().ok())
+                    .is_some_and(|ct| ct.contains("json"));
+                if is_json {
+                    if let Err(shape_error) = input_validation::check_json_shape(&body, &body_limit) {
                         return Ok(req.into_response(
                             HttpResponse::PayloadTooLarge()
                                 .json(serde_json::json!({
-                                    "error": "Request entity too large",
-                                    "maxSize": format!("{}MB", config.security.request_size_limit / 1024 / 1024),
+                                    "error": "Request body too complex",
+                                    "message": shape_error,
                                     "timestamp": std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs()
                                 }))
                                 .map_into_boxed_body()
                         ));
                     }
                 }
+
+                // Handlers now see the decoded body
+                let headers = req.headers_mut();
+                headers.remove(actix_web::http::header::CONTENT_ENCODING);
+                headers.insert(actix_web::http::header::CONTENT_LENGTH, actix_web::http::header::HeaderValue::from(body.len()));
+                req.set_payload(actix_web::dev::Payload::from(body.freeze()));
             }
 
             // 2. Content type validation
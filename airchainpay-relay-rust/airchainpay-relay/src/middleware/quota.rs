@@ -0,0 +1,428 @@
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    Error, HttpResponse,
+};
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use crate::domain::auth;
+use crate::infrastructure::storage::file_storage::{QuotaLogEntry, QuotaUsage, Storage};
+use crate::middleware::is_submission;
+use crate::utils::request_id;
+
+/// Submission quotas; a limit of 0 means unlimited. Usage is counted
+/// either way so it can be inspected before limits are switched on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuotaConfig {
+    pub device_daily: u64,
+    pub device_monthly: u64,
+    pub api_key_daily: u64,
+    pub api_key_monthly: u64,
+}
+
+impl QuotaConfig {
+    pub fn from_env() -> Self {
+        let limit = |name: &str| std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(0);
+        Self {
+            device_daily: limit("QUOTA_DEVICE_DAILY"),
+            device_monthly: limit("QUOTA_DEVICE_MONTHLY"),
+            api_key_daily: limit("QUOTA_API_KEY_DAILY"),
+            api_key_monthly: limit("QUOTA_API_KEY_MONTHLY"),
+        }
+    }
+}
+
+/// Who a submission is counted against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuotaSubject {
+    Device(String),
+    /// Id of the API key a token was issued for, from its `key_id` claim
+    ApiKey(String),
+}
+
+impl QuotaSubject {
+    fn storage_key(&self) -> String {
+        match self {
+            QuotaSubject::Device(id) => format!("device:{}", id),
+            QuotaSubject::ApiKey(hash) => format!("api_key:{}", hash),
+        }
+    }
+
+    fn limits(&self, config: &QuotaConfig) -> (u64, u64) {
+        match self {
+            QuotaSubject::Device(_) => (config.device_daily, config.device_monthly),
+            QuotaSubject::ApiKey(_) => (config.api_key_daily, config.api_key_monthly),
+        }
+    }
+}
+
+/// Usage against one limit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaPeriod {
+    pub used: u64,
+    /// 0 when unlimited
+    pub limit: u64,
+    pub remaining: Option<u64>,
+    pub resets_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaReport {
+    pub subject: String,
+    pub daily: QuotaPeriod,
+    pub monthly: QuotaPeriod,
+}
+
+/// A submission rejected for exceeding a quota
+#[derive(Debug, Clone)]
+pub struct QuotaExceeded {
+    pub subject: String,
+    pub period: &'static str,
+    pub limit: u64,
+    pub resets_at: DateTime<Utc>,
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} quota of {} submissions exceeded for {}", self.period, self.limit, self.subject)
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+fn day_key(now: DateTime<Utc>) -> String {
+    now.format("%Y-%m-%d").to_string()
+}
+
+fn month_key(now: DateTime<Utc>) -> String {
+    now.format("%Y-%m").to_string()
+}
+
+fn next_day(now: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&(now.date_naive() + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap())
+}
+
+fn next_month(now: DateTime<Utc>) -> DateTime<Utc> {
+    let (year, month) = if now.month() == 12 { (now.year() + 1, 1) } else { (now.year(), now.month() + 1) };
+    Utc.from_utc_datetime(&NaiveDate::from_ymd_opt(year, month, 1).unwrap().and_hms_opt(0, 0, 0).unwrap())
+}
+
+/// Counts in `usage` that still apply at `now`
+fn current_counts(usage: Option<&QuotaUsage>, now: DateTime<Utc>) -> (u64, u64) {
+    match usage {
+        Some(usage) => (
+            if usage.day == day_key(now) { usage.daily_count } else { 0 },
+            if usage.month == month_key(now) { usage.monthly_count } else { 0 },
+        ),
+        None => (0, 0),
+    }
+}
+
+/// Log entries written before the snapshot is rewritten and the log emptied
+const COMPACT_AFTER_ENTRIES: usize = 1000;
+
+struct QuotaState {
+    usage: HashMap<String, QuotaUsage>,
+    /// Entries in the log since the last snapshot
+    logged: usize,
+}
+
+/// Daily and monthly submission counters per device and API key, kept in
+/// `Storage` so they survive restarts. Each change is appended to a log;
+/// the full map is only rewritten every `COMPACT_AFTER_ENTRIES` changes and
+/// on `flush`.
+pub struct QuotaManager {
+    config: QuotaConfig,
+    storage: Arc<Storage>,
+    state: Mutex<QuotaState>,
+}
+
+impl QuotaManager {
+    pub fn new(config: QuotaConfig, storage: Arc<Storage>) -> Result<Self> {
+        let usage = storage.load_quota_usage()?;
+        // Start from a fresh snapshot so the log only holds new changes
+        storage.save_quota_usage(&usage)?;
+        Ok(Self {
+            config,
+            storage,
+            state: Mutex::new(QuotaState { usage, logged: 0 }),
+        })
+    }
+
+    pub fn config(&self) -> &QuotaConfig {
+        &self.config
+    }
+
+    /// Count one submission against every subject, or fail with the first
+    /// limit a subject has already reached and count nothing. Checking and
+    /// counting happen under one lock, so concurrent submissions cannot
+    /// all pass the check before any is counted.
+    pub async fn admit(&self, subjects: &[QuotaSubject], now: DateTime<Utc>) -> Result<(), QuotaExceeded> {
+        let mut state = self.state.lock().await;
+        for subject in subjects {
+            let (daily_limit, monthly_limit) = subject.limits(&self.config);
+            let (daily, monthly) = current_counts(state.usage.get(&subject.storage_key()), now);
+            if daily_limit > 0 && daily >= daily_limit {
+                return Err(QuotaExceeded { subject: subject.storage_key(), period: "daily", limit: daily_limit, resets_at: next_day(now) });
+            }
+            if monthly_limit > 0 && monthly >= monthly_limit {
+                return Err(QuotaExceeded { subject: subject.storage_key(), period: "monthly", limit: monthly_limit, resets_at: next_month(now) });
+            }
+        }
+
+        let mut entries = Vec::with_capacity(subjects.len());
+        for subject in subjects {
+            let entry = state.usage.entry(subject.storage_key()).or_default();
+            let (daily, monthly) = current_counts(Some(entry), now);
+            *entry = QuotaUsage {
+                day: day_key(now),
+                daily_count: daily + 1,
+                month: month_key(now),
+                monthly_count: monthly + 1,
+            };
+            entries.push(QuotaLogEntry { subject: subject.storage_key(), usage: entry.clone() });
+        }
+        self.persist(&mut state, &entries);
+        Ok(())
+    }
+
+    /// Give back a submission `admit` counted at `now` that was not
+    /// accepted after all
+    pub async fn refund(&self, subjects: &[QuotaSubject], now: DateTime<Utc>) {
+        let mut state = self.state.lock().await;
+        let mut entries = Vec::with_capacity(subjects.len());
+        for subject in subjects {
+            let Some(entry) = state.usage.get_mut(&subject.storage_key()) else { continue };
+            if entry.day == day_key(now) {
+                entry.daily_count = entry.daily_count.saturating_sub(1);
+            }
+            if entry.month == month_key(now) {
+                entry.monthly_count = entry.monthly_count.saturating_sub(1);
+            }
+            entries.push(QuotaLogEntry { subject: subject.storage_key(), usage: entry.clone() });
+        }
+        self.persist(&mut state, &entries);
+    }
+
+    /// Rewrite the snapshot and empty the log, e.g. on shutdown
+    pub async fn flush(&self) -> Result<()> {
+        let mut state = self.state.lock().await;
+        self.storage.save_quota_usage(&state.usage)?;
+        state.logged = 0;
+        Ok(())
+    }
+
+    /// Append `entries` to the log, compacting once enough have built up.
+    /// A write failure is logged rather than refusing the submission.
+    fn persist(&self, state: &mut QuotaState, entries: &[QuotaLogEntry]) {
+        let result = self.storage.append_quota_log(entries).and_then(|()| {
+            state.logged += entries.len();
+            if state.logged >= COMPACT_AFTER_ENTRIES {
+                self.storage.save_quota_usage(&state.usage)?;
+                state.logged = 0;
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            log::error!("Failed to persist quota usage: {}", e);
+        }
+    }
+
+    pub async fn report(&self, subject: &QuotaSubject, now: DateTime<Utc>) -> QuotaReport {
+        let state = self.state.lock().await;
+        let (daily, monthly) = current_counts(state.usage.get(&subject.storage_key()), now);
+        let (daily_limit, monthly_limit) = subject.limits(&self.config);
+        let period = |used: u64, limit: u64, resets_at| QuotaPeriod {
+            used,
+            limit,
+            remaining: (limit > 0).then(|| limit.saturating_sub(used)),
+            resets_at,
+        };
+        QuotaReport {
+            subject: subject.storage_key(),
+            daily: period(daily, daily_limit, next_day(now)),
+            monthly: period(monthly, monthly_limit, next_month(now)),
+        }
+    }
+}
+
+/// Subjects a request is counted against: the token's device (or the peer
+/// address for anonymous callers) and the API key the token was issued
+/// for. Both come from verified claims, never from headers a caller could
+/// vary between requests.
+pub fn request_subjects(req: &ServiceRequest) -> Vec<QuotaSubject> {
+    let client_ip = req.connection_info().peer_addr().unwrap_or("unknown").to_string();
+    let claims = req.headers().get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .and_then(|token| auth::verify_jwt_token(token.trim()).ok());
+    let Some(claims) = claims else {
+        return vec![QuotaSubject::Device(format!("ip:{client_ip}"))];
+    };
+    let mut subjects = vec![QuotaSubject::Device(claims.sub)];
+    if let Some(key_id) = claims.key_id {
+        subjects.push(QuotaSubject::ApiKey(key_id));
+    }
+    subjects
+}
+
+/// Rejects transaction submissions over quota with 429 and counts the
+/// rest, refunding those the handler does not accept. Other requests pass
+/// through uncounted.
+#[derive(Clone)]
+pub struct QuotaMiddleware {
+    quotas: Arc<QuotaManager>,
+}
+
+impl QuotaMiddleware {
+    pub fn new(quotas: Arc<QuotaManager>) -> Self {
+        Self { quotas }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for QuotaMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: actix_web::body::MessageBody + 'static,
+{
+    type Response = ServiceResponse<actix_web::body::BoxBody>;
+    type Error = Error;
+    type Transform = QuotaService<S, B>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(QuotaService {
+            service: Arc::new(service),
+            quotas: Arc::clone(&self.quotas),
+            _phantom: PhantomData,
+        }))
+    }
+}
+
+pub struct QuotaService<S, B> {
+    service: Arc<S>,
+    quotas: Arc<QuotaManager>,
+    _phantom: PhantomData<B>,
+}
+
+impl<S, B> Service<ServiceRequest> for QuotaService<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: actix_web::body::MessageBody + 'static,
+{
+    type Response = ServiceResponse<actix_web::body::BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Arc::clone(&self.service);
+        let quotas = Arc::clone(&self.quotas);
+
+        Box::pin(async move {
+            if !is_submission(&req) {
+                return Ok(service.call(req).await?.map_into_boxed_body());
+            }
+
+            let subjects = request_subjects(&req);
+            let now = Utc::now();
+            if let Err(exceeded) = quotas.admit(&subjects, now).await {
+                let retry_after = (exceeded.resets_at - now).num_seconds().max(1);
+                log::warn!("Rejected submission: {}", exceeded);
+                return Ok(req.into_response(
+                    HttpResponse::TooManyRequests()
+                        .insert_header(("Retry-After", retry_after.to_string()))
+                        .json(serde_json::json!({
+                            "error": "Quota exceeded",
+                            "message": exceeded.to_string(),
+                            "period": exceeded.period,
+                            "limit": exceeded.limit,
+                            "reset_at": exceeded.resets_at.to_rfc3339(),
+                            "retry_after": retry_after,
+                            "request_id": request_id::current_or_new(),
+                        }))
+                        .map_into_boxed_body(),
+                ));
+            }
+
+            let res = service.call(req).await;
+            if !res.as_ref().is_ok_and(|res| res.status().is_success()) {
+                quotas.refund(&subjects, now).await;
+            }
+            Ok(res?.map_into_boxed_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::storage::json_backend::JsonFileBackend;
+
+    #[tokio::test]
+    async fn test_device_quota_blocks_until_next_day_and_persists() {
+        let dir = std::env::temp_dir().join(format!("quota-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let data_dir = dir.to_str().unwrap().to_string();
+        let open_storage = || Arc::new(Storage::with_backend(data_dir.clone(), Box::new(JsonFileBackend::open(&data_dir).unwrap())).unwrap());
+        let config = QuotaConfig { device_daily: 2, device_monthly: 3, ..Default::default() };
+        let quotas = QuotaManager::new(config.clone(), open_storage()).unwrap();
+
+        let device = vec![QuotaSubject::Device("pos-1".to_string())];
+        let day_one = Utc.with_ymd_and_hms(2026, 1, 31, 10, 0, 0).unwrap();
+        quotas.admit(&device, day_one).await.unwrap();
+        quotas.admit(&device, day_one).await.unwrap();
+        quotas.refund(&device, day_one).await;
+        quotas.admit(&device, day_one).await.unwrap();
+        let exceeded = quotas.admit(&device, day_one).await.unwrap_err();
+        assert_eq!(exceeded.period, "daily");
+        assert_eq!(exceeded.resets_at, Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap());
+
+        // Counters come back from the log and reset at the next UTC day
+        let reloaded = QuotaManager::new(config, open_storage()).unwrap();
+        assert!(reloaded.admit(&device, day_one + Duration::hours(1)).await.is_err());
+        let report = reloaded.report(&device[0], day_one).await;
+        assert_eq!(report.monthly.used, 2);
+        assert_eq!(report.monthly.remaining, Some(1));
+        assert_eq!(report.daily.remaining, Some(0));
+        assert!(reloaded.admit(&device, Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap()).await.is_ok());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[actix_web::test]
+    async fn test_compressed_submissions_count_against_quota() {
+        use actix_web::{test, web, App};
+
+        let dir = std::env::temp_dir().join(format!("quota-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let data_dir = dir.to_str().unwrap().to_string();
+        let storage = Arc::new(Storage::with_backend(data_dir.clone(), Box::new(JsonFileBackend::open(&data_dir).unwrap())).unwrap());
+        let config = QuotaConfig { device_daily: 1, ..Default::default() };
+        let quotas = Arc::new(QuotaManager::new(config, storage).unwrap());
+        let app = test::init_service(
+            App::new().service(
+                web::scope("/api")
+                    .wrap(QuotaMiddleware::new(quotas))
+                    .route("/send_compressed_tx", web::post().to(HttpResponse::Ok)),
+            ),
+        ).await;
+
+        let submit = || test::TestRequest::post().uri("/api/send_compressed_tx").to_request();
+        assert!(test::call_service(&app, submit()).await.status().is_success());
+        assert_eq!(test::call_service(&app, submit()).await.status(), 429);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
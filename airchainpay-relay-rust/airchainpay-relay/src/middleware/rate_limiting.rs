@@ -94,60 +94,122 @@ where
                 .unwrap_or("unknown")
                 .to_string();
 
-            let mut limits_guard = limits.write().await;
             let now = Instant::now();
+            let (state, exceeded) = {
+                let mut limits_guard = limits.write().await;
+                let entry = limits_guard.entry(client_ip).or_insert(RateLimitEntry {
+                    count: 0,
+                    reset_time: now + window_size,
+                    burst_count: 0,
+                });
 
-            if let Some(entry) = limits_guard.get_mut(&client_ip) {
                 if now >= entry.reset_time {
                     // Reset window
                     *entry = RateLimitEntry {
-                        count: 1,
+                        count: 0,
                         reset_time: now + window_size,
-                        burst_count: 1,
+                        burst_count: 0,
                     };
-                } else {
-                    // Check burst limit first
-                    if entry.burst_count >= burst_limit {
-                        return Ok(req.into_response(
-                            HttpResponse::TooManyRequests()
-                                .json(serde_json::json!({
-                                    "error": "Rate limit exceeded (burst)",
-                                    "retry_after": entry.reset_time.duration_since(now).as_secs()
-                                }))
-                                .map_into_boxed_body()
-                        ));
-                    }
-
-                    // Check regular rate limit
-                    if entry.count >= rate_limit {
-                        return Ok(req.into_response(
-                            HttpResponse::TooManyRequests()
-                                .json(serde_json::json!({
-                                    "error": "Rate limit exceeded",
-                                    "retry_after": entry.reset_time.duration_since(now).as_secs()
-                                }))
-                                .map_into_boxed_body()
-                        ));
-                    }
+                }
 
+                // Check burst limit first, then the regular rate limit
+                let exceeded = if entry.burst_count >= burst_limit {
+                    Some("Rate limit exceeded (burst)")
+                } else if entry.count >= rate_limit {
+                    Some("Rate limit exceeded")
+                } else {
                     entry.count += 1;
                     entry.burst_count += 1;
-                }
-            } else {
-                limits_guard.insert(client_ip.to_string(), RateLimitEntry {
-                    count: 1,
-                    reset_time: now + window_size,
-                    burst_count: 1,
-                });
+                    None
+                };
+
+                (RateLimitState::from_entry(entry, rate_limit, burst_limit, window_size, now), exceeded)
+            };
+
+            if let Some(reason) = exceeded {
+                let retry_after = state.reset_after_secs.max(1);
+                let mut response = HttpResponse::TooManyRequests();
+                state.apply_headers(&mut response);
+                response.insert_header(("Retry-After", retry_after.to_string()));
+                return Ok(req.into_response(
+                    response
+                        .json(serde_json::json!({
+                            "error": reason,
+                            "message": format!("Too many requests. Retry after {retry_after} seconds."),
+                            "retry_after": retry_after,
+                            "limit": state.limit,
+                            "remaining": state.remaining,
+                            "burst_limit": state.burst_limit,
+                            "burst_remaining": state.burst_remaining,
+                            "reset": state.reset_epoch,
+                            "window_seconds": state.window_secs,
+                        }))
+                        .map_into_boxed_body()
+                ));
             }
 
             // Call the inner service
-            let res = service.call(req).await?;
+            let mut res = service.call(req).await?;
+            state.insert_headers(res.headers_mut());
             Ok(res.map_into_boxed_body())
         })
     }
 }
 
+/// Snapshot of a client's rate limit window, exposed to clients as headers
+#[derive(Debug, Clone)]
+pub struct RateLimitState {
+    pub limit: u32,
+    pub remaining: u32,
+    pub burst_limit: u32,
+    pub burst_remaining: u32,
+    pub reset_after_secs: u64,
+    pub reset_epoch: i64,
+    pub window_secs: u64,
+}
+
+impl RateLimitState {
+    fn from_entry(entry: &RateLimitEntry, rate_limit: u32, burst_limit: u32, window_size: Duration, now: Instant) -> Self {
+        let reset_after_secs = entry.reset_time.saturating_duration_since(now).as_secs();
+        Self {
+            limit: rate_limit,
+            remaining: rate_limit.saturating_sub(entry.count),
+            burst_limit,
+            burst_remaining: burst_limit.saturating_sub(entry.burst_count),
+            reset_after_secs,
+            reset_epoch: chrono::Utc::now().timestamp() + reset_after_secs as i64,
+            window_secs: window_size.as_secs(),
+        }
+    }
+
+    /// Header names are lowercase so they can be used with `HeaderName::from_static`
+    fn header_pairs(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("x-ratelimit-limit", self.limit.to_string()),
+            ("x-ratelimit-remaining", self.remaining.to_string()),
+            ("x-ratelimit-reset", self.reset_epoch.to_string()),
+            ("x-ratelimit-burst-limit", self.burst_limit.to_string()),
+            ("x-ratelimit-burst-remaining", self.burst_remaining.to_string()),
+            ("x-ratelimit-policy", format!("{};w={};burst={}", self.limit, self.window_secs, self.burst_limit)),
+        ]
+    }
+
+    pub fn apply_headers(&self, builder: &mut actix_web::HttpResponseBuilder) {
+        for header in self.header_pairs() {
+            builder.insert_header(header);
+        }
+    }
+
+    pub fn insert_headers(&self, headers: &mut actix_web::http::header::HeaderMap) {
+        use actix_web::http::header::{HeaderName, HeaderValue};
+        for (name, value) in self.header_pairs() {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.insert(HeaderName::from_static(name), value);
+            }
+        }
+    }
+}
+
 // Specialized rate limiters for different endpoints
 pub struct TransactionRateLimiter;
 impl TransactionRateLimiter {
@@ -0,0 +1,334 @@
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use actix_web::body::BoxBody;
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use futures_util::StreamExt;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use crate::domain::auth;
+use crate::infrastructure::ble::session::hmac_sha256;
+use crate::middleware::error_handling::ErrorResponseBuilder;
+use crate::middleware::{route_pattern, SUBMISSION_ROUTES};
+
+pub const NONCE_HEADER: &str = "X-Request-Nonce";
+pub const TIMESTAMP_HEADER: &str = "X-Request-Timestamp";
+pub const SIGNATURE_HEADER: &str = "X-Request-Signature";
+
+/// Endpoints where a nonce is mandatory even without a bearer token, as
+/// route patterns
+pub const DEFAULT_PROTECTED_PATHS: [&str; 5] = SUBMISSION_ROUTES;
+
+/// HMAC-SHA256 a bearer-token holder sends, hex-encoded, in
+/// `X-Request-Signature`: keyed with the token, over `"<nonce>.<timestamp>."`
+/// followed by the body before any `Content-Encoding`.
+pub fn request_signature(token: &str, nonce: &str, timestamp: i64, body: &[u8]) -> [u8; 32] {
+    let mut message = format!("{nonce}.{timestamp}.").into_bytes();
+    message.extend_from_slice(body);
+    hmac_sha256(token.as_bytes(), &message)
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayProtectionConfig {
+    /// Maximum clock skew accepted between client timestamp and server time
+    pub max_skew: Duration,
+    /// Upper bound on cached nonces; new ones are refused beyond it
+    pub max_entries: usize,
+    pub protected_paths: Vec<String>,
+}
+
+impl Default for ReplayProtectionConfig {
+    fn default() -> Self {
+        Self {
+            max_skew: Duration::from_secs(300),
+            max_entries: 100_000,
+            protected_paths: DEFAULT_PROTECTED_PATHS.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+}
+
+/// Outcome of recording a nonce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonceCheck {
+    Fresh,
+    /// Seen before and not yet expired
+    Replayed,
+    /// `max_entries` unexpired nonces are already cached; nothing was recorded.
+    /// Evicting one instead would let its request be replayed.
+    Full,
+}
+
+#[derive(Debug, Default)]
+struct NonceEntries {
+    expires: HashMap<String, Instant>,
+    /// The same entries ordered by expiry, so expired ones come off the front
+    by_expiry: BTreeSet<(Instant, String)>,
+}
+
+impl NonceEntries {
+    fn purge(&mut self, now: Instant) -> usize {
+        let mut removed = 0;
+        while self.by_expiry.first().is_some_and(|(expires_at, _)| *expires_at <= now) {
+            if let Some((_, key)) = self.by_expiry.pop_first() {
+                self.expires.remove(&key);
+                removed += 1;
+            }
+        }
+        removed
+    }
+}
+
+/// Short-lived cache of nonces seen within the accepted timestamp window
+#[derive(Debug, Default)]
+pub struct NonceCache {
+    seen: RwLock<NonceEntries>,
+}
+
+impl NonceCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a nonce unless it is cached already or the cache is full
+    pub async fn check_and_insert(&self, key: String, ttl: Duration, max_entries: usize) -> NonceCheck {
+        let now = Instant::now();
+        let mut seen = self.seen.write().await;
+        seen.purge(now);
+
+        if seen.expires.contains_key(&key) {
+            return NonceCheck::Replayed;
+        }
+        if seen.expires.len() >= max_entries {
+            return NonceCheck::Full;
+        }
+
+        let expires_at = now + ttl;
+        seen.by_expiry.insert((expires_at, key.clone()));
+        seen.expires.insert(key, expires_at);
+        NonceCheck::Fresh
+    }
+
+    pub async fn len(&self) -> usize {
+        self.seen.read().await.expires.len()
+    }
+
+    /// Drop expired nonces, returning how many were removed
+    pub async fn purge_expired(&self) -> usize {
+        self.seen.write().await.purge(Instant::now())
+    }
+}
+
+/// Rejects replayed requests using a client nonce plus timestamp.
+///
+/// Applies to any request carrying a bearer token, and is mandatory on the
+/// configured high-value paths. Token holders also sign the nonce,
+/// timestamp and body (`request_signature`), so a captured nonce cannot be
+/// reattached to a different body. Tokenless submissions share no key with
+/// the relay; the transactions they carry are signed, and a resubmitted
+/// transaction is caught by its hash in `TxReplayGuard`.
+#[derive(Clone)]
+pub struct ReplayProtectionMiddleware {
+    config: ReplayProtectionConfig,
+    cache: Arc<NonceCache>,
+}
+
+impl ReplayProtectionMiddleware {
+    pub fn new(config: ReplayProtectionConfig) -> Self {
+        Self {
+            config,
+            cache: Arc::new(NonceCache::new()),
+        }
+    }
+
+    pub fn cache(&self) -> Arc<NonceCache> {
+        Arc::clone(&self.cache)
+    }
+}
+
+impl<S> Transform<S, ServiceRequest> for ReplayProtectionMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<BoxBody>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Transform = ReplayProtectionService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ReplayProtectionService {
+            service: Arc::new(service),
+            config: self.config.clone(),
+            cache: Arc::clone(&self.cache),
+        }))
+    }
+}
+
+pub struct ReplayProtectionService<S> {
+    service: Arc<S>,
+    config: ReplayProtectionConfig,
+    cache: Arc<NonceCache>,
+}
+
+impl<S> Service<ServiceRequest> for ReplayProtectionService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<BoxBody>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Arc::clone(&self.service);
+        let config = self.config.clone();
+        let cache = Arc::clone(&self.cache);
+
+        Box::pin(async move {
+            let mut req = req;
+            let token = req.headers().get("Authorization")
+                .and_then(|h| h.to_str().ok())
+                .and_then(|h| h.strip_prefix("Bearer "))
+                .map(|token| token.trim().to_string());
+            let subject = token.as_deref()
+                .and_then(|token| auth::verify_jwt_token(token).ok())
+                .map(|claims| claims.sub);
+            // Unresolved routes are refused by the authorization middleware
+            let protected = route_pattern(&req)
+                .is_some_and(|pattern| config.protected_paths.iter().any(|p| *p == pattern));
+
+            if subject.is_none() && !protected {
+                return service.call(req).await;
+            }
+
+            let nonce = req.headers().get(NONCE_HEADER).and_then(|h| h.to_str().ok()).map(|s| s.to_string());
+            let timestamp = req.headers().get(TIMESTAMP_HEADER)
+                .and_then(|h| h.to_str().ok())
+                .and_then(|s| s.parse::<i64>().ok());
+
+            let (nonce, timestamp) = match (nonce, timestamp) {
+                (Some(n), Some(t)) if (16..=128).contains(&n.len()) => (n, t),
+                _ => {
+                    return Ok(req.into_response(ErrorResponseBuilder::bad_request(
+                        &format!("{NONCE_HEADER} (16-128 chars) and {TIMESTAMP_HEADER} (unix seconds) are required"),
+                    )));
+                }
+            };
+
+            let skew = (chrono::Utc::now().timestamp() - timestamp).unsigned_abs();
+            if skew > config.max_skew.as_secs() {
+                return Ok(req.into_response(ErrorResponseBuilder::unauthorized("Request timestamp outside accepted window")));
+            }
+
+            if let (Some(token), Some(_)) = (&token, &subject) {
+                let signature = req.headers().get(SIGNATURE_HEADER)
+                    .and_then(|h| h.to_str().ok())
+                    .and_then(|s| hex::decode(s).ok());
+                let Some(signature) = signature else {
+                    return Ok(req.into_response(ErrorResponseBuilder::bad_request(
+                        &format!("{SIGNATURE_HEADER} (hex HMAC-SHA256) is required with a bearer token"),
+                    )));
+                };
+
+                // The security middleware has already buffered and decoded the body
+                let mut payload = req.take_payload();
+                let mut body = bytes::BytesMut::new();
+                while let Some(chunk) = payload.next().await {
+                    body.extend_from_slice(&chunk?);
+                }
+                let expected = request_signature(token, &nonce, timestamp, &body);
+                req.set_payload(actix_web::dev::Payload::from(body.freeze()));
+                if !auth::constant_time_eq(&expected, &signature) {
+                    return Ok(req.into_response(ErrorResponseBuilder::unauthorized("Request signature does not match")));
+                }
+            }
+
+            let caller = subject.unwrap_or_else(|| {
+                req.connection_info().peer_addr().unwrap_or("unknown").to_string()
+            });
+            // A nonce stays cached for the full window on either side of "now"
+            let ttl = config.max_skew * 2;
+            match cache.check_and_insert(format!("{caller}:{nonce}"), ttl, config.max_entries).await {
+                NonceCheck::Fresh => {}
+                NonceCheck::Replayed => {
+                    log::warn!("Replay rejected for {} on {}", caller, req.path());
+                    return Ok(req.into_response(ErrorResponseBuilder::unauthorized("Replayed request")));
+                }
+                NonceCheck::Full => {
+                    log::warn!("Nonce cache full, refusing {} on {}", caller, req.path());
+                    return Ok(req.into_response(ErrorResponseBuilder::service_unavailable("Too many requests in flight, retry shortly")));
+                }
+            }
+
+            service.call(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App, HttpResponse};
+
+    #[tokio::test]
+    async fn test_full_cache_refuses_new_nonces() {
+        let cache = NonceCache::new();
+        let ttl = Duration::from_secs(60);
+        assert_eq!(cache.check_and_insert("a".to_string(), ttl, 2).await, NonceCheck::Fresh);
+        assert_eq!(cache.check_and_insert("b".to_string(), ttl, 2).await, NonceCheck::Fresh);
+        assert_eq!(cache.check_and_insert("c".to_string(), ttl, 2).await, NonceCheck::Full);
+        // Nothing was evicted to make room
+        assert_eq!(cache.check_and_insert("a".to_string(), ttl, 2).await, NonceCheck::Replayed);
+
+        let cache = NonceCache::new();
+        assert_eq!(cache.check_and_insert("a".to_string(), Duration::ZERO, 1).await, NonceCheck::Fresh);
+        assert_eq!(cache.check_and_insert("b".to_string(), ttl, 1).await, NonceCheck::Fresh);
+        assert_eq!(cache.len().await, 1);
+    }
+
+    #[actix_web::test]
+    async fn test_percent_encoded_path_still_needs_a_nonce() {
+        let app = test::init_service(
+            App::new().service(
+                web::scope("/api")
+                    .wrap(ReplayProtectionMiddleware::new(ReplayProtectionConfig::default()))
+                    .route("/send_tx", web::post().to(HttpResponse::Ok)),
+            ),
+        ).await;
+
+        for path in ["/api/send_tx", "/api/%73end_tx"] {
+            let res = test::call_service(&app, test::TestRequest::post().uri(path).to_request()).await;
+            assert_eq!(res.status(), 400, "{path} went through without a nonce");
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/api/%73end_tx")
+            .insert_header((NONCE_HEADER, "0123456789abcdef"))
+            .insert_header((TIMESTAMP_HEADER, chrono::Utc::now().timestamp().to_string()))
+            .to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+    }
+
+    #[actix_web::test]
+    async fn test_compressed_submit_needs_a_nonce() {
+        let app = test::init_service(
+            App::new().service(
+                web::scope("/api")
+                    .wrap(ReplayProtectionMiddleware::new(ReplayProtectionConfig::default()))
+                    .route("/send_compressed_tx", web::post().to(HttpResponse::Ok)),
+            ),
+        ).await;
+
+        let req = test::TestRequest::post().uri("/api/send_compressed_tx").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+}
@@ -0,0 +1,120 @@
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    Error, HttpMessage,
+};
+use actix_web::body::{BoxBody, MessageBody};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use crate::infrastructure::telemetry::{self, TRACEPARENT_HEADER};
+use crate::utils::request_id::{self, REQUEST_ID_HEADER};
+
+/// Access log format with the request id appended
+pub const ACCESS_LOG_FORMAT: &str = "%a \"%r\" %s %b \"%{Referer}i\" \"%{User-Agent}i\" %T request_id=%{X-Request-Id}i";
+
+/// Request id of the current call, available from request extensions
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Assigns every request an id, honouring a well-formed incoming `X-Request-Id`.
+///
+/// The id is written back onto the request headers (for the access log),
+/// exposed as the current request id while the request is handled, and
+/// returned on the response. When traces are exported the request span
+/// continues an incoming `traceparent`, and its trace id is returned as
+/// `X-Trace-Id` alongside the trace context.
+#[derive(Clone, Default)]
+pub struct RequestIdMiddleware;
+
+impl RequestIdMiddleware {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Transform = RequestIdService<S, B>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdService {
+            service: Arc::new(service),
+            _phantom: PhantomData,
+        }))
+    }
+}
+
+pub struct RequestIdService<S, B> {
+    service: Arc<S>,
+    _phantom: PhantomData<B>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdService<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Arc::clone(&self.service);
+
+        let id = req.headers().get(REQUEST_ID_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.trim().to_string())
+            .filter(|s| request_id::is_valid(s))
+            .unwrap_or_else(request_id::generate);
+        let header_name = HeaderName::from_static("x-request-id");
+        // Valid ids are plain ASCII, so this cannot fail
+        let header_value = HeaderValue::from_str(&id).expect("request id is a valid header value");
+
+        req.headers_mut().insert(header_name.clone(), header_value.clone());
+        req.extensions_mut().insert(RequestId(id.clone()));
+        let http_req = req.request().clone();
+
+        let span = request_id::request_span(&id);
+        let incoming: HashMap<String, String> = [TRACEPARENT_HEADER, "tracestate"]
+            .into_iter()
+            .filter_map(|name| {
+                let value = req.headers().get(name)?.to_str().ok()?;
+                Some((name.to_string(), value.to_string()))
+            })
+            .collect();
+        telemetry::set_parent(&span, &incoming);
+        let trace_id = telemetry::trace_id(&span);
+        let traceparent = telemetry::trace_headers(&span).remove(TRACEPARENT_HEADER);
+
+        Box::pin(request_id::scope_in(id, span, async move {
+            let mut res = match service.call(req).await {
+                Ok(res) => res.map_into_boxed_body(),
+                Err(e) => ServiceResponse::from_err(e, http_req),
+            };
+            res.headers_mut().insert(header_name, header_value);
+            if let Some(trace_id) = trace_id.and_then(|v| HeaderValue::from_str(&v).ok()) {
+                res.headers_mut().insert(HeaderName::from_static("x-trace-id"), trace_id);
+            }
+            if let Some(traceparent) = traceparent.and_then(|v| HeaderValue::from_str(&v).ok()) {
+                res.headers_mut().insert(HeaderName::from_static(TRACEPARENT_HEADER), traceparent);
+            }
+            Ok(res)
+        }))
+    }
+}
@@ -78,6 +78,40 @@ impl Default for SecurityConfig {
     }
 }
 
+impl SecurityConfig {
+    /// CORS settings for the configured environment. A wildcard origin is
+    /// honoured only in development; elsewhere it is dropped with a warning,
+    /// so browsers are limited to the explicitly listed origins.
+    pub fn for_environment(config: &crate::infrastructure::config::Config) -> Self {
+        let split = |value: &str| -> Vec<String> {
+            value.split(',').map(str::trim).filter(|v| !v.is_empty()).map(String::from).collect()
+        };
+        let mut allowed_origins = split(&config.security.cors_origins);
+        let wildcard = allowed_origins.iter().any(|o| o == "*");
+        if wildcard && config.environment != "development" {
+            log::warn!(
+                "⚠️ CORS_ORIGINS=* is only allowed in development; ignoring the wildcard in {}",
+                config.environment
+            );
+            allowed_origins.retain(|o| o != "*");
+        }
+        if allowed_origins.is_empty() && config.security.enable_cors {
+            log::warn!("⚠️ No CORS origins configured; cross-origin browser requests will be rejected");
+        }
+
+        Self {
+            enable_cors: config.security.enable_cors,
+            // Credentials are never combined with an open origin list
+            credentials: !allowed_origins.iter().any(|o| o == "*"),
+            allowed_origins,
+            allowed_methods: split(&config.security.cors_methods),
+            allowed_headers: split(&config.security.cors_headers),
+            exposed_headers: vec!["X-Request-Id".to_string(), "X-Trace-Id".to_string()],
+            ..Self::default()
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct SecurityMiddleware {
     security_config: SecurityConfig,
@@ -439,6 +473,21 @@ mod tests {
         // Removed invalid assertion: cors.allowed_origins() does not exist in actix-cors v0.7.1
     }
 
+    #[test]
+    fn test_wildcard_origin_only_in_development() {
+        let mut config = crate::infrastructure::config::Config::default();
+        config.security.enable_cors = true;
+        config.security.cors_origins = "*,https://app.airchainpay.com".to_string();
+
+        config.environment = "development".to_string();
+        assert!(SecurityConfig::for_environment(&config).allowed_origins.contains(&"*".to_string()));
+
+        config.environment = "production".to_string();
+        let cors = SecurityConfig::for_environment(&config);
+        assert_eq!(cors.allowed_origins, vec!["https://app.airchainpay.com".to_string()]);
+        assert!(cors.credentials);
+    }
+
     #[test]
     fn test_compression_configuration() {
         let config = SecurityConfig::default();
@@ -9,6 +9,7 @@ use uuid::Uuid;
 // use crate::logger::Logger;
 use std::time::{SystemTime, UNIX_EPOCH};
 use crate::infrastructure::monitoring::manager::MonitoringManager;
+use crate::utils::request_id;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditEvent {
@@ -64,7 +65,7 @@ pub enum AuditEventType {
     DeviceManagement,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum AuditSeverity {
     Low,
     Medium,
@@ -86,11 +87,95 @@ pub struct AuditFilter {
     pub action: Option<String>,
 }
 
+/// Which audit events are kept and for how long. Low and medium events
+/// may be sampled; security events and high or critical ones are always
+/// recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditPolicy {
+    /// Share of low-severity events recorded, 0.0 to 1.0
+    pub low_sample_rate: f64,
+    /// Share of medium-severity events recorded, 0.0 to 1.0
+    pub medium_sample_rate: f64,
+    /// Days events are kept, by severity; `None` keeps them forever
+    pub low_retention_days: Option<i64>,
+    pub medium_retention_days: Option<i64>,
+    pub high_retention_days: Option<i64>,
+    pub critical_retention_days: Option<i64>,
+}
+
+impl Default for AuditPolicy {
+    fn default() -> Self {
+        Self {
+            low_sample_rate: 1.0,
+            medium_sample_rate: 1.0,
+            low_retention_days: Some(30),
+            medium_retention_days: Some(90),
+            high_retention_days: Some(365),
+            critical_retention_days: None,
+        }
+    }
+}
+
+impl AuditPolicy {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let rate = |name: &str, default: f64| std::env::var(name)
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .map(|v| v.clamp(0.0, 1.0))
+            .unwrap_or(default);
+        // 0 keeps events of that severity forever
+        let days = |name: &str, default: Option<i64>| match std::env::var(name).ok().and_then(|v| v.parse::<i64>().ok()) {
+            Some(days) if days > 0 => Some(days),
+            Some(_) => None,
+            None => default,
+        };
+        Self {
+            low_sample_rate: rate("AUDIT_SAMPLE_RATE_LOW", defaults.low_sample_rate),
+            medium_sample_rate: rate("AUDIT_SAMPLE_RATE_MEDIUM", defaults.medium_sample_rate),
+            low_retention_days: days("AUDIT_RETENTION_DAYS_LOW", defaults.low_retention_days),
+            medium_retention_days: days("AUDIT_RETENTION_DAYS_MEDIUM", defaults.medium_retention_days),
+            high_retention_days: days("AUDIT_RETENTION_DAYS_HIGH", defaults.high_retention_days),
+            critical_retention_days: days("AUDIT_RETENTION_DAYS_CRITICAL", defaults.critical_retention_days),
+        }
+    }
+
+    /// Share of events like `event` to record
+    pub fn sample_rate(&self, event: &AuditEvent) -> f64 {
+        if event.event_type == AuditEventType::Security {
+            return 1.0;
+        }
+        match event.severity {
+            AuditSeverity::Low => self.low_sample_rate,
+            AuditSeverity::Medium => self.medium_sample_rate,
+            AuditSeverity::High | AuditSeverity::Critical => 1.0,
+        }
+    }
+
+    pub fn retention_days(&self, severity: AuditSeverity) -> Option<i64> {
+        match severity {
+            AuditSeverity::Low => self.low_retention_days,
+            AuditSeverity::Medium => self.medium_retention_days,
+            AuditSeverity::High => self.high_retention_days,
+            AuditSeverity::Critical => self.critical_retention_days,
+        }
+    }
+
+    /// Whether `event` has outlived its severity's retention at `now`
+    pub fn is_expired(&self, event: &AuditEvent, now: DateTime<Utc>) -> bool {
+        self.retention_days(event.severity)
+            .is_some_and(|days| event.timestamp < now - chrono::Duration::days(days))
+    }
+}
+
 pub struct AuditLogger {
     events: Arc<RwLock<Vec<AuditEvent>>>,
     max_events: usize,
     file_path: String,
     enabled: bool,
+    policy: AuditPolicy,
+    /// Events dropped by sampling, by severity
+    sampled_out: Arc<RwLock<HashMap<AuditSeverity, u64>>>,
 }
 
 impl AuditLogger {
@@ -100,9 +185,20 @@ impl AuditLogger {
             max_events,
             file_path,
             enabled: true,
+            policy: AuditPolicy::default(),
+            sampled_out: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    pub fn with_policy(mut self, policy: AuditPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn policy(&self) -> &AuditPolicy {
+        &self.policy
+    }
+
     fn get_server_info() -> ServerInfo {
         ServerInfo {
             uptime: SystemTime::now()
@@ -120,10 +216,22 @@ impl AuditLogger {
         }
     }
 
-    pub async fn log_event(&self, event: AuditEvent) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn log_event(&self, mut event: AuditEvent) -> Result<(), Box<dyn std::error::Error>> {
         if !self.enabled {
             return Ok(());
         }
+        let sample_rate = self.policy.sample_rate(&event);
+        if sample_rate < 1.0 && rand::random::<f64>() >= sample_rate {
+            *self.sampled_out.write().await.entry(event.severity).or_insert(0) += 1;
+            return Ok(());
+        }
+        if sample_rate < 1.0 {
+            // Lets readers scale sampled counts back up
+            event.metadata.insert("sample_rate".to_string(), serde_json::json!(sample_rate));
+        }
+        if event.request_id.is_none() {
+            event.request_id = request_id::current();
+        }
 
         let mut events = self.events.write().await;
         
@@ -223,6 +331,73 @@ impl AuditLogger {
         self.log_event(event).await
     }
 
+    /// Issuance and revocation of device credentials (enrollment tokens, certificates)
+    pub async fn log_device_credential(
+        &self,
+        action: &str,
+        device_id: &str,
+        details: HashMap<String, serde_json::Value>,
+        user_id: Option<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let event = AuditEvent {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            event_type: AuditEventType::DeviceManagement,
+            user_id,
+            ip_address: None,
+            user_agent: None,
+            device_id: Some(device_id.to_string()),
+            resource: "device_certificate".to_string(),
+            action: action.to_string(),
+            details,
+            success: true,
+            error_message: None,
+            session_id: None,
+            request_id: None,
+            severity: AuditSeverity::Medium,
+            metadata: HashMap::new(),
+            server_info: Self::get_server_info(),
+        };
+
+        self.log_event(event).await
+    }
+
+    /// Record a Noise handshake transcript; `error` is set when the handshake
+    /// was abandoned or the device failed to authenticate
+    pub async fn log_noise_handshake(
+        &self,
+        device_id: &str,
+        transport: &str,
+        session_id: Option<&str>,
+        details: HashMap<String, serde_json::Value>,
+        error: Option<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut metadata = HashMap::new();
+        metadata.insert("transport".to_string(), serde_json::Value::String(transport.to_string()));
+
+        let event = AuditEvent {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            event_type: AuditEventType::Security,
+            user_id: None,
+            ip_address: None,
+            user_agent: None,
+            device_id: Some(device_id.to_string()),
+            resource: "noise_session".to_string(),
+            action: "handshake".to_string(),
+            details,
+            success: error.is_none(),
+            severity: if error.is_none() { AuditSeverity::Low } else { AuditSeverity::High },
+            error_message: error,
+            session_id: session_id.map(str::to_string),
+            request_id: None,
+            metadata,
+            server_info: Self::get_server_info(),
+        };
+
+        self.log_event(event).await
+    }
+
     pub async fn log_backup_operation(
         &self,
         operation: &str,
@@ -285,6 +460,41 @@ impl AuditLogger {
         self.log_event(event).await
     }
 
+    pub async fn log_data_purge(
+        &self,
+        resource: &str,
+        record_ids: &[String],
+        dry_run: bool,
+        details: HashMap<String, serde_json::Value>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut details = details;
+        details.insert("record_ids".to_string(), serde_json::json!(record_ids));
+        details.insert("count".to_string(), serde_json::json!(record_ids.len()));
+        details.insert("dry_run".to_string(), serde_json::json!(dry_run));
+
+        let event = AuditEvent {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            event_type: AuditEventType::Database,
+            user_id: None,
+            ip_address: None,
+            user_agent: None,
+            device_id: None,
+            resource: resource.to_string(),
+            action: if dry_run { "purge_dry_run".to_string() } else { "purge".to_string() },
+            details,
+            success: true,
+            error_message: None,
+            session_id: None,
+            request_id: None,
+            severity: if dry_run { AuditSeverity::Low } else { AuditSeverity::Medium },
+            metadata: HashMap::new(),
+            server_info: Self::get_server_info(),
+        };
+
+        self.log_event(event).await
+    }
+
     pub async fn log_rate_limit(
         &self,
         endpoint: &str,
@@ -474,6 +684,53 @@ impl AuditLogger {
         self.log_event(event).await
     }
 
+    /// Record a sanctions screening decision for a transaction
+    pub async fn log_screening(
+        &self,
+        tx_id: &str,
+        chain_id: u64,
+        sender: Option<String>,
+        addresses: Vec<String>,
+        screener: &str,
+        outcome: &str,
+        matched: Vec<String>,
+        reason: Option<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut details = HashMap::new();
+        details.insert("tx_id".to_string(), serde_json::Value::String(tx_id.to_string()));
+        details.insert("chain_id".to_string(), serde_json::Value::Number(serde_json::Number::from(chain_id)));
+        details.insert("screener".to_string(), serde_json::Value::String(screener.to_string()));
+        details.insert("addresses".to_string(), serde_json::json!(addresses));
+        details.insert("matched".to_string(), serde_json::json!(matched));
+
+        let allowed = outcome == "allowed";
+        let event = AuditEvent {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            event_type: AuditEventType::Security,
+            user_id: sender,
+            ip_address: None,
+            user_agent: None,
+            device_id: None,
+            resource: "screening".to_string(),
+            action: format!("screening_{}", outcome),
+            details,
+            success: allowed,
+            error_message: reason,
+            session_id: None,
+            request_id: None,
+            severity: match outcome {
+                "allowed" => AuditSeverity::Low,
+                "blocked" => AuditSeverity::High,
+                _ => AuditSeverity::Medium,
+            },
+            metadata: HashMap::new(),
+            server_info: Self::get_server_info(),
+        };
+
+        self.log_event(event).await
+    }
+
     pub async fn log_security_event(
         &self,
         user_id: Option<String>,
@@ -721,6 +978,10 @@ impl AuditLogger {
         for event in events.iter() {
             *event_type_counts.entry(format!("{:?}", event.event_type)).or_insert(0) += 1;
         }
+        let sampled_out = self.sampled_out.read().await
+            .iter()
+            .map(|(severity, count)| (format!("{:?}", severity), *count))
+            .collect();
 
         AuditStats {
             total_events,
@@ -731,6 +992,7 @@ impl AuditLogger {
             failed_events,
             security_events,
             event_type_counts,
+            sampled_out,
             oldest_event: events.first().map(|e| e.timestamp),
             newest_event: events.last().map(|e| e.timestamp),
         }
@@ -748,6 +1010,43 @@ impl AuditLogger {
         Ok(())
     }
 
+    /// Remove events past their severity's retention from memory and the
+    /// audit file. Returns how many were (or, on a dry run, would be)
+    /// removed from the file.
+    pub async fn prune_expired(&self, dry_run: bool) -> Result<usize, Box<dyn std::error::Error>> {
+        let now = Utc::now();
+        // Held throughout: log_event appends to the file under the same lock
+        let mut events = self.events.write().await;
+        if !dry_run {
+            events.retain(|e| !self.policy.is_expired(e, now));
+        }
+
+        let contents = match std::fs::read_to_string(&self.file_path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        let mut kept = String::with_capacity(contents.len());
+        let mut removed = 0;
+        for line in contents.lines() {
+            // Lines that do not parse are kept rather than silently lost
+            let expired = serde_json::from_str::<AuditEvent>(line)
+                .is_ok_and(|e| self.policy.is_expired(&e, now));
+            if expired {
+                removed += 1;
+            } else {
+                kept.push_str(line);
+                kept.push('\n');
+            }
+        }
+        if !dry_run && removed > 0 {
+            let tmp_path = format!("{}.tmp", self.file_path);
+            std::fs::write(&tmp_path, kept)?;
+            std::fs::rename(&tmp_path, &self.file_path)?;
+        }
+        Ok(removed)
+    }
+
     async fn write_to_file(&self, event: &AuditEvent) -> Result<(), Box<dyn std::error::Error>> {
         use std::fs::OpenOptions;
         use std::io::Write;
@@ -793,6 +1092,8 @@ pub struct AuditStats {
     pub failed_events: usize,
     pub security_events: usize,
     pub event_type_counts: HashMap<String, usize>,
+    /// Events not recorded because of sampling, by severity
+    pub sampled_out: HashMap<String, u64>,
     pub oldest_event: Option<DateTime<Utc>>,
     pub newest_event: Option<DateTime<Utc>>,
 }
@@ -801,4 +1102,42 @@ impl Default for AuditLogger {
     fn default() -> Self {
         Self::new("audit.log".to_string(), 10000)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sampling_spares_security_events_and_retention_is_per_severity() {
+        let path = std::env::temp_dir().join(format!("audit_policy_{}.log", Uuid::new_v4()));
+        let policy = AuditPolicy {
+            low_sample_rate: 0.0,
+            low_retention_days: Some(1),
+            critical_retention_days: None,
+            ..AuditPolicy::default()
+        };
+        let logger = AuditLogger::new(path.to_string_lossy().to_string(), 100).with_policy(policy);
+
+        logger.log_data_access("read", "devices.json", HashMap::new(), None, None, None).await.unwrap();
+        // Low severity, but security events are never sampled out
+        logger.log_noise_handshake("device-1", "ble", None, HashMap::new(), None).await.unwrap();
+        let stats = logger.get_audit_stats().await;
+        assert_eq!(stats.total_events, 1);
+        assert_eq!(stats.sampled_out.get("Low"), Some(&1));
+
+        let mut old_critical = logger.get_events(None).await[0].clone();
+        old_critical.severity = AuditSeverity::Critical;
+        old_critical.timestamp = Utc::now() - chrono::Duration::days(400);
+        let mut old_low = old_critical.clone();
+        old_low.severity = AuditSeverity::Low;
+        old_low.event_type = AuditEventType::DataAccess;
+        logger.log_event(old_critical).await.unwrap();
+        std::fs::write(&path, format!("{}{}\n", std::fs::read_to_string(&path).unwrap(), serde_json::to_string(&old_low).unwrap())).unwrap();
+
+        assert_eq!(logger.prune_expired(true).await.unwrap(), 1);
+        assert_eq!(logger.prune_expired(false).await.unwrap(), 1);
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+        let _ = std::fs::remove_file(&path);
+    }
 } 
\ No newline at end of file
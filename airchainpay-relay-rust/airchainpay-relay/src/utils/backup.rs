@@ -82,6 +82,10 @@ impl BackupManager {
         }
     }
 
+    pub fn backup_dir(&self) -> &str {
+        &self.config.backup_dir
+    }
+
     pub fn with_monitoring(mut self, monitoring_manager: Arc<MonitoringManager>) -> Self {
         self.monitoring_manager = Some(monitoring_manager);
         self
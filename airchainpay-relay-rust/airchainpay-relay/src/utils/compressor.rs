@@ -1,7 +1,10 @@
 #![allow(dead_code, unused_variables)]
 use anyhow::{Result, anyhow};
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
 use serde::{Deserialize, Serialize};
+use crate::infrastructure::monitoring::manager::MonitoringManager;
 use crate::utils::protobuf_compressor::{ProtobufCompressor, DecompressionResult, CompressionStats};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,10 +24,24 @@ pub enum CompressionType {
     ProtobufCbor,
 }
 
+impl CompressionType {
+    /// Label used for the `format` dimension of the compression metrics
+    pub fn label(&self) -> &'static str {
+        match self {
+            CompressionType::None => "none",
+            CompressionType::Gzip => "gzip",
+            CompressionType::Deflate => "deflate",
+            CompressionType::LZ4 => "lz4",
+            CompressionType::ProtobufCbor => "protobuf_cbor",
+        }
+    }
+}
+
 pub struct PayloadCompressor {
     compression_threshold: usize,
     max_compression_ratio: f64,
     protobuf_compressor: ProtobufCompressor,
+    monitoring: Option<Arc<MonitoringManager>>,
 }
 
 impl PayloadCompressor {
@@ -33,10 +50,31 @@ impl PayloadCompressor {
             compression_threshold: 1024, // 1KB
             max_compression_ratio: 0.8, // 80% of original size
             protobuf_compressor: ProtobufCompressor::new(),
+            monitoring: None,
+        }
+    }
+
+    /// Report sizes and latency of every codec run to `monitoring`
+    pub fn with_monitoring(mut self, monitoring: Arc<MonitoringManager>) -> Self {
+        self.monitoring = Some(monitoring);
+        self
+    }
+
+    fn record(&self, format: &str, operation: &str, bytes_in: usize, bytes_out: usize, started: Instant) {
+        if let Some(monitoring) = &self.monitoring {
+            let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+            monitoring.record_compression(format, operation, bytes_in, bytes_out, latency_ms);
         }
     }
 
     pub fn compress(&self, data: &[u8]) -> Result<CompressedPayload> {
+        let started = Instant::now();
+        let result = self.compress_best(data)?;
+        self.record(result.compression_type.label(), "compress", result.original_size, result.compressed_size, started);
+        Ok(result)
+    }
+
+    fn compress_best(&self, data: &[u8]) -> Result<CompressedPayload> {
         if data.len() < self.compression_threshold {
             return Ok(CompressedPayload {
                 data: data.to_vec(),
@@ -59,12 +97,11 @@ impl PayloadCompressor {
             let ratio = gzip_data.len() as f64 / data.len() as f64;
             if ratio < self.max_compression_ratio && gzip_data.len() < best_result.compressed_size {
                 best_result = CompressedPayload {
+                    compressed_size: gzip_data.len(),
                     data: gzip_data,
                     compression_type: CompressionType::Gzip,
                     original_size: data.len(),
-                    compressed_size: best_result.compressed_size,
                 };
-                best_result.compressed_size = best_result.data.len();
             }
         }
 
@@ -73,12 +110,11 @@ impl PayloadCompressor {
             let ratio = deflate_data.len() as f64 / data.len() as f64;
             if ratio < self.max_compression_ratio && deflate_data.len() < best_result.compressed_size {
                 best_result = CompressedPayload {
+                    compressed_size: deflate_data.len(),
                     data: deflate_data,
                     compression_type: CompressionType::Deflate,
                     original_size: data.len(),
-                    compressed_size: best_result.compressed_size,
                 };
-                best_result.compressed_size = best_result.data.len();
             }
         }
 
@@ -87,12 +123,11 @@ impl PayloadCompressor {
             let ratio = lz4_data.len() as f64 / data.len() as f64;
             if ratio < self.max_compression_ratio && lz4_data.len() < best_result.compressed_size {
                 best_result = CompressedPayload {
+                    compressed_size: lz4_data.len(),
                     data: lz4_data,
                     compression_type: CompressionType::LZ4,
                     original_size: data.len(),
-                    compressed_size: best_result.compressed_size,
                 };
-                best_result.compressed_size = best_result.data.len();
             }
         }
 
@@ -100,6 +135,13 @@ impl PayloadCompressor {
     }
 
     pub fn decompress(&self, payload: &CompressedPayload) -> Result<Vec<u8>> {
+        let started = Instant::now();
+        let decompressed = self.decompress_raw(payload)?;
+        self.record(payload.compression_type.label(), "decompress", payload.data.len(), decompressed.len(), started);
+        Ok(decompressed)
+    }
+
+    fn decompress_raw(&self, payload: &CompressedPayload) -> Result<Vec<u8>> {
         match payload.compression_type {
             CompressionType::None => Ok(payload.data.clone()),
             CompressionType::Gzip => self.decompress_gzip(&payload.data),
@@ -114,7 +156,13 @@ impl PayloadCompressor {
 
     /// Decompress transaction payload using Protobuf and CBOR (async version)
     pub async fn decompress_transaction_payload(&mut self, compressed_data: &[u8]) -> Result<DecompressionResult> {
-        self.protobuf_compressor.decompress_transaction_payload(compressed_data).await
+        let started = Instant::now();
+        let result = self.protobuf_compressor.decompress_transaction_payload(compressed_data).await?;
+        if result.success {
+            let decoded_size = serde_json::to_vec(&result.data).map(|v| v.len()).unwrap_or(0);
+            self.record(&result.format, "decompress", compressed_data.len(), decoded_size, started);
+        }
+        Ok(result)
     }
 
     /// Try to decompress data with fallback to JSON (async version)
@@ -130,7 +178,12 @@ impl PayloadCompressor {
 
     /// Compress transaction payload using Protobuf and CBOR (async version)
     pub async fn compress_transaction_payload(&mut self, transaction_data: &serde_json::Value) -> Result<Vec<u8>> {
-        self.protobuf_compressor.compress_transaction_payload(transaction_data).await
+        let started = Instant::now();
+        let compressed = self.protobuf_compressor.compress_transaction_payload(transaction_data).await?;
+        // Measured against the JSON the payload would otherwise travel as
+        let original_size = serde_json::to_vec(transaction_data).map(|v| v.len()).unwrap_or(0);
+        self.record(CompressionType::ProtobufCbor.label(), "compress", original_size, compressed.len(), started);
+        Ok(compressed)
     }
 
     fn compress_gzip(&self, data: &[u8]) -> Result<Vec<u8>> {
@@ -215,7 +268,11 @@ impl PayloadCompressor {
     pub fn get_compression_stats(&self, payload: &CompressedPayload) -> HashMap<String, f64> {
         let mut stats = HashMap::new();
         
-        let compression_ratio = payload.compressed_size as f64 / payload.original_size as f64;
+        let compression_ratio = if payload.original_size > 0 {
+            payload.compressed_size as f64 / payload.original_size as f64
+        } else {
+            1.0
+        };
         let space_saved = 1.0 - compression_ratio;
         
         stats.insert("compression_ratio".to_string(), compression_ratio);
@@ -292,6 +349,24 @@ mod tests {
         assert!(stats.contains_key("compressed_size_bytes"));
     }
 
+    #[tokio::test]
+    async fn test_compression_records_real_sizes() {
+        let monitoring = Arc::new(MonitoringManager::new());
+        let compressor = PayloadCompressor::new().with_monitoring(Arc::clone(&monitoring));
+        let test_data = "This is a test string that should be compressed. ".repeat(100);
+
+        let compressed = compressor.compress(test_data.as_bytes()).unwrap();
+        assert_eq!(compressed.compressed_size, compressed.data.len());
+        assert!(compressed.compressed_size < compressed.original_size);
+
+        let metrics = monitoring.get_compression_metrics();
+        let entry = metrics.iter().find(|m| m.operation == "compress").unwrap();
+        assert_eq!(entry.format, compressed.compression_type.label());
+        assert_eq!(entry.bytes_in, test_data.len() as u64);
+        assert_eq!(entry.bytes_out, compressed.data.len() as u64);
+        assert!(monitoring.render_compression_metrics().contains("airchainpay_compression_duration_ms_bucket"));
+    }
+
     #[tokio::test]
     async fn test_protobuf_compression() {
         let mut compressor = PayloadCompressor::new();
@@ -0,0 +1,90 @@
+use ethers::types::H256;
+use ethers::utils::keccak256;
+
+/// Leaf for a payment transaction hash, matching `keccak256(abi.encodePacked(txHash))`
+pub fn leaf(tx_hash: H256) -> H256 {
+    H256(keccak256(tx_hash.as_bytes()))
+}
+
+/// Pairs are hashed in sorted order, as OpenZeppelin's `MerkleProof` expects,
+/// so proofs need no left/right flags
+fn hash_pair(a: H256, b: H256) -> H256 {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    let mut buf = [0u8; 64];
+    buf[..32].copy_from_slice(lo.as_bytes());
+    buf[32..].copy_from_slice(hi.as_bytes());
+    H256(keccak256(buf))
+}
+
+/// Build the next level up; an unpaired last node is carried up unchanged
+fn next_level(level: &[H256]) -> Vec<H256> {
+    level.chunks(2)
+        .map(|pair| match pair {
+            [a, b] => hash_pair(*a, *b),
+            [a] => *a,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Merkle root over `leaves` in the given order, or `None` when empty
+pub fn root(leaves: &[H256]) -> Option<H256> {
+    if leaves.is_empty() {
+        return None;
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    Some(level[0])
+}
+
+/// Sibling hashes from the leaf at `index` up to the root
+pub fn proof(leaves: &[H256], mut index: usize) -> Option<Vec<H256>> {
+    if index >= leaves.len() {
+        return None;
+    }
+    let mut proof = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            proof.push(level[sibling]);
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(proof)
+}
+
+pub fn verify(proof: &[H256], root: H256, leaf: H256) -> bool {
+    proof.iter().fold(leaf, |node, sibling| hash_pair(node, *sibling)) == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(n: u8) -> Vec<H256> {
+        (0..n).map(|i| leaf(H256::repeat_byte(i + 1))).collect()
+    }
+
+    #[test]
+    fn test_every_leaf_proves_against_root() {
+        for n in 1..=7 {
+            let leaves = leaves(n);
+            let root = root(&leaves).unwrap();
+            for (i, leaf) in leaves.iter().enumerate() {
+                assert!(verify(&proof(&leaves, i).unwrap(), root, *leaf), "leaf {i} of {n}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_proof_rejects_foreign_leaf() {
+        let leaves = leaves(4);
+        let root = root(&leaves).unwrap();
+        let proof = proof(&leaves, 0).unwrap();
+        assert!(!verify(&proof, root, leaf(H256::repeat_byte(0xff))));
+    }
+}
@@ -1,4 +1,5 @@
 pub mod protobuf_compressor;
+pub mod payload_codec;
 pub mod sanitizer;
 pub mod database;
 pub mod cache;
@@ -8,4 +9,6 @@ pub mod cleanup;
 pub mod prometheus;
 pub mod error_handler;
 pub mod critical_error_handler;
+pub mod request_id;
+pub mod merkle;
 pub mod animated_ascii; 
\ No newline at end of file
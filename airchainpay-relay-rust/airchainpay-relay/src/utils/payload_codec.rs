@@ -0,0 +1,267 @@
+//! Versioned codec for compressed payloads
+//!
+//! A frame is the magic bytes `ACPZ`, a one-byte codec version and a
+//! protobuf `CompressedPayload` (see `src/proto/transaction.proto`). The
+//! body is a protobuf `SignedTransaction` or `TransactionPayload` when the
+//! payload has exactly that shape and CBOR otherwise, LZ4-compressed when
+//! that makes it smaller. Input without the magic is the earlier format:
+//! bare LZ4-compressed CBOR, or JSON. The wallet core writes the same
+//! frames from the same schema (`shared::payload_codec`).
+
+use anyhow::{anyhow, bail, Result};
+use prost::Message as ProstMessage;
+use serde_json::{json, Map, Value};
+use std::io::{Read, Write};
+use crate::airchainpay::{CompressedPayload, PayloadCompression, PayloadEncoding, SignedTransaction, Token, TransactionPayload};
+
+pub const PAYLOAD_MAGIC: &[u8; 4] = b"ACPZ";
+/// Version written by `encode`
+pub const PAYLOAD_CODEC_VERSION: u8 = 1;
+/// Versions this relay decodes, oldest first
+pub const PAYLOAD_CODEC_VERSIONS: &[u8] = &[PAYLOAD_CODEC_VERSION];
+/// Largest body a frame may expand to
+pub(crate) const MAX_UNCOMPRESSED_SIZE: usize = 1024 * 1024;
+
+/// Highest codec version both sides speak, from the `payload_codecs` a
+/// peer lists in its control message. Peers that list none get the legacy
+/// format.
+pub fn negotiate_version(control: &Value) -> Option<u8> {
+    control.get("payload_codecs")?
+        .as_array()?
+        .iter()
+        .filter_map(|v| v.as_u64())
+        .filter_map(|v| u8::try_from(v).ok())
+        .filter(|v| PAYLOAD_CODEC_VERSIONS.contains(v))
+        .max()
+}
+
+pub fn is_framed(data: &[u8]) -> bool {
+    data.len() > PAYLOAD_MAGIC.len() && data.starts_with(PAYLOAD_MAGIC)
+}
+
+/// Encode a JSON payload as a current-version frame
+pub fn encode(value: &Value) -> Result<Vec<u8>> {
+    let (encoding, body) = if let Some(tx) = as_signed_transaction(value) {
+        (PayloadEncoding::SignedTransaction, tx.encode_to_vec())
+    } else if let Some(payload) = as_transaction_payload(value) {
+        (PayloadEncoding::Transaction, payload.encode_to_vec())
+    } else {
+        let cbor = cbor4ii::serde::to_vec(Vec::new(), value)
+            .map_err(|e| anyhow!("CBOR serialization failed: {}", e))?;
+        (PayloadEncoding::Cbor, cbor)
+    };
+
+    let uncompressed_size = u32::try_from(body.len()).map_err(|_| anyhow!("Payload too large"))?;
+    let compressed = lz4_compress(&body)?;
+    let (compression, body) = if compressed.len() < body.len() {
+        (PayloadCompression::Lz4, compressed)
+    } else {
+        (PayloadCompression::None, body)
+    };
+
+    let envelope = CompressedPayload {
+        encoding: encoding as i32,
+        compression: compression as i32,
+        body,
+        uncompressed_size,
+    };
+    let mut frame = Vec::with_capacity(PAYLOAD_MAGIC.len() + 1 + envelope.encoded_len());
+    frame.extend_from_slice(PAYLOAD_MAGIC);
+    frame.push(PAYLOAD_CODEC_VERSION);
+    envelope.encode(&mut frame)?;
+    Ok(frame)
+}
+
+/// Decode a frame of any supported version back to JSON
+pub fn decode(frame: &[u8]) -> Result<Value> {
+    let rest = frame.strip_prefix(PAYLOAD_MAGIC.as_slice())
+        .ok_or_else(|| anyhow!("Not a framed payload"))?;
+    let (&version, envelope) = rest.split_first()
+        .ok_or_else(|| anyhow!("Truncated payload frame"))?;
+    if !PAYLOAD_CODEC_VERSIONS.contains(&version) {
+        bail!("Payload codec version {} is not supported; this relay speaks {:?}", version, PAYLOAD_CODEC_VERSIONS);
+    }
+
+    let envelope = CompressedPayload::decode(envelope)
+        .map_err(|e| anyhow!("Invalid payload envelope: {}", e))?;
+    let size = envelope.uncompressed_size as usize;
+    if size > MAX_UNCOMPRESSED_SIZE {
+        bail!("Payload expands to {} bytes, above the {} byte limit", size, MAX_UNCOMPRESSED_SIZE);
+    }
+    let body = match PayloadCompression::try_from(envelope.compression) {
+        Ok(PayloadCompression::None) => envelope.body,
+        Ok(PayloadCompression::Lz4) => lz4_decompress(&envelope.body, size)?,
+        Err(_) => bail!("Unknown payload compression {}", envelope.compression),
+    };
+    if body.len() != size {
+        bail!("Payload body is {} bytes, expected {}", body.len(), size);
+    }
+
+    match PayloadEncoding::try_from(envelope.encoding) {
+        Ok(PayloadEncoding::Cbor) => cbor4ii::serde::from_slice(&body)
+            .map_err(|e| anyhow!("CBOR deserialization failed: {}", e)),
+        Ok(PayloadEncoding::SignedTransaction) => Ok(signed_transaction_json(&SignedTransaction::decode(body.as_slice())?)),
+        Ok(PayloadEncoding::Transaction) => Ok(transaction_payload_json(&TransactionPayload::decode(body.as_slice())?)),
+        _ => bail!("Unknown payload encoding {}", envelope.encoding),
+    }
+}
+
+/// Decode the format sent before frames: bare LZ4-compressed CBOR, or JSON
+pub fn decode_legacy(data: &[u8]) -> Result<Value> {
+    if let Ok(decompressed) = lz4_decompress(data, MAX_UNCOMPRESSED_SIZE) {
+        if let Ok(value) = cbor4ii::serde::from_slice::<Value>(&decompressed) {
+            return Ok(value);
+        }
+    }
+    serde_json::from_slice(data).map_err(|_| anyhow!("Payload is neither a frame, LZ4 CBOR nor JSON"))
+}
+
+fn lz4_compress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = lz4::EncoderBuilder::new()
+        .level(1) // Fast compression
+        .build(Vec::new())
+        .map_err(|e| anyhow!("LZ4 compression failed: {}", e))?;
+    encoder.write_all(data)
+        .map_err(|e| anyhow!("Failed to write data for compression: {}", e))?;
+    let (compressed, result) = encoder.finish();
+    result.map_err(|e| anyhow!("Failed to finish compression: {}", e))?;
+    Ok(compressed)
+}
+
+/// Decompress at most `limit` bytes, failing on anything larger
+pub(crate) fn lz4_decompress(data: &[u8], limit: usize) -> Result<Vec<u8>> {
+    let decoder = lz4::Decoder::new(data)
+        .map_err(|e| anyhow!("LZ4 decompression failed: {}", e))?;
+    let mut decompressed = Vec::new();
+    decoder.take(limit as u64 + 1).read_to_end(&mut decompressed)
+        .map_err(|e| anyhow!("Failed to read decompressed data: {}", e))?;
+    if decompressed.len() > limit {
+        bail!("Payload expands past {} bytes", limit);
+    }
+    Ok(decompressed)
+}
+
+/// `{"signed_tx": "0x…", "chain_id": n}` and nothing else, with lowercase
+/// hex so the frame decodes to the same JSON
+fn as_signed_transaction(value: &Value) -> Option<SignedTransaction> {
+    let object = value.as_object()?;
+    if object.len() != 2 {
+        return None;
+    }
+    let signed_tx = object.get("signed_tx")?.as_str()?;
+    let raw_transaction = hex::decode(signed_tx.strip_prefix("0x")?).ok()?;
+    if format!("0x{}", hex::encode(&raw_transaction)) != signed_tx {
+        return None;
+    }
+    Some(SignedTransaction { raw_transaction, chain_id: object.get("chain_id")?.as_u64()? })
+}
+
+fn signed_transaction_json(tx: &SignedTransaction) -> Value {
+    json!({
+        "signed_tx": format!("0x{}", hex::encode(&tx.raw_transaction)),
+        "chain_id": tx.chain_id,
+    })
+}
+
+/// A `TransactionPayload`-shaped object, if it survives the round trip
+/// unchanged
+fn as_transaction_payload(value: &Value) -> Option<TransactionPayload> {
+    let object = value.as_object()?;
+    let text = |object: &Map<String, Value>, key: &str| object.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    let token = match object.get("token") {
+        Some(token) => {
+            let token = token.as_object()?;
+            Some(Token {
+                symbol: text(token, "symbol"),
+                name: text(token, "name"),
+                decimals: u32::try_from(token.get("decimals")?.as_u64()?).ok()?,
+                address: text(token, "address"),
+                chain_id: text(token, "chainId"),
+                is_native: token.get("isNative")?.as_bool()?,
+            })
+        }
+        None => None,
+    };
+    let payload = TransactionPayload {
+        to: text(object, "to"),
+        amount: text(object, "amount"),
+        chain_id: text(object, "chainId"),
+        token,
+        payment_reference: text(object, "paymentReference"),
+        timestamp: object.get("timestamp")?.as_u64()?,
+        version: text(object, "version"),
+        r#type: text(object, "type"),
+    };
+    (transaction_payload_json(&payload) == *value).then_some(payload)
+}
+
+fn transaction_payload_json(payload: &TransactionPayload) -> Value {
+    let mut object = json!({
+        "to": payload.to,
+        "amount": payload.amount,
+        "chainId": payload.chain_id,
+        "paymentReference": payload.payment_reference,
+        "timestamp": payload.timestamp,
+        "version": payload.version,
+        "type": payload.r#type,
+    });
+    if let Some(token) = &payload.token {
+        object["token"] = json!({
+            "symbol": token.symbol,
+            "name": token.name,
+            "decimals": token.decimals,
+            "address": token.address,
+            "chainId": token.chain_id,
+            "isNative": token.is_native,
+        });
+    }
+    object
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_round_trip_and_legacy_still_decodes() {
+        let payment = json!({"signed_tx": format!("0x{}", "ab".repeat(120)), "chain_id": 84532});
+        let frame = encode(&payment).unwrap();
+        assert!(is_framed(&frame));
+        // Raw bytes instead of hex: well under the JSON size
+        assert!(frame.len() < serde_json::to_vec(&payment).unwrap().len() / 2);
+        assert_eq!(decode(&frame).unwrap(), payment);
+
+        let other = json!({"memo": "coffee", "items": [1, 2, 3]});
+        assert_eq!(decode(&encode(&other).unwrap()).unwrap(), other);
+
+        let mut future = frame.clone();
+        future[PAYLOAD_MAGIC.len()] = 9;
+        assert!(decode(&future).unwrap_err().to_string().contains("not supported"));
+
+        let legacy = lz4_compress(&cbor4ii::serde::to_vec(Vec::new(), &payment).unwrap()).unwrap();
+        assert!(!is_framed(&legacy));
+        assert_eq!(decode_legacy(&legacy).unwrap(), payment);
+
+        assert_eq!(negotiate_version(&json!({"payload_codecs": [1, 9]})), Some(1));
+        assert_eq!(negotiate_version(&json!({"type": "hello"})), None);
+    }
+
+    #[test]
+    fn test_shared_fixture_frames() {
+        // The same frames are decoded by the wallet core's tests
+        let frame = hex::decode(include_str!("../proto/fixtures/signed_transaction.hex").trim()).unwrap();
+        assert_eq!(
+            decode(&frame).unwrap(),
+            json!({"signed_tx": format!("0x{}", "ab".repeat(400)), "chain_id": 84532}),
+        );
+
+        let frame = hex::decode(include_str!("../proto/fixtures/transaction_payload.hex").trim()).unwrap();
+        let decoded = decode(&frame).unwrap();
+        assert_eq!(decoded["to"], "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6");
+        assert_eq!(decoded["paymentReference"], "order-7");
+        assert_eq!(decoded["timestamp"], 1_700_000_000);
+        assert_eq!(decoded["token"]["symbol"], "USDC");
+        assert_eq!(decoded["token"]["decimals"], 6);
+        assert_eq!(encode(&decoded).unwrap(), frame);
+    }
+}
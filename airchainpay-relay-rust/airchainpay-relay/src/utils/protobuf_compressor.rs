@@ -2,7 +2,7 @@
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use base64::{engine::general_purpose, Engine as _};
-use std::io::Write;
+use crate::utils::payload_codec;
 
 // Include the generated protobuf code
 pub mod airchainpay {
@@ -55,17 +55,30 @@ impl ProtobufCompressor {
         Ok(())
     }
 
-    /// Decompress transaction payload using Protobuf and CBOR
+    /// Decompress a transaction payload: a versioned frame from
+    /// `payload_codec`, or the earlier bare LZ4-compressed CBOR, or JSON
     pub async fn decompress_transaction_payload(&mut self, compressed_data: &[u8]) -> Result<DecompressionResult> {
         self.initialize()?;
-        
-        // Try to decompress using LZ4 first
-        let mut decompressed = Vec::new();
-        let mut decoder = lz4::Decoder::new(compressed_data)
-            .map_err(|e| anyhow::anyhow!("LZ4 decompression failed: {}", e))?;
-        
-        std::io::copy(&mut decoder, &mut decompressed)
-            .map_err(|e| anyhow::anyhow!("Failed to read decompressed data: {}", e))?;
+
+        if payload_codec::is_framed(compressed_data) {
+            return Ok(match payload_codec::decode(compressed_data) {
+                Ok(data) => DecompressionResult {
+                    data,
+                    format: "protobuf_cbor".to_string(),
+                    success: true,
+                    error: None,
+                },
+                Err(e) => DecompressionResult {
+                    data: serde_json::Value::Null,
+                    format: "protobuf_cbor".to_string(),
+                    success: false,
+                    error: Some(format!("Failed to decompress: {}", e)),
+                },
+            });
+        }
+
+        // Try the legacy LZ4-compressed CBOR first
+        let decompressed = payload_codec::lz4_decompress(compressed_data, payload_codec::MAX_UNCOMPRESSED_SIZE)?;
         
         // Try to deserialize as CBOR
         match cbor4ii::serde::from_slice::<serde_json::Value>(&decompressed) {
@@ -119,27 +132,10 @@ impl ProtobufCompressor {
         }
     }
 
-    /// Compress transaction payload using Protobuf and CBOR
+    /// Compress a transaction payload as a current-version frame
     pub async fn compress_transaction_payload(&mut self, transaction_data: &serde_json::Value) -> Result<Vec<u8>> {
         self.initialize()?;
-        
-        // Convert JSON to CBOR for compression
-        let cbor_data = cbor4ii::serde::to_vec(Vec::new(), transaction_data)
-            .map_err(|e| anyhow::anyhow!("CBOR serialization failed: {}", e))?;
-        
-        // Use LZ4 compression on the CBOR data
-        let mut compressed = Vec::new();
-        let mut encoder = lz4::EncoderBuilder::new()
-            .level(1) // Fast compression
-            .build(&mut compressed)
-            .map_err(|e| anyhow::anyhow!("LZ4 compression failed: {}", e))?;
-        
-        encoder.write_all(&cbor_data)
-            .map_err(|e| anyhow::anyhow!("Failed to write data for compression: {}", e))?;
-        
-        let (compressed, result) = encoder.finish();
-        result.map_err(|e| anyhow::anyhow!("Failed to finish compression: {}", e))?;
-        Ok(compressed.to_vec())
+        payload_codec::encode(transaction_data)
     }
 
     /// Get compression statistics
@@ -158,17 +154,15 @@ impl ProtobufCompressor {
 
     // Private helper methods
 
-    fn try_decompress_protobuf_cbor(&self, _compressed_data: &[u8], payload_type: &str) -> Result<DecompressionResult> {
+    fn try_decompress_protobuf_cbor(&self, compressed_data: &[u8], payload_type: &str) -> Result<DecompressionResult> {
         // Only support generic transaction payloads
-        let json_value = match payload_type {
-            "transaction" => {
-                // TODO: The following functions are commented out because the protobuf types are missing.
-                // Uncomment and implement when the types are available.
-                // let payload = TransactionPayload::decode(protobuf_data.as_slice())?;
-                // self.transaction_payload_to_json(payload)?
-                serde_json::Value::Null
-            }
-            _ => return Err(anyhow!("Unknown payload type: {}", payload_type)),
+        if payload_type != "transaction" {
+            return Err(anyhow!("Unknown payload type: {}", payload_type));
+        }
+        let json_value = if payload_codec::is_framed(compressed_data) {
+            payload_codec::decode(compressed_data)?
+        } else {
+            payload_codec::decode_legacy(compressed_data)?
         };
 
         Ok(DecompressionResult {
@@ -207,66 +201,6 @@ impl ProtobufCompressor {
             }
         }
     }
-
-    // TODO: The following functions are commented out because the protobuf types are missing.
-    // Uncomment and implement when the types are available.
-    // fn transaction_payload_to_json(&self, payload: TransactionPayload) -> Result<serde_json::Value> {
-    //     let mut obj = serde_json::Map::new();
-    //     
-    //     obj.insert("to".to_string(), serde_json::Value::String(payload.to));
-    //     obj.insert("amount".to_string(), serde_json::Value::String(payload.amount));
-    //     obj.insert("chainId".to_string(), serde_json::Value::String(payload.chain_id));
-    //     obj.insert("paymentReference".to_string(), serde_json::Value::String(payload.payment_reference));
-    //     obj.insert("timestamp".to_string(), serde_json::Value::Number(payload.timestamp.into()));
-    //     obj.insert("version".to_string(), serde_json::Value::String(payload.version));
-    //     obj.insert("type".to_string(), serde_json::Value::String(payload.r#type));
-
-    //     if let Some(token) = payload.token {
-    //         obj.insert("token".to_string(), self.token_to_json(token)?);
-    //     }
-
-    //     if let Some(metadata) = payload.metadata {
-    //         obj.insert("metadata".to_string(), self.payment_metadata_to_json(metadata)?);
-    //     }
-
-    //     Ok(serde_json::Value::Object(obj))
-    // }
-
-    // TODO: The following functions are commented out because the protobuf types are missing.
-    // Uncomment and implement when the types are available.
-    // fn token_to_json(&self, token: Token) -> Result<serde_json::Value> {
-    //     let mut obj = serde_json::Map::new();
-    //     
-    //     obj.insert("symbol".to_string(), serde_json::Value::String(token.symbol));
-    //     obj.insert("name".to_string(), serde_json::Value::String(token.name));
-    //     obj.insert("decimals".to_string(), serde_json::Value::Number(token.decimals.into()));
-    //     obj.insert("address".to_string(), serde_json::Value::String(token.address));
-    //     obj.insert("chainId".to_string(), serde_json::Value::String(token.chain_id));
-    //     obj.insert("isNative".to_string(), serde_json::Value::Bool(token.is_native));
-
-    //     Ok(serde_json::Value::Object(obj))
-    // }
-
-    // TODO: The following functions are commented out because the protobuf types are missing.
-    // Uncomment and implement when the types are available.
-    // fn payment_metadata_to_json(&self, metadata: PaymentMetadata) -> Result<serde_json::Value> {
-    //     let mut obj = serde_json::Map::new();
-    //     
-    //     obj.insert("merchant".to_string(), serde_json::Value::String(metadata.merchant));
-    //     obj.insert("location".to_string(), serde_json::Value::String(metadata.location));
-    //     obj.insert("maxAmount".to_string(), serde_json::Value::String(metadata.max_amount));
-    //     obj.insert("minAmount".to_string(), serde_json::Value::String(metadata.min_amount));
-    //     obj.insert("expiry".to_string(), serde_json::Value::Number(metadata.expiry.into()));
-    //     obj.insert("timestamp".to_string(), serde_json::Value::Number(metadata.timestamp.into()));
-
-    //     let mut extra_obj = serde_json::Map::new();
-    //     for (key, value) in metadata.extra {
-    //         extra_obj.insert(key, serde_json::Value::String(value));
-    //     }
-    //     obj.insert("extra".to_string(), serde_json::Value::Object(extra_obj));
-
-    //     Ok(serde_json::Value::Object(obj))
-    // }
 }
 
 impl Default for ProtobufCompressor {
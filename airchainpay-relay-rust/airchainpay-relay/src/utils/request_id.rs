@@ -0,0 +1,51 @@
+use std::future::Future;
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// Request id of the call being handled on this task, if any
+pub fn current() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// The current request id, or a fresh one outside of a request
+pub fn current_or_new() -> String {
+    current().unwrap_or_else(generate)
+}
+
+pub fn generate() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Incoming ids are echoed into logs and upstream headers, so keep them short and inert
+pub fn is_valid(id: &str) -> bool {
+    (1..=128).contains(&id.len())
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+/// Run `fut` with `id` as the current request id, inside a `request` span
+/// whose fields are attached to every log line emitted while it runs
+pub async fn scope<F: Future>(id: String, fut: F) -> F::Output {
+    let span = request_span(&id);
+    scope_in(id, span, fut).await
+}
+
+/// The `request` span `scope` runs in, for callers that need to adjust it
+/// (e.g. set a remote trace parent) before entering it
+pub fn request_span(id: &str) -> tracing::Span {
+    tracing::info_span!("request", request_id = %id, device_id = tracing::field::Empty)
+}
+
+/// Like `scope`, inside a span built with `request_span`
+pub async fn scope_in<F: Future>(id: String, span: tracing::Span, fut: F) -> F::Output {
+    CURRENT_REQUEST_ID.scope(id, fut.instrument(span)).await
+}
+
+/// Attach the calling device to the current request span
+pub fn record_device(device_id: &str) {
+    tracing::Span::current().record("device_id", device_id);
+}
@@ -1,2 +1,2 @@
 pub mod transaction_validator;
- 
\ No newline at end of file
+pub mod payload_tier;
@@ -0,0 +1,115 @@
+use ethers::types::Transaction;
+use ethers::core::utils::rlp::{Decodable, Rlp};
+use serde::{Deserialize, Serialize};
+
+/// How much of the submission pipeline a payload goes through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadTier {
+    /// A small plain signed transaction: decoded once and checked in place
+    Fast,
+    /// Everything else: network health check and the full validator
+    Full,
+}
+
+impl PayloadTier {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PayloadTier::Fast => "fast",
+            PayloadTier::Full => "full",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayloadTierConfig {
+    pub fast_path_enabled: bool,
+    /// Largest decoded transaction, in bytes, eligible for the fast path.
+    /// A native transfer is about 110 bytes and an ERC-20 transfer about 180.
+    pub fast_path_max_bytes: usize,
+}
+
+impl Default for PayloadTierConfig {
+    fn default() -> Self {
+        Self {
+            fast_path_enabled: true,
+            fast_path_max_bytes: 512,
+        }
+    }
+}
+
+impl PayloadTierConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            fast_path_enabled: std::env::var("PAYLOAD_FAST_PATH_ENABLED")
+                .map(|v| v != "false")
+                .unwrap_or(defaults.fast_path_enabled),
+            fast_path_max_bytes: std::env::var("PAYLOAD_FAST_PATH_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.fast_path_max_bytes),
+        }
+    }
+
+    /// Decode `signed_tx` if it qualifies for the fast path: 0x-prefixed
+    /// hex of at most `fast_path_max_bytes` that is a plain RLP-encoded
+    /// signed transaction. Compressed or wrapped payloads return `None` and
+    /// take the full pipeline.
+    pub fn fast_path(&self, signed_tx: &str) -> Option<Transaction> {
+        if !self.fast_path_enabled {
+            return None;
+        }
+        let hex_body = signed_tx.strip_prefix("0x")?;
+        if hex_body.len() / 2 > self.fast_path_max_bytes {
+            return None;
+        }
+        let bytes = hex::decode(hex_body).ok()?;
+        Transaction::decode(&Rlp::new(&bytes)).ok()
+    }
+
+    pub fn classify(&self, signed_tx: &str) -> PayloadTier {
+        if self.fast_path(signed_tx).is_some() {
+            PayloadTier::Fast
+        } else {
+            PayloadTier::Full
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::types::TransactionRequest;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_small_signed_tx_takes_fast_path() {
+        let wallet = LocalWallet::from_str("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")
+            .unwrap()
+            .with_chain_id(84532u64);
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(wallet.address())
+            .value(1u64)
+            .gas(21_000u64)
+            .gas_price(1_000_000_000u64)
+            .nonce(0u64)
+            .chain_id(84532u64)
+            .into();
+        let signature = wallet.sign_transaction_sync(&tx).unwrap();
+        let signed_tx = format!("0x{}", hex::encode(tx.rlp_signed(&signature)));
+
+        let config = PayloadTierConfig::default();
+        assert_eq!(config.classify(&signed_tx), PayloadTier::Fast);
+        assert_eq!(config.fast_path(&signed_tx).unwrap().recover_from().unwrap(), wallet.address());
+
+        let compressed = format!("0x04224d18{}", "ab".repeat(64));
+        assert_eq!(config.classify(&compressed), PayloadTier::Full);
+        let tight = PayloadTierConfig { fast_path_max_bytes: 16, ..PayloadTierConfig::default() };
+        assert_eq!(tight.classify(&signed_tx), PayloadTier::Full);
+        let disabled = PayloadTierConfig { fast_path_enabled: false, ..PayloadTierConfig::default() };
+        assert_eq!(disabled.classify(&signed_tx), PayloadTier::Full);
+    }
+}
@@ -83,6 +83,51 @@ impl TransactionValidator {
         Ok(result)
     }
 
+    /// Checks for a transaction the fast path has already decoded. Skips
+    /// the raw-payload stages and re-decoding, but keeps the chain, gas,
+    /// contract, amount and rate-limit policy of `validate_transaction`,
+    /// and requires the signature to recover a sender.
+    pub async fn validate_decoded(&self, tx: &Transaction, chain_id: u64) -> ValidationResult {
+        let mut result = ValidationResult {
+            valid: true,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let mut fail = |error: String| {
+            result.valid = false;
+            result.errors.push(error);
+        };
+
+        if let Err(e) = self.validate_chain_id(chain_id) {
+            fail(format!("Invalid chain ID: {e}"));
+        }
+        if let Some(tx_chain_id) = tx.chain_id.map(|id| id.as_u64()) {
+            if tx_chain_id != chain_id {
+                fail(format!("Invalid chain ID: transaction is signed for chain {tx_chain_id}, submitted for {chain_id}"));
+            }
+        }
+        if let Err(e) = tx.recover_from() {
+            fail(format!("Invalid signature: {e}"));
+        }
+        let gas_limit = tx.gas.as_u64();
+        let max_gas_limit = self.max_gas_limit(chain_id);
+        if gas_limit == 0 {
+            fail("Invalid gas limits: Gas limit cannot be zero".to_string());
+        } else if gas_limit > max_gas_limit {
+            fail(format!("Invalid gas limits: Gas limit {gas_limit} exceeds max allowed {max_gas_limit}"));
+        }
+        if let Err(e) = self.check_contract_address(tx.to.map(|to| format!("0x{:x}", to)), chain_id) {
+            fail(format!("Invalid contract interaction: {e}"));
+        }
+        if let Err(e) = self.validate_transaction_amount(&tx.value.to_string()) {
+            fail(format!("Invalid transaction amount: {e}"));
+        }
+        if let Err(e) = self.check_rate_limits().await {
+            fail(format!("Rate limit exceeded: {e}"));
+        }
+        result
+    }
+
     fn validate_transaction_format(&self, signed_tx: &str) -> Result<()> {
         if signed_tx.is_empty() {
             return Err(anyhow!("Transaction is empty"));
@@ -165,7 +210,7 @@ impl TransactionValidator {
         self.decode_transaction(signed_tx).ok().and_then(|tx| tx.to.map(|to| format!("0x{:x}", to)))
     }
 
-    fn validate_gas_limits(&self, signed_tx: &str, chain_id: u64) -> Result<()> {
+    fn max_gas_limit(&self, chain_id: u64) -> u64 {
         // Set chain-specific default max gas limits
         // Base (ETH): much lower, Core (non-ETH): higher
         let base_eth_chain_ids = [84532u64, 17000u64]; // Base Sepolia, Ethereum Holesky
@@ -183,9 +228,13 @@ impl TransactionValidator {
         };
 
         // Use per-chain config if set, otherwise use the above default
-        let max_gas_limit = self.config.supported_chains.get(&chain_id)
+        self.config.supported_chains.get(&chain_id)
             .and_then(|chain_cfg| chain_cfg.max_gas_limit)
-            .unwrap_or(default_max_gas_limit);
+            .unwrap_or(default_max_gas_limit)
+    }
+
+    fn validate_gas_limits(&self, signed_tx: &str, chain_id: u64) -> Result<()> {
+        let max_gas_limit = self.max_gas_limit(chain_id);
         let gas_limit = self.extract_gas_limit_from_transaction(signed_tx)
             .ok_or_else(|| anyhow!("Failed to extract gas limit from transaction"))?;
         if gas_limit == 0 {
@@ -209,9 +258,13 @@ impl TransactionValidator {
     }
 
     fn validate_contract_interaction(&self, signed_tx: &str, chain_id: u64) -> Result<()> {
+        self.check_contract_address(self.extract_to_address_from_transaction(signed_tx), chain_id)
+    }
+
+    fn check_contract_address(&self, to_addr: Option<String>, chain_id: u64) -> Result<()> {
         if let Some(chain_cfg) = self.config.supported_chains.get(&chain_id) {
             if !chain_cfg.contract_address.is_empty() {
-                let to_addr = self.extract_to_address_from_transaction(signed_tx)
+                let to_addr = to_addr
                     .ok_or_else(|| anyhow!("Failed to extract 'to' address from transaction"))?;
                 
                 // Validate the extracted address using ethereum validation
@@ -301,4 +354,60 @@ impl TransactionValidator {
             }
         }
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::config::{ChainConfig, RateLimitConfig};
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::types::TransactionRequest;
+    use std::str::FromStr;
+
+    const CONTRACT: &str = "0x7b79117445c57eea1cefd5a08fe75e1af2fd7c9b";
+
+    fn signed(chain_id: u64, gas: u64, to: &str) -> Transaction {
+        let wallet = LocalWallet::from_str("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")
+            .unwrap()
+            .with_chain_id(chain_id);
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(ethers::types::Address::from_str(to).unwrap())
+            .value(1u64)
+            .gas(gas)
+            .gas_price(1_000_000_000u64)
+            .nonce(0u64)
+            .chain_id(chain_id)
+            .into();
+        let signature = wallet.sign_transaction_sync(&tx).unwrap();
+        Transaction::decode(&Rlp::new(&tx.rlp_signed(&signature))).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fast_path_rejects_what_the_full_path_rejects() {
+        let mut config = Config::default();
+        config.rate_limits = RateLimitConfig { window_ms: 60_000, max_requests: 4 };
+        config.supported_chains.insert(84532, ChainConfig {
+            contract_address: CONTRACT.to_string(),
+            ..ChainConfig::default()
+        });
+        let validator = TransactionValidator::new(Arc::new(config));
+
+        assert!(validator.validate_decoded(&signed(84532, 21_000, CONTRACT), 84532).await.valid);
+
+        let wrong_chain = validator.validate_decoded(&signed(1114, 21_000, CONTRACT), 84532).await;
+        assert!(!wrong_chain.valid);
+        assert!(wrong_chain.errors[0].starts_with("Invalid chain ID"));
+
+        let zero_gas = validator.validate_decoded(&signed(84532, 0, CONTRACT), 84532).await;
+        assert_eq!(zero_gas.errors, vec!["Invalid gas limits: Gas limit cannot be zero".to_string()]);
+
+        let other = "0x000000000000000000000000000000000000dead";
+        let not_allowed = validator.validate_decoded(&signed(84532, 21_000, other), 84532).await;
+        assert!(!not_allowed.valid);
+        assert!(not_allowed.errors[0].starts_with("Invalid contract interaction"));
+
+        // The fifth submission in the window is over the limit
+        let limited = validator.validate_decoded(&signed(84532, 21_000, CONTRACT), 84532).await;
+        assert!(limited.errors[0].starts_with("Rate limit exceeded"));
+    }
+}
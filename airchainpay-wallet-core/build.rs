@@ -41,6 +41,14 @@ fn main() {
         }
     }
     
+    // Compressed payload frames share the relay's schema
+    compile_payload_protos();
+
+    // Generate the C header for the FFI surface
+    if std::env::var_os("CARGO_FEATURE_FFI").is_some() {
+        generate_ffi_header();
+    }
+
     // Set architecture-specific features
     match target_arch.as_str() {
         "x86_64" => {
@@ -56,4 +64,27 @@ fn main() {
             println!("cargo:rustc-cfg=arch_unknown");
         }
     }
-} 
\ No newline at end of file
+}
+
+/// Write `include/airchainpay_wallet_core.h` from `src/ffi.rs`. A failure
+/// only warns, so the library still builds without a fresh header.
+fn generate_ffi_header() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".to_string());
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).unwrap_or_default();
+    match cbindgen::Builder::new().with_crate(&crate_dir).with_config(config).generate() {
+        Ok(bindings) => {
+            bindings.write_to_file(format!("{}/include/airchainpay_wallet_core.h", crate_dir));
+        }
+        Err(e) => println!("cargo:warning=Failed to generate FFI header: {}", e),
+    }
+}
+
+/// Generate the `airchainpay` protobuf messages from the relay's schema
+fn compile_payload_protos() {
+    let proto_dir = "../airchainpay-relay-rust/airchainpay-relay/src/proto";
+    println!("cargo:rerun-if-changed={}/transaction.proto", proto_dir);
+    prost_build::compile_protos(&[format!("{}/transaction.proto", proto_dir)], &[proto_dir])
+        .expect("Failed to compile the relay's transaction.proto");
+}
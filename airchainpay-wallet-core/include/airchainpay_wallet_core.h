@@ -0,0 +1,178 @@
+#ifndef AIRCHAINPAY_WALLET_CORE_H
+#define AIRCHAINPAY_WALLET_CORE_H
+
+/* Generated with cbindgen:0.29.0 */
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Error codes returned in `SecureResult::error_code`. Values are part of
+ * the ABI: they are never renumbered or reused.
+ */
+typedef enum WalletCoreErrorCode {
+  WALLET_CORE_ERROR_CODE_OK = 0,
+  WALLET_CORE_ERROR_CODE_INVALID_INPUT = 1,
+  WALLET_CORE_ERROR_CODE_INVALID_NETWORK = 2,
+  WALLET_CORE_ERROR_CODE_STORAGE_UNAVAILABLE = 3,
+  WALLET_CORE_ERROR_CODE_KEY_GENERATION_FAILED = 4,
+  WALLET_CORE_ERROR_CODE_PUBLIC_KEY_FAILED = 5,
+  WALLET_CORE_ERROR_CODE_ADDRESS_FAILED = 6,
+  WALLET_CORE_ERROR_CODE_WALLET_CREATION_FAILED = 7,
+  WALLET_CORE_ERROR_CODE_SERIALIZATION_FAILED = 8,
+  WALLET_CORE_ERROR_CODE_INVALID_SEED_PHRASE = 9,
+  WALLET_CORE_ERROR_CODE_SEED_DERIVATION_FAILED = 10,
+  WALLET_CORE_ERROR_CODE_KEY_NOT_FOUND = 11,
+  WALLET_CORE_ERROR_CODE_SIGNING_FAILED = 12,
+  WALLET_CORE_ERROR_CODE_VALIDATION_FAILED = 13,
+  WALLET_CORE_ERROR_CODE_DELETION_FAILED = 14,
+  WALLET_CORE_ERROR_CODE_STRING_CONVERSION_FAILED = 15,
+  WALLET_CORE_ERROR_CODE_BALANCE_FETCH_FAILED = 16,
+  WALLET_CORE_ERROR_CODE_ATTESTATION_UNAVAILABLE = 17,
+  WALLET_CORE_ERROR_CODE_ATTESTATION_FAILED = 18,
+  WALLET_CORE_ERROR_CODE_WALLET_NOT_FOUND = 19,
+  WALLET_CORE_ERROR_CODE_WALLET_ALREADY_EXISTS = 20,
+  WALLET_CORE_ERROR_CODE_CRYPTO_FAILED = 21,
+  WALLET_CORE_ERROR_CODE_NETWORK_FAILED = 22,
+  WALLET_CORE_ERROR_CODE_TRANSACTION_FAILED = 23,
+  WALLET_CORE_ERROR_CODE_CONFIGURATION_ERROR = 24,
+  WALLET_CORE_ERROR_CODE_NOT_IMPLEMENTED = 25,
+  WALLET_CORE_ERROR_CODE_INTERNAL = 26,
+} WalletCoreErrorCode;
+
+/**
+ * Secure FFI result wrapper
+ */
+typedef struct SecureResult {
+  bool success;
+  char *data;
+  int32_t error_code;
+} SecureResult;
+
+/**
+ * Host callback that writes a `KeyAttestation` as JSON into `out`. Returns
+ * the number of bytes written, or a negative value if the key cannot be attested.
+ */
+typedef int32_t (*KeyAttestationCallback)(const char *key_id,
+                                          const char *challenge_hex,
+                                          char *out,
+                                          size_t out_len);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Create a new wallet with secure key management. Returns the wallet as
+ * JSON; its `id` names it in later calls.
+ */
+struct SecureResult wallet_core_create_wallet(const char *name, int32_t network, char **error_out);
+
+/**
+ * Import an HD wallet from a BIP39 seed phrase. The wallet id is derived
+ * from the seed, so importing the same phrase twice fails with
+ * `WalletAlreadyExists`.
+ */
+struct SecureResult wallet_core_import_wallet(const char *name,
+                                              const char *seed_phrase,
+                                              int32_t network,
+                                              char **error_out);
+
+/**
+ * List the wallets loaded in this process as a JSON array, oldest first
+ */
+struct SecureResult wallet_core_list_wallets(char **error_out);
+
+/**
+ * Delete a wallet and erase its keys and seed from secure storage
+ */
+struct SecureResult wallet_core_delete_wallet(const char *wallet_id, char **error_out);
+
+/**
+ * Sign a message with a wallet's key. Returns the hex signature.
+ */
+struct SecureResult wallet_core_sign_message(const char *wallet_id,
+                                             const char *message,
+                                             char **error_out);
+
+/**
+ * Sign a transaction given as JSON (`to`, `value`, `nonce`, `gas_limit`,
+ * fee fields and `chain_id`, as in `Transaction`). Returns
+ * `{"raw_transaction": "0x…", "hash": "0x…"}`, ready for the relay.
+ */
+struct SecureResult wallet_core_sign_transaction(const char *wallet_id,
+                                                 const char *transaction_json,
+                                                 char **error_out);
+
+/**
+ * Back up a seed-phrase wallet as `count` SLIP-39 shares, any `threshold`
+ * of which restore it. `passphrase` may be null. Returns the shares as a
+ * JSON array of mnemonics.
+ */
+struct SecureResult wallet_core_backup_wallet(const char *wallet_id,
+                                              uint8_t threshold,
+                                              uint8_t count,
+                                              const char *passphrase,
+                                              char **error_out);
+
+/**
+ * Restore a wallet from a JSON array of SLIP-39 shares made by
+ * `wallet_core_backup_wallet`. Returns the wallet as JSON. The wallet id is
+ * derived from the recovered seed, so restoring a wallet that is already
+ * loaded fails with `WalletAlreadyExists`.
+ */
+struct SecureResult wallet_core_restore_wallet(const char *name,
+                                               const char *shares_json,
+                                               const char *passphrase,
+                                               int32_t network,
+                                               char **error_out);
+
+/**
+ * Get wallet balance (real on-chain query)
+ */
+struct SecureResult wallet_core_get_balance(const char *wallet_id, char **error_out);
+
+/**
+ * Validate a wallet's private key without exposing it
+ */
+struct SecureResult wallet_core_validate_wallet(const char *wallet_id, char **error_out);
+
+/**
+ * Run the integrity self-check against platform storage. Returns the
+ * report as JSON; the call succeeds even when checks fail, so read `passed`.
+ */
+struct SecureResult wallet_core_self_check(char **error_out);
+
+/**
+ * Register the host's key attestation callback; pass null to remove it
+ */
+void wallet_core_set_key_attestation_provider(KeyAttestationCallback callback);
+
+/**
+ * Export the platform attestation chain for a wallet's signing key as JSON,
+ * bound to a hex challenge from the relay
+ */
+struct SecureResult wallet_core_export_key_attestation(const char *wallet_id,
+                                                       const char *challenge,
+                                                       char **error_out);
+
+/**
+ * Free a C string with secure memory cleanup
+ */
+void wallet_core_free_string(char *ptr);
+
+/**
+ * Free a SecureResult with secure memory cleanup
+ */
+void wallet_core_free_result(struct SecureResult *result);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* AIRCHAINPAY_WALLET_CORE_H */
@@ -0,0 +1,157 @@
+//! `Transport` over one GATT characteristic of a peripheral, using the
+//! platform Bluetooth stack through `bluest`. Frames are written with
+//! response; notifications on the same characteristic are received.
+
+use crate::core::ble::transport::Transport;
+use crate::shared::constants::{BLE_SCAN_TIMEOUT, RELAY_BLE_SERVICE_UUID};
+use crate::shared::error::WalletError;
+use crate::shared::types::BLEDeviceInfo;
+use async_trait::async_trait;
+use bluest::{Adapter, Characteristic, Device, Uuid};
+use futures_lite::stream::StreamExt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+
+/// Notifications buffered before the pump waits for `receive`
+const INBOUND_CAPACITY: usize = 256;
+
+struct Link {
+    adapter: Adapter,
+    device: Device,
+    characteristic: Characteristic,
+    pump: tokio::task::JoinHandle<()>,
+}
+
+pub struct BluestTransport {
+    service: Uuid,
+    characteristic: Uuid,
+    scan_timeout: Duration,
+    mtu: AtomicUsize,
+    link: Mutex<Option<Link>>,
+    inbound_tx: mpsc::Sender<Vec<u8>>,
+    inbound: Mutex<mpsc::Receiver<Vec<u8>>>,
+}
+
+impl BluestTransport {
+    pub fn new(service: Uuid, characteristic: Uuid) -> Self {
+        let (inbound_tx, inbound) = mpsc::channel(INBOUND_CAPACITY);
+        Self {
+            service,
+            characteristic,
+            scan_timeout: Duration::from_millis(BLE_SCAN_TIMEOUT as u64),
+            mtu: AtomicUsize::new(0),
+            link: Mutex::new(None),
+            inbound_tx,
+            inbound: Mutex::new(inbound),
+        }
+    }
+
+    /// A characteristic of the relay's AirChainPay service, such as
+    /// `RELAY_BLE_PAYMENT_CHARACTERISTIC_UUID`
+    pub fn relay(characteristic: &str) -> Result<Self, WalletError> {
+        let parse = |uuid: &str| Uuid::parse_str(uuid)
+            .map_err(|_| WalletError::ble(format!("Invalid UUID {}", uuid)));
+        Ok(Self::new(parse(RELAY_BLE_SERVICE_UUID)?, parse(characteristic)?))
+    }
+
+    pub fn with_scan_timeout(mut self, timeout: Duration) -> Self {
+        self.scan_timeout = timeout;
+        self
+    }
+
+    /// Scan for the service and pick the peripheral named like `target`, or
+    /// the first one found when `target` has no name
+    async fn find_device(&self, adapter: &Adapter, target: &BLEDeviceInfo) -> Result<Device, WalletError> {
+        let mut scan = adapter.scan(&[self.service]).await
+            .map_err(|_| WalletError::ble("Failed to start BLE scan"))?;
+        let found = tokio::time::timeout(self.scan_timeout, async {
+            while let Some(advertised) = scan.next().await {
+                let name = advertised.device.name().ok();
+                if target.name.is_empty() || name.as_deref() == Some(target.name.as_str()) {
+                    return Some(advertised.device);
+                }
+            }
+            None
+        })
+        .await;
+        found.ok().flatten()
+            .ok_or_else(|| WalletError::ble(format!("Relay {} not found", target.name)))
+    }
+}
+
+#[async_trait]
+impl Transport for BluestTransport {
+    async fn connect(&self, device: &BLEDeviceInfo) -> Result<(), WalletError> {
+        self.disconnect().await?;
+
+        let adapter = Adapter::default().await
+            .ok_or_else(|| WalletError::ble("No Bluetooth adapter found"))?;
+        adapter.wait_available().await
+            .map_err(|_| WalletError::ble("Bluetooth adapter not available"))?;
+        let peripheral = self.find_device(&adapter, device).await?;
+        adapter.connect_device(&peripheral).await
+            .map_err(|e| WalletError::ble(format!("Failed to connect to {}: {}", device.name, e)))?;
+
+        let service = peripheral.discover_services_with_uuid(self.service).await
+            .map_err(|e| WalletError::ble(format!("Service discovery failed: {}", e)))?
+            .into_iter().next()
+            .ok_or_else(|| WalletError::ble("Relay service not found"))?;
+        let characteristic = service.discover_characteristics_with_uuid(self.characteristic).await
+            .map_err(|e| WalletError::ble(format!("Characteristic discovery failed: {}", e)))?
+            .into_iter().next()
+            .ok_or_else(|| WalletError::ble("Relay characteristic not found"))?;
+        // The stack negotiates the ATT MTU on connect; this is what it agreed
+        let mtu = characteristic.max_write_len()
+            .map_err(|e| WalletError::ble(format!("Failed to read MTU: {}", e)))?;
+        self.mtu.store(mtu, Ordering::Relaxed);
+
+        let notifying = characteristic.clone();
+        let inbound = self.inbound_tx.clone();
+        let pump = tokio::spawn(async move {
+            let mut notifications = match notifying.notify().await {
+                Ok(notifications) => notifications,
+                Err(e) => {
+                    log::warn!("Failed to subscribe to relay notifications: {}", e);
+                    return;
+                }
+            };
+            while let Some(Ok(value)) = notifications.next().await {
+                if inbound.send(value).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        *self.link.lock().await = Some(Link { adapter, device: peripheral, characteristic, pump });
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> Result<(), WalletError> {
+        if let Some(link) = self.link.lock().await.take() {
+            link.pump.abort();
+            link.adapter.disconnect_device(&link.device).await
+                .map_err(|e| WalletError::ble(format!("Failed to disconnect: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    async fn send(&self, frame: &[u8]) -> Result<(), WalletError> {
+        let link = self.link.lock().await;
+        let link = link.as_ref().ok_or_else(|| WalletError::ble("Not connected"))?;
+        if frame.len() > self.mtu() {
+            return Err(WalletError::ble(format!("Frame of {} bytes exceeds MTU {}", frame.len(), self.mtu())));
+        }
+        link.characteristic.write(frame).await
+            .map_err(|e| WalletError::ble(format!("Write failed: {}", e)))
+    }
+
+    async fn receive(&self) -> Result<Vec<u8>, WalletError> {
+        self.inbound.lock().await.recv().await
+            .ok_or_else(|| WalletError::ble("Notification channel closed"))
+    }
+
+    fn mtu(&self) -> usize {
+        self.mtu.load(Ordering::Relaxed)
+    }
+}
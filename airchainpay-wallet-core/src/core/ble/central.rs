@@ -0,0 +1,180 @@
+//! BLE central role for talking to a relay peripheral
+//!
+//! A message is suffixed with the CRC-32 of its bytes (big-endian) and split
+//! into frames carrying the usual sequence/count header. The relay answers
+//! each message with one of its own, framed the same way; that reply is the
+//! acknowledgement. Messages with a bad checksum are rejected, and a request
+//! is resent when no acknowledgement arrives in time.
+
+use crate::core::ble::transport::{chunk_payload, Reassembler, Transport, MIN_FRAME_SIZE};
+use crate::shared::error::WalletError;
+use crate::shared::types::BLEDeviceInfo;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Trailing checksum on every message
+pub const CRC_SIZE: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct CentralConfig {
+    /// Frame size asked for when connecting; the link may agree to less
+    pub preferred_mtu: usize,
+    /// How long to wait for the relay's reply to a request
+    pub ack_timeout: Duration,
+    /// Sends of one request before giving up
+    pub max_attempts: u32,
+}
+
+impl Default for CentralConfig {
+    fn default() -> Self {
+        Self {
+            preferred_mtu: 512,
+            ack_timeout: Duration::from_secs(10),
+            max_attempts: 3,
+        }
+    }
+}
+
+/// Append the CRC-32 of `payload`
+pub fn seal_message(payload: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(payload.len() + CRC_SIZE);
+    message.extend_from_slice(payload);
+    message.extend_from_slice(&crc32fast::hash(payload).to_be_bytes());
+    message
+}
+
+/// Check and strip the trailing CRC-32
+pub fn open_message(mut message: Vec<u8>) -> Result<Vec<u8>, WalletError> {
+    if message.len() < CRC_SIZE {
+        return Err(WalletError::ble("Message shorter than its checksum"));
+    }
+    let crc = message.split_off(message.len() - CRC_SIZE);
+    if crc32fast::hash(&message).to_be_bytes()[..] != crc[..] {
+        return Err(WalletError::ble("Message checksum mismatch"));
+    }
+    Ok(message)
+}
+
+/// Connection to a relay peripheral over one characteristic
+pub struct BleCentral {
+    transport: Arc<dyn Transport>,
+    config: CentralConfig,
+    frame_size: AtomicUsize,
+}
+
+impl BleCentral {
+    pub fn new(transport: Arc<dyn Transport>) -> Self {
+        let frame_size = AtomicUsize::new(transport.mtu());
+        Self {
+            transport,
+            config: CentralConfig::default(),
+            frame_size,
+        }
+    }
+
+    pub fn with_config(mut self, config: CentralConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Frame size in use, as negotiated on connect
+    pub fn frame_size(&self) -> usize {
+        self.frame_size.load(Ordering::Relaxed)
+    }
+
+    /// Connect to the relay and negotiate the frame size
+    pub async fn connect(&self, relay: &BLEDeviceInfo) -> Result<usize, WalletError> {
+        self.transport.connect(relay).await?;
+        let mtu = self.transport.request_mtu(self.config.preferred_mtu).await?;
+        if mtu < MIN_FRAME_SIZE {
+            let _ = self.transport.disconnect().await;
+            return Err(WalletError::ble(format!("Negotiated MTU {} is below the minimum of {}", mtu, MIN_FRAME_SIZE)));
+        }
+        self.frame_size.store(mtu, Ordering::Relaxed);
+        log::info!("Connected to relay {} with {}-byte frames", relay.name, mtu);
+        Ok(mtu)
+    }
+
+    pub async fn disconnect(&self) -> Result<(), WalletError> {
+        self.transport.disconnect().await
+    }
+
+    /// Checksum, frame and write one message
+    pub async fn send_message(&self, payload: &[u8]) -> Result<(), WalletError> {
+        let frames = chunk_payload(&seal_message(payload), self.frame_size())?;
+        log::debug!("Sending {}-byte message in {} frames", payload.len(), frames.len());
+        for frame in &frames {
+            self.transport.send(frame).await?;
+        }
+        Ok(())
+    }
+
+    /// Read frames until a whole message arrives and verify its checksum
+    pub async fn receive_message(&self) -> Result<Vec<u8>, WalletError> {
+        let mut reassembler = Reassembler::new();
+        loop {
+            let frame = self.transport.receive().await?;
+            if let Some(message) = reassembler.push(&frame)? {
+                return open_message(message);
+            }
+        }
+    }
+
+    /// Send a message and wait for the relay's acknowledgement, resending
+    /// when none arrives in time or it arrives corrupted
+    pub async fn request(&self, payload: &[u8]) -> Result<Vec<u8>, WalletError> {
+        let mut last_error = WalletError::ble("No acknowledgement from relay");
+        for attempt in 1..=self.config.max_attempts.max(1) {
+            self.send_message(payload).await?;
+            match tokio::time::timeout(self.config.ack_timeout, self.receive_message()).await {
+                Ok(Ok(ack)) => return Ok(ack),
+                Ok(Err(e)) => {
+                    log::warn!("Bad acknowledgement from relay (attempt {}): {}", attempt, e);
+                    last_error = e;
+                }
+                Err(_) => {
+                    log::warn!("No acknowledgement from relay (attempt {})", attempt);
+                    last_error = WalletError::ble("No acknowledgement from relay");
+                }
+            }
+        }
+        Err(last_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ble::transport::MockTransport;
+
+    #[tokio::test]
+    async fn test_request_is_acknowledged_over_mock_link() {
+        let (wallet_end, relay_end) = MockTransport::pair(64);
+        let relay = BLEDeviceInfo {
+            id: "relay".to_string(),
+            name: "AirChainPay Relay".to_string(),
+            address: "00:00:00:00:00:01".to_string(),
+            rssi: -50,
+        };
+
+        let central = BleCentral::new(Arc::new(wallet_end));
+        assert_eq!(central.connect(&relay).await.unwrap(), 64);
+
+        let peripheral = BleCentral::new(Arc::new(relay_end));
+        peripheral.connect(&relay).await.unwrap();
+        let responder = tokio::spawn(async move {
+            let message = peripheral.receive_message().await.unwrap();
+            peripheral.send_message(&[b"ack:".as_slice(), &message].concat()).await.unwrap();
+        });
+
+        let payload = vec![0xabu8; 300];
+        let ack = central.request(&payload).await.unwrap();
+        responder.await.unwrap();
+        assert_eq!(ack, [b"ack:".as_slice(), &payload].concat());
+
+        let mut corrupted = seal_message(b"signed tx");
+        corrupted[0] ^= 0xff;
+        assert!(open_message(corrupted).is_err());
+    }
+}
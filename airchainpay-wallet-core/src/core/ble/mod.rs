@@ -8,18 +8,36 @@ use crate::shared::types::BLEDeviceInfo;
 use aes_gcm::{Aes256Gcm, aead::{Aead}};
 use aes_gcm::KeyInit;
 use aes_gcm::aead::generic_array::GenericArray;
-use rand_core::OsRng;
-use rand_core::RngCore;
+use crate::shared::sources::{default_random_source, RandomSource};
+use std::sync::Arc;
 use futures_lite::stream::StreamExt;
 
+pub mod central;
+pub mod noise;
+pub mod transport;
+#[cfg(not(target_os = "android"))]
+pub mod bluest_transport;
+
+pub use central::{BleCentral, CentralConfig};
+pub use noise::{BLESecureSession, NoiseKeypair};
+pub use transport::{chunk_payload, MockTransport, Reassembler, Transport};
+#[cfg(not(target_os = "android"))]
+pub use bluest_transport::BluestTransport;
+
 /// BLE security manager
 pub struct BLESecurityManager {
-   
+    rng: Arc<dyn RandomSource>,
 }
 
 impl BLESecurityManager {
     pub fn new() -> Self {
-        Self {}
+        Self { rng: default_random_source() }
+    }
+
+    /// Draw payment nonces from `rng` instead of the OS
+    pub fn with_random_source(mut self, rng: Arc<dyn RandomSource>) -> Self {
+        self.rng = rng;
+        self
     }
 
     pub async fn init(&self) -> Result<(), WalletError> {
@@ -101,11 +119,56 @@ impl BLESecurityManager {
         }
     }
 
+    /// Send a payment through an established Noise session. Prefer this over
+    /// `send_payment_over`, whose key has to be agreed out of band.
+    pub async fn send_payment_secure(
+        &self,
+        session: &mut BLESecureSession,
+        central: &BleCentral,
+        payment_data: &BLEPaymentData,
+    ) -> Result<(), WalletError> {
+        let serialized = serde_json::to_vec(payment_data)
+            .map_err(|e| WalletError::crypto(format!("Serialization failed: {}", e)))?;
+        session.send(central, &serialized).await
+    }
+
+    /// Receive a payment through an established Noise session
+    pub async fn receive_payment_secure(
+        &self,
+        session: &mut BLESecureSession,
+        central: &BleCentral,
+    ) -> Result<BLEPaymentData, WalletError> {
+        let plaintext = session.receive(central).await?;
+        serde_json::from_slice(&plaintext)
+            .map_err(|e| WalletError::crypto(format!("Deserialization failed: {}", e)))
+    }
+
+    /// Encrypt a payment and write it to a connected transport in MTU-sized frames
+    pub async fn send_payment_over(&self, transport: &dyn Transport, payment_data: &BLEPaymentData, key: &[u8]) -> Result<(), WalletError> {
+        let encrypted = self.encrypt_payment_data(payment_data, key).await?;
+        let frames = chunk_payload(&encrypted, transport.mtu())?;
+        log::info!("Sending payment via BLE transport in {} frames", frames.len());
+        for frame in &frames {
+            transport.send(frame).await?;
+        }
+        Ok(())
+    }
+
+    /// Read frames from a transport until a full payment arrives, then decrypt it
+    pub async fn receive_payment_over(&self, transport: &dyn Transport, key: &[u8]) -> Result<BLEPaymentData, WalletError> {
+        let mut reassembler = Reassembler::new();
+        loop {
+            let frame = transport.receive().await?;
+            if let Some(encrypted) = reassembler.push(&frame)? {
+                return self.decrypt_payment_data(&encrypted, key).await;
+            }
+        }
+    }
+
     pub async fn encrypt_payment_data(&self, payment_data: &BLEPaymentData, key: &[u8]) -> Result<Vec<u8>, WalletError> {
         let cipher = Aes256Gcm::new(GenericArray::from_slice(key));
         let mut nonce = [0u8; 12];
-        let mut rng = OsRng;
-        rng.fill_bytes(&mut nonce);
+        self.rng.fill_bytes(&mut nonce)?;
         let serialized = serde_json::to_vec(payment_data).map_err(|e| WalletError::crypto(format!("Serialization failed: {}", e)))?;
         let ciphertext = cipher.encrypt(GenericArray::from_slice(&nonce), serialized.as_ref())
             .map_err(|e| WalletError::crypto(format!("Encryption failed: {}", e)))?;
@@ -0,0 +1,210 @@
+//! Noise XX sessions over a BLE link
+//!
+//! ```text
+//! -> e
+//! <- e, ee, s, es
+//! -> s, se
+//! ```
+//!
+//! Both ends authenticate each other's X25519 static key and derive transport
+//! keys from ephemeral exchanges, so a leaked static key does not expose past
+//! sessions. The pattern and prologue match the relay's, and each direction
+//! rekeys every `NOISE_REKEY_INTERVAL` messages by the same rule the relay
+//! applies, so no rekey messages are exchanged.
+
+use crate::core::ble::central::BleCentral;
+use crate::shared::error::WalletError;
+use snow::{Builder, HandshakeState, TransportState};
+use zeroize::Zeroizing;
+
+/// Handshake pattern and primitives shared with the relay
+pub const NOISE_PATTERN: &str = "Noise_XX_25519_ChaChaPoly_SHA256";
+/// Binds handshakes to this protocol
+pub const NOISE_PROLOGUE: &[u8] = b"airchainpay-noise-1";
+/// Messages per direction between rekeys
+pub const NOISE_REKEY_INTERVAL: u64 = 1000;
+/// Largest Noise message allowed by the specification
+pub const MAX_NOISE_MESSAGE: usize = 65535;
+
+fn builder() -> Builder<'static> {
+    Builder::new(NOISE_PATTERN.parse().expect("valid Noise pattern"))
+}
+
+fn noise_error(e: snow::Error) -> WalletError {
+    WalletError::crypto(format!("Noise handshake failed: {}", e))
+}
+
+/// A device's long-term X25519 key for Noise handshakes
+pub struct NoiseKeypair {
+    pub private: Zeroizing<Vec<u8>>,
+    pub public: Vec<u8>,
+}
+
+impl NoiseKeypair {
+    pub fn generate() -> Result<Self, WalletError> {
+        let keypair = builder().generate_keypair().map_err(noise_error)?;
+        Ok(Self {
+            private: Zeroizing::new(keypair.private),
+            public: keypair.public,
+        })
+    }
+}
+
+/// An established, mutually authenticated Noise session
+pub struct BLESecureSession {
+    state: TransportState,
+    handshake_hash: Vec<u8>,
+    remote_static: Vec<u8>,
+}
+
+impl BLESecureSession {
+    /// Run the initiator side of the handshake over `central`. When
+    /// `expected_remote` is given, the peer must present that static key.
+    pub async fn handshake_initiator(
+        central: &BleCentral,
+        keypair: &NoiseKeypair,
+        expected_remote: Option<&[u8]>,
+    ) -> Result<Self, WalletError> {
+        let mut state = builder()
+            .local_private_key(&keypair.private)
+            .prologue(NOISE_PROLOGUE)
+            .build_initiator()
+            .map_err(noise_error)?;
+
+        central.send_message(&write_handshake(&mut state)?).await?;
+        read_handshake(&mut state, &central.receive_message().await?)?;
+        if let Some(expected) = expected_remote {
+            if state.get_remote_static() != Some(expected) {
+                return Err(WalletError::crypto("Peer presented an unexpected static key"));
+            }
+        }
+        central.send_message(&write_handshake(&mut state)?).await?;
+        Self::finish(state)
+    }
+
+    /// Run the responder side of the handshake over `central`
+    pub async fn handshake_responder(central: &BleCentral, keypair: &NoiseKeypair) -> Result<Self, WalletError> {
+        let mut state = builder()
+            .local_private_key(&keypair.private)
+            .prologue(NOISE_PROLOGUE)
+            .build_responder()
+            .map_err(noise_error)?;
+
+        read_handshake(&mut state, &central.receive_message().await?)?;
+        central.send_message(&write_handshake(&mut state)?).await?;
+        read_handshake(&mut state, &central.receive_message().await?)?;
+        Self::finish(state)
+    }
+
+    fn finish(state: HandshakeState) -> Result<Self, WalletError> {
+        let handshake_hash = state.get_handshake_hash().to_vec();
+        let remote_static = state.get_remote_static()
+            .ok_or_else(|| WalletError::crypto("Noise handshake did not authenticate the peer"))?
+            .to_vec();
+        let state = state.into_transport_mode().map_err(noise_error)?;
+        Ok(Self { state, handshake_hash, remote_static })
+    }
+
+    /// The peer's authenticated static key
+    pub fn remote_static(&self) -> &[u8] {
+        &self.remote_static
+    }
+
+    /// Handshake hash, identical on both ends; the relay records it in its
+    /// audit log, so it identifies the session when comparing records
+    pub fn handshake_hash(&self) -> &[u8] {
+        &self.handshake_hash
+    }
+
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, WalletError> {
+        let mut ciphertext = vec![0u8; MAX_NOISE_MESSAGE];
+        let len = self.state.write_message(plaintext, &mut ciphertext)
+            .map_err(|e| WalletError::crypto(format!("Encryption failed: {}", e)))?;
+        ciphertext.truncate(len);
+        if self.state.sending_nonce() % NOISE_REKEY_INTERVAL == 0 {
+            self.state.rekey_outgoing();
+        }
+        Ok(ciphertext)
+    }
+
+    /// Decrypt the peer's next message; messages must be read in the order sent
+    pub fn decrypt(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, WalletError> {
+        let mut plaintext = vec![0u8; MAX_NOISE_MESSAGE];
+        let len = self.state.read_message(ciphertext, &mut plaintext)
+            .map_err(|e| WalletError::crypto(format!("Decryption failed: {}", e)))?;
+        plaintext.truncate(len);
+        if self.state.receiving_nonce() % NOISE_REKEY_INTERVAL == 0 {
+            self.state.rekey_incoming();
+        }
+        Ok(plaintext)
+    }
+
+    pub async fn send(&mut self, central: &BleCentral, plaintext: &[u8]) -> Result<(), WalletError> {
+        let ciphertext = self.encrypt(plaintext)?;
+        central.send_message(&ciphertext).await
+    }
+
+    pub async fn receive(&mut self, central: &BleCentral) -> Result<Vec<u8>, WalletError> {
+        let ciphertext = central.receive_message().await?;
+        self.decrypt(&ciphertext)
+    }
+}
+
+fn write_handshake(state: &mut HandshakeState) -> Result<Vec<u8>, WalletError> {
+    let mut message = vec![0u8; MAX_NOISE_MESSAGE];
+    let len = state.write_message(&[], &mut message).map_err(noise_error)?;
+    message.truncate(len);
+    Ok(message)
+}
+
+fn read_handshake(state: &mut HandshakeState, message: &[u8]) -> Result<(), WalletError> {
+    let mut payload = vec![0u8; MAX_NOISE_MESSAGE];
+    state.read_message(message, &mut payload).map_err(noise_error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ble::transport::MockTransport;
+    use crate::shared::types::BLEDeviceInfo;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_xx_handshake_and_rekeying_over_mock_link() {
+        let (wallet_end, peer_end) = MockTransport::pair(128);
+        let peer_info = BLEDeviceInfo {
+            id: "peer".to_string(),
+            name: "AirChainPay Peer".to_string(),
+            address: "00:00:00:00:00:02".to_string(),
+            rssi: -40,
+        };
+        let wallet = BleCentral::new(Arc::new(wallet_end));
+        let peer = BleCentral::new(Arc::new(peer_end));
+        wallet.connect(&peer_info).await.unwrap();
+        peer.connect(&peer_info).await.unwrap();
+
+        let wallet_key = NoiseKeypair::generate().unwrap();
+        let peer_key = NoiseKeypair::generate().unwrap();
+        let peer_public = peer_key.public.clone();
+
+        let responder = tokio::spawn(async move {
+            let mut session = BLESecureSession::handshake_responder(&peer, &peer_key).await.unwrap();
+            let mut last = Vec::new();
+            for _ in 0..NOISE_REKEY_INTERVAL + 2 {
+                last = session.receive(&peer).await.unwrap();
+            }
+            (session.remote_static().to_vec(), session.handshake_hash().to_vec(), last)
+        });
+
+        let mut session = BLESecureSession::handshake_initiator(&wallet, &wallet_key, Some(&peer_public)).await.unwrap();
+        for i in 0..NOISE_REKEY_INTERVAL + 2 {
+            session.send(&wallet, format!("payment {}", i).as_bytes()).await.unwrap();
+        }
+
+        let (remote_static, handshake_hash, last) = responder.await.unwrap();
+        assert_eq!(remote_static, wallet_key.public);
+        assert_eq!(handshake_hash, session.handshake_hash());
+        assert_eq!(last, format!("payment {}", NOISE_REKEY_INTERVAL + 1).into_bytes());
+    }
+}
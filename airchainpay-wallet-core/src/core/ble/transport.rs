@@ -0,0 +1,233 @@
+//! BLE transport abstraction
+//!
+//! Payments are encrypted, split into MTU-sized frames and written to a
+//! `Transport`. Radios implement the trait on device; `MockTransport` is an
+//! in-memory loopback so the full flow runs on machines without Bluetooth.
+
+use crate::shared::error::WalletError;
+use crate::shared::types::BLEDeviceInfo;
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::{Mutex, Notify};
+
+/// Frame header: sequence number and frame count, both big-endian u16
+pub const FRAME_HEADER_SIZE: usize = 4;
+/// Smallest usable ATT payload (default MTU 23 minus 3 bytes of ATT header)
+pub const MIN_FRAME_SIZE: usize = 20;
+
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn connect(&self, device: &BLEDeviceInfo) -> Result<(), WalletError>;
+    async fn disconnect(&self) -> Result<(), WalletError>;
+    /// Write one frame, at most `mtu()` bytes
+    async fn send(&self, frame: &[u8]) -> Result<(), WalletError>;
+    /// Wait for the next frame from the peer
+    async fn receive(&self) -> Result<Vec<u8>, WalletError>;
+    /// Largest frame the link carries
+    fn mtu(&self) -> usize;
+    /// Ask for frames of up to `preferred` bytes and return the size agreed
+    async fn request_mtu(&self, preferred: usize) -> Result<usize, WalletError> {
+        Ok(self.mtu().min(preferred))
+    }
+}
+
+/// Split a payload into frames of at most `mtu` bytes, each prefixed with
+/// its sequence number and the total frame count
+pub fn chunk_payload(payload: &[u8], mtu: usize) -> Result<Vec<Vec<u8>>, WalletError> {
+    if mtu < MIN_FRAME_SIZE {
+        return Err(WalletError::ble(format!("MTU {} is below the minimum of {}", mtu, MIN_FRAME_SIZE)));
+    }
+    let body = mtu - FRAME_HEADER_SIZE;
+    let total = payload.len().div_ceil(body).max(1);
+    if total > u16::MAX as usize {
+        return Err(WalletError::ble(format!("Payload of {} bytes needs too many frames", payload.len())));
+    }
+
+    let mut frames = Vec::with_capacity(total);
+    for seq in 0..total {
+        let chunk = &payload[(seq * body).min(payload.len())..((seq + 1) * body).min(payload.len())];
+        let mut frame = Vec::with_capacity(FRAME_HEADER_SIZE + chunk.len());
+        frame.extend_from_slice(&(seq as u16).to_be_bytes());
+        frame.extend_from_slice(&(total as u16).to_be_bytes());
+        frame.extend_from_slice(chunk);
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// Collects frames, in any order, until the payload is complete
+#[derive(Debug, Default)]
+pub struct Reassembler {
+    frames: Vec<Option<Vec<u8>>>,
+    received: usize,
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a frame; returns the payload once every frame has arrived
+    pub fn push(&mut self, frame: &[u8]) -> Result<Option<Vec<u8>>, WalletError> {
+        if frame.len() < FRAME_HEADER_SIZE {
+            return Err(WalletError::ble("Frame shorter than its header"));
+        }
+        let seq = u16::from_be_bytes([frame[0], frame[1]]) as usize;
+        let total = u16::from_be_bytes([frame[2], frame[3]]) as usize;
+        if total == 0 || seq >= total {
+            return Err(WalletError::ble(format!("Invalid frame {} of {}", seq, total)));
+        }
+        if self.frames.is_empty() {
+            self.frames = vec![None; total];
+        } else if self.frames.len() != total {
+            return Err(WalletError::ble("Frame count changed mid-payload"));
+        }
+
+        if self.frames[seq].is_none() {
+            self.frames[seq] = Some(frame[FRAME_HEADER_SIZE..].to_vec());
+            self.received += 1;
+        }
+        if self.received < total {
+            return Ok(None);
+        }
+
+        let payload = self.frames.drain(..).flatten().flatten().collect();
+        self.received = 0;
+        Ok(Some(payload))
+    }
+}
+
+/// One direction of a loopback link
+#[derive(Default)]
+struct Channel {
+    frames: Mutex<VecDeque<Vec<u8>>>,
+    notify: Notify,
+}
+
+/// In-memory transport for tests and CI. Build a connected pair with
+/// [`MockTransport::pair`]; frames sent on one end queue up on the other.
+pub struct MockTransport {
+    mtu: usize,
+    outbound: Arc<Channel>,
+    inbound: Arc<Channel>,
+    connected: Mutex<bool>,
+    /// Every frame this end has sent, for assertions
+    sent: Mutex<Vec<Vec<u8>>>,
+}
+
+impl MockTransport {
+    pub fn pair(mtu: usize) -> (MockTransport, MockTransport) {
+        let a_to_b = Arc::new(Channel::default());
+        let b_to_a = Arc::new(Channel::default());
+        let end = |outbound: &Arc<Channel>, inbound: &Arc<Channel>| MockTransport {
+            mtu,
+            outbound: Arc::clone(outbound),
+            inbound: Arc::clone(inbound),
+            connected: Mutex::new(false),
+            sent: Mutex::new(Vec::new()),
+        };
+        (end(&a_to_b, &b_to_a), end(&b_to_a, &a_to_b))
+    }
+
+    pub async fn sent_frames(&self) -> Vec<Vec<u8>> {
+        self.sent.lock().await.clone()
+    }
+
+    /// Frames waiting to be received on this end
+    pub async fn pending(&self) -> usize {
+        self.inbound.frames.lock().await.len()
+    }
+}
+
+#[async_trait]
+impl Transport for MockTransport {
+    async fn connect(&self, device: &BLEDeviceInfo) -> Result<(), WalletError> {
+        log::debug!("Mock BLE transport connected to {}", device.name);
+        *self.connected.lock().await = true;
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> Result<(), WalletError> {
+        *self.connected.lock().await = false;
+        Ok(())
+    }
+
+    async fn send(&self, frame: &[u8]) -> Result<(), WalletError> {
+        if !*self.connected.lock().await {
+            return Err(WalletError::ble("Not connected"));
+        }
+        if frame.len() > self.mtu {
+            return Err(WalletError::ble(format!("Frame of {} bytes exceeds MTU {}", frame.len(), self.mtu)));
+        }
+        self.sent.lock().await.push(frame.to_vec());
+        self.outbound.frames.lock().await.push_back(frame.to_vec());
+        self.outbound.notify.notify_one();
+        Ok(())
+    }
+
+    async fn receive(&self) -> Result<Vec<u8>, WalletError> {
+        loop {
+            if let Some(frame) = self.inbound.frames.lock().await.pop_front() {
+                return Ok(frame);
+            }
+            self.inbound.notify.notified().await;
+        }
+    }
+
+    fn mtu(&self) -> usize {
+        self.mtu
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ble::BLESecurityManager;
+    use crate::shared::types::{BLEPaymentData, Network};
+
+    #[tokio::test]
+    async fn test_payment_round_trip_over_mock_transport() {
+        let (sender, receiver) = MockTransport::pair(MIN_FRAME_SIZE);
+        let device = BLEDeviceInfo {
+            id: "mock".to_string(),
+            name: "Mock Receiver".to_string(),
+            address: "00:00:00:00:00:00".to_string(),
+            rssi: -40,
+        };
+        sender.connect(&device).await.unwrap();
+
+        let manager = BLESecurityManager::new();
+        let key = [7u8; 32];
+        let payment = BLEPaymentData {
+            amount: "1000000000000000000".to_string(),
+            to_address: "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6".to_string(),
+            token_symbol: "ETH".to_string(),
+            network: Network::CoreTestnet,
+            reference: Some("Mock Payment".to_string()),
+        };
+
+        manager.send_payment_over(&sender, &payment, &key).await.unwrap();
+        assert!(sender.sent_frames().await.len() > 1);
+        assert!(sender.sent_frames().await.iter().all(|f| f.len() <= MIN_FRAME_SIZE));
+
+        let received = manager.receive_payment_over(&receiver, &key).await.unwrap();
+        assert_eq!(received.to_address, payment.to_address);
+        assert_eq!(received.reference, payment.reference);
+        assert_eq!(receiver.pending().await, 0);
+    }
+
+    #[test]
+    fn test_reassembler_accepts_out_of_order_frames() {
+        let payload: Vec<u8> = (0..100).collect();
+        let mut frames = chunk_payload(&payload, 32).unwrap();
+        frames.reverse();
+
+        let mut reassembler = Reassembler::new();
+        let mut result = None;
+        for frame in &frames {
+            result = reassembler.push(frame).unwrap();
+        }
+        assert_eq!(result, Some(payload));
+    }
+}
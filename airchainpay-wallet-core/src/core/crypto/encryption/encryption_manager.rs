@@ -3,18 +3,25 @@ use crate::shared::WalletResult;
 use aes_gcm::{Aes256Gcm, KeyInit, Key, Nonce};
 use aes_gcm::aead::Aead;
 use chacha20poly1305::{ChaCha20Poly1305, Key as ChaChaKey, Nonce as ChaChaNonce};
-use rand_core::OsRng;
-use rand_core::RngCore;
+use crate::shared::sources::{default_random_source, RandomSource};
+use std::sync::Arc;
 use super::{EncryptionAlgorithm, EncryptedData};
 
 /// Secure encryption manager
 pub struct EncryptionManager {
     algorithm: EncryptionAlgorithm,
+    rng: Arc<dyn RandomSource>,
 }
 
 impl EncryptionManager {
     pub fn new(algorithm: EncryptionAlgorithm) -> Self {
-        Self { algorithm }
+        Self { algorithm, rng: default_random_source() }
+    }
+
+    /// Draw nonces and keys from `rng` instead of the OS
+    pub fn with_random_source(mut self, rng: Arc<dyn RandomSource>) -> Self {
+        self.rng = rng;
+        self
     }
 
     pub fn new_default() -> Self {
@@ -44,7 +51,7 @@ impl EncryptionManager {
         }
 
         let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
-        let nonce_bytes = self.generate_nonce(12);
+        let nonce_bytes = self.generate_nonce(12)?;
         let nonce = Nonce::from_slice(&nonce_bytes);
 
         let ciphertext = cipher
@@ -89,7 +96,7 @@ impl EncryptionManager {
         }
 
         let cipher = ChaCha20Poly1305::new(ChaChaKey::from_slice(key));
-        let nonce_bytes = self.generate_nonce(12);
+        let nonce_bytes = self.generate_nonce(12)?;
         let nonce = ChaChaNonce::from_slice(&nonce_bytes);
 
         let ciphertext = cipher
@@ -128,19 +135,13 @@ impl EncryptionManager {
     }
 
     /// Generate a secure random nonce
-    fn generate_nonce(&self, length: usize) -> Vec<u8> {
-        let mut nonce = vec![0u8; length];
-        let mut rng = OsRng;
-        rng.fill_bytes(&mut nonce);
-        nonce
+    fn generate_nonce(&self, length: usize) -> WalletResult<Vec<u8>> {
+        self.rng.random_bytes(length)
     }
 
     /// Generate a random encryption key
-    pub fn generate_key(&self) -> Vec<u8> {
-        let mut key = vec![0u8; 32];
-        let mut rng = OsRng;
-        rng.fill_bytes(&mut key);
-        key
+    pub fn generate_key(&self) -> WalletResult<Vec<u8>> {
+        self.rng.random_bytes(32)
     }
 }
 
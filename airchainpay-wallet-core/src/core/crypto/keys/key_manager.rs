@@ -8,12 +8,22 @@ use secp256k1::{SecretKey, PublicKey, Secp256k1};
 use super::SecurePrivateKey;
 use bip32::{XPrv, DerivationPath};
 use std::str::FromStr;
+use zeroize::Zeroizing;
 use crate::infrastructure::platform::PlatformStorage;
+use crate::shared::sources::{default_random_source, RandomSource};
+use std::sync::Arc;
+use crate::shared::constants::{HD_SEED_SIZE, BIP44_COIN_TYPE_ETH, MAX_HD_ACCOUNT_INDEX};
+
+/// BIP-44 derivation path for an Ethereum account and address index
+pub fn bip44_path(account_index: u32, address_index: u32) -> String {
+    format!("m/44'/{}'/{}'/0/{}", BIP44_COIN_TYPE_ETH, account_index, address_index)
+}
 
 /// Key manager for cryptographic key operations
 pub struct KeyManager<'a> {
     secp256k1: Secp256k1<secp256k1::All>,
     storage: &'a dyn PlatformStorage,
+    rng: Arc<dyn RandomSource>,
 }
 
 impl<'a> KeyManager<'a> {
@@ -22,9 +32,16 @@ impl<'a> KeyManager<'a> {
         Self {
             secp256k1: Secp256k1::new(),
             storage,
+            rng: default_random_source(),
         }
     }
 
+    /// Generate keys from `rng` instead of the OS
+    pub fn with_random_source(mut self, rng: Arc<dyn RandomSource>) -> Self {
+        self.rng = rng;
+        self
+    }
+
     /// Initialize the key manager
     pub fn init(&self) -> Result<(), WalletError> {
         log::info!("Initializing key manager");
@@ -33,7 +50,7 @@ impl<'a> KeyManager<'a> {
 
     /// Generate a new private key and persist it securely
     pub fn generate_private_key(&self, key_id: &str) -> Result<SecurePrivateKey, WalletError> {
-        SecurePrivateKey::generate(key_id.to_string(), self.storage)
+        SecurePrivateKey::generate_with(key_id.to_string(), self.storage, self.rng.as_ref())
     }
 
     /// Import a private key and persist it securely
@@ -100,32 +117,123 @@ impl<'a> KeyManager<'a> {
 
     /// Derive a private key from a seed phrase without storing the seed phrase in memory
     pub fn derive_private_key_from_seed(&self, seed_phrase: &str, key_id: &str) -> Result<SecurePrivateKey, WalletError> {
+        let seed = Self::seed_from_phrase(seed_phrase)?;
+        self.derive_and_store(&seed[..], &bip44_path(0, 0), key_id)
+    }
+
+    /// Keep the BIP39 seed for a wallet so further accounts can be derived later.
+    /// The phrase itself is never stored.
+    pub fn store_seed(&self, seed_id: &str, seed_phrase: &str) -> Result<(), WalletError> {
+        let seed = Self::seed_from_phrase(seed_phrase)?;
+        self.storage.store(seed_id, &seed[..])
+    }
+
+    /// Store a raw BIP39 seed, such as one restored from backup shares
+    pub fn store_seed_bytes(&self, seed_id: &str, seed: &[u8]) -> Result<(), WalletError> {
+        if seed.len() != HD_SEED_SIZE {
+            return Err(WalletError::crypto("Invalid seed length".to_string()));
+        }
+        self.storage.store(seed_id, seed)
+    }
+
+    /// Read back a stored BIP39 seed
+    pub fn get_seed(&self, seed_id: &str) -> Result<Zeroizing<Vec<u8>>, WalletError> {
+        let seed = Zeroizing::new(self.storage.retrieve(seed_id)?);
+        if seed.len() != HD_SEED_SIZE {
+            return Err(WalletError::crypto("Invalid stored seed length".to_string()));
+        }
+        Ok(seed)
+    }
+
+    /// Derive the key at `m/44'/60'/account'/0/index` from a stored seed and store it under `key_id`
+    pub fn derive_account_key(&self, seed_id: &str, account_index: u32, address_index: u32, key_id: &str) -> Result<SecurePrivateKey, WalletError> {
+        if account_index > MAX_HD_ACCOUNT_INDEX {
+            return Err(WalletError::validation(format!("Account index out of range: {}", account_index)));
+        }
+        let seed = Zeroizing::new(self.storage.retrieve(seed_id)?);
+        if seed.len() != HD_SEED_SIZE {
+            return Err(WalletError::crypto("Invalid stored seed length".to_string()));
+        }
+        self.derive_and_store(&seed[..], &bip44_path(account_index, address_index), key_id)
+    }
+
+    /// Derive the key at `m/44'/60'/account'/0/index` of a seed phrase
+    /// without storing either
+    pub fn derive_key_bytes(seed_phrase: &str, account_index: u32, address_index: u32) -> Result<Zeroizing<[u8; 32]>, WalletError> {
+        if account_index > MAX_HD_ACCOUNT_INDEX {
+            return Err(WalletError::validation(format!("Account index out of range: {}", account_index)));
+        }
+        let seed = Self::seed_from_phrase(seed_phrase)?;
+        let child_xprv = Self::derive_xprv(&seed[..], &bip44_path(account_index, address_index))?;
+        Ok(Zeroizing::new(child_xprv.private_key().to_bytes().into()))
+    }
+
+    fn seed_from_phrase(seed_phrase: &str) -> Result<Zeroizing<[u8; HD_SEED_SIZE]>, WalletError> {
         use bip39::Mnemonic;
-        
-        // Parse the mnemonic
+
         let mnemonic = Mnemonic::parse_in_normalized(bip39::Language::English, seed_phrase)
             .map_err(|e| WalletError::validation(format!("Invalid BIP39 seed phrase: {}", e)))?;
-        
-        let seed = bip32::Seed::new(mnemonic.to_seed_normalized("")); // No passphrase
-        
-        // Derive the BIP32 root key
-        let xprv = XPrv::new(seed.as_bytes())
+        Ok(Zeroizing::new(mnemonic.to_seed_normalized(""))) // No passphrase
+    }
+
+    /// Stable wallet id for a seed phrase: `wallet_` followed by the first
+    /// 16 bytes of SHA-256 over account 0's compressed public key, so
+    /// importing the same seed twice yields the same id
+    pub fn wallet_id_from_seed(seed_phrase: &str) -> Result<String, WalletError> {
+        let seed = Self::seed_from_phrase(seed_phrase)?;
+        Self::wallet_id_from_seed_bytes(&seed[..])
+    }
+
+    /// `wallet_id_from_seed` for a BIP39 seed already in binary form, such
+    /// as one recovered from SLIP-39 shares
+    pub fn wallet_id_from_seed_bytes(seed: &[u8]) -> Result<String, WalletError> {
+        let account = Self::derive_xprv(seed, &bip44_path(0, 0))?;
+        Ok(Self::wallet_id_from_public_key(&account.public_key().to_bytes()))
+    }
+
+    /// Wallet id of a single imported private key. Matches the id of a seed
+    /// whose account 0 is this key, so the same key is never held twice.
+    pub fn wallet_id_from_private_key(key_bytes: &[u8]) -> Result<String, WalletError> {
+        let secret_key = SecretKey::from_byte_array(key_bytes.try_into().map_err(|_| WalletError::crypto("Invalid private key length".to_string()))?)
+            .map_err(|e| WalletError::crypto(format!("Invalid private key: {}", e)))?;
+        let public_key = PublicKey::from_secret_key(&Secp256k1::new(), &secret_key);
+        Ok(Self::wallet_id_from_public_key(&public_key.serialize()))
+    }
+
+    fn wallet_id_from_public_key(compressed_public_key: &[u8]) -> String {
+        use sha2::{Digest, Sha256};
+
+        let fingerprint = Sha256::digest(compressed_public_key);
+        format!("wallet_{}", hex::encode(&fingerprint[..16]))
+    }
+
+    /// Whether `seed_id` holds the seed of `seed_phrase`
+    pub fn seed_matches(&self, seed_id: &str, seed_phrase: &str) -> Result<bool, WalletError> {
+        let seed = Self::seed_from_phrase(seed_phrase)?;
+        let stored = Zeroizing::new(self.storage.retrieve(seed_id)?);
+        Ok(stored[..] == seed[..])
+    }
+
+    fn derive_xprv(seed: &[u8], path: &str) -> Result<XPrv, WalletError> {
+        let xprv = XPrv::new(seed)
             .map_err(|e| WalletError::crypto(format!("Failed to create XPrv: {}", e)))?;
-        
-        // Standard Ethereum path: m/44'/60'/0'/0/0
-        let derivation_path = DerivationPath::from_str("m/44'/60'/0'/0/0")
+        let derivation_path = DerivationPath::from_str(path)
             .map_err(|e| WalletError::crypto(format!("Invalid derivation path: {}", e)))?;
-        
+
         let mut child_xprv = xprv;
         for child_number in derivation_path.into_iter() {
             child_xprv = child_xprv.derive_child(child_number)
                 .map_err(|e| WalletError::crypto(format!("Failed to derive child XPrv: {}", e)))?;
         }
-        
-        let private_key_bytes = child_xprv.private_key().to_bytes();
-        
+        Ok(child_xprv)
+    }
+
+    fn derive_and_store(&self, seed: &[u8], path: &str, key_id: &str) -> Result<SecurePrivateKey, WalletError> {
+        let child_xprv = Self::derive_xprv(seed, path)?;
+        let private_key_bytes: Zeroizing<[u8; 32]> = Zeroizing::new(child_xprv.private_key().to_bytes().into());
+
         // Store the derived private key securely
-        SecurePrivateKey::from_bytes(key_id.to_string(), &private_key_bytes, self.storage)
+        SecurePrivateKey::from_bytes(key_id.to_string(), &private_key_bytes[..], self.storage)
     }
 
     /// Validate a private key without loading it into memory
@@ -311,4 +419,47 @@ mod tests {
             .expect("Failed to derive private key from seed");
         assert_eq!(private_key.key_id(), "test_id");
     }
+
+    #[test]
+    fn test_account_derivation_from_stored_seed() {
+        let storage = MockStorage::new();
+        let manager = KeyManager::new(&storage);
+        let seed_phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        manager.store_seed("seed", seed_phrase).expect("Failed to store seed");
+
+        let address_of = |key: &SecurePrivateKey| {
+            let public_key = manager.get_public_key(key).expect("Failed to get public key");
+            manager.get_address(&public_key).expect("Failed to get address")
+        };
+        let account0 = manager.derive_account_key("seed", 0, 0, "account_0").expect("Failed to derive account 0");
+        let account1 = manager.derive_account_key("seed", 1, 0, "account_1").expect("Failed to derive account 1");
+
+        // Well-known first address for this test mnemonic
+        assert_eq!(address_of(&account0), "0x9858effd232b4033e47d90003d41ec34ecaeda94");
+        assert_ne!(address_of(&account0), address_of(&account1));
+        assert_eq!(bip44_path(1, 0), "m/44'/60'/1'/0/0");
+    }
+
+    #[test]
+    fn test_wallet_id_is_stable_per_seed() {
+        let seed_phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let other = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+
+        let id = KeyManager::wallet_id_from_seed(seed_phrase).expect("Failed to derive wallet id");
+        assert_eq!(id, KeyManager::wallet_id_from_seed(seed_phrase).unwrap());
+        assert_ne!(id, KeyManager::wallet_id_from_seed(other).unwrap());
+        // The same id comes from the seed bytes recovered from shares, and
+        // from account 0's key imported on its own
+        let seed = KeyManager::seed_from_phrase(seed_phrase).unwrap();
+        assert_eq!(KeyManager::wallet_id_from_seed_bytes(&seed[..]).unwrap(), id);
+        let account0 = hex::decode("1ab42cc412b618bdea3a599e3c9bae199ebf030895b039e9db1e30dafb12b727").unwrap();
+        assert_eq!(KeyManager::wallet_id_from_private_key(&account0).unwrap(), id);
+        assert_eq!(id.len(), "wallet_".len() + 32);
+
+        let storage = MockStorage::new();
+        let manager = KeyManager::new(&storage);
+        manager.store_seed("seed", seed_phrase).unwrap();
+        assert!(manager.seed_matches("seed", seed_phrase).unwrap());
+        assert!(!manager.seed_matches("seed", other).unwrap());
+    }
 } 
\ No newline at end of file
@@ -5,8 +5,10 @@
 pub mod secure_private_key;
 pub mod key_manager;
 pub mod secure_seed_phrase;
+pub mod seed_check;
 
 // Re-export all public items from submodules
 pub use secure_private_key::*;
 pub use key_manager::*;
-pub use secure_seed_phrase::*; 
\ No newline at end of file
+pub use secure_seed_phrase::*;
+pub use seed_check::{check_seed_phrase, SeedPhraseReport, SeedPhraseWarning, SeedPhraseWarningKind}; 
\ No newline at end of file
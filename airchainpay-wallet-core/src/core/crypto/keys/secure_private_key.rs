@@ -63,13 +63,19 @@ impl SecurePrivateKey {
     /// Generate a new private key and store it securely
     /// Uses cryptographically secure random number generation
     pub fn generate(key_id: String, storage: &dyn crate::infrastructure::platform::PlatformStorage) -> Result<Self, WalletError> {
-        use rand_core::OsRng;
-        use rand_core::RngCore;
+        Self::generate_with(key_id, storage, &crate::shared::sources::OsRandom)
+    }
+
+    /// Generate a new private key from the given random source and store it securely
+    pub fn generate_with(
+        key_id: String,
+        storage: &dyn crate::infrastructure::platform::PlatformStorage,
+        rng: &dyn crate::shared::sources::RandomSource,
+    ) -> Result<Self, WalletError> {
         use secp256k1::SecretKey;
 
-        let mut rng = OsRng;
         let mut key_bytes = Zeroizing::new([0u8; PRIVATE_KEY_SIZE]);
-        rng.fill_bytes(&mut *key_bytes);
+        rng.fill_bytes(&mut *key_bytes)?;
 
         // Ensure the key is valid for secp256k1
         let _secret_key = SecretKey::from_byte_array(*key_bytes)
@@ -0,0 +1,139 @@
+//! Sanity checks for imported seed phrases
+//!
+//! A phrase can be valid BIP39 and still be unsafe: development tools ship
+//! well-known mnemonics whose accounts are swept by bots within seconds, and
+//! hand-made phrases tend to repeat words or walk the word list. These checks
+//! never reject a phrase; they return warnings for the app to show before the
+//! user funds the wallet.
+
+use crate::shared::error::WalletError;
+use bip39::{Language, Mnemonic};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use zeroize::Zeroizing;
+
+/// Public mnemonics from development tools and the BIP39 test vectors
+const KNOWN_PUBLIC_PHRASES: &[&str] = &[
+    // Hardhat and Anvil
+    "test test test test test test test test test test test junk",
+    // Ganache
+    "candy maple cake sugar pudding cream honey rich smooth crumble sweet treat",
+    // Truffle Develop
+    "myth like bonus scare over problem client lizard pioneer submit female collect",
+    // BIP39 test vectors
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+    "legal winner thank year wave sausage worth useful legal winner thank yellow",
+    "letter advice cage absurd amount doctor acoustic avoid letter advice cage above",
+    "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong",
+];
+
+/// Distinct entropy bytes at or below which a phrase counts as low entropy
+const MIN_DISTINCT_ENTROPY_BYTES: usize = 4;
+/// Longest entropy period (in bytes) treated as a repeating pattern
+const MAX_ENTROPY_PERIOD: usize = 4;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SeedPhraseWarningKind {
+    /// Published by a development tool or test suite
+    KnownPublicPhrase,
+    /// One word makes up at least half of the phrase
+    RepeatedWord,
+    /// Entropy or word indexes follow a simple pattern
+    LowEntropy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedPhraseWarning {
+    pub kind: SeedPhraseWarningKind,
+    pub message: String,
+}
+
+/// Result of the pre-import seed phrase check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedPhraseReport {
+    pub word_count: usize,
+    pub warnings: Vec<SeedPhraseWarning>,
+}
+
+impl SeedPhraseReport {
+    pub fn is_clean(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    fn warn(&mut self, kind: SeedPhraseWarningKind, message: impl Into<String>) {
+        self.warnings.push(SeedPhraseWarning { kind, message: message.into() });
+    }
+}
+
+/// Check a seed phrase before import. Fails only if the phrase is not valid
+/// BIP39; weak but valid phrases come back with warnings.
+pub fn check_seed_phrase(seed_phrase: &str) -> Result<SeedPhraseReport, WalletError> {
+    let normalized = seed_phrase.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let mnemonic = Mnemonic::parse_in_normalized(Language::English, &normalized)
+        .map_err(|e| WalletError::validation(format!("Invalid BIP39 seed phrase: {}", e)))?;
+    let mut report = SeedPhraseReport { word_count: mnemonic.word_count(), warnings: Vec::new() };
+
+    if KNOWN_PUBLIC_PHRASES.contains(&normalized.as_str()) {
+        report.warn(
+            SeedPhraseWarningKind::KnownPublicPhrase,
+            "This is a publicly known test phrase; anyone can spend funds sent to it",
+        );
+    }
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for word in mnemonic.words() {
+        *counts.entry(word).or_default() += 1;
+    }
+    if let Some((word, count)) = counts.into_iter().max_by_key(|(_, count)| *count) {
+        if count * 2 >= report.word_count {
+            report.warn(
+                SeedPhraseWarningKind::RepeatedWord,
+                format!("The word \"{}\" appears {} times in {} words", word, count, report.word_count),
+            );
+        }
+    }
+
+    let entropy = Zeroizing::new(mnemonic.to_entropy());
+    let distinct: HashSet<u8> = entropy.iter().copied().collect();
+    let periodic = (1..=MAX_ENTROPY_PERIOD).any(|period| entropy.iter().skip(period).zip(entropy.iter()).all(|(a, b)| a == b));
+    let indexes: Vec<i64> = mnemonic.word_indices().map(|i| i as i64).collect();
+    // All but the checksum-bearing last word step through the word list evenly
+    let sequential = indexes[..indexes.len() - 1].windows(3).all(|w| w[1] - w[0] == w[2] - w[1]);
+    if distinct.len() <= MIN_DISTINCT_ENTROPY_BYTES || periodic || sequential {
+        report.warn(
+            SeedPhraseWarningKind::LowEntropy,
+            "The phrase follows a simple pattern and is easy to guess",
+        );
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(phrase: &str) -> Vec<SeedPhraseWarningKind> {
+        check_seed_phrase(phrase).unwrap().warnings.into_iter().map(|w| w.kind).collect()
+    }
+
+    #[test]
+    fn test_weak_phrases_are_flagged() {
+        assert_eq!(
+            kinds("test test test test test test test test test test test junk"),
+            vec![SeedPhraseWarningKind::KnownPublicPhrase, SeedPhraseWarningKind::RepeatedWord, SeedPhraseWarningKind::LowEntropy]
+        );
+        assert_eq!(
+            kinds("Candy maple cake sugar  pudding cream honey rich smooth crumble sweet treat"),
+            vec![SeedPhraseWarningKind::KnownPublicPhrase]
+        );
+        // The first eleven words of the list in order, plus a checksum word
+        let sequential = Mnemonic::from_entropy(&[0x00, 0x00, 0x04, 0x01, 0x00, 0x30, 0x08, 0x01, 0x40, 0x30, 0x07, 0x01, 0x00, 0x24, 0x05, 0x00]).unwrap();
+        assert!(kinds(&sequential.to_string()).contains(&SeedPhraseWarningKind::LowEntropy));
+
+        let random = Mnemonic::from_entropy(&[0x6b, 0x1f, 0xd2, 0x48, 0x93, 0xa7, 0x0c, 0xe5, 0x31, 0x9e, 0x54, 0xf0, 0x27, 0x8a, 0xcd, 0x16]).unwrap();
+        assert!(check_seed_phrase(&random.to_string()).unwrap().is_clean());
+        assert!(check_seed_phrase("test test test").is_err());
+    }
+}
@@ -14,6 +14,8 @@ pub mod encryption;
 pub mod hashing;
 pub mod password;
 pub mod security_audit;
+pub mod signing;
+pub mod sss;
 
 // Re-export all public items from submodules
 pub use keys::*;
@@ -22,6 +24,7 @@ pub use encryption::*;
 pub use hashing::*;
 pub use password::*;
 pub use security_audit::*;
+pub use signing::{EcdsaSignature, LedgerBackend, LocalKeyBackend, SigningBackend};
 
 #[cfg(test)]
 mod tests {
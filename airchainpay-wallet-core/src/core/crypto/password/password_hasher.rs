@@ -2,8 +2,8 @@ use crate::shared::error::WalletError;
 use crate::shared::WalletResult;
 use argon2::{Argon2, PasswordHash, PasswordVerifier};
 use pbkdf2::pbkdf2;
-use rand_core::OsRng;
-use rand_core::RngCore;
+use crate::shared::sources::{default_random_source, RandomSource};
+use std::sync::Arc;
 use zeroize::Zeroize;
 use super::{PasswordConfig, PasswordAlgorithm};
 use argon2::PasswordHasher;
@@ -13,11 +13,18 @@ use base64::Engine;
 /// Secure password hasher
 pub struct WalletPasswordHasher {
     config: PasswordConfig,
+    rng: Arc<dyn RandomSource>,
 }
 
 impl WalletPasswordHasher {
     pub fn new(config: PasswordConfig) -> Self {
-        Self { config }
+        Self { config, rng: default_random_source() }
+    }
+
+    /// Draw salts from `rng` instead of the OS
+    pub fn with_random_source(mut self, rng: Arc<dyn RandomSource>) -> Self {
+        self.rng = rng;
+        self
     }
 
     pub fn new_default() -> Self {
@@ -26,7 +33,7 @@ impl WalletPasswordHasher {
 
     /// Hash a password securely
     pub fn hash_password(&self, password: &str) -> WalletResult<String> {
-        let salt = self.generate_salt();
+        let salt = self.generate_salt()?;
         
         match self.config.algorithm {
             PasswordAlgorithm::Argon2 => self.hash_argon2(password, &salt),
@@ -43,11 +50,8 @@ impl WalletPasswordHasher {
     }
 
     /// Generate a secure random salt
-    fn generate_salt(&self) -> Vec<u8> {
-        let mut salt = vec![0u8; self.config.salt_length];
-        let mut rng = OsRng;
-        rng.fill_bytes(&mut salt);
-        salt
+    fn generate_salt(&self) -> WalletResult<Vec<u8>> {
+        self.rng.random_bytes(self.config.salt_length)
     }
 
     /// Hash password using Argon2
@@ -6,10 +6,14 @@ use secp256k1::ecdsa::{Signature, RecoverableSignature, RecoveryId};
 use sha3::{Keccak256, Digest};
 use std::str::FromStr;
 use super::TransactionSignature;
-use crate::shared::types::Transaction;
+use crate::shared::canonical::CanonicalEncode;
+use crate::shared::types::{SignedRelayReceipt, Transaction, TransactionType};
 use ethers::types::U256;
 use rlp::RlpStream;
 
+/// EIP-2718 type byte for EIP-1559 transactions
+pub const EIP1559_TX_TYPE: u8 = 0x02;
+
 /// Digital signature manager
 pub struct SignatureManager {
     secp: Secp256k1<secp256k1::All>,
@@ -189,6 +193,163 @@ impl SignatureManager {
         Ok((raw_tx, tx_hash))
     }
 
+    /// Sign a type-2 (EIP-1559) transaction and return raw tx and tx hash
+    pub fn sign_eip1559_raw(&self, tx: &Transaction, key_bytes: &[u8]) -> WalletResult<(Vec<u8>, String)> {
+        let secret_key = SecretKey::from_byte_array(key_bytes.try_into().map_err(|_| WalletError::crypto("Invalid private key length".to_string()))?)
+            .map_err(|e| WalletError::crypto(format!("Invalid private key: {}", e)))?;
+
+        let nonce = tx.nonce.ok_or_else(|| WalletError::validation("Missing nonce"))?;
+        let max_fee = tx.max_fee_per_gas.ok_or_else(|| WalletError::validation("Missing max fee per gas"))?;
+        let priority_fee = tx.max_priority_fee_per_gas.ok_or_else(|| WalletError::validation("Missing max priority fee per gas"))?;
+        let gas_limit = tx.gas_limit.ok_or_else(|| WalletError::validation("Missing gas limit"))?;
+        if priority_fee > max_fee {
+            return Err(WalletError::validation("Max priority fee per gas exceeds max fee per gas"));
+        }
+
+        let to_bytes = if tx.to.is_empty() { Vec::new() } else { hex::decode(tx.to.trim_start_matches("0x")).map_err(|_| WalletError::validation("Invalid to address"))? };
+        let value_u256 = U256::from_dec_str(&tx.value).map_err(|_| WalletError::validation("Invalid value"))?;
+        let value_bytes = Self::u256_to_bytes_be(value_u256);
+        let data_bytes = tx.data.clone().unwrap_or_default();
+
+        // 0x02 || rlp([chain_id, nonce, priority_fee, max_fee, gas_limit, to, value, data, access_list])
+        let fields = |s: &mut RlpStream| {
+            Self::append_eip1559_fields(s, tx, nonce, priority_fee, max_fee, gas_limit, &to_bytes, &value_bytes, &data_bytes)
+        };
+
+        let mut payload = RlpStream::new_list(9);
+        fields(&mut payload);
+        let mut hasher = Keccak256::new();
+        hasher.update([EIP1559_TX_TYPE]);
+        hasher.update(payload.out());
+        let sighash = hasher.finalize();
+        let msg = Message::from_digest(sighash.as_slice().try_into().map_err(|_| WalletError::crypto("Invalid tx hash length"))?);
+
+        let rec_sig: RecoverableSignature = self.secp.sign_ecdsa_recoverable(msg, &secret_key);
+        let (rec_id, compact) = rec_sig.serialize_compact();
+        let y_parity = i32::from(rec_id) as u64;
+        let r = Self::u256_to_bytes_be(U256::from_big_endian(&compact[0..32]));
+        let s = Self::u256_to_bytes_be(U256::from_big_endian(&compact[32..64]));
+
+        let mut signed = RlpStream::new_list(12);
+        fields(&mut signed);
+        signed.append(&y_parity);
+        signed.append(&r.as_slice());
+        signed.append(&s.as_slice());
+
+        let mut raw_tx = vec![EIP1559_TX_TYPE];
+        raw_tx.extend_from_slice(&signed.out());
+        let mut hasher2 = Keccak256::new();
+        hasher2.update(&raw_tx);
+        let tx_hash = format!("0x{}", hex::encode(hasher2.finalize()));
+        Ok((raw_tx, tx_hash))
+    }
+
+    fn append_eip1559_fields(
+        s: &mut RlpStream,
+        tx: &Transaction,
+        nonce: u64,
+        priority_fee: u64,
+        max_fee: u64,
+        gas_limit: u64,
+        to_bytes: &[u8],
+        value_bytes: &[u8],
+        data_bytes: &[u8],
+    ) {
+        s.append(&tx.chain_id);
+        s.append(&nonce);
+        s.append(&priority_fee);
+        s.append(&max_fee);
+        s.append(&gas_limit);
+        if to_bytes.is_empty() {
+            s.append_empty_data();
+        } else {
+            s.append(&to_bytes);
+        }
+        s.append(&value_bytes);
+        s.append(&data_bytes);
+        s.begin_list(0);
+    }
+
+    /// Recipient, value and calldata in the byte form both transaction types encode
+    fn payload_fields(tx: &Transaction) -> WalletResult<(Vec<u8>, Vec<u8>, Vec<u8>)> {
+        let to_bytes = if tx.to.is_empty() { Vec::new() } else { hex::decode(tx.to.trim_start_matches("0x")).map_err(|_| WalletError::validation("Invalid to address"))? };
+        let value_u256 = U256::from_dec_str(&tx.value).map_err(|_| WalletError::validation("Invalid value"))?;
+        Ok((to_bytes, Self::u256_to_bytes_be(value_u256), tx.data.clone().unwrap_or_default()))
+    }
+
+    /// The bytes an external signer signs: the EIP-155 signing RLP for legacy
+    /// transactions, `0x02 || rlp(fields)` for EIP-1559. The sighash is their Keccak-256.
+    pub fn unsigned_payload(&self, tx: &Transaction) -> WalletResult<Vec<u8>> {
+        let nonce = tx.nonce.ok_or_else(|| WalletError::validation("Missing nonce"))?;
+        let gas_limit = tx.gas_limit.ok_or_else(|| WalletError::validation("Missing gas limit"))?;
+        let (to_bytes, value_bytes, data_bytes) = Self::payload_fields(tx)?;
+        match tx.tx_type {
+            TransactionType::Legacy => {
+                let gas_price = tx.gas_price.ok_or_else(|| WalletError::validation("Missing gas price"))?;
+                Ok(self.encode_legacy_signing_payload(tx, nonce, gas_price, gas_limit, to_bytes, value_bytes, data_bytes))
+            }
+            TransactionType::Eip1559 => {
+                let max_fee = tx.max_fee_per_gas.ok_or_else(|| WalletError::validation("Missing max fee per gas"))?;
+                let priority_fee = tx.max_priority_fee_per_gas.ok_or_else(|| WalletError::validation("Missing max priority fee per gas"))?;
+                let mut fields = RlpStream::new_list(9);
+                Self::append_eip1559_fields(&mut fields, tx, nonce, priority_fee, max_fee, gas_limit, &to_bytes, &value_bytes, &data_bytes);
+                let mut payload = vec![EIP1559_TX_TYPE];
+                payload.extend_from_slice(&fields.out());
+                Ok(payload)
+            }
+        }
+    }
+
+    /// Work out the recovery id of an `(r, s)` signature over `payload` by
+    /// finding which one recovers `address`. External signers report `v` in
+    /// device-specific ways, so it is not trusted.
+    pub fn recovery_parity(&self, payload: &[u8], r: &[u8; 32], s: &[u8; 32], address: &str) -> WalletResult<u8> {
+        let sighash = Keccak256::digest(payload);
+        let msg = Message::from_digest(sighash.as_slice().try_into().map_err(|_| WalletError::crypto("Invalid tx hash length"))?);
+        let compact = [r.as_slice(), s.as_slice()].concat();
+        for parity in 0..2i32 {
+            let rec_id = RecoveryId::try_from(parity).map_err(|e| WalletError::crypto(format!("Invalid recovery id: {}", e)))?;
+            let Ok(signature) = RecoverableSignature::from_compact(&compact, rec_id) else { continue };
+            let Ok(public_key) = self.secp.recover_ecdsa(msg, &signature) else { continue };
+            let hash = Keccak256::digest(&public_key.serialize_uncompressed()[1..]);
+            if hex::encode(&hash[12..]).eq_ignore_ascii_case(address.trim_start_matches("0x")) {
+                return Ok(parity as u8);
+            }
+        }
+        Err(WalletError::crypto(format!("Signature was not made by {}", address)))
+    }
+
+    /// Build the raw transaction and its hash from a signature over
+    /// `unsigned_payload(tx)`
+    pub fn assemble_signed(&self, tx: &Transaction, parity: u8, r: &[u8; 32], s: &[u8; 32]) -> WalletResult<(Vec<u8>, String)> {
+        let nonce = tx.nonce.ok_or_else(|| WalletError::validation("Missing nonce"))?;
+        let gas_limit = tx.gas_limit.ok_or_else(|| WalletError::validation("Missing gas limit"))?;
+        let (to_bytes, value_bytes, data_bytes) = Self::payload_fields(tx)?;
+        let raw_tx = match tx.tx_type {
+            TransactionType::Legacy => {
+                let gas_price = tx.gas_price.ok_or_else(|| WalletError::validation("Missing gas price"))?;
+                let v = U256::from(parity as u64 + 35 + 2 * tx.chain_id);
+                self.encode_legacy_raw_tx(nonce, gas_price, gas_limit, to_bytes, value_bytes, data_bytes, v, r.to_vec(), s.to_vec())
+            }
+            TransactionType::Eip1559 => {
+                let max_fee = tx.max_fee_per_gas.ok_or_else(|| WalletError::validation("Missing max fee per gas"))?;
+                let priority_fee = tx.max_priority_fee_per_gas.ok_or_else(|| WalletError::validation("Missing max priority fee per gas"))?;
+                let r = Self::u256_to_bytes_be(U256::from_big_endian(r));
+                let s = Self::u256_to_bytes_be(U256::from_big_endian(s));
+                let mut signed = RlpStream::new_list(12);
+                Self::append_eip1559_fields(&mut signed, tx, nonce, priority_fee, max_fee, gas_limit, &to_bytes, &value_bytes, &data_bytes);
+                signed.append(&(parity as u64));
+                signed.append(&r.as_slice());
+                signed.append(&s.as_slice());
+                let mut raw_tx = vec![EIP1559_TX_TYPE];
+                raw_tx.extend_from_slice(&signed.out());
+                raw_tx
+            }
+        };
+        let tx_hash = format!("0x{}", hex::encode(Keccak256::digest(&raw_tx)));
+        Ok((raw_tx, tx_hash))
+    }
+
     /// Recover public key from signature
     pub fn recover_public_key(&self, message: &[u8], _signature: &Signature, _v: u8) -> WalletResult<PublicKey> {
         // Hash the message (Ethereum style)
@@ -230,6 +391,43 @@ impl SignatureManager {
         self.verify_signature(payment_data, &signature_obj, public_key)
     }
 
+    /// Sign `message` as an EIP-191 personal message; returns `r || s || v` as hex
+    pub fn sign_personal_message(&self, message: &[u8], key_bytes: &[u8]) -> WalletResult<String> {
+        let secret_key = SecretKey::from_byte_array(key_bytes.try_into().map_err(|_| WalletError::crypto("Invalid private key length".to_string()))?)
+            .map_err(|e| WalletError::crypto(format!("Invalid private key: {}", e)))?;
+        let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+        prefixed.extend_from_slice(message);
+        let msg = Message::from_digest(Keccak256::digest(&prefixed).into());
+        let (rec_id, compact) = self.secp.sign_ecdsa_recoverable(msg, &secret_key).serialize_compact();
+        let mut signature = compact.to_vec();
+        signature.push(27 + i32::from(rec_id) as u8);
+        Ok(hex::encode(signature))
+    }
+
+    /// Check an EIP-191 personal-message signature (`r || s || v`, hex) against `address`
+    pub fn verify_personal_message(&self, message: &[u8], signature: &str, address: &str) -> WalletResult<bool> {
+        let bytes = hex::decode(signature.trim_start_matches("0x"))
+            .map_err(|e| WalletError::crypto(format!("Invalid signature format: {}", e)))?;
+        if bytes.len() != 65 {
+            return Err(WalletError::crypto("Signature must be 65 bytes"));
+        }
+        let r: [u8; 32] = bytes[..32].try_into().map_err(|_| WalletError::crypto("Invalid signature r"))?;
+        let s: [u8; 32] = bytes[32..64].try_into().map_err(|_| WalletError::crypto("Invalid signature s"))?;
+        let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+        prefixed.extend_from_slice(message);
+        Ok(self.recovery_parity(&prefixed, &r, &s, address).is_ok())
+    }
+
+    /// Check that a relay receipt was signed, over its canonical bytes, by
+    /// the relay it names
+    pub fn verify_relay_receipt(&self, receipt: &SignedRelayReceipt) -> WalletResult<bool> {
+        if receipt.encoding != "cbor-1" {
+            return Err(WalletError::validation(format!("Unsupported receipt encoding {:?}", receipt.encoding)));
+        }
+        let payload = receipt.receipt.canonical_bytes()?;
+        self.verify_personal_message(&payload, &receipt.signature, &receipt.receipt.relay)
+    }
+
     /// Sign QR payment data with key bytes
     pub fn sign_qr_payment_with_bytes(&self, payment_data: &[u8], key_bytes: &[u8]) -> WalletResult<String> {
         self.sign_ble_payment_with_bytes(payment_data, key_bytes)
@@ -0,0 +1,178 @@
+//! Secure Enclave / StrongBox signer
+//!
+//! The key is generated inside the platform enclave and never leaves it;
+//! the wallet only sees its public key. Each transaction's Keccak-256
+//! sighash is handed to the enclave, and the returned signature is
+//! normalised to low-s, which enclaves do not guarantee but Ethereum
+//! requires. Ethereum accounts need secp256k1: P-256 keys, the only kind
+//! the iOS Secure Enclave and Android StrongBox make, cannot control one.
+
+use super::{EcdsaSignature, SigningBackend};
+use crate::infrastructure::platform::{EnclaveCurve, SecureEnclave};
+use crate::shared::error::WalletError;
+use async_trait::async_trait;
+use secp256k1::ecdsa::Signature;
+use secp256k1::PublicKey;
+use sha3::{Digest, Keccak256};
+use std::sync::Arc;
+
+/// Signs with a secp256k1 key held in a secure enclave
+pub struct EnclaveBackend {
+    key_id: String,
+    enclave: Arc<dyn SecureEnclave>,
+    address: String,
+}
+
+impl EnclaveBackend {
+    /// Generate a new secp256k1 key in `enclave` under `key_id`
+    pub fn generate(key_id: impl Into<String>, enclave: Arc<dyn SecureEnclave>) -> Result<Self, WalletError> {
+        let key_id = key_id.into();
+        if !enclave.is_available()? {
+            return Err(WalletError::config("Secure enclave is not available"));
+        }
+        if !enclave.supported_curves()?.contains(&EnclaveCurve::Secp256k1) {
+            return Err(WalletError::config(
+                "Secure enclave cannot generate secp256k1 keys; P-256 keys cannot sign Ethereum transactions",
+            ));
+        }
+        let public_key = enclave.generate_key_pair(&key_id, EnclaveCurve::Secp256k1)?;
+        match address_of_public_key(&public_key) {
+            Ok(address) => Ok(Self { key_id, enclave, address }),
+            Err(e) => {
+                let _ = enclave.delete_key(&key_id);
+                Err(e)
+            }
+        }
+    }
+
+    /// Use a key `enclave` already holds
+    pub fn open(key_id: impl Into<String>, enclave: Arc<dyn SecureEnclave>) -> Result<Self, WalletError> {
+        let key_id = key_id.into();
+        let address = address_of_public_key(&enclave.get_public_key(&key_id)?)?;
+        Ok(Self { key_id, enclave, address })
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Erase the key from the enclave; the wallet's funds become unreachable
+    pub fn delete_key(&self) -> Result<(), WalletError> {
+        self.enclave.delete_key(&self.key_id)
+    }
+}
+
+/// `0x` address of a hex SEC1 secp256k1 public key, compressed or not
+fn address_of_public_key(public_key: &str) -> Result<String, WalletError> {
+    let bytes = hex::decode(public_key.trim_start_matches("0x"))
+        .map_err(|_| WalletError::crypto("Enclave returned a public key that is not hex"))?;
+    let public_key = PublicKey::from_slice(&bytes)
+        .map_err(|e| WalletError::crypto(format!("Enclave key is not a secp256k1 public key: {}", e)))?;
+    let hash = Keccak256::digest(&public_key.serialize_uncompressed()[1..]);
+    Ok(format!("0x{}", hex::encode(&hash[12..])))
+}
+
+#[async_trait]
+impl SigningBackend for EnclaveBackend {
+    fn name(&self) -> &str {
+        "enclave"
+    }
+
+    async fn address(&self) -> Result<String, WalletError> {
+        Ok(self.address.clone())
+    }
+
+    async fn sign_transaction_payload(&self, payload: &[u8]) -> Result<EcdsaSignature, WalletError> {
+        let sighash = Keccak256::digest(payload);
+        let raw = self.enclave.sign(&self.key_id, &sighash)?;
+        let mut signature = match raw.len() {
+            64 => Signature::from_compact(&raw),
+            _ => Signature::from_der(&raw),
+        }
+        .map_err(|e| WalletError::crypto(format!("Enclave returned an invalid signature: {}", e)))?;
+        signature.normalize_s();
+
+        let compact = signature.serialize_compact();
+        let mut signature = EcdsaSignature { r: [0u8; 32], s: [0u8; 32] };
+        signature.r.copy_from_slice(&compact[..32]);
+        signature.s.copy_from_slice(&compact[32..]);
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::crypto::signatures::SignatureManager;
+    use crate::core::transactions::{TransactionBuilder, TransactionManager};
+    use crate::shared::types::Network;
+    use secp256k1::{Message, Secp256k1, SecretKey};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// A software "enclave" answering with DER signatures, as Android Keystore does
+    struct SoftwareEnclave {
+        keys: Mutex<HashMap<String, SecretKey>>,
+        key: [u8; 32],
+    }
+
+    impl SecureEnclave for SoftwareEnclave {
+        fn is_available(&self) -> Result<bool, WalletError> {
+            Ok(true)
+        }
+
+        fn supported_curves(&self) -> Result<Vec<EnclaveCurve>, WalletError> {
+            Ok(vec![EnclaveCurve::P256, EnclaveCurve::Secp256k1])
+        }
+
+        fn generate_key_pair(&self, key_id: &str, _curve: EnclaveCurve) -> Result<String, WalletError> {
+            let secret_key = SecretKey::from_byte_array(self.key).unwrap();
+            self.keys.lock().unwrap().insert(key_id.to_string(), secret_key);
+            self.get_public_key(key_id)
+        }
+
+        fn sign(&self, key_id: &str, digest: &[u8]) -> Result<Vec<u8>, WalletError> {
+            let keys = self.keys.lock().unwrap();
+            let secret_key = keys.get(key_id).ok_or_else(|| WalletError::crypto("No such key"))?;
+            let msg = Message::from_digest(digest.try_into().unwrap());
+            Ok(Secp256k1::new().sign_ecdsa(msg, secret_key).serialize_der().to_vec())
+        }
+
+        fn get_public_key(&self, key_id: &str) -> Result<String, WalletError> {
+            let keys = self.keys.lock().unwrap();
+            let secret_key = keys.get(key_id).ok_or_else(|| WalletError::crypto("No such key"))?;
+            Ok(hex::encode(PublicKey::from_secret_key(&Secp256k1::new(), secret_key).serialize()))
+        }
+
+        fn delete_key(&self, key_id: &str) -> Result<(), WalletError> {
+            self.keys.lock().unwrap().remove(key_id);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_enclave_key_signs_like_a_stored_key() {
+        let key = hex::decode("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318").unwrap();
+        let enclave = Arc::new(SoftwareEnclave { keys: Mutex::new(HashMap::new()), key: key.clone().try_into().unwrap() });
+        let backend = EnclaveBackend::generate("wallet_enclave_key_test", enclave.clone()).unwrap();
+        assert_eq!(backend.address().await.unwrap(), "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23");
+
+        let transaction = TransactionBuilder::new(Network::CoreTestnet)
+            .to("0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6")
+            .value("1000000000000000")
+            .nonce(3)
+            .gas_limit(21000)
+            .gas_price(1_000_000_000)
+            .build()
+            .unwrap();
+        let signed = TransactionManager::new("http://localhost:8545".to_string())
+            .sign_with_backend(&transaction, &backend)
+            .await
+            .unwrap();
+        let (raw_tx, _) = SignatureManager::new().sign_legacy_raw(&transaction, &key).unwrap();
+        assert_eq!(signed.signature, raw_tx);
+
+        backend.delete_key().unwrap();
+        assert!(EnclaveBackend::open("wallet_enclave_key_test", enclave).is_err());
+    }
+}
@@ -0,0 +1,324 @@
+//! Ledger Ethereum app signer
+//!
+//! APDUs are carried in Ledger's transport framing: each packet starts with
+//! tag `0x05` and a big-endian sequence number, and the first packet also
+//! carries the APDU length. Over USB HID packets are 64 bytes, prefixed with
+//! channel `0x0101` and zero-padded; over BLE they are at most the MTU.
+
+use super::{EcdsaSignature, SigningBackend};
+use crate::core::ble::transport::Transport;
+use crate::core::crypto::keys::bip44_path;
+use crate::shared::error::WalletError;
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+const CLA_ETH: u8 = 0xe0;
+const INS_GET_ADDRESS: u8 = 0x02;
+const INS_SIGN_TX: u8 = 0x04;
+const P1_FIRST_CHUNK: u8 = 0x00;
+const P1_MORE_CHUNKS: u8 = 0x80;
+/// Largest APDU data field
+const MAX_APDU_DATA: usize = 255;
+
+const TAG_APDU: u8 = 0x05;
+#[cfg(any(feature = "hardware_wallet", test))]
+const HID_CHANNEL: u16 = 0x0101;
+#[cfg(any(feature = "hardware_wallet", test))]
+const HID_PACKET_SIZE: usize = 64;
+
+const SW_OK: u16 = 0x9000;
+const SW_USER_REJECTED: u16 = 0x6985;
+const SW_BLIND_SIGNING_DISABLED: u16 = 0x6a80;
+const SW_APP_NOT_OPEN: [u16; 2] = [0x6d00, 0x6e00];
+const SW_LOCKED: u16 = 0x5515;
+
+/// Sends one APDU to the device and returns its response, status word included
+#[async_trait]
+pub trait ApduTransport: Send + Sync {
+    async fn exchange(&self, apdu: &[u8]) -> Result<Vec<u8>, WalletError>;
+}
+
+/// Split an APDU into transport packets of `packet_size` bytes
+fn wrap_apdu(apdu: &[u8], packet_size: usize, channel: Option<u16>) -> Result<Vec<Vec<u8>>, WalletError> {
+    let header = if channel.is_some() { 5 } else { 3 };
+    if packet_size < header + 3 {
+        return Err(WalletError::ble(format!("Packet size {} too small for Ledger framing", packet_size)));
+    }
+    let mut data = (apdu.len() as u16).to_be_bytes().to_vec();
+    data.extend_from_slice(apdu);
+
+    let mut packets = Vec::new();
+    for (seq, chunk) in data.chunks(packet_size - header).enumerate() {
+        let mut packet = Vec::with_capacity(packet_size);
+        if let Some(channel) = channel {
+            packet.extend_from_slice(&channel.to_be_bytes());
+        }
+        packet.push(TAG_APDU);
+        packet.extend_from_slice(&(seq as u16).to_be_bytes());
+        packet.extend_from_slice(chunk);
+        if channel.is_some() {
+            packet.resize(packet_size, 0);
+        }
+        packets.push(packet);
+    }
+    Ok(packets)
+}
+
+/// Collects response packets until the whole APDU has arrived
+struct ApduReader {
+    channel: Option<u16>,
+    expected: Option<usize>,
+    next_seq: u16,
+    data: Vec<u8>,
+}
+
+impl ApduReader {
+    fn new(channel: Option<u16>) -> Self {
+        Self { channel, expected: None, next_seq: 0, data: Vec::new() }
+    }
+
+    fn push(&mut self, packet: &[u8]) -> Result<Option<Vec<u8>>, WalletError> {
+        let mut rest = packet;
+        if let Some(channel) = self.channel {
+            if rest.len() < 2 || u16::from_be_bytes([rest[0], rest[1]]) != channel {
+                return Err(WalletError::ble("Ledger packet on the wrong channel"));
+            }
+            rest = &rest[2..];
+        }
+        if rest.len() < 3 || rest[0] != TAG_APDU || u16::from_be_bytes([rest[1], rest[2]]) != self.next_seq {
+            return Err(WalletError::ble("Unexpected Ledger packet"));
+        }
+        rest = &rest[3..];
+        if self.expected.is_none() {
+            if rest.len() < 2 {
+                return Err(WalletError::ble("Ledger packet missing response length"));
+            }
+            self.expected = Some(u16::from_be_bytes([rest[0], rest[1]]) as usize);
+            rest = &rest[2..];
+        }
+        self.next_seq = self.next_seq.wrapping_add(1);
+
+        let expected = self.expected.unwrap_or_default();
+        let take = rest.len().min(expected - self.data.len());
+        self.data.extend_from_slice(&rest[..take]);
+        Ok((self.data.len() == expected).then(|| std::mem::take(&mut self.data)))
+    }
+}
+
+/// Ledger over BLE, through a `Transport` that writes to the device's write
+/// characteristic and yields notifications from its notify characteristic
+pub struct LedgerBleTransport {
+    transport: Arc<dyn Transport>,
+    exchange: Mutex<()>,
+}
+
+impl LedgerBleTransport {
+    pub fn new(transport: Arc<dyn Transport>) -> Self {
+        Self { transport, exchange: Mutex::new(()) }
+    }
+}
+
+#[async_trait]
+impl ApduTransport for LedgerBleTransport {
+    async fn exchange(&self, apdu: &[u8]) -> Result<Vec<u8>, WalletError> {
+        let _exchange = self.exchange.lock().await;
+        for packet in wrap_apdu(apdu, self.transport.mtu(), None)? {
+            self.transport.send(&packet).await?;
+        }
+        let mut reader = ApduReader::new(None);
+        loop {
+            if let Some(response) = reader.push(&self.transport.receive().await?)? {
+                return Ok(response);
+            }
+        }
+    }
+}
+
+/// Ledger over USB HID
+#[cfg(feature = "hardware_wallet")]
+pub struct LedgerHidTransport {
+    device: Arc<std::sync::Mutex<hidapi::HidDevice>>,
+}
+
+#[cfg(feature = "hardware_wallet")]
+impl LedgerHidTransport {
+    const VENDOR_ID: u16 = 0x2c97;
+    const USAGE_PAGE: u16 = 0xffa0;
+    const READ_TIMEOUT_MS: i32 = 60_000;
+
+    /// Open the first connected Ledger
+    pub fn open() -> Result<Self, WalletError> {
+        let api = hidapi::HidApi::new()
+            .map_err(|e| WalletError::config(format!("USB HID unavailable: {}", e)))?;
+        let info = api.device_list()
+            .find(|d| d.vendor_id() == Self::VENDOR_ID && (d.usage_page() == Self::USAGE_PAGE || d.interface_number() == 0))
+            .ok_or_else(|| WalletError::config("No Ledger device connected"))?;
+        let device = info.open_device(&api)
+            .map_err(|e| WalletError::config(format!("Failed to open Ledger: {}", e)))?;
+        Ok(Self { device: Arc::new(std::sync::Mutex::new(device)) })
+    }
+}
+
+#[cfg(feature = "hardware_wallet")]
+#[async_trait]
+impl ApduTransport for LedgerHidTransport {
+    async fn exchange(&self, apdu: &[u8]) -> Result<Vec<u8>, WalletError> {
+        let packets = wrap_apdu(apdu, HID_PACKET_SIZE, Some(HID_CHANNEL))?;
+        let device = Arc::clone(&self.device);
+        // hidapi blocks, and the device may wait on the user for a while
+        tokio::task::spawn_blocking(move || {
+            let device = device.lock().map_err(|_| WalletError::config("Ledger device lock poisoned"))?;
+            for packet in packets {
+                // Report id 0 precedes every HID write
+                let mut report = vec![0u8];
+                report.extend_from_slice(&packet);
+                device.write(&report).map_err(|e| WalletError::config(format!("Ledger write failed: {}", e)))?;
+            }
+            let mut reader = ApduReader::new(Some(HID_CHANNEL));
+            loop {
+                let mut packet = [0u8; HID_PACKET_SIZE];
+                let len = device.read_timeout(&mut packet, Self::READ_TIMEOUT_MS)
+                    .map_err(|e| WalletError::config(format!("Ledger read failed: {}", e)))?;
+                if len == 0 {
+                    return Err(WalletError::config("Ledger did not respond"));
+                }
+                if let Some(response) = reader.push(&packet[..len])? {
+                    return Ok(response);
+                }
+            }
+        })
+        .await
+        .map_err(|e| WalletError::config(format!("Ledger exchange aborted: {}", e)))?
+    }
+}
+
+/// `m/44'/60'/0'/0/0` as the Ledger app expects it: a count, then each
+/// index as a big-endian u32 with the hardened bit set where marked
+fn encode_path(path: &str) -> Result<Vec<u8>, WalletError> {
+    let indices = path.trim_start_matches("m/").split('/')
+        .map(|part| {
+            let (number, hardened) = match part.strip_suffix('\'') {
+                Some(number) => (number, 0x8000_0000u32),
+                None => (part, 0),
+            };
+            number.parse::<u32>().map(|n| n | hardened)
+                .map_err(|_| WalletError::validation(format!("Invalid derivation path {}", path)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if indices.is_empty() || indices.len() > 10 {
+        return Err(WalletError::validation(format!("Invalid derivation path {}", path)));
+    }
+    let mut encoded = vec![indices.len() as u8];
+    for index in indices {
+        encoded.extend_from_slice(&index.to_be_bytes());
+    }
+    Ok(encoded)
+}
+
+/// Split the status word off a response and map failures to errors
+fn check_status(mut response: Vec<u8>) -> Result<Vec<u8>, WalletError> {
+    if response.len() < 2 {
+        return Err(WalletError::crypto("Ledger response too short"));
+    }
+    let sw = response.split_off(response.len() - 2);
+    match u16::from_be_bytes([sw[0], sw[1]]) {
+        SW_OK => Ok(response),
+        SW_USER_REJECTED => Err(WalletError::crypto("Transaction rejected on the Ledger")),
+        SW_BLIND_SIGNING_DISABLED => Err(WalletError::crypto("Ledger refused the transaction; enable blind signing for contract calls")),
+        SW_LOCKED => Err(WalletError::crypto("Ledger is locked")),
+        sw if SW_APP_NOT_OPEN.contains(&sw) => Err(WalletError::crypto("Open the Ethereum app on the Ledger")),
+        sw => Err(WalletError::crypto(format!("Ledger error 0x{:04x}", sw))),
+    }
+}
+
+/// The Ledger Ethereum app signing for one BIP44 account
+pub struct LedgerBackend {
+    transport: Arc<dyn ApduTransport>,
+    path: String,
+}
+
+impl LedgerBackend {
+    /// Sign with the first address of account 0
+    pub fn new(transport: Arc<dyn ApduTransport>) -> Self {
+        Self { transport, path: bip44_path(0, 0) }
+    }
+
+    pub fn with_account(mut self, account_index: u32, address_index: u32) -> Self {
+        self.path = bip44_path(account_index, address_index);
+        self
+    }
+
+    fn apdu(ins: u8, p1: u8, data: &[u8]) -> Vec<u8> {
+        let mut apdu = vec![CLA_ETH, ins, p1, 0x00, data.len() as u8];
+        apdu.extend_from_slice(data);
+        apdu
+    }
+}
+
+#[async_trait]
+impl SigningBackend for LedgerBackend {
+    fn name(&self) -> &str {
+        "ledger"
+    }
+
+    async fn address(&self) -> Result<String, WalletError> {
+        let response = check_status(self.transport.exchange(&Self::apdu(INS_GET_ADDRESS, 0x00, &encode_path(&self.path)?)).await?)?;
+        // pubkey_len || pubkey || address_len || ASCII hex address
+        let pubkey_len = *response.first().ok_or_else(|| WalletError::crypto("Empty Ledger address response"))? as usize;
+        let address_len = *response.get(1 + pubkey_len)
+            .ok_or_else(|| WalletError::crypto("Truncated Ledger address response"))? as usize;
+        let address = response.get(2 + pubkey_len..2 + pubkey_len + address_len)
+            .ok_or_else(|| WalletError::crypto("Truncated Ledger address response"))?;
+        let address = std::str::from_utf8(address)
+            .map_err(|_| WalletError::crypto("Invalid Ledger address"))?;
+        Ok(format!("0x{}", address.trim_start_matches("0x").to_lowercase()))
+    }
+
+    async fn sign_transaction_payload(&self, payload: &[u8]) -> Result<EcdsaSignature, WalletError> {
+        let mut data = encode_path(&self.path)?;
+        data.extend_from_slice(payload);
+
+        let mut response = Vec::new();
+        for (i, chunk) in data.chunks(MAX_APDU_DATA).enumerate() {
+            let p1 = if i == 0 { P1_FIRST_CHUNK } else { P1_MORE_CHUNKS };
+            response = check_status(self.transport.exchange(&Self::apdu(INS_SIGN_TX, p1, chunk)).await?)?;
+        }
+
+        // v || r || s; v is truncated for large chain ids, so it is recomputed
+        if response.len() != 65 {
+            return Err(WalletError::crypto("Unexpected Ledger signature length"));
+        }
+        let mut signature = EcdsaSignature { r: [0u8; 32], s: [0u8; 32] };
+        signature.r.copy_from_slice(&response[1..33]);
+        signature.s.copy_from_slice(&response[33..65]);
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apdu_framing_round_trips_over_hid_and_ble() {
+        let apdu = LedgerBackend::apdu(INS_SIGN_TX, P1_FIRST_CHUNK, &[0x42; 200]);
+        for (size, channel) in [(HID_PACKET_SIZE, Some(HID_CHANNEL)), (23, None)] {
+            let packets = wrap_apdu(&apdu, size, channel).unwrap();
+            assert!(packets.iter().all(|p| p.len() <= size));
+            let mut reader = ApduReader::new(channel);
+            let mut result = None;
+            for packet in &packets {
+                result = reader.push(packet).unwrap();
+            }
+            assert_eq!(result.unwrap(), apdu);
+        }
+
+        assert_eq!(
+            encode_path("m/44'/60'/0'/0/1").unwrap(),
+            [vec![5], 0x8000_002cu32.to_be_bytes().to_vec(), 0x8000_003cu32.to_be_bytes().to_vec(),
+             0x8000_0000u32.to_be_bytes().to_vec(), vec![0, 0, 0, 0], vec![0, 0, 0, 1]].concat()
+        );
+        assert!(check_status(vec![0x69, 0x85]).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! Transaction signers
+//!
+//! A `SigningBackend` holds the key for one address and signs the unsigned
+//! transaction bytes from `SignatureManager::unsigned_payload`. The wallet's
+//! own key in platform storage is one backend; a Ledger device or a key
+//! generated inside the platform's secure enclave are others, so merchants
+//! can take payments without a hot key on the phone.
+
+pub mod enclave;
+pub mod ledger;
+
+pub use enclave::EnclaveBackend;
+pub use ledger::{ApduTransport, LedgerBackend, LedgerBleTransport};
+#[cfg(feature = "hardware_wallet")]
+pub use ledger::LedgerHidTransport;
+
+use crate::core::crypto::keys::{KeyManager, SecurePrivateKey};
+use crate::infrastructure::platform::PlatformStorage;
+use crate::shared::error::WalletError;
+use async_trait::async_trait;
+use secp256k1::{Message, Secp256k1, SecretKey};
+use sha3::{Digest, Keccak256};
+use std::sync::Arc;
+
+/// An ECDSA signature over the Keccak-256 of a transaction payload. The
+/// recovery id is worked out from the signer's address when assembling.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcdsaSignature {
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+}
+
+#[async_trait]
+pub trait SigningBackend: Send + Sync {
+    /// Short name for logs, such as `local` or `ledger`
+    fn name(&self) -> &str;
+
+    /// Checksum-insensitive `0x` address of the signing key
+    async fn address(&self) -> Result<String, WalletError>;
+
+    /// Sign unsigned transaction bytes. Hardware signers show the
+    /// transaction to the user and may take a while or be refused.
+    async fn sign_transaction_payload(&self, payload: &[u8]) -> Result<EcdsaSignature, WalletError>;
+}
+
+/// Signs with a private key kept in platform storage
+pub struct LocalKeyBackend {
+    key_id: String,
+    storage: Arc<dyn PlatformStorage>,
+}
+
+impl LocalKeyBackend {
+    pub fn new(key_id: impl Into<String>, storage: Arc<dyn PlatformStorage>) -> Self {
+        Self { key_id: key_id.into(), storage }
+    }
+}
+
+#[async_trait]
+impl SigningBackend for LocalKeyBackend {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    async fn address(&self) -> Result<String, WalletError> {
+        let key_manager = KeyManager::new(self.storage.as_ref());
+        let private_key = key_manager.get_private_key(&self.key_id)?;
+        let public_key = key_manager.get_public_key(&private_key)?;
+        key_manager.get_address(&public_key)
+    }
+
+    async fn sign_transaction_payload(&self, payload: &[u8]) -> Result<EcdsaSignature, WalletError> {
+        let sighash = Keccak256::digest(payload);
+        let msg = Message::from_digest(sighash.into());
+        SecurePrivateKey::new(self.key_id.clone()).with_key(self.storage.as_ref(), |key_bytes| {
+            let secret_key = SecretKey::from_byte_array(key_bytes.try_into().map_err(|_| WalletError::crypto("Invalid private key length"))?)
+                .map_err(|e| WalletError::crypto(format!("Invalid private key: {}", e)))?;
+            let compact = Secp256k1::new().sign_ecdsa_recoverable(msg, &secret_key).serialize_compact().1;
+            let mut signature = EcdsaSignature { r: [0u8; 32], s: [0u8; 32] };
+            signature.r.copy_from_slice(&compact[..32]);
+            signature.s.copy_from_slice(&compact[32..]);
+            Ok(signature)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::crypto::signatures::SignatureManager;
+    use crate::core::transactions::{TransactionBuilder, TransactionManager};
+    use crate::shared::types::{Network, TransactionType};
+
+    /// A "device" holding the key in memory, answering like an external signer
+    struct InMemorySigner {
+        key: Vec<u8>,
+    }
+
+    #[async_trait]
+    impl SigningBackend for InMemorySigner {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        async fn address(&self) -> Result<String, WalletError> {
+            Ok("0x2c7536E3605D9C16a7a3D7b1898e529396a65c23".to_string())
+        }
+
+        async fn sign_transaction_payload(&self, payload: &[u8]) -> Result<EcdsaSignature, WalletError> {
+            let secret_key = SecretKey::from_byte_array(self.key.as_slice().try_into().unwrap()).unwrap();
+            let msg = Message::from_digest(Keccak256::digest(payload).into());
+            let compact = Secp256k1::new().sign_ecdsa_recoverable(msg, &secret_key).serialize_compact().1;
+            Ok(EcdsaSignature { r: compact[..32].try_into().unwrap(), s: compact[32..].try_into().unwrap() })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_external_signer_produces_same_raw_transaction() {
+        let key = hex::decode("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318").unwrap();
+        let signer = InMemorySigner { key: key.clone() };
+        let manager = TransactionManager::new("http://localhost:8545".to_string());
+
+        for network in [Network::BaseSepolia, Network::CoreTestnet] {
+            let tx_type = network.default_transaction_type();
+            let builder = TransactionBuilder::new(network)
+                .to("0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6")
+                .value("1000000000000000")
+                .nonce(7)
+                .gas_limit(21000);
+            let transaction = match tx_type {
+                TransactionType::Legacy => builder.gas_price(1_000_000_000),
+                TransactionType::Eip1559 => builder.fees(3_000_000_000, 1_000_000_000),
+            }
+            .build()
+            .unwrap();
+            let signed = manager.sign_with_backend(&transaction, &signer).await.unwrap();
+            let (raw_tx, hash) = match transaction.tx_type {
+                TransactionType::Legacy => SignatureManager::new().sign_legacy_raw(&transaction, &key),
+                TransactionType::Eip1559 => SignatureManager::new().sign_eip1559_raw(&transaction, &key),
+            }
+            .unwrap();
+            assert_eq!(signed.signature, raw_tx);
+            assert_eq!(signed.hash, hash);
+        }
+    }
+}
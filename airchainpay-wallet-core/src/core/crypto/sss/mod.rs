@@ -0,0 +1,502 @@
+//! Shamir secret sharing backups (SLIP-39)
+//!
+//! Splits a master secret into mnemonic shares so that any `threshold` of
+//! `count` shares restore it while fewer reveal nothing. The secret is first
+//! encrypted under the passphrase with the SLIP-39 Feistel cipher, then split
+//! over GF(256); each share carries an RS1024 checksum so a mistyped word is
+//! caught before recovery. Shares are interoperable with Trezor and other
+//! SLIP-39 implementations.
+
+mod wordlist;
+
+pub use wordlist::WORDLIST;
+
+use crate::shared::error::WalletError;
+use crate::shared::sources::RandomSource;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use zeroize::Zeroizing;
+
+/// Most shares a group (or groups a secret) can be split into
+pub const MAX_SHARE_COUNT: u8 = 16;
+/// Shortest master secret SLIP-39 accepts, in bytes
+pub const MIN_SECRET_LEN: usize = 16;
+/// PBKDF2 cost exponent used when splitting: `10000 << e` iterations in total
+pub const ITERATION_EXPONENT: u8 = 1;
+
+const RADIX_BITS: usize = 10;
+const ID_BITS: usize = 15;
+const CHECKSUM_WORDS: usize = 3;
+/// Identifier, flags and group/member parameters take four words
+const METADATA_WORDS: usize = 4;
+const MIN_MNEMONIC_WORDS: usize = METADATA_WORDS + CHECKSUM_WORDS + (MIN_SECRET_LEN * 8).div_ceil(RADIX_BITS);
+const DIGEST_LEN: usize = 4;
+const DIGEST_INDEX: u8 = 254;
+const SECRET_INDEX: u8 = 255;
+const ROUND_COUNT: u8 = 4;
+const BASE_ITERATION_COUNT: u32 = 10_000;
+
+const CUSTOMIZATION: &[u8] = b"shamir";
+const CUSTOMIZATION_EXTENDABLE: &[u8] = b"shamir_extendable";
+const RS1024_GENERATOR: [u32; 10] = [
+    0xE0E040, 0x1C1C080, 0x3838100, 0x7070200, 0xE0E0009,
+    0x1C0C2412, 0x38086C24, 0x3090FC48, 0x21B1F890, 0x3F3F120,
+];
+
+/// Exponent and logarithm tables for GF(256) with the Rijndael polynomial,
+/// using 3 as the generator
+const GF_TABLES: ([u8; 255], [u8; 256]) = {
+    let mut exp = [0u8; 255];
+    let mut log = [0u8; 256];
+    let mut poly: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = poly as u8;
+        log[poly as usize] = i as u8;
+        poly ^= poly << 1;
+        if poly & 0x100 != 0 {
+            poly ^= 0x11B;
+        }
+        i += 1;
+    }
+    (exp, log)
+};
+
+/// One SLIP-39 share, decoded from or encoded to a mnemonic
+#[derive(Clone)]
+pub struct Share {
+    /// Random identifier shared by every share of one secret
+    pub identifier: u16,
+    /// Whether the identifier is left out of the encryption salt
+    pub extendable: bool,
+    pub iteration_exponent: u8,
+    pub group_index: u8,
+    pub group_threshold: u8,
+    pub group_count: u8,
+    pub member_index: u8,
+    pub member_threshold: u8,
+    pub value: Zeroizing<Vec<u8>>,
+}
+
+impl std::fmt::Debug for Share {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Share")
+            .field("identifier", &self.identifier)
+            .field("group_index", &self.group_index)
+            .field("member_index", &self.member_index)
+            .field("member_threshold", &self.member_threshold)
+            .field("value", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl Share {
+    /// Parse a mnemonic, verifying its words, checksum and padding
+    pub fn from_mnemonic(mnemonic: &str) -> Result<Self, WalletError> {
+        let words = mnemonic
+            .split_whitespace()
+            .map(|word| {
+                let word = word.to_lowercase();
+                WORDLIST
+                    .binary_search(&word.as_str())
+                    .map(|index| index as u32)
+                    .map_err(|_| WalletError::validation(format!("Unknown share word: {}", word)))
+            })
+            .collect::<Result<Vec<u32>, WalletError>>()?;
+        if words.len() < MIN_MNEMONIC_WORDS {
+            return Err(WalletError::validation(format!(
+                "Share mnemonic must have at least {} words, got {}",
+                MIN_MNEMONIC_WORDS,
+                words.len()
+            )));
+        }
+
+        let extendable = (words[1] >> 4) & 1 == 1;
+        if !rs1024_verify(customization(extendable), &words) {
+            return Err(WalletError::validation("Invalid share checksum"));
+        }
+
+        let prefix = (words[0] << 10) | words[1];
+        let parameters = (words[2] << 10) | words[3];
+        let nibble = |shift: u32| ((parameters >> shift) & 0xF) as u8;
+        let group_threshold = nibble(12) + 1;
+        let group_count = nibble(8) + 1;
+        if group_threshold > group_count {
+            return Err(WalletError::validation("Share group threshold exceeds group count"));
+        }
+
+        let value_words = &words[METADATA_WORDS..words.len() - CHECKSUM_WORDS];
+        Ok(Self {
+            identifier: (prefix >> 5) as u16,
+            extendable,
+            iteration_exponent: (prefix & 0xF) as u8,
+            group_index: nibble(16),
+            group_threshold,
+            group_count,
+            member_index: nibble(4),
+            member_threshold: nibble(0) + 1,
+            value: words_to_bytes(value_words)?,
+        })
+    }
+
+    /// Encode the share as a mnemonic
+    pub fn to_mnemonic(&self) -> Zeroizing<String> {
+        let prefix = (u32::from(self.identifier) << 5) | (u32::from(self.extendable) << 4) | u32::from(self.iteration_exponent);
+        let parameters = (u32::from(self.group_index) << 16)
+            | (u32::from(self.group_threshold - 1) << 12)
+            | (u32::from(self.group_count - 1) << 8)
+            | (u32::from(self.member_index) << 4)
+            | u32::from(self.member_threshold - 1);
+
+        let mut words = vec![prefix >> 10, prefix & 0x3FF, parameters >> 10, parameters & 0x3FF];
+        words.extend(bytes_to_words(&self.value));
+        let checksum = rs1024_checksum(customization(self.extendable), &words);
+        words.extend(checksum);
+
+        let mnemonic: Vec<&str> = words.iter().map(|&w| WORDLIST[w as usize]).collect();
+        Zeroizing::new(mnemonic.join(" "))
+    }
+}
+
+/// Split `master_secret` into `count` mnemonic shares, any `threshold` of
+/// which restore it. The passphrase is needed again at recovery; a wrong
+/// one yields a different secret rather than an error, by design.
+pub fn split_master_secret(
+    master_secret: &[u8],
+    threshold: u8,
+    count: u8,
+    passphrase: &str,
+    rng: &dyn RandomSource,
+) -> Result<Vec<Zeroizing<String>>, WalletError> {
+    if master_secret.len() < MIN_SECRET_LEN || master_secret.len() % 2 != 0 {
+        return Err(WalletError::validation(format!(
+            "Master secret must be an even number of bytes, at least {}",
+            MIN_SECRET_LEN
+        )));
+    }
+    validate_passphrase(passphrase)?;
+
+    let mut id_bytes = [0u8; 2];
+    rng.fill_bytes(&mut id_bytes)?;
+    let identifier = u16::from_be_bytes(id_bytes) & ((1 << ID_BITS) - 1);
+    let extendable = true;
+
+    let encrypted = encrypt(master_secret, passphrase, ITERATION_EXPONENT, identifier, extendable);
+    let shares = split_secret(threshold, count, &encrypted, rng)?;
+    Ok(shares
+        .into_iter()
+        .map(|(member_index, value)| {
+            Share {
+                identifier,
+                extendable,
+                iteration_exponent: ITERATION_EXPONENT,
+                group_index: 0,
+                group_threshold: 1,
+                group_count: 1,
+                member_index,
+                member_threshold: threshold,
+                value,
+            }
+            .to_mnemonic()
+        })
+        .collect())
+}
+
+/// Restore a master secret from SLIP-39 mnemonics. Shares from several
+/// groups are accepted as long as enough groups are complete.
+pub fn combine_mnemonics<S: AsRef<str>>(mnemonics: &[S], passphrase: &str) -> Result<Zeroizing<Vec<u8>>, WalletError> {
+    validate_passphrase(passphrase)?;
+    let shares = mnemonics
+        .iter()
+        .map(|m| Share::from_mnemonic(m.as_ref()))
+        .collect::<Result<Vec<Share>, WalletError>>()?;
+    let first = shares.first().ok_or_else(|| WalletError::validation("No shares provided"))?;
+
+    let mut groups: BTreeMap<u8, Vec<&Share>> = BTreeMap::new();
+    for share in &shares {
+        if share.identifier != first.identifier
+            || share.extendable != first.extendable
+            || share.iteration_exponent != first.iteration_exponent
+            || share.group_threshold != first.group_threshold
+            || share.group_count != first.group_count
+            || share.value.len() != first.value.len()
+        {
+            return Err(WalletError::validation("Shares do not belong to the same secret"));
+        }
+        let group = groups.entry(share.group_index).or_default();
+        if group.iter().any(|s| s.member_threshold != share.member_threshold) {
+            return Err(WalletError::validation(format!("Shares in group {} disagree on the threshold", share.group_index)));
+        }
+        if group.iter().any(|s| s.member_index == share.member_index) {
+            return Err(WalletError::validation(format!("Share {} of group {} given twice", share.member_index, share.group_index)));
+        }
+        group.push(share);
+    }
+
+    let mut group_shares = Vec::new();
+    for (group_index, members) in &groups {
+        let threshold = members[0].member_threshold;
+        if members.len() < usize::from(threshold) {
+            continue;
+        }
+        let member_shares: Vec<(u8, &[u8])> = members[..usize::from(threshold)]
+            .iter()
+            .map(|s| (s.member_index, s.value.as_slice()))
+            .collect();
+        group_shares.push((*group_index, recover_secret(threshold, &member_shares)?));
+        if group_shares.len() == usize::from(first.group_threshold) {
+            break;
+        }
+    }
+    if group_shares.len() < usize::from(first.group_threshold) {
+        return Err(WalletError::validation(format!(
+            "Insufficient shares: {} of {} required groups are complete",
+            group_shares.len(),
+            first.group_threshold
+        )));
+    }
+
+    let group_shares: Vec<(u8, &[u8])> = group_shares.iter().map(|(i, v)| (*i, v.as_slice())).collect();
+    let encrypted = recover_secret(first.group_threshold, &group_shares)?;
+    Ok(decrypt(&encrypted, passphrase, first.iteration_exponent, first.identifier, first.extendable))
+}
+
+fn customization(extendable: bool) -> &'static [u8] {
+    if extendable { CUSTOMIZATION_EXTENDABLE } else { CUSTOMIZATION }
+}
+
+/// SLIP-39 only allows printable ASCII passphrases
+fn validate_passphrase(passphrase: &str) -> Result<(), WalletError> {
+    if passphrase.bytes().all(|b| (32..=126).contains(&b)) {
+        Ok(())
+    } else {
+        Err(WalletError::validation("Share passphrase must be printable ASCII"))
+    }
+}
+
+fn rs1024_polymod(values: impl Iterator<Item = u32>) -> u32 {
+    let mut chk = 1u32;
+    for value in values {
+        let top = chk >> 20;
+        chk = ((chk & 0xFFFFF) << 10) ^ value;
+        for (i, generator) in RS1024_GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= generator;
+            }
+        }
+    }
+    chk
+}
+
+fn rs1024_checksum(customization: &[u8], words: &[u32]) -> [u32; CHECKSUM_WORDS] {
+    let values = customization.iter().map(|&b| u32::from(b))
+        .chain(words.iter().copied())
+        .chain([0; CHECKSUM_WORDS]);
+    let polymod = rs1024_polymod(values) ^ 1;
+    [(polymod >> 20) & 0x3FF, (polymod >> 10) & 0x3FF, polymod & 0x3FF]
+}
+
+fn rs1024_verify(customization: &[u8], words: &[u32]) -> bool {
+    rs1024_polymod(customization.iter().map(|&b| u32::from(b)).chain(words.iter().copied())) == 1
+}
+
+/// Pack bytes into 10-bit words, left-padding with zero bits
+fn bytes_to_words(bytes: &[u8]) -> Vec<u32> {
+    let mut words = Vec::new();
+    let mut acc = 0u32;
+    let mut bits = (RADIX_BITS - (bytes.len() * 8) % RADIX_BITS) % RADIX_BITS;
+    for &byte in bytes {
+        acc = (acc << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= RADIX_BITS {
+            bits -= RADIX_BITS;
+            words.push((acc >> bits) & 0x3FF);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    words
+}
+
+/// Unpack 10-bit words into bytes; the padding must be at most 8 zero bits
+fn words_to_bytes(words: &[u32]) -> Result<Zeroizing<Vec<u8>>, WalletError> {
+    let padding = (words.len() * RADIX_BITS) % 16;
+    if padding > 8 {
+        return Err(WalletError::validation("Invalid share length"));
+    }
+    let mut bytes = Zeroizing::new(Vec::with_capacity(words.len() * RADIX_BITS / 8));
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut skip = padding;
+    for &word in words {
+        acc = (acc << RADIX_BITS) | word;
+        bits += RADIX_BITS;
+        if skip > 0 {
+            if acc >> (bits - skip) != 0 {
+                return Err(WalletError::validation("Invalid share padding"));
+            }
+            bits -= skip;
+            skip = 0;
+        }
+        while bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    Ok(bytes)
+}
+
+/// Lagrange interpolation over GF(256) of the polynomial through `shares`, evaluated at `x`
+fn interpolate(shares: &[(u8, &[u8])], x: u8) -> Zeroizing<Vec<u8>> {
+    let (exp, log) = &GF_TABLES;
+    if let Some((_, value)) = shares.iter().find(|(index, _)| *index == x) {
+        return Zeroizing::new(value.to_vec());
+    }
+    let len = shares[0].1.len();
+    let log_product: i32 = shares.iter().map(|(index, _)| i32::from(log[usize::from(index ^ x)])).sum();
+
+    let mut result = Zeroizing::new(vec![0u8; len]);
+    for (index, value) in shares {
+        let others: i32 = shares
+            .iter()
+            .filter(|(other, _)| other != index)
+            .map(|(other, _)| i32::from(log[usize::from(other ^ index)]))
+            .sum();
+        let log_basis = (log_product - i32::from(log[usize::from(index ^ x)]) - others).rem_euclid(255);
+        for (out, &byte) in result.iter_mut().zip(value.iter()) {
+            if byte != 0 {
+                *out ^= exp[((i32::from(log[usize::from(byte)]) + log_basis) % 255) as usize];
+            }
+        }
+    }
+    result
+}
+
+fn share_digest(random_part: &[u8], secret: &[u8]) -> [u8; DIGEST_LEN] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(random_part).expect("HMAC accepts any key length");
+    mac.update(secret);
+    let mut digest = [0u8; DIGEST_LEN];
+    digest.copy_from_slice(&mac.finalize().into_bytes()[..DIGEST_LEN]);
+    digest
+}
+
+fn split_secret(
+    threshold: u8,
+    count: u8,
+    secret: &[u8],
+    rng: &dyn RandomSource,
+) -> Result<Vec<(u8, Zeroizing<Vec<u8>>)>, WalletError> {
+    if threshold == 0 || threshold > count || count > MAX_SHARE_COUNT {
+        return Err(WalletError::validation(format!(
+            "Invalid share threshold {} of {}; need 1 <= threshold <= count <= {}",
+            threshold, count, MAX_SHARE_COUNT
+        )));
+    }
+    if threshold == 1 {
+        return Ok((0..count).map(|i| (i, Zeroizing::new(secret.to_vec()))).collect());
+    }
+
+    let mut shares: Vec<(u8, Zeroizing<Vec<u8>>)> = (0..threshold - 2)
+        .map(|i| Ok((i, Zeroizing::new(rng.random_bytes(secret.len())?))))
+        .collect::<Result<_, WalletError>>()?;
+
+    let random_part = Zeroizing::new(rng.random_bytes(secret.len() - DIGEST_LEN)?);
+    let mut digest_share = Zeroizing::new(share_digest(&random_part, secret).to_vec());
+    digest_share.extend_from_slice(&random_part);
+
+    let mut base: Vec<(u8, &[u8])> = shares.iter().map(|(i, v)| (*i, v.as_slice())).collect();
+    base.push((DIGEST_INDEX, digest_share.as_slice()));
+    base.push((SECRET_INDEX, secret));
+    let derived: Vec<_> = (threshold - 2..count).map(|i| (i, interpolate(&base, i))).collect();
+    shares.extend(derived);
+    Ok(shares)
+}
+
+fn recover_secret(threshold: u8, shares: &[(u8, &[u8])]) -> Result<Zeroizing<Vec<u8>>, WalletError> {
+    if threshold == 1 {
+        return Ok(Zeroizing::new(shares[0].1.to_vec()));
+    }
+    let secret = interpolate(shares, SECRET_INDEX);
+    let digest_share = interpolate(shares, DIGEST_INDEX);
+    if share_digest(&digest_share[DIGEST_LEN..], &secret)[..] != digest_share[..DIGEST_LEN] {
+        return Err(WalletError::crypto("Share digest mismatch; the shares are corrupted or do not belong together"));
+    }
+    Ok(secret)
+}
+
+/// One direction of the four-round Feistel cipher; decryption runs the rounds in reverse
+fn feistel(input: &[u8], passphrase: &str, iteration_exponent: u8, identifier: u16, extendable: bool, rounds: &[u8]) -> Zeroizing<Vec<u8>> {
+    let half = input.len() / 2;
+    let mut left = Zeroizing::new(input[..half].to_vec());
+    let mut right = Zeroizing::new(input[half..].to_vec());
+    let mut salt_prefix = Vec::new();
+    if !extendable {
+        salt_prefix.extend_from_slice(CUSTOMIZATION);
+        salt_prefix.extend_from_slice(&identifier.to_be_bytes());
+    }
+    let iterations = (BASE_ITERATION_COUNT << iteration_exponent) / u32::from(ROUND_COUNT);
+
+    for &round in rounds {
+        let mut password = Zeroizing::new(vec![round]);
+        password.extend_from_slice(passphrase.as_bytes());
+        let mut salt = salt_prefix.clone();
+        salt.extend_from_slice(&right);
+        let mut round_key = Zeroizing::new(vec![0u8; half]);
+        pbkdf2::pbkdf2_hmac::<Sha256>(&password, &salt, iterations, &mut round_key);
+
+        for (l, k) in left.iter_mut().zip(round_key.iter()) {
+            *l ^= k;
+        }
+        std::mem::swap(&mut left, &mut right);
+    }
+
+    let mut output = Zeroizing::new(Vec::with_capacity(input.len()));
+    output.extend_from_slice(&right);
+    output.extend_from_slice(&left);
+    output
+}
+
+fn encrypt(master_secret: &[u8], passphrase: &str, iteration_exponent: u8, identifier: u16, extendable: bool) -> Zeroizing<Vec<u8>> {
+    let rounds: Vec<u8> = (0..ROUND_COUNT).collect();
+    feistel(master_secret, passphrase, iteration_exponent, identifier, extendable, &rounds)
+}
+
+fn decrypt(encrypted: &[u8], passphrase: &str, iteration_exponent: u8, identifier: u16, extendable: bool) -> Zeroizing<Vec<u8>> {
+    let rounds: Vec<u8> = (0..ROUND_COUNT).rev().collect();
+    feistel(encrypted, passphrase, iteration_exponent, identifier, extendable, &rounds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::sources::SeededRandom;
+
+    #[test]
+    fn test_slip39_vectors_and_round_trip() {
+        // Vectors from the SLIP-0039 test suite
+        let single = ["duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboard"];
+        assert_eq!(hex::encode(&*combine_mnemonics(&single, "TREZOR").unwrap()), "bb54aac4b89dc868ba37d9cc21b2cece");
+        let two_of_two = [
+            "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+            "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft early superior advocate guest smoking",
+        ];
+        assert_eq!(hex::encode(&*combine_mnemonics(&two_of_two, "TREZOR").unwrap()), "b43ceb7e57a0ea8766221624d01b0864");
+        assert!(combine_mnemonics(&two_of_two[..1], "TREZOR").is_err());
+
+        let seed = [0x5au8; 64];
+        let shares = split_master_secret(&seed, 3, 5, "merchant", &SeededRandom::new(39)).unwrap();
+        assert_eq!(shares.len(), 5);
+        let picked = [&shares[4], &shares[0], &shares[2]];
+        let picked: Vec<&str> = picked.iter().map(|s| s.as_str()).collect();
+        assert_eq!(&*combine_mnemonics(&picked, "merchant").unwrap(), &seed[..]);
+        assert!(combine_mnemonics(&picked[..2], "merchant").is_err());
+
+        // A single mistyped word fails the checksum
+        let mut words: Vec<&str> = picked[1].split(' ').collect();
+        let index = WORDLIST.iter().position(|w| *w == words[6]).unwrap();
+        words[6] = WORDLIST[(index + 1) % WORDLIST.len()];
+        let typo = words.join(" ");
+        assert!(Share::from_mnemonic(&typo).is_err());
+    }
+}
@@ -1,13 +1,18 @@
 //! Core wallet functionality
 //! 
 //! This module contains the core wallet functionality including
-//! wallet management, cryptography, storage, transactions, and BLE.
+//! wallet management, cryptography, storage, transactions, payment
+//! requests, BLE, the startup self-check and the background sync
+//! scheduler.
 
 pub mod wallet;
 pub mod crypto;
 pub mod storage;
 pub mod transactions;
+pub mod payments;
 pub mod ble;
+pub mod self_check;
+pub mod scheduler;
 
 /// Initialize core modules
 pub async fn init() -> Result<(), crate::shared::error::WalletError> {
@@ -0,0 +1,11 @@
+//! Payment request exchange
+//!
+//! This module turns payment requests into QR codes and links a payer's
+//! wallet can scan, and reads them back, including requests that leave the
+//! amount to the payer.
+
+pub mod open_amount;
+pub mod qr;
+
+pub use open_amount::OpenPaymentRequest;
+pub use qr::{decode_open_payment_qr, decode_payment_qr, encode_open_payment_qr, encode_payment_qr, Eip681Payment, PAYMENT_QR_PREFIX, PAYMENT_QR_TAG};
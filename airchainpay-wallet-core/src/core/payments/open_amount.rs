@@ -0,0 +1,191 @@
+//! Open-amount payment requests
+//!
+//! Tips and donations name the payee and token but leave the amount to the
+//! payer, optionally between a minimum and a maximum. The payer's choice is
+//! checked against those bounds by `select_amount` before a transaction is
+//! built, so a wallet cannot sign an amount the payee would not accept.
+//! Requests are signed and carried in QR codes like fixed-amount ones, under
+//! their own canonical tag.
+
+use crate::core::transactions::templates::transfer_builder;
+use crate::core::transactions::TransactionBuilder;
+use crate::shared::canonical::{decode_token, encode_token, CanonicalDecode, CanonicalDecoder, CanonicalEncode, CanonicalEncoder, OPEN_PAYMENT_REQUEST_TAG};
+use crate::shared::error::WalletError;
+use crate::shared::types::{Address, Amount, Network, PaymentRequest, TokenInfo};
+use crate::shared::utils::parse_amount;
+use ethers::types::{H160, U256};
+use serde::{Deserialize, Serialize};
+
+/// A payment request whose amount the payer chooses
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OpenPaymentRequest {
+    pub to_address: Address,
+    pub token: TokenInfo,
+    pub network: Network,
+    pub reference: Option<String>,
+    /// Smallest accepted amount, in the token's smallest unit
+    pub min_amount: Option<Amount>,
+    /// Largest accepted amount, in the token's smallest unit
+    pub max_amount: Option<Amount>,
+}
+
+impl OpenPaymentRequest {
+    pub fn new(to_address: &str, token: TokenInfo, network: Network) -> Result<Self, WalletError> {
+        let address = to_address.trim().parse::<H160>()
+            .map_err(|_| WalletError::validation(format!("Invalid address: {}", to_address)))?;
+        Ok(Self {
+            to_address: format!("{:#x}", address),
+            token,
+            network,
+            reference: None,
+            min_amount: None,
+            max_amount: None,
+        })
+    }
+
+    pub fn with_reference(mut self, reference: impl Into<String>) -> Self {
+        self.reference = Some(reference.into());
+        self
+    }
+
+    /// Accept amounts between `min` and `max` inclusive, in the token's
+    /// smallest unit; `None` leaves that side open
+    pub fn with_bounds(mut self, min: Option<Amount>, max: Option<Amount>) -> Result<Self, WalletError> {
+        if let (Some(min), Some(max)) = (min.as_deref().map(parse_units).transpose()?, max.as_deref().map(parse_units).transpose()?) {
+            if min > max {
+                return Err(WalletError::validation(format!("Minimum {} is above maximum {}", min, max)));
+            }
+        }
+        self.min_amount = min;
+        self.max_amount = max;
+        Ok(self)
+    }
+
+    /// Check the payer's choice, in the token's smallest unit, against the
+    /// request's bounds
+    pub fn validate_amount(&self, amount: &str) -> Result<U256, WalletError> {
+        let value = parse_units(amount)?;
+        if value.is_zero() {
+            return Err(WalletError::validation("Amount must be greater than zero"));
+        }
+        if let Some(min) = self.min_amount.as_deref().map(parse_units).transpose()? {
+            if value < min {
+                return Err(WalletError::validation(format!("Amount {} is below the minimum {}", value, min)));
+            }
+        }
+        if let Some(max) = self.max_amount.as_deref().map(parse_units).transpose()? {
+            if value > max {
+                return Err(WalletError::validation(format!("Amount {} is above the maximum {}", value, max)));
+            }
+        }
+        Ok(value)
+    }
+
+    /// Fix the amount the payer entered, in display units such as `"2.50"`,
+    /// turning the request into an ordinary payment request
+    pub fn select_amount(&self, entered: &str) -> Result<PaymentRequest, WalletError> {
+        let amount = self.validate_amount(&parse_amount(entered.trim(), self.token.decimals)?)?;
+        Ok(PaymentRequest {
+            amount: amount.to_string(),
+            to_address: self.to_address.clone(),
+            token: self.token.clone(),
+            network: self.network.clone(),
+            reference: self.reference.clone(),
+            gas_price: None,
+        })
+    }
+
+    /// A builder paying the selected request; the amount is validated again
+    /// so a request edited after selection cannot slip past the bounds
+    pub fn builder(&self, selected: &PaymentRequest) -> Result<TransactionBuilder, WalletError> {
+        if !selected.to_address.eq_ignore_ascii_case(&self.to_address)
+            || selected.network != self.network
+            || !selected.token.address.eq_ignore_ascii_case(&self.token.address)
+        {
+            return Err(WalletError::validation("Payment does not match the open request"));
+        }
+        let amount = self.validate_amount(&selected.amount)?;
+        transfer_builder(&self.network, &self.to_address, &self.token, amount)
+    }
+}
+
+fn parse_units(amount: &str) -> Result<U256, WalletError> {
+    U256::from_dec_str(amount.trim())
+        .map_err(|_| WalletError::validation(format!("Invalid amount: {}", amount)))
+}
+
+impl CanonicalEncode for OpenPaymentRequest {
+    fn canonical_bytes(&self) -> Result<Vec<u8>, WalletError> {
+        let mut encoder = CanonicalEncoder::payload(OPEN_PAYMENT_REQUEST_TAG, 6);
+        encoder.address(&self.to_address)?;
+        encode_token(&mut encoder, &self.token)?;
+        encoder.uint(self.network.chain_id());
+        match &self.reference {
+            Some(reference) => encoder.text(reference),
+            None => encoder.null(),
+        };
+        for bound in [&self.min_amount, &self.max_amount] {
+            match bound {
+                Some(amount) => encoder.amount(amount)?,
+                None => encoder.null(),
+            };
+        }
+        Ok(encoder.finish())
+    }
+}
+
+impl CanonicalDecode for OpenPaymentRequest {
+    fn from_canonical_bytes(bytes: &[u8]) -> Result<Self, WalletError> {
+        let mut decoder = CanonicalDecoder::payload(bytes, OPEN_PAYMENT_REQUEST_TAG, 6)?;
+        let to_address = decoder.address()?;
+        let token = decode_token(&mut decoder)?;
+        let chain_id = decoder.uint()?;
+        let network = Network::from_chain_id(chain_id)
+            .ok_or_else(|| WalletError::validation(format!("Unsupported network {}", chain_id)))?;
+        let reference = if decoder.null() { None } else { Some(decoder.text()?.to_string()) };
+        let min_amount = if decoder.null() { None } else { Some(decoder.amount()?) };
+        let max_amount = if decoder.null() { None } else { Some(decoder.amount()?) };
+        decoder.finish()?;
+        OpenPaymentRequest { to_address, token, network, reference, min_amount: None, max_amount: None }
+            .with_bounds(min_amount, max_amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payer_amount_is_checked_against_bounds() {
+        let usdc = TokenInfo {
+            symbol: "USDC".to_string(),
+            name: "USD Coin".to_string(),
+            decimals: 6,
+            address: "0x036cbd53842c5426634e7929541ec2318f3dcf7e".to_string(),
+            chain_id: "84532".to_string(),
+            is_native: false,
+            is_stablecoin: true,
+        };
+        let request = OpenPaymentRequest::new("0x2c7536e3605d9c16a7a3d7b1898e529396a65c23", usdc, Network::BaseSepolia)
+            .unwrap()
+            .with_reference("tip-jar")
+            .with_bounds(Some("1000000".to_string()), Some("50000000".to_string()))
+            .unwrap();
+
+        let decoded = OpenPaymentRequest::from_canonical_bytes(&request.canonical_bytes().unwrap()).unwrap();
+        assert_eq!(decoded, request);
+
+        assert!(request.select_amount("0.50").is_err());
+        assert!(request.select_amount("50.01").is_err());
+        let selected = request.select_amount("2.5").unwrap();
+        assert_eq!(selected.amount, "2500000");
+        assert_eq!(selected.reference.as_deref(), Some("tip-jar"));
+        assert!(request.builder(&selected).is_ok());
+
+        let mut edited = selected.clone();
+        edited.amount = "90000000".to_string();
+        assert!(request.builder(&edited).is_err());
+
+        assert!(request.clone().with_bounds(Some("5".to_string()), Some("4".to_string())).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+//! QR payment requests
+//!
+//! Two formats:
+//!
+//! - EIP-681 `ethereum:` URIs, which any Ethereum wallet can scan. They
+//!   carry only the recipient, chain, amount and token, and are unsigned.
+//! - AirChainPay's compact payload: `ACP1:` followed by base45 (QR
+//!   alphanumeric mode) over a canonical CBOR array of the payment
+//!   request's canonical bytes and the payee's EIP-191 signature over them.
+//!   Changing the amount, recipient, token or reference breaks the
+//!   signature. Open-amount requests travel in the same envelope; the tag
+//!   of the signed payload tells the two apart.
+
+use super::open_amount::OpenPaymentRequest;
+use crate::core::crypto::signatures::SignatureManager;
+use crate::core::storage::keystore::address_of;
+use crate::shared::canonical::{CanonicalDecode, CanonicalDecoder, CanonicalEncode, CanonicalEncoder};
+use crate::shared::error::WalletError;
+use crate::shared::types::PaymentRequest;
+use ethers::types::U256;
+
+/// Prefix of the compact payload, inside the base45 alphabet
+pub const PAYMENT_QR_PREFIX: &str = "ACP1:";
+/// Tag of the canonical array inside the compact payload
+pub const PAYMENT_QR_TAG: &str = "airchainpay/payment-qr/1";
+
+const EIP681_SCHEME: &str = "ethereum:";
+
+/// Encode `request` as a compact QR payload signed with the payee's key.
+/// The key must be the one for `request.to_address`.
+pub fn encode_payment_qr(request: &PaymentRequest, key_bytes: &[u8]) -> Result<String, WalletError> {
+    seal(&request.canonical_bytes()?, &request.to_address, key_bytes)
+}
+
+/// Decode a compact QR payload, checking that the payee signed it
+pub fn decode_payment_qr(data: &str) -> Result<PaymentRequest, WalletError> {
+    let (payload, signature) = unseal(data)?;
+    let request = PaymentRequest::from_canonical_bytes(&payload)?;
+    verify_payee(&payload, &signature, &request.to_address)?;
+    Ok(request)
+}
+
+/// Encode an open-amount request as a compact QR payload signed with the
+/// payee's key
+pub fn encode_open_payment_qr(request: &OpenPaymentRequest, key_bytes: &[u8]) -> Result<String, WalletError> {
+    seal(&request.canonical_bytes()?, &request.to_address, key_bytes)
+}
+
+/// Decode a compact QR payload carrying an open-amount request, checking
+/// that the payee signed it
+pub fn decode_open_payment_qr(data: &str) -> Result<OpenPaymentRequest, WalletError> {
+    let (payload, signature) = unseal(data)?;
+    let request = OpenPaymentRequest::from_canonical_bytes(&payload)?;
+    verify_payee(&payload, &signature, &request.to_address)?;
+    Ok(request)
+}
+
+fn seal(payload: &[u8], payee: &str, key_bytes: &[u8]) -> Result<String, WalletError> {
+    let signer = address_of(key_bytes)?;
+    if !signer.eq_ignore_ascii_case(payee.trim_start_matches("0x")) {
+        return Err(WalletError::validation(format!(
+            "Payment requests must be signed by the payee {}, not 0x{}", payee, signer
+        )));
+    }
+
+    let signature = SignatureManager::new().sign_personal_message(payload, key_bytes)?;
+    let signature = hex::decode(signature).map_err(|e| WalletError::internal(e.to_string()))?;
+
+    let mut encoder = CanonicalEncoder::payload(PAYMENT_QR_TAG, 2);
+    encoder.bytes(payload).bytes(&signature);
+    Ok(format!("{}{}", PAYMENT_QR_PREFIX, base45::encode(encoder.finish())))
+}
+
+/// The signed payload and signature inside a compact QR payload
+fn unseal(data: &str) -> Result<(Vec<u8>, Vec<u8>), WalletError> {
+    let encoded = data.trim().strip_prefix(PAYMENT_QR_PREFIX)
+        .ok_or_else(|| WalletError::validation("Not an AirChainPay payment QR"))?;
+    let bytes = base45::decode(encoded)
+        .map_err(|e| WalletError::validation(format!("Invalid base45 payload: {:?}", e)))?;
+
+    let mut decoder = CanonicalDecoder::payload(&bytes, PAYMENT_QR_TAG, 2)?;
+    let payload = decoder.bytes()?.to_vec();
+    let signature = decoder.bytes()?.to_vec();
+    decoder.finish()?;
+    Ok((payload, signature))
+}
+
+fn verify_payee(payload: &[u8], signature: &[u8], payee: &str) -> Result<(), WalletError> {
+    if !SignatureManager::new().verify_personal_message(payload, &hex::encode(signature), payee)? {
+        return Err(WalletError::validation("Payment request was not signed by its payee"));
+    }
+    Ok(())
+}
+
+/// An EIP-681 payment: native value to an address, or an ERC-20 `transfer`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Eip681Payment {
+    pub chain_id: u64,
+    pub to_address: String,
+    /// Smallest-unit amount, as a decimal string
+    pub amount: String,
+    /// ERC-20 contract; `None` for the native currency
+    pub token_address: Option<String>,
+}
+
+impl Eip681Payment {
+    pub fn from_request(request: &PaymentRequest) -> Self {
+        let token_address = if request.token.is_native || request.token.address.is_empty() {
+            None
+        } else {
+            Some(request.token.address.clone())
+        };
+        Self {
+            chain_id: request.network.chain_id(),
+            to_address: request.to_address.clone(),
+            amount: request.amount.clone(),
+            token_address,
+        }
+    }
+
+    pub fn to_uri(&self) -> String {
+        match &self.token_address {
+            Some(token) => format!(
+                "{}{}@{}/transfer?address={}&uint256={}",
+                EIP681_SCHEME, token, self.chain_id, self.to_address, self.amount
+            ),
+            None => format!("{}{}@{}?value={}", EIP681_SCHEME, self.to_address, self.chain_id, self.amount),
+        }
+    }
+
+    /// Parse an EIP-681 URI. Only hex addresses are accepted; ENS names
+    /// need a resolver this crate does not have.
+    pub fn parse(uri: &str) -> Result<Self, WalletError> {
+        let rest = uri.trim().strip_prefix(EIP681_SCHEME)
+            .ok_or_else(|| WalletError::validation("Not an ethereum: URI"))?;
+        let rest = rest.strip_prefix("pay-").unwrap_or(rest);
+
+        let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (path, function) = match path.split_once('/') {
+            Some((path, function)) => (path, Some(function)),
+            None => (path, None),
+        };
+        let (target, chain_id) = match path.split_once('@') {
+            Some((target, chain_id)) => (
+                target,
+                chain_id.parse::<u64>().map_err(|_| WalletError::validation(format!("Invalid chain id {}", chain_id)))?,
+            ),
+            // EIP-681 defaults to mainnet
+            None => (path, 1),
+        };
+        let target = parse_address(target)?;
+
+        let param = |name: &str| {
+            query.split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value)
+        };
+
+        match function {
+            None => Ok(Self {
+                chain_id,
+                to_address: target,
+                amount: param("value").map(parse_number).transpose()?.unwrap_or_else(|| "0".to_string()),
+                token_address: None,
+            }),
+            Some("transfer") => Ok(Self {
+                chain_id,
+                to_address: parse_address(param("address").ok_or_else(|| WalletError::validation("Token transfer URI has no address"))?)?,
+                amount: parse_number(param("uint256").ok_or_else(|| WalletError::validation("Token transfer URI has no uint256 amount"))?)?,
+                token_address: Some(target),
+            }),
+            Some(other) => Err(WalletError::validation(format!("Unsupported EIP-681 function {}", other))),
+        }
+    }
+}
+
+fn parse_address(value: &str) -> Result<String, WalletError> {
+    let hex_part = value.strip_prefix("0x")
+        .filter(|h| h.len() == 40 && h.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or_else(|| WalletError::validation(format!("Invalid address {}", value)))?;
+    Ok(format!("0x{}", hex_part))
+}
+
+/// EIP-681 numbers: integers with optional fraction and exponent, such as
+/// `2.014e18`, which must come out whole
+fn parse_number(value: &str) -> Result<String, WalletError> {
+    let invalid = || WalletError::validation(format!("Invalid amount {}", value));
+    let (mantissa, exponent) = match value.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<u32>().map_err(|_| invalid())?),
+        None => (value, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if whole.is_empty() || !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let fraction = fraction.trim_end_matches('0');
+    let shift = exponent.checked_sub(fraction.len() as u32).ok_or_else(invalid)?;
+    let digits = format!("{}{}{}", whole, fraction, "0".repeat(shift as usize));
+    U256::from_dec_str(&digits).map(|amount| amount.to_string()).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::types::{Network, TokenInfo};
+
+    #[test]
+    fn test_payment_qr_round_trip_and_eip681() {
+        let key = hex::decode("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318").unwrap();
+        let request = PaymentRequest {
+            amount: "1500000".to_string(),
+            to_address: "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23".to_string(),
+            token: TokenInfo {
+                symbol: "USDC".to_string(),
+                name: "USD Coin".to_string(),
+                decimals: 6,
+                address: "0x036cbd53842c5426634e7929541ec2318f3dcf7e".to_string(),
+                chain_id: "84532".to_string(),
+                is_native: false,
+                is_stablecoin: true,
+            },
+            network: Network::BaseSepolia,
+            reference: Some("order-42".to_string()),
+            gas_price: None,
+        };
+
+        let qr = encode_payment_qr(&request, &key).unwrap();
+        assert!(qr.starts_with(PAYMENT_QR_PREFIX));
+        let decoded = decode_payment_qr(&qr).unwrap();
+        assert_eq!(decoded.canonical_bytes().unwrap(), request.canonical_bytes().unwrap());
+
+        // A request for someone else's address cannot be signed with this key
+        let mut other = request.clone();
+        other.to_address = "0x742d35cc6634c0532925a3b8d4c9db96c4b4d8b6".to_string();
+        assert!(encode_payment_qr(&other, &key).is_err());
+
+        // Swapping in a different request under the same signature fails
+        let bytes = base45::decode(&qr[PAYMENT_QR_PREFIX.len()..]).unwrap();
+        let mut decoder = CanonicalDecoder::payload(&bytes, PAYMENT_QR_TAG, 2).unwrap();
+        let _ = decoder.bytes().unwrap();
+        let signature = decoder.bytes().unwrap();
+        let mut tampered_request = request.clone();
+        tampered_request.amount = "150000000".to_string();
+        let mut encoder = CanonicalEncoder::payload(PAYMENT_QR_TAG, 2);
+        encoder.bytes(&tampered_request.canonical_bytes().unwrap()).bytes(signature);
+        let tampered = format!("{}{}", PAYMENT_QR_PREFIX, base45::encode(encoder.finish()));
+        assert!(decode_payment_qr(&tampered).is_err());
+
+        let uri = Eip681Payment::from_request(&request).to_uri();
+        assert_eq!(
+            uri,
+            "ethereum:0x036cbd53842c5426634e7929541ec2318f3dcf7e@84532/transfer?address=0x2c7536e3605d9c16a7a3d7b1898e529396a65c23&uint256=1500000"
+        );
+        assert_eq!(Eip681Payment::parse(&uri).unwrap(), Eip681Payment::from_request(&request));
+
+        let native = Eip681Payment::parse("ethereum:0x2c7536e3605d9c16a7a3d7b1898e529396a65c23@1114?value=2.014e18").unwrap();
+        assert_eq!(native.amount, "2014000000000000000");
+        assert_eq!(native.token_address, None);
+        assert!(Eip681Payment::parse("ethereum:0x2c7536e3605d9c16a7a3d7b1898e529396a65c23?value=1.5").is_err());
+    }
+}
@@ -0,0 +1,423 @@
+//! Background sync scheduler
+//!
+//! Coordinates the periodic work a wallet does while the app is open:
+//! balance refresh, history sync, receipt polling and flushing the offline
+//! queue. Mobile hosts cannot keep a thread alive in the background, so the
+//! scheduler does not own one; the host calls `run_due` from its own timer
+//! (`next_due` says when) and `resume` when the app returns to the
+//! foreground, which makes foreground tasks due at once.
+//!
+//! Each run is rescheduled after its interval with random jitter, so many
+//! devices don't hit the relay in lockstep. Failures back off exponentially
+//! up to a cap. On low battery intervals are stretched, and on critical
+//! battery only essential tasks, such as delivering queued payments, run.
+
+use crate::core::transactions::{BalanceService, ConfirmationSource, OfflineQueue, TransactionHistory};
+use crate::core::wallet::WalletManager;
+use crate::shared::error::WalletError;
+use crate::shared::sources::{default_clock, default_random_source, Clock, RandomSource};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Battery level at or below which intervals are stretched
+pub const LOW_BATTERY_PERCENT: u8 = 20;
+/// Battery level at or below which only essential tasks run
+pub const CRITICAL_BATTERY_PERCENT: u8 = 5;
+/// Most times a failing task's interval is doubled
+const MAX_BACKOFF_EXPONENT: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncTaskKind {
+    BalanceRefresh,
+    HistorySync,
+    ReceiptPolling,
+    QueueFlush,
+}
+
+/// How often a task runs and how it reacts to failures and battery
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskPolicy {
+    pub interval_secs: u64,
+    /// Fraction of the interval added or removed at random, 0.0 to 0.5
+    pub jitter: f64,
+    /// Cap on the backoff after repeated failures
+    pub max_backoff_secs: u64,
+    /// Interval multiplier while the battery is low or in low-power mode
+    pub low_battery_multiplier: u32,
+    /// Keep running on critical battery
+    pub essential: bool,
+    /// Run as soon as the app returns to the foreground
+    pub run_on_resume: bool,
+}
+
+impl TaskPolicy {
+    /// Defaults for each kind of task
+    pub fn for_kind(kind: SyncTaskKind) -> Self {
+        match kind {
+            SyncTaskKind::BalanceRefresh => Self {
+                interval_secs: 60,
+                jitter: 0.2,
+                max_backoff_secs: 900,
+                low_battery_multiplier: 4,
+                essential: false,
+                run_on_resume: true,
+            },
+            SyncTaskKind::HistorySync => Self {
+                interval_secs: 120,
+                jitter: 0.2,
+                max_backoff_secs: 1800,
+                low_battery_multiplier: 4,
+                essential: false,
+                run_on_resume: true,
+            },
+            SyncTaskKind::ReceiptPolling => Self {
+                interval_secs: 15,
+                jitter: 0.1,
+                max_backoff_secs: 300,
+                low_battery_multiplier: 2,
+                essential: false,
+                run_on_resume: true,
+            },
+            SyncTaskKind::QueueFlush => Self {
+                interval_secs: 30,
+                jitter: 0.1,
+                max_backoff_secs: 600,
+                low_battery_multiplier: 2,
+                essential: true,
+                run_on_resume: true,
+            },
+        }
+    }
+
+    pub fn with_interval(mut self, secs: u64) -> Self {
+        self.interval_secs = secs.max(1);
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 0.5);
+        self
+    }
+}
+
+/// What the host reports about power
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PowerState {
+    /// `None` when the platform does not report a level
+    pub battery_percent: Option<u8>,
+    pub charging: bool,
+    pub low_power_mode: bool,
+}
+
+impl PowerState {
+    fn on_battery_at_or_below(&self, percent: u8) -> bool {
+        !self.charging && self.battery_percent.is_some_and(|level| level <= percent)
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.low_power_mode || self.on_battery_at_or_below(LOW_BATTERY_PERCENT)
+    }
+
+    pub fn is_critical(&self) -> bool {
+        self.on_battery_at_or_below(CRITICAL_BATTERY_PERCENT)
+    }
+}
+
+/// One piece of periodic work
+#[async_trait]
+pub trait SyncTask: Send + Sync {
+    async fn run(&self) -> Result<(), WalletError>;
+}
+
+#[async_trait]
+impl<T: SyncTask + ?Sized> SyncTask for Arc<T> {
+    async fn run(&self) -> Result<(), WalletError> {
+        self.as_ref().run().await
+    }
+}
+
+/// Refreshes the balances of every wallet the manager holds
+pub struct BalanceRefreshTask {
+    wallets: WalletManager,
+    balances: Arc<BalanceService>,
+}
+
+impl BalanceRefreshTask {
+    pub fn new(wallets: WalletManager, balances: Arc<BalanceService>) -> Self {
+        Self { wallets, balances }
+    }
+}
+
+#[async_trait]
+impl SyncTask for BalanceRefreshTask {
+    async fn run(&self) -> Result<(), WalletError> {
+        for wallet in self.wallets.list_wallets().await {
+            self.wallets.get_token_balances(&wallet.id, &self.balances).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Syncs history confirmations from a source: RPC receipts for history
+/// sync, the relay for receipt polling
+pub struct HistorySyncTask<'a> {
+    history: &'a TransactionHistory<'a>,
+    source: &'a dyn ConfirmationSource,
+}
+
+impl<'a> HistorySyncTask<'a> {
+    pub fn new(history: &'a TransactionHistory<'a>, source: &'a dyn ConfirmationSource) -> Self {
+        Self { history, source }
+    }
+}
+
+#[async_trait]
+impl SyncTask for HistorySyncTask<'_> {
+    async fn run(&self) -> Result<(), WalletError> {
+        let report = self.history.sync(self.source).await?;
+        match report.errors.first() {
+            // Nothing was learned; back off instead of hammering the source
+            Some(error) if report.updated == 0 && report.pending > 0 => Err(WalletError::network(error.clone())),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl SyncTask for OfflineQueue<'_> {
+    async fn run(&self) -> Result<(), WalletError> {
+        if self.flush_if_online().await?.is_none() {
+            return Err(WalletError::network("Relay is not reachable"));
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of one task run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRun {
+    pub kind: SyncTaskKind,
+    pub error: Option<String>,
+    pub consecutive_failures: u32,
+    pub next_run: DateTime<Utc>,
+}
+
+struct ScheduledTask<'a> {
+    kind: SyncTaskKind,
+    policy: TaskPolicy,
+    task: Box<dyn SyncTask + 'a>,
+    next_run: DateTime<Utc>,
+    failures: u32,
+}
+
+/// Runs registered tasks when they fall due. Driven by the host; see the
+/// module docs.
+pub struct SyncScheduler<'a> {
+    tasks: Vec<ScheduledTask<'a>>,
+    power: PowerState,
+    paused: bool,
+    clock: Arc<dyn Clock>,
+    rng: Arc<dyn RandomSource>,
+}
+
+impl<'a> SyncScheduler<'a> {
+    pub fn new() -> Self {
+        Self {
+            tasks: Vec::new(),
+            power: PowerState::default(),
+            paused: false,
+            clock: default_clock(),
+            rng: default_random_source(),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Draw jitter from `rng` instead of the OS
+    pub fn with_random_source(mut self, rng: Arc<dyn RandomSource>) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// Register `task` under `kind`, replacing an earlier task of that
+    /// kind. It first runs on the next `run_due`.
+    pub fn add_task(&mut self, kind: SyncTaskKind, policy: TaskPolicy, task: Box<dyn SyncTask + 'a>) {
+        self.tasks.retain(|t| t.kind != kind);
+        self.tasks.push(ScheduledTask { kind, policy, task, next_run: self.clock.now(), failures: 0 });
+    }
+
+    pub fn remove_task(&mut self, kind: SyncTaskKind) {
+        self.tasks.retain(|t| t.kind != kind);
+    }
+
+    pub fn set_power_state(&mut self, power: PowerState) {
+        self.power = power;
+    }
+
+    /// Stop running tasks, e.g. when the app goes to the background
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Start running again after `pause`; tasks marked `run_on_resume`
+    /// become due now, keeping any failure backoff
+    pub fn resume(&mut self) {
+        self.paused = false;
+        let now = self.clock.now();
+        for task in self.tasks.iter_mut().filter(|t| t.policy.run_on_resume && t.failures == 0) {
+            task.next_run = task.next_run.min(now);
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    fn runnable(&self, task: &ScheduledTask<'a>) -> bool {
+        task.policy.essential || !self.power.is_critical()
+    }
+
+    /// When the next task falls due, for the host's timer; None while
+    /// paused or with nothing runnable
+    pub fn next_due(&self) -> Option<DateTime<Utc>> {
+        if self.paused {
+            return None;
+        }
+        self.tasks.iter().filter(|t| self.runnable(t)).map(|t| t.next_run).min()
+    }
+
+    /// Run every task that is due, one at a time, and reschedule it
+    pub async fn run_due(&mut self) -> Vec<TaskRun> {
+        if self.paused {
+            return Vec::new();
+        }
+        let now = self.clock.now();
+        let due: Vec<usize> = (0..self.tasks.len())
+            .filter(|&i| self.tasks[i].next_run <= now && self.runnable(&self.tasks[i]))
+            .collect();
+
+        let mut runs = Vec::with_capacity(due.len());
+        for i in due {
+            let result = self.tasks[i].task.run().await;
+            let delay = self.delay(&self.tasks[i], result.is_err());
+            let finished = self.clock.now();
+            let task = &mut self.tasks[i];
+            match &result {
+                Ok(()) => task.failures = 0,
+                Err(e) => {
+                    task.failures = task.failures.saturating_add(1);
+                    log::warn!("Sync task {:?} failed ({} in a row): {}", task.kind, task.failures, e);
+                }
+            }
+            task.next_run = finished + delay;
+            runs.push(TaskRun {
+                kind: task.kind,
+                error: result.err().map(|e| e.to_string()),
+                consecutive_failures: task.failures,
+                next_run: task.next_run,
+            });
+        }
+        runs
+    }
+
+    /// Time until a task's next run: its interval, stretched on low battery
+    /// and doubled per failure up to the cap, with jitter
+    fn delay(&self, task: &ScheduledTask<'a>, failed: bool) -> Duration {
+        let mut secs = task.policy.interval_secs.max(1);
+        if self.power.is_low() {
+            secs = secs.saturating_mul(task.policy.low_battery_multiplier.max(1) as u64);
+        }
+        if failed {
+            let exponent = (task.failures + 1).min(MAX_BACKOFF_EXPONENT);
+            secs = secs.saturating_mul(1 << exponent).min(task.policy.max_backoff_secs.max(secs));
+        }
+        let jitter = task.policy.jitter.clamp(0.0, 0.5);
+        let factor = match self.rng.random_bytes(4) {
+            Ok(bytes) => {
+                let unit = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64 / u32::MAX as f64;
+                1.0 + jitter * (2.0 * unit - 1.0)
+            }
+            Err(_) => 1.0,
+        };
+        Duration::milliseconds((secs as f64 * 1000.0 * factor) as i64)
+    }
+}
+
+impl Default for SyncScheduler<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::sources::{FixedClock, SeededRandom};
+    use chrono::TimeZone;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Counts runs and fails while `failing` is set
+    struct Counter {
+        runs: AtomicU32,
+        failing: bool,
+    }
+
+    #[async_trait]
+    impl SyncTask for Counter {
+        async fn run(&self) -> Result<(), WalletError> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            if self.failing {
+                return Err(WalletError::network("relay down"));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_jitters_backs_off_and_respects_battery() {
+        let clock = Arc::new(FixedClock::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap()));
+        let balances = Arc::new(Counter { runs: AtomicU32::new(0), failing: false });
+        let queue = Arc::new(Counter { runs: AtomicU32::new(0), failing: true });
+        let mut scheduler = SyncScheduler::new()
+            .with_clock(clock.clone())
+            .with_random_source(Arc::new(SeededRandom::new(7)));
+        scheduler.add_task(SyncTaskKind::BalanceRefresh, TaskPolicy::for_kind(SyncTaskKind::BalanceRefresh), Box::new(balances.clone()));
+        scheduler.add_task(SyncTaskKind::QueueFlush, TaskPolicy::for_kind(SyncTaskKind::QueueFlush), Box::new(queue.clone()));
+
+        let runs = scheduler.run_due().await;
+        assert_eq!(runs.len(), 2);
+        let start = clock.now();
+        // 60s ± 20%
+        let balance_delay = runs[0].next_run - start;
+        assert!(balance_delay >= Duration::seconds(48) && balance_delay <= Duration::seconds(72));
+        // First failure of a 30s task: doubled, ± 10%
+        let queue_delay = runs[1].next_run - start;
+        assert!(queue_delay >= Duration::seconds(54) && queue_delay <= Duration::seconds(66));
+        assert_eq!(runs[1].consecutive_failures, 1);
+
+        // Nothing is due yet
+        assert!(scheduler.run_due().await.is_empty());
+
+        // Critical battery: only the essential queue flush runs
+        scheduler.set_power_state(PowerState { battery_percent: Some(4), charging: false, low_power_mode: false });
+        clock.advance(Duration::seconds(600));
+        let runs = scheduler.run_due().await;
+        assert_eq!(runs.iter().map(|r| r.kind).collect::<Vec<_>>(), vec![SyncTaskKind::QueueFlush]);
+        assert_eq!(balances.runs.load(Ordering::SeqCst), 1);
+
+        // Paused in the background, resumed in the foreground on a charger
+        scheduler.pause();
+        assert!(scheduler.next_due().is_none());
+        scheduler.set_power_state(PowerState { battery_percent: Some(4), charging: true, low_power_mode: false });
+        scheduler.resume();
+        let runs = scheduler.run_due().await;
+        assert!(runs.iter().any(|r| r.kind == SyncTaskKind::BalanceRefresh));
+        assert_eq!(balances.runs.load(Ordering::SeqCst), 2);
+    }
+}
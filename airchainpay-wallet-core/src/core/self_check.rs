@@ -0,0 +1,301 @@
+//! Integrity self-check
+//!
+//! Run once at startup, before the host trusts the core with keys. Crypto
+//! primitives are checked against known-answer vectors, storage with an
+//! encrypted write/read/delete, and the chain registry for entries that
+//! disagree. The structured report lets the host refuse to sign, or warn
+//! the user, instead of finding out from a failed payment.
+
+use crate::core::crypto::encryption::{EncryptionAlgorithm, EncryptionManager};
+use crate::core::crypto::hashing::HashManager;
+use crate::core::crypto::signatures::SignatureManager;
+use crate::core::storage::keystore::address_of;
+use crate::core::storage::SecureStorage;
+use crate::infrastructure::platform::PlatformStorage;
+use crate::shared::constants::{NetworkConfig, BASE_SEPOLIA_CONFIG, CORE_TESTNET_CONFIG, HOLESKY_CONFIG, LISK_SEPOLIA_CONFIG};
+use crate::shared::error::WalletError;
+use crate::shared::types::Network;
+use crate::shared::utils::validate_ethereum_address;
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Instant;
+
+/// Key written and removed by the storage round-trip
+const SELF_CHECK_STORAGE_KEY: &str = "airchainpay_self_check";
+
+/// FIPS 180-2: SHA-256("abc")
+const SHA256_ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+/// Keccak-256 of the empty string, as used by Ethereum
+const KECCAK256_EMPTY: &str = "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+/// GCM specification test case 14: zero key, zero IV, one zero block
+const AES256_GCM_CIPHERTEXT: &str = "cea7403d4d606b6e074ec5d3baf39d18d0d1c8a799996bf0265b98b5d48ab919";
+/// Address of the secp256k1 private key 1
+const KEY_ONE_ADDRESS: &str = "7e5f4552091a69125d5dfcb7b8c2659029395bdf";
+
+const ALL_NETWORKS: [Network; 4] = [Network::CoreTestnet, Network::BaseSepolia, Network::LiskSepolia, Network::EthereumHolesky];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfCheckArea {
+    Crypto,
+    Storage,
+    ChainRegistry,
+}
+
+/// Outcome of one check; `detail` says what went wrong
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfCheckResult {
+    pub area: SelfCheckArea,
+    pub name: String,
+    pub passed: bool,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfCheckReport {
+    pub passed: bool,
+    pub checks: Vec<SelfCheckResult>,
+    pub duration_ms: u64,
+}
+
+impl SelfCheckReport {
+    pub fn failures(&self) -> impl Iterator<Item = &SelfCheckResult> {
+        self.checks.iter().filter(|check| !check.passed)
+    }
+
+    /// Whether every check in `area` passed
+    pub fn area_passed(&self, area: SelfCheckArea) -> bool {
+        self.checks.iter().filter(|check| check.area == area).all(|check| check.passed)
+    }
+}
+
+struct ReportBuilder {
+    checks: Vec<SelfCheckResult>,
+}
+
+impl ReportBuilder {
+    fn record(&mut self, area: SelfCheckArea, name: &str, result: Result<(), WalletError>) {
+        if let Err(e) = &result {
+            log::error!("Self-check {} failed: {}", name, e);
+        }
+        self.checks.push(SelfCheckResult {
+            area,
+            name: name.to_string(),
+            passed: result.is_ok(),
+            detail: result.err().map(|e| e.to_string()),
+        });
+    }
+}
+
+/// Run every check. `storage` is the platform storage the wallet will use;
+/// one throwaway entry is written to it and removed again.
+pub async fn run_self_check(storage: &dyn PlatformStorage) -> SelfCheckReport {
+    let started = Instant::now();
+    let mut report = ReportBuilder { checks: Vec::new() };
+
+    report.record(SelfCheckArea::Crypto, "sha256", check_sha256());
+    report.record(SelfCheckArea::Crypto, "keccak256", check_keccak256());
+    report.record(SelfCheckArea::Crypto, "aes256_gcm", check_aes256_gcm());
+    report.record(SelfCheckArea::Crypto, "chacha20_poly1305", check_round_trip(EncryptionAlgorithm::ChaCha20Poly1305));
+    report.record(SelfCheckArea::Crypto, "secp256k1", check_secp256k1());
+
+    report.record(SelfCheckArea::Storage, "encrypted_round_trip", check_storage(storage).await);
+
+    report.record(SelfCheckArea::ChainRegistry, "unique_chain_ids", check_unique_chain_ids());
+    for network in ALL_NETWORKS {
+        let name = format!("network_{}", network.chain_id());
+        report.record(SelfCheckArea::ChainRegistry, &name, check_network(&network));
+    }
+
+    let checks = report.checks;
+    SelfCheckReport {
+        passed: checks.iter().all(|check| check.passed),
+        checks,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+fn expect_hex(actual: &[u8], expected: &str) -> Result<(), WalletError> {
+    let actual = hex::encode(actual);
+    if actual != expected {
+        return Err(WalletError::crypto(format!("expected {}, got {}", expected, actual)));
+    }
+    Ok(())
+}
+
+fn check_sha256() -> Result<(), WalletError> {
+    expect_hex(&HashManager::new().sha256(b"abc")?, SHA256_ABC)
+}
+
+fn check_keccak256() -> Result<(), WalletError> {
+    expect_hex(&HashManager::new().keccak256(b"")?, KECCAK256_EMPTY)
+}
+
+fn check_aes256_gcm() -> Result<(), WalletError> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&[0u8; 32]));
+    let ciphertext = cipher.encrypt(Nonce::from_slice(&[0u8; 12]), [0u8; 16].as_slice())
+        .map_err(|e| WalletError::crypto(format!("AES-GCM encryption failed: {}", e)))?;
+    expect_hex(&ciphertext, AES256_GCM_CIPHERTEXT)?;
+    // The storage path draws its own nonces, so check it separately
+    check_round_trip(EncryptionAlgorithm::AES256GCM)
+}
+
+/// Encrypt and decrypt through `EncryptionManager`, and check that a
+/// flipped ciphertext bit is rejected
+fn check_round_trip(algorithm: EncryptionAlgorithm) -> Result<(), WalletError> {
+    let manager = EncryptionManager::new(algorithm);
+    let key = manager.generate_key()?;
+    let plaintext = b"airchainpay self-check";
+    let mut encrypted = manager.encrypt(plaintext, &key)?;
+    if manager.decrypt(&encrypted, &key)? != plaintext {
+        return Err(WalletError::crypto("decrypted data does not match"));
+    }
+    encrypted.ciphertext[0] ^= 1;
+    if manager.decrypt(&encrypted, &key).is_ok() {
+        return Err(WalletError::crypto("tampered ciphertext was accepted"));
+    }
+    Ok(())
+}
+
+fn check_secp256k1() -> Result<(), WalletError> {
+    let mut key_one = [0u8; 32];
+    key_one[31] = 1;
+    let address = address_of(&key_one)?;
+    if address != KEY_ONE_ADDRESS {
+        return Err(WalletError::crypto(format!("expected address {}, got {}", KEY_ONE_ADDRESS, address)));
+    }
+    let signatures = SignatureManager::new();
+    let signature = signatures.sign_personal_message(b"self-check", &key_one)?;
+    if !signatures.verify_personal_message(b"self-check", &signature, &address)? {
+        return Err(WalletError::crypto("signature does not verify"));
+    }
+    if signatures.verify_personal_message(b"self-chech", &signature, &address)? {
+        return Err(WalletError::crypto("signature verifies for a different message"));
+    }
+    Ok(())
+}
+
+async fn check_storage(storage: &dyn PlatformStorage) -> Result<(), WalletError> {
+    let secure_storage = SecureStorage::new(storage);
+    let password = hex::encode(EncryptionManager::new_default().generate_key()?);
+    let plaintext = b"airchainpay storage self-check";
+
+    secure_storage.store_data(SELF_CHECK_STORAGE_KEY, plaintext, &password).await?;
+    let result = async {
+        let stored = storage.retrieve(SELF_CHECK_STORAGE_KEY)?;
+        if stored.windows(plaintext.len()).any(|window| window == plaintext) {
+            return Err(WalletError::storage("data was stored unencrypted"));
+        }
+        if secure_storage.retrieve_data(SELF_CHECK_STORAGE_KEY, &password).await? != plaintext {
+            return Err(WalletError::storage("data read back does not match"));
+        }
+        Ok(())
+    }
+    .await;
+    secure_storage.delete_data(SELF_CHECK_STORAGE_KEY).await?;
+    result
+}
+
+fn check_unique_chain_ids() -> Result<(), WalletError> {
+    let mut seen = HashSet::new();
+    for network in ALL_NETWORKS {
+        if !seen.insert(network.chain_id()) {
+            return Err(WalletError::config(format!("chain id {} is used twice", network.chain_id())));
+        }
+    }
+    Ok(())
+}
+
+fn network_config(network: &Network) -> &'static NetworkConfig {
+    match network {
+        Network::CoreTestnet => &CORE_TESTNET_CONFIG,
+        Network::BaseSepolia => &BASE_SEPOLIA_CONFIG,
+        Network::LiskSepolia => &LISK_SEPOLIA_CONFIG,
+        Network::EthereumHolesky => &HOLESKY_CONFIG,
+    }
+}
+
+/// The network and its `NetworkConfig` must describe the same chain
+fn check_network(network: &Network) -> Result<(), WalletError> {
+    if Network::from_chain_id(network.chain_id()).as_ref() != Some(network) {
+        return Err(WalletError::config("chain id does not map back to the network"));
+    }
+    let config = network_config(network);
+    let mismatches: Vec<&str> = [
+        ("chain_id", config.chain_id == network.chain_id()),
+        ("name", config.name == network.name()),
+        ("rpc_url", config.rpc_url == network.rpc_url()),
+        ("block_explorer", config.block_explorer == network.block_explorer()),
+        ("native_currency", config.native_currency == network.native_currency()),
+        ("contract_address", config.contract_address == network.contract_address()),
+    ]
+    .into_iter()
+    .filter(|(_, same)| !same)
+    .map(|(field, _)| field)
+    .collect();
+    if !mismatches.is_empty() {
+        return Err(WalletError::config(format!("network config disagrees on {}", mismatches.join(", "))));
+    }
+
+    validate_ethereum_address(network.contract_address())?;
+    if network.contract_address()[2..].chars().all(|c| c == '0') {
+        return Err(WalletError::config("payment contract address is zero"));
+    }
+    for (field, url) in [("rpc_url", network.rpc_url()), ("block_explorer", network.block_explorer())] {
+        // An empty RPC URL means it must come from the environment
+        if !(url.is_empty() && field == "rpc_url") && !url.starts_with("https://") {
+            return Err(WalletError::config(format!("{} {:?} is not an https URL", field, url)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    struct MockStorage {
+        data: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    impl PlatformStorage for MockStorage {
+        fn store(&self, key: &str, data: &[u8]) -> Result<(), WalletError> {
+            self.data.lock().unwrap().insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        fn retrieve(&self, key: &str) -> Result<Vec<u8>, WalletError> {
+            self.data.lock().unwrap().get(key).cloned()
+                .ok_or_else(|| WalletError::storage("Key not found"))
+        }
+
+        fn delete(&self, key: &str) -> Result<(), WalletError> {
+            self.data.lock().unwrap().remove(key);
+            Ok(())
+        }
+
+        fn exists(&self, key: &str) -> Result<bool, WalletError> {
+            Ok(self.data.lock().unwrap().contains_key(key))
+        }
+
+        fn list_keys(&self) -> Result<Vec<String>, WalletError> {
+            Ok(self.data.lock().unwrap().keys().cloned().collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_self_check_passes_and_leaves_storage_clean() {
+        let storage = MockStorage { data: Mutex::new(HashMap::new()) };
+        let report = run_self_check(&storage).await;
+
+        let failures: Vec<_> = report.failures().collect();
+        assert!(report.passed, "failed checks: {:?}", failures);
+        assert!(report.area_passed(SelfCheckArea::ChainRegistry));
+        assert_eq!(report.checks.iter().filter(|c| c.area == SelfCheckArea::ChainRegistry).count(), 1 + ALL_NETWORKS.len());
+        assert!(storage.list_keys().unwrap().is_empty());
+    }
+}
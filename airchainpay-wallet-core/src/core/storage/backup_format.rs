@@ -0,0 +1,93 @@
+//! Versioned wallet backup decoding
+//!
+//! Exports written before the storage refactor (version `1.0.0`) carry the
+//! ciphertext as a byte array with its salt embedded and a SHA-256 checksum,
+//! instead of base64 fields with a separate salt. Both are accepted on
+//! restore; old ones are re-encrypted into the current format.
+
+use crate::shared::error::WalletError;
+use crate::shared::types::{Network, WalletBackupInfo};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// Version written by `SecureStorage::backup_wallet`
+pub const CURRENT_BACKUP_VERSION: &str = "1.0";
+/// Version of exports made before the storage refactor
+pub const LEGACY_BACKUP_VERSION: &str = "1.0.0";
+
+/// Pre-refactor export: `encrypted_data` is `salt(32) || nonce(12) || ciphertext`
+#[derive(Debug, Clone, Deserialize)]
+pub struct LegacyBackup {
+    pub version: String,
+    pub wallet_id: String,
+    pub encrypted_data: Vec<u8>,
+    /// Hex SHA-256 of `encrypted_data`
+    pub checksum: String,
+    #[serde(default)]
+    pub created_at: u64,
+}
+
+impl LegacyBackup {
+    pub fn verify_checksum(&self) -> Result<(), WalletError> {
+        let actual = format!("{:x}", Sha256::digest(&self.encrypted_data));
+        if !actual.eq_ignore_ascii_case(self.checksum.trim()) {
+            return Err(WalletError::validation("Backup checksum mismatch; the export is corrupted"));
+        }
+        Ok(())
+    }
+}
+
+/// Wallet fields as old exports stored them; only the address and network
+/// were always present
+#[derive(Debug, Clone, Deserialize)]
+pub struct LegacyWalletPayload {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub address: String,
+    pub network: Network,
+}
+
+/// A backup in any format this build can restore
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum VersionedBackup {
+    Current(WalletBackupInfo),
+    Legacy(LegacyBackup),
+}
+
+impl VersionedBackup {
+    pub fn version(&self) -> &str {
+        match self {
+            VersionedBackup::Current(backup) => &backup.version,
+            VersionedBackup::Legacy(backup) => &backup.version,
+        }
+    }
+
+    pub fn wallet_id(&self) -> &str {
+        match self {
+            VersionedBackup::Current(backup) => &backup.wallet_id,
+            VersionedBackup::Legacy(backup) => &backup.wallet_id,
+        }
+    }
+
+    pub fn needs_upgrade(&self) -> bool {
+        matches!(self, VersionedBackup::Legacy(_))
+    }
+}
+
+/// Parse an exported backup, whatever version wrote it
+pub fn decode_backup(data: &str) -> Result<VersionedBackup, WalletError> {
+    let backup: VersionedBackup = serde_json::from_str(data)
+        .map_err(|e| WalletError::validation(format!("Unrecognized backup format: {}", e)))?;
+    match &backup {
+        VersionedBackup::Current(b) if b.version != CURRENT_BACKUP_VERSION => {
+            Err(WalletError::validation(format!("Unsupported backup version {}", b.version)))
+        }
+        VersionedBackup::Legacy(b) if b.version != LEGACY_BACKUP_VERSION => {
+            Err(WalletError::validation(format!("Unsupported backup version {}", b.version)))
+        }
+        _ => Ok(backup),
+    }
+}
@@ -0,0 +1,207 @@
+//! Batched, journaled storage writes
+//!
+//! A batch is first written whole to a journal entry, then applied key by
+//! key, then the journal is removed. A crash before the journal is complete
+//! leaves storage untouched (a torn journal fails its checksum and is
+//! dropped); a crash after it replays the journal on the next `recover`, so
+//! either every write in the batch lands or none does.
+
+use crate::infrastructure::platform::PlatformStorage;
+use crate::shared::error::WalletError;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Storage key of the pending batch, if any
+pub const JOURNAL_KEY: &str = "storage_journal";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum BatchOp {
+    /// `data` is base64, already encrypted when staged through `SecureStorage`
+    Put { key: String, data: String },
+    Delete { key: String },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Journal {
+    ops: Vec<BatchOp>,
+    /// Hex SHA-256 of the JSON-encoded `ops`
+    checksum: String,
+}
+
+fn ops_checksum(ops: &[BatchOp]) -> Result<String, WalletError> {
+    let encoded = serde_json::to_vec(ops)
+        .map_err(|e| WalletError::storage(format!("Failed to encode write batch: {}", e)))?;
+    Ok(format!("{:x}", Sha256::digest(&encoded)))
+}
+
+/// Writes staged in memory until `commit`
+#[derive(Debug, Default)]
+pub struct WriteBatch {
+    ops: Vec<BatchOp>,
+}
+
+impl WriteBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stage `data` to be stored as-is under `key`
+    pub fn put(&mut self, key: &str, data: &[u8]) -> Result<(), WalletError> {
+        Self::check_key(key)?;
+        self.ops.push(BatchOp::Put { key: key.to_string(), data: STANDARD.encode(data) });
+        Ok(())
+    }
+
+    pub fn delete(&mut self, key: &str) -> Result<(), WalletError> {
+        Self::check_key(key)?;
+        self.ops.push(BatchOp::Delete { key: key.to_string() });
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    fn check_key(key: &str) -> Result<(), WalletError> {
+        if key.is_empty() || key == JOURNAL_KEY {
+            return Err(WalletError::validation(format!("Invalid storage key in batch: {:?}", key)));
+        }
+        Ok(())
+    }
+
+    /// Journal the batch, apply it and clear the journal, returning the
+    /// number of writes. Fails without applying anything if an earlier batch
+    /// is still pending; run `recover` first.
+    pub fn commit(self, storage: &dyn PlatformStorage) -> Result<usize, WalletError> {
+        if self.ops.is_empty() {
+            return Ok(0);
+        }
+        if storage.exists(JOURNAL_KEY)? {
+            return Err(WalletError::storage("An earlier write batch has not been recovered"));
+        }
+
+        let journal = Journal { checksum: ops_checksum(&self.ops)?, ops: self.ops };
+        let encoded = serde_json::to_vec(&journal)
+            .map_err(|e| WalletError::storage(format!("Failed to encode write batch: {}", e)))?;
+        storage.store(JOURNAL_KEY, &encoded)?;
+
+        let applied = apply(storage, &journal.ops)?;
+        storage.delete(JOURNAL_KEY)?;
+        Ok(applied)
+    }
+}
+
+fn apply(storage: &dyn PlatformStorage, ops: &[BatchOp]) -> Result<usize, WalletError> {
+    for op in ops {
+        match op {
+            BatchOp::Put { key, data } => {
+                let data = STANDARD.decode(data)
+                    .map_err(|e| WalletError::storage(format!("Corrupt write batch entry {}: {}", key, e)))?;
+                storage.store(key, &data)?;
+            }
+            BatchOp::Delete { key } => {
+                if storage.exists(key)? {
+                    storage.delete(key)?;
+                }
+            }
+        }
+    }
+    Ok(ops.len())
+}
+
+/// Finish a batch interrupted by a crash. Returns the number of writes
+/// replayed, or `None` when no complete batch was pending.
+pub fn recover(storage: &dyn PlatformStorage) -> Result<Option<usize>, WalletError> {
+    if !storage.exists(JOURNAL_KEY)? {
+        return Ok(None);
+    }
+
+    let journal = storage.retrieve(JOURNAL_KEY).ok()
+        .and_then(|bytes| serde_json::from_slice::<Journal>(&bytes).ok())
+        .filter(|journal| ops_checksum(&journal.ops).ok().as_deref() == Some(journal.checksum.as_str()));
+    let Some(journal) = journal else {
+        // Torn while being written, so none of its writes were applied
+        log::warn!("Discarding incomplete storage journal");
+        storage.delete(JOURNAL_KEY)?;
+        return Ok(None);
+    };
+
+    // Puts and deletes are idempotent, so a partly applied batch replays safely
+    let applied = apply(storage, &journal.ops)?;
+    storage.delete(JOURNAL_KEY)?;
+    log::info!("Replayed {} writes from an interrupted storage batch", applied);
+    Ok(Some(applied))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// In-memory storage that fails every store once `stores_left` runs out
+    struct CrashingStorage {
+        data: Mutex<HashMap<String, Vec<u8>>>,
+        stores_left: AtomicUsize,
+    }
+
+    impl PlatformStorage for CrashingStorage {
+        fn store(&self, key: &str, data: &[u8]) -> Result<(), WalletError> {
+            if self.stores_left.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_err() {
+                return Err(WalletError::storage("Simulated crash"));
+            }
+            self.data.lock().unwrap().insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        fn retrieve(&self, key: &str) -> Result<Vec<u8>, WalletError> {
+            self.data.lock().unwrap().get(key).cloned()
+                .ok_or_else(|| WalletError::storage("Key not found"))
+        }
+
+        fn delete(&self, key: &str) -> Result<(), WalletError> {
+            self.data.lock().unwrap().remove(key);
+            Ok(())
+        }
+
+        fn exists(&self, key: &str) -> Result<bool, WalletError> {
+            Ok(self.data.lock().unwrap().contains_key(key))
+        }
+
+        fn list_keys(&self) -> Result<Vec<String>, WalletError> {
+            Ok(self.data.lock().unwrap().keys().cloned().collect())
+        }
+    }
+
+    #[test]
+    fn test_interrupted_batch_is_replayed_on_recover() {
+        let storage = CrashingStorage { data: Mutex::new(HashMap::new()), stores_left: AtomicUsize::new(2) };
+        storage.data.lock().unwrap().insert("stale".to_string(), b"old".to_vec());
+
+        let mut batch = WriteBatch::new();
+        batch.put("tx_1", b"first").unwrap();
+        batch.put("tx_2", b"second").unwrap();
+        batch.delete("stale").unwrap();
+        assert!(batch.put(JOURNAL_KEY, b"x").is_err());
+
+        // The journal and the first put land, then the "crash"
+        assert!(batch.commit(&storage).is_err());
+        assert!(storage.exists(JOURNAL_KEY).unwrap());
+        assert!(!storage.exists("tx_2").unwrap());
+
+        storage.stores_left.store(usize::MAX, Ordering::SeqCst);
+        assert_eq!(recover(&storage).unwrap(), Some(3));
+        assert_eq!(storage.retrieve("tx_2").unwrap(), b"second");
+        assert!(!storage.exists("stale").unwrap());
+        assert!(!storage.exists(JOURNAL_KEY).unwrap());
+        assert_eq!(recover(&storage).unwrap(), None);
+    }
+}
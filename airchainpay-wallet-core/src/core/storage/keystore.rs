@@ -0,0 +1,329 @@
+//! Web3 Secret Storage (V3 JSON keystore)
+//!
+//! The keystore format geth and MetaMask read and write: the private key is
+//! encrypted with AES-128-CTR under a key stretched from the password with
+//! scrypt or PBKDF2, and a Keccak-256 MAC over the second half of that key
+//! and the ciphertext detects a wrong password. Unlike `WalletBackup`, a
+//! keystore carries the private key itself, so it can move a wallet to
+//! another application.
+
+use crate::shared::error::WalletError;
+use crate::shared::sources::RandomSource;
+use aes::cipher::{KeyIvInit, StreamCipher};
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+use zeroize::Zeroizing;
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+pub const KEYSTORE_VERSION: u8 = 3;
+const DERIVED_KEY_LEN: usize = 32;
+
+// Upper bounds on the KDF cost read from an imported keystore, so a crafted
+// file cannot make import take unbounded memory or time. scrypt needs
+// 128·N·r bytes and time proportional to N·r·p; geth's standard parameters
+// sit exactly at the memory cap.
+const MAX_SCRYPT_N: u32 = 1 << 20;
+const MAX_SCRYPT_R: u32 = 16;
+const MAX_SCRYPT_P: u32 = 16;
+const MAX_SCRYPT_MEMORY: u64 = 256 * 1024 * 1024;
+const MAX_SCRYPT_WORK: u64 = 1 << 22;
+const MAX_PBKDF2_ROUNDS: u32 = 10_000_000;
+const MAX_DERIVED_KEY_LEN: usize = 64;
+
+/// scrypt cost used when exporting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScryptParams {
+    pub n: u32,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl ScryptParams {
+    /// geth's default (`StandardScryptN`)
+    pub const STANDARD: Self = Self { n: 1 << 18, r: 8, p: 1 };
+    /// geth's `LightScryptN`, for devices where the standard cost is too slow
+    pub const LIGHT: Self = Self { n: 1 << 12, r: 8, p: 6 };
+}
+
+impl Default for ScryptParams {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CipherParams {
+    pub iv: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KdfParams {
+    Scrypt {
+        dklen: usize,
+        n: u32,
+        r: u32,
+        p: u32,
+        salt: String,
+    },
+    Pbkdf2 {
+        c: u32,
+        dklen: usize,
+        prf: String,
+        salt: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeystoreCrypto {
+    pub cipher: String,
+    pub cipherparams: CipherParams,
+    pub ciphertext: String,
+    pub kdf: String,
+    pub kdfparams: KdfParams,
+    pub mac: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u8,
+    pub id: String,
+    /// Lowercase hex without `0x`, as geth writes it; optional in the spec
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// Some older tools write `Crypto`
+    #[serde(alias = "Crypto")]
+    pub crypto: KeystoreCrypto,
+}
+
+impl Keystore {
+    pub fn from_json(json: &str) -> Result<Self, WalletError> {
+        let keystore: Self = serde_json::from_str(json)
+            .map_err(|e| WalletError::validation(format!("Invalid keystore: {}", e)))?;
+        if keystore.version != KEYSTORE_VERSION {
+            return Err(WalletError::validation(format!("Unsupported keystore version {}", keystore.version)));
+        }
+        Ok(keystore)
+    }
+
+    pub fn to_json(&self) -> Result<String, WalletError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// `0x`-prefixed address recorded in the keystore, if any
+    pub fn address(&self) -> Option<String> {
+        self.address.as_ref().map(|a| format!("0x{}", a.trim_start_matches("0x").to_lowercase()))
+    }
+}
+
+fn decode_hex(field: &str, value: &str) -> Result<Vec<u8>, WalletError> {
+    hex::decode(value.trim_start_matches("0x"))
+        .map_err(|e| WalletError::validation(format!("Invalid keystore {}: {}", field, e)))
+}
+
+fn derive_key(password: &str, kdf: &KdfParams) -> Result<Zeroizing<Vec<u8>>, WalletError> {
+    match kdf {
+        KdfParams::Scrypt { dklen, n, r, p, salt } => {
+            if !(DERIVED_KEY_LEN..=MAX_DERIVED_KEY_LEN).contains(dklen) || !n.is_power_of_two() {
+                return Err(WalletError::validation("Invalid scrypt parameters in keystore"));
+            }
+            check_scrypt_cost(*n, *r, *p)?;
+            let params = scrypt::Params::new(n.trailing_zeros() as u8, *r, *p, *dklen)
+                .map_err(|e| WalletError::validation(format!("Invalid scrypt parameters in keystore: {}", e)))?;
+            let mut key = Zeroizing::new(vec![0u8; *dklen]);
+            scrypt::scrypt(password.as_bytes(), &decode_hex("salt", salt)?, &params, &mut key)
+                .map_err(|e| WalletError::crypto(format!("scrypt failed: {}", e)))?;
+            Ok(key)
+        }
+        KdfParams::Pbkdf2 { c, dklen, prf, salt } => {
+            if prf != "hmac-sha256" {
+                return Err(WalletError::validation(format!("Unsupported keystore PRF {}", prf)));
+            }
+            if !(DERIVED_KEY_LEN..=MAX_DERIVED_KEY_LEN).contains(dklen) {
+                return Err(WalletError::validation("Invalid PBKDF2 parameters in keystore"));
+            }
+            if *c > MAX_PBKDF2_ROUNDS {
+                return Err(WalletError::validation(format!(
+                    "Keystore PBKDF2 cost too high ({} rounds; limit {})",
+                    c, MAX_PBKDF2_ROUNDS
+                )));
+            }
+            let mut key = Zeroizing::new(vec![0u8; *dklen]);
+            pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), &decode_hex("salt", salt)?, *c, &mut key);
+            Ok(key)
+        }
+    }
+}
+
+fn check_scrypt_cost(n: u32, r: u32, p: u32) -> Result<(), WalletError> {
+    let (memory, work) = (128 * n as u64 * r as u64, n as u64 * r as u64 * p as u64);
+    if n > MAX_SCRYPT_N || r > MAX_SCRYPT_R || p > MAX_SCRYPT_P || memory > MAX_SCRYPT_MEMORY || work > MAX_SCRYPT_WORK {
+        return Err(WalletError::validation(format!(
+            "Keystore scrypt cost too high (n={}, r={}, p={}: {} MiB, N·r·p {}; limits {} MiB, {})",
+            n, r, p, memory >> 20, work, MAX_SCRYPT_MEMORY >> 20, MAX_SCRYPT_WORK
+        )));
+    }
+    Ok(())
+}
+
+fn mac(derived_key: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let mut hasher = Keccak256::new();
+    hasher.update(&derived_key[16..32]);
+    hasher.update(ciphertext);
+    hasher.finalize().to_vec()
+}
+
+/// Address of a raw secp256k1 private key
+pub(crate) fn address_of(private_key: &[u8]) -> Result<String, WalletError> {
+    let secret_key = SecretKey::from_byte_array(private_key.try_into().map_err(|_| WalletError::crypto("Invalid private key length"))?)
+        .map_err(|e| WalletError::crypto(format!("Invalid private key: {}", e)))?;
+    let public_key = PublicKey::from_secret_key(&Secp256k1::new(), &secret_key).serialize_uncompressed();
+    Ok(hex::encode(&Keccak256::digest(&public_key[1..])[12..]))
+}
+
+/// Encrypt a private key into a V3 keystore
+pub fn encrypt_keystore(
+    private_key: &[u8],
+    password: &str,
+    params: ScryptParams,
+    rng: &dyn RandomSource,
+) -> Result<Keystore, WalletError> {
+    let address = address_of(private_key)?;
+    let salt = rng.random_bytes(32)?;
+    let iv = rng.random_bytes(16)?;
+    let kdfparams = KdfParams::Scrypt {
+        dklen: DERIVED_KEY_LEN,
+        n: params.n,
+        r: params.r,
+        p: params.p,
+        salt: hex::encode(&salt),
+    };
+    let derived_key = derive_key(password, &kdfparams)?;
+
+    let mut ciphertext = private_key.to_vec();
+    Aes128Ctr::new(derived_key[..16].into(), iv.as_slice().into()).apply_keystream(&mut ciphertext);
+
+    let mut id = rng.random_bytes(16)?;
+    // RFC 4122 version 4 UUID
+    id[6] = (id[6] & 0x0f) | 0x40;
+    id[8] = (id[8] & 0x3f) | 0x80;
+
+    Ok(Keystore {
+        version: KEYSTORE_VERSION,
+        id: uuid::Uuid::from_slice(&id).map_err(|e| WalletError::internal(e.to_string()))?.to_string(),
+        address: Some(address),
+        crypto: KeystoreCrypto {
+            cipher: "aes-128-ctr".to_string(),
+            cipherparams: CipherParams { iv: hex::encode(&iv) },
+            mac: hex::encode(mac(&derived_key, &ciphertext)),
+            ciphertext: hex::encode(&ciphertext),
+            kdf: "scrypt".to_string(),
+            kdfparams,
+        },
+    })
+}
+
+/// Decrypt the private key in a V3 keystore. A wrong password fails the MAC
+/// check; a key that does not match the recorded address is rejected.
+pub fn decrypt_keystore(keystore: &Keystore, password: &str) -> Result<Zeroizing<Vec<u8>>, WalletError> {
+    let crypto = &keystore.crypto;
+    if crypto.cipher != "aes-128-ctr" {
+        return Err(WalletError::validation(format!("Unsupported keystore cipher {}", crypto.cipher)));
+    }
+    let expected_kdf = match crypto.kdfparams {
+        KdfParams::Scrypt { .. } => "scrypt",
+        KdfParams::Pbkdf2 { .. } => "pbkdf2",
+    };
+    if crypto.kdf != expected_kdf {
+        return Err(WalletError::validation(format!("Keystore kdf {} does not match its parameters", crypto.kdf)));
+    }
+
+    let ciphertext = decode_hex("ciphertext", &crypto.ciphertext)?;
+    let iv = decode_hex("iv", &crypto.cipherparams.iv)?;
+    if iv.len() != 16 {
+        return Err(WalletError::validation("Invalid keystore iv length"));
+    }
+    let derived_key = derive_key(password, &crypto.kdfparams)?;
+
+    let expected_mac = decode_hex("mac", &crypto.mac)?;
+    let actual_mac = mac(&derived_key, &ciphertext);
+    let matches = expected_mac.len() == actual_mac.len()
+        && expected_mac.iter().zip(&actual_mac).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0;
+    if !matches {
+        return Err(WalletError::crypto("Keystore MAC mismatch; wrong password or corrupted file"));
+    }
+
+    let mut private_key = Zeroizing::new(ciphertext);
+    Aes128Ctr::new(derived_key[..16].into(), iv.as_slice().into()).apply_keystream(&mut private_key);
+
+    if let Some(address) = keystore.address() {
+        if format!("0x{}", address_of(&private_key)?) != address {
+            return Err(WalletError::validation("Keystore key does not match its address"));
+        }
+    }
+    Ok(private_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::sources::SeededRandom;
+
+    // Test vector from the Web3 Secret Storage definition
+    const PBKDF2_VECTOR: &str = r#"{
+        "crypto": {
+            "cipher": "aes-128-ctr",
+            "cipherparams": { "iv": "6087dab2f9fdbbfaddc31a909735c1e6" },
+            "ciphertext": "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+            "kdf": "pbkdf2",
+            "kdfparams": {
+                "c": 262144,
+                "dklen": 32,
+                "prf": "hmac-sha256",
+                "salt": "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+            },
+            "mac": "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+        },
+        "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version": 3
+    }"#;
+
+    #[test]
+    fn test_keystore_round_trip_and_vector() {
+        let keystore = Keystore::from_json(PBKDF2_VECTOR).unwrap();
+        let key = decrypt_keystore(&keystore, "testpassword").unwrap();
+        assert_eq!(hex::encode(&*key), "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d");
+        assert!(decrypt_keystore(&keystore, "wrong").is_err());
+
+        let rng = SeededRandom::new(7);
+        let exported = encrypt_keystore(&key, "hunter2", ScryptParams::LIGHT, &rng).unwrap();
+        let json = exported.to_json().unwrap();
+        let imported = Keystore::from_json(&json).unwrap();
+        assert_eq!(imported.address, exported.address);
+        assert_eq!(&*decrypt_keystore(&imported, "hunter2").unwrap(), &*key);
+    }
+
+    #[test]
+    fn test_keystore_kdf_cost_is_capped() {
+        let costly = Keystore::from_json(&PBKDF2_VECTOR.replace("262144", "4000000000")).unwrap();
+        assert!(decrypt_keystore(&costly, "testpassword").is_err());
+
+        let rng = SeededRandom::new(7);
+        let mut keystore = encrypt_keystore(&[7u8; 32], "hunter2", ScryptParams::LIGHT, &rng).unwrap();
+        for (n, r, p) in [(1 << 24, 8, 1), (1 << 12, 1024, 1), (1 << 12, 8, 4096), (1 << 20, 16, 16), (1 << 19, 8, 1)] {
+            if let KdfParams::Scrypt { n: kn, r: kr, p: kp, .. } = &mut keystore.crypto.kdfparams {
+                (*kn, *kr, *kp) = (n, r, p);
+            }
+            assert!(decrypt_keystore(&keystore, "hunter2").is_err());
+        }
+
+        for params in [ScryptParams::STANDARD, ScryptParams::LIGHT] {
+            assert!(check_scrypt_cost(params.n, params.r, params.p).is_ok());
+        }
+        assert!(check_scrypt_cost(1 << 18, 8, 2).is_ok());
+        assert!(check_scrypt_cost(1 << 18, 8, 4).is_err());
+    }
+}
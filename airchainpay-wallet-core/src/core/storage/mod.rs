@@ -10,24 +10,46 @@ use aes_gcm::aead::{Aead, generic_array::GenericArray};
 use argon2::{Argon2, PasswordHasher};
 use rand_core::OsRng;
 use rand_core::RngCore;
+use crate::shared::sources::{default_random_source, RandomSource};
+use std::sync::Arc;
 use sha2::Digest;
 use serde_json;
 use crate::infrastructure::platform::{PlatformStorage, FileStorage};
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
+use zeroize::Zeroizing;
+
+pub mod backup_format;
+pub mod batch;
+pub mod keystore;
+pub mod recovery_codes;
+
+pub use backup_format::{decode_backup, VersionedBackup, CURRENT_BACKUP_VERSION};
+pub use batch::WriteBatch;
+pub use keystore::{decrypt_keystore, encrypt_keystore, Keystore, ScryptParams};
+pub use recovery_codes::{RecoveryCodeSet, DEFAULT_RECOVERY_CODE_COUNT};
 
 /// Secure storage manager
 pub struct SecureStorage<'a> {
     storage: &'a dyn PlatformStorage,
+    rng: Arc<dyn RandomSource>,
 }
 
 impl<'a> SecureStorage<'a> {
     pub fn new(storage: &'a dyn PlatformStorage) -> Self {
-        Self { storage }
+        Self { storage, rng: default_random_source() }
+    }
+
+    /// Draw salts and nonces from `rng` instead of the OS
+    pub fn with_random_source(mut self, rng: Arc<dyn RandomSource>) -> Self {
+        self.rng = rng;
+        self
     }
 
+    /// Finishes any write batch a crash interrupted
     pub async fn init(&self) -> Result<(), WalletError> {
         log::info!("Initializing secure storage");
+        batch::recover(self.storage)?;
         Ok(())
     }
 
@@ -45,6 +67,18 @@ impl<'a> SecureStorage<'a> {
         self.storage.delete(key)
     }
 
+    /// Encrypt `data` into `batch`; nothing is written until `commit_batch`
+    pub async fn stage_data(&self, batch: &mut WriteBatch, key: &str, data: &[u8], password: &str) -> Result<(), WalletError> {
+        let encrypted = self.encrypt_data(data, password).await?;
+        batch.put(key, &encrypted)
+    }
+
+    /// Apply every write in `batch` or, if interrupted, none until the
+    /// next `init` replays it
+    pub async fn commit_batch(&self, batch: WriteBatch) -> Result<usize, WalletError> {
+        batch.commit(self.storage)
+    }
+
     /// Backup wallet securely (no private keys in wallet struct)
     pub async fn backup_wallet(&self, wallet: &Wallet, password: &str) -> Result<WalletBackupInfo, WalletError> {
         // Convert to safe WalletInfo for serialization
@@ -56,26 +90,16 @@ impl<'a> SecureStorage<'a> {
         
         // Generate salt
         let mut salt = [0u8; 16];
-        let mut rng = OsRng;
-        rng.fill_bytes(&mut salt);
+        self.rng.fill_bytes(&mut salt)?;
         
         // Derive key
-        let salt_str = argon2::password_hash::SaltString::encode_b64(&salt)?;
-        let argon2 = Argon2::default();
-        let password_hash = argon2.hash_password(password.as_bytes(), &salt_str)
-            .map_err(|e| WalletError::crypto(format!("Password hashing failed: {}", e)))?;
-        
-        // Handle the case where hash might be None
-        let hash = password_hash.hash
-            .ok_or_else(|| WalletError::crypto("Password hash is empty".to_string()))?;
-        let hash_bytes = hash.as_bytes();
-        let key = GenericArray::from_slice(&hash_bytes[..32]);
+        let backup_key = backup_key(password, &salt)?;
+        let key = GenericArray::from_slice(&backup_key[..]);
         
         // Encrypt
         let cipher = Aes256Gcm::new(key);
         let mut nonce = [0u8; 12];
-        let mut rng = OsRng;
-        rng.fill_bytes(&mut nonce);
+        self.rng.fill_bytes(&mut nonce)?;
         let mut encrypted_data = nonce.to_vec();
         let ciphertext = cipher.encrypt(GenericArray::from_slice(&nonce), wallet_bytes.as_ref())
             .map_err(|e| WalletError::crypto(format!("Encryption failed: {}", e)))?;
@@ -89,58 +113,123 @@ impl<'a> SecureStorage<'a> {
             encrypted_data: STANDARD.encode(&encrypted_data),
             salt: STANDARD.encode(&salt),
             version: "1.0".to_string(),
+            recovery_codes: None,
         })
     }
 
     /// Restore wallet securely (no private keys in wallet struct)
     pub async fn restore_wallet(&self, backup: &WalletBackupInfo, password: &str) -> Result<Wallet, WalletError> {
-        let encrypted_data = STANDARD.decode(&backup.encrypted_data)
+        let salt = STANDARD.decode(&backup.salt)
             .map_err(|e| WalletError::crypto(format!("Base64 decode failed: {}", e)))?;
+        open_backup(backup, &backup_key(password, &salt)?)
+    }
+
+    /// Issue `count` single-use recovery codes that each restore `backup`
+    /// without its password. Replaces any codes issued before; the codes
+    /// are returned once and only their hashes are kept. The escrowed set
+    /// is written into `backup` as well as local storage, so export the
+    /// updated backup to keep the codes usable off this device. Codes spent
+    /// from earlier sets stay spent.
+    pub async fn create_recovery_codes(&self, backup: &mut WalletBackupInfo, password: &str, count: usize) -> Result<Vec<Zeroizing<String>>, WalletError> {
         let salt = STANDARD.decode(&backup.salt)
             .map_err(|e| WalletError::crypto(format!("Base64 decode failed: {}", e)))?;
-        
-        if encrypted_data.len() < 12 {
-            return Err(WalletError::crypto("Encrypted data too short".to_string()));
+        let key = backup_key(password, &salt)?;
+        // Refuse to escrow a key that does not open the backup
+        open_backup(backup, &key)?;
+        let (set, codes) = RecoveryCodeSet::generate(backup, &key, count, self.rng.as_ref())?;
+        let set_bytes = set.to_bytes()?;
+        self.storage.store(&recovery_codes_key(&backup.wallet_id), &set_bytes)?;
+        backup.recovery_codes = Some(STANDARD.encode(&set_bytes));
+        Ok(codes)
+    }
+
+    /// Restore `backup` with a recovery code, using the code up. The set
+    /// comes from `backup`, or from this device if the backup carries none,
+    /// and is checked against the backup key the code unseals. Spent codes
+    /// are recorded on this device only, so any copy of the backup, however
+    /// old, refuses them here.
+    pub async fn restore_with_recovery_code(&self, backup: &WalletBackupInfo, code: &str) -> Result<Wallet, WalletError> {
+        let set_bytes = match backup.recovery_codes.as_deref() {
+            Some(escrowed) => STANDARD.decode(escrowed)
+                .map_err(|e| WalletError::crypto(format!("Base64 decode failed: {}", e)))?,
+            None => {
+                let storage_key = recovery_codes_key(&backup.wallet_id);
+                if !self.storage.exists(&storage_key)? {
+                    return Err(WalletError::validation("No recovery codes were issued for this backup"));
+                }
+                self.storage.retrieve(&storage_key)?
+            }
+        };
+        let set = RecoveryCodeSet::from_bytes(&set_bytes)?;
+        let mut spent = self.spent_recovery_codes(&backup.wallet_id)?;
+        let (key, code_hash) = set.redeem(backup, code, &spent)?;
+        let wallet = open_backup(backup, &key)?;
+        // Persist the spent code before handing anything back
+        spent.push(code_hash);
+        self.storage.store(&spent_recovery_codes_key(&backup.wallet_id), &serde_json::to_vec(&spent)?)?;
+        if !self.storage.exists(&recovery_codes_key(&backup.wallet_id))? {
+            self.storage.store(&recovery_codes_key(&backup.wallet_id), &set_bytes)?;
         }
-        
-        let (nonce, ciphertext) = encrypted_data.split_at(12);
-        let salt_str = argon2::password_hash::SaltString::encode_b64(&salt)?;
-        let argon2 = Argon2::default();
-        let password_hash = argon2.hash_password(password.as_bytes(), &salt_str)
-            .map_err(|e| WalletError::crypto(format!("Password hashing failed: {}", e)))?;
-        
-        // Handle the case where hash might be None
-        let hash = password_hash.hash
-            .ok_or_else(|| WalletError::crypto("Password hash is empty".to_string()))?;
-        let hash_bytes = hash.as_bytes();
-        let key = GenericArray::from_slice(&hash_bytes[..32]);
-        let cipher = Aes256Gcm::new(key);
-        let wallet_bytes = cipher.decrypt(GenericArray::from_slice(nonce), ciphertext)
-            .map_err(|e| WalletError::crypto(format!("Decryption failed: {}", e)))?;
-        
-        // Deserialize as WalletInfo first
-        let wallet_info: WalletInfo = serde_json::from_slice(&wallet_bytes)
-            .map_err(|e| WalletError::validation(format!("Wallet deserialization failed: {}", e)))?;
-        
-        // Convert back to Wallet (no private keys)
-        let wallet = Wallet::new(
-            wallet_info.name,
-            wallet_info.address,
-            "".to_string(), // No public key needed for restore
-            wallet_info.network,
-        ).map_err(|e| WalletError::validation(format!("Wallet creation failed: {}", e)))?;
-        
+        log::info!("Wallet backup {} restored with a recovery code; {} codes left", backup.wallet_id, set.remaining(&spent));
         Ok(wallet)
     }
 
+    /// Unused recovery codes for a wallet; 0 when none were issued
+    pub fn recovery_codes_remaining(&self, wallet_id: &str) -> Result<usize, WalletError> {
+        let storage_key = recovery_codes_key(wallet_id);
+        if !self.storage.exists(&storage_key)? {
+            return Ok(0);
+        }
+        let set = RecoveryCodeSet::from_bytes(&self.storage.retrieve(&storage_key)?)?;
+        Ok(set.remaining(&self.spent_recovery_codes(wallet_id)?))
+    }
+
+    /// `code_hash`es of the recovery codes redeemed on this device
+    fn spent_recovery_codes(&self, wallet_id: &str) -> Result<Vec<String>, WalletError> {
+        let storage_key = spent_recovery_codes_key(wallet_id);
+        if !self.storage.exists(&storage_key)? {
+            return Ok(Vec::new());
+        }
+        serde_json::from_slice(&self.storage.retrieve(&storage_key)?)
+            .map_err(|e| WalletError::validation(format!("Spent recovery code list is unreadable: {}", e)))
+    }
+
+    /// Restore an exported backup of any supported version. Old exports are
+    /// re-encrypted into the current format, returned alongside the wallet
+    /// so the caller can replace the stored copy.
+    pub async fn restore_any_backup(&self, data: &str, password: &str) -> Result<(Wallet, WalletBackupInfo), WalletError> {
+        let legacy = match decode_backup(data)? {
+            VersionedBackup::Current(backup) => {
+                let wallet = self.restore_wallet(&backup, password).await?;
+                return Ok((wallet, backup));
+            }
+            VersionedBackup::Legacy(legacy) => legacy,
+        };
+
+        legacy.verify_checksum()?;
+        let payload_bytes = self.decrypt_data(&legacy.encrypted_data, password).await?;
+        let payload: backup_format::LegacyWalletPayload = serde_json::from_slice(&payload_bytes)
+            .map_err(|e| WalletError::validation(format!("Legacy wallet deserialization failed: {}", e)))?;
+
+        let mut wallet = Wallet::new(
+            payload.name.unwrap_or_else(|| "Restored Wallet".to_string()),
+            payload.address,
+            "".to_string(),
+            payload.network,
+        )?;
+        wallet.id = payload.id.unwrap_or(legacy.wallet_id);
+
+        let upgraded = self.backup_wallet(&wallet, password).await?;
+        log::info!("Upgraded wallet backup {} from version {} to {}", wallet.id, legacy.version, upgraded.version);
+        Ok((wallet, upgraded))
+    }
+
     async fn encrypt_data(&self, data: &[u8], password: &str) -> Result<Vec<u8>, WalletError> {
         use aes_gcm::{Aes256Gcm, aead::{Aead, generic_array::GenericArray}};
-        use rand_core::RngCore;
         use argon2::{Argon2, PasswordHasher};
         
         let mut salt = [0u8; 32];
-        let mut rng = OsRng;
-        rng.fill_bytes(&mut salt);
+        self.rng.fill_bytes(&mut salt)?;
         let salt_str = argon2::password_hash::SaltString::encode_b64(&salt)?;
         let argon2 = Argon2::default();
         let password_hash = argon2.hash_password(password.as_bytes(), &salt_str)
@@ -153,8 +242,7 @@ impl<'a> SecureStorage<'a> {
         let key = GenericArray::from_slice(&hash_bytes[..32]);
         let cipher = Aes256Gcm::new(key);
         let mut nonce = [0u8; 12];
-        let mut rng = OsRng;
-        rng.fill_bytes(&mut nonce);
+        self.rng.fill_bytes(&mut nonce)?;
         let ciphertext = cipher.encrypt(GenericArray::from_slice(&nonce), data)
             .map_err(|e| WalletError::crypto(format!("Encryption failed: {}", e)))?;
         let mut result = Vec::new();
@@ -191,6 +279,57 @@ impl<'a> SecureStorage<'a> {
     }
 }
 
+/// Key that encrypts a wallet backup: Argon2id over the password and the
+/// backup's salt
+pub(crate) fn backup_key(password: &str, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>, WalletError> {
+    let salt_str = argon2::password_hash::SaltString::encode_b64(salt)?;
+    let argon2 = Argon2::default();
+    let password_hash = argon2.hash_password(password.as_bytes(), &salt_str)
+        .map_err(|e| WalletError::crypto(format!("Password hashing failed: {}", e)))?;
+    
+    // Handle the case where hash might be None
+    let hash = password_hash.hash
+        .ok_or_else(|| WalletError::crypto("Password hash is empty".to_string()))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    key.copy_from_slice(&hash.as_bytes()[..32]);
+    Ok(key)
+}
+
+/// Decrypt a wallet backup with its key (no private keys in wallet struct)
+pub(crate) fn open_backup(backup: &WalletBackupInfo, key: &[u8; 32]) -> Result<Wallet, WalletError> {
+    let encrypted_data = STANDARD.decode(&backup.encrypted_data)
+        .map_err(|e| WalletError::crypto(format!("Base64 decode failed: {}", e)))?;
+    
+    if encrypted_data.len() < 12 {
+        return Err(WalletError::crypto("Encrypted data too short".to_string()));
+    }
+    
+    let (nonce, ciphertext) = encrypted_data.split_at(12);
+    let cipher = Aes256Gcm::new(GenericArray::from_slice(key));
+    let wallet_bytes = cipher.decrypt(GenericArray::from_slice(nonce), ciphertext)
+        .map_err(|e| WalletError::crypto(format!("Decryption failed: {}", e)))?;
+    
+    // Deserialize as WalletInfo first
+    let wallet_info: WalletInfo = serde_json::from_slice(&wallet_bytes)
+        .map_err(|e| WalletError::validation(format!("Wallet deserialization failed: {}", e)))?;
+    
+    // Convert back to Wallet (no private keys)
+    Wallet::new(
+        wallet_info.name,
+        wallet_info.address,
+        "".to_string(), // No public key needed for restore
+        wallet_info.network,
+    ).map_err(|e| WalletError::validation(format!("Wallet creation failed: {}", e)))
+}
+
+fn recovery_codes_key(wallet_id: &str) -> String {
+    format!("recovery_codes_{}", wallet_id)
+}
+
+fn spent_recovery_codes_key(wallet_id: &str) -> String {
+    format!("recovery_codes_spent_{}", wallet_id)
+}
+
 /// Storage manager for wallet data persistence
 pub struct StorageManager {
     // Uses FileStorage and SecureStorage for real persistent storage
@@ -214,6 +353,36 @@ impl StorageManager {
         storage.restore_wallet(backup, password).await
     }
 
+    pub async fn create_recovery_codes(&self, backup: &mut WalletBackupInfo, password: &str, count: usize) -> Result<Vec<Zeroizing<String>>, WalletError> {
+        let file_storage = FileStorage::new()?;
+        let storage = SecureStorage::new(&file_storage);
+        storage.create_recovery_codes(backup, password, count).await
+    }
+
+    pub async fn restore_with_recovery_code(&self, backup: &WalletBackupInfo, code: &str) -> Result<Wallet, WalletError> {
+        let file_storage = FileStorage::new()?;
+        let storage = SecureStorage::new(&file_storage);
+        storage.restore_with_recovery_code(backup, code).await
+    }
+
+    pub async fn restore_any_backup(&self, data: &str, password: &str) -> Result<(Wallet, WalletBackupInfo), WalletError> {
+        let file_storage = FileStorage::new()?;
+        let storage = SecureStorage::new(&file_storage);
+        storage.restore_any_backup(data, password).await
+    }
+
+    /// Store many entries in one atomic batch, as history sync does
+    pub async fn import_entries(&self, entries: &[(String, Vec<u8>)], password: &str) -> Result<usize, WalletError> {
+        let file_storage = FileStorage::new()?;
+        let storage = SecureStorage::new(&file_storage);
+        storage.init().await?;
+        let mut batch = WriteBatch::new();
+        for (key, data) in entries {
+            storage.stage_data(&mut batch, key, data, password).await?;
+        }
+        storage.commit_batch(batch).await
+    }
+
     pub async fn load_wallet(&self, wallet_id: &str, password: &str) -> Result<Wallet, WalletError> {
         let file_storage = FileStorage::new()?;
         let storage = SecureStorage::new(&file_storage);
@@ -358,4 +527,72 @@ mod tests {
         assert_eq!(restored.address, wallet.address);
         assert_eq!(restored.network, wallet.network);
     }
+
+    #[tokio::test]
+    async fn test_recovery_code_restores_on_fresh_storage() {
+        let device = MockStorage::new();
+        let secure_storage = SecureStorage::new(&device);
+        let wallet = Wallet::new(
+            "Test Wallet".to_string(),
+            "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6".to_string(),
+            "04...".to_string(),
+            Network::CoreTestnet,
+        ).expect("Failed to create test wallet");
+        let mut backup = secure_storage.backup_wallet(&wallet, "test_password").await.unwrap();
+        let codes = secure_storage.create_recovery_codes(&mut backup, "test_password", 2).await.unwrap();
+        let exported = serde_json::to_string(&backup).unwrap();
+
+        // A new device has nothing but the exported backup
+        let new_device = MockStorage::new();
+        let fresh_storage = SecureStorage::new(&new_device);
+        let imported: WalletBackupInfo = serde_json::from_str(&exported).unwrap();
+        let restored = fresh_storage.restore_with_recovery_code(&imported, &codes[0]).await
+            .expect("Failed to restore with recovery code");
+        assert_eq!(restored.address, wallet.address);
+        assert_eq!(fresh_storage.recovery_codes_remaining(&backup.wallet_id).unwrap(), 1);
+
+        // The spent code stays spent for the same, unchanged backup copy
+        let reimported: WalletBackupInfo = serde_json::from_str(&exported).unwrap();
+        assert!(fresh_storage.restore_with_recovery_code(&reimported, &codes[0]).await.is_err());
+
+        // A code set edited inside the backup is refused
+        let mut set = RecoveryCodeSet::from_bytes(&STANDARD.decode(imported.recovery_codes.as_deref().unwrap()).unwrap()).unwrap();
+        set.codes.swap(0, 1);
+        let mut edited = imported.clone();
+        edited.recovery_codes = Some(STANDARD.encode(set.to_bytes().unwrap()));
+        assert!(fresh_storage.restore_with_recovery_code(&edited, &codes[1]).await.is_err());
+        assert!(fresh_storage.restore_with_recovery_code(&imported, &codes[1]).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_legacy_backup_is_upgraded_on_restore() {
+        let storage = MockStorage::new();
+        let secure_storage = SecureStorage::new(&storage);
+        let password = "test_password";
+
+        let payload = serde_json::json!({
+            "address": "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6",
+            "network": "CoreTestnet",
+        });
+        let encrypted = secure_storage.encrypt_data(payload.to_string().as_bytes(), password).await.unwrap();
+        let legacy = serde_json::json!({
+            "version": "1.0.0",
+            "wallet_id": "wallet_legacy",
+            "checksum": format!("{:x}", sha2::Sha256::digest(&encrypted)),
+            "encrypted_data": encrypted,
+            "created_at": 1_690_000_000u64,
+        });
+
+        let (wallet, upgraded) = secure_storage.restore_any_backup(&legacy.to_string(), password).await
+            .expect("Failed to restore legacy backup");
+        assert_eq!(wallet.id, "wallet_legacy");
+        assert_eq!(wallet.address, "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6");
+        assert_eq!(upgraded.version, CURRENT_BACKUP_VERSION);
+
+        let upgraded_json = serde_json::to_string(&upgraded).unwrap();
+        let (restored, _) = secure_storage.restore_any_backup(&upgraded_json, password).await
+            .expect("Failed to restore upgraded backup");
+        assert_eq!(restored.address, wallet.address);
+        assert!(secure_storage.restore_any_backup(&legacy.to_string(), "wrong").await.is_err());
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,279 @@
+//! Single-use recovery codes for wallet backups
+//!
+//! Each code escrows a copy of one backup's encryption key, sealed with
+//! AES-256-GCM under an Argon2id key derived from the code. Only an
+//! Argon2id hash of the code is kept, so the stored set reveals neither the
+//! codes nor the key. A printed code restores the backup without its
+//! password; a new backup has a new salt and needs new codes.
+//!
+//! The set never changes after it is issued and carries an HMAC under the
+//! backup key, so a set edited in transit is refused once a code unseals
+//! that key. Which codes are spent is not part of the set: the caller keeps
+//! that list in local storage, where restoring an older backup copy cannot
+//! roll it back.
+
+use crate::shared::error::WalletError;
+use crate::shared::sources::RandomSource;
+use crate::shared::types::WalletBackupInfo;
+use aes_gcm::aead::{generic_array::GenericArray, Aead};
+use aes_gcm::{Aes256Gcm, KeyInit};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+/// Codes issued when the caller does not ask for a number
+pub const DEFAULT_RECOVERY_CODE_COUNT: usize = 10;
+/// Most codes one set may hold
+const MAX_RECOVERY_CODES: usize = 20;
+/// Crockford base32: no I, L, O or U to misread on paper
+const CODE_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+/// 60 bits, printed as `XXXX-XXXX-XXXX`
+const CODE_LENGTH: usize = 12;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscrowedBackupKey {
+    /// Argon2id PHC hash of the normalised code
+    pub code_hash: String,
+    /// Salt of the key derived from the code, base64
+    pub kdf_salt: String,
+    /// Nonce followed by the sealed backup key, base64
+    pub sealed_key: String,
+}
+
+/// The recovery codes of one wallet backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryCodeSet {
+    pub wallet_id: String,
+    /// Salt of the backup the codes open
+    pub backup_salt: String,
+    pub created_at: i64,
+    pub codes: Vec<EscrowedBackupKey>,
+    /// HMAC-SHA256 under the backup key over the rest of the set, hex
+    #[serde(default)]
+    pub mac: String,
+}
+
+impl RecoveryCodeSet {
+    /// Escrow `backup_key` under `count` fresh codes. The codes are returned
+    /// here only; print them and drop them.
+    pub fn generate(
+        backup: &WalletBackupInfo,
+        backup_key: &[u8; 32],
+        count: usize,
+        rng: &dyn RandomSource,
+    ) -> Result<(Self, Vec<Zeroizing<String>>), WalletError> {
+        if count == 0 || count > MAX_RECOVERY_CODES {
+            return Err(WalletError::validation(format!("Recovery code count must be 1 to {}", MAX_RECOVERY_CODES)));
+        }
+
+        let mut codes = Vec::with_capacity(count);
+        let mut escrowed = Vec::with_capacity(count);
+        for _ in 0..count {
+            let code = random_code(rng)?;
+            escrowed.push(seal(&normalize(&code), backup_key, rng)?);
+            codes.push(code);
+        }
+
+        let mut set = Self {
+            wallet_id: backup.wallet_id.clone(),
+            backup_salt: backup.salt.clone(),
+            created_at: chrono::Utc::now().timestamp(),
+            codes: escrowed,
+            mac: String::new(),
+        };
+        set.mac = hex::encode(set.authenticator(backup_key)?.finalize().into_bytes());
+        Ok((set, codes))
+    }
+
+    /// Codes not among `spent`, the `code_hash`es of codes already redeemed
+    pub fn remaining(&self, spent: &[String]) -> usize {
+        self.codes.iter().filter(|c| !spent.contains(&c.code_hash)).count()
+    }
+
+    /// Unseal the key of `backup` with `code`, returning it with the code's
+    /// `code_hash` for the caller to add to `spent`. Case, spaces and
+    /// dashes in the code do not matter.
+    pub fn redeem(&self, backup: &WalletBackupInfo, code: &str, spent: &[String]) -> Result<(Zeroizing<[u8; 32]>, String), WalletError> {
+        if backup.wallet_id != self.wallet_id || backup.salt != self.backup_salt {
+            return Err(WalletError::validation("Recovery codes were issued for a different backup"));
+        }
+
+        let code = normalize(code);
+        let argon2 = Argon2::default();
+        for entry in self.codes.iter().filter(|c| !spent.contains(&c.code_hash)) {
+            let hash = PasswordHash::new(&entry.code_hash)
+                .map_err(|e| WalletError::crypto(format!("Invalid recovery code hash: {}", e)))?;
+            if argon2.verify_password(code.as_bytes(), &hash).is_err() {
+                continue;
+            }
+            let key = unseal(&code, entry)?;
+            let mac = hex::decode(&self.mac).map_err(|_| WalletError::crypto("Recovery code set is not authenticated"))?;
+            self.authenticator(&key)?.verify_slice(&mac)
+                .map_err(|_| WalletError::crypto("Recovery code set was modified"))?;
+            return Ok((key, entry.code_hash.clone()));
+        }
+        Err(WalletError::validation("Recovery code is invalid or already used"))
+    }
+
+    /// HMAC under the backup key, fed everything in the set but `mac`
+    fn authenticator(&self, backup_key: &[u8; 32]) -> Result<Hmac<Sha256>, WalletError> {
+        let unsigned = Self { mac: String::new(), ..self.clone() };
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(backup_key).expect("HMAC accepts any key length");
+        mac.update(b"airchainpay-recovery-codes:");
+        mac.update(&unsigned.to_bytes()?);
+        Ok(mac)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, WalletError> {
+        serde_json::to_vec(self).map_err(|e| WalletError::validation(format!("Recovery code serialization failed: {}", e)))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WalletError> {
+        serde_json::from_slice(bytes).map_err(|e| WalletError::validation(format!("Recovery code deserialization failed: {}", e)))
+    }
+}
+
+fn random_code(rng: &dyn RandomSource) -> Result<Zeroizing<String>, WalletError> {
+    let bytes = Zeroizing::new(rng.random_bytes(CODE_LENGTH)?);
+    let mut code = Zeroizing::new(String::with_capacity(CODE_LENGTH + 2));
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 && i % 4 == 0 {
+            code.push('-');
+        }
+        // 256 is a multiple of 32, so this is uniform
+        code.push(CODE_ALPHABET[(byte & 0x1f) as usize] as char);
+    }
+    Ok(code)
+}
+
+/// Upper-case and strip separators, reading the letters Crockford base32
+/// leaves out as the digits they resemble
+fn normalize(code: &str) -> Zeroizing<String> {
+    Zeroizing::new(
+        code.chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .map(|c| match c.to_ascii_uppercase() {
+                'O' => '0',
+                'I' | 'L' => '1',
+                other => other,
+            })
+            .collect(),
+    )
+}
+
+fn code_key(code: &str, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>, WalletError> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::default()
+        .hash_password_into(code.as_bytes(), salt, &mut key[..])
+        .map_err(|e| WalletError::crypto(format!("Recovery code key derivation failed: {}", e)))?;
+    Ok(key)
+}
+
+fn seal(code: &str, backup_key: &[u8; 32], rng: &dyn RandomSource) -> Result<EscrowedBackupKey, WalletError> {
+    let hash_salt = SaltString::encode_b64(&rng.random_bytes(16)?)?;
+    let code_hash = Argon2::default()
+        .hash_password(code.as_bytes(), &hash_salt)
+        .map_err(|e| WalletError::crypto(format!("Recovery code hashing failed: {}", e)))?
+        .to_string();
+
+    let kdf_salt = rng.random_bytes(16)?;
+    let key = code_key(code, &kdf_salt)?;
+    let nonce = rng.random_bytes(12)?;
+    let ciphertext = Aes256Gcm::new(GenericArray::from_slice(&key[..]))
+        .encrypt(GenericArray::from_slice(&nonce), &backup_key[..])
+        .map_err(|e| WalletError::crypto(format!("Encryption failed: {}", e)))?;
+    let mut sealed = nonce;
+    sealed.extend_from_slice(&ciphertext);
+
+    Ok(EscrowedBackupKey {
+        code_hash,
+        kdf_salt: STANDARD.encode(&kdf_salt),
+        sealed_key: STANDARD.encode(&sealed),
+    })
+}
+
+fn unseal(code: &str, entry: &EscrowedBackupKey) -> Result<Zeroizing<[u8; 32]>, WalletError> {
+    let kdf_salt = STANDARD.decode(&entry.kdf_salt)
+        .map_err(|e| WalletError::crypto(format!("Base64 decode failed: {}", e)))?;
+    let sealed = STANDARD.decode(&entry.sealed_key)
+        .map_err(|e| WalletError::crypto(format!("Base64 decode failed: {}", e)))?;
+    if sealed.len() < 12 {
+        return Err(WalletError::crypto("Sealed key too short".to_string()));
+    }
+
+    let key = code_key(code, &kdf_salt)?;
+    let (nonce, ciphertext) = sealed.split_at(12);
+    let plaintext = Zeroizing::new(
+        Aes256Gcm::new(GenericArray::from_slice(&key[..]))
+            .decrypt(GenericArray::from_slice(nonce), ciphertext)
+            .map_err(|e| WalletError::crypto(format!("Decryption failed: {}", e)))?,
+    );
+    let mut backup_key = Zeroizing::new([0u8; 32]);
+    if plaintext.len() != backup_key.len() {
+        return Err(WalletError::crypto("Sealed key has the wrong length".to_string()));
+    }
+    backup_key.copy_from_slice(&plaintext);
+    Ok(backup_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::sources::OsRandom;
+
+    #[test]
+    fn test_recovery_code_redeems_once() {
+        let backup = WalletBackupInfo {
+            wallet_id: "wallet_1".to_string(),
+            encrypted_data: String::new(),
+            salt: "c2FsdA==".to_string(),
+            version: "1.0".to_string(),
+            recovery_codes: None,
+        };
+        let backup_key = [7u8; 32];
+        let (set, codes) = RecoveryCodeSet::generate(&backup, &backup_key, 2, &OsRandom).unwrap();
+        assert_eq!(codes.len(), 2);
+        assert_eq!(codes[0].len(), 14);
+        let stored = String::from_utf8(set.to_bytes().unwrap()).unwrap();
+        assert!(!stored.contains(codes[0].as_str()));
+
+        // Typed loosely: lower case, no dashes
+        let typed = codes[1].replace('-', "").to_lowercase();
+        let (key, code_hash) = set.redeem(&backup, &typed, &[]).unwrap();
+        assert_eq!(*key, backup_key);
+        let spent = vec![code_hash];
+        assert_eq!(set.remaining(&spent), 1);
+        assert!(set.redeem(&backup, &codes[1], &spent).is_err());
+        assert!(set.redeem(&backup, "0000-0000-0000", &spent).is_err());
+
+        let mut other = backup.clone();
+        other.salt = "b3RoZXI=".to_string();
+        assert!(set.redeem(&other, &codes[0], &spent).is_err());
+    }
+
+    #[test]
+    fn test_modified_recovery_code_set_is_refused() {
+        let backup = WalletBackupInfo {
+            wallet_id: "wallet_1".to_string(),
+            encrypted_data: String::new(),
+            salt: "c2FsdA==".to_string(),
+            version: "1.0".to_string(),
+            recovery_codes: None,
+        };
+        let (set, codes) = RecoveryCodeSet::generate(&backup, &[7u8; 32], 2, &OsRandom).unwrap();
+
+        let mut edited = set.clone();
+        edited.created_at += 1;
+        assert!(edited.redeem(&backup, &codes[0], &[]).is_err());
+
+        let mut unsigned = set.clone();
+        unsigned.mac.clear();
+        assert!(unsigned.redeem(&backup, &codes[0], &[]).is_err());
+        assert!(set.redeem(&backup, &codes[0], &[]).is_ok());
+    }
+}
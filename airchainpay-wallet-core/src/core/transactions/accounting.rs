@@ -0,0 +1,372 @@
+//! Accounting export
+//!
+//! An export values every transfer in the `TransactionHistory` at the fiat
+//! price on its date, tracks a running balance and an average-cost basis
+//! per token, and renders the result as CSV for an accountant. Prices come from a `PriceSource` so apps
+//! can plug in whichever price feed they already use.
+
+use super::history::{parse_units, token_key, HistoryEntry, TransactionHistory, TransferDirection};
+use crate::shared::error::WalletError;
+use crate::shared::types::{TokenInfo, TransactionHash};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ethers::types::{I256, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A fiat amount with `Fiat::DECIMALS` decimal places
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Fiat(i128);
+
+impl Fiat {
+    pub const DECIMALS: u32 = 8;
+    const SCALE: i128 = 100_000_000;
+
+    pub fn from_units(units: i128) -> Self {
+        Self(units)
+    }
+
+    /// Value in 10^-8 of the currency unit
+    pub fn units(&self) -> i128 {
+        self.0
+    }
+
+    /// Parse a decimal such as `"1834.25"` or `"-0.5"`
+    pub fn parse(value: &str) -> Result<Self, WalletError> {
+        let invalid = || WalletError::validation(format!("Invalid fiat amount {}", value));
+        let trimmed = value.trim();
+        let (negative, digits) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() && fraction.is_empty()
+            || fraction.len() > Self::DECIMALS as usize
+            || !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        let whole: i128 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| invalid())? };
+        let fraction: i128 = format!("{:0<8}", fraction).parse().map_err(|_| invalid())?;
+        let units = whole.checked_mul(Self::SCALE).and_then(|w| w.checked_add(fraction)).ok_or_else(invalid)?;
+        Ok(Self(if negative { -units } else { units }))
+    }
+
+    /// Decimal text rounded half away from zero to `places` (at most 8)
+    pub fn format(&self, places: u32) -> String {
+        let places = places.min(Self::DECIMALS);
+        let step = 10i128.pow(Self::DECIMALS - places);
+        let abs = self.0.unsigned_abs() as i128;
+        let rounded = (abs + step / 2) / step;
+        let scale = 10i128.pow(places);
+        let sign = if self.0 < 0 && rounded != 0 { "-" } else { "" };
+        if places == 0 {
+            return format!("{}{}", sign, rounded);
+        }
+        format!("{}{}.{:0width$}", sign, rounded / scale, rounded % scale, width = places as usize)
+    }
+
+    fn checked_add(self, other: Fiat) -> Option<Fiat> {
+        self.0.checked_add(other.0).map(Fiat)
+    }
+
+    fn checked_sub(self, other: Fiat) -> Option<Fiat> {
+        self.0.checked_sub(other.0).map(Fiat)
+    }
+}
+
+/// Historical fiat prices for tokens
+#[async_trait]
+pub trait PriceSource: Send + Sync {
+    /// Price of one whole `token` in `currency` (e.g. `USD`) at `at`
+    async fn price_at(&self, token: &TokenInfo, currency: &str, at: DateTime<Utc>) -> Result<Fiat, WalletError>;
+}
+
+/// One line of the accounting export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountingRow {
+    pub timestamp: DateTime<Utc>,
+    pub hash: TransactionHash,
+    pub chain_id: u64,
+    pub direction: TransferDirection,
+    pub token: String,
+    pub amount: String,
+    pub price: Fiat,
+    pub value: Fiat,
+    pub fee: Option<String>,
+    pub fee_value: Option<Fiat>,
+    /// Balance of `token` after this transfer and its fee
+    pub running_balance: String,
+    /// Average-cost basis of the tokens still held after this transfer
+    pub cost_basis: Fiat,
+    /// Gain or loss against cost basis on outgoing transfers
+    pub realized_gain: Option<Fiat>,
+    pub counterparty: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountingReport {
+    pub currency: String,
+    pub rows: Vec<AccountingRow>,
+}
+
+impl AccountingReport {
+    /// Render as CSV with a header row; fiat columns are rounded to cents
+    pub fn to_csv(&self) -> String {
+        let currency = &self.currency;
+        let mut out = format!(
+            "date,tx_hash,chain_id,direction,token,amount,price_{c},value_{c},fee,fee_value_{c},running_balance,cost_basis_{c},realized_gain_{c},counterparty\n",
+            c = currency.to_lowercase()
+        );
+        for row in &self.rows {
+            let fields = [
+                row.timestamp.to_rfc3339(),
+                row.hash.clone(),
+                row.chain_id.to_string(),
+                match row.direction {
+                    TransferDirection::Incoming => "in".to_string(),
+                    TransferDirection::Outgoing => "out".to_string(),
+                },
+                row.token.clone(),
+                row.amount.clone(),
+                row.price.format(Fiat::DECIMALS),
+                row.value.format(2),
+                row.fee.clone().unwrap_or_default(),
+                row.fee_value.map(|v| v.format(2)).unwrap_or_default(),
+                row.running_balance.clone(),
+                row.cost_basis.format(2),
+                row.realized_gain.map(|v| v.format(2)).unwrap_or_default(),
+                row.counterparty.clone(),
+            ];
+            let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+            out.push_str(&line.join(","));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Whole-token decimal text of signed base units, without trailing zeros
+fn format_units(value: I256, decimals: u8) -> String {
+    let digits = value.unsigned_abs().to_string();
+    let decimals = decimals as usize;
+    let padded = format!("{:0>width$}", digits, width = decimals + 1);
+    let (whole, fraction) = padded.split_at(padded.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    let sign = if value.is_negative() { "-" } else { "" };
+    if fraction.is_empty() {
+        format!("{}{}", sign, whole)
+    } else {
+        format!("{}{}.{}", sign, whole, fraction)
+    }
+}
+
+/// Fiat value of `amount` base units at `price` per whole token
+fn fiat_value(amount: U256, decimals: u8, price: Fiat) -> Result<Fiat, WalletError> {
+    if price.units() < 0 {
+        return Err(WalletError::validation("Token prices cannot be negative"));
+    }
+    let value = amount
+        .checked_mul(U256::from(price.units() as u128))
+        .map(|v| v / U256::exp10(decimals as usize))
+        .filter(|v| *v <= U256::from(i128::MAX as u128))
+        .ok_or_else(|| WalletError::validation("Fiat value out of range"))?;
+    Ok(Fiat::from_units(value.as_u128() as i128))
+}
+
+#[derive(Default)]
+struct Position {
+    balance: I256,
+    /// Tokens the cost basis covers; never negative
+    held: U256,
+    cost_basis: Fiat,
+}
+
+impl Position {
+    fn acquire(&mut self, amount: U256, value: Fiat) -> Result<(), WalletError> {
+        self.balance = self.balance.saturating_add(I256::from_raw(amount));
+        self.held = self.held.saturating_add(amount);
+        self.cost_basis = self.cost_basis.checked_add(value)
+            .ok_or_else(|| WalletError::validation("Cost basis out of range"))?;
+        Ok(())
+    }
+
+    /// Remove `amount` at average cost, returning the cost of what left
+    fn dispose(&mut self, amount: U256) -> Result<Fiat, WalletError> {
+        self.balance = self.balance.saturating_sub(I256::from_raw(amount));
+        let disposed = amount.min(self.held);
+        let cost = if self.held.is_zero() {
+            Fiat::default()
+        } else {
+            // Average cost of the disposed share; basis is never negative
+            let share = U256::from(self.cost_basis.units().max(0) as u128) * disposed / self.held;
+            Fiat::from_units(share.as_u128() as i128)
+        };
+        self.held -= disposed;
+        self.cost_basis = self.cost_basis.checked_sub(cost)
+            .ok_or_else(|| WalletError::validation("Cost basis out of range"))?;
+        Ok(cost)
+    }
+}
+
+/// Value transfers in date order and track balances and average cost per token
+pub async fn build_report(entries: &[HistoryEntry], prices: &dyn PriceSource, currency: &str) -> Result<AccountingReport, WalletError> {
+    let mut ordered: Vec<&HistoryEntry> = entries.iter().collect();
+    ordered.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.hash.cmp(&b.hash)));
+
+    let mut positions: HashMap<String, Position> = HashMap::new();
+    let mut rows = Vec::with_capacity(ordered.len());
+    for entry in ordered {
+        let amount = parse_units(&entry.amount)?;
+        let price = prices.price_at(&entry.token, currency, entry.timestamp).await?;
+        let value = fiat_value(amount, entry.token.decimals, price)?;
+
+        let position = positions.entry(token_key(entry.chain_id, &entry.token)).or_default();
+        let realized_gain = match entry.direction {
+            TransferDirection::Incoming => {
+                position.acquire(amount, value)?;
+                None
+            }
+            TransferDirection::Outgoing => {
+                let cost = position.dispose(amount)?;
+                Some(value.checked_sub(cost).ok_or_else(|| WalletError::validation("Gain out of range"))?)
+            }
+        };
+
+        let (fee, fee_value) = match &entry.fee {
+            Some(fee) => {
+                let fee_amount = parse_units(&fee.amount)?;
+                let fee_price = prices.price_at(&fee.token, currency, entry.timestamp).await?;
+                positions.entry(token_key(entry.chain_id, &fee.token)).or_default().dispose(fee_amount)?;
+                (
+                    Some(format_units(I256::from_raw(fee_amount), fee.token.decimals)),
+                    Some(fiat_value(fee_amount, fee.token.decimals, fee_price)?),
+                )
+            }
+            None => (None, None),
+        };
+
+        let position = &positions[&token_key(entry.chain_id, &entry.token)];
+        rows.push(AccountingRow {
+            timestamp: entry.timestamp,
+            hash: entry.hash.clone(),
+            chain_id: entry.chain_id,
+            direction: entry.direction,
+            token: entry.token.symbol.clone(),
+            amount: format_units(I256::from_raw(amount), entry.token.decimals),
+            price,
+            value,
+            fee,
+            fee_value,
+            running_balance: format_units(position.balance, entry.token.decimals),
+            cost_basis: position.cost_basis,
+            realized_gain,
+            counterparty: entry.counterparty.clone(),
+        });
+    }
+
+    Ok(AccountingReport { currency: currency.to_uppercase(), rows })
+}
+
+impl TransactionHistory<'_> {
+    /// Accounting report over the whole history, valued in `currency`
+    pub async fn accounting_report(&self, prices: &dyn PriceSource, currency: &str) -> Result<AccountingReport, WalletError> {
+        build_report(&self.entries().await?, prices, currency).await
+    }
+
+    pub async fn export_csv(&self, prices: &dyn PriceSource, currency: &str) -> Result<String, WalletError> {
+        Ok(self.accounting_report(prices, currency).await?.to_csv())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::transactions::history::{HistoryStatus, TransferFee};
+    use chrono::TimeZone;
+
+    /// ETH at 2000 then 3000; USDC at 1
+    struct FixedPrices;
+
+    #[async_trait]
+    impl PriceSource for FixedPrices {
+        async fn price_at(&self, token: &TokenInfo, _currency: &str, at: DateTime<Utc>) -> Result<Fiat, WalletError> {
+            match token.symbol.as_str() {
+                "ETH" if at.timestamp() < 1_700_100_000 => Fiat::parse("2000"),
+                "ETH" => Fiat::parse("3000"),
+                _ => Fiat::parse("1"),
+            }
+        }
+    }
+
+    fn token(symbol: &str, decimals: u8, address: &str) -> TokenInfo {
+        TokenInfo {
+            symbol: symbol.to_string(),
+            name: symbol.to_string(),
+            decimals,
+            address: address.to_string(),
+            chain_id: "84532".to_string(),
+            is_native: address.is_empty(),
+            is_stablecoin: symbol == "USDC",
+        }
+    }
+
+    fn entry(hash: &str, at: i64, direction: TransferDirection, asset: TokenInfo, amount: &str, fee: Option<&str>) -> HistoryEntry {
+        HistoryEntry {
+            hash: hash.to_string(),
+            chain_id: 84532,
+            timestamp: Utc.timestamp_opt(at, 0).unwrap(),
+            direction,
+            token: asset,
+            amount: amount.to_string(),
+            counterparty: "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6".to_string(),
+            fee: fee.map(|amount| TransferFee { token: token("ETH", 18, ""), amount: amount.to_string() }),
+            wallet_id: None,
+            status: HistoryStatus::Confirmed,
+            block_number: None,
+            relay_transaction_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_report_tracks_value_balance_and_cost_basis() {
+        let eth = token("ETH", 18, "");
+        let usdc = token("USDC", 6, "0x036CbD53842c5426634e7929541eC2318f3dCF7e");
+        let entries = vec![
+            // Out of order on purpose; the report sorts by date
+            entry("0x02", 1_700_200_000, TransferDirection::Outgoing, eth.clone(), "500000000000000000", Some("1000000000000000")),
+            entry("0x01", 1_700_000_000, TransferDirection::Incoming, eth.clone(), "1000000000000000000", None),
+            entry("0x03", 1_700_300_000, TransferDirection::Incoming, usdc, "25500000", None),
+        ];
+
+        let report = build_report(&entries, &FixedPrices, "usd").await.unwrap();
+        assert_eq!(report.currency, "USD");
+        let [received, sent, usdc_row] = &report.rows[..] else { panic!("expected three rows") };
+
+        assert_eq!(received.value, Fiat::parse("2000").unwrap());
+        assert_eq!(received.running_balance, "1");
+        assert_eq!(sent.value, Fiat::parse("1500").unwrap());
+        assert_eq!(sent.fee_value, Some(Fiat::parse("3").unwrap()));
+        // 0.5 bought at 2000 and sold at 3000; the fee leaves at cost too
+        assert_eq!(sent.realized_gain, Some(Fiat::parse("500").unwrap()));
+        assert_eq!(sent.running_balance, "0.499");
+        assert_eq!(sent.cost_basis, Fiat::parse("998").unwrap());
+        assert_eq!(usdc_row.running_balance, "25.5");
+
+        let csv = report.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].starts_with("date,tx_hash,chain_id,direction,token,amount,price_usd,value_usd"));
+        assert!(lines[2].contains(",out,ETH,0.5,3000.00000000,1500.00,0.001,3.00,0.499,998.00,500.00,"));
+
+        assert_eq!(Fiat::parse("-12.345").unwrap().format(2), "-12.35");
+        assert!(Fiat::parse("1.123456789").is_err());
+    }
+}
@@ -0,0 +1,385 @@
+//! Multi-token balances across networks
+//!
+//! `BalanceService` reads the native balance and every registered ERC-20
+//! balance of an address with one `eth_call` per network, batched through
+//! Multicall3's `aggregate3`. Networks without Multicall3 fall back to one
+//! call per token. Results are cached per network and address; a refresh
+//! that fails hands back the last result marked stale instead of an error,
+//! so a wallet screen keeps showing balances while offline.
+
+use crate::shared::error::WalletError;
+use crate::shared::sources::{default_clock, Clock};
+use crate::shared::types::{Address, Network, TokenBalance, TokenInfo};
+use crate::shared::utils::format_amount;
+use ethers::abi::{self, ParamType, Token};
+use ethers::types::{H160, U256};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Multicall3, deployed at the same address on every supported network
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+/// Selector of Multicall3 `aggregate3((address,bool,bytes)[])`
+const AGGREGATE3_SELECTOR: [u8; 4] = [0x82, 0xad, 0x56, 0xcb];
+/// Selector of Multicall3 `getEthBalance(address)`
+const GET_ETH_BALANCE_SELECTOR: [u8; 4] = [0x4d, 0x23, 0x01, 0xcc];
+/// Selector of ERC-20 `balanceOf(address)`
+const BALANCE_OF_SELECTOR: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+/// Cached balances younger than this are returned without a refresh
+const DEFAULT_MAX_AGE_SECS: u64 = 30;
+
+/// RPC endpoint for `network`: the `WALLET_CORE_RPC_*` override, else the
+/// network's public default
+pub(crate) fn rpc_url_for(network: &Network) -> Result<String, WalletError> {
+    let var = match network {
+        Network::CoreTestnet => "WALLET_CORE_RPC_CORE_TESTNET",
+        Network::BaseSepolia => "WALLET_CORE_RPC_BASE_SEPOLIA",
+        Network::LiskSepolia => "WALLET_CORE_RPC_LISK_SEPOLIA",
+        Network::EthereumHolesky => "WALLET_CORE_RPC_HOLESKY",
+    };
+    match std::env::var(var) {
+        Ok(url) if !url.is_empty() => Ok(url),
+        _ if !network.rpc_url().is_empty() => Ok(network.rpc_url().to_string()),
+        _ => Err(WalletError::config(format!("RPC URL not set for {}", network.name()))),
+    }
+}
+
+/// Balances of one address on one network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkBalances {
+    pub network: Network,
+    pub address: Address,
+    /// Native balance first, then tokens in registration order
+    pub balances: Vec<TokenBalance>,
+    /// Unix seconds when these balances were read from the network
+    pub fetched_at: u64,
+    /// Older than the service's max age, or the last refresh failed
+    pub stale: bool,
+    /// Why the last refresh failed, when it did
+    pub error: Option<String>,
+}
+
+impl NetworkBalances {
+    pub fn balance_of(&self, symbol: &str) -> Option<&TokenBalance> {
+        self.balances.iter().find(|b| b.token.symbol == symbol)
+    }
+}
+
+/// Fetches and caches native and ERC-20 balances on the configured networks
+pub struct BalanceService {
+    networks: Vec<Network>,
+    tokens: HashMap<Network, Vec<TokenInfo>>,
+    rpc_urls: HashMap<Network, String>,
+    multicall_address: String,
+    max_age_secs: u64,
+    clock: Arc<dyn Clock>,
+    client: Client,
+    cache: RwLock<HashMap<(Network, String), NetworkBalances>>,
+}
+
+impl BalanceService {
+    /// A service for `networks`, reading RPC URLs as `WalletManager::get_balance` does
+    pub fn new(networks: Vec<Network>) -> Self {
+        Self {
+            networks,
+            tokens: HashMap::new(),
+            rpc_urls: HashMap::new(),
+            multicall_address: MULTICALL3_ADDRESS.to_string(),
+            max_age_secs: DEFAULT_MAX_AGE_SECS,
+            clock: default_clock(),
+            client: Client::new(),
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Track an ERC-20 token on the network its `chain_id` names
+    pub fn with_token(mut self, token: TokenInfo) -> Result<Self, WalletError> {
+        if token.is_native {
+            return Ok(self);
+        }
+        let network = token.chain_id.parse().ok()
+            .and_then(Network::from_chain_id)
+            .ok_or_else(|| WalletError::validation(format!("Token {} is on unsupported chain {}", token.symbol, token.chain_id)))?;
+        token.address.parse::<H160>()
+            .map_err(|_| WalletError::validation(format!("Invalid token address: {}", token.address)))?;
+        self.tokens.entry(network).or_default().push(token);
+        Ok(self)
+    }
+
+    pub fn with_rpc_url(mut self, network: Network, rpc_url: impl Into<String>) -> Self {
+        self.rpc_urls.insert(network, rpc_url.into());
+        self
+    }
+
+    /// Multicall3 deployment to batch through, for networks that use another address
+    pub fn with_multicall_address(mut self, address: impl Into<String>) -> Self {
+        self.multicall_address = address.into();
+        self
+    }
+
+    pub fn with_max_age(mut self, max_age_secs: u64) -> Self {
+        self.max_age_secs = max_age_secs;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn networks(&self) -> &[Network] {
+        &self.networks
+    }
+
+    /// Balances of `address` on every configured network, refreshing those
+    /// whose cache is older than the max age. Networks are queried
+    /// concurrently; one that fails returns its cached balances marked
+    /// stale, or is left out if it has never been read.
+    pub async fn balances(&self, address: &str) -> Result<Vec<NetworkBalances>, WalletError> {
+        let address = format!("{:#x}", parse_address(address)?);
+        let reads = self.networks.iter().map(|network| self.network_balances(network, &address, false));
+        Ok(futures::future::join_all(reads).await.into_iter().flatten().collect())
+    }
+
+    /// Balances on one network, refreshed regardless of the cache when `force` is set
+    pub async fn network_balances(&self, network: &Network, address: &str, force: bool) -> Option<NetworkBalances> {
+        let key = (network.clone(), address.to_lowercase());
+        let now = self.clock.unix_timestamp();
+        let cached = self.cache.read().await.get(&key).cloned();
+        if let Some(cached) = &cached {
+            if !force && cached.error.is_none() && now.saturating_sub(cached.fetched_at) < self.max_age_secs {
+                return Some(cached.clone());
+            }
+        }
+
+        match self.fetch(network, address).await {
+            Ok(balances) => {
+                let fresh = NetworkBalances {
+                    network: network.clone(),
+                    address: address.to_string(),
+                    balances,
+                    fetched_at: now,
+                    stale: false,
+                    error: None,
+                };
+                self.cache.write().await.insert(key, fresh.clone());
+                Some(fresh)
+            }
+            Err(e) => {
+                log::warn!("Balance refresh on {} failed: {}", network.name(), e);
+                let mut stale = cached?;
+                stale.stale = true;
+                stale.error = Some(e.to_string());
+                self.cache.write().await.insert(key, stale.clone());
+                Some(stale)
+            }
+        }
+    }
+
+    /// Cached balances without touching the network, stale flags updated
+    pub async fn cached(&self, address: &str) -> Vec<NetworkBalances> {
+        let now = self.clock.unix_timestamp();
+        let address = address.to_lowercase();
+        let cache = self.cache.read().await;
+        self.networks.iter()
+            .filter_map(|network| cache.get(&(network.clone(), address.clone())))
+            .map(|cached| {
+                let mut cached = cached.clone();
+                cached.stale |= now.saturating_sub(cached.fetched_at) >= self.max_age_secs;
+                cached
+            })
+            .collect()
+    }
+
+    async fn fetch(&self, network: &Network, address: &str) -> Result<Vec<TokenBalance>, WalletError> {
+        let rpc_url = match self.rpc_urls.get(network) {
+            Some(url) => url.clone(),
+            None => rpc_url_for(network)?,
+        };
+        let owner = parse_address(address)?;
+        let tokens = self.tokens.get(network).cloned().unwrap_or_default();
+        let calls = balance_calls(owner, &self.multicall_address, &tokens)?;
+
+        let amounts = match self.aggregate(&rpc_url, &calls).await {
+            Ok(amounts) => amounts,
+            Err(e) => {
+                log::debug!("Multicall3 unavailable on {}, reading balances one by one: {}", network.name(), e);
+                self.read_individually(&rpc_url, address, &calls).await?
+            }
+        };
+
+        let native = native_token(network);
+        Ok(std::iter::once(native).chain(tokens).zip(amounts)
+            .map(|(token, amount)| {
+                let balance = amount.to_string();
+                TokenBalance {
+                    formatted_balance: format_amount(&balance, token.decimals).unwrap_or_else(|_| balance.clone()),
+                    balance,
+                    token,
+                }
+            })
+            .collect())
+    }
+
+    /// One `aggregate3` call for all balance reads; calls that fail read as zero
+    async fn aggregate(&self, rpc_url: &str, calls: &[(H160, Vec<u8>)]) -> Result<Vec<U256>, WalletError> {
+        let multicall = parse_address(&self.multicall_address)?;
+        let encoded = calls.iter()
+            .map(|(target, data)| Token::Tuple(vec![Token::Address(*target), Token::Bool(true), Token::Bytes(data.clone())]))
+            .collect();
+        let mut data = AGGREGATE3_SELECTOR.to_vec();
+        data.extend(abi::encode(&[Token::Array(encoded)]));
+
+        let output = self.eth_call(rpc_url, multicall, &data).await?;
+        decode_aggregate3(&output, calls.len())
+    }
+
+    async fn read_individually(&self, rpc_url: &str, address: &str, calls: &[(H160, Vec<u8>)]) -> Result<Vec<U256>, WalletError> {
+        let mut amounts = Vec::with_capacity(calls.len());
+        amounts.push(self.native_balance(rpc_url, address).await?);
+        for (target, data) in &calls[1..] {
+            let output = self.eth_call(rpc_url, *target, data).await?;
+            amounts.push(decode_uint(&output).unwrap_or_default());
+        }
+        Ok(amounts)
+    }
+
+    async fn native_balance(&self, rpc_url: &str, address: &str) -> Result<U256, WalletError> {
+        let result = self.rpc(rpc_url, "eth_getBalance", json!([address, "latest"])).await?;
+        let hex_balance = result.as_str()
+            .ok_or_else(|| WalletError::network("Missing balance result"))?;
+        U256::from_str_radix(hex_balance.trim_start_matches("0x"), 16)
+            .map_err(|_| WalletError::network("Invalid balance"))
+    }
+
+    async fn eth_call(&self, rpc_url: &str, to: H160, data: &[u8]) -> Result<Vec<u8>, WalletError> {
+        let params = json!([{ "to": format!("{:#x}", to), "data": format!("0x{}", hex::encode(data)) }, "latest"]);
+        let result = self.rpc(rpc_url, "eth_call", params).await?;
+        let output = result.as_str()
+            .ok_or_else(|| WalletError::network("Missing eth_call result"))?;
+        hex::decode(output.trim_start_matches("0x"))
+            .map_err(|_| WalletError::network("Invalid eth_call result"))
+    }
+
+    async fn rpc(&self, rpc_url: &str, method: &str, params: serde_json::Value) -> Result<serde_json::Value, WalletError> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": 1
+        });
+        let resp = self.client.post(rpc_url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| WalletError::network(format!("Failed to call {}: {}", method, e)))?;
+        let mut resp_json: serde_json::Value = resp.json().await
+            .map_err(|e| WalletError::network(format!("Invalid response: {}", e)))?;
+        if let Some(error) = resp_json.get("error") {
+            return Err(WalletError::network(format!("{} failed: {}", method, error)));
+        }
+        resp_json.get_mut("result")
+            .map(serde_json::Value::take)
+            .ok_or_else(|| WalletError::network(format!("No result from {}", method)))
+    }
+}
+
+fn native_token(network: &Network) -> TokenInfo {
+    TokenInfo {
+        symbol: network.native_currency().to_string(),
+        name: network.native_currency().to_string(),
+        decimals: 18,
+        address: format!("{:#x}", H160::zero()),
+        chain_id: network.chain_id().to_string(),
+        is_native: true,
+        is_stablecoin: false,
+    }
+}
+
+/// `getEthBalance` on Multicall3 for the native balance, then `balanceOf`
+/// on each token contract
+fn balance_calls(owner: H160, multicall_address: &str, tokens: &[TokenInfo]) -> Result<Vec<(H160, Vec<u8>)>, WalletError> {
+    let call = |selector: [u8; 4]| {
+        let mut data = selector.to_vec();
+        data.extend(abi::encode(&[Token::Address(owner)]));
+        data
+    };
+    let mut calls = vec![(parse_address(multicall_address)?, call(GET_ETH_BALANCE_SELECTOR))];
+    for token in tokens {
+        calls.push((parse_address(&token.address)?, call(BALANCE_OF_SELECTOR)));
+    }
+    Ok(calls)
+}
+
+/// The `(bool success, bytes returnData)[]` of `aggregate3`, as amounts
+fn decode_aggregate3(output: &[u8], expected: usize) -> Result<Vec<U256>, WalletError> {
+    let result_type = ParamType::Array(Box::new(ParamType::Tuple(vec![ParamType::Bool, ParamType::Bytes])));
+    let Some(Token::Array(results)) = abi::decode(&[result_type], output)
+        .map_err(|e| WalletError::network(format!("Invalid aggregate3 result: {}", e)))?
+        .into_iter()
+        .next()
+    else {
+        return Err(WalletError::network("Invalid aggregate3 result"));
+    };
+    if results.len() != expected {
+        return Err(WalletError::network(format!("aggregate3 returned {} results for {} calls", results.len(), expected)));
+    }
+    Ok(results.into_iter()
+        .map(|result| match result {
+            Token::Tuple(fields) => match fields.as_slice() {
+                [Token::Bool(true), Token::Bytes(data)] => decode_uint(data).unwrap_or_default(),
+                _ => U256::zero(),
+            },
+            _ => U256::zero(),
+        })
+        .collect())
+}
+
+fn decode_uint(data: &[u8]) -> Option<U256> {
+    (data.len() >= 32).then(|| U256::from_big_endian(&data[..32]))
+}
+
+fn parse_address(address: &str) -> Result<H160, WalletError> {
+    address.trim().parse::<H160>()
+        .map_err(|_| WalletError::validation(format!("Invalid address: {}", address)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate3_round_trip() {
+        let usdc = TokenInfo {
+            symbol: "USDC".to_string(),
+            name: "USD Coin".to_string(),
+            decimals: 6,
+            address: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".to_string(),
+            chain_id: "84532".to_string(),
+            is_native: false,
+            is_stablecoin: true,
+        };
+        let owner = parse_address("0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6").unwrap();
+        let calls = balance_calls(owner, MULTICALL3_ADDRESS, &[usdc.clone()]).unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].1[..4], GET_ETH_BALANCE_SELECTOR);
+        assert_eq!(calls[1].0, parse_address(&usdc.address).unwrap());
+        assert_eq!(calls[1].1[..4], BALANCE_OF_SELECTOR);
+
+        // Native balance succeeds, the token call fails and reads as zero
+        let uint = |v: u64| abi::encode(&[Token::Uint(U256::from(v))]);
+        let output = abi::encode(&[Token::Array(vec![
+            Token::Tuple(vec![Token::Bool(true), Token::Bytes(uint(5_000_000_000_000_000))]),
+            Token::Tuple(vec![Token::Bool(false), Token::Bytes(Vec::new())]),
+        ])]);
+        let amounts = decode_aggregate3(&output, 2).unwrap();
+        assert_eq!(amounts, vec![U256::from(5_000_000_000_000_000u64), U256::zero()]);
+        assert!(decode_aggregate3(&output, 3).is_err());
+
+        let service = BalanceService::new(vec![Network::BaseSepolia]).with_token(usdc).unwrap();
+        assert_eq!(service.tokens[&Network::BaseSepolia].len(), 1);
+    }
+}
@@ -0,0 +1,210 @@
+//! Offline fee tables
+//!
+//! Per-network fee estimates, refreshed from the chain while the wallet is
+//! online and cached in platform storage. While offline the cached entry is
+//! used, or a built-in table when nothing was ever cached, so transactions
+//! signed for later relay over BLE still carry fees that will mine.
+
+use crate::core::transactions::TransactionManager;
+use crate::infrastructure::platform::PlatformStorage;
+use crate::shared::error::WalletError;
+use crate::shared::types::{GasPrice, Network, TransactionType};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const FEE_TABLES_KEY: &str = "fee_tables";
+const GWEI: GasPrice = 1_000_000_000;
+
+/// Cached estimates older than this are refreshed when the chain is reachable
+pub const FEE_TABLE_MAX_AGE_SECS: i64 = 6 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FeeSource {
+    /// Shipped with the wallet
+    Builtin,
+    /// Fetched from the network's RPC
+    Network,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeEstimate {
+    pub chain_id: u64,
+    /// Gas price for legacy transactions
+    pub gas_price: GasPrice,
+    pub max_fee_per_gas: GasPrice,
+    pub max_priority_fee_per_gas: GasPrice,
+    pub source: FeeSource,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl FeeEstimate {
+    /// Fallback for a network that was never refreshed; errs on the high
+    /// side, since an offline transaction cannot be re-priced before relay
+    pub fn builtin(network: &Network) -> Self {
+        let (gas_price, priority_fee) = match network {
+            Network::CoreTestnet => (30 * GWEI, GWEI),
+            Network::BaseSepolia => (GWEI / 10, GWEI / 100),
+            Network::LiskSepolia => (GWEI / 10, GWEI / 100),
+            Network::EthereumHolesky => (20 * GWEI, 2 * GWEI),
+        };
+        Self {
+            chain_id: network.chain_id(),
+            gas_price,
+            max_fee_per_gas: gas_price,
+            max_priority_fee_per_gas: priority_fee,
+            source: FeeSource::Builtin,
+            updated_at: DateTime::<Utc>::UNIX_EPOCH,
+        }
+    }
+
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        Utc::now() - self.updated_at > max_age
+    }
+}
+
+/// Fee estimates by chain id, persisted in platform storage. Fees are not
+/// secret, so they are stored unencrypted.
+pub struct FeeTables<'a> {
+    storage: &'a dyn PlatformStorage,
+}
+
+impl<'a> FeeTables<'a> {
+    pub fn new(storage: &'a dyn PlatformStorage) -> Self {
+        Self { storage }
+    }
+
+    fn load(&self) -> Result<HashMap<u64, FeeEstimate>, WalletError> {
+        if !self.storage.exists(FEE_TABLES_KEY)? {
+            return Ok(HashMap::new());
+        }
+        Ok(serde_json::from_slice(&self.storage.retrieve(FEE_TABLES_KEY)?)?)
+    }
+
+    /// Cache an estimate, replacing the one for the same chain
+    pub fn update(&self, estimate: FeeEstimate) -> Result<(), WalletError> {
+        let mut tables = self.load()?;
+        tables.insert(estimate.chain_id, estimate);
+        self.storage.store(FEE_TABLES_KEY, &serde_json::to_vec(&tables)?)
+    }
+
+    /// The cached estimate for `network`, or its built-in one. Works offline.
+    pub fn estimate(&self, network: &Network) -> Result<FeeEstimate, WalletError> {
+        Ok(self.load()?
+            .remove(&network.chain_id())
+            .unwrap_or_else(|| FeeEstimate::builtin(network)))
+    }
+
+    /// Fetch current fees for `network` through `manager` and cache them
+    pub async fn refresh(&self, network: &Network, manager: &TransactionManager) -> Result<FeeEstimate, WalletError> {
+        let gas_price = manager.get_gas_price(network.clone()).await?;
+        let (max_fee_per_gas, max_priority_fee_per_gas) = match network.default_transaction_type() {
+            TransactionType::Eip1559 => manager.get_eip1559_fees().await?,
+            TransactionType::Legacy => (gas_price, gas_price.min(FeeEstimate::builtin(network).max_priority_fee_per_gas)),
+        };
+        let estimate = FeeEstimate {
+            chain_id: network.chain_id(),
+            gas_price,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            source: FeeSource::Network,
+            updated_at: Utc::now(),
+        };
+        self.update(estimate.clone())?;
+        Ok(estimate)
+    }
+
+    /// Refresh `network`'s estimate if it is older than `max_age`. When the
+    /// chain cannot be reached the cached or built-in estimate is returned.
+    pub async fn refresh_if_stale(
+        &self,
+        network: &Network,
+        manager: &TransactionManager,
+        max_age: Duration,
+    ) -> Result<FeeEstimate, WalletError> {
+        let current = self.estimate(network)?;
+        if !current.is_stale(max_age) {
+            return Ok(current);
+        }
+        match self.refresh(network, manager).await {
+            Ok(estimate) => Ok(estimate),
+            Err(e) => {
+                log::warn!("Keeping {:?} fee estimate for {}: {}", current.source, network.name(), e);
+                Ok(current)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::transactions::TransactionBuilder;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockStorage {
+        data: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    impl PlatformStorage for MockStorage {
+        fn store(&self, key: &str, data: &[u8]) -> Result<(), WalletError> {
+            self.data.lock().unwrap().insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        fn retrieve(&self, key: &str) -> Result<Vec<u8>, WalletError> {
+            self.data.lock().unwrap().get(key).cloned()
+                .ok_or_else(|| WalletError::storage("Key not found"))
+        }
+
+        fn delete(&self, key: &str) -> Result<(), WalletError> {
+            self.data.lock().unwrap().remove(key);
+            Ok(())
+        }
+
+        fn exists(&self, key: &str) -> Result<bool, WalletError> {
+            Ok(self.data.lock().unwrap().contains_key(key))
+        }
+
+        fn list_keys(&self) -> Result<Vec<String>, WalletError> {
+            Ok(self.data.lock().unwrap().keys().cloned().collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_offline_estimate_prefers_cache_and_prices_transactions() {
+        let storage = MockStorage::default();
+        let tables = FeeTables::new(&storage);
+        let max_age = Duration::seconds(FEE_TABLE_MAX_AGE_SECS);
+
+        let builtin = tables.estimate(&Network::BaseSepolia).unwrap();
+        assert_eq!(builtin.source, FeeSource::Builtin);
+        assert!(builtin.is_stale(max_age));
+
+        tables.update(FeeEstimate {
+            chain_id: 84532,
+            gas_price: 2 * GWEI,
+            max_fee_per_gas: 3 * GWEI,
+            max_priority_fee_per_gas: GWEI,
+            source: FeeSource::Network,
+            updated_at: Utc::now(),
+        }).unwrap();
+        let cached = tables.estimate(&Network::BaseSepolia).unwrap();
+        assert!(!cached.is_stale(max_age));
+        assert_eq!(tables.estimate(&Network::CoreTestnet).unwrap().source, FeeSource::Builtin);
+
+        // An unreachable RPC leaves the fresh cached entry in place
+        let manager = TransactionManager::new("http://127.0.0.1:9".to_string());
+        assert_eq!(tables.refresh_if_stale(&Network::BaseSepolia, &manager, max_age).await.unwrap(), cached);
+
+        let transaction = TransactionBuilder::new(Network::BaseSepolia)
+            .to("0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6")
+            .value("1")
+            .offline_fees(&cached)
+            .build()
+            .unwrap();
+        assert_eq!(transaction.max_fee_per_gas, Some(3 * GWEI));
+        assert_eq!(transaction.gas_price, None);
+    }
+}
@@ -0,0 +1,599 @@
+//! Transaction history
+//!
+//! Every transfer the wallet signs, sends or observes is kept encrypted in
+//! `SecureStorage` with its status. Locally signed transactions start as
+//! `Signed` or `Submitted`; `sync` asks a relay or an RPC node for their
+//! confirmations and records the block, the final status and, for
+//! transactions the wallet paid for, the fee. Entries can be queried by
+//! wallet, token and date range, and feed the accounting export.
+
+use super::templates::ERC20_TRANSFER_SELECTOR;
+use super::TransactionManager;
+use crate::core::storage::SecureStorage;
+use crate::infrastructure::platform::PlatformStorage;
+use crate::infrastructure::relay::RelayClient;
+use crate::shared::error::WalletError;
+use crate::shared::sources::{default_clock, Clock};
+use crate::shared::types::{Address, Amount, Network, SignedTransaction, TokenInfo, TransactionHash, TransactionStatus};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ethers::types::{H160, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+const HISTORY_KEY: &str = "transaction_history";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferDirection {
+    Incoming,
+    Outgoing,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryStatus {
+    /// Signed but not broadcast yet, e.g. waiting in the offline queue
+    Signed,
+    /// Handed to a relay or node and not mined yet
+    Submitted,
+    /// Mined successfully. Entries recorded before statuses were kept are
+    /// observed transfers, so this is the default.
+    #[default]
+    Confirmed,
+    /// Reverted on chain, or refused by the relay
+    Failed,
+    /// Dropped without being mined
+    Dropped,
+}
+
+impl HistoryStatus {
+    pub fn is_final(&self) -> bool {
+        !matches!(self, HistoryStatus::Signed | HistoryStatus::Submitted)
+    }
+}
+
+/// Network fee paid for a transfer, in the chain's native token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferFee {
+    pub token: TokenInfo,
+    pub amount: Amount,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub hash: TransactionHash,
+    pub chain_id: u64,
+    pub timestamp: DateTime<Utc>,
+    pub direction: TransferDirection,
+    pub token: TokenInfo,
+    /// Base units of `token`
+    pub amount: Amount,
+    pub counterparty: String,
+    /// Only set on outgoing transfers; incoming fees are paid by the sender
+    pub fee: Option<TransferFee>,
+    /// Wallet that signed or received the transfer, when known
+    #[serde(default)]
+    pub wallet_id: Option<String>,
+    #[serde(default)]
+    pub status: HistoryStatus,
+    #[serde(default)]
+    pub block_number: Option<u64>,
+    /// Id the relay gave the transaction on submission, for `sync`
+    #[serde(default)]
+    pub relay_transaction_id: Option<String>,
+}
+
+impl HistoryEntry {
+    /// An outgoing entry for a transaction `wallet_id` signed. `token` is
+    /// the asset moved: the native token for a value transfer, or the ERC-20
+    /// whose `transfer` the transaction calls.
+    pub fn outgoing(
+        wallet_id: &str,
+        signed: &SignedTransaction,
+        token: TokenInfo,
+        status: HistoryStatus,
+        timestamp: DateTime<Utc>,
+    ) -> Result<Self, WalletError> {
+        let tx = &signed.transaction;
+        let (amount, counterparty) = if token.is_native {
+            (tx.value.clone(), tx.to.clone())
+        } else {
+            if !tx.to.eq_ignore_ascii_case(&token.address) {
+                return Err(WalletError::validation(format!("Transaction calls {}, not token {}", tx.to, token.symbol)));
+            }
+            decode_transfer(tx.data.as_deref().unwrap_or_default())?
+        };
+        parse_units(&amount)?;
+        Ok(Self {
+            hash: signed.hash.clone(),
+            chain_id: tx.chain_id,
+            timestamp,
+            direction: TransferDirection::Outgoing,
+            token,
+            amount,
+            counterparty,
+            fee: None,
+            wallet_id: Some(wallet_id.to_string()),
+            status,
+            block_number: None,
+            relay_transaction_id: None,
+        })
+    }
+
+    pub(crate) fn same_transfer(&self, other: &HistoryEntry) -> bool {
+        self.chain_id == other.chain_id
+            && self.hash.eq_ignore_ascii_case(&other.hash)
+            && self.direction == other.direction
+            && token_key(self.chain_id, &self.token) == token_key(other.chain_id, &other.token)
+    }
+
+    /// Apply what a relay or node reported; returns whether anything changed
+    fn apply(&mut self, confirmation: Confirmation) -> bool {
+        let before = (self.status, self.block_number, self.hash.clone(), self.fee.is_some());
+        if confirmation.status != HistoryStatus::Signed {
+            self.status = confirmation.status;
+        }
+        if confirmation.block_number.is_some() {
+            self.block_number = confirmation.block_number;
+        }
+        if let Some(hash) = confirmation.hash.filter(|_| self.hash.is_empty()) {
+            self.hash = hash;
+        }
+        if self.fee.is_none() && self.direction == TransferDirection::Outgoing {
+            self.fee = confirmation.fee;
+        }
+        before != (self.status, self.block_number, self.hash.clone(), self.fee.is_some())
+    }
+}
+
+/// Amount and recipient of an ERC-20 `transfer(address,uint256)` call
+pub(crate) fn decode_transfer(data: &[u8]) -> Result<(Amount, Address), WalletError> {
+    if data.len() != 68 || data[..4] != ERC20_TRANSFER_SELECTOR {
+        return Err(WalletError::validation("Transaction is not an ERC-20 transfer"));
+    }
+    let recipient = H160::from_slice(&data[16..36]);
+    let amount = U256::from_big_endian(&data[36..68]);
+    Ok((amount.to_string(), format!("{:#x}", recipient)))
+}
+
+pub(crate) fn token_key(chain_id: u64, token: &TokenInfo) -> String {
+    if token.is_native || token.address.is_empty() {
+        format!("{}:native", chain_id)
+    } else {
+        format!("{}:{}", chain_id, token.address.to_lowercase())
+    }
+}
+
+pub(crate) fn parse_units(amount: &str) -> Result<U256, WalletError> {
+    U256::from_dec_str(amount.trim()).map_err(|_| WalletError::validation(format!("Invalid amount {}", amount)))
+}
+
+/// The native token of a chain, in which fees are paid
+fn native_token(chain_id: u64) -> TokenInfo {
+    let symbol = Network::from_chain_id(chain_id).map_or("ETH", |network| network.native_currency());
+    TokenInfo {
+        symbol: symbol.to_string(),
+        name: symbol.to_string(),
+        decimals: 18,
+        address: String::new(),
+        chain_id: chain_id.to_string(),
+        is_native: true,
+        is_stablecoin: false,
+    }
+}
+
+/// Filter for `TransactionHistory::query`; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    pub wallet_id: Option<String>,
+    pub chain_id: Option<u64>,
+    /// Token contract address, or symbol
+    pub token: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    /// End of the range, exclusive
+    pub until: Option<DateTime<Utc>>,
+    pub status: Option<HistoryStatus>,
+}
+
+impl HistoryQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn wallet(mut self, wallet_id: impl Into<String>) -> Self {
+        self.wallet_id = Some(wallet_id.into());
+        self
+    }
+
+    pub fn chain(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub fn between(mut self, from: DateTime<Utc>, until: DateTime<Utc>) -> Self {
+        self.from = Some(from);
+        self.until = Some(until);
+        self
+    }
+
+    pub fn status(mut self, status: HistoryStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        self.wallet_id.as_ref().is_none_or(|id| entry.wallet_id.as_ref() == Some(id))
+            && self.chain_id.is_none_or(|chain_id| entry.chain_id == chain_id)
+            && self.token.as_ref().is_none_or(|token| {
+                (!entry.token.address.is_empty() && token.eq_ignore_ascii_case(&entry.token.address))
+                    || token.eq_ignore_ascii_case(&entry.token.symbol)
+            })
+            && self.from.is_none_or(|from| entry.timestamp >= from)
+            && self.until.is_none_or(|until| entry.timestamp < until)
+            && self.status.is_none_or(|status| entry.status == status)
+    }
+}
+
+/// What a relay or node reports about a transaction
+#[derive(Debug, Clone)]
+pub struct Confirmation {
+    pub status: HistoryStatus,
+    pub block_number: Option<u64>,
+    /// Hash of a transaction the relay broadcast, once known
+    pub hash: Option<TransactionHash>,
+    pub fee: Option<TransferFee>,
+}
+
+/// Where `sync` learns whether transactions were mined
+#[async_trait]
+pub trait ConfirmationSource: Send + Sync {
+    /// Current state of `entry`, or None when this source has nothing to
+    /// report on it
+    async fn confirmation(&self, entry: &HistoryEntry) -> Result<Option<Confirmation>, WalletError>;
+}
+
+/// Asks the relay about transactions submitted through it
+#[async_trait]
+impl ConfirmationSource for RelayClient {
+    async fn confirmation(&self, entry: &HistoryEntry) -> Result<Option<Confirmation>, WalletError> {
+        let Some(transaction_id) = &entry.relay_transaction_id else {
+            return Ok(None);
+        };
+        let status = self.status(transaction_id).await?;
+        let history_status = match status.status.as_str() {
+            "completed" => HistoryStatus::Confirmed,
+            "dropped" => HistoryStatus::Dropped,
+            "failed" | "shed" | "queue_failed" => HistoryStatus::Failed,
+            _ => HistoryStatus::Submitted,
+        };
+        Ok(Some(Confirmation {
+            status: history_status,
+            block_number: status.block_number,
+            hash: status.transaction_hash,
+            fee: None,
+        }))
+    }
+}
+
+/// Reads receipts from an RPC node per chain
+pub struct RpcConfirmations {
+    nodes: HashMap<u64, TransactionManager>,
+}
+
+impl RpcConfirmations {
+    /// Nodes for every supported network that has a default RPC URL
+    pub fn new() -> Self {
+        let nodes = [Network::CoreTestnet, Network::BaseSepolia, Network::LiskSepolia, Network::EthereumHolesky]
+            .into_iter()
+            .filter(|network| !network.rpc_url().is_empty())
+            .map(|network| (network.chain_id(), TransactionManager::new(network.rpc_url().to_string())))
+            .collect();
+        Self { nodes }
+    }
+
+    /// Read `chain_id` receipts from `rpc_url`
+    pub fn with_rpc_url(mut self, chain_id: u64, rpc_url: impl Into<String>) -> Self {
+        self.nodes.insert(chain_id, TransactionManager::new(rpc_url.into()));
+        self
+    }
+}
+
+impl Default for RpcConfirmations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ConfirmationSource for RpcConfirmations {
+    async fn confirmation(&self, entry: &HistoryEntry) -> Result<Option<Confirmation>, WalletError> {
+        let Some(node) = self.nodes.get(&entry.chain_id) else {
+            return Ok(None);
+        };
+        if entry.hash.is_empty() {
+            return Ok(None);
+        }
+        let Some(receipt) = node.get_transaction_receipt(&entry.hash, entry.chain_id).await? else {
+            return Ok(None);
+        };
+        let fee = receipt.gas_used.zip(receipt.effective_gas_price).map(|(gas_used, gas_price)| TransferFee {
+            token: native_token(entry.chain_id),
+            amount: (U256::from(gas_used) * U256::from(gas_price)).to_string(),
+        });
+        Ok(Some(Confirmation {
+            status: match receipt.status {
+                TransactionStatus::Confirmed => HistoryStatus::Confirmed,
+                TransactionStatus::Failed => HistoryStatus::Failed,
+                TransactionStatus::Pending => HistoryStatus::Submitted,
+            },
+            block_number: receipt.block_number,
+            hash: None,
+            fee,
+        }))
+    }
+}
+
+/// Outcome of one `sync`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncReport {
+    /// Transactions that were not final before the sync
+    pub checked: usize,
+    pub updated: usize,
+    /// Transactions still signed or submitted afterwards
+    pub pending: usize,
+    pub errors: Vec<String>,
+}
+
+/// Encrypted log of the transfers of every wallet on the device
+pub struct TransactionHistory<'a> {
+    platform: &'a dyn PlatformStorage,
+    storage: SecureStorage<'a>,
+    password: Zeroizing<String>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<'a> TransactionHistory<'a> {
+    pub fn new(storage: &'a dyn PlatformStorage, password: &str) -> Self {
+        Self {
+            platform: storage,
+            storage: SecureStorage::new(storage),
+            password: Zeroizing::new(password.to_string()),
+            clock: default_clock(),
+        }
+    }
+
+    /// Timestamp recorded transactions with `clock` instead of the system time
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub async fn entries(&self) -> Result<Vec<HistoryEntry>, WalletError> {
+        if !self.platform.exists(HISTORY_KEY)? {
+            return Ok(Vec::new());
+        }
+        let bytes = self.storage.retrieve_data(HISTORY_KEY, &self.password).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    async fn save(&self, entries: &[HistoryEntry]) -> Result<(), WalletError> {
+        self.storage.store_data(HISTORY_KEY, &serde_json::to_vec(entries)?, &self.password).await
+    }
+
+    /// Add a transfer, replacing an earlier record of the same transfer.
+    /// Returns false if it was already recorded.
+    pub async fn record(&self, entry: HistoryEntry) -> Result<bool, WalletError> {
+        parse_units(&entry.amount)?;
+        let mut entries = self.entries().await?;
+        let added = match entries.iter_mut().find(|e| e.same_transfer(&entry)) {
+            Some(existing) => {
+                *existing = entry;
+                false
+            }
+            None => {
+                entries.push(entry);
+                true
+            }
+        };
+        self.save(&entries).await?;
+        Ok(added)
+    }
+
+    /// Record a transaction `wallet_id` signed (`Signed`) or already sent
+    /// (`Submitted`)
+    pub async fn record_sent(
+        &self,
+        wallet_id: &str,
+        signed: &SignedTransaction,
+        token: TokenInfo,
+        status: HistoryStatus,
+    ) -> Result<HistoryEntry, WalletError> {
+        let entry = HistoryEntry::outgoing(wallet_id, signed, token, status, self.clock.now())?;
+        self.record(entry.clone()).await?;
+        Ok(entry)
+    }
+
+    /// Mark a signed transaction as broadcast, noting the relay's id for it
+    /// when it went through a relay
+    pub async fn mark_submitted(&self, chain_id: u64, hash: &str, relay_transaction_id: Option<&str>) -> Result<(), WalletError> {
+        let mut entries = self.entries().await?;
+        let mut found = false;
+        for entry in entries.iter_mut().filter(|e| e.chain_id == chain_id && e.hash.eq_ignore_ascii_case(hash)) {
+            found = true;
+            if entry.status == HistoryStatus::Signed {
+                entry.status = HistoryStatus::Submitted;
+            }
+            if let Some(id) = relay_transaction_id {
+                entry.relay_transaction_id = Some(id.to_string());
+            }
+        }
+        if !found {
+            return Err(WalletError::transaction(format!("Transaction {} is not in the history", hash)));
+        }
+        self.save(&entries).await
+    }
+
+    /// Entries matching `query`, newest first
+    pub async fn query(&self, query: &HistoryQuery) -> Result<Vec<HistoryEntry>, WalletError> {
+        let mut entries: Vec<HistoryEntry> = self.entries().await?.into_iter().filter(|e| query.matches(e)).collect();
+        entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then_with(|| a.hash.cmp(&b.hash)));
+        Ok(entries)
+    }
+
+    /// Ask `source` about every transaction that is not final and record
+    /// what it reports. Errors for single transactions are collected in the
+    /// report; they stay pending for the next sync.
+    pub async fn sync(&self, source: &dyn ConfirmationSource) -> Result<SyncReport, WalletError> {
+        let mut entries = self.entries().await?;
+        let mut report = SyncReport::default();
+        for entry in entries.iter_mut().filter(|e| !e.status.is_final()) {
+            report.checked += 1;
+            match source.confirmation(entry).await {
+                Ok(Some(confirmation)) => {
+                    if entry.apply(confirmation) {
+                        report.updated += 1;
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => report.errors.push(format!("{}: {}", entry.hash, e)),
+            }
+        }
+        if report.updated > 0 {
+            self.save(&entries).await?;
+        }
+        report.pending = entries.iter().filter(|e| !e.status.is_final()).count();
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::sources::FixedClock;
+    use crate::shared::types::Transaction;
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+
+    struct MockStorage {
+        data: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    impl PlatformStorage for MockStorage {
+        fn store(&self, key: &str, data: &[u8]) -> Result<(), WalletError> {
+            self.data.lock().unwrap().insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        fn retrieve(&self, key: &str) -> Result<Vec<u8>, WalletError> {
+            self.data.lock().unwrap().get(key).cloned()
+                .ok_or_else(|| WalletError::storage("Key not found"))
+        }
+
+        fn delete(&self, key: &str) -> Result<(), WalletError> {
+            self.data.lock().unwrap().remove(key);
+            Ok(())
+        }
+
+        fn exists(&self, key: &str) -> Result<bool, WalletError> {
+            Ok(self.data.lock().unwrap().contains_key(key))
+        }
+
+        fn list_keys(&self) -> Result<Vec<String>, WalletError> {
+            Ok(self.data.lock().unwrap().keys().cloned().collect())
+        }
+    }
+
+    /// Reports every submitted transaction mined in block 7 with a fee
+    struct MinedEverything;
+
+    #[async_trait]
+    impl ConfirmationSource for MinedEverything {
+        async fn confirmation(&self, entry: &HistoryEntry) -> Result<Option<Confirmation>, WalletError> {
+            if entry.status != HistoryStatus::Submitted {
+                return Ok(None);
+            }
+            Ok(Some(Confirmation {
+                status: HistoryStatus::Confirmed,
+                block_number: Some(7),
+                hash: None,
+                fee: Some(TransferFee { token: native_token(entry.chain_id), amount: "21000000000000".to_string() }),
+            }))
+        }
+    }
+
+    fn signed(hash: &str, to: &str, value: &str, data: Option<Vec<u8>>) -> SignedTransaction {
+        SignedTransaction {
+            transaction: Transaction {
+                to: to.to_string(),
+                value: value.to_string(),
+                data,
+                gas_limit: Some(60_000),
+                gas_price: Some(1_000_000_000),
+                nonce: Some(0),
+                chain_id: 84532,
+                tx_type: Default::default(),
+                max_fee_per_gas: None,
+                max_priority_fee_per_gas: None,
+            },
+            signature: vec![1, 2, 3],
+            hash: hash.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_records_sent_transactions_and_syncs_confirmations() {
+        let storage = MockStorage { data: Mutex::new(HashMap::new()) };
+        let clock = Arc::new(FixedClock::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap()));
+        let history = TransactionHistory::new(&storage, "history-password").with_clock(clock);
+
+        let usdc = TokenInfo {
+            symbol: "USDC".to_string(),
+            name: "USD Coin".to_string(),
+            decimals: 6,
+            address: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".to_string(),
+            chain_id: "84532".to_string(),
+            is_native: false,
+            is_stablecoin: true,
+        };
+        let mut transfer = ERC20_TRANSFER_SELECTOR.to_vec();
+        transfer.extend_from_slice(&[0u8; 12]);
+        transfer.extend_from_slice(&[0x11; 20]);
+        let mut amount = [0u8; 32];
+        U256::from(2_500_000u64).to_big_endian(&mut amount);
+        transfer.extend_from_slice(&amount);
+        let token_tx = signed("0xaa", &usdc.address, "0", Some(transfer));
+        let eth_tx = signed("0xbb", "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6", "1000", None);
+
+        let entry = history.record_sent("wallet-1", &token_tx, usdc.clone(), HistoryStatus::Signed).await.unwrap();
+        assert_eq!(entry.amount, "2500000");
+        assert_eq!(entry.counterparty, format!("0x{}", "11".repeat(20)));
+        history.record_sent("wallet-2", &eth_tx, native_token(84532), HistoryStatus::Submitted).await.unwrap();
+        assert!(history.record_sent("wallet-1", &eth_tx, usdc, HistoryStatus::Signed).await.is_err());
+
+        // Only submitted transactions are mined; the signed one waits
+        let report = history.sync(&MinedEverything).await.unwrap();
+        assert_eq!((report.checked, report.updated, report.pending), (2, 1, 1));
+        history.mark_submitted(84532, "0xAA", Some("relay-tx-1")).await.unwrap();
+        let report = history.sync(&MinedEverything).await.unwrap();
+        assert_eq!((report.checked, report.updated, report.pending), (1, 1, 0));
+
+        let wallet_one = history.query(&HistoryQuery::new().wallet("wallet-1").token("usdc")).await.unwrap();
+        let [mined] = &wallet_one[..] else { panic!("expected one USDC entry") };
+        assert_eq!(mined.status, HistoryStatus::Confirmed);
+        assert_eq!(mined.block_number, Some(7));
+        assert_eq!(mined.relay_transaction_id.as_deref(), Some("relay-tx-1"));
+        assert_eq!(mined.fee.as_ref().unwrap().amount, "21000000000000");
+
+        let day = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        assert_eq!(history.query(&HistoryQuery::new().between(day, day + chrono::Duration::days(1))).await.unwrap().len(), 2);
+        assert!(history.query(&HistoryQuery::new().between(day + chrono::Duration::seconds(1), day + chrono::Duration::days(1))).await.unwrap().is_empty());
+    }
+}
@@ -3,11 +3,152 @@
 //! This module contains transaction creation, signing, and management.
 
 use crate::shared::error::WalletError;
-use crate::shared::types::{Transaction, SignedTransaction, TransactionHash, TransactionStatus, Network, Amount};
+use crate::shared::types::{Transaction, SignedTransaction, TransactionHash, TransactionReceipt, TransactionStatus, TransactionType, Network, Amount, GasLimit, GasPrice};
 use crate::core::crypto::signatures::SignatureManager;
+use crate::core::crypto::signing::SigningBackend;
 use reqwest::Client;
 use serde_json::json;
 
+pub mod accounting;
+pub mod balances;
+pub mod history;
+pub mod nonce_manager;
+pub mod offline_queue;
+pub mod fee_tables;
+pub mod payment_session;
+pub mod templates;
+
+pub use accounting::{AccountingReport, AccountingRow, Fiat, PriceSource};
+pub use balances::{BalanceService, NetworkBalances};
+pub use history::{Confirmation, ConfirmationSource, HistoryEntry, HistoryQuery, HistoryStatus, RpcConfirmations, SyncReport, TransactionHistory, TransferDirection, TransferFee};
+pub use nonce_manager::{NonceManager, NonceState, ReconcileReport};
+pub use offline_queue::{OfflineQueue, QueuedTransaction, QueuedStatus, FlushReport};
+pub use fee_tables::{FeeTables, FeeEstimate, FeeSource};
+pub use payment_session::{PaymentChannel, PaymentEvent, PaymentQuote, PaymentSession, PaymentSessionStore, PaymentStage, PaymentStep, PaymentTimeouts};
+pub use templates::{AmountFormula, TemplateInput, TemplateRegistry, TransactionTemplate};
+
+/// Builds a transaction for a network, defaulting to the network's
+/// preferred transaction type
+#[derive(Debug, Clone)]
+pub struct TransactionBuilder {
+    network: Network,
+    tx_type: TransactionType,
+    to: String,
+    value: Amount,
+    data: Option<Vec<u8>>,
+    gas_limit: Option<GasLimit>,
+    nonce: Option<u64>,
+    gas_price: Option<GasPrice>,
+    max_fee_per_gas: Option<GasPrice>,
+    max_priority_fee_per_gas: Option<GasPrice>,
+}
+
+impl TransactionBuilder {
+    pub fn new(network: Network) -> Self {
+        Self {
+            tx_type: network.default_transaction_type(),
+            network,
+            to: String::new(),
+            value: "0".to_string(),
+            data: None,
+            gas_limit: None,
+            nonce: None,
+            gas_price: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+        }
+    }
+
+    pub fn transaction_type(mut self, tx_type: TransactionType) -> Self {
+        self.tx_type = tx_type;
+        self
+    }
+
+    pub fn to(mut self, to: impl Into<String>) -> Self {
+        self.to = to.into();
+        self
+    }
+
+    pub fn value(mut self, value: impl Into<Amount>) -> Self {
+        self.value = value.into();
+        self
+    }
+
+    pub fn data(mut self, data: Vec<u8>) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    pub fn gas_limit(mut self, gas_limit: GasLimit) -> Self {
+        self.gas_limit = Some(gas_limit);
+        self
+    }
+
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Gas price for legacy transactions
+    pub fn gas_price(mut self, gas_price: GasPrice) -> Self {
+        self.gas_price = Some(gas_price);
+        self
+    }
+
+    /// Fee caps for EIP-1559 transactions
+    pub fn fees(mut self, max_fee_per_gas: GasPrice, max_priority_fee_per_gas: GasPrice) -> Self {
+        self.max_fee_per_gas = Some(max_fee_per_gas);
+        self.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+        self
+    }
+
+    /// Fee fields for the builder's transaction type from a fee table
+    /// estimate, for signing without a network connection
+    pub fn offline_fees(self, estimate: &FeeEstimate) -> Self {
+        match self.tx_type {
+            TransactionType::Legacy => self.gas_price(estimate.gas_price),
+            TransactionType::Eip1559 => self.fees(estimate.max_fee_per_gas, estimate.max_priority_fee_per_gas),
+        }
+    }
+
+    pub fn build(self) -> Result<Transaction, WalletError> {
+        if self.to.is_empty() {
+            return Err(WalletError::validation("Recipient address cannot be empty"));
+        }
+        if self.value.is_empty() {
+            return Err(WalletError::validation("Transaction value cannot be empty"));
+        }
+        match self.tx_type {
+            TransactionType::Legacy if self.max_fee_per_gas.is_some() => {
+                return Err(WalletError::validation("Legacy transactions take a gas price, not EIP-1559 fees"));
+            }
+            TransactionType::Eip1559 if self.gas_price.is_some() => {
+                return Err(WalletError::validation("EIP-1559 transactions take fee caps, not a gas price"));
+            }
+            TransactionType::Eip1559 => {
+                if let (Some(max_fee), Some(priority_fee)) = (self.max_fee_per_gas, self.max_priority_fee_per_gas) {
+                    if priority_fee > max_fee {
+                        return Err(WalletError::validation("Max priority fee per gas exceeds max fee per gas"));
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(Transaction {
+            to: self.to,
+            value: self.value,
+            data: self.data,
+            gas_limit: self.gas_limit,
+            gas_price: self.gas_price,
+            nonce: self.nonce,
+            chain_id: self.network.chain_id(),
+            tx_type: self.tx_type,
+            max_fee_per_gas: self.max_fee_per_gas,
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+        })
+    }
+}
+
 /// Transaction manager for handling blockchain transactions
 pub struct TransactionManager {
     signature_manager: SignatureManager,
@@ -33,21 +174,14 @@ impl TransactionManager {
         value: Amount,
         network: Network,
     ) -> Result<Transaction, WalletError> {
-        if to.is_empty() {
-            return Err(WalletError::validation("Recipient address cannot be empty"));
-        }
-        if value.is_empty() {
-            return Err(WalletError::validation("Transaction value cannot be empty"));
-        }
-        Ok(Transaction {
-            to,
-            value,
-            data: None,
-            gas_limit: None,
-            gas_price: None,
-            nonce: None,
-            chain_id: network.chain_id(),
-        })
+        TransactionBuilder::new(network)
+            .to(to)
+            .value(value)
+            .build()
+    }
+
+    pub fn builder(&self, network: Network) -> TransactionBuilder {
+        TransactionBuilder::new(network)
     }
 
     pub async fn sign_transaction(
@@ -60,18 +194,21 @@ impl TransactionManager {
             return Err(WalletError::crypto("Private key ID cannot be empty"));
         }
 
-        // Require nonce, gas price, gas limit for raw signing
-        if transaction.nonce.is_none() || transaction.gas_price.is_none() || transaction.gas_limit.is_none() {
-            return Err(WalletError::validation("Transaction requires nonce, gas_price, and gas_limit"));
-        }
-
         // Create a SecurePrivateKey reference (does not load key into memory)
         let private_key = crate::core::crypto::keys::SecurePrivateKey::new(private_key_id.to_string());
+        private_key.with_key(storage, |key_bytes| self.sign_with_key_bytes(transaction, key_bytes))
+    }
 
-        // Perform EIP-155 legacy signing and get raw tx bytes and hash
-        let (raw_tx, tx_hash) = private_key.with_key(storage, |key_bytes| {
-            self.signature_manager.sign_legacy_raw(transaction, key_bytes)
-        })?;
+    /// Sign with a key the caller already holds in memory, such as the
+    /// WASM wallet's
+    pub fn sign_with_key_bytes(&self, transaction: &Transaction, key_bytes: &[u8]) -> Result<SignedTransaction, WalletError> {
+        Self::check_signable(transaction)?;
+
+        // Sign as EIP-155 legacy or type-2 and get raw tx bytes and hash
+        let (raw_tx, tx_hash) = match transaction.tx_type {
+            TransactionType::Legacy => self.signature_manager.sign_legacy_raw(transaction, key_bytes)?,
+            TransactionType::Eip1559 => self.signature_manager.sign_eip1559_raw(transaction, key_bytes)?,
+        };
 
         Ok(SignedTransaction {
             transaction: transaction.clone(),
@@ -80,6 +217,45 @@ impl TransactionManager {
         })
     }
 
+    /// Require nonce, gas limit and the fee fields for the transaction type
+    fn check_signable(transaction: &Transaction) -> Result<(), WalletError> {
+        if transaction.nonce.is_none() || transaction.gas_limit.is_none() {
+            return Err(WalletError::validation("Transaction requires nonce and gas_limit"));
+        }
+        match transaction.tx_type {
+            TransactionType::Legacy if transaction.gas_price.is_none() => {
+                Err(WalletError::validation("Legacy transaction requires gas_price"))
+            }
+            TransactionType::Eip1559 if transaction.max_fee_per_gas.is_none() || transaction.max_priority_fee_per_gas.is_none() => {
+                Err(WalletError::validation("EIP-1559 transaction requires max_fee_per_gas and max_priority_fee_per_gas"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Sign through an external signer, such as a hardware wallet, that
+    /// never exposes its key
+    pub async fn sign_with_backend(
+        &self,
+        transaction: &Transaction,
+        backend: &dyn SigningBackend,
+    ) -> Result<SignedTransaction, WalletError> {
+        Self::check_signable(transaction)?;
+        let payload = self.signature_manager.unsigned_payload(transaction)?;
+        let address = backend.address().await?;
+        log::info!("Signing transaction with {} signer {}", backend.name(), address);
+
+        let signature = backend.sign_transaction_payload(&payload).await?;
+        let parity = self.signature_manager.recovery_parity(&payload, &signature.r, &signature.s, &address)?;
+        let (raw_tx, tx_hash) = self.signature_manager.assemble_signed(transaction, parity, &signature.r, &signature.s)?;
+
+        Ok(SignedTransaction {
+            transaction: transaction.clone(),
+            signature: raw_tx,
+            hash: tx_hash,
+        })
+    }
+
     pub async fn send_transaction(&self, signed_transaction: &SignedTransaction) -> Result<TransactionHash, WalletError> {
         let client = Client::new();
         let tx_hex = format!("0x{}", hex::encode(&signed_transaction.signature));
@@ -131,6 +307,52 @@ impl TransactionManager {
         }
     }
 
+    /// Receipt of a mined transaction on `chain_id`, or None while it is
+    /// pending or unknown to the node
+    pub async fn get_transaction_receipt(
+        &self,
+        transaction_hash: &TransactionHash,
+        chain_id: u64,
+    ) -> Result<Option<TransactionReceipt>, WalletError> {
+        if transaction_hash.is_empty() {
+            return Err(WalletError::validation("Transaction hash cannot be empty"));
+        }
+        let body = json!({
+            "jsonrpc": "2.0",
+            "method": "eth_getTransactionReceipt",
+            "params": [transaction_hash],
+            "id": 1
+        });
+        let resp = Client::new().post(&self.rpc_url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| WalletError::network(format!("Failed to get transaction receipt: {}", e)))?;
+        let resp_json: serde_json::Value = resp.json().await.map_err(|e| WalletError::network(format!("Invalid response: {}", e)))?;
+        if let Some(error) = resp_json.get("error") {
+            return Err(WalletError::network(format!("eth_getTransactionReceipt failed: {}", error)));
+        }
+        let receipt = match resp_json.get("result") {
+            Some(receipt) if !receipt.is_null() => receipt,
+            _ => return Ok(None),
+        };
+        let quantity = |field: &str| receipt.get(field)
+            .and_then(|v| v.as_str())
+            .and_then(|v| u64::from_str_radix(v.trim_start_matches("0x"), 16).ok());
+        let status = match quantity("status") {
+            Some(0) => TransactionStatus::Failed,
+            _ => TransactionStatus::Confirmed,
+        };
+        Ok(Some(TransactionReceipt {
+            hash: transaction_hash.clone(),
+            status,
+            block_number: quantity("blockNumber"),
+            gas_used: quantity("gasUsed"),
+            effective_gas_price: quantity("effectiveGasPrice"),
+            chain_id,
+        }))
+    }
+
     pub async fn estimate_gas(&self, to_address: &str, amount: u64) -> Result<u64, WalletError> {
         let client = Client::new();
         let params = json!([{ "to": to_address, "value": format!("0x{:x}", amount) }]);
@@ -154,6 +376,65 @@ impl TransactionManager {
         }
     }
 
+    /// Suggested `(max_fee_per_gas, max_priority_fee_per_gas)`: the node's
+    /// priority fee on top of twice the latest base fee, which stays valid
+    /// through several full blocks
+    pub async fn get_eip1559_fees(&self) -> Result<(GasPrice, GasPrice), WalletError> {
+        let client = Client::new();
+        let rpc = |method: &str, params: serde_json::Value| json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": 1
+        });
+        let parse_hex = |value: Option<&serde_json::Value>, what: &str| -> Result<u64, WalletError> {
+            let hex = value.and_then(|v| v.as_str())
+                .ok_or_else(|| WalletError::network(format!("No {} returned", what)))?;
+            u64::from_str_radix(hex.trim_start_matches("0x"), 16)
+                .map_err(|_| WalletError::network(format!("Invalid {}", what)))
+        };
+
+        let resp = client.post(&self.rpc_url)
+            .json(&rpc("eth_getBlockByNumber", json!(["latest", false])))
+            .send()
+            .await
+            .map_err(|e| WalletError::network(format!("Failed to get latest block: {}", e)))?;
+        let block: serde_json::Value = resp.json().await.map_err(|e| WalletError::network(format!("Invalid response: {}", e)))?;
+        let base_fee = parse_hex(block.get("result").and_then(|b| b.get("baseFeePerGas")), "base fee")?;
+
+        let resp = client.post(&self.rpc_url)
+            .json(&rpc("eth_maxPriorityFeePerGas", json!([])))
+            .send()
+            .await
+            .map_err(|e| WalletError::network(format!("Failed to get priority fee: {}", e)))?;
+        let tip: serde_json::Value = resp.json().await.map_err(|e| WalletError::network(format!("Invalid response: {}", e)))?;
+        let priority_fee = parse_hex(tip.get("result"), "priority fee")?;
+
+        Ok((base_fee.saturating_mul(2).saturating_add(priority_fee), priority_fee))
+    }
+
+    /// Pending transaction count of `address`, i.e. the next nonce the
+    /// network expects from it
+    pub async fn get_transaction_count(&self, address: &str) -> Result<u64, WalletError> {
+        let client = Client::new();
+        let body = json!({
+            "jsonrpc": "2.0",
+            "method": "eth_getTransactionCount",
+            "params": [address, "pending"],
+            "id": 1
+        });
+        let resp = client.post(&self.rpc_url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| WalletError::network(format!("Failed to get transaction count: {}", e)))?;
+        let resp_json: serde_json::Value = resp.json().await.map_err(|e| WalletError::network(format!("Invalid response: {}", e)))?;
+        let count = resp_json.get("result").and_then(|v| v.as_str())
+            .ok_or_else(|| WalletError::network("No transaction count returned".to_string()))?;
+        u64::from_str_radix(count.trim_start_matches("0x"), 16)
+            .map_err(|_| WalletError::network("Invalid transaction count".to_string()))
+    }
+
     pub async fn get_gas_price(&self, _network: Network) -> Result<u64, WalletError> {
         let client = Client::new();
         let body = json!({
@@ -223,5 +504,34 @@ mod tests {
         assert_eq!(transaction.to, "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6");
         assert_eq!(transaction.value, "1000000000000000000");
         assert_eq!(transaction.chain_id, 1114);
+        assert_eq!(transaction.tx_type, TransactionType::Legacy);
+    }
+
+    #[test]
+    fn test_eip1559_transaction_signs_as_type_2() {
+        use ethers::types::transaction::eip2718::TypedTransaction;
+        use ethers::types::Address;
+        use std::str::FromStr;
+
+        let transaction = TransactionBuilder::new(Network::BaseSepolia)
+            .to("0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6")
+            .value("1000000000000000")
+            .nonce(7)
+            .gas_limit(21000)
+            .fees(3_000_000_000, 1_000_000_000)
+            .build()
+            .expect("Failed to build transaction");
+        assert_eq!(transaction.tx_type, TransactionType::Eip1559);
+
+        let key = hex::decode("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318").unwrap();
+        let (raw_tx, tx_hash) = SignatureManager::new().sign_eip1559_raw(&transaction, &key).unwrap();
+        assert_eq!(raw_tx[0], 0x02);
+
+        let rlp = ethers::utils::rlp::Rlp::new(&raw_tx);
+        let (decoded, signature) = TypedTransaction::decode_signed(&rlp).unwrap();
+        let signer = signature.recover(decoded.sighash()).unwrap();
+        assert_eq!(signer, Address::from_str("0x2c7536E3605D9C16a7a3D7b1898e529396a65c23").unwrap());
+        assert_eq!(decoded.nonce().unwrap().as_u64(), 7);
+        assert_eq!(tx_hash, format!("0x{}", hex::encode(ethers::utils::keccak256(&raw_tx))));
     }
 } 
\ No newline at end of file
@@ -0,0 +1,237 @@
+//! Nonce management
+//!
+//! Offline payments are signed before the network can be asked for a nonce,
+//! so the wallet hands nonces out itself. Each (wallet, chain) pair keeps
+//! the next usable nonce and the nonces reserved for transactions that are
+//! signed but not yet seen on chain. Reconciling with the network's pending
+//! transaction count clears reservations that were mined and moves the
+//! counter past transactions sent from other devices.
+
+use crate::core::storage::SecureStorage;
+use crate::core::transactions::TransactionManager;
+use crate::infrastructure::platform::PlatformStorage;
+use crate::shared::error::WalletError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use tokio::sync::Mutex;
+use zeroize::Zeroizing;
+
+const NONCES_KEY: &str = "nonce_manager";
+/// Counters kept by `OfflineQueue` before nonces had their own store
+const LEGACY_NONCES_KEY: &str = "offline_queue_nonces";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NonceState {
+    /// Lowest nonce not yet handed out
+    pub next: u64,
+    /// Handed out but not yet counted by the network
+    pub reserved: BTreeSet<u64>,
+    /// Pending transaction count at the last reconcile
+    pub network_nonce: Option<u64>,
+}
+
+impl NonceState {
+    /// Nonces below `next` that are neither reserved nor counted by the
+    /// network. A transaction at a later nonce cannot be mined until these
+    /// are filled.
+    pub fn gaps(&self) -> Vec<u64> {
+        let Some(network_nonce) = self.network_nonce else {
+            return Vec::new();
+        };
+        (network_nonce..self.next).filter(|n| !self.reserved.contains(n)).collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconcileReport {
+    pub network_nonce: u64,
+    /// Reservations the network has now counted
+    pub cleared: Vec<u64>,
+    pub next: u64,
+    pub gaps: Vec<u64>,
+}
+
+fn nonce_key(chain_id: u64, address: &str) -> String {
+    format!("{}:{}", chain_id, address.to_lowercase())
+}
+
+/// Tracks the next usable nonce per (wallet, chain)
+pub struct NonceManager<'a> {
+    platform: &'a dyn PlatformStorage,
+    storage: SecureStorage<'a>,
+    password: Zeroizing<String>,
+    /// Serialises read-modify-write cycles so concurrent reservations
+    /// within the process never get the same nonce
+    lock: Mutex<()>,
+}
+
+impl<'a> NonceManager<'a> {
+    pub fn new(storage: &'a dyn PlatformStorage, password: &str) -> Self {
+        Self {
+            platform: storage,
+            storage: SecureStorage::new(storage),
+            password: Zeroizing::new(password.to_string()),
+            lock: Mutex::new(()),
+        }
+    }
+
+    async fn load(&self) -> Result<HashMap<String, NonceState>, WalletError> {
+        if self.platform.exists(NONCES_KEY)? {
+            let bytes = self.storage.retrieve_data(NONCES_KEY, &self.password).await?;
+            return Ok(serde_json::from_slice(&bytes)?);
+        }
+        if self.platform.exists(LEGACY_NONCES_KEY)? {
+            let bytes = self.storage.retrieve_data(LEGACY_NONCES_KEY, &self.password).await?;
+            let legacy: HashMap<String, u64> = serde_json::from_slice(&bytes)?;
+            return Ok(legacy.into_iter()
+                .map(|(key, next)| (key, NonceState { next, ..Default::default() }))
+                .collect());
+        }
+        Ok(HashMap::new())
+    }
+
+    async fn save(&self, states: &HashMap<String, NonceState>) -> Result<(), WalletError> {
+        self.storage.store_data(NONCES_KEY, &serde_json::to_vec(states)?, &self.password).await
+    }
+
+    pub async fn state(&self, chain_id: u64, address: &str) -> Result<NonceState, WalletError> {
+        Ok(self.load().await?.remove(&nonce_key(chain_id, address)).unwrap_or_default())
+    }
+
+    /// Next nonce that would be handed out, without reserving it
+    pub async fn peek(&self, chain_id: u64, address: &str) -> Result<u64, WalletError> {
+        Ok(self.state(chain_id, address).await?.next)
+    }
+
+    /// Hand out the next nonce and hold it until the network counts it or
+    /// it is released
+    pub async fn reserve(&self, chain_id: u64, address: &str) -> Result<u64, WalletError> {
+        let _guard = self.lock.lock().await;
+        let mut states = self.load().await?;
+        let state = states.entry(nonce_key(chain_id, address)).or_default();
+        let nonce = state.next;
+        state.reserved.insert(nonce);
+        state.next += 1;
+        self.save(&states).await?;
+        Ok(nonce)
+    }
+
+    /// Record the nonce a transaction was signed with, reserving it if it
+    /// did not come from `reserve`
+    pub async fn mark_used(&self, chain_id: u64, address: &str, nonce: u64) -> Result<(), WalletError> {
+        let _guard = self.lock.lock().await;
+        let mut states = self.load().await?;
+        let state = states.entry(nonce_key(chain_id, address)).or_default();
+        state.reserved.insert(nonce);
+        state.next = state.next.max(nonce + 1);
+        self.save(&states).await
+    }
+
+    /// Give back a reserved nonce whose transaction will never be sent.
+    /// Only the highest nonce is reused; releasing an earlier one leaves a
+    /// gap that later transactions wait behind.
+    pub async fn release(&self, chain_id: u64, address: &str, nonce: u64) -> Result<(), WalletError> {
+        let _guard = self.lock.lock().await;
+        let mut states = self.load().await?;
+        let Some(state) = states.get_mut(&nonce_key(chain_id, address)) else {
+            return Ok(());
+        };
+        if state.reserved.remove(&nonce) && nonce + 1 == state.next {
+            state.next = nonce;
+        }
+        self.save(&states).await
+    }
+
+    /// Bring the local state in line with the network's pending transaction
+    /// count (`eth_getTransactionCount` at `pending`)
+    pub async fn reconcile(&self, chain_id: u64, address: &str, network_nonce: u64) -> Result<ReconcileReport, WalletError> {
+        let _guard = self.lock.lock().await;
+        let mut states = self.load().await?;
+        let state = states.entry(nonce_key(chain_id, address)).or_default();
+
+        let still_reserved = state.reserved.split_off(&network_nonce);
+        let cleared: Vec<u64> = std::mem::replace(&mut state.reserved, still_reserved).into_iter().collect();
+        state.next = state.next.max(network_nonce);
+        state.network_nonce = Some(network_nonce);
+
+        let report = ReconcileReport {
+            network_nonce,
+            cleared,
+            next: state.next,
+            gaps: state.gaps(),
+        };
+        self.save(&states).await?;
+        if !report.gaps.is_empty() {
+            log::warn!("Nonce gaps for {} on chain {}: {:?}", address, chain_id, report.gaps);
+        }
+        Ok(report)
+    }
+
+    /// Reconcile with the transaction count `transactions` reads from its RPC
+    pub async fn sync(&self, chain_id: u64, address: &str, transactions: &TransactionManager) -> Result<ReconcileReport, WalletError> {
+        let network_nonce = transactions.get_transaction_count(address).await?;
+        self.reconcile(chain_id, address, network_nonce).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex as StdMutex;
+
+    struct MockStorage {
+        data: StdMutex<HashMap<String, Vec<u8>>>,
+    }
+
+    impl PlatformStorage for MockStorage {
+        fn store(&self, key: &str, data: &[u8]) -> Result<(), WalletError> {
+            self.data.lock().unwrap().insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        fn retrieve(&self, key: &str) -> Result<Vec<u8>, WalletError> {
+            self.data.lock().unwrap().get(key).cloned()
+                .ok_or_else(|| WalletError::storage("Key not found"))
+        }
+
+        fn delete(&self, key: &str) -> Result<(), WalletError> {
+            self.data.lock().unwrap().remove(key);
+            Ok(())
+        }
+
+        fn exists(&self, key: &str) -> Result<bool, WalletError> {
+            Ok(self.data.lock().unwrap().contains_key(key))
+        }
+
+        fn list_keys(&self) -> Result<Vec<String>, WalletError> {
+            Ok(self.data.lock().unwrap().keys().cloned().collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reserve_release_and_reconcile() {
+        let storage = MockStorage { data: StdMutex::new(HashMap::new()) };
+        let nonces = NonceManager::new(&storage, "pw");
+        let wallet = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94";
+
+        // Offline: two payments get distinct nonces
+        assert_eq!(nonces.reserve(1114, wallet).await.unwrap(), 0);
+        assert_eq!(nonces.reserve(1114, &wallet.to_lowercase()).await.unwrap(), 1);
+        assert_eq!(nonces.reserve(84532, wallet).await.unwrap(), 0);
+
+        // The network has seen five transactions sent from elsewhere
+        let report = nonces.reconcile(1114, wallet, 5).await.unwrap();
+        assert_eq!(report.cleared, vec![0, 1]);
+        assert_eq!(nonces.reserve(1114, wallet).await.unwrap(), 5);
+        assert_eq!(nonces.reserve(1114, wallet).await.unwrap(), 6);
+
+        // Releasing the latest nonce reuses it; an earlier one leaves a gap
+        nonces.release(1114, wallet, 6).await.unwrap();
+        assert_eq!(nonces.peek(1114, wallet).await.unwrap(), 6);
+        nonces.reserve(1114, wallet).await.unwrap();
+        nonces.release(1114, wallet, 5).await.unwrap();
+        assert_eq!(nonces.reconcile(1114, wallet, 5).await.unwrap().gaps, vec![5]);
+        nonces.mark_used(1114, wallet, 9).await.unwrap();
+        assert_eq!(nonces.state(1114, wallet).await.unwrap().gaps(), vec![5, 7, 8]);
+    }
+}
@@ -0,0 +1,307 @@
+//! Offline transaction queue
+//!
+//! Signed transactions that could not be broadcast are kept in
+//! `SecureStorage` with the nonce they were signed at, and submitted to the
+//! relay in nonce order once it is reachable again.
+
+use crate::core::storage::SecureStorage;
+use crate::core::transactions::nonce_manager::NonceManager;
+use crate::infrastructure::platform::PlatformStorage;
+use crate::infrastructure::relay::RelayClient;
+use crate::infrastructure::relay_pool::{RelayLink, RelayPool};
+use crate::shared::error::WalletError;
+use crate::shared::sources::{default_clock, default_random_source, Clock, RandomSource};
+use crate::shared::types::{SignedTransaction, TransactionHash};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+const QUEUE_KEY: &str = "offline_queue";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueuedStatus {
+    /// Waiting for connectivity
+    Pending,
+    /// The relay refused it; kept for the user to inspect or discard
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedTransaction {
+    pub id: String,
+    pub from: String,
+    pub chain_id: u64,
+    pub nonce: u64,
+    /// 0x-prefixed raw signed transaction
+    pub raw_tx: String,
+    pub hash: TransactionHash,
+    pub status: QueuedStatus,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub queued_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlushReport {
+    /// Relay transaction ids of submitted entries
+    pub submitted: Vec<String>,
+    pub rejected: Vec<String>,
+    /// Entries left queued because the relay became unreachable
+    pub remaining: usize,
+}
+
+/// Durable queue of signed-but-unbroadcast transactions
+pub struct OfflineQueue<'a> {
+    platform: &'a dyn PlatformStorage,
+    storage: SecureStorage<'a>,
+    password: Zeroizing<String>,
+    relay: Arc<dyn RelayLink>,
+    nonces: NonceManager<'a>,
+    rng: Arc<dyn RandomSource>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<'a> OfflineQueue<'a> {
+    pub fn new(storage: &'a dyn PlatformStorage, password: &str, relay_url: impl Into<String>) -> Self {
+        Self {
+            platform: storage,
+            storage: SecureStorage::new(storage),
+            password: Zeroizing::new(password.to_string()),
+            relay: Arc::new(RelayClient::new(relay_url)),
+            nonces: NonceManager::new(storage, password),
+            rng: default_random_source(),
+            clock: default_clock(),
+        }
+    }
+
+    /// Draw entry ids and encryption salts from `rng` instead of the OS
+    pub fn with_random_source(mut self, rng: Arc<dyn RandomSource>) -> Self {
+        self.storage = SecureStorage::new(self.platform).with_random_source(Arc::clone(&rng));
+        self.rng = rng;
+        self
+    }
+
+    /// Submit through a configured client, e.g. one holding an API key
+    pub fn with_relay_client(mut self, relay: RelayClient) -> Self {
+        self.relay = Arc::new(relay);
+        self
+    }
+
+    /// Submit through several relays, failing over between them
+    pub fn with_relay_pool(mut self, pool: RelayPool) -> Self {
+        self.relay = Arc::new(pool);
+        self
+    }
+
+    /// Timestamp queued entries with `clock` instead of the system time
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    async fn load<T: for<'de> Deserialize<'de> + Default>(&self, key: &str) -> Result<T, WalletError> {
+        if !self.platform.exists(key)? {
+            return Ok(T::default());
+        }
+        let bytes = self.storage.retrieve_data(key, &self.password).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    async fn save<T: Serialize>(&self, key: &str, value: &T) -> Result<(), WalletError> {
+        self.storage.store_data(key, &serde_json::to_vec(value)?, &self.password).await
+    }
+
+    /// Nonce to sign the next offline transaction from `from` with. Pass the
+    /// network's pending nonce when online so the local count catches up
+    /// with transactions sent from elsewhere.
+    pub async fn next_nonce(&self, chain_id: u64, from: &str, network_nonce: Option<u64>) -> Result<u64, WalletError> {
+        if let Some(network_nonce) = network_nonce {
+            self.nonces.reconcile(chain_id, from, network_nonce).await?;
+        }
+        self.nonces.peek(chain_id, from).await
+    }
+
+    pub fn nonces(&self) -> &NonceManager<'a> {
+        &self.nonces
+    }
+
+    /// Persist a signed transaction until it can be broadcast
+    pub async fn enqueue(&self, signed: &SignedTransaction, from: &str) -> Result<QueuedTransaction, WalletError> {
+        let nonce = signed.transaction.nonce
+            .ok_or_else(|| WalletError::validation("Queued transactions must be signed with a nonce"))?;
+        let chain_id = signed.transaction.chain_id;
+
+        let mut queue: Vec<QueuedTransaction> = self.load(QUEUE_KEY).await?;
+        if queue.iter().any(|q| q.chain_id == chain_id && q.from.eq_ignore_ascii_case(from) && q.nonce == nonce) {
+            return Err(WalletError::transaction(format!("A transaction with nonce {} is already queued for {}", nonce, from)));
+        }
+
+        let mut id_bytes = [0u8; 16];
+        self.rng.fill_bytes(&mut id_bytes)?;
+        let entry = QueuedTransaction {
+            id: uuid::Builder::from_random_bytes(id_bytes).into_uuid().to_string(),
+            from: from.to_string(),
+            chain_id,
+            nonce,
+            raw_tx: format!("0x{}", hex::encode(&signed.signature)),
+            hash: signed.hash.clone(),
+            status: QueuedStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            queued_at: self.clock.now(),
+        };
+        queue.push(entry.clone());
+        self.save(QUEUE_KEY, &queue).await?;
+
+        self.nonces.mark_used(chain_id, from, nonce).await?;
+
+        log::info!("Queued offline transaction {} (chain {}, nonce {})", entry.hash, chain_id, nonce);
+        Ok(entry)
+    }
+
+    /// Everything still queued, in submission order
+    pub async fn list(&self) -> Result<Vec<QueuedTransaction>, WalletError> {
+        let mut queue: Vec<QueuedTransaction> = self.load(QUEUE_KEY).await?;
+        queue.sort_by(|a, b| (a.chain_id, &a.from, a.nonce).cmp(&(b.chain_id, &b.from, b.nonce)));
+        Ok(queue)
+    }
+
+    /// Drop a queued transaction, e.g. one the relay rejected, and release
+    /// its nonce
+    pub async fn remove(&self, id: &str) -> Result<(), WalletError> {
+        let mut queue: Vec<QueuedTransaction> = self.load(QUEUE_KEY).await?;
+        let Some(index) = queue.iter().position(|q| q.id == id) else {
+            return Ok(());
+        };
+        let entry = queue.remove(index);
+        self.save(QUEUE_KEY, &queue).await?;
+        self.nonces.release(entry.chain_id, &entry.from, entry.nonce).await
+    }
+
+    pub async fn is_relay_reachable(&self) -> bool {
+        self.relay.probe().await.is_ok()
+    }
+
+    /// Submit pending transactions to the relay in nonce order. Stops once
+    /// the relay stays unreachable through the client's retries, leaving the
+    /// rest queued for the next attempt.
+    pub async fn flush(&self) -> Result<FlushReport, WalletError> {
+        let mut queue = self.list().await?;
+        let mut report = FlushReport::default();
+
+        let mut index = 0;
+        while index < queue.len() {
+            if queue[index].status != QueuedStatus::Pending {
+                index += 1;
+                continue;
+            }
+            queue[index].attempts += 1;
+            match self.relay.submit(&queue[index].raw_tx, queue[index].chain_id).await {
+                Ok(response) => {
+                    queue.remove(index);
+                    report.submitted.push(response.transaction_id);
+                }
+                Err(WalletError::Transaction(message)) => {
+                    log::warn!("Relay rejected queued transaction {}: {}", queue[index].hash, message);
+                    queue[index].status = QueuedStatus::Rejected;
+                    queue[index].last_error = Some(message);
+                    report.rejected.push(queue[index].id.clone());
+                    index += 1;
+                }
+                Err(e) => {
+                    queue[index].last_error = Some(e.to_string());
+                    break;
+                }
+            }
+        }
+
+        report.remaining = queue.iter().filter(|q| q.status == QueuedStatus::Pending).count();
+        self.save(QUEUE_KEY, &queue).await?;
+        Ok(report)
+    }
+
+    /// Flush only if the relay answers its health check
+    pub async fn flush_if_online(&self) -> Result<Option<FlushReport>, WalletError> {
+        if !self.is_relay_reachable().await {
+            return Ok(None);
+        }
+        self.flush().await.map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::types::{Transaction, TransactionType};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    struct MockStorage {
+        data: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    impl PlatformStorage for MockStorage {
+        fn store(&self, key: &str, data: &[u8]) -> Result<(), WalletError> {
+            self.data.lock().unwrap().insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        fn retrieve(&self, key: &str) -> Result<Vec<u8>, WalletError> {
+            self.data.lock().unwrap().get(key).cloned()
+                .ok_or_else(|| WalletError::storage("Key not found"))
+        }
+
+        fn delete(&self, key: &str) -> Result<(), WalletError> {
+            self.data.lock().unwrap().remove(key);
+            Ok(())
+        }
+
+        fn exists(&self, key: &str) -> Result<bool, WalletError> {
+            Ok(self.data.lock().unwrap().contains_key(key))
+        }
+
+        fn list_keys(&self) -> Result<Vec<String>, WalletError> {
+            Ok(self.data.lock().unwrap().keys().cloned().collect())
+        }
+    }
+
+    fn signed(nonce: u64) -> SignedTransaction {
+        SignedTransaction {
+            transaction: Transaction {
+                to: "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6".to_string(),
+                value: "1".to_string(),
+                data: None,
+                gas_limit: Some(21000),
+                gas_price: Some(1),
+                nonce: Some(nonce),
+                chain_id: 1114,
+                tx_type: TransactionType::Legacy,
+                max_fee_per_gas: None,
+                max_priority_fee_per_gas: None,
+            },
+            signature: vec![0xf8, nonce as u8],
+            hash: format!("0x{:064x}", nonce),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queue_persists_and_tracks_nonces() {
+        let storage = MockStorage { data: Mutex::new(HashMap::new()) };
+        let from = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94";
+        {
+            let queue = OfflineQueue::new(&storage, "pw", "http://localhost:4000");
+            assert_eq!(queue.next_nonce(1114, from, Some(3)).await.unwrap(), 3);
+            queue.enqueue(&signed(3), from).await.unwrap();
+            queue.enqueue(&signed(4), from).await.unwrap();
+            assert!(queue.enqueue(&signed(4), from).await.is_err());
+        }
+
+        // A fresh queue over the same storage sees the persisted state
+        let queue = OfflineQueue::new(&storage, "pw", "http://localhost:4000");
+        assert_eq!(queue.next_nonce(1114, &from.to_lowercase(), Some(3)).await.unwrap(), 5);
+        let queued = queue.list().await.unwrap();
+        assert_eq!(queued.iter().map(|q| q.nonce).collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(queued[0].raw_tx, "0xf803");
+    }
+}
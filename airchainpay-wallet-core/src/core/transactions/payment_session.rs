@@ -0,0 +1,431 @@
+//! Payment session state machine
+//!
+//! A BLE or QR payment moves through request → quote → sign → transfer →
+//! relay ack → confirmation. `PaymentSession` records which stage a payment
+//! is in and the deadline for leaving it, and accepts only the transitions
+//! below; anything else is an error. Sessions are plain data, so one that
+//! was interrupted by an app restart or a dropped link is reloaded from
+//! `PaymentSessionStore` and picks up at `next_step`.
+//!
+//! ```text
+//! Requested -quote-> Quoted -sign-> Signed -transfer-> Transferred -ack-> Acknowledged -confirm-> Confirmed
+//!                                              ^-----transfer (retry)-----'
+//! any non-terminal stage -fail/cancel/deadline-> Failed / Cancelled / Expired
+//! ```
+
+use crate::core::storage::SecureStorage;
+use crate::infrastructure::platform::PlatformStorage;
+use crate::shared::error::WalletError;
+use crate::shared::sources::{default_clock, default_random_source, Clock, RandomSource};
+use crate::shared::types::{Amount, BLEPaymentData, BlockNumber, TransactionHash};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+const SESSIONS_KEY: &str = "payment_sessions";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentChannel {
+    Ble,
+    Qr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentStage {
+    /// The payee's request has been received
+    Requested,
+    /// Amount, token and fee are fixed and shown to the payer
+    Quoted,
+    /// The transaction is signed but has not left the device
+    Signed,
+    /// Handed to the relay or the payee's device, waiting for acceptance
+    Transferred,
+    /// The relay accepted the transaction for broadcast
+    Acknowledged,
+    Confirmed,
+    Failed,
+    Expired,
+    Cancelled,
+}
+
+impl PaymentStage {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Confirmed | Self::Failed | Self::Expired | Self::Cancelled)
+    }
+}
+
+/// What the payer has agreed to pay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentQuote {
+    pub amount: Amount,
+    pub token_symbol: String,
+    /// Estimated network fee in wei
+    pub fee_estimate: String,
+    /// The quote may not be signed after this
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PaymentEvent {
+    Quote(PaymentQuote),
+    Sign { tx_hash: TransactionHash, raw_tx: String },
+    /// Sent to the relay or payee; repeatable while unacknowledged
+    Transfer,
+    RelayAck { transaction_id: String },
+    Confirm { block_number: Option<BlockNumber> },
+    Fail { reason: String },
+    Cancel,
+}
+
+/// How long a payment may stay in each stage, in seconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentTimeouts {
+    pub quote_secs: i64,
+    pub sign_secs: i64,
+    pub transfer_secs: i64,
+    pub ack_secs: i64,
+    pub confirmation_secs: i64,
+    /// Transfers of one signed transaction before the payment fails
+    pub max_transfer_attempts: u32,
+}
+
+impl Default for PaymentTimeouts {
+    fn default() -> Self {
+        Self {
+            quote_secs: 60,
+            sign_secs: 120,
+            transfer_secs: 60,
+            ack_secs: 60,
+            confirmation_secs: 600,
+            max_transfer_attempts: 3,
+        }
+    }
+}
+
+/// What a resumed session should do next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentStep {
+    Quote,
+    Sign,
+    /// Send, or resend, the signed transaction
+    Transfer,
+    AwaitConfirmation,
+    Done,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageChange {
+    pub stage: PaymentStage,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentSession {
+    pub id: String,
+    pub channel: PaymentChannel,
+    pub stage: PaymentStage,
+    pub request: BLEPaymentData,
+    pub quote: Option<PaymentQuote>,
+    pub tx_hash: Option<TransactionHash>,
+    /// 0x-prefixed raw signed transaction, kept so a transfer can be repeated
+    pub raw_tx: Option<String>,
+    pub transfer_attempts: u32,
+    pub relay_transaction_id: Option<String>,
+    pub block_number: Option<BlockNumber>,
+    pub error: Option<String>,
+    pub timeouts: PaymentTimeouts,
+    /// When the current stage times out; unset once terminal
+    pub deadline: Option<DateTime<Utc>>,
+    pub history: Vec<StageChange>,
+}
+
+impl PaymentSession {
+    pub fn new(id: impl Into<String>, channel: PaymentChannel, request: BLEPaymentData, now: DateTime<Utc>) -> Self {
+        Self::with_timeouts(id, channel, request, PaymentTimeouts::default(), now)
+    }
+
+    pub fn with_timeouts(
+        id: impl Into<String>,
+        channel: PaymentChannel,
+        request: BLEPaymentData,
+        timeouts: PaymentTimeouts,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let mut session = Self {
+            id: id.into(),
+            channel,
+            stage: PaymentStage::Requested,
+            request,
+            quote: None,
+            tx_hash: None,
+            raw_tx: None,
+            transfer_attempts: 0,
+            relay_transaction_id: None,
+            block_number: None,
+            error: None,
+            timeouts,
+            deadline: None,
+            history: Vec::new(),
+        };
+        session.enter(PaymentStage::Requested, now);
+        session
+    }
+
+    fn stage_timeout(&self, stage: PaymentStage) -> Option<Duration> {
+        let secs = match stage {
+            PaymentStage::Requested => self.timeouts.quote_secs,
+            PaymentStage::Quoted => self.timeouts.sign_secs,
+            PaymentStage::Signed => self.timeouts.transfer_secs,
+            PaymentStage::Transferred => self.timeouts.ack_secs,
+            PaymentStage::Acknowledged => self.timeouts.confirmation_secs,
+            _ => return None,
+        };
+        Some(Duration::seconds(secs))
+    }
+
+    fn enter(&mut self, stage: PaymentStage, now: DateTime<Utc>) {
+        self.stage = stage;
+        self.deadline = self.stage_timeout(stage).map(|timeout| now + timeout);
+        // A quote cannot be signed after it lapses
+        if stage == PaymentStage::Quoted {
+            if let (Some(deadline), Some(quote)) = (self.deadline, &self.quote) {
+                self.deadline = Some(deadline.min(quote.expires_at));
+            }
+        }
+        self.history.push(StageChange { stage, at: now });
+    }
+
+    /// Move to `Expired` if the current stage's deadline has passed
+    pub fn check_deadline(&mut self, now: DateTime<Utc>) -> bool {
+        match self.deadline {
+            Some(deadline) if !self.stage.is_terminal() && now > deadline => {
+                self.error = Some(format!("Timed out in stage {:?}", self.stage));
+                self.enter(PaymentStage::Expired, now);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Apply an event, returning the new stage
+    pub fn apply(&mut self, event: PaymentEvent, now: DateTime<Utc>) -> Result<PaymentStage, WalletError> {
+        if self.check_deadline(now) {
+            return Err(WalletError::transaction(format!("Payment {} expired", self.id)));
+        }
+        if self.stage.is_terminal() {
+            return Err(WalletError::transaction(format!("Payment {} already ended as {:?}", self.id, self.stage)));
+        }
+
+        let next = match (self.stage, event) {
+            (_, PaymentEvent::Cancel) => PaymentStage::Cancelled,
+            (_, PaymentEvent::Fail { reason }) => {
+                self.error = Some(reason);
+                PaymentStage::Failed
+            }
+            (PaymentStage::Requested, PaymentEvent::Quote(quote)) => {
+                if quote.expires_at <= now {
+                    return Err(WalletError::validation("Quote has already expired"));
+                }
+                self.quote = Some(quote);
+                PaymentStage::Quoted
+            }
+            (PaymentStage::Quoted, PaymentEvent::Sign { tx_hash, raw_tx }) => {
+                self.tx_hash = Some(tx_hash);
+                self.raw_tx = Some(raw_tx);
+                PaymentStage::Signed
+            }
+            (PaymentStage::Signed | PaymentStage::Transferred, PaymentEvent::Transfer) => {
+                if self.transfer_attempts >= self.timeouts.max_transfer_attempts {
+                    self.error = Some(format!("No acknowledgement after {} transfers", self.transfer_attempts));
+                    self.enter(PaymentStage::Failed, now);
+                    return Err(WalletError::transaction(format!("Payment {} was not acknowledged", self.id)));
+                }
+                self.transfer_attempts += 1;
+                PaymentStage::Transferred
+            }
+            (PaymentStage::Transferred, PaymentEvent::RelayAck { transaction_id }) => {
+                self.relay_transaction_id = Some(transaction_id);
+                PaymentStage::Acknowledged
+            }
+            (PaymentStage::Acknowledged, PaymentEvent::Confirm { block_number }) => {
+                self.block_number = block_number;
+                PaymentStage::Confirmed
+            }
+            (stage, event) => {
+                return Err(WalletError::validation(format!("{:?} is not allowed in stage {:?}", event, stage)));
+            }
+        };
+        self.enter(next, now);
+        Ok(next)
+    }
+
+    /// Where to pick up after a restart; expires the session first if its
+    /// deadline passed while the app was away
+    pub fn next_step(&mut self, now: DateTime<Utc>) -> PaymentStep {
+        self.check_deadline(now);
+        match self.stage {
+            PaymentStage::Requested => PaymentStep::Quote,
+            PaymentStage::Quoted => PaymentStep::Sign,
+            // An unacknowledged transfer is resent; the relay drops duplicates
+            PaymentStage::Signed | PaymentStage::Transferred => PaymentStep::Transfer,
+            PaymentStage::Acknowledged => PaymentStep::AwaitConfirmation,
+            _ => PaymentStep::Done,
+        }
+    }
+}
+
+/// Payment sessions kept encrypted in `SecureStorage` so they survive restarts
+pub struct PaymentSessionStore<'a> {
+    platform: &'a dyn PlatformStorage,
+    storage: SecureStorage<'a>,
+    password: Zeroizing<String>,
+    rng: Arc<dyn RandomSource>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<'a> PaymentSessionStore<'a> {
+    pub fn new(storage: &'a dyn PlatformStorage, password: &str) -> Self {
+        Self {
+            platform: storage,
+            storage: SecureStorage::new(storage),
+            password: Zeroizing::new(password.to_string()),
+            rng: default_random_source(),
+            clock: default_clock(),
+        }
+    }
+
+    /// Draw session ids and encryption salts from `rng` instead of the OS
+    pub fn with_random_source(mut self, rng: Arc<dyn RandomSource>) -> Self {
+        self.storage = SecureStorage::new(self.platform).with_random_source(Arc::clone(&rng));
+        self.rng = rng;
+        self
+    }
+
+    /// Time stages and deadlines with `clock` instead of the system time
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    async fn load(&self) -> Result<HashMap<String, PaymentSession>, WalletError> {
+        if !self.platform.exists(SESSIONS_KEY)? {
+            return Ok(HashMap::new());
+        }
+        let bytes = self.storage.retrieve_data(SESSIONS_KEY, &self.password).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    async fn save_all(&self, sessions: &HashMap<String, PaymentSession>) -> Result<(), WalletError> {
+        self.storage.store_data(SESSIONS_KEY, &serde_json::to_vec(sessions)?, &self.password).await
+    }
+
+    /// Open and persist a session for an incoming payment request
+    pub async fn start(&self, channel: PaymentChannel, request: BLEPaymentData) -> Result<PaymentSession, WalletError> {
+        let mut id_bytes = [0u8; 16];
+        self.rng.fill_bytes(&mut id_bytes)?;
+        let id = uuid::Builder::from_random_bytes(id_bytes).into_uuid().to_string();
+        let session = PaymentSession::new(id, channel, request, self.clock.now());
+        self.save(&session).await?;
+        Ok(session)
+    }
+
+    pub async fn save(&self, session: &PaymentSession) -> Result<(), WalletError> {
+        let mut sessions = self.load().await?;
+        sessions.insert(session.id.clone(), session.clone());
+        self.save_all(&sessions).await
+    }
+
+    /// Apply an event to a stored session and persist the result. A session
+    /// that expired is saved as such before the error is returned.
+    pub async fn advance(&self, id: &str, event: PaymentEvent) -> Result<PaymentSession, WalletError> {
+        let mut sessions = self.load().await?;
+        let session = sessions.get_mut(id)
+            .ok_or_else(|| WalletError::validation(format!("Unknown payment session {}", id)))?;
+        let result = session.apply(event, self.clock.now());
+        let updated = session.clone();
+        self.save_all(&sessions).await?;
+        result.map(|_| updated)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<PaymentSession>, WalletError> {
+        Ok(self.load().await?.remove(id))
+    }
+
+    /// Unfinished sessions with the step each should resume at, oldest
+    /// first. Sessions whose deadline passed are expired and saved.
+    pub async fn resumable(&self) -> Result<Vec<(PaymentSession, PaymentStep)>, WalletError> {
+        let mut sessions = self.load().await?;
+        let now = self.clock.now();
+        let mut resumable = Vec::new();
+        for session in sessions.values_mut() {
+            let step = session.next_step(now);
+            if step != PaymentStep::Done {
+                resumable.push((session.clone(), step));
+            }
+        }
+        self.save_all(&sessions).await?;
+        resumable.sort_by_key(|(session, _)| session.history.first().map(|change| change.at));
+        Ok(resumable)
+    }
+
+    /// Forget sessions that have ended
+    pub async fn prune_finished(&self) -> Result<usize, WalletError> {
+        let mut sessions = self.load().await?;
+        let before = sessions.len();
+        sessions.retain(|_, session| !session.stage.is_terminal());
+        self.save_all(&sessions).await?;
+        Ok(before - sessions.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::types::Network;
+
+    fn request() -> BLEPaymentData {
+        BLEPaymentData {
+            amount: "1000000000000000000".to_string(),
+            to_address: "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6".to_string(),
+            token_symbol: "ETH".to_string(),
+            network: Network::CoreTestnet,
+            reference: None,
+        }
+    }
+
+    #[test]
+    fn test_payment_session_runs_and_resumes() {
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut session = PaymentSession::new("p1", PaymentChannel::Ble, request(), start);
+        let quote = PaymentQuote {
+            amount: "1000000000000000000".to_string(),
+            token_symbol: "ETH".to_string(),
+            fee_estimate: "21000000000000".to_string(),
+            expires_at: start + Duration::seconds(30),
+        };
+
+        assert!(session.apply(PaymentEvent::Transfer, start).is_err());
+        session.apply(PaymentEvent::Quote(quote), start).unwrap();
+        session.apply(PaymentEvent::Sign { tx_hash: "0xabc".to_string(), raw_tx: "0x01".to_string() }, start).unwrap();
+        session.apply(PaymentEvent::Transfer, start).unwrap();
+
+        // Reloaded after a restart with the transfer unacknowledged
+        let mut resumed: PaymentSession = serde_json::from_slice(&serde_json::to_vec(&session).unwrap()).unwrap();
+        assert_eq!(resumed.next_step(start + Duration::seconds(5)), PaymentStep::Transfer);
+        resumed.apply(PaymentEvent::Transfer, start + Duration::seconds(5)).unwrap();
+        resumed.apply(PaymentEvent::RelayAck { transaction_id: "tx-1".to_string() }, start + Duration::seconds(6)).unwrap();
+        assert_eq!(resumed.apply(PaymentEvent::Confirm { block_number: Some(7) }, start + Duration::seconds(20)).unwrap(), PaymentStage::Confirmed);
+        assert_eq!(resumed.transfer_attempts, 2);
+
+        // A quote left unsigned past its expiry times out
+        let mut stale = PaymentSession::new("p2", PaymentChannel::Qr, request(), start);
+        stale.apply(PaymentEvent::Quote(PaymentQuote { expires_at: start + Duration::seconds(10), ..resumed.quote.clone().unwrap() }), start).unwrap();
+        assert!(stale.apply(PaymentEvent::Sign { tx_hash: "0xdef".to_string(), raw_tx: "0x02".to_string() }, start + Duration::seconds(11)).is_err());
+        assert_eq!(stale.stage, PaymentStage::Expired);
+    }
+}
@@ -0,0 +1,295 @@
+//! Reusable transaction templates
+//!
+//! A template names a recipient, a token and how the amount is worked out,
+//! so "pay the usual" is one call instead of a builder chain. Templates are
+//! kept per network: the same name may point at different addresses on
+//! Base Sepolia and Core Testnet. Instantiating a template yields a
+//! `TransactionBuilder` with the recipient, value and ERC-20 call data
+//! filled in; nonce and fees are left to the caller as with any builder.
+
+use crate::core::transactions::TransactionBuilder;
+use crate::shared::error::WalletError;
+use crate::shared::types::{Address, Amount, Network, PaymentRequest, TokenInfo};
+use crate::shared::utils::parse_amount;
+use ethers::abi::{self, Token};
+use ethers::types::{H160, U256};
+use serde::{Deserialize, Serialize};
+
+/// Selector of ERC-20 `transfer(address,uint256)`
+pub(crate) const ERC20_TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+/// Most templates one registry may hold
+const MAX_TEMPLATES: usize = 100;
+
+/// How a template's amount is worked out, in the token's smallest unit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AmountFormula {
+    /// Always the same amount
+    Fixed { amount: Amount },
+    /// A unit price times the quantity given at instantiation
+    PerUnit { unit_amount: Amount, max_quantity: Option<u64> },
+    /// Entered by the payer each time, in display units, within bounds
+    Entered { min: Option<Amount>, max: Option<Amount> },
+}
+
+/// What the payer supplies when instantiating a template
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TemplateInput {
+    pub quantity: Option<u64>,
+    /// Display units, such as `"2.50"`, for `Entered` amounts
+    pub amount: Option<String>,
+}
+
+impl TemplateInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_quantity(mut self, quantity: u64) -> Self {
+        self.quantity = Some(quantity);
+        self
+    }
+
+    pub fn with_amount(mut self, amount: impl Into<String>) -> Self {
+        self.amount = Some(amount.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionTemplate {
+    pub name: String,
+    pub network: Network,
+    pub recipient: Address,
+    pub recipient_label: Option<String>,
+    pub token: TokenInfo,
+    pub amount: AmountFormula,
+    /// Payment reference copied into payment requests made from the template
+    pub reference: Option<String>,
+}
+
+impl TransactionTemplate {
+    pub fn new(name: &str, network: Network, recipient: &str, token: TokenInfo, amount: AmountFormula) -> Result<Self, WalletError> {
+        if name.trim().is_empty() {
+            return Err(WalletError::validation("Template name cannot be empty"));
+        }
+        if !token.is_native && token.chain_id != network.chain_id().to_string() {
+            return Err(WalletError::validation(format!("Token {} is not on {:?}", token.symbol, network)));
+        }
+        let template = Self {
+            name: name.trim().to_string(),
+            network,
+            recipient: format!("{:#x}", parse_address(recipient)?),
+            recipient_label: None,
+            token,
+            amount,
+            reference: None,
+        };
+        template.check_formula()?;
+        Ok(template)
+    }
+
+    pub fn with_recipient_label(mut self, label: impl Into<String>) -> Self {
+        self.recipient_label = Some(label.into());
+        self
+    }
+
+    pub fn with_reference(mut self, reference: impl Into<String>) -> Self {
+        self.reference = Some(reference.into());
+        self
+    }
+
+    /// The amount for `input`, in the token's smallest unit
+    pub fn amount_for(&self, input: &TemplateInput) -> Result<U256, WalletError> {
+        let amount = match &self.amount {
+            AmountFormula::Fixed { amount } => parse_units(amount)?,
+            AmountFormula::PerUnit { unit_amount, max_quantity } => {
+                let quantity = input.quantity
+                    .ok_or_else(|| WalletError::validation(format!("Template {} needs a quantity", self.name)))?;
+                if quantity == 0 || max_quantity.is_some_and(|max| quantity > max) {
+                    return Err(WalletError::validation(format!("Quantity {} is out of range for template {}", quantity, self.name)));
+                }
+                parse_units(unit_amount)?.checked_mul(U256::from(quantity))
+                    .ok_or_else(|| WalletError::validation("Template amount overflows"))?
+            }
+            AmountFormula::Entered { min, max } => {
+                let entered = input.amount.as_deref()
+                    .ok_or_else(|| WalletError::validation(format!("Template {} needs an amount", self.name)))?;
+                let amount = parse_units(&parse_amount(entered, self.token.decimals)?)?;
+                if min.as_deref().map(parse_units).transpose()?.is_some_and(|min| amount < min)
+                    || max.as_deref().map(parse_units).transpose()?.is_some_and(|max| amount > max)
+                {
+                    return Err(WalletError::validation(format!("Amount {} is out of range for template {}", entered, self.name)));
+                }
+                amount
+            }
+        };
+        if amount.is_zero() {
+            return Err(WalletError::validation("Template amount must be greater than zero"));
+        }
+        Ok(amount)
+    }
+
+    /// A builder for the payment: native value to the recipient, or an
+    /// ERC-20 `transfer` call on the token contract
+    pub fn builder(&self, input: &TemplateInput) -> Result<TransactionBuilder, WalletError> {
+        transfer_builder(&self.network, &self.recipient, &self.token, self.amount_for(input)?)
+    }
+
+    /// The payment as a request, for QR codes or a BLE quote
+    pub fn payment_request(&self, input: &TemplateInput) -> Result<PaymentRequest, WalletError> {
+        Ok(PaymentRequest {
+            amount: self.amount_for(input)?.to_string(),
+            to_address: self.recipient.clone(),
+            token: self.token.clone(),
+            network: self.network.clone(),
+            reference: self.reference.clone(),
+            gas_price: None,
+        })
+    }
+
+    fn check_formula(&self) -> Result<(), WalletError> {
+        match &self.amount {
+            AmountFormula::Fixed { amount } => {
+                parse_units(amount)?;
+            }
+            AmountFormula::PerUnit { unit_amount, .. } => {
+                parse_units(unit_amount)?;
+            }
+            AmountFormula::Entered { min, max } => {
+                let min = min.as_deref().map(parse_units).transpose()?;
+                let max = max.as_deref().map(parse_units).transpose()?;
+                if let (Some(min), Some(max)) = (min, max) {
+                    if min > max {
+                        return Err(WalletError::validation("Template minimum exceeds its maximum"));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Templates by network and name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TemplateRegistry {
+    templates: Vec<TransactionTemplate>,
+}
+
+impl TemplateRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a template, replacing any of the same name on its network
+    pub fn register(&mut self, template: TransactionTemplate) -> Result<(), WalletError> {
+        match self.templates.iter_mut().find(|t| t.network == template.network && t.name == template.name) {
+            Some(existing) => *existing = template,
+            None if self.templates.len() >= MAX_TEMPLATES => {
+                return Err(WalletError::validation(format!("At most {} templates can be registered", MAX_TEMPLATES)));
+            }
+            None => self.templates.push(template),
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, network: &Network, name: &str) -> bool {
+        let before = self.templates.len();
+        self.templates.retain(|t| !(t.network == *network && t.name == name));
+        self.templates.len() != before
+    }
+
+    pub fn get(&self, network: &Network, name: &str) -> Result<&TransactionTemplate, WalletError> {
+        self.templates.iter()
+            .find(|t| t.network == *network && t.name == name)
+            .ok_or_else(|| WalletError::validation(format!("No template {} on {:?}", name, network)))
+    }
+
+    /// Templates for one network, in registration order
+    pub fn for_network(&self, network: &Network) -> Vec<&TransactionTemplate> {
+        self.templates.iter().filter(|t| t.network == *network).collect()
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, WalletError> {
+        serde_json::to_vec(self).map_err(|e| WalletError::validation(format!("Template serialization failed: {}", e)))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WalletError> {
+        serde_json::from_slice(bytes).map_err(|e| WalletError::validation(format!("Template deserialization failed: {}", e)))
+    }
+}
+
+/// A builder paying `amount` of `token` to `recipient`: native value, or an
+/// ERC-20 `transfer` call on the token contract
+pub(crate) fn transfer_builder(network: &Network, recipient: &str, token: &TokenInfo, amount: U256) -> Result<TransactionBuilder, WalletError> {
+    let builder = TransactionBuilder::new(network.clone());
+    if token.is_native {
+        return Ok(builder.to(recipient.to_string()).value(amount.to_string()));
+    }
+    let recipient = parse_address(recipient)?;
+    let mut data = ERC20_TRANSFER_SELECTOR.to_vec();
+    data.extend(abi::encode(&[Token::Address(recipient), Token::Uint(amount)]));
+    Ok(builder.to(format!("{:#x}", parse_address(&token.address)?)).value("0").data(data))
+}
+
+fn parse_address(address: &str) -> Result<H160, WalletError> {
+    address.trim().parse::<H160>()
+        .map_err(|_| WalletError::validation(format!("Invalid address: {}", address)))
+}
+
+fn parse_units(amount: &str) -> Result<U256, WalletError> {
+    U256::from_dec_str(amount.trim())
+        .map_err(|_| WalletError::validation(format!("Invalid amount: {}", amount)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_instantiates_erc20_transfer() {
+        let usdc = TokenInfo {
+            symbol: "USDC".to_string(),
+            name: "USD Coin".to_string(),
+            decimals: 6,
+            address: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".to_string(),
+            chain_id: "84532".to_string(),
+            is_native: false,
+            is_stablecoin: true,
+        };
+        let coffee = TransactionTemplate::new(
+            "coffee",
+            Network::BaseSepolia,
+            "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6",
+            usdc.clone(),
+            AmountFormula::PerUnit { unit_amount: "3500000".to_string(), max_quantity: Some(10) },
+        )
+        .unwrap()
+        .with_recipient_label("Coffee shop");
+
+        let mut registry = TemplateRegistry::new();
+        registry.register(coffee).unwrap();
+        let registry = TemplateRegistry::from_bytes(&registry.to_bytes().unwrap()).unwrap();
+        let template = registry.get(&Network::BaseSepolia, "coffee").unwrap();
+        assert!(registry.get(&Network::CoreTestnet, "coffee").is_err());
+
+        let transaction = template.builder(&TemplateInput::new().with_quantity(2)).unwrap().nonce(0).build().unwrap();
+        assert_eq!(transaction.to, "0x036cbd53842c5426634e7929541ec2318f3dcf7e");
+        assert_eq!(transaction.value, "0");
+        let data = transaction.data.unwrap();
+        assert_eq!(data[..4], ERC20_TRANSFER_SELECTOR);
+        assert_eq!(U256::from_big_endian(&data[36..68]), U256::from(7_000_000u64));
+        assert!(template.amount_for(&TemplateInput::new().with_quantity(11)).is_err());
+
+        let tip = TransactionTemplate::new(
+            "tip",
+            Network::BaseSepolia,
+            "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6",
+            usdc,
+            AmountFormula::Entered { min: None, max: Some("5000000".to_string()) },
+        )
+        .unwrap();
+        assert_eq!(tip.payment_request(&TemplateInput::new().with_amount("2.5")).unwrap().amount, "2500000");
+        assert!(tip.amount_for(&TemplateInput::new().with_amount("6")).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+//! Address book and lookalike-recipient detection
+//!
+//! Address poisoning works by sending dust from an address that shares the
+//! first and last few characters with one the victim pays regularly, hoping
+//! it gets copied from history. Before signing, the recipient is compared
+//! against saved contacts and recent counterparties; a recipient that is not
+//! itself known but matches one only on its prefix and suffix is flagged.
+
+use crate::shared::error::WalletError;
+use crate::shared::types::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Leading hex characters (after `0x`) that must match to count as a lookalike
+pub const LOOKALIKE_MIN_PREFIX: usize = 3;
+/// Trailing hex characters that must match to count as a lookalike
+pub const LOOKALIKE_MIN_SUFFIX: usize = 3;
+/// Combined prefix and suffix match needed; wallets show roughly 4+4
+pub const LOOKALIKE_MIN_MATCHED: usize = 8;
+/// Recipients remembered per wallet
+pub const MAX_RECENT_COUNTERPARTIES: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AddressBookEntry {
+    pub address: Address,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KnownAddressSource {
+    AddressBook,
+    RecentCounterparty,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressWarningKind {
+    /// Shares a prefix and suffix with a known address but is not it
+    Lookalike,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressWarning {
+    pub kind: AddressWarningKind,
+    pub recipient: Address,
+    /// The known address the recipient resembles
+    pub similar_to: Address,
+    pub similar_to_label: Option<String>,
+    pub source: KnownAddressSource,
+    pub matched_prefix: usize,
+    pub matched_suffix: usize,
+    pub message: String,
+}
+
+/// Result of the pre-sign recipient check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreSignReport {
+    pub recipient: Address,
+    /// The recipient is itself a saved contact or recent counterparty
+    pub known: bool,
+    pub warnings: Vec<AddressWarning>,
+}
+
+impl PreSignReport {
+    pub fn is_clean(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Lowercase hex body of an address, without `0x`
+fn normalize(address: &str) -> Result<String, WalletError> {
+    let body = address.trim().trim_start_matches("0x").trim_start_matches("0X").to_lowercase();
+    if body.len() != 40 || !body.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(WalletError::validation(format!("Invalid address: {}", address)));
+    }
+    Ok(body)
+}
+
+/// Matching leading and trailing characters of two equal-length addresses
+fn affix_match(a: &str, b: &str) -> (usize, usize) {
+    let prefix = a.chars().zip(b.chars()).take_while(|(x, y)| x == y).count();
+    let suffix = a.chars().rev().zip(b.chars().rev()).take_while(|(x, y)| x == y).count();
+    (prefix, suffix)
+}
+
+/// A wallet's saved contacts and the addresses it recently paid
+#[derive(Debug, Clone, Default)]
+pub struct AddressBook {
+    entries: Vec<AddressBookEntry>,
+    recent: VecDeque<Address>,
+}
+
+impl AddressBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Save or relabel a contact
+    pub fn add_entry(&mut self, address: &str, label: &str) -> Result<(), WalletError> {
+        let body = normalize(address)?;
+        let address = format!("0x{}", body);
+        match self.entries.iter_mut().find(|e| e.address == address) {
+            Some(entry) => entry.label = label.to_string(),
+            None => self.entries.push(AddressBookEntry { address, label: label.to_string() }),
+        }
+        Ok(())
+    }
+
+    pub fn remove_entry(&mut self, address: &str) -> Result<(), WalletError> {
+        let address = format!("0x{}", normalize(address)?);
+        self.entries.retain(|e| e.address != address);
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[AddressBookEntry] {
+        &self.entries
+    }
+
+    /// Remember an address the wallet just paid, most recent first
+    pub fn record_counterparty(&mut self, address: &str) -> Result<(), WalletError> {
+        let address = format!("0x{}", normalize(address)?);
+        self.recent.retain(|a| *a != address);
+        self.recent.push_front(address);
+        self.recent.truncate(MAX_RECENT_COUNTERPARTIES);
+        Ok(())
+    }
+
+    /// Compare a recipient against every known address
+    pub fn check(&self, recipient: &str) -> Result<PreSignReport, WalletError> {
+        let body = normalize(recipient)?;
+        let recipient = format!("0x{}", body);
+
+        let known_addresses = self.entries.iter()
+            .map(|e| (&e.address, Some(e.label.clone()), KnownAddressSource::AddressBook))
+            .chain(self.recent.iter().map(|a| (a, None, KnownAddressSource::RecentCounterparty)));
+
+        let mut known = false;
+        let mut warnings: Vec<AddressWarning> = Vec::new();
+        for (address, label, source) in known_addresses {
+            if *address == recipient {
+                known = true;
+                continue;
+            }
+            if warnings.iter().any(|w| w.similar_to == *address) {
+                continue;
+            }
+            let (prefix, suffix) = affix_match(&body, &address[2..]);
+            if prefix >= LOOKALIKE_MIN_PREFIX && suffix >= LOOKALIKE_MIN_SUFFIX && prefix + suffix >= LOOKALIKE_MIN_MATCHED {
+                let name = label.clone().unwrap_or_else(|| "a recent recipient".to_string());
+                warnings.push(AddressWarning {
+                    kind: AddressWarningKind::Lookalike,
+                    recipient: recipient.clone(),
+                    similar_to: address.clone(),
+                    similar_to_label: label,
+                    source,
+                    matched_prefix: prefix,
+                    matched_suffix: suffix,
+                    message: format!(
+                        "Recipient {} looks like {} ({}) but is a different address",
+                        recipient, name, address
+                    ),
+                });
+            }
+        }
+
+        // A recipient the user saved or already paid is trusted as-is
+        if known {
+            warnings.clear();
+        }
+        Ok(PreSignReport { recipient, known, warnings })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poisoned_lookalike_is_flagged() {
+        let mut book = AddressBook::new();
+        book.add_entry("0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6", "Coffee shop").unwrap();
+
+        // Same first 5 and last 4 characters, different middle
+        let report = book.check("0x742d3000000000000000000000000000000fd8b6").unwrap();
+        assert!(!report.known);
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.warnings[0].similar_to_label.as_deref(), Some("Coffee shop"));
+        assert_eq!((report.warnings[0].matched_prefix, report.warnings[0].matched_suffix), (5, 4));
+
+        let exact = book.check("0x742D35CC6634C0532925A3B8D4C9DB96C4B4D8B6").unwrap();
+        assert!(exact.known && exact.is_clean());
+
+        let unrelated = book.check("0x9858effd232b4033e47d90003d41ec34ecaeda94").unwrap();
+        assert!(!unrelated.known && unrelated.is_clean());
+    }
+}
@@ -2,27 +2,159 @@
 //! 
 //! This module handles wallet creation, management, and operations.
 
-use crate::domain::{SecureWallet, WalletBalance};
+pub mod address_book;
+pub mod signing_policy;
+
+pub use address_book::{AddressBook, AddressBookEntry, AddressWarning, PreSignReport};
+pub use signing_policy::{AuthReason, SigningPolicy, SigningPolicyStore};
+
+use crate::domain::{HdAccount, SecureWallet, WalletBalance};
+use crate::core::crypto::keys::{bip44_path, check_seed_phrase, SeedPhraseReport};
+use crate::core::crypto::signing::{EnclaveBackend, SigningBackend};
+use crate::core::crypto::sss::{combine_mnemonics, split_master_secret};
+use crate::core::payments::OpenPaymentRequest;
+use crate::core::transactions::balances::rpc_url_for;
+use crate::core::transactions::{BalanceService, HistoryEntry, HistoryQuery, HistoryStatus, NetworkBalances, TemplateInput, TemplateRegistry, TransactionBuilder, TransactionHistory, TransactionTemplate};
+use crate::core::storage::{decrypt_keystore, encrypt_keystore, Keystore, ScryptParams};
+use crate::infrastructure::platform::{BiometricAuth, PlatformStorage, SecureEnclave};
+use crate::shared::constants::HD_SEED_SIZE;
 use crate::shared::error::WalletError;
-use crate::shared::types::{Network, Transaction, SignedTransaction};
+use crate::shared::sources::{default_clock, default_random_source, Clock, RandomSource};
+use crate::shared::types::{Network, PaymentRequest, Transaction, SignedTransaction, TokenInfo};
 use reqwest::Client;
 use ethers::types::U256;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use zeroize::Zeroizing;
+
+/// Wallets, cached balances and derived accounts, kept under one lock so a
+/// wallet and its balance entry appear or change together
+#[derive(Default)]
+struct WalletRegistry {
+    wallets: HashMap<String, SecureWallet>,
+    balances: HashMap<String, WalletBalance>,
+    accounts: HashMap<String, Vec<HdAccount>>,
+    address_books: HashMap<String, AddressBook>,
+    templates: TemplateRegistry,
+    /// External signers for wallets whose key is not held on this device
+    signers: HashMap<String, Arc<dyn SigningBackend>>,
+    /// Secure enclave keys of hardware wallets, erased with the wallet
+    enclave_keys: HashMap<String, Arc<EnclaveBackend>>,
+    /// Wallet ids whose keys are being generated or imported
+    reserved: HashSet<String>,
+}
+
+impl WalletRegistry {
+    fn insert_wallet(&mut self, wallet: SecureWallet) {
+        let currency = wallet.network.native_currency().to_string();
+        let balance = WalletBalance::new(wallet.id.clone(), wallet.network.clone(), "0".to_string(), currency);
+        self.balances.insert(wallet.id.clone(), balance);
+        self.reserved.remove(&wallet.id);
+        self.wallets.insert(wallet.id.clone(), wallet);
+    }
+}
 
 /// Wallet manager for handling multiple wallets
+///
+/// # Concurrency
+///
+/// `WalletManager` is `Send + Sync` and cheap to clone; clones share the same
+/// registry, so one instance can serve every FFI or platform thread.
+///
+/// - Registry updates are atomic: readers see a wallet together with its
+///   balance entry, or neither.
+/// - Creating or importing a wallet reserves its id first, so two callers
+///   racing on the same id cannot both generate keys; the loser gets
+///   `WalletAlreadyExists`.
+/// - No lock is held across network calls or key storage I/O, and the
+///   returned futures are `Send`, so they can be spawned on a multi-threaded
+///   runtime.
+/// - Balances are last-write-wins.
+#[derive(Clone)]
 pub struct WalletManager {
-    // Removed CryptoManager for simplicity
-    wallets: std::sync::Arc<tokio::sync::RwLock<std::collections::HashMap<String, SecureWallet>>>,
-    balances: std::sync::Arc<tokio::sync::RwLock<std::collections::HashMap<String, WalletBalance>>>,
+    registry: Arc<RwLock<WalletRegistry>>,
+    rng: Arc<dyn RandomSource>,
+    clock: Arc<dyn Clock>,
+}
+
+/// Remove an id reservation if key generation fails before the wallet is registered
+struct Reservation<'a> {
+    registry: &'a Arc<RwLock<WalletRegistry>>,
+    wallet_id: String,
+    committed: bool,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        let registry = Arc::clone(self.registry);
+        let wallet_id = std::mem::take(&mut self.wallet_id);
+        match registry.try_write() {
+            Ok(mut registry) => {
+                registry.reserved.remove(&wallet_id);
+            }
+            Err(_) => {
+                if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                    handle.spawn(async move {
+                        registry.write().await.reserved.remove(&wallet_id);
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Storage id of a wallet's BIP39 seed
+fn seed_id(wallet_id: &str) -> String {
+    format!("wallet_seed_{}", wallet_id)
+}
+
+/// Storage id of an account key; account 0 keeps the wallet's primary key id
+fn account_key_id(wallet_id: &str, account_index: u32) -> String {
+    if account_index == 0 {
+        format!("wallet_key_{}", wallet_id)
+    } else {
+        format!("wallet_key_{}_account_{}", wallet_id, account_index)
+    }
 }
 
 impl WalletManager {
     pub fn new() -> Self {
         Self {
-            wallets: std::sync::Arc::new(tokio::sync::RwLock::new(std::collections::HashMap::new())),
-            balances: std::sync::Arc::new(tokio::sync::RwLock::new(std::collections::HashMap::new())),
+            registry: Arc::new(RwLock::new(WalletRegistry::default())),
+            rng: default_random_source(),
+            clock: default_clock(),
         }
     }
 
+    /// Generate wallet keys from `rng` instead of the OS
+    pub fn with_random_source(mut self, rng: Arc<dyn RandomSource>) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// Stamp new wallets with `clock` instead of the system time
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Claim a wallet id for creation; fails if it exists or is being created
+    async fn reserve(&self, wallet_id: &str) -> Result<Reservation<'_>, WalletError> {
+        let mut registry = self.registry.write().await;
+        if registry.wallets.contains_key(wallet_id) || !registry.reserved.insert(wallet_id.to_string()) {
+            return Err(WalletError::wallet_already_exists(format!("Wallet {} already exists", wallet_id)));
+        }
+        Ok(Reservation {
+            registry: &self.registry,
+            wallet_id: wallet_id.to_string(),
+            committed: false,
+        })
+    }
+
     /// Create a new wallet
     pub async fn create_wallet(
         &self,
@@ -30,52 +162,360 @@ impl WalletManager {
         name: &str,
         network: Network,
     ) -> Result<SecureWallet, WalletError> {
-        // Initialize secure file storage and key manager
-        let file_storage = crate::infrastructure::platform::FileStorage::new()?;
-        let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
+        let mut reservation = self.reserve(wallet_id).await?;
 
-        // Derive deterministic key id from wallet id
-        let key_id = format!("wallet_key_{}", wallet_id);
+        let address = {
+            // Initialize secure file storage and key manager
+            let file_storage = crate::infrastructure::platform::FileStorage::new()?;
+            let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage)
+                .with_random_source(Arc::clone(&self.rng));
 
-        // Generate a private key and derive public key and address
-        let private_key = key_manager.generate_private_key(&key_id)?;
-        let public_key = key_manager.get_public_key(&private_key)?;
-        let address = key_manager.get_address(&public_key)?;
+            // Derive deterministic key id from wallet id
+            let key_id = format!("wallet_key_{}", wallet_id);
+
+            // Generate a private key and derive public key and address
+            let private_key = key_manager.generate_private_key(&key_id)?;
+            let public_key = key_manager.get_public_key(&private_key)?;
+            key_manager.get_address(&public_key)?
+        };
 
         // Construct secure wallet entity
-        let wallet = SecureWallet::new(
+        let now = self.clock.unix_timestamp();
+        let wallet = SecureWallet::new_at(
             wallet_id.to_string(),
             name.to_string(),
             address,
             network.clone(),
+            now,
+        );
+
+        // Persist in manager state; the balance cache starts at zero until an on-chain fetch updates it
+        self.registry.write().await.insert_wallet(SecureWallet::new_at(
+            wallet.id.clone(),
+            wallet.name.clone(),
+            wallet.address.clone(),
+            wallet.network.clone(),
+            now,
+        ));
+        reservation.committed = true;
+
+        Ok(wallet)
+    }
+
+    /// Create a wallet whose key is generated inside `enclave` and never
+    /// leaves it. Only the public key is read back; every transaction is
+    /// signed by the enclave. Needs an enclave that supports secp256k1.
+    pub async fn create_hardware_wallet(
+        &self,
+        wallet_id: &str,
+        name: &str,
+        network: Network,
+        enclave: Arc<dyn SecureEnclave>,
+    ) -> Result<SecureWallet, WalletError> {
+        let mut reservation = self.reserve(wallet_id).await?;
+
+        let backend = Arc::new(EnclaveBackend::generate(format!("wallet_enclave_key_{}", wallet_id), enclave)?);
+        let address = backend.address().await?;
+
+        let wallet = SecureWallet::new_at(
+            wallet_id.to_string(),
+            name.to_string(),
+            address,
+            network,
+            self.clock.unix_timestamp(),
         );
 
-        // Persist in manager state
+        let mut registry = self.registry.write().await;
+        registry.insert_wallet(SecureWallet::new_at(
+            wallet.id.clone(),
+            wallet.name.clone(),
+            wallet.address.clone(),
+            wallet.network.clone(),
+            wallet.created_at,
+        ));
+        registry.signers.insert(wallet_id.to_string(), backend.clone());
+        registry.enclave_keys.insert(wallet_id.to_string(), backend);
+        reservation.committed = true;
+
+        Ok(wallet)
+    }
+
+    /// Pre-import hook: flag publicly known, repetitive or low-entropy seed
+    /// phrases so the app can warn before the wallet is funded
+    pub fn pre_import_check(&self, seed_phrase: &str) -> Result<SeedPhraseReport, WalletError> {
+        let report = check_seed_phrase(seed_phrase)?;
+        for warning in &report.warnings {
+            log::warn!("Pre-import check for seed phrase: {}", warning.message);
+        }
+        Ok(report)
+    }
+
+    /// Import an HD wallet from a seed phrase. The seed is kept in secure
+    /// storage so more accounts can be derived; account 0 becomes the wallet's key.
+    /// Importing a seed that is already loaded fails with `WalletAlreadyExists`.
+    pub async fn import_wallet(
+        &self,
+        wallet_id: &str,
+        name: &str,
+        seed_phrase: &str,
+        network: Network,
+    ) -> Result<SecureWallet, WalletError> {
+        self.pre_import_check(seed_phrase)?;
         {
-            let mut wallets = self.wallets.write().await;
-            wallets.insert(wallet_id.to_string(), SecureWallet::new(
-                wallet.id.clone(),
-                wallet.name.clone(),
-                wallet.address.clone(),
-                wallet.network.clone(),
-            ));
+            let file_storage = crate::infrastructure::platform::FileStorage::new()?;
+            let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
+            if file_storage.exists(&seed_id(wallet_id))? && !key_manager.seed_matches(&seed_id(wallet_id), seed_phrase)? {
+                return Err(WalletError::crypto(format!("Wallet id collision: {} already belongs to a different seed", wallet_id)));
+            }
+        }
+        let mut reservation = self.reserve(wallet_id).await?;
+        {
+            let file_storage = crate::infrastructure::platform::FileStorage::new()?;
+            let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
+            key_manager.store_seed(&seed_id(wallet_id), seed_phrase)?;
         }
 
-        // Initialize balance cache with zero until on-chain fetch updates it
+        let account = self.derive_and_record(wallet_id, 0).await?;
+        let now = self.clock.unix_timestamp();
+        self.registry.write().await.insert_wallet(SecureWallet::new_at(
+            wallet_id.to_string(),
+            name.to_string(),
+            account.address.clone(),
+            network.clone(),
+            now,
+        ));
+        reservation.committed = true;
+
+        Ok(SecureWallet::new_at(wallet_id.to_string(), name.to_string(), account.address, network, now))
+    }
+
+    /// Import a wallet from a V3 JSON keystore, such as one exported by geth
+    /// or MetaMask. The key becomes the wallet's primary key; a keystore
+    /// wallet has no seed, so further accounts cannot be derived. Importing
+    /// a key that is already loaded fails with `WalletAlreadyExists`.
+    pub async fn import_keystore(
+        &self,
+        name: &str,
+        keystore: &Keystore,
+        password: &str,
+        network: Network,
+    ) -> Result<SecureWallet, WalletError> {
+        let private_key_bytes = decrypt_keystore(keystore, password)?;
+        // The keystore's own id is unchecked input; the key decides the wallet
+        let wallet_id = &crate::core::crypto::keys::KeyManager::wallet_id_from_private_key(&private_key_bytes)?;
+        let mut reservation = self.reserve(wallet_id).await?;
+        let address = {
+            let file_storage = crate::infrastructure::platform::FileStorage::new()?;
+            let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
+            let private_key = key_manager.import_private_key(&account_key_id(wallet_id, 0), &private_key_bytes)?;
+            let public_key = key_manager.get_public_key(&private_key)?;
+            key_manager.get_address(&public_key)?
+        };
+
+        let now = self.clock.unix_timestamp();
+        self.registry.write().await.insert_wallet(SecureWallet::new_at(
+            wallet_id.to_string(),
+            name.to_string(),
+            address.clone(),
+            network.clone(),
+            now,
+        ));
+        reservation.committed = true;
+
+        Ok(SecureWallet::new_at(wallet_id.to_string(), name.to_string(), address, network, now))
+    }
+
+    /// Export a wallet's primary key as a V3 JSON keystore encrypted with
+    /// `password`
+    pub async fn export_keystore(&self, wallet_id: &str, password: &str, params: ScryptParams) -> Result<Keystore, WalletError> {
+        if !self.registry.read().await.wallets.contains_key(wallet_id) {
+            return Err(WalletError::wallet_not_found(format!("Wallet not found: {}", wallet_id)));
+        }
+        let file_storage = crate::infrastructure::platform::FileStorage::new()?;
+        let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
+        let private_key = key_manager.get_private_key(&account_key_id(wallet_id, 0))?;
+        private_key.with_key(&file_storage, |key_bytes| {
+            encrypt_keystore(key_bytes, password, params, self.rng.as_ref())
+        })
+    }
+
+    /// Split a wallet's seed into `count` SLIP-39 mnemonic shares, any
+    /// `threshold` of which restore it with `restore_from_shares`. Only
+    /// wallets imported from a seed phrase have a seed to split.
+    pub async fn backup_wallet_shares(
+        &self,
+        wallet_id: &str,
+        threshold: u8,
+        count: u8,
+        passphrase: &str,
+    ) -> Result<Vec<Zeroizing<String>>, WalletError> {
+        if !self.registry.read().await.wallets.contains_key(wallet_id) {
+            return Err(WalletError::wallet_not_found(format!("Wallet not found: {}", wallet_id)));
+        }
+        let file_storage = crate::infrastructure::platform::FileStorage::new()?;
+        if !file_storage.exists(&seed_id(wallet_id))? {
+            return Err(WalletError::validation(format!("Wallet {} was not created from a seed phrase", wallet_id)));
+        }
+        let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
+        let seed = key_manager.get_seed(&seed_id(wallet_id))?;
+        split_master_secret(&seed, threshold, count, passphrase, self.rng.as_ref())
+    }
+
+    /// Restore an HD wallet from SLIP-39 shares made by
+    /// `backup_wallet_shares`. A wrong passphrase restores a different,
+    /// empty wallet rather than failing, as SLIP-39 intends. The wallet id
+    /// is derived from the recovered seed, so restoring a wallet that is
+    /// already loaded fails with `WalletAlreadyExists`.
+    pub async fn restore_from_shares<S: AsRef<str>>(
+        &self,
+        name: &str,
+        shares: &[S],
+        passphrase: &str,
+        network: Network,
+    ) -> Result<SecureWallet, WalletError> {
+        let seed = combine_mnemonics(shares, passphrase)?;
+        if seed.len() != HD_SEED_SIZE {
+            return Err(WalletError::validation("Shares do not hold a wallet seed"));
+        }
+        let wallet_id = &crate::core::crypto::keys::KeyManager::wallet_id_from_seed_bytes(&seed)?;
+        let mut reservation = self.reserve(wallet_id).await?;
         {
-            let mut balances = self.balances.write().await;
-            let currency = network.native_currency().to_string();
-            let balance = WalletBalance::new(wallet_id.to_string(), network.clone(), "0".to_string(), currency);
-            balances.insert(wallet_id.to_string(), balance);
+            let file_storage = crate::infrastructure::platform::FileStorage::new()?;
+            let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
+            key_manager.store_seed_bytes(&seed_id(wallet_id), &seed)?;
         }
 
-        Ok(wallet)
+        let account = self.derive_and_record(wallet_id, 0).await?;
+        let now = self.clock.unix_timestamp();
+        self.registry.write().await.insert_wallet(SecureWallet::new_at(
+            wallet_id.to_string(),
+            name.to_string(),
+            account.address.clone(),
+            network.clone(),
+            now,
+        ));
+        reservation.committed = true;
+
+        Ok(SecureWallet::new_at(wallet_id.to_string(), name.to_string(), account.address, network, now))
+    }
+
+    /// Derive (or re-derive) the account at `m/44'/60'/account_index'/0/0`
+    pub async fn derive_account(&self, wallet_id: &str, account_index: u32) -> Result<HdAccount, WalletError> {
+        if !self.registry.read().await.wallets.contains_key(wallet_id) {
+            return Err(WalletError::wallet_not_found(format!("Wallet not found: {}", wallet_id)));
+        }
+        self.derive_and_record(wallet_id, account_index).await
+    }
+
+    async fn derive_and_record(&self, wallet_id: &str, account_index: u32) -> Result<HdAccount, WalletError> {
+        let address = {
+            let file_storage = crate::infrastructure::platform::FileStorage::new()?;
+            let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
+            if !file_storage.exists(&seed_id(wallet_id))? {
+                return Err(WalletError::validation(format!("Wallet {} was not created from a seed phrase", wallet_id)));
+            }
+
+            let private_key = key_manager.derive_account_key(
+                &seed_id(wallet_id),
+                account_index,
+                0,
+                &account_key_id(wallet_id, account_index),
+            )?;
+            let public_key = key_manager.get_public_key(&private_key)?;
+            key_manager.get_address(&public_key)?
+        };
+        let account = HdAccount {
+            wallet_id: wallet_id.to_string(),
+            account_index,
+            derivation_path: bip44_path(account_index, 0),
+            address,
+        };
+
+        let mut registry = self.registry.write().await;
+        let wallet_accounts = registry.accounts.entry(wallet_id.to_string()).or_default();
+        wallet_accounts.retain(|a| a.account_index != account_index);
+        wallet_accounts.push(account.clone());
+        wallet_accounts.sort_by_key(|a| a.account_index);
+        Ok(account)
+    }
+
+    /// All derived accounts, grouped by wallet and ordered by account index
+    pub async fn list_accounts(&self) -> Vec<HdAccount> {
+        let registry = self.registry.read().await;
+        let accounts = &registry.accounts;
+        let mut wallet_ids: Vec<&String> = accounts.keys().collect();
+        wallet_ids.sort();
+        wallet_ids.into_iter()
+            .flat_map(|id| accounts[id].iter().cloned())
+            .collect()
+    }
+
+    /// Sign a message with a derived account's key
+    pub async fn sign_account_message(&self, wallet_id: &str, account_index: u32, message: &str) -> Result<String, WalletError> {
+        let file_storage = crate::infrastructure::platform::FileStorage::new()?;
+        let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
+        let private_key = key_manager.get_private_key(&account_key_id(wallet_id, account_index))?;
+        key_manager.sign_message(&private_key, message)
+    }
+
+    /// Save a contact in a wallet's address book
+    pub async fn add_address_book_entry(&self, wallet_id: &str, address: &str, label: &str) -> Result<(), WalletError> {
+        let mut registry = self.registry.write().await;
+        if !registry.wallets.contains_key(wallet_id) {
+            return Err(WalletError::wallet_not_found(format!("Wallet not found: {}", wallet_id)));
+        }
+        registry.address_books.entry(wallet_id.to_string()).or_default().add_entry(address, label)
+    }
+
+    pub async fn address_book(&self, wallet_id: &str) -> Vec<AddressBookEntry> {
+        self.registry.read().await.address_books.get(wallet_id)
+            .map(|book| book.entries().to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Pre-sign hook: check a transaction's recipient against the wallet's
+    /// address book and recent counterparties for lookalike addresses
+    pub async fn pre_sign_check(&self, wallet_id: &str, transaction: &Transaction) -> Result<PreSignReport, WalletError> {
+        let registry = self.registry.read().await;
+        let report = match registry.address_books.get(wallet_id) {
+            Some(book) => book.check(&transaction.to)?,
+            None => AddressBook::new().check(&transaction.to)?,
+        };
+        for warning in &report.warnings {
+            log::warn!("Pre-sign check for wallet {}: {}", wallet_id, warning.message);
+        }
+        Ok(report)
+    }
+
+    /// Register a transaction template, replacing any of the same name on
+    /// its network
+    pub async fn register_transaction_template(&self, template: TransactionTemplate) -> Result<(), WalletError> {
+        self.registry.write().await.templates.register(template)
+    }
+
+    pub async fn remove_transaction_template(&self, network: &Network, name: &str) -> bool {
+        self.registry.write().await.templates.remove(network, name)
+    }
+
+    /// Templates registered for a network
+    pub async fn transaction_templates(&self, network: &Network) -> Vec<TransactionTemplate> {
+        self.registry.read().await.templates.for_network(network).into_iter().cloned().collect()
+    }
+
+    /// Instantiate a template on the wallet's network. Set the nonce and
+    /// fees on the returned builder, then pass the transaction to
+    /// `sign_transaction`.
+    pub async fn template_transaction(&self, wallet_id: &str, name: &str, input: &TemplateInput) -> Result<TransactionBuilder, WalletError> {
+        let registry = self.registry.read().await;
+        let wallet = registry.wallets.get(wallet_id)
+            .ok_or_else(|| WalletError::wallet_not_found(format!("Wallet not found: {}", wallet_id)))?;
+        registry.templates.get(&wallet.network, name)?.builder(input)
     }
 
     /// Get a wallet by ID
     pub async fn get_wallet(&self, wallet_id: &str) -> Result<SecureWallet, WalletError> {
-        let wallets = self.wallets.read().await;
-        wallets.get(wallet_id)
+        let registry = self.registry.read().await;
+        registry.wallets.get(wallet_id)
             .map(|w| SecureWallet::new(
                 w.id.clone(),
                 w.name.clone(),
@@ -84,29 +524,62 @@ impl WalletManager {
             ))
             .ok_or_else(|| WalletError::wallet_not_found(format!("Wallet not found: {}", wallet_id)))
     }
-    
+
+    /// All wallets held by this manager, oldest first
+    pub async fn list_wallets(&self) -> Vec<SecureWallet> {
+        let registry = self.registry.read().await;
+        let mut wallets: Vec<SecureWallet> = registry.wallets.values()
+            .map(|w| SecureWallet::new_at(w.id.clone(), w.name.clone(), w.address.clone(), w.network.clone(), w.created_at))
+            .collect();
+        wallets.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        wallets
+    }
+
+    /// Remove a wallet and erase its keys, derived account keys and seed
+    /// from platform storage
+    pub async fn delete_wallet(&self, wallet_id: &str) -> Result<(), WalletError> {
+        let (accounts, enclave_key) = {
+            let mut registry = self.registry.write().await;
+            if registry.wallets.remove(wallet_id).is_none() {
+                return Err(WalletError::wallet_not_found(format!("Wallet not found: {}", wallet_id)));
+            }
+            registry.balances.remove(wallet_id);
+            registry.address_books.remove(wallet_id);
+            registry.signers.remove(wallet_id);
+            (registry.accounts.remove(wallet_id).unwrap_or_default(), registry.enclave_keys.remove(wallet_id))
+        };
+
+        if let Some(backend) = enclave_key {
+            return backend.delete_key();
+        }
+
+        let file_storage = crate::infrastructure::platform::FileStorage::new()?;
+        let mut account_indices: Vec<u32> = accounts.iter().map(|a| a.account_index).collect();
+        if !account_indices.contains(&0) {
+            account_indices.push(0);
+        }
+        for account_index in account_indices {
+            file_storage.delete(&account_key_id(wallet_id, account_index))?;
+        }
+        if file_storage.exists(&seed_id(wallet_id))? {
+            file_storage.delete(&seed_id(wallet_id))?;
+        }
+        Ok(())
+    }
+
     /// Get wallet balance (queries RPC by network and updates cache)
     pub async fn get_balance(&self, wallet_id: &str) -> Result<String, WalletError> {
         // Resolve wallet, network, and address
         let (address, network) = {
-            let wallets = self.wallets.read().await;
-            let wallet = wallets
+            let registry = self.registry.read().await;
+            let wallet = registry.wallets
                 .get(wallet_id)
                 .ok_or_else(|| WalletError::wallet_not_found(format!("Wallet not found: {}", wallet_id)))?;
             (wallet.address.clone(), wallet.network.clone())
         };
 
         // Resolve RPC URL via env override or network defaults
-        let rpc_url = match network {
-            Network::CoreTestnet => std::env::var("WALLET_CORE_RPC_CORE_TESTNET")
-                .unwrap_or_else(|_| Network::CoreTestnet.rpc_url().to_string()),
-            Network::BaseSepolia => std::env::var("WALLET_CORE_RPC_BASE_SEPOLIA")
-                .unwrap_or_else(|_| Network::BaseSepolia.rpc_url().to_string()),
-            Network::LiskSepolia => std::env::var("WALLET_CORE_RPC_LISK_SEPOLIA")
-                .map_err(|_| WalletError::config("RPC URL not set for Lisk Sepolia"))?,
-            Network::EthereumHolesky => std::env::var("WALLET_CORE_RPC_HOLESKY")
-                .map_err(|_| WalletError::config("RPC URL not set for Holesky"))?,
-        };
+        let rpc_url = rpc_url_for(&network)?;
 
         // Query eth_getBalance
         let client = Client::new();
@@ -145,15 +618,42 @@ impl WalletManager {
 
         // Update cache
         {
-            let mut balances = self.balances.write().await;
             let currency = network.native_currency().to_string();
             let balance = WalletBalance::new(wallet_id.to_string(), network.clone(), dec_balance.clone(), currency);
-            balances.insert(wallet_id.to_string(), balance);
+            self.registry.write().await.balances.insert(wallet_id.to_string(), balance);
         }
 
         Ok(dec_balance)
     }
 
+    /// Native and token balances of a wallet's address on every network
+    /// `balances` is configured for, with stale markers from its cache.
+    /// The wallet's own network entry also refreshes `get_balance`'s cache.
+    pub async fn get_token_balances(&self, wallet_id: &str, balances: &BalanceService) -> Result<Vec<NetworkBalances>, WalletError> {
+        let address = {
+            let registry = self.registry.read().await;
+            registry.wallets
+                .get(wallet_id)
+                .ok_or_else(|| WalletError::wallet_not_found(format!("Wallet not found: {}", wallet_id)))?
+                .address
+                .clone()
+        };
+        let network_balances = balances.balances(&address).await?;
+
+        let mut registry = self.registry.write().await;
+        if let Some(wallet) = registry.wallets.get(wallet_id) {
+            let network = wallet.network.clone();
+            let native = network_balances.iter()
+                .filter(|b| b.network == network && !b.stale)
+                .find_map(|b| b.balances.first());
+            if let Some(native) = native {
+                let balance = WalletBalance::new(wallet_id.to_string(), network.clone(), native.balance.clone(), native.token.symbol.clone());
+                registry.balances.insert(wallet_id.to_string(), balance);
+            }
+        }
+        Ok(network_balances)
+    }
+
     /// Sign a message using a wallet's private key
     pub async fn sign_message(&self, wallet_id: &str, message: &str) -> Result<String, WalletError> {
         // Get secure storage and key manager
@@ -168,15 +668,54 @@ impl WalletManager {
         key_manager.sign_message(&private_key, message)
     }
 
-    /// Sign and broadcast a transaction using the wallet's private key
-    pub async fn send_transaction(&self, wallet_id: &str, transaction: Transaction) -> Result<SignedTransaction, WalletError> {
-        // Resolve wallet and network
-        let (network, rpc_url) = {
-            let wallets = self.wallets.read().await;
-            let wallet = wallets.get(wallet_id)
+    /// Encode a payment request to this wallet as a signed QR payload
+    pub async fn payment_qr(&self, wallet_id: &str, request: &PaymentRequest) -> Result<String, WalletError> {
+        let file_storage = crate::infrastructure::platform::FileStorage::new()?;
+        let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
+        let private_key = key_manager.get_private_key(&format!("wallet_key_{}", wallet_id))?;
+        private_key.with_key(&file_storage, |key_bytes| crate::core::payments::encode_payment_qr(request, key_bytes))
+    }
+
+    /// Encode a request for a payer-chosen amount to this wallet as a signed
+    /// QR payload
+    pub async fn open_payment_qr(&self, wallet_id: &str, request: &OpenPaymentRequest) -> Result<String, WalletError> {
+        let file_storage = crate::infrastructure::platform::FileStorage::new()?;
+        let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
+        let private_key = key_manager.get_private_key(&format!("wallet_key_{}", wallet_id))?;
+        private_key.with_key(&file_storage, |key_bytes| crate::core::payments::encode_open_payment_qr(request, key_bytes))
+    }
+
+    /// Route a wallet's transaction signing to an external signer, such as a
+    /// Ledger. The signer must hold the key for the wallet's address.
+    pub async fn set_signing_backend(&self, wallet_id: &str, backend: Arc<dyn SigningBackend>) -> Result<(), WalletError> {
+        let wallet_address = self.registry.read().await.wallets.get(wallet_id)
+            .map(|wallet| wallet.address.clone())
+            .ok_or_else(|| WalletError::wallet_not_found(format!("Wallet not found: {}", wallet_id)))?;
+        let signer_address = backend.address().await?;
+        if !signer_address.eq_ignore_ascii_case(&wallet_address) {
+            return Err(WalletError::validation(format!(
+                "{} signer holds {}, not wallet address {}", backend.name(), signer_address, wallet_address
+            )));
+        }
+        self.registry.write().await.signers.insert(wallet_id.to_string(), backend);
+        Ok(())
+    }
+
+    /// Go back to signing with the wallet's key in platform storage
+    pub async fn clear_signing_backend(&self, wallet_id: &str) {
+        self.registry.write().await.signers.remove(wallet_id);
+    }
+
+    /// Sign a transaction with the wallet's signer: its external backend if
+    /// one is set, otherwise its private key in platform storage
+    pub async fn sign_transaction(&self, wallet_id: &str, transaction: &Transaction) -> Result<SignedTransaction, WalletError> {
+        // Resolve wallet, network and signer
+        let (network, rpc_url, backend) = {
+            let registry = self.registry.read().await;
+            let wallet = registry.wallets.get(wallet_id)
                 .ok_or_else(|| WalletError::wallet_not_found(format!("Wallet not found: {}", wallet_id)))?;
             let rpc = wallet.network.rpc_url().to_string();
-            (wallet.network.clone(), rpc)
+            (wallet.network.clone(), rpc, registry.signers.get(wallet_id).cloned())
         };
 
         // Validate chain id alignment
@@ -184,33 +723,94 @@ impl WalletManager {
             return Err(WalletError::validation("Transaction chain_id does not match wallet network"));
         }
 
-        // Prepare signing/storage
+        // Refuse lookalike recipients; saving the address to the address book clears the warning
+        let report = self.pre_sign_check(wallet_id, transaction).await?;
+        if let Some(warning) = report.warnings.first() {
+            return Err(WalletError::validation(format!("{}; add it to the address book if it is intended", warning.message)));
+        }
+
+        let tx_manager = crate::core::transactions::TransactionManager::new(rpc_url);
+        if let Some(backend) = backend {
+            return tx_manager.sign_with_backend(transaction, backend.as_ref()).await;
+        }
+
+        // Sign with the key in platform storage
         let file_storage = crate::infrastructure::platform::FileStorage::new()?;
         let key_id = format!("wallet_key_{}", wallet_id);
+        tx_manager.sign_transaction(transaction, &key_id, &file_storage).await
+    }
 
-        // Sign using the transaction manager
-        let tx_manager = crate::core::transactions::TransactionManager::new(rpc_url);
-        let mut signed = tx_manager
-            .sign_transaction(&transaction, &key_id, &file_storage)
-            .await?;
+    /// `sign_transaction`, first prompting for biometrics when the wallet's
+    /// signing policy asks for it
+    pub async fn sign_transaction_with_policy(
+        &self,
+        wallet_id: &str,
+        transaction: &Transaction,
+        policies: &SigningPolicyStore<'_>,
+        biometric: &dyn BiometricAuth,
+    ) -> Result<SignedTransaction, WalletError> {
+        let report = self.pre_sign_check(wallet_id, transaction).await?;
+        let reasons = policies.authorize(wallet_id, transaction, report.known, biometric).await?;
+        if !reasons.is_empty() {
+            log::info!("Wallet {} authenticated for signing: {:?}", wallet_id, reasons);
+        }
+        self.sign_transaction(wallet_id, transaction).await
+    }
+
+    /// Sign and broadcast a transaction with the wallet's signer
+    pub async fn send_transaction(&self, wallet_id: &str, transaction: Transaction) -> Result<SignedTransaction, WalletError> {
+        let signed = self.sign_transaction(wallet_id, &transaction).await?;
+        self.broadcast_signed(wallet_id, signed).await
+    }
+
+    /// `send_transaction`, recording the transaction in `history` once it is
+    /// signed and again once it is broadcast. `token` is the asset moved.
+    pub async fn send_transaction_recorded(
+        &self,
+        wallet_id: &str,
+        transaction: Transaction,
+        token: TokenInfo,
+        history: &TransactionHistory<'_>,
+    ) -> Result<SignedTransaction, WalletError> {
+        let signed = self.sign_transaction(wallet_id, &transaction).await?;
+        let entry = history.record_sent(wallet_id, &signed, token, HistoryStatus::Signed).await?;
+        let sent = self.broadcast_signed(wallet_id, signed).await?;
+        history.mark_submitted(entry.chain_id, &entry.hash, None).await?;
+        Ok(sent)
+    }
+
+    async fn broadcast_signed(&self, wallet_id: &str, mut signed: SignedTransaction) -> Result<SignedTransaction, WalletError> {
+        let rpc_url = {
+            let registry = self.registry.read().await;
+            let wallet = registry.wallets.get(wallet_id)
+                .ok_or_else(|| WalletError::wallet_not_found(format!("Wallet not found: {}", wallet_id)))?;
+            wallet.network.rpc_url().to_string()
+        };
 
         // Broadcast and attach returned hash
+        let tx_manager = crate::core::transactions::TransactionManager::new(rpc_url);
         let tx_hash = tx_manager.send_transaction(&signed).await?;
         signed.hash = tx_hash;
+
+        self.registry.write().await
+            .address_books.entry(wallet_id.to_string()).or_default()
+            .record_counterparty(&signed.transaction.to)?;
         Ok(signed)
     }
 
-    /// Get transaction history
-    pub async fn get_transaction_history(&self, _wallet_id: &str) -> Result<Vec<SignedTransaction>, WalletError> {
-        // Transaction history requires an indexer or third-party API; JSON-RPC alone cannot query by address efficiently.
-        Err(WalletError::not_implemented("Transaction history requires an indexer; not supported via JSON-RPC only"))
+    /// Transactions recorded for a wallet in `history`, newest first
+    pub async fn get_transaction_history(&self, wallet_id: &str, history: &TransactionHistory<'_>) -> Result<Vec<HistoryEntry>, WalletError> {
+        if !self.registry.read().await.wallets.contains_key(wallet_id) {
+            return Err(WalletError::wallet_not_found(format!("Wallet not found: {}", wallet_id)));
+        }
+        history.query(&HistoryQuery::new().wallet(wallet_id)).await
     }
 
     /// Update wallet balance (uses wallet's configured network and currency)
     pub async fn update_balance(&self, wallet_id: &str, balance: String) -> Result<(), WalletError> {
+        let mut registry = self.registry.write().await;
         let (network, currency) = {
-            let wallets = self.wallets.read().await;
-            if let Some(wallet) = wallets.get(wallet_id) {
+            if let Some(wallet) = registry.wallets.get(wallet_id) {
                 let n = wallet.network.clone();
                 let c = n.native_currency().to_string();
                 (n, c)
@@ -221,14 +821,13 @@ impl WalletManager {
             }
         };
 
-        let mut balances = self.balances.write().await;
         let wallet_balance = WalletBalance::new(
             wallet_id.to_string(),
             network,
             balance,
             currency,
         );
-        balances.insert(wallet_id.to_string(), wallet_balance);
+        registry.balances.insert(wallet_id.to_string(), wallet_balance);
         Ok(())
     }
 }
@@ -258,14 +857,41 @@ mod tests {
             .expect("Failed to update wallet balance");
         
         // Get the balance from cache (not from blockchain)
-        let balances = manager.balances.read().await;
-        if let Some(balance) = balances.get("test_wallet") {
+        let registry = manager.registry.read().await;
+        if let Some(balance) = registry.balances.get("test_wallet") {
             assert_eq!(balance.amount, "1000000");
         } else {
             panic!("Balance not found in cache");
         }
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_create_same_id() {
+        let manager = WalletManager::new();
+        let wallet_id = format!("race_wallet_{}", uuid::Uuid::new_v4());
+
+        let tasks: Vec<_> = (0..8).map(|_| {
+            let manager = manager.clone();
+            let wallet_id = wallet_id.clone();
+            tokio::spawn(async move {
+                manager.create_wallet(&wallet_id, "Race Wallet", Network::CoreTestnet).await
+            })
+        }).collect();
+
+        let mut created = 0;
+        for task in tasks {
+            match task.await.expect("Task panicked") {
+                Ok(_) => created += 1,
+                Err(e) => assert!(matches!(e, WalletError::WalletAlreadyExists(_))),
+            }
+        }
+        assert_eq!(created, 1);
+
+        let registry = manager.registry.read().await;
+        assert!(registry.balances.contains_key(&wallet_id));
+        assert!(registry.reserved.is_empty());
+    }
+
     #[tokio::test]
     async fn test_wallet_not_found() {
         let manager = WalletManager::new();
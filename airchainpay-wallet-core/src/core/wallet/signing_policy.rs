@@ -0,0 +1,381 @@
+//! Biometric signing policies
+//!
+//! A wallet's `SigningPolicy` decides when signing needs the user's
+//! biometrics: transfers above a per-token amount, payments to a recipient
+//! that is not in the address book or recent counterparties, and the first
+//! signature after the session has been idle too long. Policies are kept
+//! encrypted in `SecureStorage`; sessions live in memory, so restarting the
+//! app always starts a new one.
+
+use crate::core::storage::SecureStorage;
+use crate::core::transactions::history::{decode_transfer, parse_units};
+use crate::infrastructure::platform::{BiometricAuth, PlatformStorage};
+use crate::shared::error::WalletError;
+use crate::shared::sources::{default_clock, Clock};
+use crate::shared::types::{Address, Amount, Transaction};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use zeroize::Zeroizing;
+
+fn policy_key(wallet_id: &str) -> String {
+    format!("signing_policy_{}", wallet_id)
+}
+
+/// When signing for a wallet needs biometric authentication
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SigningPolicy {
+    /// Native value, in wei, above which signing asks for biometrics
+    #[serde(default)]
+    pub native_threshold: Option<Amount>,
+    /// ERC-20 transfer amounts, in the token's base units, keyed by the
+    /// lowercase token contract address
+    #[serde(default)]
+    pub token_thresholds: HashMap<Address, Amount>,
+    /// Ask before paying an address that is not saved or recently paid
+    #[serde(default)]
+    pub require_for_new_recipients: bool,
+    /// Ask again once this long has passed since the last authentication
+    #[serde(default)]
+    pub session_timeout_secs: Option<u64>,
+}
+
+impl SigningPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_native_threshold(mut self, wei: impl Into<Amount>) -> Self {
+        self.native_threshold = Some(wei.into());
+        self
+    }
+
+    pub fn with_token_threshold(mut self, token_address: &str, amount: impl Into<Amount>) -> Self {
+        self.token_thresholds.insert(token_address.to_lowercase(), amount.into());
+        self
+    }
+
+    pub fn with_new_recipient_check(mut self) -> Self {
+        self.require_for_new_recipients = true;
+        self
+    }
+
+    pub fn with_session_timeout(mut self, secs: u64) -> Self {
+        self.session_timeout_secs = Some(secs);
+        self
+    }
+
+    fn validate(&self) -> Result<(), WalletError> {
+        for amount in self.native_threshold.iter().chain(self.token_thresholds.values()) {
+            parse_units(amount)?;
+        }
+        if self.session_timeout_secs == Some(0) {
+            return Err(WalletError::validation("Session timeout must be greater than zero"));
+        }
+        Ok(())
+    }
+
+    /// Reasons `transaction` needs authentication. `recipient_known` is
+    /// whether the payee is in the address book or recent counterparties;
+    /// `last_authenticated` is the session's last successful authentication.
+    pub fn evaluate(
+        &self,
+        transaction: &Transaction,
+        recipient_known: bool,
+        last_authenticated: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<AuthReason>, WalletError> {
+        let mut reasons = Vec::new();
+
+        let value = parse_units(&transaction.value)?;
+        if let Some(threshold) = &self.native_threshold {
+            if value > parse_units(threshold)? {
+                reasons.push(AuthReason::AmountAboveThreshold {
+                    token: None,
+                    amount: value.to_string(),
+                    threshold: threshold.clone(),
+                });
+            }
+        }
+        let transfer = transaction.data.as_deref().and_then(|data| decode_transfer(data).ok());
+        if let Some((amount, _)) = &transfer {
+            let token = transaction.to.to_lowercase();
+            if let Some(threshold) = self.token_thresholds.get(&token) {
+                if parse_units(amount)? > parse_units(threshold)? {
+                    reasons.push(AuthReason::AmountAboveThreshold {
+                        token: Some(token),
+                        amount: amount.clone(),
+                        threshold: threshold.clone(),
+                    });
+                }
+            }
+        }
+
+        if self.require_for_new_recipients && !recipient_known {
+            let recipient = transfer.map_or_else(|| transaction.to.to_lowercase(), |(_, to)| to);
+            reasons.push(AuthReason::NewRecipient { recipient });
+        }
+
+        if let Some(timeout) = self.session_timeout_secs {
+            let expired = last_authenticated
+                .map_or(true, |at| now - at >= Duration::seconds(timeout.min(i64::MAX as u64) as i64));
+            if expired {
+                reasons.push(AuthReason::SessionExpired { timeout_secs: timeout });
+            }
+        }
+        Ok(reasons)
+    }
+}
+
+/// Why a signature needs biometric authentication
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AuthReason {
+    /// `token` is `None` for the native currency
+    AmountAboveThreshold { token: Option<Address>, amount: Amount, threshold: Amount },
+    NewRecipient { recipient: Address },
+    SessionExpired { timeout_secs: u64 },
+}
+
+impl AuthReason {
+    /// Text for the system biometric prompt
+    pub fn message(&self) -> String {
+        match self {
+            AuthReason::AmountAboveThreshold { .. } => "Confirm a payment above your approval limit".to_string(),
+            AuthReason::NewRecipient { recipient } => format!("Confirm a payment to new recipient {}", recipient),
+            AuthReason::SessionExpired { .. } => "Confirm it's you to continue signing".to_string(),
+        }
+    }
+}
+
+/// Signing policies of every wallet on the device and their sessions
+pub struct SigningPolicyStore<'a> {
+    platform: &'a dyn PlatformStorage,
+    storage: SecureStorage<'a>,
+    password: Zeroizing<String>,
+    clock: Arc<dyn Clock>,
+    /// Last successful authentication per wallet
+    sessions: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl<'a> SigningPolicyStore<'a> {
+    pub fn new(storage: &'a dyn PlatformStorage, password: &str) -> Self {
+        Self {
+            platform: storage,
+            storage: SecureStorage::new(storage),
+            password: Zeroizing::new(password.to_string()),
+            clock: default_clock(),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Time sessions with `clock` instead of the system time
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The wallet's policy; wallets without one never ask
+    pub async fn policy(&self, wallet_id: &str) -> Result<SigningPolicy, WalletError> {
+        let key = policy_key(wallet_id);
+        if !self.platform.exists(&key)? {
+            return Ok(SigningPolicy::default());
+        }
+        let bytes = self.storage.retrieve_data(&key, &self.password).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    pub async fn set_policy(&self, wallet_id: &str, policy: &SigningPolicy) -> Result<(), WalletError> {
+        policy.validate()?;
+        self.storage.store_data(&policy_key(wallet_id), &serde_json::to_vec(policy)?, &self.password).await
+    }
+
+    pub async fn remove_policy(&self, wallet_id: &str) -> Result<(), WalletError> {
+        self.end_session(wallet_id);
+        let key = policy_key(wallet_id);
+        if self.platform.exists(&key)? {
+            self.storage.delete_data(&key).await?;
+        }
+        Ok(())
+    }
+
+    /// Forget the wallet's last authentication, e.g. when the app locks
+    pub fn end_session(&self, wallet_id: &str) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.remove(wallet_id);
+        }
+    }
+
+    fn last_authenticated(&self, wallet_id: &str) -> Option<DateTime<Utc>> {
+        self.sessions.lock().ok()?.get(wallet_id).copied()
+    }
+
+    /// Reasons signing `transaction` for `wallet_id` needs authentication now
+    pub async fn evaluate(&self, wallet_id: &str, transaction: &Transaction, recipient_known: bool) -> Result<Vec<AuthReason>, WalletError> {
+        let policy = self.policy(wallet_id).await?;
+        policy.evaluate(transaction, recipient_known, self.last_authenticated(wallet_id), self.clock.now())
+    }
+
+    /// Prompt for biometrics if the policy requires it. Fails if the user
+    /// declines or biometrics are unavailable; a successful prompt starts a
+    /// new session. Returns the reasons that were authenticated.
+    pub async fn authorize(
+        &self,
+        wallet_id: &str,
+        transaction: &Transaction,
+        recipient_known: bool,
+        biometric: &dyn BiometricAuth,
+    ) -> Result<Vec<AuthReason>, WalletError> {
+        let reasons = self.evaluate(wallet_id, transaction, recipient_known).await?;
+        let Some(reason) = reasons.first() else {
+            return Ok(reasons);
+        };
+        if !biometric.is_available()? || !biometric.is_enabled()? {
+            return Err(WalletError::config(format!(
+                "Signing policy for wallet {} requires biometric authentication, which is not available", wallet_id
+            )));
+        }
+        if !biometric.authenticate(&reason.message())? {
+            return Err(WalletError::validation("Biometric authentication was declined"));
+        }
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.insert(wallet_id.to_string(), self.clock.now());
+        }
+        Ok(reasons)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::transactions::templates::ERC20_TRANSFER_SELECTOR;
+    use crate::shared::sources::FixedClock;
+    use crate::shared::types::TransactionType;
+    use chrono::TimeZone;
+    use std::cell::Cell;
+
+    #[derive(Default)]
+    struct MockStorage {
+        data: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    impl PlatformStorage for MockStorage {
+        fn store(&self, key: &str, data: &[u8]) -> Result<(), WalletError> {
+            self.data.lock().unwrap().insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        fn retrieve(&self, key: &str) -> Result<Vec<u8>, WalletError> {
+            self.data.lock().unwrap().get(key).cloned()
+                .ok_or_else(|| WalletError::storage("Key not found"))
+        }
+
+        fn delete(&self, key: &str) -> Result<(), WalletError> {
+            self.data.lock().unwrap().remove(key);
+            Ok(())
+        }
+
+        fn exists(&self, key: &str) -> Result<bool, WalletError> {
+            Ok(self.data.lock().unwrap().contains_key(key))
+        }
+
+        fn list_keys(&self) -> Result<Vec<String>, WalletError> {
+            Ok(self.data.lock().unwrap().keys().cloned().collect())
+        }
+    }
+
+    /// Accepts or declines every prompt and counts them
+    struct MockBiometric {
+        accept: bool,
+        prompts: Cell<usize>,
+    }
+
+    impl BiometricAuth for MockBiometric {
+        fn is_available(&self) -> Result<bool, WalletError> {
+            Ok(true)
+        }
+
+        fn authenticate(&self, _reason: &str) -> Result<bool, WalletError> {
+            self.prompts.set(self.prompts.get() + 1);
+            Ok(self.accept)
+        }
+
+        fn is_enabled(&self) -> Result<bool, WalletError> {
+            Ok(true)
+        }
+
+        fn enable(&self) -> Result<(), WalletError> {
+            Ok(())
+        }
+
+        fn disable(&self) -> Result<(), WalletError> {
+            Ok(())
+        }
+    }
+
+    fn transaction(to: &str, value: &str, data: Option<Vec<u8>>) -> Transaction {
+        Transaction {
+            to: to.to_string(),
+            value: value.to_string(),
+            data,
+            gas_limit: None,
+            gas_price: None,
+            nonce: None,
+            chain_id: 84532,
+            tx_type: TransactionType::Legacy,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_policy_prompts_for_large_amounts_new_recipients_and_idle_sessions() {
+        let storage = MockStorage::default();
+        let clock = Arc::new(FixedClock::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap()));
+        let store = SigningPolicyStore::new(&storage, "policy-password").with_clock(clock.clone());
+        let usdc = "0x036CbD53842c5426634e7929541eC2318f3dCF7e";
+        let policy = SigningPolicy::new()
+            .with_native_threshold("1000000000000000000")
+            .with_token_threshold(usdc, "100000000")
+            .with_new_recipient_check()
+            .with_session_timeout(300);
+        store.set_policy("wallet-1", &policy).await.unwrap();
+        assert_eq!(store.policy("wallet-1").await.unwrap(), policy);
+
+        let payee = "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6";
+        let small = transaction(payee, "1000", None);
+        let biometric = MockBiometric { accept: true, prompts: Cell::new(0) };
+
+        // No session yet
+        let reasons = store.authorize("wallet-1", &small, true, &biometric).await.unwrap();
+        assert_eq!(reasons, vec![AuthReason::SessionExpired { timeout_secs: 300 }]);
+        assert_eq!(biometric.prompts.get(), 1);
+
+        // Inside the session, a small payment to a known payee signs silently
+        clock.advance(Duration::seconds(60));
+        assert!(store.authorize("wallet-1", &small, true, &biometric).await.unwrap().is_empty());
+        assert_eq!(biometric.prompts.get(), 1);
+
+        // 250 USDC to an unknown address
+        let mut transfer = ERC20_TRANSFER_SELECTOR.to_vec();
+        transfer.extend_from_slice(&[0u8; 12]);
+        transfer.extend_from_slice(&[0x99; 20]);
+        let mut amount = [0u8; 32];
+        amount[28..].copy_from_slice(&250_000_000u32.to_be_bytes());
+        transfer.extend_from_slice(&amount);
+        let reasons = store.evaluate("wallet-1", &transaction(usdc, "0", Some(transfer)), false).await.unwrap();
+        assert_eq!(reasons.len(), 2);
+        assert!(matches!(&reasons[0], AuthReason::AmountAboveThreshold { token: Some(t), .. } if *t == usdc.to_lowercase()));
+        assert_eq!(reasons[1], AuthReason::NewRecipient { recipient: format!("0x{}", "99".repeat(20)) });
+
+        // Idle past the timeout, and a declined prompt refuses signing
+        clock.advance(Duration::seconds(300));
+        let declined = MockBiometric { accept: false, prompts: Cell::new(0) };
+        assert!(store.authorize("wallet-1", &small, true, &declined).await.is_err());
+        assert_eq!(declined.prompts.get(), 1);
+
+        store.remove_policy("wallet-1").await.unwrap();
+        assert!(store.evaluate("wallet-1", &transaction(payee, "5000000000000000000", None), false).await.unwrap().is_empty());
+    }
+}
@@ -108,7 +108,11 @@ impl SecureWallet {
             .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or_else(|_| std::time::Duration::from_secs(0))
             .as_secs();
-        
+        Self::new_at(id, name, address, network, now)
+    }
+
+    /// Create a secure wallet stamped with the given Unix time
+    pub fn new_at(id: String, name: String, address: Address, network: Network, now: u64) -> Self {
         Self {
             id,
             name,
@@ -236,6 +240,15 @@ impl WalletBalance {
     }
 }
 
+/// An account derived from an HD wallet's seed (no key material)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HdAccount {
+    pub wallet_id: String,
+    pub account_index: u32,
+    pub derivation_path: String,
+    pub address: Address,
+}
+
 /// Wallet backup information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WalletBackupInfo {
@@ -1,21 +1,88 @@
 //! FFI bindings for the wallet core
-//! 
+//!
 //! This module provides C-compatible function bindings for the wallet core.
 //! All functions are designed to be safe and handle errors gracefully.
-//! 
+//!
 //! SECURITY: This module implements hardened FFI boundaries with:
 //! - No raw string exposure of private keys
 //! - Secure memory management with zeroization
 //! - Input validation and sanitization
 //! - Error handling that doesn't leak sensitive information
+//!
+//! Every call returns a `SecureResult`. On failure `error_code` holds a
+//! `WalletCoreErrorCode` and, when the caller passes a non-null `error_out`,
+//! a UTF-8 description is written there. Free `data` and `*error_out` with
+//! `wallet_core_free_string`. The C header is generated by cbindgen into
+//! `include/airchainpay_wallet_core.h`.
+//!
+//! Wallets live in one process-wide `WalletManager`, so a wallet created or
+//! imported through one call is visible to the next; keys stay in platform
+//! storage.
 
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::ptr;
-use crate::domain::Wallet;
-use crate::shared::types::Network;
+use std::sync::{Mutex, OnceLock};
+use zeroize::Zeroizing;
+use crate::core::crypto::keys::KeyManager;
+use crate::core::wallet::WalletManager;
+use crate::domain::SecureWallet;
+use crate::infrastructure::platform::{KeyAttestation, KeyAttestationProvider, PlatformManager, MAX_ATTESTATION_CHALLENGE};
+use crate::shared::types::{Network, Transaction};
 use crate::shared::error::WalletError;
 
+/// Error codes returned in `SecureResult::error_code`. Values are part of
+/// the ABI: they are never renumbered or reused.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalletCoreErrorCode {
+    Ok = 0,
+    InvalidInput = 1,
+    InvalidNetwork = 2,
+    StorageUnavailable = 3,
+    KeyGenerationFailed = 4,
+    PublicKeyFailed = 5,
+    AddressFailed = 6,
+    WalletCreationFailed = 7,
+    SerializationFailed = 8,
+    InvalidSeedPhrase = 9,
+    SeedDerivationFailed = 10,
+    KeyNotFound = 11,
+    SigningFailed = 12,
+    ValidationFailed = 13,
+    DeletionFailed = 14,
+    StringConversionFailed = 15,
+    BalanceFetchFailed = 16,
+    AttestationUnavailable = 17,
+    AttestationFailed = 18,
+    WalletNotFound = 19,
+    WalletAlreadyExists = 20,
+    CryptoFailed = 21,
+    NetworkFailed = 22,
+    TransactionFailed = 23,
+    ConfigurationError = 24,
+    NotImplemented = 25,
+    Internal = 26,
+}
+
+impl From<&WalletError> for WalletCoreErrorCode {
+    fn from(error: &WalletError) -> Self {
+        match error {
+            WalletError::Config(_) => WalletCoreErrorCode::ConfigurationError,
+            WalletError::Crypto(_) => WalletCoreErrorCode::CryptoFailed,
+            WalletError::Validation(_) => WalletCoreErrorCode::InvalidInput,
+            WalletError::Storage(_) => WalletCoreErrorCode::StorageUnavailable,
+            WalletError::Network(_) => WalletCoreErrorCode::NetworkFailed,
+            WalletError::WalletNotFound(_) => WalletCoreErrorCode::WalletNotFound,
+            WalletError::WalletAlreadyExists(_) => WalletCoreErrorCode::WalletAlreadyExists,
+            WalletError::Transaction(_) => WalletCoreErrorCode::TransactionFailed,
+            WalletError::NotImplemented(_) => WalletCoreErrorCode::NotImplemented,
+            WalletError::Ble(_) | WalletError::Internal(_) => WalletCoreErrorCode::Internal,
+        }
+    }
+}
+
 /// Secure FFI result wrapper
 #[repr(C)]
 pub struct SecureResult {
@@ -31,27 +98,117 @@ impl SecureResult {
             Ok(c_string) => Self {
                 success: true,
                 data: c_string.into_raw(),
-                error_code: 0,
-            },
-            Err(_) => Self {
-                success: false,
-                data: ptr::null_mut(),
-                error_code: 15, // String conversion failed
+                error_code: WalletCoreErrorCode::Ok as i32,
             },
+            Err(_) => Self::error(WalletCoreErrorCode::StringConversionFailed),
         }
     }
 
-    fn error(error_code: i32) -> Self {
+    fn error(error_code: WalletCoreErrorCode) -> Self {
         Self {
             success: false,
             data: ptr::null_mut(),
-            error_code,
+            error_code: error_code as i32,
         }
     }
 }
 
+/// An FFI failure: the code returned to the host and its description
+struct FfiError {
+    code: WalletCoreErrorCode,
+    message: String,
+}
+
+impl FfiError {
+    fn new(code: WalletCoreErrorCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    /// Keep the `WalletError` description but report `code`
+    fn with_code(code: WalletCoreErrorCode) -> impl FnOnce(WalletError) -> Self {
+        move |error| Self::new(code, error.to_string())
+    }
+}
+
+impl From<WalletError> for FfiError {
+    fn from(error: WalletError) -> Self {
+        Self::new(WalletCoreErrorCode::from(&error), error.to_string())
+    }
+}
+
+/// Write `message` to `error_out` if the caller asked for it
+fn write_error(error_out: *mut *mut c_char, message: &str) {
+    if error_out.is_null() {
+        return;
+    }
+    let message = CString::new(message.replace('\0', " "))
+        .map(CString::into_raw)
+        .unwrap_or(ptr::null_mut());
+    unsafe {
+        *error_out = message;
+    }
+}
+
+/// Run an FFI call body, mapping errors and panics to a failed `SecureResult`
+fn ffi_call<F>(error_out: *mut *mut c_char, f: F) -> SecureResult
+where
+    F: FnOnce() -> Result<String, FfiError>,
+{
+    if !error_out.is_null() {
+        unsafe {
+            *error_out = ptr::null_mut();
+        }
+    }
+    let error = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(data)) => return SecureResult::success(data),
+        Ok(Err(error)) => error,
+        Err(_) => FfiError::new(WalletCoreErrorCode::Internal, "Internal error in wallet core"),
+    };
+    write_error(error_out, &error.message);
+    SecureResult::error(error.code)
+}
+
+static WALLET_MANAGER: OnceLock<WalletManager> = OnceLock::new();
+static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+
+/// The wallet manager shared by every FFI call
+fn wallet_manager() -> &'static WalletManager {
+    WALLET_MANAGER.get_or_init(WalletManager::new)
+}
+
+/// Run a wallet manager future to completion on the shared runtime
+fn block_on<F: std::future::Future>(future: F) -> Result<F::Output, FfiError> {
+    let runtime = match RUNTIME.get() {
+        Some(runtime) => runtime,
+        None => {
+            let runtime = tokio::runtime::Runtime::new()
+                .map_err(|e| FfiError::new(WalletCoreErrorCode::Internal, format!("Failed to start runtime: {}", e)))?;
+            RUNTIME.get_or_init(|| runtime)
+        }
+    };
+    Ok(runtime.block_on(future))
+}
+
 /// Input validation and sanitization
 fn validate_input(input: *const c_char, max_length: usize) -> Result<String, WalletError> {
+    let input_str = read_text(input, max_length)?;
+
+    // Sanitize input - remove any potentially dangerous characters
+    let sanitized = input_str
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+        .collect::<String>();
+
+    if sanitized != *input_str {
+        return Err(WalletError::validation("Input contains invalid characters".to_string()));
+    }
+
+    Ok(sanitized)
+}
+
+/// Read free text such as a message, seed phrase or JSON document: checked
+/// for UTF-8 and length only. Returned zeroizing, as it may be a secret.
+fn read_text(input: *const c_char, max_length: usize) -> Result<Zeroizing<String>, WalletError> {
     if input.is_null() {
         return Err(WalletError::validation("Null input pointer".to_string()));
     }
@@ -71,298 +228,332 @@ fn validate_input(input: *const c_char, max_length: usize) -> Result<String, Wal
         return Err(WalletError::validation("Empty input".to_string()));
     }
 
-    // Sanitize input - remove any potentially dangerous characters
-    let sanitized = input_str
-        .chars()
-        .filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
-        .collect::<String>();
+    Ok(Zeroizing::new(input_str.to_string()))
+}
 
-    if sanitized != input_str {
-        return Err(WalletError::validation("Input contains invalid characters".to_string()));
+/// Optional passphrase: null or empty means none
+fn read_passphrase(input: *const c_char) -> Result<Zeroizing<String>, WalletError> {
+    if input.is_null() || unsafe { *input } == 0 {
+        return Ok(Zeroizing::new(String::new()));
     }
-
-    Ok(sanitized)
+    read_text(input, MAX_PASSPHRASE_LENGTH)
 }
 
 /// Validate network ID
-fn validate_network(network: i32) -> Result<Network, WalletError> {
-    match network {
-        1114 => Ok(Network::CoreTestnet),
-        84532 => Ok(Network::BaseSepolia),
-        _ => Err(WalletError::validation("Unsupported network".to_string())),
-    }
+fn validate_network(network: i32) -> Result<Network, FfiError> {
+    [Network::CoreTestnet, Network::BaseSepolia, Network::LiskSepolia, Network::EthereumHolesky]
+        .into_iter()
+        .find(|n| n.chain_id() == network as u64)
+        .ok_or_else(|| FfiError::new(WalletCoreErrorCode::InvalidNetwork, format!("Unsupported network {}", network)))
 }
 
-/// Create a new wallet with secure key management
+const MAX_NAME_LENGTH: usize = 50;
+const MAX_WALLET_ID_LENGTH: usize = 100;
+const MAX_SEED_PHRASE_LENGTH: usize = 300;
+const MAX_MESSAGE_LENGTH: usize = 1000;
+const MAX_PASSPHRASE_LENGTH: usize = 256;
+/// Transaction and share-list JSON
+const MAX_JSON_LENGTH: usize = 64 * 1024;
+
+fn wallet_json(wallet: &SecureWallet) -> Result<String, FfiError> {
+    serde_json::to_string(&wallet.to_safe_wallet_info())
+        .map_err(|_| FfiError::new(WalletCoreErrorCode::SerializationFailed, "Failed to serialize wallet"))
+}
+
+/// Create a new wallet with secure key management. Returns the wallet as
+/// JSON; its `id` names it in later calls.
 #[no_mangle]
 pub extern "C" fn wallet_core_create_wallet(
     name: *const c_char,
     network: i32,
+    error_out: *mut *mut c_char,
 ) -> SecureResult {
-    // Validate inputs
-    let name_str = match validate_input(name, 50) {
-        Ok(s) => s,
-        Err(_) => return SecureResult::error(1), // Invalid input
-    };
-
-    let network_enum = match validate_network(network) {
-        Ok(n) => n,
-        Err(_) => return SecureResult::error(2), // Invalid network
-    };
+    ffi_call(error_out, || {
+        let name_str = validate_input(name, MAX_NAME_LENGTH)?;
+        let network_enum = validate_network(network)?;
+        let wallet_id = format!("wallet_{}", uuid::Uuid::new_v4().simple());
 
-    // Create secure storage and key manager
-    let file_storage = match crate::infrastructure::platform::FileStorage::new() {
-        Ok(storage) => storage,
-        Err(_) => return SecureResult::error(3), // Storage initialization failed
-    };
-    
-    let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
-    
-    // Generate a unique key ID for this wallet
-    let key_id = format!("wallet_key_{}", uuid::Uuid::new_v4());
-    
-    // Generate private key securely
-    let private_key = match key_manager.generate_private_key(&key_id) {
-        Ok(pk) => pk,
-        Err(_) => return SecureResult::error(4), // Key generation failed
-    };
-    
-    // Get public key without loading private key into memory
-    let public_key = match key_manager.get_public_key(&private_key) {
-        Ok(pk) => pk,
-        Err(_) => return SecureResult::error(5), // Public key generation failed
-    };
-    
-    // Get address from public key
-    let address = match key_manager.get_address(&public_key) {
-        Ok(addr) => addr,
-        Err(_) => return SecureResult::error(6), // Address generation failed
-    };
-    
-    // Create wallet (no private key stored in wallet struct)
-    let wallet = match Wallet::new(
-        name_str,
-        address,
-        public_key,
-        network_enum,
-    ) {
-        Ok(w) => w,
-        Err(_) => return SecureResult::error(7), // Wallet creation failed
-    };
-    
-    // Convert to safe WalletInfo for serialization
-    let wallet_info = wallet.to_wallet_info();
-    
-    let wallet_json = match serde_json::to_string(&wallet_info) {
-        Ok(json) => json,
-        Err(_) => return SecureResult::error(8), // Serialization failed
-    };
-    
-    SecureResult::success(wallet_json)
+        let wallet = block_on(wallet_manager().create_wallet(&wallet_id, &name_str, network_enum))?
+            .map_err(FfiError::with_code(WalletCoreErrorCode::KeyGenerationFailed))?;
+        wallet_json(&wallet)
+    })
 }
 
-/// Import wallet from seed phrase with secure key management
+/// Import an HD wallet from a BIP39 seed phrase. The wallet id is derived
+/// from the seed, so importing the same phrase twice fails with
+/// `WalletAlreadyExists`.
 #[no_mangle]
 pub extern "C" fn wallet_core_import_wallet(
+    name: *const c_char,
     seed_phrase: *const c_char,
+    network: i32,
+    error_out: *mut *mut c_char,
 ) -> SecureResult {
-    // Validate seed phrase input
-    let seed_phrase_str = match validate_input(seed_phrase, 200) {
-        Ok(s) => s,
-        Err(_) => return SecureResult::error(1), // Invalid input
-    };
+    ffi_call(error_out, || {
+        let name_str = validate_input(name, MAX_NAME_LENGTH)?;
+        let seed_phrase_str = read_text(seed_phrase, MAX_SEED_PHRASE_LENGTH)?;
+        let network_enum = validate_network(network)?;
 
-    // Validate seed phrase format
-    let words: Vec<&str> = seed_phrase_str.split_whitespace().collect();
-    if words.len() < 12 || words.len() > 24 {
-        return SecureResult::error(9); // Invalid seed phrase length
-    }
+        // Validate seed phrase format
+        let words = seed_phrase_str.split_whitespace().count();
+        if !(12..=24).contains(&words) {
+            return Err(FfiError::new(WalletCoreErrorCode::InvalidSeedPhrase, "Seed phrase must have 12 to 24 words"));
+        }
 
-    // Create secure storage and key manager
-    let file_storage = match crate::infrastructure::platform::FileStorage::new() {
-        Ok(storage) => storage,
-        Err(_) => return SecureResult::error(3), // Storage initialization failed
-    };
-    
-    let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
-    
-    // Generate a unique key ID for this wallet
-    let key_id = format!("wallet_key_{}", uuid::Uuid::new_v4());
-    
-    // Derive private key from seed phrase securely
-    let private_key = match key_manager.derive_private_key_from_seed(&seed_phrase_str, &key_id) {
-        Ok(pk) => pk,
-        Err(_) => return SecureResult::error(10), // Seed phrase derivation failed
-    };
-    
-    // Get public key without loading private key into memory
-    let public_key = match key_manager.get_public_key(&private_key) {
-        Ok(pk) => pk,
-        Err(_) => return SecureResult::error(5), // Public key generation failed
-    };
-    
-    // Get address from public key
-    let address = match key_manager.get_address(&public_key) {
-        Ok(addr) => addr,
-        Err(_) => return SecureResult::error(6), // Address generation failed
-    };
-    
-    // Create wallet (no private key stored in wallet struct)
-    let wallet = match Wallet::new(
-        "Imported Wallet".to_string(),
-        address,
-        public_key,
-        Network::CoreTestnet, // Default to CoreTestnet for import
-    ) {
-        Ok(w) => w,
-        Err(_) => return SecureResult::error(7), // Wallet creation failed
-    };
-    
-    // Convert to safe WalletInfo for serialization
-    let wallet_info = wallet.to_wallet_info();
-    
-    let wallet_json = match serde_json::to_string(&wallet_info) {
-        Ok(json) => json,
-        Err(_) => return SecureResult::error(8), // Serialization failed
-    };
-    
-    SecureResult::success(wallet_json)
+        let wallet_id = KeyManager::wallet_id_from_seed(&seed_phrase_str)
+            .map_err(FfiError::with_code(WalletCoreErrorCode::InvalidSeedPhrase))?;
+        let wallet = block_on(wallet_manager().import_wallet(&wallet_id, &name_str, &seed_phrase_str, network_enum))??;
+        wallet_json(&wallet)
+    })
+}
+
+/// List the wallets loaded in this process as a JSON array, oldest first
+#[no_mangle]
+pub extern "C" fn wallet_core_list_wallets(error_out: *mut *mut c_char) -> SecureResult {
+    ffi_call(error_out, || {
+        let wallets: Vec<_> = block_on(wallet_manager().list_wallets())?
+            .iter()
+            .map(SecureWallet::to_safe_wallet_info)
+            .collect();
+        serde_json::to_string(&wallets)
+            .map_err(|_| FfiError::new(WalletCoreErrorCode::SerializationFailed, "Failed to serialize wallets"))
+    })
+}
+
+/// Delete a wallet and erase its keys and seed from secure storage
+#[no_mangle]
+pub extern "C" fn wallet_core_delete_wallet(
+    wallet_id: *const c_char,
+    error_out: *mut *mut c_char,
+) -> SecureResult {
+    ffi_call(error_out, || {
+        let wallet_id_str = validate_input(wallet_id, MAX_WALLET_ID_LENGTH)?;
+        block_on(wallet_manager().delete_wallet(&wallet_id_str))?
+            .map_err(|e| match e {
+                WalletError::WalletNotFound(_) => FfiError::from(e),
+                e => FfiError::with_code(WalletCoreErrorCode::DeletionFailed)(e),
+            })?;
+        Ok("deleted".to_string())
+    })
 }
 
-/// Sign a message using a wallet's private key with secure memory management
+/// Sign a message with a wallet's key. Returns the hex signature.
 #[no_mangle]
 pub extern "C" fn wallet_core_sign_message(
     wallet_id: *const c_char,
     message: *const c_char,
+    error_out: *mut *mut c_char,
 ) -> SecureResult {
-    // Validate inputs
-    let wallet_id_str = match validate_input(wallet_id, 100) {
-        Ok(s) => s,
-        Err(_) => return SecureResult::error(1), // Invalid input
-    };
+    ffi_call(error_out, || {
+        let wallet_id_str = validate_input(wallet_id, MAX_WALLET_ID_LENGTH)?;
+        let message_str = read_text(message, MAX_MESSAGE_LENGTH)?;
+        let signature = block_on(wallet_manager().sign_message(&wallet_id_str, &message_str))?
+            .map_err(|e| match e {
+                WalletError::Storage(_) => FfiError::with_code(WalletCoreErrorCode::KeyNotFound)(e),
+                e => FfiError::with_code(WalletCoreErrorCode::SigningFailed)(e),
+            })?;
+        Ok(signature)
+    })
+}
 
-    let message_str = match validate_input(message, 1000) {
-        Ok(s) => s,
-        Err(_) => return SecureResult::error(1), // Invalid input
-    };
+/// Sign a transaction given as JSON (`to`, `value`, `nonce`, `gas_limit`,
+/// fee fields and `chain_id`, as in `Transaction`). Returns
+/// `{"raw_transaction": "0x…", "hash": "0x…"}`, ready for the relay.
+#[no_mangle]
+pub extern "C" fn wallet_core_sign_transaction(
+    wallet_id: *const c_char,
+    transaction_json: *const c_char,
+    error_out: *mut *mut c_char,
+) -> SecureResult {
+    ffi_call(error_out, || {
+        let wallet_id_str = validate_input(wallet_id, MAX_WALLET_ID_LENGTH)?;
+        let transaction_str = read_text(transaction_json, MAX_JSON_LENGTH)?;
+        let transaction: Transaction = serde_json::from_str(&transaction_str)
+            .map_err(|e| FfiError::new(WalletCoreErrorCode::InvalidInput, format!("Invalid transaction JSON: {}", e)))?;
 
-    // Get secure storage and key manager
-    let file_storage = match crate::infrastructure::platform::FileStorage::new() {
-        Ok(storage) => storage,
-        Err(_) => return SecureResult::error(3), // Storage initialization failed
-    };
-    
-    let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
-    
-    // Get private key reference (does not load key into memory)
-    let private_key = match key_manager.get_private_key(&wallet_id_str) {
-        Ok(pk) => pk,
-        Err(_) => return SecureResult::error(11), // Private key not found
-    };
-    
-    // Sign message without loading private key into memory
-    let signature = match key_manager.sign_message(&private_key, &message_str) {
-        Ok(sig) => sig,
-        Err(_) => return SecureResult::error(12), // Signing failed
-    };
-    
-    SecureResult::success(signature)
+        let signed = block_on(wallet_manager().sign_transaction(&wallet_id_str, &transaction))??;
+        Ok(serde_json::json!({
+            "raw_transaction": format!("0x{}", hex::encode(&signed.signature)),
+            "hash": signed.hash,
+        })
+        .to_string())
+    })
 }
 
-/// Get wallet balance (real on-chain query)
+/// Back up a seed-phrase wallet as `count` SLIP-39 shares, any `threshold`
+/// of which restore it. `passphrase` may be null. Returns the shares as a
+/// JSON array of mnemonics.
 #[no_mangle]
-pub extern "C" fn wallet_core_get_balance(
+pub extern "C" fn wallet_core_backup_wallet(
     wallet_id: *const c_char,
+    threshold: u8,
+    count: u8,
+    passphrase: *const c_char,
+    error_out: *mut *mut c_char,
 ) -> SecureResult {
-    // Validate input
-    let wallet_id_str = match validate_input(wallet_id, 100) {
-        Ok(s) => s,
-        Err(_) => return SecureResult::error(1), // Invalid input
-    };
+    ffi_call(error_out, || {
+        let wallet_id_str = validate_input(wallet_id, MAX_WALLET_ID_LENGTH)?;
+        let passphrase_str = read_passphrase(passphrase)?;
+        let shares = block_on(wallet_manager().backup_wallet_shares(&wallet_id_str, threshold, count, &passphrase_str))??;
+        let shares: Vec<&str> = shares.iter().map(|share| share.as_str()).collect();
+        serde_json::to_string(&shares)
+            .map_err(|_| FfiError::new(WalletCoreErrorCode::SerializationFailed, "Failed to serialize shares"))
+    })
+}
 
-    // Use a local runtime to call async balance method without exposing runtime externally
-    let rt = match tokio::runtime::Runtime::new() {
-        Ok(rt) => rt,
-        Err(_) => return SecureResult::error(15), // Runtime creation failed
-    };
+/// Restore a wallet from a JSON array of SLIP-39 shares made by
+/// `wallet_core_backup_wallet`. Returns the wallet as JSON. The wallet id is
+/// derived from the recovered seed, so restoring a wallet that is already
+/// loaded fails with `WalletAlreadyExists`.
+#[no_mangle]
+pub extern "C" fn wallet_core_restore_wallet(
+    name: *const c_char,
+    shares_json: *const c_char,
+    passphrase: *const c_char,
+    network: i32,
+    error_out: *mut *mut c_char,
+) -> SecureResult {
+    ffi_call(error_out, || {
+        let name_str = validate_input(name, MAX_NAME_LENGTH)?;
+        let shares_str = read_text(shares_json, MAX_JSON_LENGTH)?;
+        let passphrase_str = read_passphrase(passphrase)?;
+        let network_enum = validate_network(network)?;
+        let shares: Vec<Zeroizing<String>> = serde_json::from_str::<Vec<String>>(&shares_str)
+            .map_err(|_| FfiError::new(WalletCoreErrorCode::InvalidInput, "Shares must be a JSON array of strings"))?
+            .into_iter()
+            .map(Zeroizing::new)
+            .collect();
 
-    let result = rt.block_on(async {
-        let manager = crate::core::wallet::WalletManager::new();
-        manager.get_balance(&wallet_id_str).await
-    });
+        let wallet = block_on(wallet_manager().restore_from_shares(&name_str, &shares, &passphrase_str, network_enum))??;
+        wallet_json(&wallet)
+    })
+}
 
-    match result {
-        Ok(balance) => SecureResult::success(balance),
-        Err(_) => SecureResult::error(16), // Balance fetch failed
-    }
+/// Get wallet balance (real on-chain query)
+#[no_mangle]
+pub extern "C" fn wallet_core_get_balance(
+    wallet_id: *const c_char,
+    error_out: *mut *mut c_char,
+) -> SecureResult {
+    ffi_call(error_out, || {
+        let wallet_id_str = validate_input(wallet_id, MAX_WALLET_ID_LENGTH)?;
+        block_on(wallet_manager().get_balance(&wallet_id_str))?
+            .map_err(|e| match e {
+                WalletError::WalletNotFound(_) => FfiError::from(e),
+                e => FfiError::with_code(WalletCoreErrorCode::BalanceFetchFailed)(e),
+            })
+    })
 }
 
 /// Validate a wallet's private key without exposing it
 #[no_mangle]
 pub extern "C" fn wallet_core_validate_wallet(
     wallet_id: *const c_char,
+    error_out: *mut *mut c_char,
 ) -> SecureResult {
-    // Validate input
-    let wallet_id_str = match validate_input(wallet_id, 100) {
-        Ok(s) => s,
-        Err(_) => return SecureResult::error(1), // Invalid input
-    };
+    ffi_call(error_out, || {
+        let wallet_id_str = validate_input(wallet_id, MAX_WALLET_ID_LENGTH)?;
 
-    // Get secure storage and key manager
-    let file_storage = match crate::infrastructure::platform::FileStorage::new() {
-        Ok(storage) => storage,
-        Err(_) => return SecureResult::error(3), // Storage initialization failed
-    };
-    
-    let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
-    
-    // Get private key reference
-    let private_key = match key_manager.get_private_key(&wallet_id_str) {
-        Ok(pk) => pk,
-        Err(_) => return SecureResult::error(11), // Private key not found
-    };
-    
-    // Validate the private key without exposing it
-    let is_valid = match private_key.validate(&file_storage) {
-        Ok(valid) => valid,
-        Err(_) => return SecureResult::error(13), // Validation failed
-    };
-    
-    let result = if is_valid { "true" } else { "false" };
-    SecureResult::success(result.to_string())
+        // Get secure storage and key manager
+        let file_storage = crate::infrastructure::platform::FileStorage::new()
+            .map_err(FfiError::with_code(WalletCoreErrorCode::StorageUnavailable))?;
+        let key_manager = KeyManager::new(&file_storage);
+
+        // Get private key reference
+        let private_key = key_manager.get_private_key(&format!("wallet_key_{}", wallet_id_str))
+            .map_err(FfiError::with_code(WalletCoreErrorCode::KeyNotFound))?;
+
+        // Validate the private key without exposing it
+        let is_valid = private_key.validate(&file_storage)
+            .map_err(FfiError::with_code(WalletCoreErrorCode::ValidationFailed))?;
+        Ok(if is_valid { "true" } else { "false" }.to_string())
+    })
 }
 
-/// Delete a wallet and its associated private key
+/// Run the integrity self-check against platform storage. Returns the
+/// report as JSON; the call succeeds even when checks fail, so read `passed`.
 #[no_mangle]
-pub extern "C" fn wallet_core_delete_wallet(
+pub extern "C" fn wallet_core_self_check(error_out: *mut *mut c_char) -> SecureResult {
+    ffi_call(error_out, || {
+        let file_storage = crate::infrastructure::platform::FileStorage::new()
+            .map_err(FfiError::with_code(WalletCoreErrorCode::StorageUnavailable))?;
+        let report = block_on(crate::core::self_check::run_self_check(&file_storage))?;
+        serde_json::to_string(&report)
+            .map_err(|_| FfiError::new(WalletCoreErrorCode::SerializationFailed, "Failed to serialize self-check report"))
+    })
+}
+
+/// Host callback that writes a `KeyAttestation` as JSON into `out`. Returns
+/// the number of bytes written, or a negative value if the key cannot be attested.
+pub type KeyAttestationCallback = extern "C" fn(
+    key_id: *const c_char,
+    challenge_hex: *const c_char,
+    out: *mut c_char,
+    out_len: usize,
+) -> i32;
+
+/// Largest attestation JSON accepted from the host
+const MAX_ATTESTATION_JSON: usize = 64 * 1024;
+
+static KEY_ATTESTATION_CALLBACK: Mutex<Option<KeyAttestationCallback>> = Mutex::new(None);
+
+/// Android Keystore / Secure Enclave attestation supplied by the host app
+struct HostKeyAttestation(KeyAttestationCallback);
+
+impl KeyAttestationProvider for HostKeyAttestation {
+    fn attest_key(&self, key_id: &str, challenge: &[u8]) -> Result<KeyAttestation, WalletError> {
+        let key_id = CString::new(key_id)
+            .map_err(|_| WalletError::validation("Invalid key id".to_string()))?;
+        let challenge_hex = CString::new(hex::encode(challenge))
+            .map_err(|_| WalletError::validation("Invalid challenge".to_string()))?;
+
+        let mut out = vec![0u8; MAX_ATTESTATION_JSON];
+        let written = (self.0)(key_id.as_ptr(), challenge_hex.as_ptr(), out.as_mut_ptr() as *mut c_char, out.len());
+        if written < 0 || written as usize > out.len() {
+            return Err(WalletError::config("Platform could not attest the key".to_string()));
+        }
+        out.truncate(written as usize);
+        serde_json::from_slice(&out)
+            .map_err(|_| WalletError::crypto("Invalid attestation from platform".to_string()))
+    }
+}
+
+/// Register the host's key attestation callback; pass null to remove it
+#[no_mangle]
+pub extern "C" fn wallet_core_set_key_attestation_provider(callback: Option<KeyAttestationCallback>) {
+    if let Ok(mut current) = KEY_ATTESTATION_CALLBACK.lock() {
+        *current = callback;
+    }
+}
+
+/// Export the platform attestation chain for a wallet's signing key as JSON,
+/// bound to a hex challenge from the relay
+#[no_mangle]
+pub extern "C" fn wallet_core_export_key_attestation(
     wallet_id: *const c_char,
+    challenge: *const c_char,
+    error_out: *mut *mut c_char,
 ) -> SecureResult {
-    // Validate input
-    let wallet_id_str = match validate_input(wallet_id, 100) {
-        Ok(s) => s,
-        Err(_) => return SecureResult::error(1), // Invalid input
-    };
+    ffi_call(error_out, || {
+        let wallet_id_str = validate_input(wallet_id, MAX_WALLET_ID_LENGTH)?;
+        let challenge_bytes = validate_input(challenge, MAX_ATTESTATION_CHALLENGE * 2)
+            .ok()
+            .and_then(|hex_str| hex::decode(hex_str).ok())
+            .ok_or_else(|| FfiError::new(WalletCoreErrorCode::InvalidInput, "Challenge must be hex"))?;
 
-    // Get secure storage and key manager
-    let file_storage = match crate::infrastructure::platform::FileStorage::new() {
-        Ok(storage) => storage,
-        Err(_) => return SecureResult::error(3), // Storage initialization failed
-    };
-    
-    let key_manager = crate::core::crypto::keys::KeyManager::new(&file_storage);
-    
-    // Get private key reference
-    let private_key = match key_manager.get_private_key(&wallet_id_str) {
-        Ok(pk) => pk,
-        Err(_) => return SecureResult::error(11), // Private key not found
-    };
-    
-    // Delete the private key from secure storage
-    if let Err(_) = private_key.delete(&file_storage) {
-        return SecureResult::error(14); // Deletion failed
-    };
-    
-    SecureResult::success("deleted".to_string())
+        let callback = KEY_ATTESTATION_CALLBACK.lock().ok().and_then(|current| *current)
+            .ok_or_else(|| FfiError::new(WalletCoreErrorCode::AttestationUnavailable, "No key attestation provider registered"))?;
+
+        let platform = PlatformManager::new()
+            .map_err(FfiError::with_code(WalletCoreErrorCode::StorageUnavailable))?
+            .with_attestation_provider(Box::new(HostKeyAttestation(callback)));
+
+        let attestation = platform.export_key_attestation(&wallet_id_str, &challenge_bytes)
+            .map_err(FfiError::with_code(WalletCoreErrorCode::AttestationFailed))?;
+
+        serde_json::to_string(&attestation)
+            .map_err(|_| FfiError::new(WalletCoreErrorCode::SerializationFailed, "Failed to serialize attestation"))
+    })
 }
 
 /// Free a C string with secure memory cleanup
@@ -383,7 +574,39 @@ pub extern "C" fn wallet_core_free_result(result: *mut SecureResult) {
             let result_ref = &mut *result;
             if !result_ref.data.is_null() {
                 let _ = CString::from_raw(result_ref.data);
+                result_ref.data = ptr::null_mut();
             }
         }
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_map_to_codes_and_messages() {
+        let name = CString::new("Savings").unwrap();
+        let mut error: *mut c_char = ptr::null_mut();
+
+        let result = wallet_core_create_wallet(name.as_ptr(), 1, &mut error);
+        assert!(!result.success);
+        assert_eq!(result.error_code, WalletCoreErrorCode::InvalidNetwork as i32);
+        assert_eq!(unsafe { CStr::from_ptr(error) }.to_str().unwrap(), "Unsupported network 1");
+        wallet_core_free_string(error);
+
+        let result = wallet_core_delete_wallet(name.as_ptr(), &mut error);
+        assert_eq!(result.error_code, WalletCoreErrorCode::WalletNotFound as i32);
+        assert!(!error.is_null());
+        wallet_core_free_string(error);
+
+        let bad_name = CString::new("Savings; DROP").unwrap();
+        let result = wallet_core_create_wallet(bad_name.as_ptr(), 84532, ptr::null_mut());
+        assert_eq!(result.error_code, WalletCoreErrorCode::InvalidInput as i32);
+
+        let mut result = wallet_core_list_wallets(&mut error);
+        assert!(result.success && error.is_null());
+        assert_eq!(unsafe { CStr::from_ptr(result.data) }.to_str().unwrap(), "[]");
+        wallet_core_free_result(&mut result);
+    }
+}
@@ -4,10 +4,14 @@
 //! for the wallet system, including storage, networking, and platform services.
 
 pub mod platform;
+pub mod relay;
+pub mod relay_pool;
 // pub mod network;
 // pub mod persistence;
 
 // Re-export infrastructure components
 pub use platform::*;
+pub use relay::{RelayClient, RelayClientConfig, RelayTransactionStatus, SubmitResponse};
+pub use relay_pool::{BleRelay, RelayLink, RelayLinkKind, RelayPool, RelayStatus};
 // pub use network::*;
 // pub use persistence::*; 
\ No newline at end of file
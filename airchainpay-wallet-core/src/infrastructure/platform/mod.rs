@@ -14,8 +14,11 @@ use crate::shared::types::SecurityLevel;
 use aes_gcm::{Aes256Gcm, KeyInit, aead::{Aead}};
 use aes_gcm::aead::generic_array::GenericArray;
 use argon2::{Argon2, PasswordHasher};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
 use rand_core::OsRng;
 use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::PathBuf;
@@ -94,8 +97,8 @@ impl PlatformFeatures {
     }
 }
 
-/// Platform-specific storage implementation
-pub trait PlatformStorage {
+/// Platform-specific storage implementation; shared across threads by `WalletManager`
+pub trait PlatformStorage: Send + Sync {
     /// Store data securely
     fn store(&self, key: &str, data: &[u8]) -> Result<(), WalletError>;
     
@@ -130,30 +133,79 @@ pub trait BiometricAuth {
     fn disable(&self) -> Result<(), WalletError>;
 }
 
-/// Platform-specific secure enclave operations
-pub trait SecureEnclave {
+/// Elliptic curve of a key generated inside a secure enclave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnclaveCurve {
+    /// The curve Ethereum accounts use
+    Secp256k1,
+    /// NIST P-256, the only curve of the iOS Secure Enclave and StrongBox
+    P256,
+}
+
+/// Platform-specific secure enclave operations. Keys never leave the
+/// enclave; only public keys and signatures are returned.
+pub trait SecureEnclave: Send + Sync {
     /// Check if secure enclave is available
     fn is_available(&self) -> Result<bool, WalletError>;
-    
-    /// Generate key pair in secure enclave
-    fn generate_key_pair(&self, key_id: &str) -> Result<String, WalletError>;
-    
-    /// Sign data using secure enclave
-    fn sign(&self, key_id: &str, data: &[u8]) -> Result<Vec<u8>, WalletError>;
-    
-    /// Get public key from secure enclave
+
+    /// Curves the enclave can generate keys on
+    fn supported_curves(&self) -> Result<Vec<EnclaveCurve>, WalletError>;
+
+    /// Generate key pair in secure enclave, returning the hex SEC1 public key
+    fn generate_key_pair(&self, key_id: &str, curve: EnclaveCurve) -> Result<String, WalletError>;
+
+    /// Sign a 32-byte digest without hashing it again, returning a DER or
+    /// 64-byte `r || s` ECDSA signature
+    fn sign(&self, key_id: &str, digest: &[u8]) -> Result<Vec<u8>, WalletError>;
+
+    /// Get the hex SEC1 public key of a key in the secure enclave
     fn get_public_key(&self, key_id: &str) -> Result<String, WalletError>;
-    
+
     /// Delete key from secure enclave
     fn delete_key(&self, key_id: &str) -> Result<(), WalletError>;
 }
 
+/// Shortest relay challenge accepted for key attestation
+pub const MIN_ATTESTATION_CHALLENGE: usize = 16;
+/// Longest relay challenge accepted for key attestation
+pub const MAX_ATTESTATION_CHALLENGE: usize = 128;
+
+/// Platform attestation scheme a chain was produced by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttestationFormat {
+    /// Android Keystore key attestation, chaining to the Google hardware root
+    AndroidKeystore,
+    /// Apple App Attest for a Secure Enclave key
+    AppleAppAttest,
+}
+
+/// Platform evidence that a wallet's signing key is hardware-backed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyAttestation {
+    pub key_id: String,
+    pub format: AttestationFormat,
+    /// Hex relay challenge bound into the attestation
+    pub challenge: String,
+    /// Base64 DER certificates, leaf first
+    pub certificate_chain: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Key attestation from the platform keystore. The host app implements this
+/// where Android Keystore or the Secure Enclave can attest keys.
+pub trait KeyAttestationProvider: Send + Sync {
+    fn attest_key(&self, key_id: &str, challenge: &[u8]) -> Result<KeyAttestation, WalletError>;
+}
+
 /// Platform manager
 pub struct PlatformManager {
     features: PlatformFeatures,
     storage: Box<dyn PlatformStorage>,
     biometric_auth: Box<dyn BiometricAuth>,
     secure_enclave: Box<dyn SecureEnclave>,
+    attestation: Option<Box<dyn KeyAttestationProvider>>,
 }
 
 impl PlatformManager {
@@ -184,9 +236,16 @@ impl PlatformManager {
             storage,
             biometric_auth,
             secure_enclave,
+            attestation: None,
         })
     }
 
+    /// Attest keys through the host's platform keystore
+    pub fn with_attestation_provider(mut self, provider: Box<dyn KeyAttestationProvider>) -> Self {
+        self.attestation = Some(provider);
+        self
+    }
+
     /// Get platform features
     pub fn features(&self) -> &PlatformFeatures {
         &self.features
@@ -211,6 +270,34 @@ impl PlatformManager {
     pub fn init(&self) -> Result<(), WalletError> {
         Ok(())
     }
+
+    /// Attestation chain for a wallet's signing key, for the relay's device
+    /// registry to verify hardware backing. `challenge` comes from the relay.
+    pub fn export_key_attestation(&self, key_id: &str, challenge: &[u8]) -> Result<KeyAttestation, WalletError> {
+        if !(MIN_ATTESTATION_CHALLENGE..=MAX_ATTESTATION_CHALLENGE).contains(&challenge.len()) {
+            return Err(WalletError::validation(format!(
+                "Attestation challenge must be {} to {} bytes",
+                MIN_ATTESTATION_CHALLENGE, MAX_ATTESTATION_CHALLENGE
+            )));
+        }
+        let provider = self.attestation.as_ref()
+            .ok_or_else(|| WalletError::config("Key attestation is not available on this platform"))?;
+
+        let attestation = provider.attest_key(key_id, challenge)?;
+        if attestation.key_id != key_id {
+            return Err(WalletError::crypto("Attestation is for a different key"));
+        }
+        if attestation.challenge != hex::encode(challenge) {
+            return Err(WalletError::crypto("Attestation does not carry the requested challenge"));
+        }
+        if attestation.certificate_chain.is_empty() {
+            return Err(WalletError::crypto("Attestation certificate chain is empty"));
+        }
+        if attestation.certificate_chain.iter().any(|cert| general_purpose::STANDARD.decode(cert).is_err()) {
+            return Err(WalletError::crypto("Attestation certificate is not valid base64"));
+        }
+        Ok(attestation)
+    }
 }
 
 // Hardened file storage implementation
@@ -417,22 +504,28 @@ impl FileStorage {
         Ok(key)
     }
 
-    // Helper: Get file path for a given key
-    fn file_path(key: &str) -> PathBuf {
+    // Helper: Get the path of `key`'s file with `extension`. Keys become
+    // file names, so anything that could leave the directory is refused.
+    fn key_path(key: &str, extension: &str) -> Result<PathBuf, WalletError> {
+        if key.is_empty() || key.contains(['/', '\\', '\0']) || key.contains("..") {
+            return Err(WalletError::storage(format!("Invalid storage key: {:?}", key)));
+        }
         // Use OS-specific secure app data directory
         let base_dir = dirs::data_dir().unwrap_or_else(|| PathBuf::from("./secure_storage"));
         let mut path = base_dir.join("airchainpay");
         fs::create_dir_all(&path).ok();
-        path.push(format!("{}.dat", key));
-        path
+        path.push(format!("{}.{}", key, extension));
+        Ok(path)
+    }
+
+    // Helper: Get file path for a given key
+    fn file_path(key: &str) -> Result<PathBuf, WalletError> {
+        Self::key_path(key, "dat")
     }
 
     // Helper: Get or generate salt for a key
     fn get_salt(key: &str) -> Result<Vec<u8>, WalletError> {
-        let base_dir = dirs::data_dir().unwrap_or_else(|| PathBuf::from("./secure_storage"));
-        let mut salt_path = base_dir.join("airchainpay");
-        fs::create_dir_all(&salt_path).ok();
-        salt_path.push(format!("{}.salt", key));
+        let salt_path = Self::key_path(key, "salt")?;
         if salt_path.exists() {
             let mut salt = vec![];
             File::open(&salt_path)?.read_to_end(&mut salt)?;
@@ -459,7 +552,7 @@ impl PlatformStorage for FileStorage {
         rng.fill_bytes(&mut nonce);
         let ciphertext = cipher.encrypt(GenericArray::from_slice(&nonce), data)
             .map_err(|e| WalletError::crypto(format!("Encryption failed: {}", e)))?;
-        let mut file = File::create(Self::file_path(key))?;
+        let mut file = File::create(Self::file_path(key)?)?;
         file.set_permissions(fs::Permissions::from_mode(0o600))?;
         file.write_all(&nonce)?;
         file.write_all(&ciphertext)?;
@@ -471,7 +564,7 @@ impl PlatformStorage for FileStorage {
         let salt = Self::get_salt(key)?;
         let key_bytes = Self::derive_key(&password, &salt)?;
         let cipher = Aes256Gcm::new(GenericArray::from_slice(&key_bytes));
-        let mut file = File::open(Self::file_path(key))?;
+        let mut file = File::open(Self::file_path(key)?)?;
         let mut nonce = [0u8; 12];
         file.read_exact(&mut nonce)?;
         let mut ciphertext = vec![];
@@ -482,16 +575,13 @@ impl PlatformStorage for FileStorage {
     }
 
     fn delete(&self, key: &str) -> Result<(), WalletError> {
-        let _ = fs::remove_file(Self::file_path(key));
-        let base_dir = dirs::data_dir().unwrap_or_else(|| PathBuf::from("./secure_storage"));
-        let mut salt_path = base_dir.join("airchainpay");
-        salt_path.push(format!("{}.salt", key));
-        let _ = fs::remove_file(salt_path);
+        let _ = fs::remove_file(Self::file_path(key)?);
+        let _ = fs::remove_file(Self::key_path(key, "salt")?);
         Ok(())
     }
 
     fn exists(&self, key: &str) -> Result<bool, WalletError> {
-        Ok(Self::file_path(key).exists())
+        Ok(Self::file_path(key)?.exists())
     }
 
     fn list_keys(&self) -> Result<Vec<String>, WalletError> {
@@ -557,11 +647,15 @@ impl SecureEnclave for NoSecureEnclave {
         Err(WalletError::config("Secure enclave is not available on this platform."))
     }
     
-    fn generate_key_pair(&self, _key_id: &str) -> Result<String, WalletError> {
+    fn supported_curves(&self) -> Result<Vec<EnclaveCurve>, WalletError> {
         Err(WalletError::config("Secure enclave is not available on this platform."))
     }
     
-    fn sign(&self, _key_id: &str, _data: &[u8]) -> Result<Vec<u8>, WalletError> {
+    fn generate_key_pair(&self, _key_id: &str, _curve: EnclaveCurve) -> Result<String, WalletError> {
+        Err(WalletError::config("Secure enclave is not available on this platform."))
+    }
+    
+    fn sign(&self, _key_id: &str, _digest: &[u8]) -> Result<Vec<u8>, WalletError> {
         Err(WalletError::config("Secure enclave is not available on this platform."))
     }
     
@@ -590,4 +684,44 @@ mod tests {
         let manager = PlatformManager::new();
         assert!(manager.is_ok());
     }
+
+    #[test]
+    fn test_file_storage_rejects_keys_outside_its_directory() {
+        assert!(FileStorage::file_path("wallet_key_wallet_0123abcd").is_ok());
+        for key in ["../../x", "a/b", "a\\b", "..", ""] {
+            assert!(FileStorage::file_path(key).is_err(), "{:?} accepted", key);
+        }
+    }
+
+    struct FixedAttestation {
+        challenge: Option<String>,
+    }
+
+    impl KeyAttestationProvider for FixedAttestation {
+        fn attest_key(&self, key_id: &str, challenge: &[u8]) -> Result<KeyAttestation, WalletError> {
+            Ok(KeyAttestation {
+                key_id: key_id.to_string(),
+                format: AttestationFormat::AndroidKeystore,
+                challenge: self.challenge.clone().unwrap_or_else(|| hex::encode(challenge)),
+                certificate_chain: vec![general_purpose::STANDARD.encode(b"leaf"), general_purpose::STANDARD.encode(b"root")],
+                created_at: Utc::now(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_key_attestation_export() {
+        let challenge = [9u8; 32];
+        assert!(PlatformManager::new().unwrap().export_key_attestation("wallet_1", &challenge).is_err());
+
+        let manager = PlatformManager::new().unwrap()
+            .with_attestation_provider(Box::new(FixedAttestation { challenge: None }));
+        let attestation = manager.export_key_attestation("wallet_1", &challenge).unwrap();
+        assert_eq!(attestation.certificate_chain.len(), 2);
+        assert!(manager.export_key_attestation("wallet_1", &[1u8; 4]).is_err());
+
+        let stale = PlatformManager::new().unwrap()
+            .with_attestation_provider(Box::new(FixedAttestation { challenge: Some(hex::encode([0u8; 32])) }));
+        assert!(stale.export_key_attestation("wallet_1", &challenge).is_err());
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,441 @@
+//! HTTP client for the AirChainPay relay
+//!
+//! Speaks the relay's API so apps do not each reimplement it: API-key
+//! authentication (solving the proof-of-work challenge when the relay asks
+//! for one), transaction submission with the replay-protection headers,
+//! gzip-compressed bodies for large payloads, submission as a compressed
+//! payload frame (`shared::payload_codec`) and status polling. Network
+//! errors, 429 and 5xx answers are retried with backoff; other refusals come
+//! back as `WalletError::Transaction`, unreachable relays as
+//! `WalletError::Network`.
+
+use crate::shared::error::WalletError;
+use crate::shared::payload_codec;
+use crate::shared::sources::{default_clock, default_random_source, Clock, RandomSource};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use zeroize::Zeroizing;
+
+const NONCE_HEADER: &str = "X-Request-Nonce";
+const TIMESTAMP_HEADER: &str = "X-Request-Timestamp";
+const SIGNATURE_HEADER: &str = "X-Request-Signature";
+const POW_CHALLENGE_HEADER: &str = "X-PoW-Challenge";
+const POW_NONCE_HEADER: &str = "X-PoW-Nonce";
+/// Hardest proof-of-work challenge the client will attempt
+const MAX_POW_DIFFICULTY: u32 = 32;
+/// Relay statuses after which a transaction no longer changes
+const FINAL_STATUSES: &[&str] = &["completed", "failed", "dropped", "shed", "queue_failed"];
+
+#[derive(Debug, Clone)]
+pub struct RelayClientConfig {
+    pub timeout: Duration,
+    /// Retries after the first attempt for network errors, 429 and 5xx
+    pub max_retries: u32,
+    /// Backoff before the first retry; doubled on every further one
+    pub retry_delay: Duration,
+    pub max_retry_delay: Duration,
+    /// Request bodies of at least this many bytes are sent gzip-encoded
+    pub compression_threshold: usize,
+}
+
+impl Default for RelayClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(15),
+            max_retries: 3,
+            retry_delay: Duration::from_millis(500),
+            max_retry_delay: Duration::from_secs(10),
+            compression_threshold: 1024,
+        }
+    }
+}
+
+/// The relay's answer to a submitted transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitResponse {
+    pub transaction_id: String,
+    pub status: String,
+    pub chain_id: u64,
+}
+
+/// Progress of a submitted transaction as reported by the relay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayTransactionStatus {
+    pub transaction_id: String,
+    pub status: String,
+    pub chain_id: u64,
+    #[serde(default)]
+    pub transaction_hash: Option<String>,
+    #[serde(default)]
+    pub block_number: Option<u64>,
+    #[serde(default)]
+    pub gas_used: Option<u64>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl RelayTransactionStatus {
+    pub fn is_final(&self) -> bool {
+        FINAL_STATUSES.contains(&self.status.as_str())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChallengeResponse {
+    required: bool,
+    challenge: Challenge,
+}
+
+#[derive(Debug, Deserialize)]
+struct Challenge {
+    challenge: String,
+    difficulty: u32,
+}
+
+/// Outcome of a single request
+enum Attempt {
+    Done(serde_json::Value),
+    Retry(WalletError, Option<Duration>),
+    Unauthorized,
+    Failed(WalletError),
+}
+
+/// Find a nonce such that `sha256("<challenge>:<nonce>")` starts with
+/// `difficulty` zero bits
+pub fn solve_challenge(challenge: &str, difficulty: u32) -> Result<String, WalletError> {
+    if difficulty > MAX_POW_DIFFICULTY {
+        return Err(WalletError::network(format!("Relay challenge difficulty {} is too high", difficulty)));
+    }
+    (0u64..)
+        .map(|n| n.to_string())
+        .find(|nonce| leading_zero_bits(&Sha256::digest(format!("{}:{}", challenge, nonce).as_bytes())) >= difficulty)
+        .ok_or_else(|| WalletError::internal("No proof-of-work solution found"))
+}
+
+fn leading_zero_bits(digest: &[u8]) -> u32 {
+    let mut zeros = 0;
+    for byte in digest {
+        zeros += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    zeros
+}
+
+fn gzip(body: &[u8]) -> Result<Vec<u8>, WalletError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body)?;
+    Ok(encoder.finish()?)
+}
+
+/// The relay's `X-Request-Signature`: HMAC-SHA256 keyed with the bearer
+/// token over `"<nonce>.<timestamp>."` and the uncompressed body, so the
+/// nonce cannot be reattached to another body
+fn request_signature(token: &str, nonce: &str, timestamp: u64, body: &[u8]) -> String {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(token.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{}.{}.", nonce, timestamp).as_bytes());
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn compressed_submit_body(raw_tx: &str, chain_id: u64) -> Result<serde_json::Value, WalletError> {
+    let raw = raw_tx.strip_prefix("0x")
+        .ok_or_else(|| WalletError::validation("Signed transaction must be 0x-prefixed"))?;
+    let raw = hex::decode(raw)
+        .map_err(|e| WalletError::validation(format!("Invalid signed transaction hex: {}", e)))?;
+    let frame = payload_codec::encode_signed_transaction(&raw, chain_id)?;
+    Ok(json!({ "payload": STANDARD.encode(frame) }))
+}
+
+fn error_message(status: StatusCode, body: &serde_json::Value) -> String {
+    ["message", "error"]
+        .iter()
+        .find_map(|key| body.get(*key).and_then(|v| v.as_str()))
+        .map(|message| format!("Relay returned {}: {}", status, message))
+        .unwrap_or_else(|| format!("Relay returned {}", status))
+}
+
+pub struct RelayClient {
+    base_url: String,
+    api_key: Option<Zeroizing<String>>,
+    config: RelayClientConfig,
+    client: Client,
+    token: RwLock<Option<Zeroizing<String>>>,
+    rng: Arc<dyn RandomSource>,
+    clock: Arc<dyn Clock>,
+}
+
+impl RelayClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: None,
+            config: RelayClientConfig::default(),
+            client: Client::new(),
+            token: RwLock::new(None),
+            rng: default_random_source(),
+            clock: default_clock(),
+        }
+    }
+
+    /// Authenticate with this API key, on first use and whenever the relay
+    /// answers 401
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(Zeroizing::new(api_key.to_string()));
+        self
+    }
+
+    pub fn with_config(mut self, config: RelayClientConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Draw request nonces from `rng` instead of the OS
+    pub fn with_random_source(mut self, rng: Arc<dyn RandomSource>) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// Timestamp requests with `clock` instead of the system time
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub async fn is_reachable(&self) -> bool {
+        self.client.get(format!("{}/health", self.base_url))
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .map(|r| r.status().is_success())
+            .unwrap_or(false)
+    }
+
+    /// Exchange the API key for a bearer token, solving the relay's
+    /// proof-of-work challenge first if it requires one
+    pub async fn authenticate(&self) -> Result<(), WalletError> {
+        let api_key = self.api_key.as_ref()
+            .ok_or_else(|| WalletError::config("No relay API key configured"))?;
+
+        let challenge = self.send(Method::GET, "/api/auth/challenge", None, &[], false).await?
+            .ok_or_else(|| WalletError::network("Relay refused the challenge request"))?;
+        let challenge: ChallengeResponse = serde_json::from_value(challenge)?;
+        let mut headers = Vec::new();
+        if challenge.required {
+            let nonce = solve_challenge(&challenge.challenge.challenge, challenge.challenge.difficulty)?;
+            headers.push((POW_CHALLENGE_HEADER, challenge.challenge.challenge));
+            headers.push((POW_NONCE_HEADER, nonce));
+        }
+
+        let body = json!({ "api_key": api_key.as_str() });
+        let response = self.send(Method::POST, "/api/auth/token", Some(&body), &headers, false).await?
+            .ok_or_else(|| WalletError::network("Relay rejected the API key"))?;
+        let token = response.get("token").and_then(|v| v.as_str())
+            .ok_or_else(|| WalletError::network("Relay returned no token"))?;
+        *self.token.write().await = Some(Zeroizing::new(token.to_string()));
+        Ok(())
+    }
+
+    /// Submit a 0x-prefixed signed transaction for broadcast
+    pub async fn submit(&self, raw_tx: &str, chain_id: u64) -> Result<SubmitResponse, WalletError> {
+        let body = json!({
+            "signed_tx": raw_tx,
+            "rpc_url": "",
+            "chain_id": chain_id,
+        });
+        let response = self.execute(Method::POST, "/api/send_tx", Some(&body)).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Submit a 0x-prefixed signed transaction as an `ACPZ` payload frame,
+    /// the relay's `/send_compressed_tx`
+    pub async fn submit_compressed(&self, raw_tx: &str, chain_id: u64) -> Result<SubmitResponse, WalletError> {
+        let body = compressed_submit_body(raw_tx, chain_id)?;
+        let response = self.execute(Method::POST, "/api/send_compressed_tx", Some(&body)).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    pub async fn status(&self, transaction_id: &str) -> Result<RelayTransactionStatus, WalletError> {
+        let path = format!("/api/transaction/{}/status", transaction_id);
+        Ok(serde_json::from_value(self.execute(Method::GET, &path, None).await?)?)
+    }
+
+    /// Poll until the transaction reaches a final status or `timeout` passes
+    pub async fn wait_for_final(&self, transaction_id: &str, poll_interval: Duration, timeout: Duration) -> Result<RelayTransactionStatus, WalletError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let status = self.status(transaction_id).await?;
+            if status.is_final() {
+                return Ok(status);
+            }
+            if tokio::time::Instant::now() + poll_interval > deadline {
+                return Err(WalletError::network(format!(
+                    "Transaction {} still {} after {:?}", transaction_id, status.status, timeout
+                )));
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Send an authenticated request, fetching a token first if there is
+    /// none and once more if the relay answers 401
+    async fn execute(&self, method: Method, path: &str, body: Option<&serde_json::Value>) -> Result<serde_json::Value, WalletError> {
+        if self.api_key.is_some() && self.token.read().await.is_none() {
+            self.authenticate().await?;
+        }
+        if let Some(response) = self.send(method.clone(), path, body, &[], true).await? {
+            return Ok(response);
+        }
+        if self.api_key.is_none() {
+            return Err(WalletError::network(format!("Relay refused {} without credentials", path)));
+        }
+        self.authenticate().await?;
+        self.send(method, path, body, &[], true).await?
+            .ok_or_else(|| WalletError::network(format!("Relay refused {} after re-authenticating", path)))
+    }
+
+    /// Send a request, retrying network errors, 429 and 5xx with backoff.
+    /// Returns `None` if the relay answers 401.
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+        headers: &[(&str, String)],
+        authenticated: bool,
+    ) -> Result<Option<serde_json::Value>, WalletError> {
+        let mut attempt = 0;
+        loop {
+            let request = self.build(method.clone(), path, body, headers, authenticated).await?;
+            let (error, retry_after) = match self.attempt(request).await {
+                Attempt::Done(value) => return Ok(Some(value)),
+                Attempt::Unauthorized => return Ok(None),
+                Attempt::Failed(error) => return Err(error),
+                Attempt::Retry(error, retry_after) => (error, retry_after),
+            };
+            if attempt >= self.config.max_retries {
+                return Err(error);
+            }
+            let backoff = self.config.retry_delay.saturating_mul(1 << attempt.min(16)).min(self.config.max_retry_delay);
+            log::warn!("Relay request {} {} failed ({}), retrying", method, path, error);
+            tokio::time::sleep(retry_after.unwrap_or(backoff)).await;
+            attempt += 1;
+        }
+    }
+
+    async fn build(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+        headers: &[(&str, String)],
+        authenticated: bool,
+    ) -> Result<RequestBuilder, WalletError> {
+        // A fresh nonce per attempt; the relay refuses a nonce it has seen
+        let mut nonce = [0u8; 16];
+        self.rng.fill_bytes(&mut nonce)?;
+        let nonce = hex::encode(nonce);
+        let timestamp = self.clock.unix_timestamp();
+        let bytes = body.map(serde_json::to_vec).transpose()?;
+        let mut request = self.client.request(method, format!("{}{}", self.base_url, path))
+            .timeout(self.config.timeout)
+            .header(NONCE_HEADER, nonce.as_str())
+            .header(TIMESTAMP_HEADER, timestamp.to_string());
+        for (name, value) in headers {
+            request = request.header(*name, value.as_str());
+        }
+        if authenticated {
+            if let Some(token) = self.token.read().await.as_ref() {
+                let signature = request_signature(token, &nonce, timestamp, bytes.as_deref().unwrap_or_default());
+                request = request.bearer_auth(token.as_str()).header(SIGNATURE_HEADER, signature);
+            }
+        }
+        if let Some(bytes) = bytes {
+            request = request.header("Content-Type", "application/json");
+            request = if bytes.len() >= self.config.compression_threshold {
+                request.header("Content-Encoding", "gzip").body(gzip(&bytes)?)
+            } else {
+                request.body(bytes)
+            };
+        }
+        Ok(request)
+    }
+
+    async fn attempt(&self, request: RequestBuilder) -> Attempt {
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => return Attempt::Retry(WalletError::network(format!("Relay unreachable: {}", e)), None),
+        };
+        let status = response.status();
+        let retry_after = response.headers().get("Retry-After")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_secs);
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+
+        if status.is_success() {
+            Attempt::Done(body)
+        } else if status == StatusCode::UNAUTHORIZED {
+            Attempt::Unauthorized
+        } else if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            Attempt::Retry(WalletError::network(error_message(status, &body)), retry_after)
+        } else {
+            Attempt::Failed(WalletError::transaction(error_message(status, &body)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_challenge_solution_and_compression() {
+        let nonce = solve_challenge("1700000000.abcd.ef01", 12).unwrap();
+        let digest = Sha256::digest(format!("1700000000.abcd.ef01:{}", nonce).as_bytes());
+        assert!(leading_zero_bits(&digest) >= 12);
+        assert_eq!(leading_zero_bits(&[0x00, 0x0f, 0xff]), 12);
+        assert!(solve_challenge("x", MAX_POW_DIFFICULTY + 1).is_err());
+
+        let body = serde_json::to_vec(&json!({ "signed_tx": format!("0x{}", "ab".repeat(600)) })).unwrap();
+        let mut decoded = Vec::new();
+        GzDecoder::new(&gzip(&body).unwrap()[..]).read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, body);
+
+        let submit = compressed_submit_body(&format!("0x{}", "ab".repeat(600)), 84532).unwrap();
+        let frame = STANDARD.decode(submit["payload"].as_str().unwrap()).unwrap();
+        assert!(frame.len() < 600);
+        assert_eq!(
+            payload_codec::decode(&frame).unwrap(),
+            payload_codec::DecodedPayload::SignedTransaction(payload_codec::SignedTransaction { raw_transaction: vec![0xab; 600], chain_id: 84532 }),
+        );
+        assert!(compressed_submit_body("abcd", 84532).is_err());
+
+        let status: RelayTransactionStatus = serde_json::from_value(json!({
+            "success": true,
+            "transaction_id": "tx-1",
+            "status": "broadcast",
+            "chain_id": 1114,
+            "transaction_hash": "0xabc",
+        })).unwrap();
+        assert!(!status.is_final());
+        assert!(RelayTransactionStatus { status: "dropped".to_string(), ..status }.is_final());
+    }
+}
@@ -0,0 +1,359 @@
+//! Multiple relays with health probes and failover
+//!
+//! A wallet can reach relays over HTTP or, when offline, over BLE to a
+//! nearby relay peripheral. `RelayPool` probes every configured relay,
+//! prefers the reachable one with the lowest smoothed latency and moves on
+//! to the next when a submission cannot reach it. A relay that answers but
+//! refuses a transaction is not retried elsewhere; another relay would
+//! refuse it for the same reason.
+
+use crate::core::ble::{BLESecureSession, BleCentral, NoiseKeypair};
+use crate::infrastructure::relay::{RelayClient, SubmitResponse};
+use crate::shared::error::WalletError;
+use crate::shared::types::BLEDeviceInfo;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Weight of the newest sample in the smoothed latency
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayLinkKind {
+    Network,
+    Ble,
+}
+
+/// A way of handing signed transactions to a relay
+#[async_trait]
+pub trait RelayLink: Send + Sync {
+    fn name(&self) -> String;
+    fn kind(&self) -> RelayLinkKind;
+    /// Succeeds if the relay can be reached right now
+    async fn probe(&self) -> Result<(), WalletError>;
+    /// Submit a 0x-prefixed signed transaction. Refusals by the relay are
+    /// `WalletError::Transaction`; anything else means it was not reached.
+    async fn submit(&self, raw_tx: &str, chain_id: u64) -> Result<SubmitResponse, WalletError>;
+}
+
+#[async_trait]
+impl RelayLink for RelayClient {
+    fn name(&self) -> String {
+        self.base_url().to_string()
+    }
+
+    fn kind(&self) -> RelayLinkKind {
+        RelayLinkKind::Network
+    }
+
+    async fn probe(&self) -> Result<(), WalletError> {
+        if self.is_reachable().await {
+            Ok(())
+        } else {
+            Err(WalletError::network(format!("Relay {} is unreachable", self.base_url())))
+        }
+    }
+
+    async fn submit(&self, raw_tx: &str, chain_id: u64) -> Result<SubmitResponse, WalletError> {
+        RelayClient::submit(self, raw_tx, chain_id).await
+    }
+}
+
+/// A relay peripheral reached over BLE, with payments sent inside a Noise
+/// session
+pub struct BleRelay {
+    central: BleCentral,
+    device: BLEDeviceInfo,
+    keypair: NoiseKeypair,
+    /// The relay's Noise static key, when pinned
+    relay_static: Option<Vec<u8>>,
+}
+
+impl BleRelay {
+    pub fn new(central: BleCentral, device: BLEDeviceInfo, keypair: NoiseKeypair) -> Self {
+        Self { central, device, keypair, relay_static: None }
+    }
+
+    /// Only talk to a relay presenting this Noise static key
+    pub fn with_relay_static(mut self, relay_static: Vec<u8>) -> Self {
+        self.relay_static = Some(relay_static);
+        self
+    }
+
+    async fn exchange(&self, payload: &[u8]) -> Result<Vec<u8>, WalletError> {
+        let mut session = BLESecureSession::handshake_initiator(&self.central, &self.keypair, self.relay_static.as_deref()).await?;
+        session.send(&self.central, payload).await?;
+        session.receive(&self.central).await
+    }
+}
+
+#[async_trait]
+impl RelayLink for BleRelay {
+    fn name(&self) -> String {
+        format!("ble:{}", self.device.name)
+    }
+
+    fn kind(&self) -> RelayLinkKind {
+        RelayLinkKind::Ble
+    }
+
+    async fn probe(&self) -> Result<(), WalletError> {
+        self.central.connect(&self.device).await?;
+        self.central.disconnect().await
+    }
+
+    async fn submit(&self, raw_tx: &str, chain_id: u64) -> Result<SubmitResponse, WalletError> {
+        self.central.connect(&self.device).await?;
+        let payload = serde_json::to_vec(&json!({ "signed_tx": raw_tx, "chain_id": chain_id }))?;
+        let reply = self.exchange(&payload).await;
+        let _ = self.central.disconnect().await;
+
+        let reply: serde_json::Value = serde_json::from_slice(&reply?)
+            .map_err(|e| WalletError::ble(format!("Invalid relay reply: {}", e)))?;
+        let status = reply.get("status").and_then(|v| v.as_str()).unwrap_or_default();
+        if matches!(status, "error" | "failed" | "rejected") {
+            let message = reply.get("message").and_then(|v| v.as_str()).unwrap_or("rejected by relay");
+            return Err(WalletError::transaction(format!("Relay {} refused the transaction: {}", self.device.name, message)));
+        }
+        Ok(SubmitResponse {
+            transaction_id: reply.get("transaction_id").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+            status: status.to_string(),
+            chain_id,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct RelayHealth {
+    /// `None` until the first probe or submission
+    reachable: Option<bool>,
+    latency: Option<Duration>,
+    failures: u64,
+    last_checked: Option<DateTime<Utc>>,
+}
+
+impl RelayHealth {
+    fn record_success(&mut self, elapsed: Duration) {
+        self.reachable = Some(true);
+        self.latency = Some(match self.latency {
+            Some(previous) => previous.mul_f64(1.0 - LATENCY_SMOOTHING) + elapsed.mul_f64(LATENCY_SMOOTHING),
+            None => elapsed,
+        });
+        self.last_checked = Some(Utc::now());
+    }
+
+    fn record_failure(&mut self) {
+        self.reachable = Some(false);
+        self.failures += 1;
+        self.last_checked = Some(Utc::now());
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayStatus {
+    pub name: String,
+    pub kind: RelayLinkKind,
+    pub reachable: Option<bool>,
+    pub latency_ms: Option<u64>,
+    pub failures: u64,
+    pub last_checked: Option<DateTime<Utc>>,
+    pub preferred: bool,
+}
+
+struct PoolEntry {
+    link: Arc<dyn RelayLink>,
+    health: Mutex<RelayHealth>,
+}
+
+/// Ordered relays, tried healthiest and fastest first
+pub struct RelayPool {
+    relays: Vec<PoolEntry>,
+    probe_timeout: Duration,
+}
+
+impl Default for RelayPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RelayPool {
+    pub fn new() -> Self {
+        Self {
+            relays: Vec::new(),
+            probe_timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Add a relay; on equal health and latency, earlier relays are preferred
+    pub fn with_relay(mut self, link: Arc<dyn RelayLink>) -> Self {
+        self.relays.push(PoolEntry { link, health: Mutex::new(RelayHealth::default()) });
+        self
+    }
+
+    pub fn with_probe_timeout(mut self, timeout: Duration) -> Self {
+        self.probe_timeout = timeout;
+        self
+    }
+
+    /// Relay indexes in the order they are tried: reachable or unprobed
+    /// before unreachable, then by latency, then by configured order
+    fn ranked(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.relays.len()).collect();
+        order.sort_by_key(|&i| {
+            let health = self.relays[i].health.lock().unwrap();
+            (health.reachable == Some(false), health.latency.unwrap_or(Duration::MAX))
+        });
+        order
+    }
+
+    /// The relay submissions go to first
+    pub fn preferred(&self) -> Option<Arc<dyn RelayLink>> {
+        self.ranked().first().map(|&i| Arc::clone(&self.relays[i].link))
+    }
+
+    /// Probe every relay concurrently and update its health and latency
+    pub async fn probe_all(&self) {
+        let probes = self.relays.iter().map(|entry| async move {
+            let started = Instant::now();
+            let result = tokio::time::timeout(self.probe_timeout, entry.link.probe()).await;
+            let mut health = entry.health.lock().unwrap();
+            match result {
+                Ok(Ok(())) => health.record_success(started.elapsed()),
+                _ => health.record_failure(),
+            }
+        });
+        futures::future::join_all(probes).await;
+    }
+
+    /// Submit through the preferred relay, failing over to the next while
+    /// relays cannot be reached. Returns the name of the relay that took it.
+    pub async fn submit_with_failover(&self, raw_tx: &str, chain_id: u64) -> Result<(String, SubmitResponse), WalletError> {
+        if self.relays.is_empty() {
+            return Err(WalletError::config("No relays configured"));
+        }
+        let mut errors = Vec::new();
+        for index in self.ranked() {
+            let entry = &self.relays[index];
+            let started = Instant::now();
+            match entry.link.submit(raw_tx, chain_id).await {
+                Ok(response) => {
+                    entry.health.lock().unwrap().record_success(started.elapsed());
+                    return Ok((entry.link.name(), response));
+                }
+                Err(e @ WalletError::Transaction(_)) => {
+                    entry.health.lock().unwrap().record_success(started.elapsed());
+                    return Err(e);
+                }
+                Err(e) => {
+                    entry.health.lock().unwrap().record_failure();
+                    log::warn!("Relay {} unreachable, trying the next one: {}", entry.link.name(), e);
+                    errors.push(format!("{}: {}", entry.link.name(), e));
+                }
+            }
+        }
+        Err(WalletError::network(format!("All relays unreachable: {}", errors.join("; "))))
+    }
+
+    pub fn statuses(&self) -> Vec<RelayStatus> {
+        let preferred = self.ranked().first().copied();
+        self.relays.iter().enumerate()
+            .map(|(index, entry)| {
+                let health = entry.health.lock().unwrap().clone();
+                RelayStatus {
+                    name: entry.link.name(),
+                    kind: entry.link.kind(),
+                    reachable: health.reachable,
+                    latency_ms: health.latency.map(|l| l.as_millis() as u64),
+                    failures: health.failures,
+                    last_checked: health.last_checked,
+                    preferred: preferred == Some(index),
+                }
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl RelayLink for RelayPool {
+    fn name(&self) -> String {
+        self.preferred().map(|link| link.name()).unwrap_or_else(|| "relay pool".to_string())
+    }
+
+    fn kind(&self) -> RelayLinkKind {
+        self.preferred().map(|link| link.kind()).unwrap_or(RelayLinkKind::Network)
+    }
+
+    async fn probe(&self) -> Result<(), WalletError> {
+        self.probe_all().await;
+        if self.relays.iter().any(|entry| entry.health.lock().unwrap().reachable == Some(true)) {
+            Ok(())
+        } else {
+            Err(WalletError::network("No relay is reachable"))
+        }
+    }
+
+    async fn submit(&self, raw_tx: &str, chain_id: u64) -> Result<SubmitResponse, WalletError> {
+        Ok(self.submit_with_failover(raw_tx, chain_id).await?.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeRelay {
+        name: &'static str,
+        delay: Duration,
+        up: bool,
+    }
+
+    #[async_trait]
+    impl RelayLink for FakeRelay {
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        fn kind(&self) -> RelayLinkKind {
+            RelayLinkKind::Network
+        }
+
+        async fn probe(&self) -> Result<(), WalletError> {
+            tokio::time::sleep(self.delay).await;
+            if self.up { Ok(()) } else { Err(WalletError::network("down")) }
+        }
+
+        async fn submit(&self, _raw_tx: &str, chain_id: u64) -> Result<SubmitResponse, WalletError> {
+            self.probe().await?;
+            Ok(SubmitResponse { transaction_id: format!("{}-tx", self.name), status: "queued".to_string(), chain_id })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prefers_fastest_and_fails_over() {
+        let pool = RelayPool::new()
+            .with_relay(Arc::new(FakeRelay { name: "primary", delay: Duration::from_millis(100), up: true }))
+            .with_relay(Arc::new(FakeRelay { name: "nearby", delay: Duration::from_millis(5), up: true }))
+            .with_relay(Arc::new(FakeRelay { name: "down", delay: Duration::ZERO, up: false }));
+        assert_eq!(pool.preferred().unwrap().name(), "primary");
+
+        pool.probe_all().await;
+        assert_eq!(pool.preferred().unwrap().name(), "nearby");
+        let statuses = pool.statuses();
+        assert_eq!(statuses[2].reachable, Some(false));
+        assert!(statuses[1].preferred);
+
+        // The unreachable relay is tried last, after every reachable one
+        let pool = RelayPool::new()
+            .with_relay(Arc::new(FakeRelay { name: "down", delay: Duration::ZERO, up: false }))
+            .with_relay(Arc::new(FakeRelay { name: "backup", delay: Duration::ZERO, up: true }));
+        let (relay, response) = pool.submit_with_failover("0x02", 1114).await.unwrap();
+        assert_eq!((relay.as_str(), response.transaction_id.as_str()), ("backup", "backup-tx"));
+        assert_eq!(pool.preferred().unwrap().name(), "backup");
+    }
+}
@@ -52,20 +52,30 @@ use crate::core::storage::StorageManager;
 use crate::shared::types::WalletBackupInfo;
 
 // Re-export specific components
-pub use core::wallet::WalletManager;
-pub use core::storage::SecureStorage;
-pub use core::transactions::TransactionManager;
-pub use core::ble::BLESecurityManager;
+pub use core::wallet::{WalletManager, SigningPolicy, SigningPolicyStore, AuthReason};
+pub use core::storage::{SecureStorage, Keystore, ScryptParams};
+pub use core::transactions::{TransactionManager, TransactionBuilder, NonceManager, OfflineQueue, FeeTables, PaymentSession, PaymentSessionStore, TransactionHistory, HistoryEntry, HistoryQuery, HistoryStatus, RpcConfirmations, PriceSource, AccountingReport, TransactionTemplate, TemplateInput, AmountFormula};
+pub use core::payments::{decode_open_payment_qr, decode_payment_qr, encode_open_payment_qr, encode_payment_qr, Eip681Payment, OpenPaymentRequest};
+pub use core::ble::{BLESecurityManager, BLESecureSession, BleCentral, NoiseKeypair, Transport, MockTransport};
+pub use infrastructure::relay::{RelayClient, RelayClientConfig, RelayTransactionStatus};
+pub use infrastructure::relay_pool::{BleRelay, RelayLink, RelayLinkKind, RelayPool, RelayStatus};
 
 // Re-export domain entities
 pub use crate::domain::Wallet;
-pub use shared::types::{Transaction, TokenInfo, Network};
+pub use shared::types::{Transaction, TransactionType, TokenInfo, Network};
 
 // Re-export shared types
 pub use shared::types::WalletBackup;
 pub use shared::types::SignedTransaction;
 pub use shared::types::TransactionHash;
 pub use shared::types::Balance;
+pub use shared::sources::{RandomSource, Clock, OsRandom, SystemClock, SeededRandom, FixedClock};
+pub use shared::chains::{ChainRegistry, ChainSpec, NativeCurrency};
+pub use shared::canonical::{CanonicalDecode, CanonicalDecoder, CanonicalEncode, CanonicalEncoder};
+pub use shared::types::{PaymentRequest, RelayReceipt, SignedRelayReceipt};
+pub use core::crypto::keys::{SeedPhraseReport, SeedPhraseWarning, SeedPhraseWarningKind};
+pub use core::self_check::{run_self_check, SelfCheckArea, SelfCheckReport, SelfCheckResult};
+pub use core::scheduler::{BalanceRefreshTask, HistorySyncTask, PowerState, SyncScheduler, SyncTask, SyncTaskKind, TaskPolicy, TaskRun};
 
 // Initialize logging and configuration
 pub fn init() -> Result<(), Box<dyn std::error::Error>> {
@@ -199,27 +209,22 @@ impl WalletCore {
         Ok(Wallet::from(secure_wallet))
     }
 
+    /// Check crypto primitives, encrypted storage and the chain registry.
+    /// Hosts run this once at startup and refuse to sign when it fails.
+    pub async fn self_check(&self) -> Result<SelfCheckReport, WalletError> {
+        let file_storage = crate::infrastructure::platform::FileStorage::new()?;
+        Ok(run_self_check(&file_storage).await)
+    }
+
+    /// Warnings for a publicly known or weak seed phrase, to show before import
+    pub fn check_seed_phrase(&self, seed_phrase: &str) -> Result<SeedPhraseReport, WalletError> {
+        self.wallet_manager.pre_import_check(seed_phrase)
+    }
+
     pub async fn import_wallet(&self, seed_phrase: &str) -> Result<Wallet, WalletError> {
-        use bip39::{Mnemonic};
-        use bip32::{XPrv, DerivationPath, Seed};
-        use std::str::FromStr;
-        let mnemonic = Mnemonic::parse(seed_phrase)
-            .map_err(|e| WalletError::validation(format!("Invalid seed phrase: {}", e)))?;
-        let seed_bytes = mnemonic.to_seed("");
-        let seed = Seed::new(seed_bytes); 
-        let xprv = XPrv::new(seed.as_bytes())
-            .map_err(|e| WalletError::crypto(format!("Failed to create XPrv: {}", e)))?;
-        let derivation_path = DerivationPath::from_str("m/44'/60'/0'/0/0")
-            .map_err(|e| WalletError::crypto(format!("Invalid derivation path: {}", e)))?;
-        let mut child_xprv = xprv;
-        for child_number in derivation_path.into_iter() {
-            child_xprv = child_xprv.derive_child(child_number)
-                .map_err(|e| WalletError::crypto(format!("Failed to derive child XPrv: {}", e)))?;
-        }
-        let _private_key_bytes = child_xprv.private_key().to_bytes();
-        let wallet_id = format!("wallet_{}", uuid::Uuid::new_v4());
+        let wallet_id = crate::core::crypto::keys::KeyManager::wallet_id_from_seed(seed_phrase)?;
         let network = Network::CoreTestnet;
-        let wallet = self.wallet_manager.create_wallet(&wallet_id, "Imported Wallet", network).await?;
+        let wallet = self.wallet_manager.import_wallet(&wallet_id, "Imported Wallet", seed_phrase, network).await?;
         Ok(Wallet::from(wallet))
     }
 
@@ -240,6 +245,44 @@ impl WalletCore {
         let backup_info = WalletBackupInfo::from(backup.clone());
         self.storage.restore_wallet(&backup_info, password).await
     }
+
+    /// Issue single-use recovery codes that restore `backup` without its
+    /// password. Show them to the user once; only their hashes are kept.
+    /// `backup` gains the escrowed code set, so store the updated copy
+    /// wherever the backup is kept off the device.
+    pub async fn create_recovery_codes(&self, backup: &mut WalletBackup, password: &str) -> Result<Vec<zeroize::Zeroizing<String>>, WalletError> {
+        let mut backup_info = WalletBackupInfo::from(backup.clone());
+        let codes = self.storage.create_recovery_codes(&mut backup_info, password, core::storage::DEFAULT_RECOVERY_CODE_COUNT).await?;
+        *backup = WalletBackup::from(backup_info);
+        Ok(codes)
+    }
+
+    /// Restore a backup with one of its recovery codes, using the code up.
+    /// Spent codes are recorded on this device, not in `backup`.
+    pub async fn restore_wallet_with_recovery_code(&self, backup: &WalletBackup, code: &str) -> Result<Wallet, WalletError> {
+        self.storage.restore_with_recovery_code(&WalletBackupInfo::from(backup.clone()), code).await
+    }
+
+    /// Restore an exported backup of any supported version, returning the
+    /// backup re-encoded in the current format
+    pub async fn restore_wallet_export(&self, data: &str, password: &str) -> Result<(Wallet, WalletBackup), WalletError> {
+        let (wallet, backup_info) = self.storage.restore_any_backup(data, password).await?;
+        Ok((wallet, WalletBackup::from(backup_info)))
+    }
+
+    /// Export a wallet as a V3 JSON keystore that geth and MetaMask can import
+    pub async fn export_keystore(&self, wallet: &Wallet, password: &str) -> Result<String, WalletError> {
+        self.wallet_manager.export_keystore(&wallet.id, password, ScryptParams::default()).await?.to_json()
+    }
+
+    /// Import a V3 JSON keystore exported by geth, MetaMask or `export_keystore`.
+    /// The wallet id is derived from the key, as for seed imports; the
+    /// keystore's own id is ignored.
+    pub async fn import_keystore(&self, json: &str, password: &str, network: Network) -> Result<Wallet, WalletError> {
+        let keystore = Keystore::from_json(json)?;
+        let wallet = self.wallet_manager.import_keystore("Imported Keystore", &keystore, password, network).await?;
+        Ok(Wallet::from(wallet))
+    }
 }
 
 // Implement Drop for secure cleanup
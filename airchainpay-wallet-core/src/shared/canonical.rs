@@ -0,0 +1,408 @@
+//! Canonical binary encoding for signed payloads
+//!
+//! A subset of CBOR with deterministic encoding (RFC 8949 §4.2), so the
+//! wallet core, its WASM build and the relay produce identical bytes for the
+//! same values regardless of JSON key order or formatting:
+//!
+//! - a payload is a definite-length array whose first item is a text tag
+//!   naming it and its version, e.g. `airchainpay/payment-request/1`
+//! - integers, lengths and array sizes use the shortest head
+//! - no maps, floats, tags or indefinite lengths; absent values are `null`
+//! - amounts are big-endian byte strings without leading zeros, addresses
+//!   20-byte strings and hashes lowercase `0x` text
+//!
+//! `CanonicalDecoder` reads the same subset back and rejects anything else,
+//! including non-shortest heads, so a decoded payload re-encodes to the
+//! bytes that were signed.
+//!
+//! The relay carries the same encoder; the two must change together.
+
+use crate::shared::error::WalletError;
+use crate::shared::types::{Network, PaymentRequest, RelayReceipt, TokenInfo};
+use ethers::types::U256;
+
+const MAJOR_UINT: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const SIMPLE_FALSE: u8 = 0xf4;
+const SIMPLE_TRUE: u8 = 0xf5;
+const SIMPLE_NULL: u8 = 0xf6;
+
+/// Tag of the canonical payment request encoding
+pub const PAYMENT_REQUEST_TAG: &str = "airchainpay/payment-request/1";
+/// Tag of the canonical open-amount payment request encoding
+pub const OPEN_PAYMENT_REQUEST_TAG: &str = "airchainpay/open-payment-request/1";
+/// Tag of the canonical relay receipt encoding
+pub const RELAY_RECEIPT_TAG: &str = "airchainpay/relay-receipt/1";
+
+#[derive(Debug, Default)]
+pub struct CanonicalEncoder {
+    out: Vec<u8>,
+}
+
+impl CanonicalEncoder {
+    /// Start a payload of `fields` items after its `tag`
+    pub fn payload(tag: &str, fields: usize) -> Self {
+        let mut encoder = Self::default();
+        encoder.array(fields + 1);
+        encoder.text(tag);
+        encoder
+    }
+
+    fn head(&mut self, major: u8, value: u64) {
+        let major = major << 5;
+        match value {
+            0..=23 => self.out.push(major | value as u8),
+            24..=0xff => self.out.extend_from_slice(&[major | 24, value as u8]),
+            0x100..=0xffff => {
+                self.out.push(major | 25);
+                self.out.extend_from_slice(&(value as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                self.out.push(major | 26);
+                self.out.extend_from_slice(&(value as u32).to_be_bytes());
+            }
+            _ => {
+                self.out.push(major | 27);
+                self.out.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+    }
+
+    pub fn uint(&mut self, value: u64) -> &mut Self {
+        self.head(MAJOR_UINT, value);
+        self
+    }
+
+    pub fn int(&mut self, value: i64) -> &mut Self {
+        if value < 0 {
+            self.head(MAJOR_NEGATIVE, !(value as u64));
+        } else {
+            self.head(MAJOR_UINT, value as u64);
+        }
+        self
+    }
+
+    pub fn bool(&mut self, value: bool) -> &mut Self {
+        self.out.push(if value { SIMPLE_TRUE } else { SIMPLE_FALSE });
+        self
+    }
+
+    pub fn bytes(&mut self, value: &[u8]) -> &mut Self {
+        self.head(MAJOR_BYTES, value.len() as u64);
+        self.out.extend_from_slice(value);
+        self
+    }
+
+    pub fn text(&mut self, value: &str) -> &mut Self {
+        self.head(MAJOR_TEXT, value.len() as u64);
+        self.out.extend_from_slice(value.as_bytes());
+        self
+    }
+
+    pub fn array(&mut self, len: usize) -> &mut Self {
+        self.head(MAJOR_ARRAY, len as u64);
+        self
+    }
+
+    pub fn null(&mut self) -> &mut Self {
+        self.out.push(SIMPLE_NULL);
+        self
+    }
+
+    /// A decimal amount as a uint256, so `"0010"` and `"10"` encode alike
+    pub fn amount(&mut self, value: &str) -> Result<&mut Self, WalletError> {
+        let value = U256::from_dec_str(value.trim())
+            .map_err(|_| WalletError::validation(format!("Invalid amount {}", value)))?;
+        let mut buf = [0u8; 32];
+        value.to_big_endian(&mut buf);
+        let first = buf.iter().position(|&b| b != 0).unwrap_or(32);
+        Ok(self.bytes(&buf[first..]))
+    }
+
+    /// A 20-byte address from its hex form, in any letter case
+    pub fn address(&mut self, address: &str) -> Result<&mut Self, WalletError> {
+        let bytes = hex::decode(address.trim_start_matches("0x"))
+            .map_err(|_| WalletError::validation(format!("Invalid address {}", address)))?;
+        if bytes.len() != 20 {
+            return Err(WalletError::validation(format!("Invalid address {}", address)));
+        }
+        Ok(self.bytes(&bytes))
+    }
+
+    /// A hash or other hex identifier, normalised to lowercase `0x` text
+    pub fn hex_text(&mut self, value: &str) -> &mut Self {
+        let normalized = format!("0x{}", value.trim_start_matches("0x").to_lowercase());
+        self.text(&normalized)
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.out
+    }
+}
+
+/// Types with a canonical binary form to sign and verify
+pub trait CanonicalEncode {
+    fn canonical_bytes(&self) -> Result<Vec<u8>, WalletError>;
+}
+
+/// Types that can be read back from their canonical binary form
+pub trait CanonicalDecode: Sized {
+    fn from_canonical_bytes(bytes: &[u8]) -> Result<Self, WalletError>;
+}
+
+fn malformed(what: &str) -> WalletError {
+    WalletError::validation(format!("Malformed canonical payload: {}", what))
+}
+
+#[derive(Debug)]
+pub struct CanonicalDecoder<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> CanonicalDecoder<'a> {
+    /// Start reading a payload of `fields` items tagged `tag`
+    pub fn payload(input: &'a [u8], tag: &str, fields: usize) -> Result<Self, WalletError> {
+        let mut decoder = Self { input, pos: 0 };
+        if decoder.array()? != fields + 1 {
+            return Err(malformed("unexpected field count"));
+        }
+        let found = decoder.text()?;
+        if found != tag {
+            return Err(WalletError::validation(format!("Expected a {} payload, found {}", tag, found)));
+        }
+        Ok(decoder)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], WalletError> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.input.len())
+            .ok_or_else(|| malformed("truncated"))?;
+        let bytes = &self.input[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn head(&mut self, major: u8) -> Result<u64, WalletError> {
+        let initial = self.take(1)?[0];
+        if initial >> 5 != major {
+            return Err(malformed("unexpected item type"));
+        }
+        let info = initial & 0x1f;
+        let value = match info {
+            0..=23 => info as u64,
+            24 => self.take(1)?[0] as u64,
+            25 => u16::from_be_bytes(self.take(2)?.try_into().map_err(|_| malformed("truncated"))?) as u64,
+            26 => u32::from_be_bytes(self.take(4)?.try_into().map_err(|_| malformed("truncated"))?) as u64,
+            27 => u64::from_be_bytes(self.take(8)?.try_into().map_err(|_| malformed("truncated"))?),
+            _ => return Err(malformed("indefinite or reserved length")),
+        };
+        let shortest = match value {
+            0..=23 => value as u8,
+            24..=0xff => 24,
+            0x100..=0xffff => 25,
+            0x1_0000..=0xffff_ffff => 26,
+            _ => 27,
+        };
+        if info != shortest {
+            return Err(malformed("non-shortest integer"));
+        }
+        Ok(value)
+    }
+
+    pub fn uint(&mut self) -> Result<u64, WalletError> {
+        self.head(MAJOR_UINT)
+    }
+
+    pub fn bool(&mut self) -> Result<bool, WalletError> {
+        match self.take(1)?[0] {
+            SIMPLE_TRUE => Ok(true),
+            SIMPLE_FALSE => Ok(false),
+            _ => Err(malformed("expected a boolean")),
+        }
+    }
+
+    pub fn bytes(&mut self) -> Result<&'a [u8], WalletError> {
+        let len = self.head(MAJOR_BYTES)?;
+        self.take(usize::try_from(len).map_err(|_| malformed("length too large"))?)
+    }
+
+    pub fn text(&mut self) -> Result<&'a str, WalletError> {
+        let len = self.head(MAJOR_TEXT)?;
+        let bytes = self.take(usize::try_from(len).map_err(|_| malformed("length too large"))?)?;
+        std::str::from_utf8(bytes).map_err(|_| malformed("invalid UTF-8 text"))
+    }
+
+    pub fn array(&mut self) -> Result<usize, WalletError> {
+        let len = self.head(MAJOR_ARRAY)?;
+        usize::try_from(len).map_err(|_| malformed("length too large"))
+    }
+
+    /// Consume a `null` if one is next
+    pub fn null(&mut self) -> bool {
+        if self.peek() == Some(SIMPLE_NULL) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// A uint256 amount, as a decimal string
+    pub fn amount(&mut self) -> Result<String, WalletError> {
+        let bytes = self.bytes()?;
+        if bytes.len() > 32 || bytes.first() == Some(&0) {
+            return Err(malformed("amount is not a minimal uint256"));
+        }
+        Ok(U256::from_big_endian(bytes).to_string())
+    }
+
+    /// A 20-byte address, as lowercase `0x` hex
+    pub fn address(&mut self) -> Result<String, WalletError> {
+        let bytes = self.bytes()?;
+        if bytes.len() != 20 {
+            return Err(malformed("address is not 20 bytes"));
+        }
+        Ok(format!("0x{}", hex::encode(bytes)))
+    }
+
+    /// Require that the whole input was read
+    pub fn finish(self) -> Result<(), WalletError> {
+        if self.pos != self.input.len() {
+            return Err(malformed("trailing bytes"));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn encode_token(encoder: &mut CanonicalEncoder, token: &TokenInfo) -> Result<(), WalletError> {
+    encoder.array(7).text(&token.symbol).text(&token.name).uint(token.decimals as u64);
+    // Native tokens have no contract address
+    if token.address.is_empty() {
+        encoder.null();
+    } else {
+        encoder.address(&token.address)?;
+    }
+    let chain_id = token.chain_id.trim().parse::<u64>()
+        .map_err(|_| WalletError::validation(format!("Invalid token chain id {}", token.chain_id)))?;
+    encoder.uint(chain_id).bool(token.is_native).bool(token.is_stablecoin);
+    Ok(())
+}
+
+impl CanonicalEncode for PaymentRequest {
+    fn canonical_bytes(&self) -> Result<Vec<u8>, WalletError> {
+        let mut encoder = CanonicalEncoder::payload(PAYMENT_REQUEST_TAG, 6);
+        encoder.amount(&self.amount)?.address(&self.to_address)?;
+        encode_token(&mut encoder, &self.token)?;
+        encoder.uint(self.network.chain_id());
+        match &self.reference {
+            Some(reference) => encoder.text(reference),
+            None => encoder.null(),
+        };
+        match self.gas_price {
+            Some(gas_price) => encoder.uint(gas_price),
+            None => encoder.null(),
+        };
+        Ok(encoder.finish())
+    }
+}
+
+pub(crate) fn decode_token(decoder: &mut CanonicalDecoder) -> Result<TokenInfo, WalletError> {
+    if decoder.array()? != 7 {
+        return Err(malformed("token is not 7 items"));
+    }
+    let symbol = decoder.text()?.to_string();
+    let name = decoder.text()?.to_string();
+    let decimals = u8::try_from(decoder.uint()?).map_err(|_| malformed("token decimals out of range"))?;
+    let address = if decoder.null() { String::new() } else { decoder.address()? };
+    Ok(TokenInfo {
+        symbol,
+        name,
+        decimals,
+        address,
+        chain_id: decoder.uint()?.to_string(),
+        is_native: decoder.bool()?,
+        is_stablecoin: decoder.bool()?,
+    })
+}
+
+impl CanonicalDecode for PaymentRequest {
+    fn from_canonical_bytes(bytes: &[u8]) -> Result<Self, WalletError> {
+        let mut decoder = CanonicalDecoder::payload(bytes, PAYMENT_REQUEST_TAG, 6)?;
+        let amount = decoder.amount()?;
+        let to_address = decoder.address()?;
+        let token = decode_token(&mut decoder)?;
+        let chain_id = decoder.uint()?;
+        let network = Network::from_chain_id(chain_id)
+            .ok_or_else(|| WalletError::validation(format!("Unsupported network {}", chain_id)))?;
+        let reference = if decoder.null() { None } else { Some(decoder.text()?.to_string()) };
+        let gas_price = if decoder.null() { None } else { Some(decoder.uint()?) };
+        decoder.finish()?;
+        Ok(PaymentRequest { amount, to_address, token, network, reference, gas_price })
+    }
+}
+
+impl CanonicalEncode for RelayReceipt {
+    fn canonical_bytes(&self) -> Result<Vec<u8>, WalletError> {
+        let mut encoder = CanonicalEncoder::payload(RELAY_RECEIPT_TAG, 5);
+        encoder
+            .text(&self.transaction_id)
+            .hex_text(&self.tx_hash)
+            .uint(self.chain_id)
+            .int(self.timestamp)
+            .address(&self.relay)?;
+        Ok(encoder.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::types::Network;
+
+    fn request(amount: &str, to: &str) -> PaymentRequest {
+        PaymentRequest {
+            amount: amount.to_string(),
+            to_address: to.to_string(),
+            token: TokenInfo {
+                symbol: "USDC".to_string(),
+                name: "USD Coin".to_string(),
+                decimals: 6,
+                address: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".to_string(),
+                chain_id: "84532".to_string(),
+                is_native: false,
+                is_stablecoin: true,
+            },
+            network: Network::BaseSepolia,
+            reference: Some("order-42".to_string()),
+            gas_price: None,
+        }
+    }
+
+    #[test]
+    fn test_equivalent_requests_encode_identically() {
+        let a = request("1000000", "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6");
+        let b = request("0001000000", "0x742d35cc6634c0532925a3b8d4c9db96c4b4d8b6");
+        assert_eq!(a.canonical_bytes().unwrap(), b.canonical_bytes().unwrap());
+        assert_ne!(a.canonical_bytes().unwrap(), request("1000001", &a.to_address).canonical_bytes().unwrap());
+
+        // Same bytes as the relay's encoder for a receipt
+        let receipt = RelayReceipt {
+            transaction_id: "tx-1".to_string(),
+            tx_hash: "0xABC".to_string(),
+            chain_id: 1114,
+            timestamp: 1_700_000_000,
+            relay: "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23".to_string(),
+        };
+        let bytes = receipt.canonical_bytes().unwrap();
+        assert_eq!(bytes[..3], [0x86, 0x78, 27]);
+        assert_eq!(&bytes[30..36], &[0x64, b't', b'x', b'-', b'1', 0x65]);
+        assert_eq!(&bytes[36..41], b"0xabc");
+    }
+}
@@ -0,0 +1,245 @@
+//! Chain registry
+//!
+//! The built-in `Network`s plus any custom chains a deployment adds, read
+//! from a TOML or JSON document. The relay uses the same format for
+//! `CHAIN_REGISTRY_FILE` and serves its chains as one at
+//! `GET /api/chains/registry`, so wallet and relay can share a file.
+
+use crate::core::transactions::balances::rpc_url_for;
+use crate::shared::error::WalletError;
+use crate::shared::types::{Address, Network};
+use crate::shared::utils::validate_ethereum_address;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Native currency of a chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NativeCurrency {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+/// Everything the wallet needs to know about one chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainSpec {
+    pub chain_id: u64,
+    pub name: String,
+    /// Primary RPC URL first, then fallbacks
+    pub rpc_urls: Vec<String>,
+    pub explorer: String,
+    /// AirChainPay payment contract, if deployed on this chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract_address: Option<Address>,
+    pub native_currency: NativeCurrency,
+}
+
+impl ChainSpec {
+    /// Primary RPC URL, if any is known
+    pub fn rpc_url(&self) -> Option<&str> {
+        self.rpc_urls.first().map(String::as_str)
+    }
+
+    /// Explorer link for a transaction on this chain
+    pub fn explorer_tx_url(&self, tx_hash: &str) -> String {
+        format!("{}/tx/{}", self.explorer.trim_end_matches('/'), tx_hash)
+    }
+
+    fn validate(&self) -> Result<(), WalletError> {
+        if self.chain_id == 0 {
+            return Err(WalletError::config(format!("Chain {} has chain id 0", self.name)));
+        }
+        if self.name.trim().is_empty() {
+            return Err(WalletError::config(format!("Chain {} has no name", self.chain_id)));
+        }
+        let is_url = |url: &str| url.starts_with("https://") || url.starts_with("http://");
+        if let Some(url) = self.rpc_urls.iter().find(|url| !is_url(url)) {
+            return Err(WalletError::config(format!("Invalid RPC URL for chain {}: {:?}", self.chain_id, url)));
+        }
+        if !is_url(&self.explorer) {
+            return Err(WalletError::config(format!("Invalid explorer URL for chain {}: {:?}", self.chain_id, self.explorer)));
+        }
+        if let Some(address) = &self.contract_address {
+            validate_ethereum_address(address)?;
+        }
+        if self.native_currency.symbol.trim().is_empty() || self.native_currency.decimals > 36 {
+            return Err(WalletError::config(format!("Invalid native currency for chain {}", self.chain_id)));
+        }
+        Ok(())
+    }
+}
+
+impl From<&Network> for ChainSpec {
+    fn from(network: &Network) -> Self {
+        let symbol = network.native_currency();
+        ChainSpec {
+            chain_id: network.chain_id(),
+            name: network.name().to_string(),
+            // Honours the WALLET_CORE_RPC_* overrides
+            rpc_urls: rpc_url_for(network).into_iter().collect(),
+            explorer: network.block_explorer().to_string(),
+            contract_address: Some(network.contract_address().to_string()),
+            native_currency: NativeCurrency {
+                name: if symbol == "ETH" { "Ether".to_string() } else { symbol.to_string() },
+                symbol: symbol.to_string(),
+                decimals: 18,
+            },
+        }
+    }
+}
+
+/// On-disk form: `[[chains]]` in TOML, `{"chains": [...]}` in JSON
+#[derive(Serialize, Deserialize)]
+struct RegistryDocument {
+    #[serde(default)]
+    chains: Vec<ChainSpec>,
+}
+
+/// Known chains by chain id
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChainRegistry {
+    chains: BTreeMap<u64, ChainSpec>,
+}
+
+impl ChainRegistry {
+    /// The built-in networks; those without an RPC URL have no `rpc_urls`
+    pub fn builtin() -> Self {
+        let mut registry = Self::default();
+        for network in [Network::CoreTestnet, Network::BaseSepolia, Network::LiskSepolia, Network::EthereumHolesky] {
+            registry.chains.insert(network.chain_id(), ChainSpec::from(&network));
+        }
+        registry
+    }
+
+    /// The built-in networks, updated from the file named in
+    /// `WALLET_CORE_CHAIN_REGISTRY` when it is set
+    pub fn from_env() -> Result<Self, WalletError> {
+        let mut registry = Self::builtin();
+        if let Ok(path) = std::env::var("WALLET_CORE_CHAIN_REGISTRY") {
+            if !path.is_empty() {
+                registry.merge(Self::load(path)?);
+            }
+        }
+        Ok(registry)
+    }
+
+    pub fn from_json_str(content: &str) -> Result<Self, WalletError> {
+        let document: RegistryDocument = serde_json::from_str(content)
+            .map_err(|e| WalletError::config(format!("Invalid chain registry JSON: {}", e)))?;
+        Self::from_document(document)
+    }
+
+    pub fn from_toml_str(content: &str) -> Result<Self, WalletError> {
+        let document: RegistryDocument = toml::from_str(content)
+            .map_err(|e| WalletError::config(format!("Invalid chain registry TOML: {}", e)))?;
+        Self::from_document(document)
+    }
+
+    /// Read a registry file, TOML when the extension is `.toml` and JSON otherwise
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WalletError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| WalletError::config(format!("Failed to read chain registry {}: {}", path.display(), e)))?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml_str(&content),
+            _ => Self::from_json_str(&content),
+        }
+    }
+
+    fn from_document(document: RegistryDocument) -> Result<Self, WalletError> {
+        let mut registry = Self::default();
+        for chain in document.chains {
+            chain.validate()?;
+            if registry.chains.insert(chain.chain_id, chain).is_some() {
+                return Err(WalletError::config("Chain registry lists a chain twice"));
+            }
+        }
+        Ok(registry)
+    }
+
+    pub fn to_json(&self) -> Result<String, WalletError> {
+        Ok(serde_json::to_string_pretty(&self.document())?)
+    }
+
+    pub fn to_toml(&self) -> Result<String, WalletError> {
+        toml::to_string_pretty(&self.document())
+            .map_err(|e| WalletError::config(format!("Failed to write chain registry: {}", e)))
+    }
+
+    fn document(&self) -> RegistryDocument {
+        RegistryDocument { chains: self.chains.values().cloned().collect() }
+    }
+
+    /// Add `other`'s chains, replacing any with the same chain id
+    pub fn merge(&mut self, other: ChainRegistry) {
+        self.chains.extend(other.chains);
+    }
+
+    pub fn get(&self, chain_id: u64) -> Option<&ChainSpec> {
+        self.chains.get(&chain_id)
+    }
+
+    /// Add or replace a chain
+    pub fn upsert(&mut self, chain: ChainSpec) -> Result<(), WalletError> {
+        chain.validate()?;
+        self.chains.insert(chain.chain_id, chain);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, chain_id: u64) -> Option<ChainSpec> {
+        self.chains.remove(&chain_id)
+    }
+
+    /// All chains, by chain id
+    pub fn chains(&self) -> impl Iterator<Item = &ChainSpec> {
+        self.chains.values()
+    }
+
+    /// Primary RPC URL of a chain
+    pub fn rpc_url(&self, chain_id: u64) -> Result<&str, WalletError> {
+        self.get(chain_id)
+            .and_then(ChainSpec::rpc_url)
+            .ok_or_else(|| WalletError::config(format!("RPC URL not set for chain {}", chain_id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_overrides_builtin_and_adds_custom_chain() {
+        let file = ChainRegistry::from_toml_str(r#"
+            [[chains]]
+            chain_id = 4202
+            name = "Lisk Sepolia"
+            rpc_urls = ["https://rpc.sepolia-api.lisk.com"]
+            explorer = "https://sepolia-blockscout.lisk.com"
+            contract_address = "0xaBEEEc6e6c1f6bfDE1d05db74B28847Ba5b44EAF"
+            native_currency = { name = "Ether", symbol = "ETH", decimals = 18 }
+
+            [[chains]]
+            chain_id = 31337
+            name = "Local devnet"
+            rpc_urls = ["http://127.0.0.1:8545"]
+            explorer = "http://127.0.0.1:4000/"
+            native_currency = { name = "Ether", symbol = "ETH", decimals = 18 }
+        "#).unwrap();
+
+        let mut registry = ChainRegistry::builtin();
+        assert!(registry.rpc_url(4202).is_err());
+        registry.merge(file);
+        assert_eq!(registry.rpc_url(4202).unwrap(), "https://rpc.sepolia-api.lisk.com");
+        assert_eq!(registry.get(31337).unwrap().explorer_tx_url("0xabc"), "http://127.0.0.1:4000/tx/0xabc");
+        assert_eq!(registry.get(1114).unwrap().native_currency.symbol, "TCORE2");
+
+        let reloaded = ChainRegistry::from_json_str(&registry.to_json().unwrap()).unwrap();
+        assert_eq!(reloaded, registry);
+        assert_eq!(ChainRegistry::from_toml_str(&registry.to_toml().unwrap()).unwrap(), registry);
+
+        let mut bad = registry.get(31337).unwrap().clone();
+        bad.rpc_urls = vec!["ws://127.0.0.1:8546".to_string()];
+        assert!(registry.upsert(bad).is_err());
+    }
+}
@@ -54,6 +54,10 @@ pub const BLE_CHARACTERISTIC_UUID: &str = "87654321-4321-4321-4321-cba987654321"
 pub const BLE_ADVERTISEMENT_INTERVAL: u32 = 100; // milliseconds
 pub const BLE_CONNECTION_TIMEOUT: u32 = 30000; // milliseconds
 pub const BLE_SCAN_TIMEOUT: u32 = 10000; // milliseconds
+// Relay GATT peripheral: payment frames and JSON control messages
+pub const RELAY_BLE_SERVICE_UUID: &str = "0000abcd-0000-1000-8000-00805f9b34fb";
+pub const RELAY_BLE_PAYMENT_CHARACTERISTIC_UUID: &str = "0000abce-0000-1000-8000-00805f9b34fb";
+pub const RELAY_BLE_CONTROL_CHARACTERISTIC_UUID: &str = "0000abcf-0000-1000-8000-00805f9b34fb";
 
 // Performance constants
 pub const MAX_CONCURRENT_OPERATIONS: usize = 10;
@@ -174,6 +178,9 @@ pub const ARGON2_PARALLELISM: u32 = 1;
 pub const PBKDF2_ITERATIONS: u32 = 100000;
 pub const AES_KEY_SIZE: usize = 32;
 pub const CHACHA_KEY_SIZE: usize = 32;
+pub const HD_SEED_SIZE: usize = 64; // BIP39 seed
+pub const BIP44_COIN_TYPE_ETH: u32 = 60;
+pub const MAX_HD_ACCOUNT_INDEX: u32 = 0x7fff_ffff; // Highest hardened child index
 
 // Validation constants
 pub const MIN_ADDRESS_LENGTH: usize = 42; // 0x + 40 hex chars
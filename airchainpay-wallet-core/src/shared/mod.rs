@@ -7,9 +7,15 @@ pub mod types;
 pub mod utils;
 pub mod constants;
 pub mod error;
+pub mod sources;
+pub mod canonical;
+pub mod chains;
+pub mod payload_codec;
 
 // Re-export shared components
 pub use types::*;
 pub use utils::*;
 pub use constants::*;
-pub use error::*; 
\ No newline at end of file
+pub use error::*;
+pub use sources::*;
+pub use canonical::{CanonicalEncode, CanonicalEncoder}; 
\ No newline at end of file
@@ -0,0 +1,204 @@
+//! Compressed payload frames, as the relay reads them
+//!
+//! A frame is the magic bytes `ACPZ`, a one-byte codec version and a
+//! protobuf `CompressedPayload`, with the body LZ4-compressed when that
+//! makes it smaller. The schema is the relay's
+//! `src/proto/transaction.proto`, compiled by `build.rs`, so both sides
+//! read the same messages. The wallet writes `SignedTransaction` and
+//! `TransactionPayload` bodies; CBOR bodies written by the relay are
+//! handed back undecoded.
+
+use crate::shared::error::WalletError;
+use prost::Message;
+use std::io::{Read, Write};
+
+mod proto {
+    include!(concat!(env!("OUT_DIR"), "/airchainpay.rs"));
+}
+
+pub use proto::{SignedTransaction, Token, TransactionPayload};
+use proto::{CompressedPayload, PayloadCompression, PayloadEncoding};
+
+pub const PAYLOAD_MAGIC: &[u8; 4] = b"ACPZ";
+/// Version written by `encode_*`
+pub const PAYLOAD_CODEC_VERSION: u8 = 1;
+/// Versions this wallet decodes, oldest first; listed as `payload_codecs`
+/// when negotiating with a relay
+pub const PAYLOAD_CODEC_VERSIONS: &[u8] = &[PAYLOAD_CODEC_VERSION];
+/// Largest body a frame may expand to
+const MAX_UNCOMPRESSED_SIZE: usize = 1024 * 1024;
+
+/// Body of a decoded frame
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedPayload {
+    SignedTransaction(SignedTransaction),
+    Transaction(TransactionPayload),
+    /// CBOR-encoded JSON value, left for the caller
+    Cbor(Vec<u8>),
+}
+
+pub fn is_framed(data: &[u8]) -> bool {
+    data.len() > PAYLOAD_MAGIC.len() && data.starts_with(PAYLOAD_MAGIC)
+}
+
+/// Frame a raw signed transaction for `chain_id`
+pub fn encode_signed_transaction(raw_transaction: &[u8], chain_id: u64) -> Result<Vec<u8>, WalletError> {
+    let tx = SignedTransaction { raw_transaction: raw_transaction.to_vec(), chain_id };
+    encode(PayloadEncoding::SignedTransaction, tx.encode_to_vec())
+}
+
+/// Frame an offline payment payload
+pub fn encode_transaction_payload(payload: &TransactionPayload) -> Result<Vec<u8>, WalletError> {
+    encode(PayloadEncoding::Transaction, payload.encode_to_vec())
+}
+
+fn encode(encoding: PayloadEncoding, body: Vec<u8>) -> Result<Vec<u8>, WalletError> {
+    let uncompressed_size = u32::try_from(body.len())
+        .map_err(|_| WalletError::validation("Payload too large"))?;
+    let compressed = lz4_compress(&body)?;
+    let (compression, body) = if compressed.len() < body.len() {
+        (PayloadCompression::Lz4, compressed)
+    } else {
+        (PayloadCompression::None, body)
+    };
+
+    let envelope = CompressedPayload {
+        encoding: encoding as i32,
+        compression: compression as i32,
+        body,
+        uncompressed_size,
+    };
+    let mut frame = Vec::with_capacity(PAYLOAD_MAGIC.len() + 1 + envelope.encoded_len());
+    frame.extend_from_slice(PAYLOAD_MAGIC);
+    frame.push(PAYLOAD_CODEC_VERSION);
+    envelope.encode(&mut frame)
+        .map_err(|e| WalletError::validation(format!("Payload encoding failed: {}", e)))?;
+    Ok(frame)
+}
+
+/// Decode a frame of any supported version
+pub fn decode(frame: &[u8]) -> Result<DecodedPayload, WalletError> {
+    let rest = frame.strip_prefix(PAYLOAD_MAGIC.as_slice())
+        .ok_or_else(|| WalletError::validation("Not a framed payload"))?;
+    let (&version, envelope) = rest.split_first()
+        .ok_or_else(|| WalletError::validation("Truncated payload frame"))?;
+    if !PAYLOAD_CODEC_VERSIONS.contains(&version) {
+        return Err(WalletError::validation(format!("Payload codec version {} is not supported", version)));
+    }
+
+    let envelope = CompressedPayload::decode(envelope)
+        .map_err(|e| WalletError::validation(format!("Invalid payload envelope: {}", e)))?;
+    let size = envelope.uncompressed_size as usize;
+    if size > MAX_UNCOMPRESSED_SIZE {
+        return Err(WalletError::validation(format!("Payload expands to {} bytes, above the {} byte limit", size, MAX_UNCOMPRESSED_SIZE)));
+    }
+    let body = match PayloadCompression::try_from(envelope.compression) {
+        Ok(PayloadCompression::None) => envelope.body,
+        Ok(PayloadCompression::Lz4) => lz4_decompress(&envelope.body, size)?,
+        Err(_) => return Err(WalletError::validation(format!("Unknown payload compression {}", envelope.compression))),
+    };
+    if body.len() != size {
+        return Err(WalletError::validation(format!("Payload body is {} bytes, expected {}", body.len(), size)));
+    }
+
+    let invalid = |e: prost::DecodeError| WalletError::validation(format!("Invalid payload body: {}", e));
+    match PayloadEncoding::try_from(envelope.encoding) {
+        Ok(PayloadEncoding::SignedTransaction) => Ok(DecodedPayload::SignedTransaction(SignedTransaction::decode(body.as_slice()).map_err(invalid)?)),
+        Ok(PayloadEncoding::Transaction) => Ok(DecodedPayload::Transaction(TransactionPayload::decode(body.as_slice()).map_err(invalid)?)),
+        Ok(PayloadEncoding::Cbor) => Ok(DecodedPayload::Cbor(body)),
+        _ => Err(WalletError::validation(format!("Unknown payload encoding {}", envelope.encoding))),
+    }
+}
+
+fn lz4_compress(data: &[u8]) -> Result<Vec<u8>, WalletError> {
+    let mut encoder = lz4::EncoderBuilder::new()
+        .level(1)
+        .build(Vec::new())
+        .map_err(|e| WalletError::validation(format!("LZ4 compression failed: {}", e)))?;
+    encoder.write_all(data)
+        .map_err(|e| WalletError::validation(format!("LZ4 compression failed: {}", e)))?;
+    let (compressed, result) = encoder.finish();
+    result.map_err(|e| WalletError::validation(format!("LZ4 compression failed: {}", e)))?;
+    Ok(compressed)
+}
+
+/// Decompress at most `limit` bytes, failing on anything larger
+fn lz4_decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, WalletError> {
+    let decoder = lz4::Decoder::new(data)
+        .map_err(|e| WalletError::validation(format!("LZ4 decompression failed: {}", e)))?;
+    let mut decompressed = Vec::new();
+    decoder.take(limit as u64 + 1).read_to_end(&mut decompressed)
+        .map_err(|e| WalletError::validation(format!("LZ4 decompression failed: {}", e)))?;
+    if decompressed.len() > limit {
+        return Err(WalletError::validation(format!("Payload expands past {} bytes", limit)));
+    }
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_round_trip() {
+        let raw = [0xabu8; 400];
+        let frame = encode_signed_transaction(&raw, 84532).unwrap();
+        assert!(is_framed(&frame));
+        assert!(frame.len() < raw.len());
+        assert_eq!(
+            decode(&frame).unwrap(),
+            DecodedPayload::SignedTransaction(SignedTransaction { raw_transaction: raw.to_vec(), chain_id: 84532 }),
+        );
+
+        let payload = TransactionPayload {
+            to: "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6".to_string(),
+            amount: "1.5".to_string(),
+            chain_id: "84532".to_string(),
+            token: None,
+            payment_reference: "order-7".to_string(),
+            timestamp: 1_700_000_000,
+            version: "1.0".to_string(),
+            r#type: "payment".to_string(),
+        };
+        let frame = encode_transaction_payload(&payload).unwrap();
+        assert_eq!(decode(&frame).unwrap(), DecodedPayload::Transaction(payload));
+
+        let mut future = frame.clone();
+        future[PAYLOAD_MAGIC.len()] = 9;
+        assert!(decode(&future).is_err());
+        assert!(decode(b"ACP").is_err());
+    }
+
+    #[test]
+    fn test_shared_fixture_frames() {
+        // The relay's tests decode the same frames
+        let fixture = |hex_frame: &str| hex::decode(hex_frame.trim()).unwrap();
+
+        let frame = fixture(include_str!("../../../airchainpay-relay-rust/airchainpay-relay/src/proto/fixtures/signed_transaction.hex"));
+        assert_eq!(
+            decode(&frame).unwrap(),
+            DecodedPayload::SignedTransaction(SignedTransaction { raw_transaction: vec![0xab; 400], chain_id: 84532 }),
+        );
+
+        let payload = TransactionPayload {
+            to: "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6".to_string(),
+            amount: "1.5".to_string(),
+            chain_id: "84532".to_string(),
+            token: Some(Token {
+                symbol: "USDC".to_string(),
+                name: "USD Coin".to_string(),
+                decimals: 6,
+                address: "0x036CbD53842c5426634e7929541eC2318f3dCF7e".to_string(),
+                chain_id: "84532".to_string(),
+                is_native: false,
+            }),
+            payment_reference: "order-7".to_string(),
+            timestamp: 1_700_000_000,
+            version: "1.0".to_string(),
+            r#type: "payment".to_string(),
+        };
+        let frame = fixture(include_str!("../../../airchainpay-relay-rust/airchainpay-relay/src/proto/fixtures/transaction_payload.hex"));
+        assert_eq!(decode(&frame).unwrap(), DecodedPayload::Transaction(payload.clone()));
+        assert_eq!(encode_transaction_payload(&payload).unwrap(), frame);
+    }
+}
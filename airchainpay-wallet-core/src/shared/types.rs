@@ -30,6 +30,13 @@ impl Network {
         }
     }
 
+    /// The supported network with chain id `chain_id`
+    pub fn from_chain_id(chain_id: u64) -> Option<Network> {
+        [Network::CoreTestnet, Network::BaseSepolia, Network::LiskSepolia, Network::EthereumHolesky]
+            .into_iter()
+            .find(|network| network.chain_id() == chain_id)
+    }
+
     pub fn name(&self) -> &'static str {
         match self {
             Network::CoreTestnet => "Core Testnet",
@@ -67,6 +74,17 @@ impl Network {
         }
     }
 
+    /// Transaction type used unless the caller picks one; Core Testnet
+    /// does not accept type-2 transactions
+    pub fn default_transaction_type(&self) -> TransactionType {
+        match self {
+            Network::CoreTestnet => TransactionType::Legacy,
+            Network::BaseSepolia => TransactionType::Eip1559,
+            Network::LiskSepolia => TransactionType::Eip1559,
+            Network::EthereumHolesky => TransactionType::Eip1559,
+        }
+    }
+
     pub fn contract_address(&self) -> &'static str {
         match self {
             Network::CoreTestnet => "0x8d7eaB03a72974F5D9F5c99B4e4e1B393DBcfCAB",
@@ -78,6 +96,15 @@ impl Network {
 }
 
 // Transaction types - minimal and aligned with TypeScript
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TransactionType {
+    /// Pre-1559 transaction signed with EIP-155 replay protection
+    #[default]
+    Legacy,
+    /// Type-2 transaction with a max fee and priority fee
+    Eip1559,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
     pub to: Address,
@@ -87,6 +114,12 @@ pub struct Transaction {
     pub gas_price: Option<GasPrice>,
     pub nonce: Option<u64>,
     pub chain_id: u64,
+    #[serde(default)]
+    pub tx_type: TransactionType,
+    #[serde(default)]
+    pub max_fee_per_gas: Option<GasPrice>,
+    #[serde(default)]
+    pub max_priority_fee_per_gas: Option<GasPrice>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -113,6 +146,30 @@ pub struct TransactionReceipt {
     pub chain_id: u64,
 }
 
+/// Receipt a relay signs once it has broadcast a payment
+/// (`GET /api/transaction/{id}/receipt`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayReceipt {
+    pub transaction_id: String,
+    pub tx_hash: String,
+    pub chain_id: u64,
+    /// Unix seconds at broadcast
+    pub timestamp: i64,
+    /// Identity address of the relay
+    pub relay: Address,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedRelayReceipt {
+    #[serde(flatten)]
+    pub receipt: RelayReceipt,
+    /// EIP-191 signature over the receipt's canonical bytes
+    pub signature: String,
+    /// `cbor-1` for canonical receipts; older `text` receipts are not accepted
+    #[serde(default)]
+    pub encoding: String,
+}
+
 // Token types - aligned with TypeScript TokenInfo
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenInfo {
@@ -154,6 +211,10 @@ pub struct WalletBackup {
     pub encrypted_data: String,
     pub salt: String,
     pub version: String,
+    /// Escrowed recovery code set (base64), carried with the backup so the
+    /// codes still work on a device that never issued them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_codes: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -162,6 +223,10 @@ pub struct WalletBackupInfo {
     pub encrypted_data: String,
     pub salt: String,
     pub version: String,
+    /// Escrowed recovery code set (base64), carried with the backup so the
+    /// codes still work on a device that never issued them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_codes: Option<String>,
 }
 
 impl From<WalletBackupInfo> for WalletBackup {
@@ -171,6 +236,7 @@ impl From<WalletBackupInfo> for WalletBackup {
             encrypted_data: info.encrypted_data,
             salt: info.salt,
             version: info.version,
+            recovery_codes: info.recovery_codes,
         }
     }
 }
@@ -182,6 +248,7 @@ impl From<WalletBackup> for WalletBackupInfo {
             encrypted_data: backup.encrypted_data,
             salt: backup.salt,
             version: backup.version,
+            recovery_codes: backup.recovery_codes,
         }
     }
 }
@@ -288,6 +355,9 @@ mod tests {
             gas_price: None,
             nonce: None,
             chain_id: 1114,
+            tx_type: TransactionType::Legacy,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
         };
 
         assert_eq!(transaction.to, "0x1234");
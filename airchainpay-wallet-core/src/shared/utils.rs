@@ -3,7 +3,7 @@
 //! This module contains common utility functions used throughout the wallet core.
 
 use crate::shared::error::WalletError;
-use std::time::{SystemTime, UNIX_EPOCH};
+use crate::shared::sources::{Clock, OsRandom, RandomSource, SystemClock};
 use bip39::Mnemonic;
 use rand_core::OsRng;
 use rand_core::RngCore;
@@ -15,10 +15,7 @@ pub fn generate_id() -> String {
 
 /// Get current timestamp in seconds
 pub fn current_timestamp() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_else(|_| std::time::Duration::from_secs(0))
-        .as_secs()
+    SystemClock.unix_timestamp()
 }
 
 /// Validate Ethereum address format
@@ -203,10 +200,7 @@ pub fn generate_random_bytes(length: usize) -> Vec<u8> {
 
 /// Generate secure random bytes
 pub fn generate_secure_random_bytes(length: usize) -> Result<Vec<u8>, WalletError> {
-    let mut bytes = vec![0u8; length];
-    let mut rng = OsRng;
-    rng.fill_bytes(&mut bytes);
-    Ok(bytes)
+    OsRandom.random_bytes(length)
 }
 
 #[cfg(test)]
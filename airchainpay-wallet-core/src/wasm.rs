@@ -1,7 +1,174 @@
-//! WASM bindings stub for `airchainpay-wallet-core`
+//! WASM bindings for `airchainpay-wallet-core`
 //!
-//! This module is a placeholder to satisfy the `wasm` feature flag.
-//! Real WASM bindings can be added here when needed.
+//! `WasmWallet` keeps one account's private key in WASM linear memory. The
+//! key only leaves it encrypted, through `encryptBackup`, and is zeroized
+//! when the wallet is dropped or freed from JavaScript. Every operation
+//! returns a Promise so browser callers can await it.
+//!
+//! ```js
+//! const wallet = await WasmWallet.importMnemonic(phrase, 84532);
+//! const { raw_transaction } = JSON.parse(await wallet.signTransaction(txJson));
+//! const keystore = await wallet.encryptBackup(password);
+//! wallet.free();
+//! ```
+
+use crate::core::crypto::keys::KeyManager;
+use crate::core::storage::keystore::address_of;
+use crate::core::storage::{encrypt_keystore, ScryptParams};
+use crate::core::transactions::TransactionManager;
+use crate::shared::error::WalletError;
+use crate::shared::sources::default_random_source;
+use crate::shared::types::{Network, Transaction};
+use js_sys::Promise;
+use secp256k1::SecretKey;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+use zeroize::Zeroizing;
+
+/// A single-account wallet whose key lives in WASM memory
+#[wasm_bindgen]
+pub struct WasmWallet {
+    private_key: Zeroizing<[u8; 32]>,
+    address: String,
+    network: Network,
+}
+
+#[wasm_bindgen]
+impl WasmWallet {
+    /// Create a wallet with a fresh random key on the network with chain id `chain_id`
+    #[wasm_bindgen(js_name = createWallet)]
+    pub fn create_wallet(chain_id: u32) -> Promise {
+        future_to_promise(async move {
+            let network = network_for(chain_id)?;
+            Self::generate(network).map(JsValue::from).map_err(to_js)
+        })
+    }
+
+    /// Import account 0 of a BIP39 mnemonic on the network with chain id `chain_id`
+    #[wasm_bindgen(js_name = importMnemonic)]
+    pub fn import_mnemonic(mnemonic: String, chain_id: u32) -> Promise {
+        let mnemonic = Zeroizing::new(mnemonic);
+        future_to_promise(async move {
+            let network = network_for(chain_id)?;
+            Self::from_mnemonic(&mnemonic, network).map(JsValue::from).map_err(to_js)
+        })
+    }
+
+    /// Lowercase `0x` address of the wallet
+    #[wasm_bindgen(getter)]
+    pub fn address(&self) -> String {
+        self.address.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = chainId)]
+    pub fn chain_id(&self) -> u32 {
+        self.network.chain_id() as u32
+    }
+
+    /// Sign a transaction given as JSON, as in `Transaction`. Resolves to
+    /// `{"raw_transaction": "0x…", "hash": "0x…"}`, ready for the relay.
+    #[wasm_bindgen(js_name = signTransaction)]
+    pub fn sign_transaction(&self, transaction_json: String) -> Promise {
+        let private_key = self.private_key.clone();
+        let network = self.network.clone();
+        future_to_promise(async move {
+            sign(&private_key, &network, &transaction_json)
+                .map(|signed| JsValue::from_str(&signed))
+                .map_err(to_js)
+        })
+    }
+
+    /// Encrypt the key as a V3 JSON keystore under `password`. Resolves to
+    /// the keystore JSON, which geth, MetaMask and `import_keystore` read.
+    #[wasm_bindgen(js_name = encryptBackup)]
+    pub fn encrypt_backup(&self, password: String) -> Promise {
+        let private_key = self.private_key.clone();
+        let password = Zeroizing::new(password);
+        future_to_promise(async move {
+            encrypt_keystore(&private_key[..], &password, ScryptParams::default(), default_random_source().as_ref())
+                .and_then(|keystore| keystore.to_json())
+                .map(|json| JsValue::from_str(&json))
+                .map_err(to_js)
+        })
+    }
+}
+
+impl WasmWallet {
+    fn generate(network: Network) -> Result<Self, WalletError> {
+        let rng = default_random_source();
+        loop {
+            let mut key = Zeroizing::new([0u8; 32]);
+            rng.fill_bytes(&mut key[..])?;
+            // Out-of-range scalars are astronomically rare; draw again
+            if SecretKey::from_byte_array(*key).is_ok() {
+                return Self::from_key(key, network);
+            }
+        }
+    }
+
+    fn from_mnemonic(mnemonic: &str, network: Network) -> Result<Self, WalletError> {
+        Self::from_key(KeyManager::derive_key_bytes(mnemonic, 0, 0)?, network)
+    }
+
+    fn from_key(private_key: Zeroizing<[u8; 32]>, network: Network) -> Result<Self, WalletError> {
+        let address = format!("0x{}", address_of(&private_key[..])?);
+        Ok(Self { private_key, address, network })
+    }
+}
+
+fn sign(private_key: &[u8; 32], network: &Network, transaction_json: &str) -> Result<String, WalletError> {
+    let transaction: Transaction = serde_json::from_str(transaction_json)
+        .map_err(|e| WalletError::validation(format!("Invalid transaction JSON: {}", e)))?;
+    if transaction.chain_id != network.chain_id() {
+        return Err(WalletError::validation("Transaction chain_id does not match wallet network"));
+    }
+    let signed = TransactionManager::new(network.rpc_url().to_string()).sign_with_key_bytes(&transaction, &private_key[..])?;
+    Ok(serde_json::json!({
+        "raw_transaction": format!("0x{}", hex::encode(&signed.signature)),
+        "hash": signed.hash,
+    })
+    .to_string())
+}
+
+fn network_for(chain_id: u32) -> Result<Network, JsValue> {
+    [Network::CoreTestnet, Network::BaseSepolia, Network::LiskSepolia, Network::EthereumHolesky]
+        .into_iter()
+        .find(|n| n.chain_id() == chain_id as u64)
+        .ok_or_else(|| JsError::new(&format!("Unsupported network {}", chain_id)).into())
+}
+
+fn to_js(error: WalletError) -> JsValue {
+    JsError::new(&error.to_string()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::transactions::TransactionBuilder;
+    use crate::shared::types::TransactionType;
+
+    #[test]
+    fn test_import_mnemonic_and_sign() {
+        let wallet = WasmWallet::from_mnemonic(
+            "test test test test test test test test test test test junk",
+            Network::BaseSepolia,
+        )
+        .unwrap();
+        assert_eq!(wallet.address, "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266");
 
-// Public API surface can be expanded later; keep empty for now.
+        let transaction = TransactionBuilder::new(Network::BaseSepolia)
+            .transaction_type(TransactionType::Eip1559)
+            .to("0x70997970c51812dc3a010c7d01b50e0d17dc79c8")
+            .value("1000")
+            .nonce(0)
+            .gas_limit(21_000)
+            .fees(2_000_000_000, 1_000_000_000)
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&transaction).unwrap();
 
+        let signed: serde_json::Value = serde_json::from_str(&sign(&wallet.private_key, &wallet.network, &json).unwrap()).unwrap();
+        assert!(signed["raw_transaction"].as_str().unwrap().starts_with("0x02"));
+        assert!(sign(&wallet.private_key, &Network::CoreTestnet, &json).is_err());
+    }
+}